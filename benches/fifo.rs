@@ -0,0 +1,22 @@
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use investments::bench::FifoBenchmark;
+
+fn fifo(c: &mut Criterion) {
+    let mut group = c.benchmark_group("FIFO");
+    group.sample_size(10);
+
+    for trades in [10_000, 100_000, 300_000] {
+        for symbols in [1, 100] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{} symbols", symbols), trades), &(trades, symbols),
+                |b, &(trades, symbols)| b.iter_batched(
+                    || FifoBenchmark::new(trades, symbols), FifoBenchmark::run, BatchSize::LargeInput));
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, fifo);
+criterion_main!(benches);
@@ -0,0 +1 @@
+DROP TABLE instrument_reference
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE instrument_reference (
+  id TEXT NOT NULL,
+  time DATETIME NOT NULL,
+  info TEXT,
+  PRIMARY KEY (id)
+) WITHOUT ROWID
@@ -0,0 +1,6 @@
+CREATE TABLE rate_limits (
+  name TEXT NOT NULL,
+  period BIGINT NOT NULL,
+  time DATETIME NOT NULL,
+  PRIMARY KEY (name, period)
+) WITHOUT ROWID
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE trading_results (
+  portfolio TEXT NOT NULL,
+  market TEXT NOT NULL,
+  year INTEGER NOT NULL,
+  taxable_profit TEXT NOT NULL,
+  PRIMARY KEY (portfolio, market, year)
+) WITHOUT ROWID
@@ -0,0 +1 @@
+DROP TABLE manual_trades
\ No newline at end of file
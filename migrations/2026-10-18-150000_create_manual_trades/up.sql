@@ -0,0 +1,9 @@
+CREATE TABLE manual_trades (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  portfolio TEXT NOT NULL,
+  date DATE NOT NULL,
+  symbol TEXT NOT NULL,
+  quantity TEXT NOT NULL,
+  price TEXT NOT NULL,
+  currency TEXT NOT NULL
+)
\ No newline at end of file
@@ -1,10 +1,12 @@
 use std::collections::{BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
 
 use serde::Deserialize;
 use serde::de::{Deserializer, Error};
 use validator::Validate;
 
 use crate::core::EmptyResult;
+use crate::types::Decimal;
 
 #[derive(Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
@@ -19,6 +21,12 @@ pub struct AssetGroupConfig {
     #[serde(default)]
     #[validate(length(min = 1))]
     pub portfolios: Option<HashSet<String>>,
+
+    // Bounds of the group share in net asset value of its portfolios
+    #[serde(default, deserialize_with = "deserialize_share")]
+    pub min_share: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_share")]
+    pub max_share: Option<Decimal>,
 }
 
 impl AssetGroupConfig {
@@ -28,10 +36,56 @@ impl AssetGroupConfig {
                 return Err!("Invalid portfolio name: {:?}", name)
             }
         }
+
+        if let (Some(min_share), Some(max_share)) = (self.min_share, self.max_share) {
+            if min_share > max_share {
+                return Err!("Minimum share is greater than maximum share");
+            }
+        }
+
         Ok(())
     }
 }
 
+fn deserialize_share<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where D: Deserializer<'de>
+{
+    let share: String = Deserialize::deserialize(deserializer)?;
+
+    let value = share.strip_suffix('%')
+        .and_then(|share| Decimal::from_str(share.trim()).ok())
+        .filter(|&share| share >= dec!(0) && share <= dec!(100))
+        .ok_or_else(|| D::Error::custom(format!("Invalid asset group share: {:?}", share)))?;
+
+    Ok(Some(value / dec!(100)))
+}
+
+/// Stress testing scenario: price changes of asset allocation groups/instruments and currencies (relative to portfolio
+/// currency)
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StressScenarioConfig {
+    #[serde(default, deserialize_with = "deserialize_shocks")]
+    pub assets: HashMap<String, Decimal>,
+    #[serde(default, deserialize_with = "deserialize_shocks")]
+    pub currencies: HashMap<String, Decimal>,
+}
+
+fn deserialize_shocks<'de, D>(deserializer: D) -> Result<HashMap<String, Decimal>, D::Error>
+    where D: Deserializer<'de>
+{
+    let shocks: HashMap<String, String> = Deserialize::deserialize(deserializer)?;
+
+    shocks.into_iter().map(|(name, shock)| {
+        let value = shock.strip_suffix('%')
+            .and_then(|shock| Decimal::from_str(shock).ok())
+            .filter(|&shock| shock >= dec!(-100))
+            .ok_or_else(|| D::Error::custom(format!("Invalid {:?} shock: {:?}", name, shock)))?;
+
+        Ok((name, value / dec!(100)))
+    }).collect()
+}
+
 #[derive(Clone, Default)]
 pub struct PerformanceMergingConfig {
     mapping: HashMap<String, HashSet<String>>,
@@ -107,6 +161,46 @@ mod tests {
     use rstest::rstest;
     use super::*;
 
+    #[test]
+    fn asset_group_parsing() {
+        let group: AssetGroupConfig = serde_yaml::from_str(indoc::indoc!("
+            instruments: [VTI]
+            currencies: [USD]
+            min_share: 20%
+            max_share: 35.5%
+        ")).unwrap();
+
+        assert_eq!(group.min_share, Some(dec!(0.2)));
+        assert_eq!(group.max_share, Some(dec!(0.355)));
+        group.validate_inner(&HashSet::new()).unwrap();
+
+        let group: AssetGroupConfig = serde_yaml::from_str(
+            "{instruments: [VTI], currencies: [USD], min_share: 40%, max_share: 30%}").unwrap();
+        assert_matches!(group.validate_inner(&HashSet::new()),
+            Err(e) if e.to_string() == "Minimum share is greater than maximum share");
+
+        assert!(serde_yaml::from_str::<AssetGroupConfig>(
+            "{instruments: [VTI], currencies: [USD], max_share: 120%}").is_err());
+        assert!(serde_yaml::from_str::<AssetGroupConfig>(
+            "{instruments: [VTI], currencies: [USD], max_share: 0.3}").is_err());
+    }
+
+    #[test]
+    fn stress_scenario_parsing() {
+        let scenario: StressScenarioConfig = serde_yaml::from_str(indoc::indoc!("
+            assets:
+              Stocks: -20.5%
+            currencies:
+              USD: +50%
+        ")).unwrap();
+
+        assert_eq!(scenario.assets, hashmap!{s!("Stocks") => dec!(-0.205)});
+        assert_eq!(scenario.currencies, hashmap!{s!("USD") => dec!(0.5)});
+
+        assert!(serde_yaml::from_str::<StressScenarioConfig>("assets: {Stocks: -120%}").is_err());
+        assert!(serde_yaml::from_str::<StressScenarioConfig>("assets: {Stocks: -20}").is_err());
+    }
+
     #[rstest(iterations => [1, 2])]
     fn config_merging(iterations: usize) {
         let mut config = PerformanceMergingConfig::default();
@@ -2,23 +2,26 @@ use chrono::Datelike;
 
 use crate::core::GenericResult;
 #[cfg(test)] use crate::currency;
+use crate::taxes::DepositInterestTax;
 use crate::time::{Date, Month};
 use crate::types::Decimal;
 
-pub struct DepositEmulator {
+pub struct DepositEmulator<'a> {
     date: Date,
     end_date: Date,
 
     monthly_capitalization: bool,
+    interest_tax: Option<&'a DepositInterestTax>,
     interest_periods: Vec<InterestPeriod>,
     interest_period: Option<ActiveInterestPeriod>,
 
     daily_interest: Decimal,
     assets: Decimal,
+    interest_payments: Vec<Transaction>,
 }
 
-impl DepositEmulator {
-    pub fn new(start_date: Date, end_date: Date, interest: Decimal) -> DepositEmulator {
+impl<'a> DepositEmulator<'a> {
+    pub fn new(start_date: Date, end_date: Date, interest: Decimal) -> DepositEmulator<'a> {
         assert!(start_date <= end_date);
 
         let mut interest_periods = Vec::new();
@@ -31,25 +34,67 @@ impl DepositEmulator {
             end_date: end_date,
 
             monthly_capitalization: true,
+            interest_tax: None,
             interest_periods: interest_periods,
             interest_period: None,
 
             daily_interest: interest / dec!(100) / dec!(365),
             assets: dec!(0),
+            interest_payments: Vec::new(),
         }
     }
 
-    pub fn with_monthly_capitalization(mut self, monthly_capitalization: bool) -> DepositEmulator {
+    pub fn with_monthly_capitalization(mut self, monthly_capitalization: bool) -> DepositEmulator<'a> {
         self.monthly_capitalization = monthly_capitalization;
         self
     }
 
-    pub fn with_interest_periods(mut self, custom_interest_periods: &[InterestPeriod]) -> DepositEmulator {
+    /// Withholds tax from the interest paid during each year at the beginning of the next year (and at the end date
+    /// for the last year). Monthly statement doesn't support it, because the tax is calculated for all deposits.
+    pub fn with_interest_tax(mut self, interest_tax: &'a DepositInterestTax) -> DepositEmulator<'a> {
+        self.interest_tax = Some(interest_tax);
+        self
+    }
+
+    pub fn with_interest_periods(mut self, custom_interest_periods: &[InterestPeriod]) -> DepositEmulator<'a> {
         self.interest_periods = custom_interest_periods.iter().rev().cloned().collect();
         self
     }
 
     pub fn emulate(mut self, transactions: &[Transaction]) -> Decimal {
+        self.process(transactions);
+        self.assets
+    }
+
+    /// Emulates the deposit and returns its per-month statement: contributions, paid (capitalized) interest and assets
+    /// at the end of each month from the start to the end date.
+    pub fn emulate_monthly(mut self, transactions: &[Transaction]) -> Vec<MonthlyStatement> {
+        assert!(self.interest_tax.is_none());
+        let start_month = Month::from(self.date);
+        let end_month = Month::from(self.end_date);
+        self.process(transactions);
+
+        let mut statements = Vec::new();
+        let mut assets = dec!(0);
+        let mut month = start_month;
+
+        loop {
+            let contributions = sum_month_transactions(transactions, month);
+            let interest = sum_month_transactions(&self.interest_payments, month);
+            assets += contributions + interest;
+
+            statements.push(MonthlyStatement {month, contributions, interest, assets});
+
+            if month == end_month {
+                break;
+            }
+            month = month.next();
+        }
+
+        statements
+    }
+
+    fn process(&mut self, transactions: &[Transaction]) {
         self.select_interest_period();
 
         for transaction in transactions {
@@ -59,7 +104,9 @@ impl DepositEmulator {
         self.process_to(self.end_date);
         assert!(self.interest_period.is_none());
 
-        self.assets
+        if self.interest_tax.is_some() {
+            self.withhold_interest_tax(self.end_date.year());
+        }
     }
 
     fn select_interest_period(&mut self) {
@@ -95,6 +142,18 @@ impl DepositEmulator {
     }
 
     fn process_to(&mut self, date: Date) {
+        if self.interest_tax.is_some() {
+            while self.date.year() < date.year() {
+                let year = self.date.year();
+                self.process_to_date(date!(year + 1, 1, 1));
+                self.withhold_interest_tax(year);
+            }
+        }
+
+        self.process_to_date(date);
+    }
+
+    fn process_to_date(&mut self, date: Date) {
         assert!(self.date <= date);
 
         while self.date < date {
@@ -153,22 +212,39 @@ impl DepositEmulator {
         let interest_period = self.interest_period.as_mut().unwrap();
         assert_eq!(self.date, interest_period.next_capitalization_date);
 
-        self.assets += interest_period.accumulated_income;
+        let income = interest_period.accumulated_income;
         interest_period.accumulated_income = dec!(0);
-
         interest_period.set_next_capitalization_date();
+
+        self.pay_interest(income);
     }
 
     fn close_interest_period(&mut self) {
         let interest_period = self.interest_period.take().unwrap();
         assert_eq!(self.date, interest_period.end_date);
-        self.assets += interest_period.accumulated_income;
+        self.pay_interest(interest_period.accumulated_income);
 
         self.select_interest_period();
     }
+
+    fn withhold_interest_tax(&mut self, year: i32) {
+        let interest = self.interest_payments.iter()
+            .filter(|payment| payment.date.year() == year)
+            .map(|payment| payment.amount)
+            .sum();
+
+        self.assets -= self.interest_tax.unwrap().tax(year, interest);
+    }
+
+    fn pay_interest(&mut self, income: Decimal) {
+        self.assets += income;
+        if !income.is_zero() {
+            self.interest_payments.push(Transaction::new(self.date, income));
+        }
+    }
 }
 
-#[cfg_attr(test, derive(Clone, Copy))]
+#[derive(Clone, Copy)]
 pub struct Transaction {
     pub date: Date,
     pub amount: Decimal,
@@ -183,6 +259,20 @@ impl Transaction {
     }
 }
 
+pub struct MonthlyStatement {
+    pub month: Month,
+    pub contributions: Decimal,
+    pub interest: Decimal,
+    pub assets: Decimal,
+}
+
+fn sum_month_transactions(transactions: &[Transaction], month: Month) -> Decimal {
+    transactions.iter()
+        .filter(|transaction| Month::from(transaction.date) == month)
+        .map(|transaction| transaction.amount)
+        .sum()
+}
+
 #[derive(Clone, Copy)]
 pub struct InterestPeriod {
     pub start: Date,
@@ -274,6 +364,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn monthly_statement() {
+        let open_date = date!(2019, 1, 31);
+        let transactions = vec![
+            Transaction::new(open_date, dec!(190_000)),
+            Transaction::new(date!(2019, 2,  5), dec!(60_000)),
+            Transaction::new(date!(2019, 2, 21), dec!(50_000)),
+        ];
+
+        let statements = DepositEmulator::new(open_date, date!(2019, 4, 30), dec!(7))
+            .emulate_monthly(&transactions);
+
+        let statements: Vec<_> = statements.iter().map(|statement| (
+            statement.month.format(), statement.contributions, currency::round(statement.interest),
+            currency::round(statement.assets),
+        )).collect();
+
+        assert_eq!(statements, vec![
+            (s!("01.2019"), dec!(190_000), dec!(0),        dec!(190_000)),
+            (s!("02.2019"), dec!(110_000), dec!(1_352.05), dec!(301_352.05)),
+            (s!("03.2019"), dec!(0),       dec!(1_791.60), dec!(303_143.65)),
+            (s!("04.2019"), dec!(0),       dec!(1_744.11), dec!(304_887.77)),
+        ]);
+    }
+
+    #[test]
+    fn interest_tax() {
+        let country = crate::localities::russia(&Default::default());
+        let interest_tax = DepositInterestTax::new(&country, "RUB", 2023..=2024).unwrap();
+
+        let open_date = date!(2023, 1, 1);
+        let close_date = date!(2024, 12, 31);
+        let transactions = vec![Transaction::new(open_date, dec!(2_000_000))];
+
+        let emulate = |interest_tax| {
+            let mut emulator = DepositEmulator::new(open_date, close_date, dec!(10))
+                .with_monthly_capitalization(false)
+                .with_interest_periods(&[
+                    InterestPeriod::new(open_date, date!(2023, 12, 31)),
+                    InterestPeriod::new(date!(2024, 1, 1), close_date),
+                ]);
+            if let Some(interest_tax) = interest_tax {
+                emulator = emulator.with_interest_tax(interest_tax);
+            }
+            currency::round(emulator.emulate(&transactions))
+        };
+
+        assert_eq!(emulate(None), dec!(2_000_000) + dec!(199_452.05) + dec!(219_945.21));
+
+        // 2023: 199 452.05 interest with 150 000 tax-free amount
+        // 2024: 219 302.31 interest (on the assets reduced by the tax) with 210 000 tax-free amount
+        assert_eq!(emulate(Some(&interest_tax)),
+            dec!(2_000_000) + dec!(199_452.05) - dec!(6_429) + dec!(219_302.31) - dec!(1_209));
+    }
+
     #[test]
     fn real_deposit_with_contributions() {
         let open_date = date!(2019, 1, 31);
@@ -8,13 +8,17 @@ use log::{self, log_enabled, debug, trace, warn};
 use crate::core::EmptyResult;
 use crate::currency::Cash;
 use crate::formatting;
+use crate::taxes::DepositInterestTax;
 use crate::types::Decimal;
 use crate::util;
 
 use super::deposit_emulator::{DepositEmulator, Transaction, InterestPeriod};
 
+/// Finds the interest of a bank deposit which gives the same result for the same cash flows. If interest tax is
+/// specified, the deposit is emulated with it, so the interest is comparable to the bank deposit rates.
 pub fn compare_to_bank_deposit(
-    transactions: &[Transaction], interest_periods: &[InterestPeriod], current_assets: Decimal
+    transactions: &[Transaction], interest_periods: &[InterestPeriod], current_assets: Decimal,
+    interest_tax: Option<&DepositInterestTax>,
 ) -> Option<(Decimal, Decimal)> {
     if log_enabled!(log::Level::Trace) {
         let transactions = transactions.iter().map(|transaction| {
@@ -44,9 +48,12 @@ pub fn compare_to_bank_deposit(
     );
 
     let emulate = |interest: Decimal| -> Decimal {
-        let result_assets = DepositEmulator::new(start_date, end_date, interest)
-            .with_interest_periods(interest_periods)
-            .emulate(transactions);
+        let mut emulator = DepositEmulator::new(start_date, end_date, interest)
+            .with_interest_periods(interest_periods);
+        if let Some(interest_tax) = interest_tax {
+            emulator = emulator.with_interest_tax(interest_tax);
+        }
+        let result_assets = emulator.emulate(transactions);
 
         (current_assets - result_assets).abs()
     };
@@ -150,7 +157,7 @@ mod tests {
     fn real_joint_deposits() {
         let compare = |transactions: &[Transaction], interest_periods: &[InterestPeriod], current_assets: Decimal| {
             let (interest, difference) = compare_to_bank_deposit(
-                transactions, interest_periods, current_assets).unwrap();
+                transactions, interest_periods, current_assets, None).unwrap();
 
             assert_eq!(interest, dec!(7));
             assert!(difference < dec!(0.01));
@@ -272,6 +279,6 @@ mod tests {
             InterestPeriod::new(date!(2021, 11, 12), date!(2022, 2, 4)),
         ];
 
-        assert_matches!(compare_to_bank_deposit(&transactions, &interest_periods, dec!(0)), None);
+        assert_matches!(compare_to_bank_deposit(&transactions, &interest_periods, dec!(0), None), None);
     }
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::BrokerStatement;
+use crate::config::PortfolioConfig;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::time::{self, Date};
+use crate::types::Decimal;
+use crate::util;
+
+/// Account age and contributions of a portfolio
+pub struct PortfolioFunding {
+    pub portfolio: String,
+    pub open_date: Date,
+    pub age: Decimal, // In years
+    pub contributions: Cash, // Deposits minus withdrawals
+    pub average_annual_contribution: Cash,
+}
+
+pub fn analyse(
+    portfolio: &PortfolioConfig, statement: &BrokerStatement, converter: &CurrencyConverter,
+) -> GenericResult<PortfolioFunding> {
+    let currency = portfolio.currency();
+    let open_date = portfolio.open_date.unwrap_or_else(|| statement.period.first_date());
+
+    let mut contributions = Cash::zero(currency);
+    for assets in &statement.deposits_and_withdrawals {
+        contributions.amount += converter.convert_to(assets.date, assets.cash, currency)?;
+    }
+
+    let age = Decimal::from(std::cmp::max(0, (time::today() - open_date).num_days())) / dec!(365.25);
+
+    // Don't extrapolate contributions of the accounts which are younger than one year
+    let average_annual_contribution = Cash::new(currency, contributions.amount / std::cmp::max(age, dec!(1)));
+
+    Ok(PortfolioFunding {
+        portfolio: portfolio.name.clone(),
+        open_date, age, contributions, average_annual_contribution,
+    })
+}
+
+pub fn print(funding: &[PortfolioFunding]) {
+    let mut table = FundingTable::new();
+
+    for portfolio in funding {
+        table.add_row(Row {
+            portfolio: portfolio.portfolio.clone(),
+            open_date: portfolio.open_date,
+            age: util::round(portfolio.age, 1),
+            contributions: portfolio.contributions.round(),
+            average_annual_contribution: portfolio.average_annual_contribution.round(),
+        });
+    }
+
+    table.print("Portfolio funding");
+}
+
+#[derive(StaticTable)]
+#[table(name="FundingTable")]
+struct Row {
+    #[column(name="Portfolio")]
+    portfolio: String,
+    #[column(name="Open date")]
+    open_date: Date,
+    #[column(name="Age (years)")]
+    age: Decimal,
+    #[column(name="Contributions")]
+    contributions: Cash,
+    #[column(name="Average annual contribution")]
+    average_annual_contribution: Cash,
+}
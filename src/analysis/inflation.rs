@@ -22,6 +22,11 @@ impl InflationCalc {
         })
     }
 
+    /// Returns yearly inflation (in percents) for all known years since the specified one
+    pub fn history(&self, since: i32) -> Vec<Decimal> {
+        (since..=self.today.year()).filter_map(self.get_inflation).collect()
+    }
+
     pub fn adjust(&self, mut date: Date, mut amount: Decimal) -> Decimal {
         while date < self.today {
             let year = date.year();
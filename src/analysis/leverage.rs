@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, NetAssets};
+use crate::cash_flow;
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting::table::Cell;
+use crate::time::Date;
+use crate::types::Decimal;
+use crate::util;
+
+/// Margin borrowing (negative cash balances) of a portfolio
+pub struct LeverageAnalysis {
+    pub portfolio: String,
+    pub current: Option<Leverage>,
+    pub max_borrowed: Option<Leverage>,
+    pub max_ratio: Option<Leverage>,
+}
+
+pub struct Leverage {
+    pub date: Option<Date>,
+    pub borrowed: Cash,
+    /// Gross assets to net value ratio (if net value is known)
+    pub ratio: Option<Decimal>,
+}
+
+/// Detects margin borrowing in the broker statement. Returns `None` if the portfolio has never borrowed anything.
+pub fn analyse(
+    portfolio: &str, statement: &BrokerStatement, net_value: Cash, converter: &CurrencyConverter,
+) -> GenericResult<Option<LeverageAnalysis>> {
+    calculate(
+        portfolio, &statement.assets.cash, net_value, &cash_flow::calculate_daily_cash_assets(statement),
+        &statement.historical_assets, converter)
+}
+
+fn calculate(
+    portfolio: &str, cash_assets: &MultiCurrencyCashAccount, net_value: Cash,
+    daily_cash_assets: &[(Date, MultiCurrencyCashAccount)], historical_assets: &BTreeMap<Date, NetAssets>,
+    converter: &CurrencyConverter,
+) -> GenericResult<Option<LeverageAnalysis>> {
+    let currency = net_value.currency;
+
+    let current = match get_borrowed(cash_assets) {
+        Some(borrowed) => {
+            let borrowed = Cash::new(currency, borrowed.total_assets_real_time(currency, converter)?);
+            Some(Leverage {
+                date: None,
+                ratio: get_ratio(net_value.amount, borrowed.amount),
+                borrowed,
+            })
+        },
+        None => None,
+    };
+
+    let mut max_borrowed: Option<Leverage> = None;
+
+    for (date, assets) in daily_cash_assets {
+        let Some(borrowed) = get_borrowed(assets) else {
+            continue;
+        };
+
+        let borrowed = Cash::new(currency, borrowed.total_assets(*date, currency, converter)?);
+        if max_borrowed.as_ref().map(|max| borrowed.amount > max.borrowed.amount).unwrap_or(true) {
+            max_borrowed = Some(Leverage {date: Some(*date), borrowed, ratio: None});
+        }
+    }
+
+    // Net value is known only for the dates where the broker provides other assets value
+    let mut max_ratio: Option<Leverage> = None;
+
+    for (date, assets) in historical_assets {
+        let (Some(other), Some(borrowed)) = (assets.other, get_borrowed(&assets.cash)) else {
+            continue;
+        };
+
+        let borrowed = Cash::new(currency, borrowed.total_assets(*date, currency, converter)?);
+        let net_value = assets.cash.total_assets(*date, currency, converter)?
+            + converter.convert_to(*date, other, currency)?;
+
+        let Some(ratio) = get_ratio(net_value, borrowed.amount) else {
+            continue;
+        };
+
+        if max_ratio.as_ref().and_then(|max| max.ratio).map(|max| ratio > max).unwrap_or(true) {
+            max_ratio = Some(Leverage {date: Some(*date), borrowed, ratio: Some(ratio)});
+        }
+    }
+
+    if current.is_none() && max_borrowed.is_none() && max_ratio.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(LeverageAnalysis {
+        portfolio: portfolio.to_owned(),
+        current, max_borrowed, max_ratio,
+    }))
+}
+
+// Returns negative cash balances as positive amounts
+fn get_borrowed(assets: &MultiCurrencyCashAccount) -> Option<MultiCurrencyCashAccount> {
+    let mut borrowed = MultiCurrencyCashAccount::new();
+
+    for amount in assets.iter() {
+        if amount.is_negative() {
+            borrowed.deposit(-amount);
+        }
+    }
+
+    (!borrowed.is_empty()).then_some(borrowed)
+}
+
+fn get_ratio(net_value: Decimal, borrowed: Decimal) -> Option<Decimal> {
+    (net_value.is_sign_positive() && !net_value.is_zero()).then(|| (net_value + borrowed) / net_value)
+}
+
+#[derive(StaticTable)]
+#[table(name="LeverageTable")]
+struct Row {
+    #[column(name="Portfolio")]
+    portfolio: String,
+    #[column(name="Borrowed")]
+    borrowed: Option<Cash>,
+    #[column(name="Leverage")]
+    ratio: Option<Decimal>,
+    #[column(name="Max borrowed")]
+    max_borrowed: Option<Cash>,
+    #[column(name="Date")]
+    max_borrowed_date: Option<Cell>,
+    #[column(name="Max leverage")]
+    max_ratio: Option<Decimal>,
+    #[column(name="Date")]
+    max_ratio_date: Option<Cell>,
+}
+
+pub fn print(analyses: &[LeverageAnalysis]) {
+    let round_ratio = |ratio| util::round(ratio, 2);
+    let mut table = LeverageTable::new();
+
+    for analysis in analyses {
+        table.add_row(Row {
+            portfolio: analysis.portfolio.clone(),
+            borrowed: analysis.current.as_ref().map(|current| current.borrowed.round()),
+            ratio: analysis.current.as_ref().and_then(|current| current.ratio).map(round_ratio),
+            max_borrowed: analysis.max_borrowed.as_ref().map(|max| max.borrowed.round()),
+            max_borrowed_date: analysis.max_borrowed.as_ref().and_then(|max| max.date).map(Cell::from),
+            max_ratio: analysis.max_ratio.as_ref().and_then(|max| max.ratio).map(round_ratio),
+            max_ratio_date: analysis.max_ratio.as_ref().and_then(|max| max.date).map(Cell::from),
+        });
+    }
+
+    table.print("Margin borrowing");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::currency::converter::CurrencyConverter;
+
+    use super::*;
+
+    #[test]
+    fn calculation() {
+        let usd = |amount| Cash::new("USD", amount);
+        let account = |amount| MultiCurrencyCashAccount::from(usd(amount));
+
+        let converter = CurrencyConverter::mock();
+
+        let daily_cash_assets = [
+            (date!(2023, 1, 10), account(dec!(1000))),
+            (date!(2023, 2, 10), account(dec!(-500))),
+            (date!(2023, 3, 10), account(dec!(-2000))),
+            (date!(2023, 4, 10), account(dec!(-300))),
+        ];
+
+        let historical_assets = btreemap! {
+            date!(2023, 2, 28) => NetAssets {
+                cash: account(dec!(-500)), other: Some(usd(dec!(2500))), receivables: None},
+            date!(2023, 3, 31) => NetAssets {
+                cash: account(dec!(-2000)), other: Some(usd(dec!(3000))), receivables: None},
+            date!(2023, 4, 30) => NetAssets {
+                cash: account(dec!(-300)), other: None, receivables: None},
+        };
+
+        let analysis = calculate(
+            "ib", &account(dec!(-300)), usd(dec!(1200)), &daily_cash_assets, &historical_assets, &converter,
+        ).unwrap().unwrap();
+
+        let current = analysis.current.unwrap();
+        assert_eq!((current.borrowed, current.ratio), (usd(dec!(300)), Some(dec!(1.25))));
+
+        let max_borrowed = analysis.max_borrowed.unwrap();
+        assert_eq!((max_borrowed.date, max_borrowed.borrowed), (Some(date!(2023, 3, 10)), usd(dec!(2000))));
+
+        let max_ratio = analysis.max_ratio.unwrap();
+        assert_eq!((max_ratio.date, max_ratio.ratio), (Some(date!(2023, 3, 31)), Some(dec!(3))));
+
+        assert!(calculate(
+            "ib", &account(dec!(100)), usd(dec!(100)), &daily_cash_assets[..1], &BTreeMap::new(), &converter,
+        ).unwrap().is_none());
+    }
+}
@@ -0,0 +1,217 @@
+use itertools::Itertools;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::BrokerStatement;
+use crate::commissions::CommissionCalc;
+use crate::config::PortfolioConfig;
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverterRc;
+use crate::localities::Country;
+use crate::quotes::Quotes;
+use crate::taxes::{LtoDeductibleProfit, LtoDeductionCalculator, TaxExemption, long_term_ownership};
+use crate::time::{self, Date};
+use crate::types::Decimal;
+
+struct Lot {
+    symbol: String,
+    date: Date,
+    quantity: Decimal,
+    exemption_date: Option<Date>,
+    profit: Cash,
+    exempt_profit: Option<Decimal>,
+    years: Option<u32>,
+}
+
+/// Lists open positions' lots with the dates when long-term ownership tax exemption becomes applicable to them and
+/// estimates the exemption limit usage per tax year assuming the current prices.
+pub fn plan(
+    country: &Country, portfolio: &PortfolioConfig, mut statement: BrokerStatement, converter: CurrencyConverterRc,
+    quotes: &Quotes,
+) -> EmptyResult {
+    if !portfolio.tax_exemptions.contains(&TaxExemption::LongTermOwnership) {
+        return Err!(
+            "Long-term ownership tax exemption is not enabled for {:?} portfolio (see tax_exemptions option)",
+            portfolio.name);
+    }
+
+    let symbols: Vec<String> = statement.open_positions.keys().cloned().sorted_unstable().collect();
+    if symbols.is_empty() {
+        println!("The portfolio has no open positions.");
+        return Ok(());
+    }
+
+    for symbol in &symbols {
+        quotes.batch(statement.get_quote_query(symbol))?;
+    }
+
+    let today = time::today();
+    let (current_tax_year, _) = portfolio.tax_payment_day().get(today, true);
+
+    // Emulate selling of all open positions to get their FIFO lots with the profit they'd give us today
+    let net_value = statement.net_value(&converter, quotes, portfolio.currency(), true)?;
+    let mut commission_calc = CommissionCalc::new(
+        converter.clone(), statement.broker.commission_spec.clone(), net_value)?;
+
+    for symbol in &symbols {
+        let quantity = statement.open_positions[symbol];
+        let price = quotes.get(statement.get_quote_query(symbol))?;
+        statement.emulate_sell(symbol, quantity, price, &mut commission_calc)?;
+    }
+
+    statement.process_trades(None)?;
+
+    let mut lots = Vec::new();
+    let mut realized = Vec::new();
+
+    for trade in &statement.stock_sells {
+        let (tax_year, _) = portfolio.tax_payment_day().get(trade.execution_date, true);
+        if !trade.emulation && tax_year != current_tax_year {
+            continue;
+        }
+
+        let instrument = statement.instrument_info.get_or_empty(&trade.symbol);
+        let details = trade.calculate(country, &instrument, &portfolio.tax_exemptions, &converter)?;
+
+        for source in details.fifo {
+            let deductible = source.long_term_ownership_deductible;
+
+            if !trade.emulation {
+                realized.extend(deductible);
+                continue;
+            }
+
+            let quantity = source.quantity * source.multiplier;
+            let revenue = converter.convert_to_cash_rounding(
+                trade.execution_date, details.revenue * quantity / trade.quantity, country.currency)?;
+            let cost = source.total_cost(country.currency, &converter)?;
+
+            let exemption_date = long_term_ownership::exemption_date(source.execution_date).filter(|&date| {
+                let sell_date = std::cmp::max(date, trade.execution_date);
+                long_term_ownership::is_applicable(&instrument.isin, sell_date).unwrap_or_default()
+            });
+
+            lots.push(Lot {
+                symbol: trade.symbol.clone(),
+                date: source.execution_date,
+                quantity: quantity.normalize(),
+                exemption_date,
+                profit: revenue - cost,
+                exempt_profit: deductible.map(|deductible| deductible.profit),
+                years: deductible.map(|deductible| deductible.years),
+            });
+        }
+    }
+
+    print_lots(country, &lots);
+    print_limits(country, portfolio, &lots, &realized, current_tax_year);
+
+    Ok(())
+}
+
+fn print_lots(country: &Country, lots: &[Lot]) {
+    let mut table = LotsTable::new();
+
+    for lot in lots {
+        table.add_row(LotRow {
+            symbol: lot.symbol.clone(),
+            date: lot.date,
+            quantity: lot.quantity,
+            exemption_date: lot.exemption_date,
+            profit: lot.profit,
+            exempt_profit: lot.exempt_profit.map(|profit| Cash::new(country.currency, profit)),
+            limit: lot.years.map(|years| Cash::new(country.currency, Decimal::from(years) * dec!(3_000_000))),
+        });
+    }
+
+    table.print("Long-term ownership exemption");
+}
+
+// Estimates the exemption limit usage for each tax year since the current one until all lots become eligible,
+// assuming that all eligible lots are sold at the end of the year at the current prices.
+fn print_limits(
+    country: &Country, portfolio: &PortfolioConfig, lots: &[Lot], realized: &[LtoDeductibleProfit],
+    current_tax_year: i32,
+) {
+    let last_tax_year = lots.iter()
+        .filter_map(|lot| lot.exemption_date)
+        .map(|date| portfolio.tax_payment_day().get(date, true).0)
+        .max().unwrap_or(current_tax_year);
+
+    let mut table = LimitsTable::new();
+    if realized.is_empty() {
+        table.hide_realized_profit();
+    }
+
+    for tax_year in current_tax_year..=std::cmp::max(current_tax_year, last_tax_year) {
+        let year_end = date!(tax_year, 12, 31);
+        let mut calculator = LtoDeductionCalculator::new();
+        let mut realized_profit = dec!(0);
+        let mut eligible_profit = dec!(0);
+
+        // Profit realized in the current year consumes its limit
+        if tax_year == current_tax_year {
+            for deductible in realized {
+                calculator.add(deductible.profit, deductible.years, false);
+                realized_profit += deductible.profit;
+            }
+        }
+
+        for lot in lots {
+            if lot.exemption_date.is_none_or(|date| date > year_end) || !lot.profit.is_positive() {
+                continue;
+            }
+
+            let years = long_term_ownership::calculate_ownership_years(lot.date, year_end);
+            calculator.add(lot.profit.amount, years, false);
+            eligible_profit += lot.profit.amount;
+        }
+
+        let limit = calculator.calculate().limit;
+
+        table.add_row(LimitRow {
+            year: tax_year,
+            realized_profit: Cash::new(country.currency, realized_profit),
+            eligible_profit: Cash::new(country.currency, eligible_profit),
+            limit: Cash::new(country.currency, limit),
+            remaining_limit: Cash::new(country.currency, std::cmp::max(
+                dec!(0), limit - realized_profit - eligible_profit)),
+        });
+    }
+
+    table.print("Long-term ownership exemption limits");
+}
+
+#[derive(StaticTable)]
+#[table(name="LotsTable")]
+struct LotRow {
+    #[column(name="Symbol")]
+    symbol: String,
+    #[column(name="Purchase date")]
+    date: Date,
+    #[column(name="Quantity")]
+    quantity: Decimal,
+    #[column(name="Exemption date")]
+    exemption_date: Option<Date>,
+    #[column(name="Profit")]
+    profit: Cash,
+    #[column(name="Exempt profit")]
+    exempt_profit: Option<Cash>,
+    #[column(name="Limit")]
+    limit: Option<Cash>,
+}
+
+#[derive(StaticTable)]
+#[table(name="LimitsTable")]
+struct LimitRow {
+    #[column(name="Year")]
+    year: i32,
+    #[column(name="Realized exempt profit")]
+    realized_profit: Cash,
+    #[column(name="Eligible profit")]
+    eligible_profit: Cash,
+    #[column(name="Limit")]
+    limit: Cash,
+    #[column(name="Remaining limit")]
+    remaining_limit: Cash,
+}
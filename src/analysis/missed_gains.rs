@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use log::{debug, warn};
+use static_table_derive::StaticTable;
+
+use crate::brokers::BrokerInfo;
+use crate::config::PortfolioConfig;
+use crate::core::GenericResult;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting::{self, table::Cell};
+use crate::quotes::{HistoricalQuoteKind, QuoteQuery, Quotes};
+use crate::time::{self, Date};
+use crate::types::Decimal;
+use crate::util;
+
+use super::deposit_emulator::InterestPeriod;
+use super::deposit_performance;
+use super::portfolio_performance_types::PortfolioPerformanceAnalysis;
+
+/// Compares actual portfolio result to the result which would have been achieved if every deposit had been
+/// immediately invested into the target asset allocation, which quantifies the cash drag caused by investment timing.
+///
+/// The target portfolio is emulated using historical prices only, so to compare like with like the actual result is
+/// also reduced to its price-only part: dividends, interest, taxes, tax deductions and commissions are excluded from
+/// it.
+pub struct MissedGainsAnalysis {
+    pub currency: String,
+    pub contributions: Decimal,
+
+    pub actual_result: Decimal,
+    pub actual_interest: Option<Decimal>,
+
+    pub target_result: Decimal,
+    pub target_interest: Option<Decimal>,
+}
+
+impl MissedGainsAnalysis {
+    pub fn missed_gains(&self) -> Decimal {
+        self.target_result - self.actual_result
+    }
+}
+
+pub fn analyse(
+    portfolio: &PortfolioConfig, broker: &BrokerInfo, currency: &str, performance: &PortfolioPerformanceAnalysis,
+    converter: &CurrencyConverter, quotes: &Quotes,
+) -> GenericResult<MissedGainsAnalysis> {
+    let weights = portfolio.get_target_weights();
+    if weights.is_empty() {
+        return Err!("{:?} portfolio has no target asset allocation", portfolio.name);
+    }
+
+    let today = time::today();
+    let contributions = &performance.contributions;
+    let start_date = contributions.first().map(|transaction| transaction.date).ok_or(
+        "The portfolio has no deposits")?;
+
+    let query = |symbol: &str| QuoteQuery::Stock(symbol.to_owned(), broker.exchanges());
+    quotes.prefetch_historical(weights.keys().flat_map(|symbol| {
+        contributions.iter().map(move |transaction| (query(symbol), transaction.date))
+    }))?;
+
+    // Emulate the target asset allocation portfolio using historical prices: each deposit is immediately invested
+    // according to the target weights and each withdrawal sells all positions proportionally.
+    let mut quantities: BTreeMap<&str, Decimal> = weights.keys().map(|symbol| (symbol.as_str(), dec!(0))).collect();
+
+    for transaction in contributions {
+        let mut prices = BTreeMap::new();
+        for symbol in weights.keys() {
+            let price = get_historical_price(symbol, transaction.date, currency, &query, converter, quotes)?;
+            prices.insert(symbol.as_str(), price);
+        }
+
+        if transaction.amount.is_sign_positive() {
+            for (symbol, quantity) in quantities.iter_mut() {
+                *quantity += transaction.amount * weights[*symbol] / prices[symbol];
+            }
+        } else {
+            let value: Decimal = quantities.iter().map(|(symbol, quantity)| quantity * prices[symbol]).sum();
+            if value.is_zero() {
+                continue;
+            }
+
+            let remaining = dec!(1) - std::cmp::min(-transaction.amount / value, dec!(1));
+            for quantity in quantities.values_mut() {
+                *quantity *= remaining;
+            }
+        }
+    }
+
+    let mut target_result = dec!(0);
+
+    for (symbol, quantity) in quantities {
+        let price = quotes.get(query(symbol))?;
+        let value = converter.real_time_convert_to(price * quantity, currency)?;
+
+        debug!("Target asset allocation: {} - {}% ({} units worth {} {}).",
+            symbol, (weights[symbol] * dec!(100)).normalize(), util::round(quantity, 2), util::round(value, 2),
+            currency);
+
+        target_result += value;
+    }
+
+    let income = &performance.income_structure;
+    let actual_result = performance.portfolio.result - income.net_profit + income.gross_trading_income();
+
+    let activity_periods = [InterestPeriod::new(start_date, std::cmp::max(start_date, today))];
+    let [actual_interest, target_interest] = [actual_result, target_result].map(|result| {
+        deposit_performance::compare_to_bank_deposit(contributions, &activity_periods, result, None)
+            .map(|(interest, _difference)| interest)
+    });
+
+    Ok(MissedGainsAnalysis {
+        currency: currency.to_owned(),
+        contributions: contributions.iter().map(|transaction| transaction.amount).sum(),
+
+        actual_result,
+        actual_interest,
+
+        target_result,
+        target_interest,
+    })
+}
+
+fn get_historical_price(
+    symbol: &str, date: Date, currency: &str, query: &dyn Fn(&str) -> QuoteQuery,
+    converter: &CurrencyConverter, quotes: &Quotes,
+) -> GenericResult<Decimal> {
+    let quote = quotes.get_historical(query(symbol), date)?.ok_or_else(|| format!(
+        "Unable to get {} price as of {}: there are no historical quotes for it",
+        symbol, formatting::format_date(date)))?;
+
+    if quote.kind != HistoricalQuoteKind::Actual {
+        warn!("There is no actual {} quote as of {}. Using {}.",
+            symbol, formatting::format_date(date), quote.price);
+    }
+
+    let price = converter.convert_to(date, quote.price, currency)?;
+    if price.is_zero() || price.is_sign_negative() {
+        return Err!("Got an invalid {} price as of {}: {}", symbol, formatting::format_date(date), price);
+    }
+
+    Ok(price)
+}
+
+#[derive(StaticTable)]
+#[table(name="MissedGainsTable")]
+struct Row {
+    #[column(name="Currency")]
+    currency: String,
+    #[column(name="Contributions")]
+    contributions: Cell,
+    #[column(name="Actual result")]
+    actual_result: Cell,
+    #[column(name="Actual interest", align="right")]
+    actual_interest: Option<String>,
+    #[column(name="Target result")]
+    target_result: Cell,
+    #[column(name="Target interest", align="right")]
+    target_interest: Option<String>,
+    #[column(name="Missed gains")]
+    missed_gains: Cell,
+}
+
+pub fn print(analyses: &[MissedGainsAnalysis]) {
+    let mut table = MissedGainsTable::new();
+
+    for analysis in analyses {
+        table.add_row(Row {
+            currency: analysis.currency.clone(),
+            contributions: Cell::new_round_decimal(util::round(analysis.contributions, 0)),
+            actual_result: Cell::new_round_decimal(util::round(analysis.actual_result, 0)),
+            actual_interest: analysis.actual_interest.map(|interest| format!("{}%", interest)),
+            target_result: Cell::new_round_decimal(util::round(analysis.target_result, 0)),
+            target_interest: analysis.target_interest.map(|interest| format!("{}%", interest)),
+            missed_gains: Cell::new_round_decimal(util::round(analysis.missed_gains(), 0)),
+        });
+    }
+
+    table.print(concat!(
+        "Missed gains compared to immediate investment into the target asset allocation ",
+        "(price changes only, excluding dividends, interest, taxes and commissions)"));
+}
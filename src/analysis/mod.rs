@@ -1,39 +1,56 @@
 pub mod config;
 pub mod deposit_emulator;
 mod deposit_performance;
+mod funding;
 mod inflation;
 mod instrument_view;
+mod leverage;
+mod lto_plan;
+mod missed_gains;
 mod portfolio_analysis;
 mod portfolio_performance_types;
 mod portfolio_performance;
+mod receivables;
 mod sell_simulation;
+mod stress_test;
+mod wash_sales;
+mod withdrawal_plan;
 pub mod portfolio_statistics;
 
 use std::collections::HashMap;
 use std::rc::Rc;
 
+use chrono::Datelike;
 use easy_logging::GlobalContext;
 
-use crate::broker_statement::{BrokerStatement, ReadingStrictness};
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections};
 use crate::config::{Config, PortfolioConfig};
 use crate::core::GenericResult;
 use crate::currency::converter::{CurrencyConverter, CurrencyConverterRc};
 use crate::db;
+use crate::instruments;
+use crate::portfolio::ManualTrade;
 use crate::quotes::{Quotes, QuotesRc};
 use crate::taxes::{LtoDeductionCalculator, TaxCalculator};
 use crate::telemetry::TelemetryRecordBuilder;
+use crate::time;
 use crate::types::Decimal;
 
 use self::config::{AssetGroupConfig, PerformanceMergingConfig};
+use self::inflation::InflationCalc;
 use self::portfolio_analysis::PortfolioAnalyser;
 use self::portfolio_statistics::PortfolioStatistics;
+use self::withdrawal_plan::{WithdrawalPlanner, INFLATION_HISTORY_SINCE};
+
+pub use crate::broker_statement::SellLot;
 
 pub use self::portfolio_performance_types::PerformanceAnalysisMethod;
+pub use self::withdrawal_plan::{WithdrawalPlanConfig, WithdrawalStrategy};
 
 pub fn analyse(
     config: &Config, portfolio_name: Option<&str>, include_closed_positions: bool,
     asset_groups: &HashMap<String, AssetGroupConfig>, merge_performance: Option<&PerformanceMergingConfig>,
-    interactive: bool,
+    attribute_fees: bool, interactive: bool,
 ) -> GenericResult<(PortfolioStatistics, QuotesRc, TelemetryRecordBuilder)> {
     let mut telemetry = TelemetryRecordBuilder::new();
 
@@ -49,7 +66,7 @@ pub fn analyse(
 
     let analyser = PortfolioAnalyser {
         country: country.clone(),
-        interactive, include_closed_positions,
+        interactive, include_closed_positions, attribute_fees,
 
         asset_groups, merge_performance,
         quotes: quotes.clone(), converter,
@@ -59,29 +76,122 @@ pub fn analyse(
     };
     analyser.process(portfolios, &mut statistics)?;
 
+    // Asset group shares are meaningful only when all portfolios are analysed
+    if portfolio_name.is_none() {
+        statistics.check_asset_groups();
+    }
+
     Ok((statistics, quotes, telemetry))
 }
 
-pub fn simulate_sell(
-    config: &Config, portfolio_name: &str, positions: Option<Vec<(String, Option<Decimal>)>>,
-    base_currency: Option<&str>,
+pub fn analyse_missed_gains(
+    config: &Config, portfolio_name: &str, method: PerformanceAnalysisMethod, include_closed_positions: bool,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let (statistics, quotes, telemetry) = analyse(
+        config, Some(portfolio_name), include_closed_positions, &Default::default(), None, false, true)?;
+    let converter = CurrencyConverter::new(db::connect(&config.db_path)?, Some(quotes.clone()), false);
+
+    let analyses = statistics.currencies.iter().map(|statistics| {
+        missed_gains::analyse(
+            portfolio, &broker, &statistics.currency, statistics.performance(method), &converter, &quotes)
+    }).collect::<GenericResult<Vec<_>>>()?;
+
+    missed_gains::print(&analyses);
+    Ok(telemetry)
+}
+
+pub fn plan_withdrawals(
+    config: &Config, portfolio_name: &str, plan_config: WithdrawalPlanConfig,
 ) -> GenericResult<TelemetryRecordBuilder> {
     let portfolio = config.get_portfolio(portfolio_name)?;
+    let currency = portfolio.currency();
+
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let (statistics, quotes, telemetry) = analyse(
+        config, Some(portfolio_name), false, &Default::default(), None, false, true)?;
+    let converter = CurrencyConverter::new(db::connect(&config.db_path)?, Some(quotes.clone()), false);
+
+    let statistics = statistics.currencies.iter()
+        .find(|statistics| statistics.currency == currency)
+        .ok_or_else(|| format!("{} currency is not supported by withdrawal planning", currency))?;
+
+    // Use tax-aware performance since taxes reduce the amount available for withdrawal
+    let performance = &statistics.performance(PerformanceAnalysisMethod::Real).portfolio;
+    let expected_return = performance.interest.ok_or(
+        "Unable to calculate the portfolio rate of return")?;
+
+    let assets = statistics.assets.iter().map(|(instrument, portfolios)| {
+        (instrument.clone(), portfolios.values().map(|asset| asset.value).sum())
+    }).collect();
+    let returns = withdrawal_plan::get_historical_returns(
+        &assets, currency, &broker.exchanges(), time::today().year(), &converter, &quotes)?;
+
+    let inflation = InflationCalc::new(currency, time::today())?.history(INFLATION_HISTORY_SINCE);
+    let planner = WithdrawalPlanner::new(plan_config, performance.result, expected_return, returns, inflation)?;
+    planner.simulate(currency).print();
+
+    Ok(telemetry)
+}
+
+pub fn plan_lto(config: &Config, portfolio_name: &str) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
 
     let statement = load_portfolio(config, portfolio,
         ReadingStrictness::TRADE_SETTLE_DATE | ReadingStrictness::OTC_INSTRUMENTS | ReadingStrictness::TAX_EXEMPTIONS)?;
     let (converter, quotes) = load_tools(config)?;
 
+    lto_plan::plan(&config.get_tax_country(), portfolio, statement, converter, &quotes)?;
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+pub fn stress_test(config: &Config, portfolio_name: &str, scenario_name: &str) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let scenario = stress_test::get_scenario(config, scenario_name)?;
+
+    let statement = load_portfolio(config, portfolio, ReadingStrictness::empty())?;
+    statement.check_date();
+
+    let (converter, quotes) = load_tools(config)?;
+    let result = stress_test::stress_test(portfolio, &statement, &scenario, &converter, &quotes)?;
+    result.print(scenario_name);
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+pub fn simulate_sell(
+    config: &Config, portfolio_name: &str, positions: Option<Vec<(String, Option<Decimal>)>>, lots: Vec<SellLot>,
+    base_currency: Option<&str>,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let reading_strictness =
+        ReadingStrictness::TRADE_SETTLE_DATE | ReadingStrictness::OTC_INSTRUMENTS | ReadingStrictness::TAX_EXEMPTIONS;
+
+    let statement = load_portfolio(config, portfolio, reading_strictness)?;
+
+    // Selling of the specified lots is compared against the default FIFO matching
+    let fifo_statement = if lots.is_empty() {
+        None
+    } else {
+        Some(load_portfolio(config, portfolio, reading_strictness)?)
+    };
+
+    let (converter, quotes) = load_tools(config)?;
+
     sell_simulation::simulate_sell(
-        &config.get_tax_country(), portfolio, statement,
-        converter, &quotes, positions, base_currency)?;
+        &config.get_tax_country(), portfolio, statement, fifo_statement,
+        converter, &quotes, positions, lots, base_currency)?;
 
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
 }
 
 fn load_portfolios<'a>(config: &'a Config, name: Option<&str>) -> GenericResult<Vec<(&'a PortfolioConfig, BrokerStatement)>> {
     let mut portfolios = Vec::new();
-    let reading_strictness = ReadingStrictness::REPO_TRADES | ReadingStrictness::TAX_EXEMPTIONS;
+    let reading_strictness = ReadingStrictness::TAX_EXEMPTIONS;
 
     if let Some(name) = name {
         let portfolio = config.get_portfolio(name)?;
@@ -106,13 +216,26 @@ fn load_portfolios<'a>(config: &'a Config, name: Option<&str>) -> GenericResult<
 
 fn load_portfolio(config: &Config, portfolio: &PortfolioConfig, strictness: ReadingStrictness) -> GenericResult<BrokerStatement> {
     let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
-    BrokerStatement::read(
-        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
-        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
-        &portfolio.corporate_actions, strictness)
+
+    let mut statement = match portfolio.statements {
+        Some(ref path) => BrokerStatement::read(
+            broker, path, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+            &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
+            &portfolio.corporate_actions, strictness, StatementSections::all())?,
+
+        // Portfolios without broker statements may be analysed using manually recorded trades
+        None => {
+            let database = db::connect(&config.db_path)?;
+            let trades = ManualTrade::load(database, &portfolio.name)?;
+            BrokerStatement::new_manual(broker, &trades, &portfolio.instrument_names)?
+        },
+    };
+
+    instruments::reference::enrich(config, &mut statement.instrument_info);
+    Ok(statement)
 }
 
-fn load_tools(config: &Config) -> GenericResult<(CurrencyConverterRc, QuotesRc)> {
+pub fn load_tools(config: &Config) -> GenericResult<(CurrencyConverterRc, QuotesRc)> {
     let database = db::connect(&config.db_path)?;
     let quotes = Rc::new(Quotes::new(config, database.clone())?);
     let converter = CurrencyConverter::new(database, Some(quotes.clone()), false);
@@ -12,10 +12,16 @@ use crate::currency::Cash;
 use crate::currency::converter::CurrencyConverterRc;
 use crate::instruments::Instrument;
 use crate::localities::Country;
+use crate::portfolio;
 use crate::quotes::QuotesRc;
-use crate::taxes::{LtoDeductionCalculator, TaxCalculator};
+use crate::taxes::{IisDeduction, LtoDeductionCalculator, TaxCalculator};
+use crate::time;
 
 use super::config::{AssetGroupConfig, PerformanceMergingConfig};
+use super::funding;
+use super::leverage;
+use super::receivables;
+use super::wash_sales;
 use super::portfolio_performance::PortfolioPerformanceAnalyser;
 use super::portfolio_performance_types::PerformanceAnalysisMethod;
 use super::portfolio_statistics::{AssetGroup, PortfolioStatistics, LtoStatistics};
@@ -24,6 +30,7 @@ pub struct PortfolioAnalyser<'a> {
     pub country: Country,
     pub interactive: bool,
     pub include_closed_positions: bool,
+    pub attribute_fees: bool,
 
     pub asset_groups: &'a HashMap<String, AssetGroupConfig>,
     pub merge_performance: Option<&'a PerformanceMergingConfig>,
@@ -54,6 +61,9 @@ impl<'a> PortfolioAnalyser<'a> {
             let group = AssetGroup {
                 taxes: TaxCalculator::new(self.country.clone()),
                 net_value: config.currencies.iter().map(|currency| Cash::zero(currency)).collect(),
+                total_net_value: config.currencies.iter().map(|currency| Cash::zero(currency)).collect(),
+                min_share: config.min_share,
+                max_share: config.max_share,
             };
             assert!(statistics.asset_groups.insert(name.clone(), group).is_none());
         }
@@ -64,16 +74,44 @@ impl<'a> PortfolioAnalyser<'a> {
             let broker = statement.broker.type_;
             if self.interactive {
                 statement.check_date();
+                portfolio::check_idle_cash(&self.country, portfolio, statement, &self.converter, &self.quotes)?;
+                wash_sales::check(&self.country, portfolio, statement, &self.converter)?;
             }
 
             statistics.process(|statistics| {
                 let cash_assets = statement.assets.cash.total_assets_real_time(
                     &statistics.currency, &self.converter)?;
+                statistics.add_assets(&portfolio.name, broker, "Cash", cash_assets, cash_assets);
 
-                Ok(statistics.add_assets(&portfolio.name, broker, "Cash", cash_assets, cash_assets))
+                if let Some(receivables) = statement.expected_receivables(&self.converter, &statistics.currency)? {
+                    let receivables = receivables.amount;
+                    if !receivables.is_zero() {
+                        statistics.add_assets(&portfolio.name, broker, "Receivables", receivables, receivables);
+                    }
+                }
+
+                Ok(())
             })?;
 
+            if !self.asset_groups.is_empty() {
+                let currency = portfolio.currency();
+                let mut cash_assets = Cash::new(
+                    currency, statement.assets.cash.total_assets_real_time(currency, &self.converter)?);
+
+                if let Some(receivables) = statement.expected_receivables(&self.converter, currency)? {
+                    cash_assets += receivables;
+                }
+
+                self.add_asset_groups_total(portfolio, &[cash_assets], statistics)?;
+            }
+
             let net_value = statement.net_value(&self.converter, &self.quotes, portfolio.currency(), true)?;
+            if let Some(leverage) = leverage::analyse(&portfolio.name, statement, net_value, &self.converter)? {
+                statistics.leverage.push(leverage);
+            }
+            statistics.at_risk_receivables.extend(receivables::analyse(&portfolio.name, statement));
+            statistics.funding.push(funding::analyse(portfolio, statement, &self.converter)?);
+
             let mut commission_calc = CommissionCalc::new(
                 self.converter.clone(), statement.broker.commission_spec.clone(), net_value)?;
 
@@ -91,6 +129,10 @@ impl<'a> PortfolioAnalyser<'a> {
                 Ok(())
             })?;
 
+            if portfolio.iis_deduction {
+                self.process_iis_deductions(portfolio, statement, statistics)?;
+            }
+
             statement.process_trades(None)?;
 
             for trade in statement.stock_sells.iter().rev() {
@@ -106,6 +148,28 @@ impl<'a> PortfolioAnalyser<'a> {
         self.process_totals(portfolios, statistics)
     }
 
+    fn process_iis_deductions(
+        &self, portfolio: &PortfolioConfig, statement: &BrokerStatement, statistics: &mut PortfolioStatistics,
+    ) -> EmptyResult {
+        let today = time::today();
+        let mut projected = Cash::zero(self.country.currency);
+
+        let deductions = IisDeduction::calculate(
+            &statement.deposits_and_withdrawals, &portfolio.iis_paid_taxes, false)?;
+
+        for deduction in deductions {
+            if deduction.date() > today {
+                projected.amount += deduction.deduction;
+            }
+        }
+
+        statistics.process(|statistics| {
+            let projected = self.converter.real_time_convert_to(projected, &statistics.currency)?;
+            statistics.projected_tax_deductions += projected;
+            Ok(())
+        })
+    }
+
     fn process_asset(
         &mut self, portfolio: &PortfolioConfig, instrument: &Instrument, trade: &StockSell,
         statistics: &mut PortfolioStatistics,
@@ -126,6 +190,7 @@ impl<'a> PortfolioAnalyser<'a> {
 
         let (tax_year, _) = portfolio.tax_payment_day().get(trade.execution_date, true);
         let totals_tax = details.tax(&mut self.taxes, tax_year);
+        self.add_asset_groups_total(portfolio, &[volume, -commission, -totals_tax.to_pay], statistics)?;
 
         for (name, config) in self.asset_groups {
             if let Some(portfolios) = config.portfolios.as_ref() {
@@ -166,6 +231,28 @@ impl<'a> PortfolioAnalyser<'a> {
         })
     }
 
+    fn add_asset_groups_total(
+        &self, portfolio: &PortfolioConfig, amounts: &[Cash], statistics: &mut PortfolioStatistics,
+    ) -> EmptyResult {
+        for (name, config) in self.asset_groups {
+            if let Some(portfolios) = config.portfolios.as_ref() {
+                if !portfolios.contains(&portfolio.name) {
+                    continue;
+                }
+            }
+
+            let group = statistics.asset_groups.get_mut(name).unwrap();
+
+            for total_net_value in group.total_net_value.iter_mut() {
+                for &amount in amounts {
+                    total_net_value.amount += self.converter.real_time_convert_to(amount, total_net_value.currency)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn process_totals(
         self, portfolios: Vec<(&'a PortfolioConfig, BrokerStatement)>, statistics: &mut PortfolioStatistics,
     ) -> EmptyResult {
@@ -177,7 +264,7 @@ impl<'a> PortfolioAnalyser<'a> {
             statistics.process(|statistics| {
                 let mut analyser = PortfolioPerformanceAnalyser::new(
                     &self.country, &statistics.currency, &self.converter,
-                    method, self.include_closed_positions);
+                    method, self.include_closed_positions, self.attribute_fees);
 
                 for (portfolio, statement) in &portfolios {
                     let mut performance_merging_config = portfolio.merge_performance.clone();
@@ -1,16 +1,20 @@
 use std::collections::{HashMap, BTreeMap};
 
+use chrono::Datelike;
 use itertools::Itertools;
 use log::{self, log_enabled, trace};
 
-use crate::broker_statement::{BrokerStatement, StockSource, StockSellType};
+use crate::broker_statement::{
+    BrokerStatement, CashGrantsTaxCalculator, CorporateActionType, StockSource, StockSellType};
 use crate::config::PortfolioConfig;
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
 use crate::currency::converter::CurrencyConverter;
 use crate::formatting;
-use crate::localities::Country;
-use crate::taxes::{NetTax, NetTaxCalculator, NetLtoDeduction, NetLtoDeductionCalculator, TaxCalculator};
+use crate::localities::{Country, Jurisdiction};
+use crate::taxes::{
+    DepositInterestTax, IisDeduction, NetTax, NetTaxCalculator, NetLtoDeduction, NetLtoDeductionCalculator,
+    TaxCalculator};
 use crate::time::{self, Date, DateOptTime};
 use crate::types::Decimal;
 
@@ -21,6 +25,7 @@ use super::inflation::InflationCalc;
 use super::instrument_view::InstrumentDepositView;
 use super::portfolio_performance_types::{
     PerformanceAnalysisMethod, PortfolioPerformanceAnalysis, InstrumentPerformanceAnalysis, IncomeStructure};
+use super::wash_sales;
 
 /// Calculates average rate of return from cash investments by comparing portfolio performance to
 /// performance of a bank deposit with exactly the same investments and monthly capitalization.
@@ -31,11 +36,14 @@ pub struct PortfolioPerformanceAnalyser<'a> {
     converter: &'a CurrencyConverter,
     method: PerformanceAnalysisMethod,
     include_closed_positions: bool,
+    attribute_fees: bool,
     performance_merging_config: Option<PerformanceMergingConfig>,
 
     transactions: Vec<Transaction>,
+    contributions: Vec<Transaction>,
     income_structure: IncomeStructure,
     instruments: Option<BTreeMap<String, InstrumentDepositView>>,
+    holding_changes: Vec<HoldingChange>,
     net_lto_calc: NetLtoDeductionCalculator,
     tax_calculator: TaxCalculator,
     current_assets: Decimal,
@@ -44,7 +52,7 @@ pub struct PortfolioPerformanceAnalyser<'a> {
 impl <'a> PortfolioPerformanceAnalyser<'a> {
     pub fn new(
         country: &'a Country, currency: &'a str, converter: &'a CurrencyConverter,
-        method: PerformanceAnalysisMethod, include_closed_positions: bool,
+        method: PerformanceAnalysisMethod, include_closed_positions: bool, attribute_fees: bool,
     ) -> PortfolioPerformanceAnalyser<'a> {
         PortfolioPerformanceAnalyser {
             today: time::today(),
@@ -53,11 +61,14 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
             converter,
             method,
             include_closed_positions,
+            attribute_fees,
             performance_merging_config: None,
 
             transactions: Vec::new(),
+            contributions: Vec::new(),
             income_structure: Default::default(),
             instruments: Some(BTreeMap::new()),
+            holding_changes: Vec::new(),
             net_lto_calc: NetLtoDeductionCalculator::new(),
             tax_calculator: TaxCalculator::new(country.clone()),
             current_assets: dec!(0),
@@ -81,15 +92,23 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
                 "Unable to calculate current assets: The broker statement has open positions");
         }
 
+        if !statement.derivative_positions.is_empty() {
+            return Err!("Unable to calculate current assets: Open derivative positions aren't supported yet");
+        }
+
         trace!("Deposit emulator transactions for {:?}:", portfolio.name);
         self.process_deposits_and_withdrawals(statement)?;
+        self.holding_changes.clear();
         self.process_positions(statement, portfolio)?;
         self.process_dividends(statement, portfolio)?;
+        self.process_coupons(statement, portfolio)?;
+        self.process_amortizations(statement)?;
         self.process_interest(statement, portfolio)?;
-        self.process_grants(statement)?;
+        self.process_margin_interest(statement)?;
+        self.process_grants(statement, portfolio)?;
         self.process_fees(statement)?;
         self.process_tax_agent_withholdings(statement)?;
-        self.process_tax_deductions(portfolio)?;
+        self.process_tax_deductions(statement, portfolio)?;
         self.process_cash_assets(statement)?;
 
         for (symbol, deposit_view) in self.instruments.as_mut().unwrap().iter_mut() {
@@ -117,9 +136,12 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
 
         let portfolio_performance = self.analyse_portfolio_performance()?;
         self.income_structure.net_profit = portfolio_performance.net_profit();
+        self.contributions.sort_by_key(|transaction| transaction.date);
+        let contributions = self.adjust_transactions(&self.contributions)?;
 
         Ok((PortfolioPerformanceAnalysis {
             income_structure: self.income_structure,
+            contributions,
             instruments: instrument_performance,
             portfolio: portfolio_performance,
         }, self.net_lto_calc.calculate()))
@@ -134,7 +156,7 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
         let adjusted_transactions = self.adjust_transactions(&deposit_view.transactions)?;
 
         let interest = deposit_performance::compare_to_bank_deposit(
-            &adjusted_transactions, &deposit_view.interest_periods, dec!(0),
+            &adjusted_transactions, &deposit_view.interest_periods, dec!(0), None,
         ).map(|(interest, difference)| -> GenericResult<Decimal> {
             deposit_performance::check_emulation_precision(
                 symbol, self.currency, &adjusted_transactions,
@@ -172,11 +194,16 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
         self.transactions.sort_by_key(|transaction| transaction.date);
         let adjusted_transactions = self.adjust_transactions(&self.transactions)?;
 
-        let activity_periods = vec![InterestPeriod::new(
-            self.transactions.first().unwrap().date, self.today)];
+        let start_date = self.transactions.first().unwrap().date;
+        let activity_periods = vec![InterestPeriod::new(start_date, self.today)];
+
+        // Deposit interest tax-free amount is applied to all person's deposits, so take it into account only for the
+        // whole portfolio
+        let interest_tax = self.method.tax_aware().then(|| DepositInterestTax::new(
+            self.country, self.currency, start_date.year()..=self.today.year())).flatten();
 
         let interest = deposit_performance::compare_to_bank_deposit(
-            &adjusted_transactions, &activity_periods, self.current_assets,
+            &adjusted_transactions, &activity_periods, self.current_assets, interest_tax.as_ref(),
         ).map(|(interest, difference)| -> GenericResult<Decimal> {
             deposit_performance::check_emulation_precision(
                 "portfolio", self.currency, &adjusted_transactions,
@@ -238,6 +265,7 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
             }, formatting::format_date(assets.date), amount.normalize());
 
             self.transaction(assets.date, amount);
+            self.contributions.push(Transaction::new(assets.date, amount));
         }
 
         Ok(())
@@ -274,11 +302,15 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
                     deposit_view.trade(&portfolio.name, &trade.symbol, trade.conclusion_time, quantity);
                     deposit_view.transaction(trade.conclusion_time, volume);
                     deposit_view.transaction(trade.conclusion_time, commission);
+
+                    self.holding_change(&trade.symbol, trade.conclusion_time, quantity, volume + commission);
                 },
 
                 StockSource::CorporateAction | StockSource::Grant => {
                     self.get_deposit_view(&trade.symbol).trade(
                         &portfolio.name, &trade.symbol, trade.conclusion_time, quantity);
+
+                    self.holding_change(&trade.symbol, trade.conclusion_time, quantity, dec!(0));
                 },
             };
         }
@@ -309,10 +341,19 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
                         }
                     }
 
+                    self.holding_change(&trade.symbol, trade.conclusion_time, -quantity, -volume + commission);
+
                     if let Some(taxes) = taxes.as_mut() {
                         let (tax_year, _) = portfolio.tax_payment_day().get(trade.execution_date, true);
                         let instrument = statement.instrument_info.get_or_empty(&trade.symbol);
-                        let details = trade.calculate(self.country, &instrument, &portfolio.tax_exemptions, self.converter)?;
+                        let mut details = trade.calculate(
+                            self.country, &instrument, &portfolio.tax_exemptions, self.converter)?;
+
+                        // Don't count on the deduction from the losses which may be challenged by tax authorities
+                        if portfolio.exclude_wash_sale_losses &&
+                            wash_sales::is_wash_sale(portfolio, statement, trade, &details) {
+                            details.taxable_local_profit.amount = dec!(0);
+                        }
 
                         let mut lto_deductibles = Vec::new();
 
@@ -339,6 +380,8 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
                 StockSellType::CorporateAction => {
                     self.get_deposit_view(&trade.symbol).trade(
                         &portfolio.name, &trade.symbol, trade.conclusion_time, -quantity);
+
+                    self.holding_change(&trade.symbol, trade.conclusion_time, -quantity, dec!(0));
                 },
             };
         }
@@ -413,8 +456,51 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
         Ok(())
     }
 
+    fn process_coupons(&mut self, statement: &BrokerStatement, portfolio: &PortfolioConfig) -> EmptyResult {
+        for coupon in &statement.coupons {
+            let income = self.converter.convert_to(coupon.date, coupon.amount, self.currency)?;
+
+            self.get_deposit_view(&coupon.symbol).transaction(coupon.date.into(), -income);
+            self.income_structure.interest += income;
+
+            if self.method.tax_aware() {
+                let tax_to_pay = coupon.tax(self.country, self.converter, &mut self.tax_calculator)?;
+                let (_, tax_payment_date) = portfolio.tax_payment_day().get(coupon.date, false);
+
+                if let Some(amount) = self.map_tax_to_deposit_amount(tax_payment_date, tax_to_pay)? {
+                    trace!("* {} {} coupon {} tax: {}",
+                        coupon.original_symbol, formatting::format_date(coupon.date),
+                        formatting::format_date(tax_payment_date), amount);
+
+                    self.get_deposit_view(&coupon.symbol).transaction(tax_payment_date.into(), amount);
+                    self.transaction(tax_payment_date, amount);
+                    self.income_structure.interest_taxes += amount;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Amortization returns a part of the bond face value, so it's accounted as a partial return of the investments
+    fn process_amortizations(&mut self, statement: &BrokerStatement) -> EmptyResult {
+        for action in &statement.corporate_actions {
+            let CorporateActionType::Amortization {amount, ref currency} = action.action else {
+                continue;
+            };
+
+            let amount = self.converter.convert_to(action.time.date, Cash::new(currency, amount), self.currency)?;
+            self.get_deposit_view(&action.symbol).transaction(action.time, -amount);
+            self.holding_change(&action.symbol, action.time, dec!(0), -amount);
+        }
+
+        Ok(())
+    }
+
     fn process_interest(&mut self, statement: &BrokerStatement, portfolio: &PortfolioConfig) -> EmptyResult {
-        for interest in &statement.idle_cash_interest {
+        let other_income: Vec<_> = statement.securities_lending_income().chain(statement.repo_income()).collect();
+
+        for interest in statement.idle_cash_interest.iter().chain(&other_income) {
             self.income_structure.interest += self.converter.convert_to(
                 interest.date, interest.amount, self.currency)?;
 
@@ -436,9 +522,35 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
         Ok(())
     }
 
-    fn process_grants(&mut self, statement: &BrokerStatement) -> EmptyResult {
+    fn process_margin_interest(&mut self, statement: &BrokerStatement) -> EmptyResult {
+        for interest in &statement.margin_interest {
+            self.income_structure.margin_interest += self.converter.convert_to(
+                interest.date, interest.amount, self.currency)?;
+        }
+
+        Ok(())
+    }
+
+    fn process_grants(&mut self, statement: &BrokerStatement, portfolio: &PortfolioConfig) -> EmptyResult {
+        let mut grants_tax_calculator = CashGrantsTaxCalculator::new(self.country, self.converter);
+
         for grant in &statement.cash_grants {
             self.income_structure.grants += self.converter.convert_to(grant.date, grant.amount, self.currency)?;
+
+            // Only Russian brokers are tax agents for the grants (see process_grants() of broker statement)
+            if self.method.tax_aware() && statement.broker.type_.jurisdiction() == Jurisdiction::Russia {
+                let (_, tax_to_pay) = grants_tax_calculator.tax(grant, &mut self.tax_calculator)?;
+                let (_, tax_payment_date) = portfolio.tax_payment_day().get(grant.date, false);
+
+                if let Some(amount) = self.map_tax_to_deposit_amount(tax_payment_date, tax_to_pay)? {
+                    trace!("* {} cash grant {} tax: {}",
+                        formatting::format_date(grant.date),
+                        formatting::format_date(tax_payment_date), amount);
+
+                    self.transaction(tax_payment_date, amount);
+                    self.income_structure.grant_taxes += amount;
+                }
+            }
         }
 
         Ok(())
@@ -446,13 +558,51 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
 
     fn process_fees(&mut self, statement: &BrokerStatement) -> EmptyResult {
         for fee in &statement.fees {
-            self.income_structure.commissions += self.converter.convert_to(
-                fee.date, fee.amount.withholding(), self.currency)?;
+            let amount = self.converter.convert_to(fee.date, fee.amount.withholding(), self.currency)?;
+
+            if self.attribute_fees && self.attribute_fee(fee.date, amount) {
+                self.income_structure.holding_costs += amount;
+            } else {
+                self.income_structure.commissions += amount;
+            }
         }
 
         Ok(())
     }
 
+    // Attributes the fee to the instruments which are held at the fee date. We don't have historical quotes, so
+    // instrument value is approximated by the net amount invested into it. Returns false if there are no open
+    // positions to attribute the fee to.
+    fn attribute_fee(&mut self, date: Date, amount: Decimal) -> bool {
+        let mut holdings: BTreeMap<&str, (Decimal, Decimal)> = BTreeMap::new();
+
+        for change in &self.holding_changes {
+            if change.date <= date {
+                let holding = holdings.entry(&change.symbol).or_default();
+                holding.0 += change.quantity;
+                holding.1 += change.investments;
+            }
+        }
+
+        let weights: Vec<(String, Decimal)> = holdings.into_iter()
+            .filter(|&(_, (quantity, investments))| quantity > dec!(0) && investments > dec!(0))
+            .map(|(symbol, (_, investments))| (symbol.to_owned(), investments))
+            .collect();
+
+        let total: Decimal = weights.iter().map(|(_, weight)| weight).sum();
+        if total.is_zero() {
+            return false;
+        }
+
+        for (symbol, weight) in weights {
+            let cost = amount * weight / total;
+            trace!("* {} {} holding cost: {}", symbol, formatting::format_date(date), cost.normalize());
+            self.get_deposit_view(&symbol).transaction(date.into(), cost);
+        }
+
+        true
+    }
+
     fn process_tax_agent_withholdings(&mut self, statement: &BrokerStatement) -> EmptyResult {
         for tax in &statement.tax_agent_withholdings {
             let amount = self.converter.convert_to(tax.date, tax.amount.withholding(), self.currency)?;
@@ -463,12 +613,29 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
         Ok(())
     }
 
-    fn process_tax_deductions(&mut self, portfolio: &PortfolioConfig) -> EmptyResult {
+    fn process_tax_deductions(&mut self, statement: &BrokerStatement, portfolio: &PortfolioConfig) -> EmptyResult {
         if !self.method.tax_aware() {
             return Ok(());
         }
 
-        for &(date, amount) in &portfolio.tax_deductions {
+        let mut deductions = portfolio.tax_deductions.clone();
+
+        // Not received yet deductions are accounted as projected ones
+        if portfolio.iis_deduction {
+            let today = time::today();
+
+            let iis_deductions = IisDeduction::calculate(
+                &statement.deposits_and_withdrawals, &portfolio.iis_paid_taxes, false)?;
+
+            for deduction in iis_deductions {
+                let date = deduction.date();
+                if date <= today {
+                    deductions.push((date, deduction.deduction));
+                }
+            }
+        }
+
+        for (date, amount) in deductions {
             let amount = self.converter.convert(self.country.currency, self.currency, date, amount)?;
             trace!("* Tax deduction {}: {}", formatting::format_date(date), amount);
             self.transaction(date, -amount);
@@ -491,6 +658,13 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
             .or_insert_with(|| InstrumentDepositView::new(mapped_symbol))
     }
 
+    fn holding_change(&mut self, symbol: &str, time: DateOptTime, quantity: Decimal, investments: Decimal) {
+        if self.attribute_fees {
+            let symbol = self.performance_merging_config.as_ref().unwrap().map(symbol).to_owned();
+            self.holding_changes.push(HoldingChange {date: time.date, symbol, quantity, investments});
+        }
+    }
+
     fn transaction(&mut self, date: Date, amount: Decimal) {
         self.transactions.push(Transaction::new(date, amount));
     }
@@ -531,6 +705,13 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
     }
 }
 
+struct HoldingChange {
+    date: Date,
+    symbol: String,
+    quantity: Decimal,
+    investments: Decimal,
+}
+
 fn get_total_activity_duration(periods: &[InterestPeriod]) -> u32 {
     periods.iter().map(InterestPeriod::days).sum()
 }
\ No newline at end of file
@@ -6,6 +6,8 @@ use crate::formatting::table::{Cell, Style};
 use crate::types::Decimal;
 use crate::util;
 
+use super::deposit_emulator::Transaction;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[derive(strum::Display, strum::EnumIter, strum::EnumMessage, strum::EnumString, strum::IntoStaticStr)]
 #[strum(serialize_all = "kebab-case")]
@@ -30,6 +32,7 @@ impl PerformanceAnalysisMethod {
 
 pub struct PortfolioPerformanceAnalysis {
     pub income_structure: IncomeStructure,
+    pub contributions: Vec<Transaction>,
     pub instruments: BTreeMap<String, InstrumentPerformanceAnalysis>,
     pub portfolio: InstrumentPerformanceAnalysis,
 }
@@ -53,27 +56,35 @@ pub struct IncomeStructure {
 
     pub dividends: Decimal,
     pub interest: Decimal,
+    pub grants: Decimal,
 
     pub trading_taxes: Decimal,
     pub dividend_taxes: Decimal,
     pub interest_taxes: Decimal,
+    pub grant_taxes: Decimal,
 
     pub trading_tax_deductions: Decimal,
     pub additional_tax_deductions: Decimal,
 
     pub commissions: Decimal,
-
-    // For now process it as a part of trading income since its amount is too small to make a distinct category for it
-    pub grants: Decimal,
+    pub margin_interest: Decimal,
+    // Recurring broker fees attributed to the held instruments (when fees attribution is enabled)
+    pub holding_costs: Decimal,
 }
 
 impl IncomeStructure {
     pub fn profit(&self) -> Decimal {
-        self.net_profit + self.taxes() + self.commissions
+        self.net_profit + self.taxes() + self.commissions + self.margin_interest + self.holding_costs
     }
 
     pub fn net_trading_income(&self) -> Decimal {
-        self.net_profit - self.net_dividend_income() - self.net_interest_income() - self.tax_deductions()
+        self.net_profit - self.net_dividend_income() - self.net_interest_income() - self.net_other_income()
+            - self.tax_deductions()
+    }
+
+    // Trading income before taxes and costs - the part of the result which is driven only by price changes
+    pub fn gross_trading_income(&self) -> Decimal {
+        self.net_trading_income() + self.trading_taxes + self.commissions + self.margin_interest + self.holding_costs
     }
 
     pub fn net_dividend_income(&self) -> Decimal {
@@ -84,8 +95,13 @@ impl IncomeStructure {
         self.interest - self.interest_taxes
     }
 
+    // Cash grants and bonuses from brokers
+    pub fn net_other_income(&self) -> Decimal {
+        self.grants - self.grant_taxes
+    }
+
     pub fn taxes(&self) -> Decimal {
-        self.trading_taxes + self.dividend_taxes + self.interest_taxes
+        self.trading_taxes + self.dividend_taxes + self.interest_taxes + self.grant_taxes
     }
 
     pub fn tax_deductions(&self) -> Decimal {
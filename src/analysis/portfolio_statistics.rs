@@ -9,18 +9,55 @@ use crate::localities::Country;
 use crate::taxes::{LtoDeduction, NetLtoDeduction, TaxCalculator};
 use crate::types::Decimal;
 
+use super::funding::{self, PortfolioFunding};
+use super::leverage::{self, LeverageAnalysis};
 use super::portfolio_performance_types::{PerformanceAnalysisMethod, PortfolioPerformanceAnalysis};
+use super::receivables::{self, AtRiskReceivable};
 
 pub struct PortfolioStatistics {
     country: Country,
     pub currencies: Vec<PortfolioCurrencyStatistics>,
     pub asset_groups: BTreeMap<String, AssetGroup>,
     pub lto: Option<LtoStatistics>,
+    pub leverage: Vec<LeverageAnalysis>,
+    pub funding: Vec<PortfolioFunding>,
+    pub at_risk_receivables: Vec<AtRiskReceivable>,
 }
 
 pub struct AssetGroup {
     pub taxes: TaxCalculator,
     pub net_value: Vec<Cash>,
+
+    // Net value of all portfolios the group is scoped to
+    pub total_net_value: Vec<Cash>,
+    pub min_share: Option<Decimal>,
+    pub max_share: Option<Decimal>,
+}
+
+impl AssetGroup {
+    pub fn share(&self) -> Option<Decimal> {
+        let (net_value, total_net_value) = (self.net_value.first()?, self.total_net_value.first()?);
+        if !total_net_value.is_positive() {
+            return None;
+        }
+        Some(net_value.amount / total_net_value.amount)
+    }
+
+    pub fn has_constraints(&self) -> bool {
+        self.min_share.is_some() || self.max_share.is_some()
+    }
+
+    /// Returns group share if it's out of the configured bounds
+    pub fn breach(&self) -> Option<Decimal> {
+        let share = self.share()?;
+
+        if self.min_share.is_some_and(|min_share| share < min_share) ||
+            self.max_share.is_some_and(|max_share| share > max_share) {
+            return Some(share);
+        }
+
+        None
+    }
 }
 
 pub struct LtoStatistics {
@@ -50,6 +87,23 @@ impl PortfolioStatistics {
             )).collect(),
             asset_groups: BTreeMap::new(),
             lto: None,
+            leverage: Vec::new(),
+            funding: Vec::new(),
+            at_risk_receivables: Vec::new(),
+        }
+    }
+
+    pub fn check_asset_groups(&self) {
+        for (name, group) in &self.asset_groups {
+            let Some(share) = group.breach() else {
+                continue;
+            };
+
+            let bound = |share: Option<Decimal>| share.map(|share| format!("{}%", (share * dec!(100)).normalize()))
+                .unwrap_or_else(|| s!("-"));
+
+            warn!("{:?} asset group share ({}%) is out of the configured bounds: [{}, {}].",
+                  name, (share * dec!(100)).round_dp(1).normalize(), bound(group.min_share), bound(group.max_share));
         }
     }
 
@@ -78,6 +132,18 @@ impl PortfolioStatistics {
         if method.tax_aware() && !lto.projected.deduction.is_zero() {
             lto.projected.print("Projected LTO deduction")
         }
+
+        if !self.funding.is_empty() {
+            funding::print(&self.funding);
+        }
+
+        if !self.leverage.is_empty() {
+            leverage::print(&self.leverage);
+        }
+
+        if !self.at_risk_receivables.is_empty() {
+            receivables::print(&self.at_risk_receivables);
+        }
     }
 
     pub fn process<F>(&mut self, mut handler: F) -> EmptyResult
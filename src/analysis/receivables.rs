@@ -0,0 +1,64 @@
+use log::warn;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::BrokerStatement;
+use crate::currency::Cash;
+use crate::time::{self, Date};
+
+/// Declared dividend which is overdue for too long and thus may never be paid (for example, due to sanctions)
+pub struct AtRiskReceivable {
+    pub portfolio: String,
+    pub instrument: String,
+    pub ex_date: Date,
+    pub payment_date: Date,
+    pub amount: Cash,
+}
+
+pub fn analyse(portfolio: &str, statement: &BrokerStatement) -> Vec<AtRiskReceivable> {
+    statement.at_risk_dividends().map(|dividend| AtRiskReceivable {
+        portfolio: portfolio.to_owned(),
+        instrument: statement.instrument_info.get_name(&dividend.symbol),
+        ex_date: dividend.ex_date,
+        payment_date: dividend.payment_date,
+        amount: dividend.amount,
+    }).collect()
+}
+
+pub fn print(receivables: &[AtRiskReceivable]) {
+    warn!(concat!(
+        "Some declared dividends are overdue for too long and may never be paid. They are excluded from receivables ",
+        "and net asset value."));
+
+    let today = time::today();
+    let mut table = AtRiskReceivablesTable::new();
+
+    for receivable in receivables {
+        table.add_row(Row {
+            portfolio: receivable.portfolio.clone(),
+            instrument: receivable.instrument.clone(),
+            ex_date: receivable.ex_date,
+            payment_date: receivable.payment_date,
+            overdue_days: (today - receivable.payment_date).num_days() as i32,
+            amount: receivable.amount,
+        });
+    }
+
+    table.print("At-risk receivables");
+}
+
+#[derive(StaticTable)]
+#[table(name="AtRiskReceivablesTable")]
+struct Row {
+    #[column(name="Portfolio")]
+    portfolio: String,
+    #[column(name="Instrument")]
+    instrument: String,
+    #[column(name="Ex-dividend date")]
+    ex_date: Date,
+    #[column(name="Payment date")]
+    payment_date: Date,
+    #[column(name="Overdue days")]
+    overdue_days: i32,
+    #[column(name="Amount")]
+    amount: Cash,
+}
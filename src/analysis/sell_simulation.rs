@@ -3,10 +3,11 @@ use std::collections::BTreeMap;
 use itertools::Itertools;
 use static_table_derive::StaticTable;
 
-use crate::broker_statement::{BrokerStatement, StockSell, StockSellType};
+use crate::broker_statement::{BrokerStatement, SellLot, StockSell, StockSellType};
+use crate::brokers::BrokerInfo;
 use crate::commissions::CommissionCalc;
 use crate::config::PortfolioConfig;
-use crate::core::EmptyResult;
+use crate::core::{EmptyResult, GenericResult};
 use crate::currency::{Cash, MultiCurrencyCashAccount};
 use crate::currency::converter::{CurrencyConverter, CurrencyConverterRc};
 use crate::formatting::table::Cell;
@@ -19,20 +20,36 @@ use crate::types::{Date, Decimal};
 use crate::util;
 
 pub fn simulate_sell(
-    country: &Country, portfolio: &PortfolioConfig, mut statement: BrokerStatement,
-    converter: CurrencyConverterRc, quotes: &Quotes,
-    positions: Option<Vec<(String, Option<Decimal>)>>, base_currency: Option<&str>,
+    country: &Country, portfolio: &PortfolioConfig, statement: BrokerStatement,
+    fifo_statement: Option<BrokerStatement>, converter: CurrencyConverterRc, quotes: &Quotes,
+    positions: Option<Vec<(String, Option<Decimal>)>>, lots: Vec<SellLot>, base_currency: Option<&str>,
 ) -> EmptyResult {
     let (positions, all_positions) = match positions {
         Some(positions) => (positions, false),
+        // Sell exactly the specified lots
+        None if !lots.is_empty() => {
+            let mut positions: Vec<(String, Option<Decimal>)> = Vec::new();
+
+            for lot in &lots {
+                if let Some((_, Some(quantity))) = positions.iter_mut().find(|(symbol, _)| *symbol == lot.symbol) {
+                    *quantity += lot.quantity;
+                } else {
+                    positions.push((lot.symbol.clone(), Some(lot.quantity)));
+                }
+            }
+
+            (positions, false)
+        },
         None => {
+            // Pledged positions can't be sold, so simulate selling of their free part only
             let positions: Vec<_> = statement.open_positions.keys()
+                .filter(|symbol| !statement.free_quantity(symbol).is_zero())
                 .map(|symbol| (symbol.to_owned(), None))
                 .sorted_unstable()
                 .collect();
 
             if positions.is_empty() {
-                println!("The portfolio has no open positions.");
+                println!("The portfolio has no open positions available for selling.");
                 return Ok(())
             }
 
@@ -40,6 +57,12 @@ pub fn simulate_sell(
         }
     };
 
+    for lot in &lots {
+        if !positions.iter().any(|(symbol, _)| *symbol == lot.symbol) {
+            return Err!("{} lot is specified, but the position isn't going to be sold", lot.symbol);
+        }
+    }
+
     for (symbol, _quantity) in &positions {
         if !all_positions {
             if !statement.open_positions.contains_key(symbol) {
@@ -49,27 +72,52 @@ pub fn simulate_sell(
         quotes.batch(statement.get_quote_query(symbol))?;
     }
 
+    let broker = statement.broker.clone();
+    let results = simulate(
+        country, portfolio, statement, &converter, quotes, &positions, all_positions, &lots, base_currency)?;
+    results.print();
+
+    // Show how the tax outcome differs from the default FIFO lots matching
+    if let Some(fifo_statement) = fifo_statement.filter(|_| !lots.is_empty()) {
+        let fifo = simulate(
+            country, portfolio, fifo_statement, &converter, quotes, &positions, all_positions, &[], base_currency)?;
+        print_lots_comparison(&results, &fifo);
+    }
+
+    print_conversion(portfolio.currency(), &broker, results.proceeds, &converter)
+}
+
+fn simulate(
+    country: &Country, portfolio: &PortfolioConfig, mut statement: BrokerStatement, converter: &CurrencyConverterRc,
+    quotes: &Quotes, positions: &[(String, Option<Decimal>)], all_positions: bool, lots: &[SellLot],
+    base_currency: Option<&str>,
+) -> GenericResult<SellSimulation> {
     let net_value = statement.net_value(
-        &converter, quotes, portfolio.currency(),
+        converter, quotes, portfolio.currency(),
         all_positions // To be able to simulate sell for portfolio with symbols for which quotes aren't available
     )?;
 
     let mut commission_calc = CommissionCalc::new(
         converter.clone(), statement.broker.commission_spec.clone(), net_value)?;
 
-    for (symbol, quantity) in &positions {
-        let quantity = *match quantity {
-            Some(quantity) => quantity,
-            None => statement.open_positions.get(symbol).ok_or_else(|| format!(
-                "The portfolio has no open {:?} positions", symbol))?,
-        };
+    for (symbol, quantity) in positions {
+        let free_quantity = statement.free_quantity(symbol);
+        let pledged_quantity = statement.pledged_quantity(symbol);
+        let quantity = quantity.unwrap_or(free_quantity);
+
+        if !pledged_quantity.is_zero() && (quantity.is_zero() || quantity > free_quantity) {
+            return Err!(
+                "Unable to sell {}: only {} shares are available for selling ({} are pledged as collateral)",
+                symbol, free_quantity, pledged_quantity);
+        }
 
         let mut price = quotes.get(statement.get_quote_query(symbol))?;
         if let Some(base_currency) = base_currency {
-            price = trades::convert_price(price, quantity, base_currency, &converter)?;
+            price = trades::convert_price(price, quantity, base_currency, converter)?;
         }
 
-        statement.emulate_sell(symbol, quantity, price, &mut commission_calc)?;
+        let symbol_lots = lots.iter().filter(|lot| lot.symbol == *symbol).cloned().collect();
+        statement.emulate_sell_lots(symbol, quantity, price, symbol_lots, &mut commission_calc)?;
     }
 
     statement.process_trades(None)?;
@@ -80,7 +128,8 @@ pub fn simulate_sell(
         .cloned().collect::<Vec<_>>();
     assert_eq!(stock_sells.len(), positions.len());
 
-    print_results(country, portfolio, &statement.instrument_info, stock_sells, additional_commissions, &converter)
+    calculate_results(
+        country, portfolio, &statement.instrument_info, stock_sells, additional_commissions, converter)
 }
 
 struct TaxYearTotals {
@@ -99,11 +148,35 @@ impl TaxYearTotals {
     }
 }
 
-fn print_results(
+struct SellSimulation {
+    trades_table: TradesTable,
+    fifo_table: FifoTable,
+    lto_deductions: BTreeMap<i32, LtoDeduction>,
+    proceeds: MultiCurrencyCashAccount,
+    taxable_local_profit: Cash,
+    tax_to_pay: Cash,
+}
+
+impl SellSimulation {
+    fn print(&self) {
+        self.trades_table.print("Sell simulation results");
+        self.fifo_table.print("FIFO details");
+
+        for (tax_year, lto) in &self.lto_deductions {
+            let mut title = s!("Long term ownership deduction");
+            if self.lto_deductions.len() > 1 {
+                title = format!("{} ({})", title, tax_year)
+            }
+            lto.print(&title);
+        }
+    }
+}
+
+fn calculate_results(
     country: &Country, portfolio: &PortfolioConfig, instrument_info: &InstrumentInfo,
     stock_sells: Vec<StockSell>, additional_commissions: MultiCurrencyCashAccount,
     converter: &CurrencyConverter,
-) -> EmptyResult {
+) -> GenericResult<SellSimulation> {
     let mut trades_table = TradesTable::new();
     let mut fifo_table = FifoTable::new();
 
@@ -251,6 +324,11 @@ fn print_results(
         converter.real_time_date(), total_purchase_cost, total_purchase_local_cost,
         total_profit.clone(), total_local_profit, total_tax_to_pay, converter)?;
 
+    let mut proceeds = total_revenue.clone();
+    for commission in total_commission.iter() {
+        proceeds.withdraw(commission);
+    }
+
     let mut totals = trades_table.add_empty_row();
     totals.set_commission(total_commission);
     totals.set_revenue(total_revenue);
@@ -283,15 +361,72 @@ fn print_results(
         fifo_table.hide_long_term_ownership();
     }
 
-    trades_table.print("Sell simulation results");
-    fifo_table.print("FIFO details");
+    Ok(SellSimulation {
+        trades_table, fifo_table, lto_deductions, proceeds,
+        taxable_local_profit: total_taxable_local_profit,
+        tax_to_pay: total_tax_to_pay,
+    })
+}
 
-    for (tax_year, lto) in &lto_deductions {
-        let mut title = s!("Long term ownership deduction");
-        if lto_deductions.len() > 1 {
-            title = format!("{} ({})", title, tax_year)
-        }
-        lto.print(&title);
+fn print_lots_comparison(lots: &SellSimulation, fifo: &SellSimulation) {
+    let mut table = LotsComparisonTable::new();
+
+    for (method, results) in [("FIFO", fifo), ("Specified lots", lots)] {
+        table.add_row(LotsComparisonRow {
+            method: method.to_owned(),
+            taxable_local_profit: results.taxable_local_profit,
+            tax_to_pay: results.tax_to_pay,
+        });
+    }
+
+    let mut totals = table.add_empty_row();
+    totals.set_tax_to_pay(lots.tax_to_pay - fifo.tax_to_pay);
+
+    table.print("Lot selection comparison");
+}
+
+fn print_conversion(
+    base_currency: &str, broker: &BrokerInfo, proceeds: MultiCurrencyCashAccount, converter: &CurrencyConverter,
+) -> EmptyResult {
+    let mut table = ConversionTable::new();
+    let mut has_commission = false;
+
+    let mut proceeds: Vec<_> = proceeds.iter()
+        .filter(|amount| amount.currency != base_currency && amount.is_positive())
+        .collect();
+    proceeds.sort_by_key(|amount| amount.currency);
+
+    for amount in proceeds {
+        let rate = converter.real_time_currency_rate(amount.currency, base_currency)?;
+        let converted = converter.convert_to_cash(converter.real_time_date(), amount, base_currency)?.round();
+
+        let commission = broker.get_forex_commission(converted);
+        has_commission |= commission.is_some();
+
+        table.add_row(ConversionRow {
+            proceeds: amount,
+            rate: util::round(rate, 4),
+            converted,
+            commission,
+            net: converted - commission.unwrap_or_else(|| Cash::zero(converted.currency)),
+        });
+    }
+
+    if table.is_empty() {
+        return Ok(());
+    }
+
+    if !has_commission {
+        table.hide_commission();
+        table.hide_net();
+    }
+
+    table.print(&format!("Proceeds conversion to {}", base_currency));
+
+    if !has_commission {
+        println!(
+            "Specify forex commission in {} broker configuration to estimate the conversion costs.",
+            broker.brief_name);
     }
 
     Ok(())
@@ -347,4 +482,30 @@ struct FifoRow {
     long_term_ownership: bool,
     #[column(name="Tax free", align="center")]
     tax_free: bool,
-}
\ No newline at end of file
+}
+
+#[derive(StaticTable)]
+#[table(name="LotsComparisonTable")]
+struct LotsComparisonRow {
+    #[column(name="Method")]
+    method: String,
+    #[column(name="Taxable profit")]
+    taxable_local_profit: Cash,
+    #[column(name="Tax to pay")]
+    tax_to_pay: Cash,
+}
+
+#[derive(StaticTable)]
+#[table(name="ConversionTable")]
+struct ConversionRow {
+    #[column(name="Proceeds")]
+    proceeds: Cash,
+    #[column(name="Rate")]
+    rate: Decimal,
+    #[column(name="Converted")]
+    converted: Cash,
+    #[column(name="Forex commission")]
+    commission: Option<Cash>,
+    #[column(name="Net amount")]
+    net: Cash,
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use log::warn;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::BrokerStatement;
+use crate::config::{AssetAllocationConfig, Config, PortfolioConfig};
+use crate::core::GenericResult;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting::table::Cell;
+use crate::quotes::Quotes;
+use crate::types::Decimal;
+use crate::util;
+
+use super::config::StressScenarioConfig;
+
+// Approximate peak-to-trough changes observed during the crises
+const BUILTIN_SCENARIOS: &[(&str, &[(&str, Decimal)], &[(&str, Decimal)])] = &[
+    ("2008", &[
+        ("Stocks", dec!(-0.50)),
+        ("Bonds", dec!(0.05)),
+        ("Gold", dec!(0.05)),
+    ], &[
+        ("USD", dec!(0.50)),
+        ("RUB", dec!(-0.33)),
+    ]),
+    ("2020", &[
+        ("Stocks", dec!(-0.34)),
+        ("Bonds", dec!(-0.05)),
+        ("Gold", dec!(-0.03)),
+    ], &[
+        ("USD", dec!(0.25)),
+        ("RUB", dec!(-0.20)),
+    ]),
+    ("2022-RU", &[
+        ("Stocks", dec!(-0.45)),
+        ("Bonds", dec!(-0.15)),
+    ], &[
+        ("USD", dec!(0.60)),
+        ("RUB", dec!(-0.38)),
+    ]),
+];
+
+pub fn get_scenario(config: &Config, name: &str) -> GenericResult<StressScenarioConfig> {
+    if let Some(scenario) = config.stress_scenarios.get(name) {
+        return Ok(scenario.clone());
+    }
+
+    let (_, assets, currencies) = BUILTIN_SCENARIOS.iter()
+        .find(|(scenario, ..)| scenario.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!(
+            "Unknown stress testing scenario: {:?}. Available scenarios: {}", name,
+            BUILTIN_SCENARIOS.iter().map(|(name, ..)| *name)
+                .chain(config.stress_scenarios.keys().map(String::as_str).sorted())
+                .join(", ")))?;
+
+    let convert = |shocks: &[(&str, Decimal)]| -> HashMap<String, Decimal> {
+        shocks.iter().map(|&(name, shock)| (name.to_owned(), shock)).collect()
+    };
+
+    Ok(StressScenarioConfig {
+        assets: convert(assets),
+        currencies: convert(currencies),
+    })
+}
+
+pub struct StressTestResult {
+    currency: String,
+    assets: Vec<StressedAsset>,
+    unmatched_assets: Vec<String>, // Assets which the scenario doesn't specify shock for
+
+    net_value: Decimal,
+    stressed_net_value: Decimal,
+    net_investments: Decimal,
+}
+
+struct StressedAsset {
+    name: String,
+    currency: String, // Currency exposure
+    value: Decimal,
+    shock: Decimal,
+    stressed_value: Decimal,
+    target_weight: Option<Decimal>,
+    rebalancing: Option<Decimal>,
+}
+
+pub fn stress_test(
+    portfolio: &PortfolioConfig, statement: &BrokerStatement, scenario: &StressScenarioConfig,
+    converter: &CurrencyConverter, quotes: &Quotes,
+) -> GenericResult<StressTestResult> {
+    let currency = portfolio.currency();
+    let target_weights = portfolio.get_target_weights();
+
+    let currency_shock = |asset_currency: &str| -> Decimal {
+        if asset_currency == currency {
+            return dec!(0);
+        }
+        scenario.currencies.get(asset_currency).cloned().unwrap_or_default()
+    };
+
+    let mut assets = Vec::new();
+    let mut unmatched_assets = Vec::new();
+    statement.batch_quotes(quotes)?;
+
+    for (symbol, &quantity) in statement.open_positions.iter().sorted_unstable_by_key(|(symbol, _)| *symbol) {
+        let price = quotes.get(statement.get_quote_query(symbol))?;
+        let value = converter.real_time_convert_to(price * quantity, currency)?;
+
+        let exposure = portfolio.get_currency_exposure(symbol, price.currency);
+        let asset_shock = get_asset_shock(&portfolio.assets, symbol, scenario).unwrap_or_else(|| {
+            warn!("The stress testing scenario doesn't specify shock for {}. Assuming it's not affected.", symbol);
+            unmatched_assets.push(symbol.clone());
+            dec!(0)
+        });
+        let shock = (dec!(1) + asset_shock) * (dec!(1) + currency_shock(exposure)) - dec!(1);
+
+        assets.push(StressedAsset {
+            name: symbol.clone(),
+            currency: exposure.to_owned(),
+            value, shock,
+            stressed_value: value * (dec!(1) + shock),
+            target_weight: target_weights.get(symbol).cloned(),
+            rebalancing: None,
+        });
+    }
+
+    for cash in statement.assets.cash.iter().sorted_unstable_by_key(|cash| cash.currency) {
+        let value = converter.real_time_convert_to(cash, currency)?;
+        let shock = currency_shock(cash.currency);
+
+        assets.push(StressedAsset {
+            name: format!("{} cash", cash.currency),
+            currency: cash.currency.to_owned(),
+            value, shock,
+            stressed_value: value * (dec!(1) + shock),
+            target_weight: None,
+            rebalancing: None,
+        });
+    }
+
+    let net_value = assets.iter().map(|asset| asset.value).sum();
+    let stressed_net_value: Decimal = assets.iter().map(|asset| asset.stressed_value).sum();
+
+    // Rebalancing is calculated for the stressed portfolio the same way as regular rebalancing does it: all free cash
+    // assets except the configured minimum are distributed according to target weights.
+    let investable_value = std::cmp::max(
+        dec!(0), stressed_net_value - portfolio.min_cash_assets.unwrap_or_default());
+
+    for asset in &mut assets {
+        if let Some(weight) = asset.target_weight {
+            asset.rebalancing = Some(investable_value * weight - asset.stressed_value);
+        }
+    }
+
+    let mut net_investments = dec!(0);
+    for assets in &statement.deposits_and_withdrawals {
+        net_investments += converter.convert_to(assets.date, assets.cash, currency)?;
+    }
+
+    Ok(StressTestResult {
+        currency: currency.to_owned(),
+        assets, unmatched_assets, net_value, stressed_net_value, net_investments,
+    })
+}
+
+// Instrument's own shock has the highest priority, then the nearest asset allocation group's one
+fn get_asset_shock(assets: &[AssetAllocationConfig], symbol: &str, scenario: &StressScenarioConfig) -> Option<Decimal> {
+    let find = |name: &str| scenario.assets.iter()
+        .find(|(asset, _)| asset.eq_ignore_ascii_case(name))
+        .map(|(_, &shock)| shock);
+
+    if let Some(shock) = find(symbol) {
+        return Some(shock);
+    }
+
+    for asset in assets {
+        if asset.symbol.as_deref() == Some(symbol) {
+            return find(&asset.name);
+        }
+
+        if let Some(ref children) = asset.assets {
+            if children_contain(children, symbol) {
+                return get_asset_shock(children, symbol, scenario).or_else(|| find(&asset.name));
+            }
+        }
+    }
+
+    None
+}
+
+fn children_contain(assets: &[AssetAllocationConfig], symbol: &str) -> bool {
+    assets.iter().any(|asset| {
+        asset.symbol.as_deref() == Some(symbol) ||
+            asset.assets.as_ref().map(|assets| children_contain(assets, symbol)).unwrap_or_default()
+    })
+}
+
+#[derive(StaticTable)]
+#[table(name="StressTestTable")]
+struct AssetRow {
+    #[column(name="Asset")]
+    name: String,
+    #[column(name="Currency")]
+    currency: String,
+    #[column(name="Value")]
+    value: Cell,
+    #[column(name="Shock")]
+    shock: Cell,
+    #[column(name="Stressed value")]
+    stressed_value: Cell,
+    #[column(name="Target weight")]
+    target_weight: Option<Cell>,
+    #[column(name="Stressed weight")]
+    stressed_weight: Cell,
+    #[column(name="Rebalancing")]
+    rebalancing: Option<Cell>,
+}
+
+#[derive(StaticTable)]
+#[table(name="StressTestSummaryTable")]
+struct SummaryRow {
+    #[column(name="Net value")]
+    net_value: Cell,
+    #[column(name="Stressed net value")]
+    stressed_net_value: Cell,
+    #[column(name="Drawdown")]
+    drawdown: Cell,
+    #[column(name="Drawdown, %")]
+    drawdown_ratio: Option<Cell>,
+    #[column(name="Net investments")]
+    net_investments: Cell,
+    #[column(name="Margin of safety")]
+    margin_of_safety: Cell,
+}
+
+impl StressTestResult {
+    pub fn drawdown(&self) -> Decimal {
+        self.net_value - self.stressed_net_value
+    }
+
+    /// How much the stressed portfolio is still above the invested money
+    pub fn margin_of_safety(&self) -> Decimal {
+        self.stressed_net_value - self.net_investments
+    }
+
+    pub fn print(&self, scenario: &str) {
+        let round = |value| Cell::new_round_decimal(util::round(value, 0));
+        let mut table = StressTestTable::new();
+
+        for asset in &self.assets {
+            let stressed_weight = if self.stressed_net_value.is_zero() {
+                dec!(0)
+            } else {
+                asset.stressed_value / self.stressed_net_value
+            };
+
+            table.add_row(AssetRow {
+                name: asset.name.clone(),
+                currency: asset.currency.clone(),
+                value: round(asset.value),
+                shock: Cell::new_ratio(asset.shock),
+                stressed_value: round(asset.stressed_value),
+                target_weight: asset.target_weight.map(Cell::new_ratio),
+                stressed_weight: Cell::new_ratio(stressed_weight),
+                rebalancing: asset.rebalancing.map(round),
+            });
+        }
+
+        table.print(&format!("{:?} stress testing scenario ({})", scenario, self.currency));
+
+        if !self.unmatched_assets.is_empty() {
+            println!(
+                "The scenario doesn't specify shock for the following assets, so they are considered unaffected: {}.",
+                self.unmatched_assets.join(", "));
+        }
+
+        let mut summary = StressTestSummaryTable::new();
+        summary.add_row(SummaryRow {
+            net_value: round(self.net_value),
+            stressed_net_value: round(self.stressed_net_value),
+            drawdown: round(self.drawdown()),
+            drawdown_ratio: (!self.net_value.is_zero()).then(|| Cell::new_ratio(self.drawdown() / self.net_value)),
+            net_investments: round(self.net_investments),
+            margin_of_safety: round(self.margin_of_safety()),
+        });
+        summary.print("Stress testing summary");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_shocks() {
+        let asset = |name: &str, symbol: Option<&str>, assets| AssetAllocationConfig {
+            name: name.to_owned(),
+            symbol: symbol.map(ToOwned::to_owned),
+            exchange: None,
+            weight: dec!(0.5),
+            restrict_buying: None,
+            restrict_selling: None,
+            assets,
+        };
+
+        let assets = vec![
+            asset("Stocks", None, Some(vec![
+                asset("Russian stocks", None, Some(vec![
+                    asset("Sberbank", Some("SBER"), None),
+                    asset("Gazprom", Some("GAZP"), None),
+                ])),
+                asset("US stocks", Some("VTI"), None),
+            ])),
+            asset("Bonds", Some("BND"), None),
+        ];
+
+        let scenario = StressScenarioConfig {
+            assets: hashmap!{
+                s!("stocks") => dec!(-0.5),
+                s!("Russian stocks") => dec!(-0.6),
+                s!("GAZP") => dec!(-0.7),
+            },
+            currencies: HashMap::new(),
+        };
+
+        assert_eq!(get_asset_shock(&assets, "SBER", &scenario), Some(dec!(-0.6)));
+        assert_eq!(get_asset_shock(&assets, "GAZP", &scenario), Some(dec!(-0.7)));
+        assert_eq!(get_asset_shock(&assets, "VTI", &scenario), Some(dec!(-0.5)));
+        assert_eq!(get_asset_shock(&assets, "BND", &scenario), None);
+        assert_eq!(get_asset_shock(&assets, "UNKNOWN", &scenario), None);
+    }
+}
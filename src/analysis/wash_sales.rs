@@ -0,0 +1,68 @@
+use log::warn;
+
+use crate::broker_statement::{BrokerStatement, SellDetails, StockSell, StockSellType, StockSource};
+use crate::config::PortfolioConfig;
+use crate::core::EmptyResult;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting;
+use crate::localities::Country;
+use crate::time::{self, Date};
+
+// Repurchase of a position within this number of days after selling it at a loss may be considered as a sham deal
+const WASH_SALE_DAYS: i64 = 30;
+
+/// Returns the repurchase date if the stock sell is a potential wash sale. Only sells which taxes haven't been paid yet
+/// are checked, since it matters only for the projected taxes.
+pub fn find_repurchase(portfolio: &PortfolioConfig, statement: &BrokerStatement, trade: &StockSell) -> Option<Date> {
+    if trade.emulation || !matches!(trade.type_, StockSellType::Trade {..}) {
+        return None;
+    }
+
+    let (_, tax_payment_date) = portfolio.tax_payment_day().get(trade.execution_date, true);
+    if tax_payment_date < time::today() {
+        return None;
+    }
+
+    let sell_date = trade.conclusion_time.date;
+
+    statement.stock_buys.iter()
+        .filter(|buy| buy.symbol == trade.symbol && matches!(buy.type_, StockSource::Trade {..}))
+        .map(|buy| buy.conclusion_time.date)
+        .find(|&buy_date| buy_date > sell_date && (buy_date - sell_date).num_days() <= WASH_SALE_DAYS)
+}
+
+/// Checks whether the sell is a potential wash sale which loss may be challenged by tax authorities
+pub fn is_wash_sale(
+    portfolio: &PortfolioConfig, statement: &BrokerStatement, trade: &StockSell, details: &SellDetails,
+) -> bool {
+    details.taxable_local_profit.is_negative() && find_repurchase(portfolio, statement, trade).is_some()
+}
+
+/// Warns about the potential wash sales
+pub fn check(
+    country: &Country, portfolio: &PortfolioConfig, statement: &BrokerStatement, converter: &CurrencyConverter,
+) -> EmptyResult {
+    for trade in &statement.stock_sells {
+        let Some(repurchase_date) = find_repurchase(portfolio, statement, trade) else {
+            continue;
+        };
+
+        let instrument = statement.instrument_info.get_or_empty(&trade.symbol);
+        let details = trade.calculate(country, &instrument, &portfolio.tax_exemptions, converter)?;
+        if !details.taxable_local_profit.is_negative() {
+            continue;
+        }
+
+        warn!(concat!(
+            "{} position has been sold at a loss of {} on {} and repurchased on {}: ",
+            "the loss may be challenged by the tax authorities{}."),
+            trade.symbol, (-details.taxable_local_profit).round(), formatting::format_date(trade.conclusion_time),
+            formatting::format_date(repurchase_date), if portfolio.exclude_wash_sale_losses {
+                " (excluded from the projected tax deduction)"
+            } else {
+                ""
+            });
+    }
+
+    Ok(())
+}
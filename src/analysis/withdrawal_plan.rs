@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+
+use log::debug;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use static_table_derive::StaticTable;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::exchanges::Exchange;
+use crate::formatting::table::Cell;
+use crate::progress;
+use crate::quotes::{QuoteQuery, Quotes};
+use crate::types::Decimal;
+use crate::util;
+
+// Russian inflation of the 90s is too extreme to be used as a realistic scenario
+pub const INFLATION_HISTORY_SINCE: i32 = 2000;
+pub const RETURNS_HISTORY_SINCE: i32 = INFLATION_HISTORY_SINCE;
+const MIN_RETURNS_HISTORY_YEARS: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+pub enum WithdrawalStrategy {
+    /// Fixed yearly amount in today's money which is indexed by inflation
+    FixedAmount(Decimal),
+    /// Initial withdrawal rate: the first year amount is calculated as a share of the portfolio and then indexed by
+    /// inflation
+    Rate(Decimal),
+}
+
+pub struct WithdrawalPlanConfig {
+    pub strategy: WithdrawalStrategy,
+    pub years: u32,
+    pub simulations: u32,
+    // Random number generator seed: the same seed gives the same results for the same input data
+    pub seed: u64,
+}
+
+pub struct WithdrawalPlanner {
+    config: WithdrawalPlanConfig,
+    portfolio: Decimal,
+    expected_return: Decimal,
+    return_deviations: Vec<Decimal>,
+    inflation: Vec<Decimal>,
+    rng: StdRng,
+}
+
+pub struct WithdrawalPlan {
+    pub currency: String,
+    pub portfolio: Decimal,
+    pub withdrawal: Decimal,
+    pub years: Vec<WithdrawalPlanYear>,
+}
+
+pub struct WithdrawalPlanYear {
+    pub year: u32,
+    pub depletion_probability: Decimal,
+    pub median_real_value: Decimal,
+}
+
+impl WithdrawalPlanner {
+    /// Yearly returns are bootstrapped from the historical ones (in percents), but shifted to have the expected
+    /// average: history gives a realistic distribution of returns, while its average is a poor predictor of the
+    /// future one.
+    pub fn new(
+        config: WithdrawalPlanConfig, portfolio: Decimal, expected_return: Decimal, returns: Vec<Decimal>,
+        inflation: Vec<Decimal>,
+    ) -> GenericResult<WithdrawalPlanner> {
+        if returns.is_empty() {
+            return Err!("There is no historical returns data")
+        }
+
+        if inflation.is_empty() {
+            return Err!("There is no historical inflation data")
+        }
+
+        let average_return = returns.iter().sum::<Decimal>() / Decimal::from(returns.len());
+        let return_deviations = returns.into_iter().map(|value| value - average_return).collect();
+        let rng = StdRng::seed_from_u64(config.seed);
+
+        Ok(WithdrawalPlanner {
+            config, portfolio, expected_return, return_deviations, inflation, rng,
+        })
+    }
+
+    pub fn simulate(mut self, currency: &str) -> WithdrawalPlan {
+        let years = self.config.years as usize;
+        let simulations = self.config.simulations as usize;
+
+        let withdrawal = match self.config.strategy {
+            WithdrawalStrategy::FixedAmount(amount) => amount,
+            WithdrawalStrategy::Rate(rate) => self.portfolio * rate,
+        };
+
+        let mut depleted = vec![0_u32; years];
+        let mut real_values: Vec<Vec<Decimal>> = vec![Vec::with_capacity(simulations); years];
+
+        let mut progress = progress::start("Simulating withdrawals", simulations);
+
+        for _ in 0..simulations {
+            let mut value = self.portfolio;
+            let mut price_index = dec!(1);
+
+            for year in 0..years {
+                let nominal_withdrawal = withdrawal * price_index;
+
+                if value <= nominal_withdrawal {
+                    value = dec!(0);
+                } else {
+                    value -= nominal_withdrawal;
+                    value *= dec!(1) + self.sample_return();
+                    value = value.max(dec!(0));
+                }
+
+                price_index *= dec!(1) + self.sample_inflation();
+
+                if value.is_zero() {
+                    depleted[year] += 1;
+                }
+                real_values[year].push(value / price_index);
+            }
+
+            progress.inc();
+        }
+
+        let simulations = Decimal::from(simulations);
+
+        WithdrawalPlan {
+            currency: currency.to_owned(),
+            portfolio: self.portfolio,
+            withdrawal,
+            years: depleted.into_iter().zip(real_values).enumerate().map(|(year, (depleted, mut values))| {
+                values.sort();
+
+                WithdrawalPlanYear {
+                    year: year as u32 + 1,
+                    depletion_probability: Decimal::from(depleted) / simulations,
+                    median_real_value: values[values.len() / 2],
+                }
+            }).collect(),
+        }
+    }
+
+    fn sample_return(&mut self) -> Decimal {
+        let index = self.rng.gen_range(0..self.return_deviations.len());
+        (self.expected_return + self.return_deviations[index]) / dec!(100)
+    }
+
+    fn sample_inflation(&mut self) -> Decimal {
+        let index = self.rng.gen_range(0..self.inflation.len());
+        self.inflation[index] / dec!(100)
+    }
+}
+
+/// Calculates historical yearly returns (in percents) of the portfolio with the specified current assets (by their
+/// value) using year end prices. Assets without quotes (cash for example) are considered to have zero return.
+/// Instruments which have no price history for a year are excluded from it with their weight redistributed between
+/// the others.
+pub fn get_historical_returns(
+    assets: &BTreeMap<String, Decimal>, currency: &str, exchanges: &[Exchange], today_year: i32,
+    converter: &CurrencyConverter, quotes: &Quotes,
+) -> GenericResult<Vec<Decimal>> {
+    let query = |symbol: &str| QuoteQuery::Stock(symbol.to_owned(), exchanges.to_vec());
+    let year_end = |year: i32| date!(year, 12, 31);
+
+    let instruments: Vec<&str> = assets.keys()
+        .map(String::as_str)
+        .filter(|&symbol| symbol != "Cash" && symbol != "Receivables")
+        .collect();
+
+    quotes.prefetch_historical(instruments.iter().flat_map(|&symbol| {
+        (RETURNS_HISTORY_SINCE - 1..today_year).map(move |year| (query(symbol), year_end(year)))
+    }))?;
+
+    let mut prices: BTreeMap<(&str, i32), Decimal> = BTreeMap::new();
+    for &symbol in &instruments {
+        for year in RETURNS_HISTORY_SINCE - 1..today_year {
+            let date = year_end(year);
+            if let Some(quote) = quotes.get_historical(query(symbol), date)? {
+                prices.insert((symbol, year), converter.convert_to(date, quote.price, currency)?);
+            }
+        }
+    }
+
+    let mut returns = Vec::new();
+
+    for year in RETURNS_HISTORY_SINCE..today_year {
+        let mut priced = false;
+        let mut total_weight = dec!(0);
+        let mut total_return = dec!(0);
+
+        for (symbol, &weight) in assets {
+            let symbol = symbol.as_str();
+
+            let instrument_return = if instruments.contains(&symbol) {
+                match (prices.get(&(symbol, year - 1)), prices.get(&(symbol, year))) {
+                    (Some(&start), Some(&end)) if !start.is_zero() => {
+                        priced = true;
+                        end / start - dec!(1)
+                    },
+                    _ => continue,
+                }
+            } else {
+                dec!(0)
+            };
+
+            total_weight += weight;
+            total_return += weight * instrument_return;
+        }
+
+        if !priced || total_weight.is_zero() {
+            continue;
+        }
+
+        let value = total_return / total_weight * dec!(100);
+        debug!("Portfolio return in {}: {}%.", year, util::round(value, 2));
+        returns.push(value);
+    }
+
+    if returns.len() < MIN_RETURNS_HISTORY_YEARS {
+        return Err!(
+            "Unable to calculate historical returns of the portfolio: there is price history only for {} years",
+            returns.len());
+    }
+
+    Ok(returns)
+}
+
+#[derive(StaticTable)]
+#[table(name="WithdrawalPlanTable")]
+struct Row {
+    #[column(name="Year")]
+    year: u32,
+    #[column(name="Depletion probability", align="right")]
+    depletion_probability: String,
+    #[column(name="Median real value")]
+    median_real_value: Cell,
+}
+
+impl WithdrawalPlan {
+    pub fn print(&self) {
+        let mut table = WithdrawalPlanTable::new();
+
+        for year in &self.years {
+            if year.year % 5 != 0 && year.year as usize != self.years.len() {
+                continue;
+            }
+
+            table.add_row(Row {
+                year: year.year,
+                depletion_probability: format!("{}%", util::round(year.depletion_probability * dec!(100), 1)),
+                median_real_value: Cell::new_round_decimal(util::round(year.median_real_value, 0)),
+            });
+        }
+
+        table.print(&format!(
+            "Withdrawal of {} yearly from {} portfolio",
+            Cash::new(&self.currency, util::round(self.withdrawal, 0)),
+            Cash::new(&self.currency, util::round(self.portfolio, 0))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulate(
+        strategy: WithdrawalStrategy, expected_return: Decimal, returns: Vec<Decimal>, inflation: Vec<Decimal>,
+    ) -> WithdrawalPlan {
+        let config = WithdrawalPlanConfig {
+            strategy,
+            years: 30,
+            simulations: 1000,
+            seed: 0,
+        };
+        WithdrawalPlanner::new(config, dec!(1_000_000), expected_return, returns, inflation).unwrap().simulate("RUB")
+    }
+
+    #[test]
+    fn deterministic() {
+        // 10% withdrawal without any returns depletes the portfolio in exactly 10 years
+        let plan = simulate(WithdrawalStrategy::FixedAmount(dec!(100_000)), dec!(0), vec![dec!(15)], vec![dec!(0)]);
+        assert_eq!(plan.years[8].depletion_probability, dec!(0));
+        assert_eq!(plan.years[9].depletion_probability, dec!(1));
+        assert_eq!(plan.years[29].median_real_value, dec!(0));
+
+        // Returns which fully compensate inflation and withdrawals never deplete the portfolio
+        let plan = simulate(WithdrawalStrategy::Rate(dec!(0.04)), dec!(10), vec![dec!(-5)], vec![dec!(5)]);
+        assert_eq!(plan.withdrawal, dec!(40_000));
+        assert_eq!(plan.years[29].depletion_probability, dec!(0));
+    }
+
+    #[test]
+    fn stochastic() {
+        let returns = vec![dec!(-35), dec!(-10), dec!(5), dec!(15), dec!(25), dec!(40)];
+        let plan = simulate(WithdrawalStrategy::Rate(dec!(0.06)), dec!(7), returns, vec![dec!(2), dec!(4), dec!(8)]);
+
+        let probability = plan.years.last().unwrap().depletion_probability;
+        assert!(probability > dec!(0) && probability < dec!(1), "{}", probability);
+
+        for years in plan.years.windows(2) {
+            assert!(years[0].depletion_probability <= years[1].depletion_probability);
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use investments::analysis::PerformanceAnalysisMethod;
+use investments::analysis::{PerformanceAnalysisMethod, SellLot, WithdrawalPlanConfig};
+use investments::broker_costs::TradingProfile;
+use investments::cash_flow::ComparisonTolerance;
 use investments::time::Date;
 use investments::types::Decimal;
 
@@ -9,29 +11,47 @@ pub enum Action {
         name: Option<String>,
         method: PerformanceAnalysisMethod,
         show_closed_positions: bool,
+        attribute_fees: bool,
+        missed_gains: bool,
+        stress_scenario: Option<String>,
+    },
+    WithdrawalPlan {
+        name: String,
+        config: WithdrawalPlanConfig,
     },
     SimulateSell {
         name: String,
         positions: Option<Vec<(String, Option<Decimal>)>>,
+        lots: Vec<SellLot>,
         base_currency: Option<String>,
     },
+    PlanLto(String),
+    BrokerCosts(TradingProfile),
 
-    Sync(String),
+    Sync {
+        name: String,
+        dry_run: bool,
+    },
     Buy {
         name: String,
         positions: Vec<(String, Decimal)>,
         cash_assets: Decimal,
+        price: Option<Decimal>,
+        date: Option<Date>,
     },
     Sell {
         name: String,
         positions: Vec<(String, Option<Decimal>)>,
         cash_assets: Decimal,
+        price: Option<Decimal>,
+        date: Option<Date>,
     },
     SetCashAssets(String, Decimal),
 
     Show {
         name: String,
         flat: bool,
+        as_of: Option<Date>,
     },
     Rebalance {
         name: String,
@@ -42,18 +62,74 @@ pub enum Action {
         name: String,
         year: Option<i32>,
         tax_statement_path: Option<PathBuf>,
+        preview: bool,
+        diff: bool,
+        report_path: Option<PathBuf>,
+        audit_path: Option<PathBuf>,
+    },
+    RealizedPnl {
+        name: String,
+        year: Option<i32>,
+        export_path: Option<PathBuf>,
     },
     CashFlow {
         name: String,
         year: Option<i32>,
+        export_path: Option<PathBuf>,
+        tolerance: ComparisonTolerance,
     },
+    YearEnd {
+        name: String,
+        year: i32,
+    },
+    TaxRates {
+        name: Option<String>,
+        year: Option<i32>,
+    },
+    Forex {
+        name: String,
+        year: Option<i32>,
+    },
+    Timeline(String),
+    CorporateActionAdd(String),
+    MapInternalIds(String),
 
     Deposits {
         date: Date,
         cron_mode: bool,
+        monthly: bool,
     },
 
-    Metrics(PathBuf),
+    Metrics {
+        path: PathBuf,
+        incremental: bool,
+    },
+    Doctor {
+        check_dividends: bool,
+    },
+    ForexRates(Vec<String>),
+    CheckStatement {
+        broker: String,
+        path: PathBuf,
+    },
+    ExportStatement {
+        broker: String,
+        path: PathBuf,
+        output: Option<PathBuf>,
+    },
+    ImportStatement {
+        name: String,
+        path: PathBuf,
+    },
+    SampleStatement {
+        broker: String,
+        path: PathBuf,
+        output: PathBuf,
+        rows: usize,
+        seed: Option<u64>,
+    },
+    Init,
+    SetSecret(String),
     ShellCompletion {
         path: PathBuf,
         data: Vec<u8>,
@@ -6,23 +6,39 @@ mod positions;
 #[macro_use] extern crate maplit;
 
 use std::fs::File;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
 use std::process;
 use std::time::Duration;
 
-use log::error;
+use log::{Level, SetLoggerError, error};
 
 use investments::analysis;
+use investments::broker_costs;
 use investments::cash_flow;
 use investments::config::Config;
+use investments::corporate_action_editor;
 use investments::core::{EmptyResult, GenericResult};
 use investments::db;
 use investments::deposits;
+use investments::doctor;
+use investments::forex_rates;
+use investments::forex_trades;
+use investments::formatting;
+use investments::internal_ids_editor;
 use investments::metrics;
+use investments::network;
 use investments::portfolio;
+use investments::profiling;
+use investments::progress;
+use investments::secrets;
+use investments::setup;
+use investments::statement_check;
+use investments::statement_json;
+use investments::statement_sampler;
 use investments::tax_statement;
 use investments::telemetry::{Telemetry, TelemetryRecordBuilder};
+use investments::timeline;
 
 use self::action::Action;
 use self::parser::{Parser, GlobalOptions};
@@ -35,7 +51,7 @@ fn main() {
         process::exit(1);
     });
 
-    if let Err(e) = easy_logging::init(module_path!().split("::").next().unwrap(), global.log_level) {
+    if let Err(e) = init_logging(global.log_level) {
         let _ = writeln!(io::stderr(), "Failed to initialize the logging: {}.", e);
         process::exit(1);
     }
@@ -53,21 +69,46 @@ fn main() {
     }
 }
 
+fn init_logging(level: Level) -> Result<(), SetLoggerError> {
+    let module_name = module_path!().split("::").next().unwrap();
+    let (max_level, logger) = easy_logging::LoggingConfig::new(module_name, level).dispatch().into_log();
+
+    log::set_boxed_logger(progress::wrap_logger(logger))?;
+    log::set_max_level(max_level);
+
+    Ok(())
+}
+
 fn main_inner(global: GlobalOptions, parser: Parser) -> EmptyResult {
+    if global.profile_timings {
+        profiling::enable();
+    }
+
+    // Verbose logging would constantly interrupt the progress bar, so log messages are used instead in this case
+    if io::stderr().is_terminal() && global.log_level <= Level::Info {
+        progress::enable_interactive();
+    }
+
     let config_dir_path = Path::new(&global.config_dir);
     let config_path = config_dir_path.join("config.yaml");
 
-    let mut config = Config::load(config_path.to_str().unwrap()).map_err(|e| format!(
-        "Error while reading {:?} configuration file: {}", config_path, e))?;
+    // The configuration file may not exist yet or refer to the secrets, so these commands must work without it
+    if let Some(action) = parser.parse_standalone() {
+        return run_standalone(config_dir_path, action);
+    }
+
+    let mut config = Config::load(config_path.to_str().unwrap())?;
 
     config_dir_path.join("db.sqlite").to_str().unwrap()
         .clone_into(&mut config.db_path);
+    network::configure(config.proxy.clone(), config.retry)?;
+    formatting::configure(config.locale);
 
     let (command, action) = parser.parse(&mut config)?;
-    run(config, &command, action)
+    run(config, &config_path, &command, action)
 }
 
-fn run(config: Config, command: &str, action: Action) -> EmptyResult {
+fn run(config: Config, config_path: &Path, command: &str, action: Action) -> EmptyResult {
     let telemetry = (!config.telemetry.disable).then(|| -> GenericResult<Telemetry> {
         let connection = db::connect(&config.db_path)?;
         let user_id = config.telemetry.user_id.map(|user_id| user_id.to_string());
@@ -82,40 +123,78 @@ fn run(config: Config, command: &str, action: Action) -> EmptyResult {
     }).transpose()?;
 
     let record: TelemetryRecordBuilder = match action {
-        Action::Analyse {name, method, show_closed_positions} => {
-            let (statistics, _, telemetry) = analysis::analyse(
-                &config, name.as_deref(), show_closed_positions, &Default::default(), None, true)?;
-            statistics.print(method);
-            telemetry
+        Action::Analyse {name, method, show_closed_positions, attribute_fees, missed_gains, stress_scenario} => {
+            if let Some(scenario) = stress_scenario {
+                let name = name.as_deref().ok_or("Portfolio name must be specified for stress testing")?;
+                analysis::stress_test(&config, name, &scenario)?
+            } else if missed_gains {
+                let name = name.as_deref().ok_or("Portfolio name must be specified for missed gains analysis")?;
+                analysis::analyse_missed_gains(&config, name, method, show_closed_positions)?
+            } else {
+                let (statistics, _, telemetry) = analysis::analyse(
+                    &config, name.as_deref(), show_closed_positions, &Default::default(), None, attribute_fees, true)?;
+                statistics.print(method);
+                telemetry
+            }
         },
-        Action::SimulateSell {name, positions, base_currency} => analysis::simulate_sell(
-            &config, &name, positions, base_currency.as_deref())?,
-
-        Action::Sync(name) => portfolio::sync(&config, &name)?,
-        Action::Buy {name, positions, cash_assets} =>
-            portfolio::buy(&config, &name, &positions, cash_assets)?,
-        Action::Sell {name, positions, cash_assets} =>
-            portfolio::sell(&config, &name, &positions, cash_assets)?,
+        Action::WithdrawalPlan {name, config: plan_config} =>
+            analysis::plan_withdrawals(&config, &name, plan_config)?,
+        Action::SimulateSell {name, positions, lots, base_currency} => analysis::simulate_sell(
+            &config, &name, positions, lots, base_currency.as_deref())?,
+        Action::PlanLto(name) => analysis::plan_lto(&config, &name)?,
+        Action::BrokerCosts(profile) => broker_costs::compare(&config, &profile)?,
+
+        Action::Sync {name, dry_run} => portfolio::sync(&config, &name, dry_run)?,
+        Action::Buy {name, positions, cash_assets, price, date} =>
+            portfolio::buy(&config, &name, &positions, cash_assets, price, date)?,
+        Action::Sell {name, positions, cash_assets, price, date} =>
+            portfolio::sell(&config, &name, &positions, cash_assets, price, date)?,
         Action::SetCashAssets(name, cash_assets) =>
             portfolio::set_cash_assets(&config, &name, cash_assets)?,
 
-        Action::Show {name, flat} => portfolio::show(&config, &name, flat)?,
+        Action::Show {name, flat, as_of} => match as_of {
+            Some(date) => portfolio::show_as_of(&config, &name, date)?,
+            None => portfolio::show(&config, &name, flat)?,
+        },
         Action::Rebalance {name, flat} => portfolio::rebalance(&config, &name, flat)?,
 
-        Action::TaxStatement {name, year, tax_statement_path} =>
+        Action::TaxStatement {name, year, tax_statement_path, preview, diff, report_path, audit_path} =>
             tax_statement::generate_tax_statement(
-                &config, &name, year, tax_statement_path.as_deref())?,
-        Action::CashFlow {name, year} =>
-            cash_flow::generate_cash_flow_report(&config, &name, year)?,
-
-        Action::Deposits {date, cron_mode} => {
+                &config, &name, year, tax_statement_path.as_deref(), preview, diff, report_path.as_deref(),
+                audit_path.as_deref())?,
+        Action::RealizedPnl {name, year, export_path} =>
+            tax_statement::generate_realized_pnl_ledger(&config, &name, year, export_path.as_deref())?,
+        Action::CashFlow {name, year, export_path, tolerance} =>
+            cash_flow::generate_cash_flow_report(&config, &name, year, export_path.as_deref(), tolerance)?,
+        Action::YearEnd {name, year} =>
+            tax_statement::generate_year_end_report(&config, &name, year)?,
+        Action::TaxRates {name, year} =>
+            tax_statement::show_tax_rates(&config, name.as_deref(), year)?,
+        Action::Forex {name, year} =>
+            forex_trades::generate_forex_report(&config, &name, year)?,
+        Action::Timeline(name) => timeline::show(&config, &name)?,
+        Action::CorporateActionAdd(name) =>
+            corporate_action_editor::add(&config, config_path, &name)?,
+        Action::MapInternalIds(name) =>
+            internal_ids_editor::map(&config, config_path, &name)?,
+
+        Action::Deposits {date, cron_mode, monthly} => {
             deposits::list(
-                &config.get_tax_country(), config.deposits, date, cron_mode,
-                config.notify_deposit_closing_days);
+                &config.get_tax_country(), &config.db_path, config.deposits, date, cron_mode, monthly,
+                config.notify_deposit_closing_days)?;
             TelemetryRecordBuilder::new()
         },
 
-        Action::Metrics(path) => metrics::collect(&config, &path)?,
+        Action::Metrics {path, incremental} => metrics::collect(&config, &path, incremental)?,
+        Action::Doctor {check_dividends} => doctor::run(&config, check_dividends)?,
+        Action::ForexRates(pairs) => forex_rates::audit(&config, &pairs)?,
+        Action::CheckStatement {broker, path} => statement_check::check(&config, &broker, &path)?,
+        Action::ExportStatement {broker, path, output} => statement_json::export(
+            &config, &broker, &path, output.as_deref())?,
+        Action::ImportStatement {name, path} => statement_json::import(&config, &name, &path)?,
+        Action::SampleStatement {broker, path, output, rows, seed} => statement_sampler::sample(
+            &config, &broker, &path, &output, rows, seed)?,
+        Action::Init | Action::SetSecret(_) => unreachable!(),
 
         Action::ShellCompletion {path, data} => {
             write_shell_completion(&path, &data).map_err(|e| format!(
@@ -124,6 +203,8 @@ fn run(config: Config, command: &str, action: Action) -> EmptyResult {
         },
     };
 
+    profiling::print();
+
     if let Some(telemetry) = telemetry.as_ref() {
         telemetry.add(record.build(command))?;
     }
@@ -131,6 +212,34 @@ fn run(config: Config, command: &str, action: Action) -> EmptyResult {
     Ok(())
 }
 
+fn run_standalone(config_dir_path: &Path, action: Action) -> EmptyResult {
+    match action {
+        Action::Init => setup::init(config_dir_path),
+        Action::SetSecret(name) => set_secret(&name),
+        _ => unreachable!(),
+    }
+}
+
+fn set_secret(name: &str) -> EmptyResult {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Enter {:?} secret: ", name);
+    }
+
+    let mut value = String::new();
+    stdin.read_line(&mut value)?;
+
+    let value = value.trim_end_matches(['\r', '\n']);
+    if value.is_empty() {
+        return Err!("The secret is empty");
+    }
+
+    secrets::set(name, value)?;
+    println!("The secret has been saved. Use keyring:{} value to refer it from the configuration file.", name);
+
+    Ok(())
+}
+
 fn write_shell_completion(path: &Path, data: &[u8]) -> EmptyResult {
     Ok(File::create(path)?.write_all(data)?)
 }
\ No newline at end of file
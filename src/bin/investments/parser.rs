@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use chrono::Datelike;
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
 use clap::builder::NonEmptyStringValueParser;
 use clap_complete::{self, Shell};
 use itertools::Itertools;
@@ -9,11 +10,14 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use strum::{EnumMessage, IntoEnumIterator};
 
-use investments::analysis::PerformanceAnalysisMethod;
+use investments::analysis::{PerformanceAnalysisMethod, SellLot, WithdrawalPlanConfig, WithdrawalStrategy};
+use investments::broker_costs::TradingProfile;
+use investments::cash_flow::ComparisonTolerance;
 use investments::config::Config;
 use investments::core::GenericResult;
 use investments::time;
 use investments::types::{Date, Decimal};
+use investments::util::{self, DecimalRestrictions};
 
 use super::action::Action;
 use super::positions::PositionsParser;
@@ -41,6 +45,7 @@ pub struct Parser {
 pub struct GlobalOptions {
     pub log_level: log::Level,
     pub config_dir: PathBuf,
+    pub profile_timings: bool,
 }
 
 impl Parser {
@@ -79,7 +84,11 @@ impl Parser {
 
                 Arg::new("verbose").short('v').long("verbose")
                     .help("Set verbosity level")
-                    .action(ArgAction::Count)
+                    .action(ArgAction::Count),
+
+                Arg::new("profile_timings").long("profile-timings")
+                    .help("Report time spent in each command execution phase")
+                    .action(ArgAction::SetTrue),
             ])
 
             .subcommand(Command::new("analyse")
@@ -103,6 +112,28 @@ impl Parser {
                         .help("Don't hide closed positions")
                         .action(ArgAction::SetTrue),
 
+                    Arg::new("attribute_fees").short('f').long("attribute-fees")
+                        .help(concat!(
+                            "Attribute recurring broker and depositary fees to the held instruments to account them ",
+                            "in instruments performance"))
+                        .action(ArgAction::SetTrue),
+
+                    Arg::new("missed_gains").short('g').long("missed-gains")
+                        .help(concat!(
+                            "Compare the portfolio to immediate investment of every deposit into the target asset ",
+                            "allocation"))
+                        .requires("PORTFOLIO")
+                        .action(ArgAction::SetTrue),
+
+                    Arg::new("stress").short('s').long("stress")
+                        .help(concat!(
+                            "Reprice current positions under the specified stress testing scenario (2008, 2020, ",
+                            "2022-RU or a custom one from the configuration file)"))
+                        .value_name("SCENARIO")
+                        .value_parser(NonEmptyStringValueParser::new())
+                        .requires("PORTFOLIO")
+                        .conflicts_with("missed_gains"),
+
                     Arg::new("PORTFOLIO")
                         .help("Portfolio name (omit to show an aggregated result for all portfolios)")
                         .value_parser(NonEmptyStringValueParser::new()),
@@ -115,12 +146,26 @@ impl Parser {
                         .help("Flat view")
                         .action(ArgAction::SetTrue),
 
+                    Arg::new("as_of").long("as-of")
+                        .help(concat!(
+                            "Show positions and cash reconstructed from broker statements as of the specified date ",
+                            "(in DD.MM.YYYY format)"))
+                        .value_name("DATE")
+                        .value_parser(time::parse_user_date)
+                        .conflicts_with("flat"),
+
                     portfolio::arg(),
                 ]))
 
             .subcommand(Command::new("sync")
                 .about("Sync portfolio with broker statement")
-                .arg(portfolio::arg()))
+                .args([
+                    Arg::new("dry_run").short('n').long("dry-run")
+                        .help("Show open positions changes without saving them")
+                        .action(ArgAction::SetTrue),
+
+                    portfolio::arg(),
+                ]))
 
             .subcommand(Command::new("buy")
                 .about("Add the specified stock shares to the portfolio")
@@ -128,7 +173,8 @@ impl Parser {
                     portfolio::arg(),
                     self.bought.arg(),
                     cash_assets::arg(),
-                ]))
+                ])
+                .args(manual_trade_args()))
 
             .subcommand(Command::new("sell")
                 .about("Remove the specified stock shares from the portfolio")
@@ -136,7 +182,8 @@ impl Parser {
                     portfolio::arg(),
                     self.sold.arg(),
                     cash_assets::arg(),
-                ]))
+                ])
+                .args(manual_trade_args()))
 
             .subcommand(Command::new("cash")
                 .about("Set current cash assets")
@@ -163,10 +210,108 @@ impl Parser {
                         .value_name("CURRENCY")
                         .value_parser(NonEmptyStringValueParser::new()),
 
+                    Arg::new("lot").short('l').long("lot")
+                        .help(concat!(
+                            "Specific lot to sell identified by its purchase date instead of FIFO matching ",
+                            "(may be specified multiple times)"))
+                        .value_name("SYMBOL:DATE:QUANTITY")
+                        .value_parser(parse_sell_lot)
+                        .action(ArgAction::Append),
+
                     portfolio::arg(),
                     self.to_sell.arg(),
                 ]))
 
+            .subcommand(Command::new("plan-lto")
+                .about("Plan sales according to long-term ownership tax exemption")
+                .long_about(long_about!("
+                    Lists open positions' lots with the dates when long-term ownership tax
+                    exemption (3+ years of ownership) becomes applicable to them, the profit which
+                    is exempt from taxes if they are sold today and the estimated exemption limit
+                    usage per tax year assuming the current prices.
+                "))
+                .arg(portfolio::arg()))
+
+            .subcommand(Command::new("withdrawal-plan")
+                .about("Simulate withdrawals from the portfolio")
+                .long_about(long_about!("
+                    Simulates yearly withdrawals from the current portfolio using its average rate of
+                    return (taking taxes into account) and historical inflation, and reports the
+                    probability of portfolio depletion.
+
+                    Yearly returns are randomly sampled from the historical returns of the current portfolio
+                    assets (calculated by year end prices) shifted to have the portfolio's average rate of
+                    return.
+
+                    Withdrawals are indexed by inflation each year, so they stay fixed in today's
+                    money.
+                "))
+                .args([
+                    Arg::new("amount").short('a').long("amount")
+                        .help("Yearly withdrawal amount in today's money")
+                        .value_name("AMOUNT")
+                        .value_parser(parse_positive_decimal),
+
+                    Arg::new("rate").short('r').long("rate")
+                        .help("Initial yearly withdrawal rate (in percents of the portfolio)")
+                        .value_name("PERCENT")
+                        .value_parser(parse_percent),
+
+                    Arg::new("years").short('y').long("years")
+                        .help("Withdrawal period in years")
+                        .value_name("YEARS")
+                        .value_parser(value_parser!(u32).range(1..=100))
+                        .default_value("30"),
+
+                    Arg::new("simulations").short('s').long("simulations")
+                        .help("Number of simulations")
+                        .value_name("NUMBER")
+                        .value_parser(value_parser!(u32).range(1..=1_000_000))
+                        .default_value("10000"),
+
+                    Arg::new("seed").long("seed")
+                        .help("Random number generator seed (the same seed gives reproducible results)")
+                        .value_name("NUMBER")
+                        .value_parser(value_parser!(u64))
+                        .default_value("0"),
+
+                    portfolio::arg(),
+                ])
+                .group(ArgGroup::new("strategy").args(["amount", "rate"]).required(true)))
+
+            .subcommand(Command::new("broker-costs")
+                .about("Compare annual broker costs for the specified trading profile")
+                .long_about(long_about!("
+                    Simulates the specified monthly trading activity during the last year for all
+                    supported brokers and their commission plans and compares the resulting annual
+                    costs (trade commissions, monthly minimums and depositary fees).
+                "))
+                .args([
+                    Arg::new("turnover").short('t').long("turnover")
+                        .help("Monthly trading turnover")
+                        .value_name("AMOUNT")
+                        .value_parser(parse_positive_decimal)
+                        .required(true),
+
+                    Arg::new("trades").short('n').long("trades")
+                        .help("Number of trades per month")
+                        .value_name("NUMBER")
+                        .value_parser(value_parser!(u32).range(1..=10_000))
+                        .required(true),
+
+                    Arg::new("portfolio").short('p').long("portfolio")
+                        .help("Portfolio size")
+                        .value_name("AMOUNT")
+                        .value_parser(parse_non_negative_decimal)
+                        .default_value("0"),
+
+                    Arg::new("currency").short('c').long("currency")
+                        .help("Currency of the specified amounts")
+                        .value_name("CURRENCY")
+                        .value_parser(["RUB", "USD"])
+                        .default_value("RUB"),
+                ]))
+
             .subcommand(Command::new("tax-statement")
                 .about("Generate tax statement")
                 .long_about(long_about!("
@@ -176,8 +321,49 @@ impl Parser {
 
                     If tax statement file is not specified only outputs the data which is going to
                     be declared.
+
+                    In preview mode prints the records which are going to be added to the tax
+                    statement grouped by income code and country without modifying the file.
+
+                    The original tax statement file is backed up before modification.
+
+                    In XLSX format additionally saves the income calculation tables (per-trade and
+                    per-dividend income, currency rates, paid and withheld taxes and totals) to a
+                    spreadsheet which can be attached to the tax statement as supporting
+                    documentation.
+
+                    With --audit option exports all calculated figures along with their inputs
+                    (dates, currency rates, amounts from broker statements) to a JSON file, so any
+                    number of the declaration can be traced back to its origin.
                 "))
                 .args([
+                    Arg::new("diff").short('d').long("diff")
+                        .help("Print the records added to the tax statement")
+                        .requires("TAX_STATEMENT")
+                        .action(ArgAction::SetTrue),
+
+                    Arg::new("preview").short('p').long("preview")
+                        .help("Print the records to add without modifying the tax statement")
+                        .requires("TAX_STATEMENT")
+                        .action(ArgAction::SetTrue),
+
+                    Arg::new("format").short('f').long("format")
+                        .help("Report format")
+                        .value_name("FORMAT")
+                        .value_parser(["text", "xlsx"])
+                        .default_value("text"),
+
+                    Arg::new("output").short('o').long("output")
+                        .help("Path to save the XLSX report to")
+                        .value_name("PATH")
+                        .value_parser(value_parser!(PathBuf))
+                        .required_if_eq("format", "xlsx"),
+
+                    Arg::new("audit").short('a').long("audit")
+                        .help("Export the calculation audit trail to *.json file")
+                        .value_name("PATH")
+                        .value_parser(value_parser!(PathBuf)),
+
                     portfolio::arg(),
 
                     Arg::new("YEAR")
@@ -189,6 +375,27 @@ impl Parser {
                         .value_parser(value_parser!(PathBuf))
                 ]))
 
+            .subcommand(Command::new("realized-pnl")
+                .about("Show realized profit and loss ledger")
+                .long_about(long_about!("
+                    Shows a ledger of all closed positions with one entry per sold lot: purchase and
+                    sale details, currency rates and profit in both trade and local currencies. Useful
+                    for cross-checking tax reports provided by brokers.
+                "))
+                .args([
+                    portfolio::arg(),
+
+                    Arg::new("year").short('y').long("year")
+                        .help("Tax year to show the ledger for")
+                        .value_name("YEAR")
+                        .value_parser(parse_year),
+
+                    Arg::new("export").short('e').long("export")
+                        .help("Export the ledger to *.csv file")
+                        .value_name("PATH")
+                        .value_parser(value_parser!(PathBuf)),
+                ]))
+
             .subcommand(Command::new("cash-flow")
                 .about("Generate cash flow report")
                 .long_about("Generates cash flow report for tax inspection notification")
@@ -198,8 +405,111 @@ impl Parser {
                     Arg::new("YEAR")
                         .help("Year to generate the report for")
                         .value_parser(parse_year),
+
+                    Arg::new("export").short('e').long("export")
+                        .help("Export the annual report summary to *.csv or *.xlsx file")
+                        .value_name("PATH")
+                        .value_parser(value_parser!(PathBuf))
+                        .requires("YEAR"),
+
+                    Arg::new("date_tolerance").short('d').long("date-tolerance")
+                        .help(concat!(
+                            "Number of days around the date to compare calculated cash assets with the ",
+                            "broker-reported ones (to tolerate T+N settlement drift)"))
+                        .value_name("DAYS")
+                        .value_parser(value_parser!(u32))
+                        .default_value("0"),
+
+                    Arg::new("amount_tolerance").short('a').long("amount-tolerance")
+                        .help("Maximum allowed difference between calculated and broker-reported cash assets")
+                        .value_name("AMOUNT")
+                        .value_parser(parse_non_negative_decimal)
+                        .default_value("0"),
+                ]))
+
+            .subcommand(Command::new("year-end")
+                .about("Show tax year closing report")
+                .long_about(long_about!("
+                    Aggregates everything needed at the end of the tax year: realized profit,
+                    dividends, expected and actual broker tax withholdings, income which must be
+                    declared, unused long-term ownership deduction limit and a checklist of actions
+                    with their deadlines.
+                "))
+                .args([
+                    portfolio::arg(),
+
+                    Arg::new("year").short('y').long("year")
+                        .help("Tax year to show the report for [default: current year]")
+                        .value_name("YEAR")
+                        .value_parser(parse_year),
                 ]))
 
+            .subcommand(Command::new("tax-rates")
+                .about("Show tax brackets of investment income")
+                .long_about(long_about!("
+                    Aggregates taxable investment income (trading profit, dividends and interest)
+                    per year from broker statements and shows the marginal tax rate applied to it,
+                    how much income remains until the next tax bracket and the resulting tax.
+                "))
+                .args([
+                    Arg::new("year").short('y').long("year")
+                        .help("Tax year to show the report for")
+                        .value_name("YEAR")
+                        .value_parser(parse_year),
+
+                    Arg::new("PORTFOLIO")
+                        .help("Portfolio name (omit to aggregate income of all portfolios)")
+                        .value_parser(NonEmptyStringValueParser::new()),
+                ]))
+
+            .subcommand(Command::new("corporate-action")
+                .about("Manage corporate actions")
+                .subcommand_required(true)
+                .subcommand(Command::new("add")
+                    .about("Add a corporate action to the configuration file")
+                    .long_about(long_about!("
+                        Interactively asks for a corporate action, validates the symbol and the
+                        date against the broker statement, previews the effect on open positions
+                        and appends the corporate action to the portfolio configuration.
+                    "))
+                    .arg(portfolio::arg())))
+
+            .subcommand(Command::new("map-internal-ids")
+                .about("Map broker-specific instrument internal IDs to symbols")
+                .long_about(long_about!("
+                    Some brokers identify instruments in dividends and corporate actions by their
+                    internal IDs, which must be mapped to symbols via `instrument_internal_ids`
+                    configuration option. The command suggests symbols for all unmapped IDs by
+                    matching them with instrument names and ISINs from all broker statements and
+                    appends the accepted mappings to the configuration file.
+                "))
+                .arg(portfolio::arg()))
+
+            .subcommand(Command::new("forex")
+                .about("Show currency conversions report")
+                .long_about(long_about!("
+                    Lists all currency conversions with the achieved rate compared to CBR rate on
+                    the conversion date and cumulative conversion costs (broker spreads and
+                    commissions).
+                "))
+                .args([
+                    portfolio::arg(),
+
+                    Arg::new("year").short('y').long("year")
+                        .help("Year to show the conversions for")
+                        .value_name("YEAR")
+                        .value_parser(parse_year),
+                ]))
+
+            .subcommand(Command::new("timeline")
+                .about("Show portfolio events timeline")
+                .long_about(long_about!("
+                    Prints a chronological feed of deposits, trades, dividends, fees, corporate
+                    actions and tax withholdings with running cash balance, which is useful for
+                    auditing the broker statements and explaining analysis results.
+                "))
+                .arg(portfolio::arg()))
+
             .subcommand(Command::new("deposits")
                 .about("List deposits")
                 .args([
@@ -211,15 +521,174 @@ impl Parser {
                     Arg::new("cron").long("cron")
                         .help("cron mode (use for notifications about expiring and closed deposits)")
                         .action(ArgAction::SetTrue),
+
+                    Arg::new("monthly").short('m').long("monthly")
+                        .help("Show per-month statement of accrued interest and taxes")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("cron"),
                 ]))
 
             .subcommand(Command::new("metrics")
                 .about("Generate Prometheus metrics for Node Exporter Textfile Collector")
+                .arg(Arg::new("incremental").short('i').long("incremental")
+                    .help(concat!(
+                        "Analyse only portfolios with changed broker statements reusing the previously ",
+                        "collected metrics for the others"))
+                    .action(ArgAction::SetTrue))
                 .arg(Arg::new("PATH")
                     .help("Path to write the metrics to")
                     .value_parser(value_parser!(PathBuf))
                     .required(true)))
 
+            .subcommand(Command::new("doctor")
+                .about("Check the environment and data for problems")
+                .long_about(long_about!("
+                    Checks database integrity, configuration consistency, broker statements
+                    coverage, quotes providers API tokens and availability of quotes for all
+                    held instruments and prints the found problems ordered by their severity.
+                "))
+                .arg(Arg::new("check_dividends").long("check-dividends")
+                    .help(concat!(
+                        "Cross-check dividends of Moscow Exchange instruments against public dividend ",
+                        "history"))
+                    .action(ArgAction::SetTrue)))
+
+            .subcommand(Command::new("forex-rates")
+                .about("Show sources of current currency rates")
+                .long_about(long_about!("
+                    Requests current quotes of the specified currency pairs (or the ones
+                    configured for metrics) bypassing the quotes cache and prints which provider
+                    has returned each quote and how it has been derived: directly, by reversing
+                    the opposite pair quote or as a cross rate.
+                "))
+                .arg(Arg::new("PAIR")
+                    .help("Currency pair (USD/RUB for example)")
+                    .num_args(0..)))
+
+            .subcommand(Command::new("check-statement")
+                .about("Parse a single broker statement and print its summary")
+                .long_about(long_about!("
+                    Parses the specified broker statement file in isolation (without reading the
+                    rest of the statements directory) and prints its period, assets and number of
+                    records with totals per each statement section.
+                "))
+                .args([
+                    Arg::new("broker").short('b').long("broker")
+                        .help(concat!(
+                            "Broker ID (alfa, bcs, firstrade, interactive-brokers, open-broker, sber, ",
+                            "schwab, tbank)"))
+                        .value_name("ID")
+                        .required(true),
+
+                    Arg::new("PATH")
+                        .help("Path to the statement file")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ]))
+
+            .subcommand(Command::new("export-statement")
+                .about("Convert a single broker statement to the normalized JSON format")
+                .long_about(long_about!("
+                    Parses the specified broker statement file in isolation and converts it to
+                    the normalized JSON statement format (see docs/brokers.md) which is useful for
+                    debugging and as a starting point for hand-crafted statements.
+                "))
+                .args([
+                    Arg::new("broker").short('b').long("broker")
+                        .help(concat!(
+                            "Broker ID (alfa, bcs, firstrade, interactive-brokers, open-broker, sber, ",
+                            "schwab, tbank)"))
+                        .value_name("ID")
+                        .required(true),
+
+                    Arg::new("output").short('o').long("output")
+                        .help("Path to the output file (stdout by default)")
+                        .value_name("PATH")
+                        .value_parser(value_parser!(PathBuf)),
+
+                    Arg::new("PATH")
+                        .help("Path to the statement file")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ]))
+
+            .subcommand(Command::new("import-statement")
+                .about("Import a statement in the normalized JSON format")
+                .long_about(long_about!("
+                    Validates the specified statement in the normalized JSON format (see
+                    docs/brokers.md) and copies it to the portfolio's statements directory, where
+                    it's read along with the broker's native statements.
+                "))
+                .args([
+                    portfolio::arg(),
+
+                    Arg::new("PATH")
+                        .help("Path to the JSON statement file")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ]))
+
+            .subcommand(Command::new("sample-statement")
+                .about("Generate a minimal synthetic statement for the test corpus")
+                .long_about(long_about!("
+                    Generates a minimal synthetic statement from an anonymized broker statement:
+                    keeps only a few records of each kind (by data type, currency, asset category
+                    and shape of the values) per statement section and scrambles the amounts, so
+                    the result exercises the same parser code paths without publishing personal
+                    data. Only Interactive Brokers statements are supported for now.
+                "))
+                .args([
+                    Arg::new("broker").short('b').long("broker")
+                        .help("Broker ID (interactive-brokers)")
+                        .value_name("ID")
+                        .required(true),
+
+                    Arg::new("rows").short('r').long("rows")
+                        .help("Number of records to keep per each kind of records")
+                        .value_name("NUMBER")
+                        .value_parser(value_parser!(usize))
+                        .default_value("1"),
+
+                    Arg::new("seed").short('s').long("seed")
+                        .help("Seed for amounts scrambling to get reproducible results")
+                        .value_name("NUMBER")
+                        .value_parser(value_parser!(u64)),
+
+                    Arg::new("output").short('o').long("output")
+                        .help("Path to the output file")
+                        .value_name("PATH")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+
+                    Arg::new("PATH")
+                        .help("Path to the statement file")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ]))
+
+            .subcommand(Command::new("init")
+                .about("Create a starter configuration file")
+                .long_about(long_about!("
+                    Interactively creates a starter configuration file: asks for your brokers,
+                    broker statements directories and quotes providers API tokens (which are
+                    validated by requesting some quotes).
+                ")))
+
+            .subcommand(Command::new("config")
+                .about("Manage the configuration")
+                .subcommand_required(true)
+                .subcommand(Command::new("set-secret")
+                    .about("Save a secret to the OS keyring")
+                    .long_about(long_about!("
+                        Reads a secret (API token for example) from stdin and saves it to the OS
+                        keyring, so it can be referred from the configuration file as keyring:$NAME
+                        instead of storing it in plaintext.
+                    "))
+                    .arg(Arg::new("NAME")
+                        .help("Secret name")
+                        .value_parser(NonEmptyStringValueParser::new())
+                        .required(true))))
+
             .subcommand(Command::new("completion")
                 .about("Generate shell completion rules")
                 .args([
@@ -246,6 +715,7 @@ impl Parser {
 
         let config_dir = matches.get_one("config").cloned().unwrap_or_else(||
             PathBuf::from(shellexpand::tilde(DEFAULT_CONFIG_DIR_PATH).to_string()));
+        let profile_timings = matches.get_flag("profile_timings");
 
         {
             let mut app = app;
@@ -261,14 +731,30 @@ impl Parser {
 
         self.matches = Some(matches);
 
-        Ok(GlobalOptions {log_level, config_dir})
+        Ok(GlobalOptions {log_level, config_dir, profile_timings})
+    }
+
+    /// Parses commands which must be executed without the configuration file
+    pub fn parse_standalone(&self) -> Option<Action> {
+        let (command, matches) = self.matches.as_ref().unwrap().subcommand().unwrap();
+
+        Some(match command {
+            "init" => Action::Init,
+            "config" => match matches.subcommand().unwrap() {
+                ("set-secret", matches) => Action::SetSecret(matches.get_one("NAME").cloned().unwrap()),
+                _ => unreachable!(),
+            },
+            _ => return None,
+        })
     }
 
     pub fn parse(mut self, config: &mut Config) -> GenericResult<(String, Action)> {
         let matches = self.matches.take().unwrap();
 
         if let Some(expire_time) = matches.get_one("cache_expire_time").cloned() {
+            // Explicitly specified expire time overrides all configured ones
             config.cache_expire_time = expire_time;
+            config.quotes.cache_expire_time.clear();
         };
 
         let (command, matches) = matches.subcommand().unwrap();
@@ -283,9 +769,15 @@ impl Parser {
                 name: matches.get_one("PORTFOLIO").cloned(),
                 method: matches.get_one("method").cloned().unwrap(),
                 show_closed_positions: matches.get_flag("all"),
+                attribute_fees: matches.get_flag("attribute_fees"),
+                missed_gains: matches.get_flag("missed_gains"),
+                stress_scenario: matches.get_one("stress").cloned(),
             },
 
-            "sync" => Action::Sync(portfolio::get(matches)),
+            "sync" => Action::Sync {
+                name: portfolio::get(matches),
+                dry_run: matches.get_flag("dry_run"),
+            },
             "buy" | "sell" | "cash" => {
                 let name = portfolio::get(matches);
                 let cash_assets = Decimal::from_str(&cash_assets::get(matches))
@@ -297,10 +789,14 @@ impl Parser {
                         positions: self.bought.parse(matches)?.unwrap().into_iter().map(|(symbol, shares)| {
                             (symbol, shares.unwrap())
                         }).collect(),
+                        price: matches.get_one("price").cloned(),
+                        date: matches.get_one("date").cloned(),
                     },
                     "sell" => Action::Sell {
                         name, cash_assets,
                         positions: self.sold.parse(matches)?.unwrap(),
+                        price: matches.get_one("price").cloned(),
+                        date: matches.get_one("date").cloned(),
                     },
                     "cash" => Action::SetCashAssets(name, cash_assets),
                     _ => unreachable!(),
@@ -310,6 +806,7 @@ impl Parser {
             "show" => Action::Show {
                 name: portfolio::get(matches),
                 flat: matches.get_flag("flat"),
+                as_of: matches.get_one("as_of").cloned(),
             },
 
             "rebalance" => Action::Rebalance {
@@ -317,17 +814,56 @@ impl Parser {
                 flat: matches.get_flag("flat"),
             },
 
+            "withdrawal-plan" => Action::WithdrawalPlan {
+                name: portfolio::get(matches),
+                config: WithdrawalPlanConfig {
+                    strategy: match matches.get_one("amount").cloned() {
+                        Some(amount) => WithdrawalStrategy::FixedAmount(amount),
+                        None => WithdrawalStrategy::Rate(
+                            matches.get_one::<Decimal>("rate").cloned().unwrap() / Decimal::ONE_HUNDRED),
+                    },
+                    years: matches.get_one("years").cloned().unwrap(),
+                    simulations: matches.get_one("simulations").cloned().unwrap(),
+                    seed: matches.get_one("seed").cloned().unwrap(),
+                },
+            },
+
             "simulate-sell" => Action::SimulateSell {
                 name: portfolio::get(matches),
                 positions: self.to_sell.parse(matches)?,
+                lots: matches.get_many("lot").unwrap_or_default().cloned().collect(),
                 base_currency: matches.get_one("base_currency").cloned(),
             },
 
+            "plan-lto" => Action::PlanLto(portfolio::get(matches)),
+
+            "broker-costs" => Action::BrokerCosts(TradingProfile {
+                currency: matches.get_one("currency").cloned().unwrap(),
+                monthly_turnover: matches.get_one("turnover").cloned().unwrap(),
+                monthly_trades: matches.get_one("trades").cloned().unwrap(),
+                portfolio_size: matches.get_one("portfolio").cloned().unwrap(),
+            }),
+
             "tax-statement" => {
                 Action::TaxStatement {
                     name: portfolio::get(matches),
                     year: matches.get_one("YEAR").cloned(),
                     tax_statement_path: matches.get_one("TAX_STATEMENT").cloned(),
+                    preview: matches.get_flag("preview"),
+                    diff: matches.get_flag("diff"),
+                    report_path: match matches.get_one::<String>("format").unwrap().as_str() {
+                        "xlsx" => matches.get_one("output").cloned(),
+                        _ => None,
+                    },
+                    audit_path: matches.get_one("audit").cloned(),
+                }
+            },
+
+            "realized-pnl" => {
+                Action::RealizedPnl {
+                    name: portfolio::get(matches),
+                    year: matches.get_one("year").cloned(),
+                    export_path: matches.get_one("export").cloned(),
                 }
             },
 
@@ -335,18 +871,75 @@ impl Parser {
                 Action::CashFlow {
                     name: portfolio::get(matches),
                     year: matches.get_one("YEAR").cloned(),
+                    export_path: matches.get_one("export").cloned(),
+                    tolerance: ComparisonTolerance {
+                        days: matches.get_one("date_tolerance").cloned().unwrap(),
+                        amount: matches.get_one("amount_tolerance").cloned().unwrap(),
+                    },
+                }
+            },
+
+            "year-end" => {
+                Action::YearEnd {
+                    name: portfolio::get(matches),
+                    year: matches.get_one("year").cloned().unwrap_or_else(|| time::today().year()),
+                }
+            },
+
+            "tax-rates" => Action::TaxRates {
+                name: matches.get_one("PORTFOLIO").cloned(),
+                year: matches.get_one("year").cloned(),
+            },
+
+            "forex" => {
+                Action::Forex {
+                    name: portfolio::get(matches),
+                    year: matches.get_one("year").cloned(),
                 }
             },
+            "timeline" => Action::Timeline(portfolio::get(matches)),
+            "corporate-action" => match matches.subcommand().unwrap() {
+                ("add", matches) => Action::CorporateActionAdd(portfolio::get(matches)),
+                _ => unreachable!(),
+            },
+            "map-internal-ids" => Action::MapInternalIds(portfolio::get(matches)),
 
             "deposits" => {
                 Action::Deposits {
                     date: matches.get_one("date").cloned().unwrap_or_else(time::today),
                     cron_mode: matches.get_flag("cron"),
+                    monthly: matches.get_flag("monthly"),
                 }
             },
 
-            "metrics" => {
-                Action::Metrics(matches.get_one("PATH").cloned().unwrap())
+            "metrics" => Action::Metrics {
+                path: matches.get_one("PATH").cloned().unwrap(),
+                incremental: matches.get_flag("incremental"),
+            },
+            "forex-rates" => Action::ForexRates(
+                matches.get_many::<String>("PAIR").map(|pairs| pairs.cloned().collect()).unwrap_or_default()),
+            "doctor" => Action::Doctor {
+                check_dividends: matches.get_flag("check_dividends"),
+            },
+            "check-statement" => Action::CheckStatement {
+                broker: matches.get_one::<String>("broker").cloned().unwrap(),
+                path: matches.get_one("PATH").cloned().unwrap(),
+            },
+            "export-statement" => Action::ExportStatement {
+                broker: matches.get_one::<String>("broker").cloned().unwrap(),
+                path: matches.get_one("PATH").cloned().unwrap(),
+                output: matches.get_one("output").cloned(),
+            },
+            "import-statement" => Action::ImportStatement {
+                name: portfolio::get(matches),
+                path: matches.get_one("PATH").cloned().unwrap(),
+            },
+            "sample-statement" => Action::SampleStatement {
+                broker: matches.get_one::<String>("broker").cloned().unwrap(),
+                path: matches.get_one("PATH").cloned().unwrap(),
+                output: matches.get_one("output").cloned().unwrap(),
+                rows: matches.get_one("rows").cloned().unwrap(),
+                seed: matches.get_one("seed").cloned(),
             },
 
             "completion" => Action::ShellCompletion {
@@ -359,12 +952,60 @@ impl Parser {
     }
 }
 
+fn manual_trade_args() -> [Arg; 2] {
+    [
+        Arg::new("price").short('p').long("price")
+            .help(concat!(
+                "Trade price per share in portfolio currency. If specified, the trade is recorded to be taken into ",
+                "account by performance analysis of portfolios without broker statements"))
+            .value_name("PRICE")
+            .value_parser(parse_positive_decimal),
+
+        Arg::new("date").short('d').long("date")
+            .help("Trade date (in DD.MM.YYYY format, today by default)")
+            .value_name("DATE")
+            .value_parser(time::parse_user_date)
+            .requires("price"),
+    ]
+}
+
 fn parse_year(year: &str) -> GenericResult<i32> {
     Ok(year.parse::<i32>().ok()
         .and_then(|year| Date::from_ymd_opt(year, 1, 1).and(Some(year)))
         .ok_or_else(|| format!("Invalid year: {}", year))?)
 }
 
+fn parse_positive_decimal(value: &str) -> GenericResult<Decimal> {
+    util::parse_decimal(value, DecimalRestrictions::StrictlyPositive)
+}
+
+fn parse_non_negative_decimal(value: &str) -> GenericResult<Decimal> {
+    util::parse_decimal(value, DecimalRestrictions::PositiveOrZero)
+}
+
+fn parse_sell_lot(value: &str) -> GenericResult<SellLot> {
+    let mut parts = value.split(':');
+
+    let (symbol, date, quantity) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(symbol), Some(date), Some(quantity), None) if !symbol.is_empty() => (symbol, date, quantity),
+        _ => return Err!("Invalid lot specification: {:?}", value),
+    };
+
+    let date = time::parse_date(date, "%Y-%m-%d").or_else(|_| time::parse_user_date(date))?;
+    let quantity = util::parse_decimal(quantity, DecimalRestrictions::StrictlyPositive)
+        .map_err(|_| format!("Invalid lot quantity: {:?}", quantity))?;
+
+    Ok(SellLot {symbol: symbol.to_owned(), date, quantity})
+}
+
+fn parse_percent(value: &str) -> GenericResult<Decimal> {
+    let percent = util::parse_decimal(value.strip_suffix('%').unwrap_or(value), DecimalRestrictions::PositiveOrZero)?;
+    if percent > Decimal::ONE_HUNDRED {
+        return Err!("The value is too big");
+    }
+    Ok(percent)
+}
+
 macro_rules! arg {
     ($id:ident, $name:expr, $help:expr) => {
         mod $id {
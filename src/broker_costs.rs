@@ -0,0 +1,163 @@
+use chrono::Duration;
+use static_table_derive::StaticTable;
+use strum::IntoEnumIterator;
+
+use crate::brokers::Broker;
+use crate::commissions::{CommissionCalc, CommissionSpec};
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::{CurrencyConverter, CurrencyConverterRc};
+use crate::db;
+use crate::formatting::table::Cell;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::{self, Month};
+use crate::types::{Decimal, TradeType};
+
+// Commissions depend on the share price only for per-share plans, so assume a typical price in trading currency
+const SHARE_PRICE: Decimal = dec!(100);
+
+/// Hypothetical monthly trading activity
+pub struct TradingProfile {
+    pub currency: String,
+    pub monthly_turnover: Decimal,
+    pub monthly_trades: u32,
+    pub portfolio_size: Decimal,
+}
+
+/// Compares annual costs of the specified trading profile across all supported brokers and plans
+pub fn compare(config: &Config, profile: &TradingProfile) -> GenericResult<TelemetryRecordBuilder> {
+    let currency = ["RUB", "USD"].into_iter().find(|&currency| currency == profile.currency).ok_or_else(|| format!(
+        "Unsupported currency: {}", profile.currency))?;
+
+    let database = db::connect(&config.db_path)?;
+    let converter = CurrencyConverter::new(database, None, true);
+
+    let mut plans = Vec::new();
+    for broker in Broker::iter() {
+        for (name, spec) in broker.get_commission_plans() {
+            plans.push((broker, name, spec));
+        }
+    }
+
+    let costs = calculate(&converter, currency, profile, plans, Month::from(time::today()))?;
+    print(profile, costs);
+
+    Ok(TelemetryRecordBuilder::new())
+}
+
+struct PlanCost {
+    broker: Broker,
+    plan: &'static str,
+    cost: Cash,
+}
+
+// Simulates the trading profile during the year before the specified month
+fn calculate(
+    converter: &CurrencyConverterRc, currency: &'static str, profile: &TradingProfile,
+    plans: Vec<(Broker, &'static str, CommissionSpec)>, current_month: Month,
+) -> GenericResult<Vec<PlanCost>> {
+    let mut months = vec![current_month.prev()];
+    while months.len() < 12 {
+        months.push(months.last().unwrap().prev());
+    }
+    months.reverse();
+
+    let portfolio_net_value = Cash::new(currency, profile.portfolio_size);
+    let trade_volume = profile.monthly_turnover / Decimal::from(profile.monthly_trades);
+
+    let mut costs = Vec::new();
+
+    for (broker, plan, spec) in plans {
+        let trade_currency = broker.jurisdiction().traits().currency;
+        let mut calc = CommissionCalc::new(converter.clone(), spec, portfolio_net_value)?;
+        let mut cost = dec!(0);
+
+        for month in &months {
+            let period = month.period();
+
+            for trade_id in 0..profile.monthly_trades {
+                let date = period.first_date() + Duration::days(
+                    period.days() * i64::from(trade_id) / i64::from(profile.monthly_trades));
+
+                let trade_type = if trade_id % 2 == 0 {
+                    TradeType::Buy
+                } else {
+                    TradeType::Sell
+                };
+
+                let volume = converter.convert(currency, trade_currency, date, trade_volume)?;
+                let shares = volume / SHARE_PRICE;
+
+                let commission = calc.add_trade(date, trade_type, shares, Cash::new(trade_currency, SHARE_PRICE))?;
+                cost += converter.convert_to(date, commission, currency)?;
+            }
+        }
+
+        for (date, commissions) in calc.calculate()? {
+            cost += commissions.total_assets(date, currency, converter)?;
+        }
+
+        costs.push(PlanCost {broker, plan, cost: Cash::new(currency, cost).round()});
+    }
+
+    costs.sort_by_key(|cost| cost.cost.amount);
+
+    Ok(costs)
+}
+
+#[derive(StaticTable)]
+#[table(name="BrokerCostsTable")]
+struct Row {
+    #[column(name="Broker")]
+    broker: &'static str,
+    #[column(name="Plan")]
+    plan: &'static str,
+    #[column(name="Annual cost")]
+    cost: Cash,
+    #[column(name="Of portfolio")]
+    ratio: Option<Cell>,
+}
+
+fn print(profile: &TradingProfile, costs: Vec<PlanCost>) {
+    let mut table = BrokerCostsTable::new();
+
+    for PlanCost {broker, plan, cost} in costs {
+        table.add_row(Row {
+            broker: broker.brief_name(),
+            plan,
+            cost,
+            ratio: (!profile.portfolio_size.is_zero()).then(|| Cell::new_ratio(cost.amount / profile.portfolio_size)),
+        });
+    }
+
+    table.print("Annual broker costs");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculation() {
+        let profile = TradingProfile {
+            currency: s!("RUB"),
+            monthly_turnover: dec!(100_000),
+            monthly_trades: 4,
+            portfolio_size: dec!(1_000_000),
+        };
+
+        let plans = Broker::Tbank.get_commission_plans().into_iter()
+            .map(|(name, spec)| (Broker::Tbank, name, spec))
+            .collect();
+
+        let costs = calculate(&CurrencyConverter::mock(), "RUB", &profile, plans, Month::from(date!(2024, 1, 15))).unwrap();
+        let costs: Vec<_> = costs.iter().map(|cost| (cost.plan, cost.cost)).collect();
+
+        assert_eq!(costs, vec![
+            ("Премиум", Cash::new("RUB", dec!(480))),
+            ("Инвестор", Cash::new("RUB", dec!(3600))),
+            ("Трейдер", Cash::new("RUB", dec!(4080))),
+        ]);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::broker_statement::partial::PartialBrokerStatementRc;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::formats::xls::{self, XlsTableRow, XlsStatementParser, SectionParser, TableReader, Cell};
+use crate::instruments;
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::common::{parse_currency, trim_column_title};
+
+pub struct CashAssetsParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl CashAssetsParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(CashAssetsParser {statement})
+    }
+}
+
+impl SectionParser for CashAssetsParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        for assets in xls::read_table::<CashAssetsRow>(&mut parser.sheet)? {
+            let currency = parse_currency(&assets.currency)?;
+
+            if !assets.starting.is_zero() {
+                statement.has_starting_assets.replace(true);
+            }
+
+            if assets.ending.is_sign_negative() {
+                return Err!("Leverage is not supported yet");
+            } else if !assets.ending.is_zero() {
+                statement.assets.cash.as_mut().unwrap().deposit(Cash::new(currency, assets.ending));
+            }
+        }
+
+        statement.has_starting_assets.get_or_insert(false);
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+#[table(trim_column_title="trim_column_title")]
+struct CashAssetsRow {
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Остаток на начало периода")]
+    starting: Decimal,
+    #[column(name="Остаток на конец периода")]
+    ending: Decimal,
+}
+
+impl TableReader for CashAssetsRow {
+    fn skip_row(row: &[Option<&Cell>]) -> GenericResult<bool> {
+        Ok(xls::get_string_cell(row[0].unwrap())? == "Итого")
+    }
+}
+
+pub struct SecuritiesParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl SecuritiesParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(SecuritiesParser {statement})
+    }
+}
+
+impl SectionParser for SecuritiesParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        for security in xls::read_table::<SecurityRow>(&mut parser.sheet)? {
+            let symbol = security.symbol.trim();
+
+            let instrument = statement.instrument_info.get_or_add(symbol);
+            instrument.set_name(security.name.trim());
+            instrument.add_isin(instruments::parse_isin(security.isin.trim())?);
+
+            if !security.starting.is_zero() {
+                statement.has_starting_assets.replace(true);
+            }
+
+            if !security.ending.is_zero() {
+                statement.add_open_position(symbol, security.ending)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+#[table(trim_column_title="trim_column_title")]
+struct SecurityRow {
+    #[column(name="Тикер")]
+    symbol: String,
+    #[column(name="ISIN")]
+    isin: String,
+    #[column(name="Наименование")]
+    name: String,
+    #[column(name="Количество на начало периода", validate_with="validate_quantity")]
+    starting: Decimal,
+    #[column(name="Количество на конец периода", validate_with="validate_quantity")]
+    ending: Decimal,
+}
+
+impl TableReader for SecurityRow {
+}
+
+fn validate_quantity(quantity: &Decimal) -> EmptyResult {
+    util::validate_decimal(*quantity, DecimalRestrictions::PositiveOrZero)?;
+    Ok(())
+}
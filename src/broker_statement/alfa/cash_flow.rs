@@ -0,0 +1,114 @@
+use chrono::Datelike;
+
+use crate::broker_statement::fees::Fee;
+use crate::broker_statement::interest::IdleCashInterest;
+use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
+use crate::broker_statement::payments::Withholding;
+use crate::core::EmptyResult;
+use crate::currency::CashAssets;
+use crate::formats::xls::{self, XlsTableRow, XlsStatementParser, SectionParser, TableReader};
+use crate::formatting;
+use crate::time::Date;
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::common::{parse_currency, parse_date_cell, trim_column_title};
+
+pub struct CashFlowParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl CashFlowParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(CashFlowParser {statement})
+    }
+}
+
+impl SectionParser for CashFlowParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        for cash_flow in &xls::read_table::<CashFlowRow>(&mut parser.sheet)? {
+            cash_flow.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {:?} operation from {}: {}",
+                cash_flow.operation, formatting::format_date(cash_flow.date), e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+#[table(trim_column_title="trim_column_title")]
+struct CashFlowRow {
+    #[column(name="Дата", parse_with="parse_date_cell")]
+    date: Date,
+    #[column(name="Операция")]
+    operation: String,
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Сумма")]
+    amount: Decimal,
+    #[column(name="Комментарий")]
+    comment: Option<String>,
+}
+
+impl TableReader for CashFlowRow {
+}
+
+impl CashFlowRow {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let operation = self.operation.trim();
+        let currency = parse_currency(&self.currency)?;
+
+        let positive = || util::validate_named_cash(
+            "amount", currency, self.amount, DecimalRestrictions::StrictlyPositive);
+        let negative = || util::validate_named_cash(
+            "amount", currency, -self.amount, DecimalRestrictions::StrictlyPositive);
+
+        match operation {
+            "Ввод ДС" | "Зачисление ДС" => {
+                let amount = positive()?;
+                statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(self.date, amount));
+            },
+
+            "Вывод ДС" | "Списание ДС" => {
+                let amount = negative()?;
+                statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(self.date, -amount));
+            },
+
+            // Trade settlements and commissions are taken from trades table
+            "Расчеты по сделке" | "Комиссия по сделке" => {},
+
+            "Проценты на остаток" => {
+                let amount = positive()?;
+                statement.idle_cash_interest.push(IdleCashInterest::new(self.date, amount));
+            },
+
+            "НДФЛ" => {
+                let withheld_tax = negative()?;
+
+                // The tax may be withheld for the previous year in which case the comment contains the year
+                let year = match self.comment.as_deref().map(str::trim) {
+                    Some(comment) if comment.len() == 4 => comment.parse::<i32>().map_err(|_| format!(
+                        "Got an unexpected comment: {:?}", comment))?,
+                    _ => self.date.year(),
+                };
+
+                statement.tax_agent_withholdings.add(self.date, year, Withholding::new(withheld_tax))?;
+            },
+
+            _ => {
+                let Some(description) = operation.strip_prefix("Комиссия ") else {
+                    return Err!("Unsupported cash flow operation");
+                };
+
+                let amount = negative()?;
+                let description = format!("Комиссия брокера: {}", description);
+                statement.fees.push(Fee::new(self.date, Withholding::new(amount), Some(description)));
+            },
+        };
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::core::GenericResult;
+use crate::formats::xls::{self, Cell};
+use crate::time::{self, Date, Time};
+
+pub fn parse_date(date: &str) -> GenericResult<Date> {
+    time::parse_date(date, "%d.%m.%Y")
+}
+
+pub fn parse_date_cell(cell: &Cell) -> GenericResult<Date> {
+    parse_date(xls::get_string_cell(cell)?.trim())
+}
+
+pub fn parse_time_cell(cell: &Cell) -> GenericResult<Time> {
+    time::parse_time(xls::get_string_cell(cell)?.trim(), "%H:%M:%S")
+}
+
+pub fn parse_currency(code: &str) -> GenericResult<&'static str> {
+    Ok(match code.trim() {
+        "RUB" | "RUR" => "RUB",
+        "USD" => "USD",
+        "EUR" => "EUR",
+        "CNY" => "CNY",
+        "HKD" => "HKD",
+        _ => return Err!("Unsupported currency: {:?}", code),
+    })
+}
+
+pub fn trim_column_title(title: &str) -> Cow<str> {
+    lazy_static! {
+        static ref SPACES_REGEX: Regex = Regex::new(r"\s+").unwrap();
+    }
+    // Column titles are often wrapped to several lines
+    SPACES_REGEX.replace_all(title.trim(), " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(code, expected,
+        case("RUB", "RUB"),
+        case("RUR", "RUB"),
+        case(" USD ", "USD"),
+    )]
+    fn currency_parsing(code: &str, expected: &str) {
+        assert_eq!(parse_currency(code).unwrap(), expected);
+    }
+
+    #[rstest(input, expected,
+        case("Вид сделки", "Вид сделки"),
+        case("Комиссия\nброкера", "Комиссия брокера"),
+        case(" Дата  расчетов ", "Дата расчетов"),
+    )]
+    fn column_title_trimming(input: &str, expected: &str) {
+        assert_eq!(trim_column_title(input), expected);
+    }
+}
@@ -0,0 +1,62 @@
+mod assets;
+mod cash_flow;
+mod common;
+mod period;
+mod trades;
+
+use std::rc::Rc;
+
+use crate::core::GenericResult;
+use crate::exchanges::Exchange;
+use crate::formats::xls::{XlsStatementParser, Section, SheetParser};
+
+use super::{BrokerStatementReader, PartialBrokerStatement};
+
+use assets::{CashAssetsParser, SecuritiesParser};
+use cash_flow::CashFlowParser;
+use period::PeriodParser;
+use trades::TradesParser;
+
+pub struct StatementReader {
+}
+
+impl StatementReader {
+    pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
+        Ok(Box::new(StatementReader{}))
+    }
+}
+
+impl BrokerStatementReader for StatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        Ok(path.ends_with(".xlsx"))
+    }
+
+    fn read(&mut self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        let parser = Box::new(StatementSheetParser{});
+        let statement = PartialBrokerStatement::new_rc(&[Exchange::Moex, Exchange::Spb], true);
+
+        XlsStatementParser::read(path, parser, vec![
+            Section::new(PeriodParser::PERIOD_PREFIX).by_prefix()
+                .parser(PeriodParser::new(statement.clone())).required(),
+            Section::new("Денежные средства")
+                .parser(CashAssetsParser::new(statement.clone())).required(),
+            Section::new("Движение денежных средств")
+                .parser(CashFlowParser::new(statement.clone())).required(),
+            Section::new("Сделки с ценными бумагами")
+                .parser(TradesParser::new(statement.clone())),
+            Section::new("Ценные бумаги")
+                .parser(SecuritiesParser::new(statement.clone())),
+        ])?;
+
+        Rc::try_unwrap(statement).ok().unwrap().into_inner().validate()
+    }
+}
+
+struct StatementSheetParser {
+}
+
+impl SheetParser for StatementSheetParser {
+    fn sheet_name(&self) -> &str {
+        "Брокерский отчет"
+    }
+}
@@ -0,0 +1,126 @@
+use crate::broker_statement::bonds;
+use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
+use crate::broker_statement::trades::{StockBuy, StockSell};
+use crate::core::EmptyResult;
+use crate::exchanges::Exchange;
+use crate::formats::xls::{self, XlsTableRow, XlsStatementParser, SectionParser, TableReader, SkipCell};
+use crate::time::{Date, DateTime, Time};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::common::{parse_currency, parse_date_cell, parse_time_cell, trim_column_title};
+
+pub struct TradesParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl TradesParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(TradesParser {statement})
+    }
+}
+
+impl SectionParser for TradesParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        let mut trades = xls::read_table::<TradeRow>(&mut parser.sheet)?;
+        trades.sort_by(|a, b| (a.date, a.time, &a.id).cmp(&(b.date, b.time, &b.id)));
+
+        for trade in trades {
+            trade.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse #{} trade: {}", trade.id.trim(), e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+#[table(trim_column_title="trim_column_title")]
+struct TradeRow {
+    #[column(name="Дата заключения", parse_with="parse_date_cell")]
+    date: Date,
+    #[column(name="Время заключения", parse_with="parse_time_cell")]
+    time: Time,
+    #[column(name="Номер сделки")]
+    id: String,
+    #[column(name="Тикер")]
+    symbol: String,
+    #[column(name="ISIN")]
+    _4: SkipCell,
+    #[column(name="Вид сделки")]
+    operation: String,
+    #[column(name="Количество")]
+    quantity: Decimal,
+    #[column(name="Цена")]
+    price: Decimal,
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Сумма сделки")]
+    volume: Decimal,
+    #[column(name="НКД", optional=true)]
+    accumulated_coupon_income: Option<Decimal>,
+    #[column(name="Комиссия брокера")]
+    broker_commission: Decimal,
+    #[column(name="Комиссия биржи")]
+    exchange_commission: Decimal,
+    #[column(name="Дата расчетов", parse_with="parse_date_cell")]
+    execution_date: Date,
+    #[column(name="Площадка")]
+    exchange: String,
+}
+
+impl TableReader for TradeRow {
+}
+
+impl TradeRow {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let symbol = self.symbol.trim();
+        let currency = parse_currency(&self.currency)?;
+        let conclusion_time = DateTime::new(self.date, self.time).into();
+
+        let exchange = match self.exchange.trim() {
+            "МБ" | "Московская Биржа" => Exchange::Moex,
+            "СПБ" | "СПБ Биржа" => Exchange::Spb,
+            "Внебиржевой рынок" => Exchange::Otc,
+            _ => return Err!("Unknown exchange: {:?}", self.exchange),
+        };
+
+        let quantity = util::validate_named_decimal(
+            "quantity", self.quantity, DecimalRestrictions::StrictlyPositive)?;
+        let mut price = util::validate_named_cash(
+            "price", currency, self.price, DecimalRestrictions::StrictlyPositive)?;
+        let mut volume = util::validate_named_cash(
+            "trade volume", currency, self.volume, DecimalRestrictions::StrictlyPositive)?;
+
+        match self.accumulated_coupon_income {
+            Some(accumulated_coupon_income) if !accumulated_coupon_income.is_zero() => {
+                (price, volume) = bonds::get_bond_trade_volume(quantity, volume, accumulated_coupon_income)?;
+            },
+            _ => debug_assert_eq!(volume, (price * quantity).round()),
+        }
+
+        let commission = util::validate_named_cash(
+            "broker commission", currency, self.broker_commission, DecimalRestrictions::PositiveOrZero,
+        )? + util::validate_named_cash(
+            "exchange commission", currency, self.exchange_commission, DecimalRestrictions::PositiveOrZero,
+        )?;
+
+        statement.instrument_info.get_or_add(symbol).exchanges.add_prioritized(exchange);
+
+        match self.operation.trim() {
+            "Покупка" => {
+                statement.stock_buys.push(StockBuy::new_trade(
+                    symbol, quantity, price, volume, commission, conclusion_time, self.execution_date));
+            },
+            "Продажа" => {
+                statement.stock_sells.push(StockSell::new_trade(
+                    symbol, quantity, price, volume, commission, conclusion_time, self.execution_date, false));
+            },
+            _ => return Err!("Unsupported trade type: {:?}", self.operation),
+        }
+
+        Ok(())
+    }
+}
@@ -64,8 +64,8 @@ struct AssetRow {
     _5: SkipCell,
     #[column(name="Сумма, в т.ч. НКД")]
     start_value: Option<Decimal>,
-    #[column(name="Кол-во ЦБ / Масса ДМ (шт/г)", alias="Кол-во ценных бумаг")]
-    end_quantity: Option<Decimal>,
+    #[column(name="Кол-во ЦБ / Масса ДМ (шт/г)", alias="Кол-во ценных бумаг", default="dec!(0)", validate_with="validate_quantity")]
+    end_quantity: Decimal,
     #[column(name="Цена закрытия/ котировка вторич.")]
     _8: SkipCell,
     #[column(name="Сумма НКД")]
@@ -110,9 +110,7 @@ impl AssetRow {
 
     fn parse_stock(&self, statement: &mut PartialBrokerStatement, blocked: bool) -> EmptyResult {
         let symbol = parse_symbol(&self.name)?;
-        let quantity = util::validate_named_decimal(
-            &format!("open position for {:?}", self.name),
-            self.end_quantity.unwrap_or_default(), DecimalRestrictions::PositiveOrZero)?;
+        let quantity = self.end_quantity;
 
         let isin = self.id.as_ref().and_then(|id| instruments::parse_isin(id).ok())
             .or_else(|| instruments::parse_isin(&self.name).ok())
@@ -135,4 +133,9 @@ impl AssetRow {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn validate_quantity(quantity: &Decimal) -> EmptyResult {
+    util::validate_decimal(*quantity, DecimalRestrictions::PositiveOrZero)?;
+    Ok(())
+}
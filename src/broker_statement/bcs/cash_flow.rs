@@ -1,4 +1,5 @@
 use crate::broker_statement::fees::Fee;
+use crate::broker_statement::grants::CashGrant;
 use crate::broker_statement::interest::IdleCashInterest;
 use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
 use crate::broker_statement::payments::Withholding;
@@ -105,6 +106,15 @@ impl CashFlowRow {
                 statement.idle_cash_interest.push(IdleCashInterest::new(self.date, amount));
             },
 
+            "Бонус" | "Зачисление бонуса" => {
+                validator.deposit = DecimalRestrictions::StrictlyPositive;
+                validator.validate()?;
+
+                let amount = Cash::new(currency, self.deposit);
+                let description = self.comment.as_deref().unwrap_or(operation);
+                statement.cash_grants.push(CashGrant::new(self.date, amount, description));
+            },
+
             "Покупка/Продажа" | "Покупка/Продажа (репо)" | "Внебиржевая сделка ОТС" => {
                 validator.deposit = DecimalRestrictions::PositiveOrZero;
                 validator.withdrawal = DecimalRestrictions::PositiveOrZero;
@@ -14,7 +14,7 @@ use crate::exchanges::Exchange;
 use crate::formats::xls::{XlsStatementParser, Section, SheetParser};
 #[cfg(test)] use crate::taxes::TaxRemapping;
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementSections};
 use super::{BrokerStatementReader, PartialBrokerStatement};
 
 use assets::AssetsParser;
@@ -107,7 +107,7 @@ mod tests {
 
         let statement = BrokerStatement::read(
             broker, &path, &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(), &[],
-            corporate_actions, ReadingStrictness::all()).unwrap();
+            corporate_actions, ReadingStrictness::all(), StatementSections::all()).unwrap();
 
         assert!(!statement.assets.cash.is_empty());
         assert!(statement.assets.other.is_none()); // TODO(konishchev): Get it from statements
@@ -189,6 +189,7 @@ impl TradeRow {
 
             statement.cash_flows.push(CashFlow::new(conclusion_time, amount, CashFlowType::Repo {
                 symbol: symbol.to_owned(),
+                quantity: quantity.into(),
                 commission
             }));
         } else {
@@ -0,0 +1,61 @@
+use chrono::Duration;
+
+use crate::brokers::Broker;
+use crate::config::Config;
+use crate::currency::Cash;
+use crate::exchanges::Exchange;
+use crate::time::{DateTime, Period};
+
+use super::BrokerStatement;
+use super::partial::PartialBrokerStatement;
+use super::trades::{StockBuy, StockSell};
+
+/// FIFO processing benchmark (see benches/fifo.rs) on a synthetic trade history
+pub struct FifoBenchmark(BrokerStatement);
+
+impl FifoBenchmark {
+    /// Generates the specified number of trades evenly distributed between the specified number of stocks: each stock
+    /// is bought three times by 10 shares and then 25 shares are sold, so sells consume both full and partial lots.
+    pub fn new(trades: usize, symbols: usize) -> FifoBenchmark {
+        let broker = Broker::InteractiveBrokers.get_info(&Config::mock(), None).unwrap();
+        let start_time: DateTime = date!(2000, 1, 1).and_hms_opt(0, 0, 0).unwrap();
+        let end_time = start_time + Duration::minutes(trades as i64);
+
+        let mut partial = PartialBrokerStatement::new(&[Exchange::Us], true);
+        partial.set_period(Period::new(start_time.date(), end_time.date()).unwrap()).unwrap();
+        partial.set_has_starting_assets(false).unwrap();
+
+        let mut statement = BrokerStatement::new_empty_from(broker, &partial).unwrap();
+        let price = Cash::new("USD", dec!(100));
+        let commission = Cash::new("USD", dec!(1));
+
+        for index in 0..trades {
+            let symbol = format!("STOCK{}", index % symbols);
+            let conclusion_time = start_time + Duration::minutes(index as i64);
+
+            if (index / symbols) % 4 == 3 {
+                let quantity = dec!(25);
+                statement.stock_sells.push(StockSell::new_trade(
+                    &symbol, quantity, price, price * quantity, commission,
+                    conclusion_time.into(), conclusion_time.date(), false));
+                *statement.open_positions.get_mut(&symbol).unwrap() -= quantity;
+            } else {
+                let quantity = dec!(10);
+                statement.stock_buys.push(StockBuy::new_trade(
+                    &symbol, quantity, price, price * quantity, commission,
+                    conclusion_time.into(), conclusion_time.date()));
+                *statement.open_positions.entry(symbol).or_default() += quantity;
+            }
+        }
+
+        statement.open_positions.retain(|_, quantity| !quantity.is_zero());
+
+        FifoBenchmark(statement)
+    }
+
+    // Returns the processed statement to not measure its destruction time
+    pub fn run(mut self) -> BrokerStatement {
+        self.0.process_trades(None).unwrap();
+        self.0
+    }
+}
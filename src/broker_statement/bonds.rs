@@ -0,0 +1,66 @@
+use chrono::Datelike;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::Country;
+use crate::taxes::{IncomeType, TaxCalculator};
+use crate::time::Date;
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+pub struct Coupon {
+    pub date: Date,
+    pub symbol: String,
+    pub original_symbol: String,
+    pub amount: Cash,
+}
+
+impl Coupon {
+    pub fn new(date: Date, symbol: &str, amount: Cash) -> Coupon {
+        Coupon {
+            date,
+            symbol: symbol.to_owned(),
+            original_symbol: symbol.to_owned(),
+            amount,
+        }
+    }
+
+    // Coupons are taxed as interest income
+    pub fn tax(
+        &self, country: &Country, converter: &CurrencyConverter, calculator: &mut TaxCalculator,
+    ) -> GenericResult<Cash> {
+        let amount = converter.convert_to_cash_rounding(self.date, self.amount, country.currency)?;
+        Ok(calculator.tax_income(IncomeType::Interest, self.date.year(), amount, None).expected)
+    }
+}
+
+// Bond prices are quoted as a percentage of the face value and the trade volume doesn't include accumulated coupon
+// income (ACI) which is paid by the buyer to the seller. Paid ACI is an expense and received ACI is an income from
+// operations with securities, so we include it into the trade volume and use the resulting price per bond.
+pub fn get_bond_trade_volume(
+    quantity: Decimal, volume: Cash, accumulated_coupon_income: Decimal,
+) -> GenericResult<(Cash, Cash)> {
+    let accumulated_coupon_income = util::validate_named_cash(
+        "accumulated coupon income", volume.currency, accumulated_coupon_income,
+        DecimalRestrictions::PositiveOrZero)?;
+
+    let volume = volume.add(accumulated_coupon_income)?;
+    let price = volume / quantity;
+
+    Ok((price, volume))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bond_trade_volume() {
+        let (price, volume) = get_bond_trade_volume(
+            dec!(10), Cash::new("RUB", dec!(9_870)), dec!(153.4)).unwrap();
+
+        assert_eq!(volume, Cash::new("RUB", dec!(10_023.4)));
+        assert_eq!(price, Cash::new("RUB", dec!(1_002.34)));
+    }
+}
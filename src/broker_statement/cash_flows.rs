@@ -1,5 +1,6 @@
 use crate::currency::Cash;
 use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
 
 // Represents actual cash flows on account including reversal operations. Used to be able to
 // calculate cash balance for specific point of time.
@@ -12,7 +13,8 @@ pub struct CashFlow {
 pub enum CashFlowType {
     Dividend {date: Date, issuer: String},
     Tax {date: Date, issuer: String},
-    Repo {symbol: String, commission: Cash},
+    Repo {symbol: String, quantity: Decimal, commission: Cash},
+    SecuritiesLending, // Income from securities lent to the broker (taxed as interest income)
 }
 
 impl CashFlow {
@@ -25,6 +27,7 @@ impl CashFlow {
             CashFlowType::Dividend {issuer, ..} => issuer,
             CashFlowType::Tax {issuer, ..} => issuer,
             CashFlowType::Repo {symbol, ..} => symbol,
+            CashFlowType::SecuritiesLending => return None,
         })
     }
 
@@ -33,6 +36,7 @@ impl CashFlow {
             CashFlowType::Dividend {issuer, ..} => issuer,
             CashFlowType::Tax {issuer, ..} => issuer,
             CashFlowType::Repo {symbol, ..} => symbol,
+            CashFlowType::SecuritiesLending => return None,
         })
     }
 
@@ -40,7 +44,7 @@ impl CashFlow {
         (self.date, self.symbol(), match self.type_ {
             CashFlowType::Dividend {date, ..} => Some(date),
             CashFlowType::Tax {date, ..} => Some(date),
-            CashFlowType::Repo {..} => None,
+            CashFlowType::Repo {..} | CashFlowType::SecuritiesLending => None,
         })
     }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use std::cmp::Ordering;
 use std::collections::{HashMap, BTreeMap, hash_map, btree_map};
+use std::fmt;
 use std::ops::Bound;
+use std::str::FromStr;
 
 use lazy_static::lazy_static;
 use log::debug;
@@ -11,11 +13,12 @@ use serde::de::{Deserializer, Error};
 
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
+use crate::exchanges::Exchanges;
 use crate::formatting::format_date;
 use crate::localities::Jurisdiction;
 use crate::time::{Date, DateTime, DateOptTime, deserialize_date_opt_time};
 use crate::types::Decimal;
-use crate::util;
+use crate::util::{self, DecimalRestrictions};
 
 use super::BrokerStatement;
 use super::trades::{StockBuy, StockSell, StockSellSource, PurchaseTotalCost};
@@ -50,6 +53,14 @@ impl CorporateAction {
 #[cfg_attr(test, derive(PartialEq))]
 #[serde(tag = "type", rename_all="kebab-case")]
 pub enum CorporateActionType {
+    // Partial redemption of bond face value. It's considered to be a return of a part of the purchase cost, so it
+    // reduces cost basis of the open position which is accounted on its sell or redemption at maturity.
+    #[serde(skip)]
+    Amortization {
+        amount: Decimal,
+        currency: String,
+    },
+
     // See https://github.com/KonishchevDmitry/investments/issues/73 for details
     //
     // Intended for events similar to delisting of FinEx FXRB fund which lost all its assets and has been closed
@@ -63,6 +74,15 @@ pub enum CorporateActionType {
         quantity: Decimal,
     },
 
+    // Dividend reinvestment (DRIP): the paid dividend is immediately spent on buying fractional shares of the issuer.
+    // The dividend itself is reported by the broker as an ordinary one, so here we only emulate the buy to account the
+    // position cost basis.
+    DividendReinvestment {
+        quantity: Decimal,
+        price: Decimal,
+        currency: String,
+    },
+
     #[serde(skip)]
     Liquidation {
         quantity: Decimal,
@@ -71,6 +91,23 @@ pub enum CorporateActionType {
         currency: String,
     },
 
+    // Cash-and-stock merger: the position is converted into shares of another company plus a cash payment.
+    //
+    // The position cost basis is allocated between the cash and the new shares proportionally to their value (price is
+    // the market price of a new share at the merger date), so the cash part is taxed as an ordinary sell of the
+    // corresponding part of the position while the rest of the cost basis is carried over to the new shares.
+    //
+    // Stock-only (zero cash) and cash-only (zero new quantity) mergers don't require the price.
+    Merger {
+        quantity: Decimal,
+        new_symbol: String,
+        new_quantity: Decimal,
+        cash: Decimal,
+        #[serde(default)]
+        price: Option<Decimal>,
+        currency: String,
+    },
+
     // See https://github.com/KonishchevDmitry/investments/issues/29 for details
     Rename {
         new_symbol: String,
@@ -119,15 +156,15 @@ impl StockSplitRatio {
     }
 }
 
-impl<'de> Deserialize<'de> for StockSplitRatio {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
-        let ratio: String = Deserialize::deserialize(deserializer)?;
+impl FromStr for StockSplitRatio {
+    type Err = String;
 
+    fn from_str(ratio: &str) -> Result<Self, Self::Err> {
         lazy_static! {
             static ref REGEX: Regex = Regex::new(r"^(?P<to>[1-9]\d*):(?P<from>[1-9]\d*)$").unwrap();
         }
 
-        REGEX.captures(&ratio).and_then(|captures| {
+        REGEX.captures(ratio).and_then(|captures| {
             let from = captures.name("from").unwrap().as_str().parse::<u32>().ok();
             let to = captures.name("to").unwrap().as_str().parse::<u32>().ok();
 
@@ -135,7 +172,20 @@ impl<'de> Deserialize<'de> for StockSplitRatio {
                 (Some(from), Some(to)) => Some(StockSplitRatio::new(from, to)),
                 _ => None,
             }
-        }).ok_or_else(|| D::Error::custom(format!("Invalid stock split ratio: {:?}", ratio)))
+        }).ok_or_else(|| format!("Invalid stock split ratio: {:?}", ratio))
+    }
+}
+
+impl fmt::Display for StockSplitRatio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.to, self.from)
+    }
+}
+
+impl<'de> Deserialize<'de> for StockSplitRatio {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let ratio: String = Deserialize::deserialize(deserializer)?;
+        ratio.parse().map_err(D::Error::custom)
     }
 }
 
@@ -227,12 +277,44 @@ pub fn process_corporate_actions(statement: &mut BrokerStatement) -> EmptyResult
 
 fn process_corporate_action(statement: &mut BrokerStatement, action: CorporateAction) -> EmptyResult {
     match action.action {
+        CorporateActionType::Amortization {..} => {
+            process_amortization(statement, &action).map_err(|e| format!(
+                "Failed to process {} amortization from {}: {}",
+                action.symbol, format_date(action.time), e))?;
+        },
+
         CorporateActionType::Delisting {quantity} => {
             statement.stock_sells.push(StockSell::new_corporate_action(
                 &action.symbol, quantity, action.time, action.execution_date()));
             statement.sort_and_validate_stock_sells()?;
         },
 
+        CorporateActionType::DividendReinvestment {quantity, price, ref currency} => {
+            let quantity = util::validate_named_decimal(
+                "dividend reinvestment quantity", quantity, DecimalRestrictions::StrictlyPositive)?;
+            let price = util::validate_named_cash(
+                "dividend reinvestment price", currency, price, DecimalRestrictions::StrictlyPositive)?;
+
+            // Some brokers (Firstrade for example) report reinvestment as an ordinary buy, so the action would
+            // duplicate it
+            if statement.stock_buys.iter().any(|trade| {
+                trade.symbol == action.symbol && trade.quantity == quantity &&
+                    trade.conclusion_time.date == action.time.date
+            }) {
+                return Err!(
+                    "{} dividend reinvestment from {} is already reported by the broker as a buy trade",
+                    action.symbol, format_date(action.time));
+            }
+
+            let volume = (price * quantity).round();
+            let commission = Cash::zero(currency);
+
+            statement.stock_buys.push(StockBuy::new_trade(
+                &action.symbol, quantity, price, volume, commission,
+                action.time, action.execution_date()));
+            statement.sort_and_validate_stock_buys()?;
+        },
+
         CorporateActionType::Liquidation {quantity, price, volume, ref currency} => {
             let price = Cash::new(currency, price);
             let volume = Cash::new(currency, volume);
@@ -244,6 +326,12 @@ fn process_corporate_action(statement: &mut BrokerStatement, action: CorporateAc
             statement.sort_and_validate_stock_sells()?;
         },
 
+        CorporateActionType::Merger {ref new_symbol, ..} => {
+            process_merger(statement, &action).map_err(|e| format!(
+                "Failed to process {} -> {} merger from {}: {}",
+                action.symbol, new_symbol, format_date(action.time), e))?;
+        },
+
         CorporateActionType::Rename {ref new_symbol} => {
             statement.rename_symbol(&action.symbol, new_symbol, Some(action.time), true).map_err(|e| format!(
                 "Failed to process {} -> {} rename corporate action: {}",
@@ -350,7 +438,133 @@ fn process_complex_stock_split(
     // beginning of the list to be sure that they will be placed before any corporate action related
     // trades issued by broker after list sorting.
 
-    let (sell, buy) = convert_stocks(symbol, quantity, new_quantity, split_time, sell_sources);
+    let (sell, buy) = convert_stocks(symbol, quantity, symbol, new_quantity, split_time, sell_sources);
+
+    statement.stock_sells.insert(0, sell);
+    statement.sort_and_validate_stock_sells()?;
+
+    statement.stock_buys.insert(0, buy);
+    statement.sort_and_validate_stock_buys()?;
+
+    Ok(())
+}
+
+fn process_merger(statement: &mut BrokerStatement, action: &CorporateAction) -> EmptyResult {
+    let CorporateActionType::Merger {
+        quantity, ref new_symbol, new_quantity, cash, price, ref currency,
+    } = action.action else {
+        unreachable!();
+    };
+    let (symbol, time) = (action.symbol.as_str(), action.time);
+
+    if *new_symbol == symbol {
+        return Err!("The new symbol must differ from the old one");
+    }
+
+    let quantity = util::validate_named_decimal(
+        "merger quantity", quantity, DecimalRestrictions::StrictlyPositive)?;
+    let new_quantity = util::validate_named_decimal(
+        "merger new quantity", new_quantity, DecimalRestrictions::PositiveOrZero)?;
+    let cash = util::validate_named_decimal(
+        "merger cash", cash, DecimalRestrictions::PositiveOrZero)?;
+    if new_quantity.is_zero() && cash.is_zero() {
+        return Err!("Either new quantity or cash must be specified");
+    }
+
+    statement.process_trades(Some(time))?;
+
+    let position = get_open_position(statement, symbol, time)?;
+    if position != quantity {
+        return Err!(
+            "The portfolio has {} {} shares at {} while the merger is declared for {} shares",
+            position, symbol, format_date(time), quantity);
+    }
+
+    // Quantity of the old shares which are considered to be sold for cash
+    let mut cash_quantity = if cash.is_zero() {
+        dec!(0)
+    } else if new_quantity.is_zero() {
+        quantity
+    } else {
+        let price = price.ok_or("New shares price must be specified for cash-and-stock merger")?;
+        let price = util::validate_named_decimal(
+            "merger new shares price", price, DecimalRestrictions::StrictlyPositive)?;
+        util::round(quantity * cash / (cash + price * new_quantity), 8)
+    };
+
+    let mut cash_sources = Vec::new();
+    let mut stock_sources = Vec::new();
+
+    // Stock buys are sorted by conclusion time, so the cash part is taken in FIFO order like in ordinary sells
+    for stock_buy in &mut statement.stock_buys {
+        if stock_buy.symbol != symbol || stock_buy.is_sold() || stock_buy.conclusion_time >= time {
+            continue;
+        }
+
+        let multiplier = statement.stock_splits.get_multiplier(
+            symbol, stock_buy.conclusion_time, time);
+
+        let cash_part = if new_quantity.is_zero() {
+            stock_buy.get_unsold()
+        } else {
+            std::cmp::min(stock_buy.get_unsold(), util::round(cash_quantity / multiplier, 8))
+        };
+        if cash_part > dec!(0) {
+            let source = stock_buy.sell(cash_part, multiplier);
+            cash_quantity -= source.quantity * source.multiplier;
+            cash_sources.push(source);
+        }
+
+        if !stock_buy.is_sold() {
+            stock_sources.push(stock_buy.sell(stock_buy.get_unsold(), multiplier));
+        }
+    }
+
+    if new_quantity.is_zero() {
+        assert!(stock_sources.is_empty());
+    } else if stock_sources.is_empty() {
+        return Err!("The whole position is considered to be sold for cash, which is unexpected");
+    }
+
+    let get_quantity = |sources: &[StockSellSource]| -> Decimal {
+        sources.iter().map(|source| source.quantity * source.multiplier).sum()
+    };
+
+    if !cash_sources.is_empty() {
+        let cash_quantity = get_quantity(&cash_sources);
+        let price = Cash::new(currency, util::round(cash / cash_quantity, 6));
+        let volume = Cash::new(currency, cash);
+        let commission = Cash::zero(currency);
+
+        let mut sell = StockSell::new_trade(
+            symbol, cash_quantity, price, volume, commission,
+            time, action.execution_date(), false);
+        sell.process(cash_sources);
+
+        statement.stock_sells.push(sell);
+    }
+
+    let stock_quantity = get_quantity(&stock_sources);
+    debug!("{} -> {} merger from {}: {} -> {} shares, {} shares are sold for {}.",
+        symbol, new_symbol, format_date(time.date), stock_quantity, new_quantity, quantity - stock_quantity,
+        Cash::new(currency, cash));
+
+    if stock_sources.is_empty() {
+        statement.sort_and_validate_stock_sells()?;
+        return Ok(());
+    }
+
+    // The new shares may be not mentioned in the broker statement until the next trades with them, so register them
+    // to make them known to securities reference and trading mode logic.
+    let exchanges = statement.instrument_info.get(symbol).map(|instrument| instrument.exchanges.get_prioritized());
+    let new_instrument = statement.instrument_info.get_or_add(new_symbol);
+    if let Some(exchanges) = exchanges {
+        if new_instrument.exchanges.is_empty() {
+            new_instrument.exchanges = Exchanges::new(&exchanges);
+        }
+    }
+
+    let (sell, buy) = convert_stocks(symbol, stock_quantity, new_symbol, new_quantity, time, stock_sources);
 
     statement.stock_sells.insert(0, sell);
     statement.sort_and_validate_stock_sells()?;
@@ -361,6 +575,49 @@ fn process_complex_stock_split(
     Ok(())
 }
 
+fn process_amortization(statement: &mut BrokerStatement, action: &CorporateAction) -> EmptyResult {
+    let CorporateActionType::Amortization {amount, ref currency} = action.action else {
+        unreachable!();
+    };
+    let (symbol, time) = (action.symbol.as_str(), action.time);
+
+    let amount = util::validate_named_cash(
+        "amortization amount", currency, amount, DecimalRestrictions::StrictlyPositive)?;
+
+    statement.process_trades(Some(time))?;
+    let position = get_open_position(statement, symbol, time)?;
+
+    // The amount is distributed between the open lots proportionally to their quantity
+    for stock_buy in &mut statement.stock_buys {
+        if stock_buy.symbol != symbol || stock_buy.is_sold() || stock_buy.conclusion_time >= time {
+            continue;
+        }
+
+        let multiplier = statement.stock_splits.get_multiplier(
+            symbol, stock_buy.conclusion_time, time);
+
+        stock_buy.reduce_cost(time.date, amount * (stock_buy.get_unsold() * multiplier / position));
+    }
+
+    debug!("{} amortization from {}: {} for {} bonds.", symbol, format_date(time.date), amount, position);
+    Ok(())
+}
+
+fn get_open_position(statement: &BrokerStatement, symbol: &str, time: DateOptTime) -> GenericResult<Decimal> {
+    let position: Decimal = statement.stock_buys.iter()
+        .filter(|stock_buy| stock_buy.symbol == symbol && !stock_buy.is_sold() && stock_buy.conclusion_time < time)
+        .map(|stock_buy| {
+            stock_buy.get_unsold() * statement.stock_splits.get_multiplier(symbol, stock_buy.conclusion_time, time)
+        })
+        .sum();
+
+    if position.is_zero() {
+        return Err!("The portfolio has no open {} position at {}", symbol, format_date(time));
+    }
+
+    Ok(position)
+}
+
 fn calculate_stock_split(
     quantity: Decimal, ratio: StockSplitRatio,
     from_change: Option<Decimal>, to_change: Option<Decimal>,
@@ -414,7 +671,7 @@ fn get_stock_split_lots(quantity: Decimal, ratio: StockSplitRatio) -> Option<u32
 }
 
 fn convert_stocks(
-    symbol: &str, old_quantity: Decimal, new_quantity: Decimal,
+    symbol: &str, old_quantity: Decimal, new_symbol: &str, new_quantity: Decimal,
     conclusion_time: DateOptTime, sell_sources: Vec<StockSellSource>,
 ) -> (StockSell, StockBuy) {
     let mut cost = PurchaseTotalCost::new();
@@ -427,7 +684,156 @@ fn convert_stocks(
     sell.process(sell_sources);
 
     let buy = StockBuy::new_corporate_action(
-        symbol, new_quantity, cost, conclusion_time, conclusion_time.date);
+        new_symbol, new_quantity, cost, conclusion_time, conclusion_time.date);
 
     (sell, buy)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::brokers::Broker;
+    use crate::config::Config;
+    use crate::exchanges::Exchange;
+    use crate::time::Period;
+    use super::super::partial::PartialBrokerStatement;
+    use super::super::trades::StockSource;
+    use super::*;
+
+    fn statement(lots: &[(Date, Decimal)]) -> BrokerStatement {
+        let broker = Broker::InteractiveBrokers.get_info(&Config::mock(), None).unwrap();
+
+        let mut partial = PartialBrokerStatement::new(&[Exchange::Us], true);
+        partial.set_period(Period::new(date!(2020, 1, 1), date!(2020, 12, 31)).unwrap()).unwrap();
+        partial.set_has_starting_assets(false).unwrap();
+
+        let mut statement = BrokerStatement::new_empty_from(broker, &partial).unwrap();
+        for &(date, quantity) in lots {
+            let price = Cash::new("USD", dec!(10));
+            statement.stock_buys.push(StockBuy::new_trade(
+                "OLD", quantity, price, price * quantity, Cash::zero("USD"), date.into(), date));
+        }
+
+        statement
+    }
+
+    fn merge(statement: &mut BrokerStatement, new_quantity: Decimal, cash: Decimal) -> EmptyResult {
+        process_corporate_action(statement, CorporateAction {
+            time: date!(2020, 6, 1).into(),
+            report_date: None,
+            symbol: s!("OLD"),
+            action: CorporateActionType::Merger {
+                quantity: dec!(10),
+                new_symbol: s!("NEW"),
+                new_quantity, cash,
+                price: (!cash.is_zero() && !new_quantity.is_zero()).then_some(dec!(50)),
+                currency: s!("USD"),
+            },
+        })
+    }
+
+    // Returns sells as (symbol, quantity, (lot date, quantity with split multiplier) of each source)
+    fn sells(statement: &BrokerStatement) -> Vec<(&str, Decimal, Vec<(Date, Decimal)>)> {
+        statement.stock_sells.iter().map(|sell| {
+            let sources = sell.sources().iter().map(|source| {
+                (source.conclusion_time.date, source.quantity * source.multiplier)
+            }).collect();
+            (sell.symbol.as_str(), sell.quantity, sources)
+        }).collect()
+    }
+
+    fn buys(statement: &BrokerStatement, symbol: &str) -> Vec<Decimal> {
+        statement.stock_buys.iter()
+            .filter(|buy| buy.symbol == symbol)
+            .map(|buy| buy.quantity)
+            .collect()
+    }
+
+    fn reinvest(statement: &mut BrokerStatement) -> EmptyResult {
+        process_corporate_action(statement, CorporateAction {
+            time: date!(2020, 3, 2).into(),
+            report_date: None,
+            symbol: s!("OLD"),
+            action: CorporateActionType::DividendReinvestment {
+                quantity: dec!(0.123),
+                price: dec!(12.34),
+                currency: s!("USD"),
+            },
+        })
+    }
+
+    #[test]
+    fn dividend_reinvestment() {
+        let mut statement = statement(&[(date!(2020, 1, 10), dec!(4)), (date!(2020, 4, 10), dec!(6))]);
+        reinvest(&mut statement).unwrap();
+
+        let buys: Vec<_> = statement.stock_buys.iter().map(|trade| {
+            let StockSource::Trade {price, volume, commission} = trade.type_ else {
+                unreachable!();
+            };
+            (trade.conclusion_time.date, trade.quantity, price.amount, volume.amount, commission.amount)
+        }).collect();
+
+        assert_eq!(buys, vec![
+            (date!(2020, 1, 10), dec!(4),     dec!(10),    dec!(40),   dec!(0)),
+            (date!(2020, 3,  2), dec!(0.123), dec!(12.34), dec!(1.52), dec!(0)),
+            (date!(2020, 4, 10), dec!(6),     dec!(10),    dec!(60),   dec!(0)),
+        ]);
+    }
+
+    #[test]
+    fn duplicated_dividend_reinvestment() {
+        let mut statement = statement(&[(date!(2020, 1, 10), dec!(4)), (date!(2020, 3, 2), dec!(0.123))]);
+        assert_eq!(
+            reinvest(&mut statement).unwrap_err().to_string(),
+            "OLD dividend reinvestment from 02.03.2020 is already reported by the broker as a buy trade");
+    }
+
+    #[test]
+    fn cash_and_stock_merger() {
+        let mut statement = statement(&[(date!(2020, 1, 10), dec!(4)), (date!(2020, 2, 10), dec!(6))]);
+        merge(&mut statement, dec!(10), dec!(500)).unwrap();
+
+        // 500 cash / (500 cash + 10 * 50 new shares value) = 0.5 of the position is sold for cash
+        assert_eq!(sells(&statement), vec![
+            ("OLD", dec!(5), vec![(date!(2020, 2, 10), dec!(5))]),
+            ("OLD", dec!(5), vec![(date!(2020, 1, 10), dec!(4)), (date!(2020, 2, 10), dec!(1))]),
+        ]);
+        assert_eq!(buys(&statement, "NEW"), vec![dec!(10)]);
+        assert!(statement.instrument_info.get("NEW").is_some());
+    }
+
+    #[test]
+    fn stock_merger() {
+        let mut statement = statement(&[(date!(2020, 1, 10), dec!(4)), (date!(2020, 2, 10), dec!(6))]);
+        merge(&mut statement, dec!(15), dec!(0)).unwrap();
+
+        assert_eq!(sells(&statement), vec![
+            ("OLD", dec!(10), vec![(date!(2020, 1, 10), dec!(4)), (date!(2020, 2, 10), dec!(6))]),
+        ]);
+        assert_eq!(buys(&statement, "NEW"), vec![dec!(15)]);
+    }
+
+    #[test]
+    fn cash_merger() {
+        let mut statement = statement(&[(date!(2020, 1, 10), dec!(4)), (date!(2020, 2, 10), dec!(6))]);
+        merge(&mut statement, dec!(0), dec!(1000)).unwrap();
+
+        assert_eq!(sells(&statement), vec![
+            ("OLD", dec!(10), vec![(date!(2020, 1, 10), dec!(4)), (date!(2020, 2, 10), dec!(6))]),
+        ]);
+        assert!(buys(&statement, "NEW").is_empty());
+        assert!(statement.instrument_info.get("NEW").is_none());
+    }
+
+    #[test]
+    fn merger_after_stock_split() {
+        let mut statement = statement(&[(date!(2020, 1, 10), dec!(3)), (date!(2020, 4, 10), dec!(4))]);
+        statement.stock_splits.add(date!(2020, 3, 1).into(), "OLD", 2).unwrap().unwrap();
+        merge(&mut statement, dec!(10), dec!(500)).unwrap();
+
+        assert_eq!(sells(&statement), vec![
+            ("OLD", dec!(5), vec![(date!(2020, 1, 10), dec!(1)), (date!(2020, 4, 10), dec!(4))]),
+            ("OLD", dec!(5), vec![(date!(2020, 1, 10), dec!(5))]),
+        ]);
+        assert_eq!(buys(&statement, "NEW"), vec![dec!(10)]);
+    }
+}
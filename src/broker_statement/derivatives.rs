@@ -0,0 +1,239 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::Datelike;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting;
+use crate::time::DateOptTime;
+use crate::types::{Date, Decimal};
+
+use super::trades::{DerivativeTrade, DerivativeType};
+
+// Realized result of closing derivative contracts
+pub struct DerivativeIncome {
+    pub type_: DerivativeType,
+    pub symbol: String,
+    pub quantity: Decimal, // Always positive
+    pub currency: &'static str,
+    pub conclusion_time: DateOptTime,
+    pub execution_date: Date,
+
+    // Premiums / notional values and commissions of the opening and closing trades
+    volumes: Vec<(Date, Cash)>,
+    commissions: Vec<(Date, Cash)>,
+}
+
+impl DerivativeIncome {
+    // Futures position result excluding commissions (accumulated variation margin)
+    pub fn trading_result(&self) -> Cash {
+        let mut result = Cash::zero(self.currency);
+        for &(_, amount) in &self.volumes {
+            result.amount += amount.amount;
+        }
+        result
+    }
+
+    // Futures variation margin is taxed in the year it's accrued, but we don't track daily variation margin and
+    // realize the whole result at position close, which is correct only for positions opened in the same year.
+    pub fn held_over_year_end(&self) -> bool {
+        let year = self.execution_date.year();
+        self.volumes.iter().any(|(date, _)| date.year() != year)
+    }
+
+    pub fn profit(&self, currency: &str, converter: &CurrencyConverter) -> GenericResult<Cash> {
+        let mut profit = Cash::zero(currency);
+
+        for &(date, amount) in self.volumes.iter().chain(&self.commissions) {
+            profit.amount += converter.convert_to_rounding(date, amount, currency)?;
+        }
+
+        Ok(profit)
+    }
+}
+
+struct OpenContracts {
+    quantity: Decimal, // Signed quantity of still open contracts
+    trade_quantity: Decimal,
+    volume: Cash,
+    commission: Cash,
+    conclusion_date: Date,
+    execution_date: Date,
+}
+
+impl OpenContracts {
+    fn close(&mut self, quantity: Decimal, income: &mut DerivativeIncome) {
+        let fraction = quantity / self.trade_quantity.abs();
+        income.volumes.push((self.execution_date, self.volume * fraction));
+        income.commissions.push((self.conclusion_date, -self.commission * fraction));
+        if self.quantity.is_sign_positive() {
+            self.quantity -= quantity;
+        } else {
+            self.quantity += quantity;
+        }
+    }
+}
+
+// Matches derivative trades (which must be sorted by conclusion time) in FIFO order. Returns realized income and
+// remaining open positions.
+//
+// Option exercises, assignments and expirations are reported by brokers as trades with zero price which close the
+// position, so they are handled as ordinary closing trades: the premium is realized and the underlying stock trade
+// (if any) is reported separately.
+pub fn process_derivative_trades(
+    trades: &[DerivativeTrade],
+) -> GenericResult<(Vec<DerivativeIncome>, HashMap<String, Decimal>)> {
+    let mut incomes = Vec::new();
+    let mut open_positions: HashMap<&str, VecDeque<OpenContracts>> = HashMap::new();
+
+    for trade in trades {
+        let symbol_positions = open_positions.entry(&trade.symbol).or_default();
+        if let Some(position) = symbol_positions.front() {
+            if position.volume.currency != trade.volume.currency {
+                return Err!(
+                    "Got {} trade from {} in {} while the position is opened in {}",
+                    trade.symbol, formatting::format_date(trade.conclusion_time),
+                    trade.volume.currency, position.volume.currency);
+            }
+        }
+
+        let mut remaining = trade.quantity.abs();
+        let mut lot = OpenContracts {
+            quantity: trade.quantity,
+            trade_quantity: trade.quantity,
+            volume: trade.volume,
+            commission: trade.commission,
+            conclusion_date: trade.conclusion_time.date,
+            execution_date: trade.execution_date,
+        };
+        let mut income = DerivativeIncome {
+            type_: trade.type_,
+            symbol: trade.symbol.clone(),
+            quantity: dec!(0),
+            currency: trade.volume.currency,
+            conclusion_time: trade.conclusion_time,
+            execution_date: trade.execution_date,
+            volumes: Vec::new(),
+            commissions: Vec::new(),
+        };
+
+        while !remaining.is_zero() {
+            let Some(position) = symbol_positions.front_mut() else {
+                break;
+            };
+
+            // Position of the same direction - the trade increases it
+            if position.quantity.is_sign_positive() == trade.quantity.is_sign_positive() {
+                break;
+            }
+
+            let quantity = std::cmp::min(remaining, position.quantity.abs());
+            position.close(quantity, &mut income);
+            lot.close(quantity, &mut income);
+            income.quantity += quantity;
+            remaining -= quantity;
+
+            if position.quantity.is_zero() {
+                symbol_positions.pop_front();
+            }
+        }
+
+        if !income.quantity.is_zero() {
+            incomes.push(income);
+        }
+
+        if !remaining.is_zero() {
+            symbol_positions.push_back(lot);
+        }
+    }
+
+    let open_positions = open_positions.into_iter().filter_map(|(symbol, positions)| {
+        let quantity: Decimal = positions.iter().map(|position| position.quantity).sum();
+        (!quantity.is_zero()).then(|| (symbol.to_owned(), quantity))
+    }).collect();
+
+    Ok((incomes, open_positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(day: u32, quantity: Decimal, volume: Decimal) -> DerivativeTrade {
+        let date = date!(2024, 1, day);
+        DerivativeTrade::new(
+            DerivativeType::Option, "AAPL 19JAN24 200 C", quantity, Cash::new("USD", (volume / quantity).abs()),
+            Cash::new("USD", volume), Cash::new("USD", dec!(1)), date.into(), date)
+    }
+
+    fn profit(income: &DerivativeIncome) -> Decimal {
+        income.volumes.iter().chain(&income.commissions).map(|(_, amount)| amount.amount).sum()
+    }
+
+    #[test]
+    fn long_position() {
+        let trades = [
+            trade(2, dec!(2), dec!(-200)),
+            trade(3, dec!(1), dec!(-300)),
+            trade(4, dec!(-2), dec!(500)),
+        ];
+
+        let (incomes, open_positions) = process_derivative_trades(&trades).unwrap();
+        assert_eq!(incomes.len(), 1);
+        assert_eq!(incomes[0].quantity, dec!(2));
+        assert_eq!(profit(&incomes[0]), dec!(500) - dec!(200) - dec!(2));
+        assert_eq!(open_positions, hashmap!{s!("AAPL 19JAN24 200 C") => dec!(1)});
+    }
+
+    #[test]
+    fn written_option_expiration() {
+        let trades = [
+            trade(2, dec!(-4), dec!(600)),
+            trade(3, dec!(2), dec!(-100)),
+            // Expiration
+            DerivativeTrade::new(
+                DerivativeType::Option, "AAPL 19JAN24 200 C", dec!(2), Cash::zero("USD"), Cash::zero("USD"),
+                Cash::zero("USD"), date!(2024, 1, 19).into(), date!(2024, 1, 19)),
+        ];
+
+        let (incomes, open_positions) = process_derivative_trades(&trades).unwrap();
+        assert_eq!(incomes.iter().map(profit).collect::<Vec<_>>(), vec![
+            dec!(300) - dec!(100) - dec!(0.5) - dec!(1),
+            dec!(300) - dec!(0.5),
+        ]);
+        assert!(open_positions.is_empty());
+    }
+
+    #[test]
+    fn futures_held_over_year_end() {
+        let futures = |date: Date, quantity: Decimal, volume: Decimal| DerivativeTrade::new(
+            DerivativeType::Futures, "ESH5", quantity, Cash::new("USD", (volume / quantity).abs()),
+            Cash::new("USD", volume), Cash::new("USD", dec!(1)), date.into(), date);
+
+        let trades = [
+            futures(date!(2024, 12, 2), dec!(1), dec!(-5000)),
+            futures(date!(2024, 12, 3), dec!(1), dec!(-5000)),
+            futures(date!(2024, 12, 4), dec!(-1), dec!(5100)),
+            futures(date!(2025, 1, 2), dec!(-1), dec!(5200)),
+        ];
+
+        let (incomes, open_positions) = process_derivative_trades(&trades).unwrap();
+        assert_eq!(incomes.iter().map(DerivativeIncome::held_over_year_end).collect::<Vec<_>>(), vec![false, true]);
+        assert!(open_positions.is_empty());
+    }
+
+    #[test]
+    fn position_reversal() {
+        let trades = [
+            trade(2, dec!(1), dec!(-100)),
+            trade(3, dec!(-2), dec!(300)),
+        ];
+
+        let (incomes, open_positions) = process_derivative_trades(&trades).unwrap();
+        assert_eq!(incomes.len(), 1);
+        assert_eq!(incomes[0].quantity, dec!(1));
+        assert_eq!(profit(&incomes[0]), dec!(150) - dec!(100) - dec!(1) - dec!(0.5));
+        assert_eq!(open_positions, hashmap!{s!("AAPL 19JAN24 200 C") => dec!(-1)});
+    }
+}
@@ -0,0 +1,183 @@
+// Cross-check of the parsed dividends against public dividend history to catch statement parsing errors and missing
+// accruals
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use chrono::Duration;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::exchanges::Exchange;
+use crate::formatting;
+use crate::time::Date;
+use crate::types::Decimal;
+
+use super::BrokerStatement;
+
+// Issuer must pay the dividends to the nominal holders within 10 working days and to the other shareholders within 25
+// working days after record date. Brokers pay them out a few days after receiving, so use some margin.
+const PAYMENT_DAYS: i64 = 45;
+
+// Maximum allowed relative difference between expected and actual dividend amount (brokers may round the amount)
+const TOLERANCE: Decimal = dec!(0.005);
+
+/// Source of public dividend history: per share dividends by record date
+pub trait DividendHistory {
+    fn get_dividends(&self, symbol: &str) -> GenericResult<Vec<(Date, Cash)>>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DividendDiscrepancy {
+    Missing {symbol: String, record_date: Date, expected: Cash},
+    Mismatch {symbol: String, date: Date, expected: Cash, actual: Cash},
+    Unexpected {symbol: String, date: Date, actual: Cash},
+}
+
+impl fmt::Display for DividendDiscrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DividendDiscrepancy::Missing {symbol, record_date, expected} => write!(
+                f, "{symbol} dividend with {} record date ({expected} expected) is missing in the broker statement",
+                formatting::format_date(*record_date)),
+            DividendDiscrepancy::Mismatch {symbol, date, expected, actual} => write!(
+                f, "{symbol} dividend from {} is {actual} while {expected} is expected according to public data",
+                formatting::format_date(*date)),
+            DividendDiscrepancy::Unexpected {symbol, date, actual} => write!(
+                f, "{symbol} dividend from {} ({actual}) is missing in public dividend history",
+                formatting::format_date(*date)),
+        }
+    }
+}
+
+impl BrokerStatement {
+    /// Compares dividends of the instruments traded on Moscow Exchange with the public dividend history
+    pub fn validate_dividends(&self, history: &dyn DividendHistory) -> GenericResult<Vec<DividendDiscrepancy>> {
+        let mut symbols = BTreeSet::new();
+        symbols.extend(self.stock_buys.iter().map(|trade| trade.symbol.as_str()));
+        symbols.extend(self.dividends.iter().map(|dividend| dividend.issuer.as_str()));
+
+        let mut discrepancies = Vec::new();
+
+        for symbol in symbols {
+            if !self.get_instrument_supposed_exchanges(symbol).get_prioritized().contains(&Exchange::Moex) {
+                continue;
+            }
+
+            let public = history.get_dividends(symbol)?;
+
+            let received: Vec<(Date, Cash)> = self.dividends.iter()
+                .filter(|dividend| dividend.issuer == symbol)
+                .map(|dividend| (dividend.date, dividend.amount))
+                .collect();
+
+            let position = |date: Date| -> Decimal {
+                let bought: Decimal = self.stock_buys.iter()
+                    .filter(|trade| trade.symbol == symbol && trade.execution_date <= date)
+                    .map(|trade| trade.quantity)
+                    .sum();
+
+                let sold: Decimal = self.stock_sells.iter()
+                    .filter(|trade| trade.symbol == symbol && trade.execution_date <= date)
+                    .map(|trade| trade.quantity)
+                    .sum();
+
+                bought - sold
+            };
+
+            discrepancies.extend(compare(
+                symbol, &public, position, &received, self.period.first_date(), self.period.last_date()));
+        }
+
+        Ok(discrepancies)
+    }
+}
+
+fn compare<P: Fn(Date) -> Decimal>(
+    symbol: &str, public: &[(Date, Cash)], position: P, received: &[(Date, Cash)], first_date: Date, last_date: Date,
+) -> Vec<DividendDiscrepancy> {
+    let mut discrepancies = Vec::new();
+    let mut matched = vec![false; received.len()];
+
+    for &(record_date, per_share) in public {
+        // The dividend may be paid within the period even if its record date is before the period start
+        let payment_deadline = record_date + Duration::days(PAYMENT_DAYS);
+        if payment_deadline < first_date || record_date > last_date {
+            continue;
+        }
+
+        let quantity = position(record_date);
+        if quantity.is_zero() {
+            continue;
+        }
+        let expected = (per_share * quantity).round();
+
+        let index = received.iter().enumerate().position(|(index, &(date, _))| {
+            !matched[index] && date >= record_date && date <= payment_deadline
+        });
+
+        let Some(index) = index else {
+            if record_date >= first_date && payment_deadline <= last_date {
+                discrepancies.push(DividendDiscrepancy::Missing {
+                    symbol: symbol.to_owned(), record_date, expected,
+                });
+            }
+            continue;
+        };
+        matched[index] = true;
+
+        let (date, actual) = received[index];
+        let difference = (actual.amount - expected.amount).abs();
+        if actual.currency != expected.currency || difference > expected.amount * TOLERANCE {
+            discrepancies.push(DividendDiscrepancy::Mismatch {symbol: symbol.to_owned(), date, expected, actual});
+        }
+    }
+
+    for (index, &(date, actual)) in received.iter().enumerate() {
+        if !matched[index] {
+            discrepancies.push(DividendDiscrepancy::Unexpected {symbol: symbol.to_owned(), date, actual});
+        }
+    }
+
+    discrepancies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparison() {
+        let rub = |amount| Cash::new("RUB", amount);
+
+        let public = [
+            (date!(2022, 5, 10), rub(dec!(10))),   // Before the period
+            (date!(2023, 3, 1), rub(dec!(8))),     // No position
+            (date!(2023, 5, 11), rub(dec!(25))),   // Matched
+            (date!(2023, 7, 11), rub(dec!(5))),    // Mismatched
+            (date!(2023, 10, 11), rub(dec!(7))),   // Missing
+            (date!(2023, 12, 20), rub(dec!(3))),   // Not paid yet
+        ];
+        let received = [
+            (date!(2023, 5, 20), rub(dec!(249.5))),
+            (date!(2023, 7, 20), rub(dec!(40))),
+            (date!(2023, 8, 1), rub(dec!(15))),
+        ];
+        let position = |date| if date < date!(2023, 4, 1) { dec!(0) } else { dec!(10) };
+
+        assert_eq!(
+            compare("SBER", &public, position, &received, date!(2023, 1, 1), date!(2023, 12, 31)),
+            vec![
+                DividendDiscrepancy::Mismatch {
+                    symbol: s!("SBER"), date: date!(2023, 7, 20), expected: rub(dec!(50)), actual: rub(dec!(40)),
+                },
+                DividendDiscrepancy::Missing {
+                    symbol: s!("SBER"), record_date: date!(2023, 10, 11), expected: rub(dec!(70)),
+                },
+                DividendDiscrepancy::Unexpected {
+                    symbol: s!("SBER"), date: date!(2023, 8, 1), actual: rub(dec!(15)),
+                },
+            ],
+        );
+    }
+}
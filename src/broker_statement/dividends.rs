@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use chrono::Datelike;
+use chrono::{Datelike, Duration};
 
 use crate::core::GenericResult;
 use crate::currency::Cash;
@@ -63,6 +63,23 @@ impl DividendId {
     }
 }
 
+/// Dividend which is already declared (the position went ex-dividend), but not paid yet
+#[derive(Clone, Debug)]
+pub struct PendingDividend {
+    pub symbol: String,
+    pub ex_date: Date,
+    pub payment_date: Date,
+    pub amount: Cash, // Net amount (after tax withholding)
+}
+
+impl PendingDividend {
+    /// Dividends on blocked securities (for example, due to sanctions) may never be paid, so dividends which are
+    /// overdue for more than the specified number of days are considered to be at risk.
+    pub fn is_at_risk(&self, today: Date, delay_days: u32) -> bool {
+        today > self.payment_date + Duration::days(delay_days.into())
+    }
+}
+
 pub type DividendAccruals = Payments;
 
 pub fn process_dividend_accruals(
@@ -129,4 +146,25 @@ pub fn process_dividend_accruals(
     };
 
     Ok((dividend, cash_flows))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(today, at_risk,
+        case(date!(2024, 3, 1), false),
+        case(date!(2024, 3, 31), false),
+        case(date!(2024, 4, 1), true),
+    )]
+    fn pending_dividend_risk(today: Date, at_risk: bool) {
+        let dividend = PendingDividend {
+            symbol: s!("VTI"),
+            ex_date: date!(2024, 2, 20),
+            payment_date: date!(2024, 3, 1),
+            amount: Cash::new("USD", dec!(10)),
+        };
+        assert_eq!(dividend.is_at_risk(today, 30), at_risk);
+    }
+}
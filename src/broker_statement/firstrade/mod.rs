@@ -15,7 +15,7 @@ use std::io::{Read, BufReader, BufRead, Seek};
 use crate::core::GenericResult;
 #[cfg(test)] use crate::taxes::TaxRemapping;
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementSections};
 use super::{BrokerStatementReader, PartialBrokerStatement};
 
 use self::parser::{StatementParser, Ofx};
@@ -86,7 +86,7 @@ mod tests {
 
         let statement = BrokerStatement::read(
             broker, "testdata/firstrade/my", &Default::default(), &Default::default(), &Default::default(),
-            TaxRemapping::new(), &[], &[], ReadingStrictness::all()).unwrap();
+            TaxRemapping::new(), &[], &[], ReadingStrictness::all(), StatementSections::all()).unwrap();
 
         assert!(!statement.assets.cash.is_empty());
         assert!(statement.assets.other.is_none()); // TODO(konishchev): Get it from statements
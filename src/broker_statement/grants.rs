@@ -1,8 +1,13 @@
+use std::collections::HashMap;
+
+use chrono::Datelike;
 use log::warn;
 
-use crate::core::EmptyResult;
+use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
-use crate::localities::Jurisdiction;
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::{Country, Jurisdiction};
+use crate::taxes::{IncomeType, TaxCalculator};
 use crate::time::Date;
 use crate::types::Decimal;
 
@@ -24,6 +29,41 @@ impl CashGrant {
     }
 }
 
+// Cash grants (promotional bonuses and prizes) are taxed as other income, but gifts and prizes from organizations are
+// tax-exempt up to 4000 RUB per year (paragraph 28 of Article 217 of the Tax Code of the Russian Federation).
+pub struct CashGrantsTaxCalculator<'a> {
+    country: &'a Country,
+    converter: &'a CurrencyConverter,
+    exemptions: HashMap<i32, Decimal>,
+}
+
+impl<'a> CashGrantsTaxCalculator<'a> {
+    const TAX_FREE_AMOUNT: Decimal = dec!(4000);
+
+    pub fn new(country: &'a Country, converter: &'a CurrencyConverter) -> CashGrantsTaxCalculator<'a> {
+        CashGrantsTaxCalculator {country, converter, exemptions: HashMap::new()}
+    }
+
+    /// Returns grant amount in local currency and tax to pay for it. Grants must be passed in chronological order.
+    pub fn tax(&mut self, grant: &CashGrant, calculator: &mut TaxCalculator) -> GenericResult<(Cash, Cash)> {
+        let year = grant.date.year();
+        let amount = self.converter.convert_to_cash_rounding(grant.date, grant.amount, self.country.currency)?;
+
+        let mut taxable = amount;
+
+        if self.country.jurisdiction == Jurisdiction::Russia {
+            let exemption = self.exemptions.entry(year).or_insert(Self::TAX_FREE_AMOUNT);
+            let exempted = std::cmp::min(*exemption, taxable.amount);
+
+            *exemption -= exempted;
+            taxable.amount -= exempted;
+        }
+
+        let tax = calculator.tax_income(IncomeType::Other, year, taxable, None).expected;
+        Ok((amount, tax))
+    }
+}
+
 pub struct StockGrant {
     pub date: Date,
     pub symbol: String,
@@ -41,9 +81,10 @@ impl StockGrant {
 }
 
 pub fn process_grants(statement: &mut BrokerStatement, strict: bool) -> EmptyResult {
-    // For now I saw only grants from Sber which have 100% tax deduction, so we don't process any taxation for them
+    // Russian brokers are tax agents for the grants, so we process them as other income. Grants from foreign brokers
+    // should be declared in the tax statement, which is not supported yet.
     if !statement.cash_grants.is_empty() && strict && statement.broker.type_.jurisdiction() != Jurisdiction::Russia {
-        warn!("The statement contains cash grants which is not supported yet (won't be taxed).");
+        warn!("The statement contains cash grants which is not supported yet (won't be declared).");
     }
 
     if !statement.stock_grants.is_empty() {
@@ -61,4 +102,36 @@ pub fn process_grants(statement: &mut BrokerStatement, strict: bool) -> EmptyRes
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::localities;
+    use crate::taxes::TaxConfig;
+
+    use super::*;
+
+    #[rstest(grants, expected,
+        case(&[(date!(2023, 3, 1), dec!(3000))], &[dec!(0)]),
+        case(&[(date!(2023, 3, 1), dec!(3000)), (date!(2023, 5, 1), dec!(2000))], &[dec!(0), dec!(130)]),
+        case(&[(date!(2023, 3, 1), dec!(5000)), (date!(2024, 5, 1), dec!(5000))], &[dec!(130), dec!(130)]),
+    )]
+    fn cash_grants_taxation(grants: &[(Date, Decimal)], expected: &[Decimal]) {
+        let country = localities::russia(&TaxConfig::default());
+        let converter = CurrencyConverter::mock();
+
+        let mut calculator = TaxCalculator::new(country.clone());
+        let mut grants_calculator = CashGrantsTaxCalculator::new(&country, &converter);
+
+        let taxes: Vec<Decimal> = grants.iter().map(|&(date, amount)| {
+            let grant = CashGrant::new(date, Cash::new("RUB", amount), "Бонус");
+            let (local_amount, tax) = grants_calculator.tax(&grant, &mut calculator).unwrap();
+            assert_eq!(local_amount, grant.amount);
+            tax.amount
+        }).collect();
+
+        assert_eq!(taxes, expected);
+    }
+}
@@ -1,10 +1,10 @@
 use crate::core::EmptyResult;
 use crate::currency::{Cash, CashAssets};
-use crate::util::{self, DecimalRestrictions};
+use crate::util::DecimalRestrictions;
 
 use super::StatementParser;
 use super::cash_flows::CashFlowId;
-use super::common::{Record, RecordParser, parse_date};
+use super::common::{Record, RecordParser, parse_date, parse_decimal};
 
 pub struct CashReportParser {}
 
@@ -84,7 +84,7 @@ impl RecordParser for StatementOfFundsParser {
             (true, false) => (credit, DecimalRestrictions::StrictlyPositive),
             _ => return Err!("Got an unexpected debit + credit combination"),
         };
-        let amount = Cash::new(currency, util::parse_decimal(amount, restrictions)?);
+        let amount = Cash::new(currency, parse_decimal(amount, restrictions)?);
 
         let id = CashFlowId::new(statement_date, description, amount);
         parser.cash_flows.add(id, date);
@@ -8,6 +8,7 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::broker_statement::ib::StatementParser;
+use crate::brokers::Broker;
 use crate::core::{EmptyResult, GenericResult, GenericError};
 use crate::currency::Cash;
 use crate::time;
@@ -199,7 +200,7 @@ pub fn format_error_record(record: &StringRecord) -> String {
     let mut human = format!("({})", format_record(record));
 
     if let Some(position) = record.position() {
-        human = format!("{} ({} line)", human, position.line());
+        human = format!("{} (line {}, byte offset {})", human, position.line(), position.byte());
     }
 
     human
@@ -229,22 +230,12 @@ pub fn parse_symbol(symbol: &str) -> GenericResult<String> {
 
 fn parse_quantity(quantity: &str) -> GenericResult<Decimal> {
     // See https://github.com/KonishchevDmitry/investments/issues/34
+    parse_decimal(quantity, DecimalRestrictions::No).map_err(|_| format!(
+        "Invalid quantity: {:?}", quantity).into())
+}
 
-    lazy_static! {
-        static ref DECIMAL_SEPARATOR_REGEX: Regex = Regex::new(
-            r"([1-9]0*),(\d{3})(,|\.|$)").unwrap();
-    }
-    let mut stripped = quantity.to_owned();
-
-    while stripped.contains(',') {
-        let new = DECIMAL_SEPARATOR_REGEX.replace_all(&stripped, "$1$2$3");
-        if new == stripped {
-            return Err!("Invalid quantity: {:?}", quantity)
-        }
-        stripped = new.into_owned();
-    }
-
-    Ok(Decimal::from_str(&stripped).map_err(|_| format!("Invalid quantity: {:?}", quantity))?)
+pub fn parse_decimal(value: &str, restrictions: DecimalRestrictions) -> GenericResult<Decimal> {
+    util::parse_formatted_decimal(value, Broker::InteractiveBrokers.number_format(), restrictions)
 }
 
 #[cfg(test)]
@@ -2,6 +2,8 @@ use std::collections::{HashMap, hash_map::Entry};
 #[cfg(test)] use std::fs;
 #[cfg(test)] use std::path::Path;
 
+use csv::StringRecord;
+
 use crate::core::{GenericResult, EmptyResult};
 use crate::formatting::format_date;
 use crate::time::{Date, DateTime};
@@ -25,12 +27,10 @@ pub fn try_parse(path: &str, execution_info: &mut TradeExecutionInfo) -> Generic
     let mut reader = csv::ReaderBuilder::new()
         .has_headers(false)
         .from_path(path)?;
-    let mut records = reader.records();
-
-    let headers = match records.next() {
-        Some(record) => record?,
-        None => return Ok(false),
-    };
+    let mut headers = StringRecord::new();
+    if !reader.read_record(&mut headers)? {
+        return Ok(false);
+    }
     let headers = headers.iter().collect::<Vec<&str>>();
 
     // Assume that it's trade confirmation report if it doesn't look like activity report, because
@@ -42,8 +42,9 @@ pub fn try_parse(path: &str, execution_info: &mut TradeExecutionInfo) -> Generic
 
     let record_spec = RecordSpec::new("Trade confirmation", headers, 0);
 
-    for record in records {
-        let record = record?;
+    let mut record = StringRecord::new();
+
+    while reader.read_record(&mut record)? {
         parse_record(&Record::new(&record_spec, &record), execution_info).map_err(|e| format!(
             "Failed to parse {} record: {}", format_error_record(&record), e
         ))?;
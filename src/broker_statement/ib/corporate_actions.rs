@@ -42,35 +42,40 @@ impl CorporateActionsParser {
     }
 
     pub fn commit(self, parser: &mut StatementParser) -> EmptyResult {
-        // Here we postprocess parsed corporate actions:
-        // * Complex stock splits are represented by two records, so we join them here
-
-        let mut stock_splits = Vec::<CorporateAction>::new();
-
-        for action in self.corporate_actions {
-            match action.action {
-                CorporateActionType::StockSplit {..} => {
-                    if let Some(last) = stock_splits.last() {
-                        if action.time == last.time && action.symbol == last.symbol {
-                            stock_splits.push(action);
-                        } else {
-                            parser.statement.corporate_actions.push(join_stock_splits(stock_splits)?);
-                            stock_splits = vec![action];
-                        }
-                    } else {
+        parser.statement.corporate_actions.extend(postprocess(self.corporate_actions)?);
+        Ok(())
+    }
+}
+
+// Here we postprocess parsed corporate actions:
+// * Complex stock splits are represented by two records, so we join them here
+pub fn postprocess(corporate_actions: Vec<CorporateAction>) -> GenericResult<Vec<CorporateAction>> {
+    let mut result = Vec::new();
+    let mut stock_splits = Vec::<CorporateAction>::new();
+
+    for action in corporate_actions {
+        match action.action {
+            CorporateActionType::StockSplit {..} => {
+                if let Some(last) = stock_splits.last() {
+                    if action.time == last.time && action.symbol == last.symbol {
                         stock_splits.push(action);
+                    } else {
+                        result.push(join_stock_splits(stock_splits)?);
+                        stock_splits = vec![action];
                     }
-                },
-                _ => parser.statement.corporate_actions.push(action),
-            }
-        }
-
-        if !stock_splits.is_empty() {
-            parser.statement.corporate_actions.push(join_stock_splits(stock_splits)?);
+                } else {
+                    stock_splits.push(action);
+                }
+            },
+            _ => result.push(action),
         }
+    }
 
-        Ok(())
+    if !stock_splits.is_empty() {
+        result.push(join_stock_splits(stock_splits)?);
     }
+
+    Ok(result)
 }
 
 fn parse(record: &Record) -> GenericResult<CorporateAction> {
@@ -127,7 +132,7 @@ fn parse(record: &Record) -> GenericResult<CorporateAction> {
             let volume = record.parse_amount("Proceeds", DecimalRestrictions::PositiveOrZero)?;
             let quantity = -record.parse_quantity("Quantity", DecimalRestrictions::StrictlyNegative)?;
 
-            let price = util::parse_decimal(
+            let price = common::parse_decimal(
                 captures.name("price").unwrap().as_str(),
                 DecimalRestrictions::PositiveOrZero)?;
 
@@ -1,6 +1,7 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::broker_statement::PendingDividend;
 use crate::core::{EmptyResult, GenericResult};
 use crate::instruments::InstrumentId;
 use crate::util::DecimalRestrictions;
@@ -46,6 +47,28 @@ impl RecordParser for DividendsParser {
     }
 }
 
+/// Dividends which are already declared, but not paid yet
+pub struct OpenDividendAccrualsParser {}
+
+impl RecordParser for OpenDividendAccrualsParser {
+    fn skip_totals(&self) -> bool {
+        true
+    }
+
+    fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
+        let currency = record.get_value("Currency")?;
+
+        parser.statement.pending_dividends.push(PendingDividend {
+            symbol: record.parse_symbol("Symbol")?,
+            ex_date: record.parse_date("Ex Date")?,
+            payment_date: record.parse_date("Pay Date")?,
+            amount: record.parse_cash("Net Amount", currency, DecimalRestrictions::StrictlyPositive)?,
+        });
+
+        Ok(())
+    }
+}
+
 fn parse_dividend_description(description: &str) -> GenericResult<(String, SecurityID)> {
     lazy_static! {
         static ref DESCRIPTION_REGEX: Regex = Regex::new(&format!(
@@ -0,0 +1,148 @@
+use serde::Deserialize;
+
+use crate::broker_statement::{CashFlow, CashFlowType, Fee, Withholding};
+use crate::broker_statement::interest::{IdleCashInterest, MarginInterest};
+use crate::core::EmptyResult;
+use crate::currency::CashAssets;
+use crate::instruments::InstrumentId;
+use crate::util::DecimalRestrictions;
+
+use super::super::common::parse_symbol;
+use super::super::interest::is_securities_lending_interest;
+use super::StatementParser;
+use super::common::{parse_cash, parse_date, parse_date_opt_time};
+
+#[derive(Deserialize)]
+pub struct CashReport {
+    #[serde(rename = "CashReportCurrency", default)]
+    currencies: Vec<CashReportCurrency>,
+}
+
+#[derive(Deserialize)]
+struct CashReportCurrency {
+    #[serde(rename = "@currency")]
+    currency: String,
+    #[serde(rename = "@endingCash")]
+    ending_cash: String,
+}
+
+impl CashReport {
+    pub fn parse(self, parser: &mut StatementParser) -> EmptyResult {
+        let cash_assets = parser.statement.assets.cash.get_or_insert_with(Default::default);
+
+        for report in self.currencies {
+            // Summary in base currency
+            if report.currency == "BASE_SUMMARY" {
+                continue;
+            }
+
+            if cash_assets.has_assets(&report.currency) {
+                return Err!("Got duplicated {} assets", report.currency);
+            }
+
+            cash_assets.deposit(parse_cash(
+                "ending cash", &report.currency, &report.ending_cash, DecimalRestrictions::No)?);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct CashTransactions {
+    #[serde(rename = "CashTransaction", default)]
+    transactions: Vec<CashTransaction>,
+}
+
+#[derive(Deserialize)]
+struct CashTransaction {
+    #[serde(rename = "@type")]
+    type_: String,
+    #[serde(rename = "@currency")]
+    currency: String,
+    #[serde(rename = "@symbol")]
+    symbol: String,
+    #[serde(rename = "@description")]
+    description: String,
+    #[serde(rename = "@dateTime")]
+    date: String,
+    #[serde(rename = "@settleDate")]
+    settle_date: String,
+    #[serde(rename = "@amount")]
+    amount: String,
+    #[serde(rename = "@levelOfDetail")]
+    level_of_detail: String,
+}
+
+impl CashTransactions {
+    pub fn parse(self, parser: &mut StatementParser) -> EmptyResult {
+        for transaction in self.transactions {
+            transaction.parse(parser).map_err(|e| format!(
+                "Failed to parse {:?} cash transaction: {}", transaction.description, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl CashTransaction {
+    fn parse(&self, parser: &mut StatementParser) -> EmptyResult {
+        if self.level_of_detail != "DETAIL" {
+            return Ok(());
+        }
+
+        let date = parse_date_opt_time(&self.date)?;
+        let settle_date = parse_date(&self.settle_date)?;
+        let amount = parse_cash("amount", &self.currency, &self.amount, DecimalRestrictions::NonZero)?;
+
+        match self.type_.as_str() {
+            "Deposits/Withdrawals" => {
+                parser.statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(settle_date, amount));
+            },
+
+            "Dividends" | "Payment In Lieu Of Dividends" => {
+                let accruals = parser.statement.dividend_accruals(
+                    date, InstrumentId::Symbol(parse_symbol(&self.symbol)?), true);
+
+                if amount.is_negative() {
+                    accruals.reverse(settle_date, -amount);
+                } else {
+                    accruals.add(settle_date, amount);
+                }
+            },
+
+            "Withholding Tax" => {
+                // See notes about tax remapping in CSV statements parser
+                let date = parser.tax_remapping.map(date, &self.description);
+                let accruals = parser.statement.tax_accruals(
+                    date, InstrumentId::Symbol(parse_symbol(&self.symbol)?), true);
+
+                if amount.is_positive() {
+                    accruals.reverse(settle_date, amount);
+                } else {
+                    accruals.add(settle_date, -amount);
+                }
+            },
+
+            "Broker Interest Received" => {
+                if is_securities_lending_interest(&self.description) {
+                    parser.statement.cash_flows.push(CashFlow::new(
+                        settle_date.into(), amount, CashFlowType::SecuritiesLending));
+                } else {
+                    parser.statement.idle_cash_interest.push(IdleCashInterest::new(settle_date, amount));
+                }
+            },
+
+            "Broker Interest Paid" => {
+                parser.statement.margin_interest.push(MarginInterest::new(settle_date, -amount));
+            },
+
+            "Other Fees" | "Broker Fees" => {
+                parser.statement.fees.push(Fee::new(settle_date, Withholding::new(-amount), None));
+            },
+
+            _ => return Err!("Unsupported cash transaction type: {:?}", self.type_),
+        }
+
+        Ok(())
+    }
+}
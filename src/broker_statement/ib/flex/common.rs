@@ -0,0 +1,50 @@
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::time::{self, Date, DateTime};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::super::common;
+
+// Flex Queries use yyyyMMdd date format and yyyyMMdd;HHmmss date/time format by default
+pub fn parse_date(date: &str) -> GenericResult<Date> {
+    time::parse_date(date, "%Y%m%d")
+}
+
+pub fn parse_date_time(date_time: &str) -> GenericResult<DateTime> {
+    time::parse_date_time(date_time, "%Y%m%d;%H%M%S")
+}
+
+// Cash transactions have time only for some transaction types
+pub fn parse_date_opt_time(date_time: &str) -> GenericResult<Date> {
+    Ok(match date_time.split_once(';') {
+        Some(_) => parse_date_time(date_time)?.date(),
+        None => parse_date(date_time)?,
+    })
+}
+
+pub fn parse_decimal(value: &str) -> GenericResult<Decimal> {
+    common::parse_decimal(value, DecimalRestrictions::No)
+}
+
+pub fn parse_named_decimal(name: &str, value: &str, restrictions: DecimalRestrictions) -> GenericResult<Decimal> {
+    util::validate_named_decimal(name, parse_decimal(value)?, restrictions)
+}
+
+pub fn parse_cash(name: &str, currency: &str, value: &str, restrictions: DecimalRestrictions) -> GenericResult<Cash> {
+    Ok(Cash::new(currency, parse_named_decimal(name, value, restrictions)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("20230328", date!(2023, 3, 28)),
+        case("20230328;202000", date!(2023, 3, 28)),
+    )]
+    fn date_opt_time_parsing(value: &str, expected: Date) {
+        assert_eq!(parse_date_opt_time(value).unwrap(), expected);
+    }
+}
@@ -0,0 +1,119 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::broker_statement::corporate_actions::{CorporateAction, CorporateActionType, StockSplitRatio};
+use crate::core::{EmptyResult, GenericResult};
+use crate::util::{self, DecimalRestrictions};
+
+use super::super::common::{self, SecurityID, parse_symbol};
+use super::super::corporate_actions;
+use super::StatementParser;
+use super::common::{parse_date, parse_date_time, parse_named_decimal};
+
+#[derive(Deserialize, Default)]
+pub struct CorporateActions {
+    #[serde(rename = "CorporateAction", default)]
+    actions: Vec<CorporateActionInfo>,
+}
+
+#[derive(Deserialize)]
+struct CorporateActionInfo {
+    #[serde(rename = "@assetCategory")]
+    asset_category: String,
+    #[serde(rename = "@symbol")]
+    symbol: String,
+    #[serde(rename = "@type")]
+    type_: String,
+    #[serde(rename = "@description")]
+    description: String,
+    #[serde(rename = "@dateTime")]
+    time: String,
+    #[serde(rename = "@reportDate")]
+    report_date: String,
+    #[serde(rename = "@quantity")]
+    quantity: String,
+    #[serde(rename = "@levelOfDetail", default)]
+    level_of_detail: String,
+}
+
+impl CorporateActions {
+    pub fn parse(self, parser: &mut StatementParser) -> EmptyResult {
+        let mut actions = Vec::new();
+
+        for action in self.actions {
+            // Supplementary records for corporate actions like delisting and liquidation
+            if action.level_of_detail == "CLOSED_LOT" {
+                continue;
+            }
+
+            actions.push(action.parse().map_err(|e| format!(
+                "Failed to parse {:?} corporate action: {}", action.description, e))?);
+        }
+
+        parser.statement.corporate_actions.extend(corporate_actions::postprocess(actions)?);
+        Ok(())
+    }
+}
+
+impl CorporateActionInfo {
+    fn parse(&self) -> GenericResult<CorporateAction> {
+        if self.asset_category != "STK" {
+            return Err!("Unsupported asset category: {:?}", self.asset_category);
+        }
+
+        let symbol = parse_symbol(&self.symbol)?;
+        let time = parse_date_time(&self.time)?;
+        let report_date = Some(parse_date(&self.report_date)?);
+
+        let action = match self.type_.as_str() {
+            // Forward and reverse stock splits
+            "FS" | "RS" => {
+                let ratio = parse_stock_split_ratio(&self.description)?;
+
+                let change = parse_named_decimal("quantity", &self.quantity, DecimalRestrictions::NonZero)?;
+                let (from_change, to_change) = if change.is_sign_positive() {
+                    (None, Some(change))
+                } else {
+                    (Some(-change), None)
+                };
+
+                CorporateActionType::StockSplit {ratio, from_change, to_change}
+            },
+            _ => return Err!("Unsupported corporate action type: {:?}", self.type_),
+        };
+
+        Ok(CorporateAction {time: time.into(), report_date, symbol, action})
+    }
+}
+
+fn parse_stock_split_ratio(description: &str) -> GenericResult<StockSplitRatio> {
+    lazy_static! {
+        static ref REGEX: Regex = Regex::new(&format!(
+            r"^{symbol} ?\({id}\) Split (?P<to>[1-9]\d*) for (?P<from>[1-9]\d*) ",
+            symbol=common::STOCK_SYMBOL_REGEX, id=SecurityID::REGEX)).unwrap();
+    }
+
+    let description = util::fold_spaces(description);
+    let captures = REGEX.captures(&description).ok_or_else(|| format!(
+        "Unsupported stock split description: {:?}", description))?;
+
+    let from: u32 = captures.name("from").unwrap().as_str().parse()?;
+    let to: u32 = captures.name("to").unwrap().as_str().parse()?;
+
+    Ok(StockSplitRatio::new(from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(description, from, to,
+        case("AAPL(US0378331005) Split 4 for 1 (AAPL, APPLE INC, US0378331005)", 1, 4),
+        case("VISL(US92836Y2019) Split 1 for 6 (VISL, VISLINK TECHNOLOGIES INC, US92836Y3009)", 6, 1),
+    )]
+    fn stock_split_ratio_parsing(description: &str, from: u32, to: u32) {
+        assert_eq!(parse_stock_split_ratio(description).unwrap(), StockSplitRatio::new(from, to));
+    }
+}
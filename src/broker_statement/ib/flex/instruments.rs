@@ -0,0 +1,105 @@
+use serde::Deserialize;
+
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::util::DecimalRestrictions;
+
+use super::super::common::{SecurityID, parse_symbol};
+use super::StatementParser;
+use super::common::{parse_decimal, parse_named_decimal};
+
+#[derive(Deserialize)]
+pub struct OpenPositions {
+    #[serde(rename = "OpenPosition", default)]
+    positions: Vec<OpenPosition>,
+}
+
+#[derive(Deserialize)]
+struct OpenPosition {
+    #[serde(rename = "@assetCategory")]
+    asset_category: String,
+    #[serde(rename = "@symbol")]
+    symbol: String,
+    #[serde(rename = "@position")]
+    quantity: String,
+    #[serde(rename = "@positionValue")]
+    value: String,
+    #[serde(rename = "@fxRateToBase")]
+    fx_rate_to_base: String,
+    #[serde(rename = "@levelOfDetail")]
+    level_of_detail: String,
+}
+
+impl OpenPositions {
+    pub fn parse(self, parser: &mut StatementParser) -> EmptyResult {
+        let mut value = Cash::zero(parser.base_currency()?);
+
+        for position in self.positions {
+            // Lot level of detail contains open position calculation
+            if position.level_of_detail != "SUMMARY" {
+                continue;
+            }
+
+            if position.asset_category != "STK" {
+                return Err!("Got an unsupported asset category of {} open position: {}",
+                            position.symbol, position.asset_category);
+            }
+
+            let symbol = parse_symbol(&position.symbol)?;
+            let quantity = parse_named_decimal("quantity", &position.quantity, DecimalRestrictions::No)?;
+            if quantity.is_sign_negative() {
+                return Err!("Short positions aren't supported: {}", symbol);
+            }
+            parser.statement.add_open_position(&symbol, quantity)?;
+
+            let position_value = parse_named_decimal(
+                "position value", &position.value, DecimalRestrictions::PositiveOrZero)?;
+            value.amount += position_value * parse_decimal(&position.fx_rate_to_base)?;
+        }
+
+        parser.statement.assets.other.replace(value.round());
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct SecuritiesInfo {
+    #[serde(rename = "SecurityInfo", default)]
+    securities: Vec<SecurityInfo>,
+}
+
+#[derive(Deserialize)]
+struct SecurityInfo {
+    #[serde(rename = "@symbol")]
+    symbol: String,
+    #[serde(rename = "@description")]
+    description: String,
+    #[serde(rename = "@isin", default)]
+    isin: String,
+    #[serde(rename = "@cusip", default)]
+    cusip: String,
+}
+
+impl SecuritiesInfo {
+    pub fn parse(self, parser: &mut StatementParser) -> EmptyResult {
+        for security in self.securities {
+            let symbol = parse_symbol(&security.symbol)?;
+            let instrument = parser.statement.instrument_info.get_or_add(&symbol);
+            instrument.set_name(&security.description);
+
+            for id in [&security.isin, &security.cusip] {
+                if id.is_empty() {
+                    continue;
+                }
+
+                match id.parse::<SecurityID>()? {
+                    SecurityID::Isin(isin) => instrument.add_isin(isin),
+                    SecurityID::Cusip(cusip) => instrument.add_cusip(cusip),
+                    SecurityID::Conid(_) => return Err!("Got an unsupported security ID for {}: {:?}", symbol, id),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
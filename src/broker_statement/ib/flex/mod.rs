@@ -0,0 +1,196 @@
+// Flex Query statements support. Flex Queries are configured by user, so we expect the following sections to be
+// enabled (with all fields and in default date/time format):
+// * Account Information
+// * Change in NAV
+// * Cash Report
+// * Open Positions (Summary level of detail)
+// * Trades (Execution level of detail)
+// * Cash Transactions (Detail level of detail)
+// * Corporate Actions
+// * Financial Instrument Information
+
+mod cash;
+mod common;
+mod corporate_actions;
+mod instruments;
+mod trades;
+
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::exchanges::Exchange;
+use crate::taxes::TaxRemapping;
+use crate::time::Period;
+
+use super::super::PartialBrokerStatement;
+
+use self::cash::{CashReport, CashTransactions};
+use self::common::{parse_date, parse_date_time, parse_decimal};
+use self::corporate_actions::CorporateActions;
+use self::instruments::{OpenPositions, SecuritiesInfo};
+use self::trades::Trades;
+
+pub struct StatementParser<'a> {
+    statement: PartialBrokerStatement,
+    base_currency: Option<String>,
+    tax_remapping: &'a mut TaxRemapping,
+}
+
+impl StatementParser<'_> {
+    pub fn parse(path: &str, tax_remapping: &mut TaxRemapping) -> GenericResult<PartialBrokerStatement> {
+        let response: FlexQueryResponse = quick_xml::de::from_str(&fs::read_to_string(path)?)?;
+
+        let mut statements = response.statements.statements;
+        if statements.len() != 1 {
+            return Err!("Got {} statements in Flex Query response (multi-account queries aren't supported)",
+                        statements.len());
+        }
+
+        let mut parser = StatementParser {
+            statement: PartialBrokerStatement::new(&[Exchange::Us, Exchange::Other], false),
+            base_currency: None,
+            tax_remapping,
+        };
+
+        statements.pop().unwrap().parse(&mut parser)?;
+        parser.statement.validate()
+    }
+
+    fn base_currency(&self) -> GenericResult<&str> {
+        Ok(self.base_currency.as_deref().ok_or("Unable to determine account base currency")?)
+    }
+}
+
+#[derive(Deserialize)]
+struct FlexQueryResponse {
+    #[serde(rename = "FlexStatements")]
+    statements: FlexStatements,
+}
+
+#[derive(Deserialize)]
+struct FlexStatements {
+    #[serde(rename = "FlexStatement", default)]
+    statements: Vec<FlexStatement>,
+}
+
+#[derive(Deserialize)]
+struct FlexStatement {
+    #[serde(rename = "@fromDate")]
+    from_date: String,
+    #[serde(rename = "@toDate")]
+    to_date: String,
+    #[serde(rename = "@whenGenerated")]
+    generation_time: String,
+
+    #[serde(rename = "AccountInformation")]
+    account_information: Option<AccountInformation>,
+    #[serde(rename = "ChangeInNAV")]
+    change_in_nav: Option<ChangeInNav>,
+    #[serde(rename = "CashReport")]
+    cash_report: Option<CashReport>,
+    #[serde(rename = "OpenPositions")]
+    open_positions: Option<OpenPositions>,
+    #[serde(rename = "Trades", default)]
+    trades: Trades,
+    #[serde(rename = "CashTransactions", default)]
+    cash_transactions: CashTransactions,
+    #[serde(rename = "CorporateActions", default)]
+    corporate_actions: CorporateActions,
+    #[serde(rename = "SecuritiesInfo", default)]
+    securities_info: SecuritiesInfo,
+}
+
+impl FlexStatement {
+    fn parse(self, parser: &mut StatementParser) -> EmptyResult {
+        let period = Period::new(parse_date(&self.from_date)?, parse_date(&self.to_date)?)?;
+        parser.statement.set_period(period)?;
+        parser.statement.set_generation_time(parse_date_time(&self.generation_time)?)?;
+
+        let account_information = self.account_information.ok_or(
+            "Account Information section is missing in the statement")?;
+        parser.base_currency.replace(account_information.currency);
+
+        let change_in_nav = self.change_in_nav.ok_or("Change in NAV section is missing in the statement")?;
+        let starting_value = parse_decimal(&change_in_nav.starting_value)?;
+        parser.statement.set_has_starting_assets(!starting_value.is_zero())?;
+
+        self.cash_report.ok_or("Cash Report section is missing in the statement")?.parse(parser)?;
+        self.open_positions.ok_or("Open Positions section is missing in the statement")?.parse(parser)?;
+        self.securities_info.parse(parser)?;
+
+        self.trades.parse(parser)?;
+        self.cash_transactions.parse(parser)?;
+        self.corporate_actions.parse(parser)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct AccountInformation {
+    #[serde(rename = "@currency")]
+    currency: String,
+}
+
+#[derive(Deserialize)]
+struct ChangeInNav {
+    #[serde(rename = "@startingValue")]
+    starting_value: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::currency::Cash;
+    use crate::instruments::InstrumentId;
+    use crate::time::Date;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let path = Path::new(file!()).parent().unwrap().parent().unwrap().join("testdata/flex-statement.xml");
+        let mut tax_remapping = TaxRemapping::new();
+        let statement = StatementParser::parse(path.to_str().unwrap(), &mut tax_remapping).unwrap();
+
+        let period = statement.get_period().unwrap();
+        assert_eq!((period.first_date(), period.last_date()), (date!(2023, 1, 1), date!(2023, 12, 31)));
+        assert_eq!(statement.generation_time, Some(date_time!(2024, 1, 5, 10, 10, 10)));
+        assert_eq!(statement.has_starting_assets, Some(true));
+
+        let cash = statement.assets.cash.as_ref().unwrap();
+        assert_eq!(cash.get("USD"), Some(Cash::new("USD", dec!(1011.39))));
+        assert_eq!(cash.get("EUR"), Some(Cash::new("EUR", dec!(100))));
+        assert_eq!(statement.assets.other, Some(Cash::new("USD", dec!(4155.5))));
+
+        assert_eq!(statement.open_positions, hashmap!{
+            s!("VTI") => dec!(10),
+            s!("BRK-B") => dec!(5),
+        });
+        assert_eq!(statement.instrument_info.get_name("BRK-B"), "BERKSHIRE HATHAWAY INC-CL B (BRK-B)");
+
+        assert_eq!(statement.forex_trades.len(), 1);
+        assert_eq!(statement.stock_buys.len(), 1);
+        assert_eq!(statement.stock_sells.len(), 1);
+
+        let trade = statement.stock_buys.first().unwrap();
+        assert_eq!(trade.symbol, "VTI");
+        assert_eq!(trade.conclusion_time, date_time!(2023, 1, 3, 9, 35, 12).into());
+        assert_eq!(trade.execution_date, date!(2023, 1, 5));
+
+        assert_eq!(statement.deposits_and_withdrawals.len(), 1);
+        assert_eq!(statement.idle_cash_interest.len(), 1);
+        assert_eq!(statement.fees.len(), 1);
+
+        let date: Date = date!(2023, 3, 28);
+        let dividend = statement.dividend_accruals.keys().next().unwrap();
+        assert_eq!((dividend.date, &dividend.issuer), (date, &InstrumentId::Symbol(s!("VTI"))));
+        assert_eq!(statement.dividend_accruals.len(), 1);
+        assert_eq!(statement.tax_accruals.len(), 1);
+
+        assert_eq!(statement.corporate_actions.len(), 1);
+    }
+}
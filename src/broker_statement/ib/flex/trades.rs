@@ -0,0 +1,120 @@
+use serde::Deserialize;
+
+use crate::broker_statement::trades::{ForexTrade, StockBuy, StockSell};
+use crate::core::EmptyResult;
+use crate::util::DecimalRestrictions;
+
+use super::super::common::{check_volume, parse_symbol};
+use super::StatementParser;
+use super::common::{parse_cash, parse_date, parse_date_time, parse_named_decimal};
+
+#[derive(Deserialize, Default)]
+pub struct Trades {
+    #[serde(rename = "Trade", default)]
+    trades: Vec<Trade>,
+}
+
+#[derive(Deserialize)]
+struct Trade {
+    #[serde(rename = "@assetCategory")]
+    asset_category: String,
+    #[serde(rename = "@symbol")]
+    symbol: String,
+    #[serde(rename = "@currency")]
+    currency: String,
+    #[serde(rename = "@dateTime")]
+    conclusion_time: String,
+    #[serde(rename = "@settleDateTarget")]
+    execution_date: String,
+    #[serde(rename = "@quantity")]
+    quantity: String,
+    #[serde(rename = "@tradePrice")]
+    price: String,
+    #[serde(rename = "@proceeds")]
+    proceeds: String,
+    #[serde(rename = "@ibCommission")]
+    commission: String,
+    #[serde(rename = "@ibCommissionCurrency")]
+    commission_currency: String,
+    #[serde(rename = "@levelOfDetail")]
+    level_of_detail: String,
+}
+
+impl Trades {
+    pub fn parse(self, parser: &mut StatementParser) -> EmptyResult {
+        for trade in self.trades {
+            trade.parse(parser).map_err(|e| format!(
+                "Failed to parse {} trade at {}: {}", trade.symbol, trade.conclusion_time, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl Trade {
+    fn parse(&self, parser: &mut StatementParser) -> EmptyResult {
+        if self.level_of_detail != "EXECUTION" {
+            return Err!("Got an unsupported trade level of detail: {:?}", self.level_of_detail);
+        }
+
+        match self.asset_category.as_str() {
+            "CASH" => self.parse_forex(parser),
+            "STK" => self.parse_stock(parser),
+            _ => Err!("Unsupported asset category: {}", self.asset_category),
+        }
+    }
+
+    fn parse_forex(&self, parser: &mut StatementParser) -> EmptyResult {
+        let (base, quote) = self.symbol.split_once('.').ok_or_else(|| format!(
+            "Invalid forex pair: {}", self.symbol))?;
+
+        let volume = parse_cash("proceeds", quote, &self.proceeds, DecimalRestrictions::NonZero)?;
+        let quantity = parse_cash("quantity", base, &self.quantity, DecimalRestrictions::NonZero)?;
+
+        let (from, to) = if quantity.is_positive() {
+            (-volume, quantity)
+        } else {
+            (-quantity, volume)
+        };
+        if from.is_negative() || to.is_negative() {
+            return Err!("Unexpected Forex quantity/volume values: {}/{}", quantity, volume);
+        }
+
+        let commission = -parse_cash(
+            "commission", &self.commission_currency, &self.commission, DecimalRestrictions::NegativeOrZero)?;
+
+        let conclusion_time = parse_date_time(&self.conclusion_time)?;
+        parser.statement.forex_trades.push(ForexTrade::new(conclusion_time.into(), from, to, commission));
+
+        Ok(())
+    }
+
+    fn parse_stock(&self, parser: &mut StatementParser) -> EmptyResult {
+        let symbol = parse_symbol(&self.symbol)?;
+        let conclusion_time = parse_date_time(&self.conclusion_time)?;
+        let execution_date = parse_date(&self.execution_date)?;
+
+        let price = parse_cash("price", &self.currency, &self.price, DecimalRestrictions::StrictlyPositive)?;
+        let quantity = parse_named_decimal("quantity", &self.quantity, DecimalRestrictions::NonZero)?;
+        let commission = -parse_cash(
+            "commission", &self.commission_currency, &self.commission, DecimalRestrictions::NegativeOrZero)?;
+
+        let volume = parse_cash("proceeds", &self.currency, &self.proceeds, if quantity.is_sign_positive() {
+            DecimalRestrictions::StrictlyNegative
+        } else {
+            DecimalRestrictions::StrictlyPositive
+        })?;
+        check_volume(-quantity, price, volume)?;
+
+        if quantity.is_sign_positive() {
+            parser.statement.stock_buys.push(StockBuy::new_trade(
+                &symbol, quantity, price, -volume, commission,
+                conclusion_time.into(), execution_date));
+        } else {
+            parser.statement.stock_sells.push(StockSell::new_trade(
+                &symbol, -quantity, price, volume, commission,
+                conclusion_time.into(), execution_date, false));
+        }
+
+        Ok(())
+    }
+}
@@ -9,6 +9,11 @@ use super::common::{self, Record, RecordParser, SecurityID, parse_symbol};
 pub struct OpenPositionsParser {}
 
 impl RecordParser for OpenPositionsParser {
+    // Derivatives have their own header
+    fn allow_multiple(&self) -> bool {
+        true
+    }
+
     fn skip_data_types(&self) -> Option<&'static [&'static str]> {
         Some(&["Total"])
     }
@@ -16,6 +21,9 @@ impl RecordParser for OpenPositionsParser {
     fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
         let data_type_field = "DataDiscriminator";
         match record.get_value(data_type_field)? {
+            // Derivative positions are calculated from trades
+            "Summary" if is_derivative(record.get_value("Asset Category")?) => return Ok(()),
+
             // Default Activity Statement contains only this type
             "Summary" => record.check_values(&[
                 ("Asset Category", "Stocks"),
@@ -39,7 +47,18 @@ pub struct FinancialInstrumentInformationParser {
 }
 
 impl RecordParser for FinancialInstrumentInformationParser {
+    // Derivatives have their own header
+    fn allow_multiple(&self) -> bool {
+        true
+    }
+
     fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
+        if is_derivative(record.get_value("Asset Category")?) {
+            let symbol = record.get_value("Symbol")?;
+            parser.statement.instrument_info.get_or_add(symbol).set_name(record.get_value("Description")?);
+            return Ok(());
+        }
+
         // If symbol renames save its ISIN the column contains both symbols
         // (see https://github.com/KonishchevDmitry/investments/issues/29)
 
@@ -76,4 +95,8 @@ impl RecordParser for FinancialInstrumentInformationParser {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn is_derivative(asset_category: &str) -> bool {
+    matches!(asset_category, "Equity and Index Options" | "Futures")
+}
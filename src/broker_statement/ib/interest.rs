@@ -1,5 +1,6 @@
 use crate::core::EmptyResult;
-use crate::broker_statement::interest::IdleCashInterest;
+use crate::broker_statement::cash_flows::{CashFlow, CashFlowType};
+use crate::broker_statement::interest::{IdleCashInterest, MarginInterest};
 use crate::util::DecimalRestrictions;
 
 use super::StatementParser;
@@ -15,8 +16,47 @@ impl RecordParser for InterestParser {
     fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
         let currency = record.get_value("Currency")?;
         let date = record.parse_date("Date")?;
+        let description = record.get_value("Description")?;
         let amount = record.parse_cash("Amount", currency, DecimalRestrictions::NonZero)?;
-        parser.statement.idle_cash_interest.push(IdleCashInterest::new(date, amount));
+
+        if is_securities_lending_interest(description) {
+            parser.statement.cash_flows.push(CashFlow::new(date.into(), amount, CashFlowType::SecuritiesLending));
+        } else if is_margin_interest(description) {
+            parser.statement.margin_interest.push(MarginInterest::new(date, -amount));
+        } else {
+            parser.statement.idle_cash_interest.push(IdleCashInterest::new(date, amount));
+        }
+
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// Interest on borrowed cash is reported as debit records along with idle cash interest:
+// * USD Debit Interest for Jan-2024
+fn is_margin_interest(description: &str) -> bool {
+    description.contains("Debit Interest")
+}
+
+// Stock Yield Enhancement Program (fully paid securities lending) interest is reported along with idle cash interest:
+// * USD IBKR Managed Securities (SYEP) Interest for Jan-2024
+// * USD Stock Yield Enhancement Program Securities Lent Interest for Jan-2024
+pub fn is_securities_lending_interest(description: &str) -> bool {
+    description.contains("(SYEP)") || description.contains("Securities Lent")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(description, margin, securities_lending,
+        case("USD Credit Interest for Jan-2024", false, false),
+        case("USD Debit Interest for Jan-2024", true, false),
+        case("USD IBKR Managed Securities (SYEP) Interest for Jan-2024", false, true),
+        case("USD Stock Yield Enhancement Program Securities Lent Interest for Jan-2024", false, true),
+    )]
+    fn interest_classification(description: &str, margin: bool, securities_lending: bool) {
+        assert_eq!(is_margin_interest(description), margin);
+        assert_eq!(is_securities_lending_interest(description), securities_lending);
+    }
+}
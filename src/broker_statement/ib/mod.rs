@@ -5,6 +5,7 @@ mod confirmation;
 mod corporate_actions;
 mod dividends;
 mod fees;
+mod flex;
 mod grants;
 mod interest;
 mod instruments;
@@ -13,7 +14,8 @@ mod summary;
 mod taxes;
 mod trades;
 
-use std::iter::Iterator;
+use std::io::Read;
+use std::mem;
 
 #[cfg(test)] use chrono::Datelike;
 use csv::{self, StringRecord};
@@ -29,13 +31,14 @@ use crate::taxes::TaxRemapping;
 use crate::time::{Date, DateTime};
 
 #[cfg(test)] use super::BrokerStatement;
-use super::{BrokerStatementReader, ReadingStrictness, PartialBrokerStatement};
+use super::{BrokerStatementReader, ReadingStrictness, StatementSections, PartialBrokerStatement};
 
 use self::cash_flows::CashFlows;
 use self::common::{Record, format_record, format_error_record, is_header_field};
 use self::confirmation::{TradeExecutionInfo, OrderId};
 
 pub struct StatementReader {
+    sections: StatementSections,
     tax_remapping: TaxRemapping,
     trade_execution_info: TradeExecutionInfo,
 
@@ -45,8 +48,11 @@ pub struct StatementReader {
 }
 
 impl StatementReader {
-    pub fn new(tax_remapping: TaxRemapping, strictness: ReadingStrictness) -> GenericResult<Box<dyn BrokerStatementReader>> {
+    pub fn new(
+        tax_remapping: TaxRemapping, strictness: ReadingStrictness, sections: StatementSections,
+    ) -> GenericResult<Box<dyn BrokerStatementReader>> {
         Ok(Box::new(StatementReader {
+            sections: sections,
             tax_remapping: tax_remapping,
             trade_execution_info: TradeExecutionInfo::new(),
 
@@ -59,7 +65,9 @@ impl StatementReader {
 
 impl BrokerStatementReader for StatementReader {
     fn check(&mut self, path: &str) -> GenericResult<bool> {
-        if !path.ends_with(".csv") {
+        if path.ends_with(".xml") {
+            return Ok(true);
+        } else if !path.ends_with(".csv") {
             return Ok(false)
         }
 
@@ -70,7 +78,12 @@ impl BrokerStatementReader for StatementReader {
     }
 
     fn read(&mut self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        if path.ends_with(".xml") {
+            return flex::StatementParser::parse(path, &mut self.tax_remapping);
+        }
+
         StatementParser {
+            sections: self.sections,
             statement: PartialBrokerStatement::new(&[Exchange::Us, Exchange::Other], false),
 
             base_currency: None,
@@ -87,17 +100,16 @@ impl BrokerStatementReader for StatementReader {
     }
 
     fn close(self: Box<StatementReader>) -> EmptyResult {
+        // Tax remapping rules are applied only to withheld taxes
+        if !self.sections.contains(StatementSections::DIVIDENDS) {
+            return Ok(());
+        }
         self.tax_remapping.ensure_all_mapped()
     }
 }
 
-enum State {
-    None,
-    Record(StringRecord),
-    Header(StringRecord),
-}
-
 pub struct StatementParser<'a> {
+    sections: StatementSections,
     statement: PartialBrokerStatement,
 
     base_currency: Option<String>,
@@ -119,99 +131,38 @@ impl StatementParser<'_> {
             .flexible(true)
             .from_path(path)?;
 
-        let mut state = Some(State::None);
-        let mut records = reader.records();
-        let mut section_parsers = sections::SectionParsers::new();
-
-        'state: loop {
-            match state.take().unwrap() {
-                State::None => {
-                    match records.next() {
-                        Some(result) => state = Some(State::Record(result?)),
-                        None => break 'state,
-                    };
+        // Statements may be pretty big, so we stream them reusing record buffers instead of allocating a new record
+        // for each line.
+        let mut header = StringRecord::new();
+        let mut record = StringRecord::new();
+        let mut section_parsers = sections::SectionParsers::new(self.sections);
+
+        let mut has_record = reader.read_record(&mut record)?;
+
+        while has_record {
+            if record.len() < 2 {
+                let value = record.get(0).unwrap_or("");
+
+                // An empty "Option Exercises, Assignments and Expirations" section in
+                // Custom Activity Statement is rendered as a single value record without
+                // header record:
+                // "No exercises, assignments or expirations for May 21, 2018 - December 31, 2018"
+                if value.starts_with("No exercises, assignments or expirations for ") {
+                    has_record = reader.read_record(&mut record)?;
+                    continue;
                 }
 
-                State::Record(record) => {
-                    if record.len() < 2 {
-                        let value = record.get(0).unwrap_or("");
-
-                        // An empty "Option Exercises, Assignments and Expirations" section in
-                        // Custom Activity Statement is rendered as a single value record without
-                        // header record:
-                        // "No exercises, assignments or expirations for May 21, 2018 - December 31, 2018"
-                        if value.starts_with("No exercises, assignments or expirations for ") {
-                            state = Some(State::None);
-                            continue 'state;
-                        }
-
-                        return Err!("Invalid record: {}", format_error_record(&record));
-                    }
-
-                    if is_header_field(record.get(1).unwrap()) {
-                        state = Some(State::Header(record));
-                    } else if record.get(1).unwrap() == "" {
-                        trace!("Headerless record: {}.", format_record(&record));
-                        state = Some(State::None);
-                    } else {
-                        return Err!("Invalid record: {}", format_error_record(&record));
-                    }
-                },
-
-                State::Header(record) => {
-                    let (spec, parser) = section_parsers.select(&record)?;
-
-                    let data_types = parser.data_types();
-                    let skip_data_types = parser.skip_data_types();
-                    let skip_totals = parser.skip_totals();
-
-                    for result in &mut records {
-                        let record = result?;
-                        if record.get(0) != Some(spec.name) {
-                            state = Some(State::Record(record));
-                            continue 'state;
-                        } else if record.len() < 3 {
-                            return Err!("Invalid record: {}", format_error_record(&record));
-                        }
-
-                        let data_type = record.get(1).unwrap();
-                        if is_header_field(data_type) {
-                            state = Some(State::Header(record));
-                            continue 'state;
-                        } else if data_type == "Notes" {
-                            continue
-                        }
-
-                        if let Some(skip_data_types) = skip_data_types {
-                            if skip_data_types.contains(&data_type) {
-                                continue;
-                            }
-                        }
-
-                        if let Some(data_types) = data_types {
-                            if !data_types.contains(&data_type) {
-                                return Err!("Invalid data record type: {}", format_error_record(&record));
-                            }
-                        }
-
-                        // Matches totals records. For example:
-                        // * Deposits & Withdrawals,Data,Total,,,1000
-                        // * Deposits & Withdrawals,Data,Total in USD,,,1000
-                        // * Deposits & Withdrawals,Data,Total Deposits & Withdrawals in USD,,,1000
-                        // * Interest,Data,Total,,,100
-                        // * Interest,Data,Total in USD,,,100
-                        // * Interest,Data,Total Interest in USD,,,100
-                        if skip_totals && record.get(2).unwrap().starts_with("Total") {
-                            continue;
-                        }
-
-                        parser.parse(&mut self, &Record::new(&spec, &record)).map_err(|e| format!(
-                            "Failed to parse {} record: {}", format_error_record(&record), e
-                        ))?;
-                    }
-
-                    break 'state;
-                }
+                return Err!("Invalid record: {}", format_error_record(&record));
+            }
+
+            if is_header_field(record.get(1).unwrap()) {
+                mem::swap(&mut header, &mut record);
+                has_record = self.parse_section(&mut reader, &mut section_parsers, &header, &mut record)?;
+            } else if record.get(1).unwrap() == "" {
+                trace!("Headerless record: {}.", format_record(&record));
+                has_record = reader.read_record(&mut record)?;
+            } else {
+                return Err!("Invalid record: {}", format_error_record(&record));
             }
         }
 
@@ -220,6 +171,64 @@ impl StatementParser<'_> {
         self.statement.validate()
     }
 
+    // Parses section records until a record of another section or a new header is met. Returns false on end of file
+    // or true if the met record has been read into the record buffer.
+    fn parse_section<R: Read>(
+        &mut self, reader: &mut csv::Reader<R>, section_parsers: &mut sections::SectionParsers,
+        header: &StringRecord, record: &mut StringRecord,
+    ) -> GenericResult<bool> {
+        let (spec, parser) = section_parsers.select(header).map_err(|e| format!(
+            "Failed to parse {} header: {}", format_error_record(header), e))?;
+
+        let data_types = parser.data_types();
+        let skip_data_types = parser.skip_data_types();
+        let skip_totals = parser.skip_totals();
+
+        while reader.read_record(record)? {
+            if record.get(0) != Some(spec.name) {
+                return Ok(true);
+            } else if record.len() < 3 {
+                return Err!("Invalid record: {}", format_error_record(record));
+            }
+
+            let data_type = record.get(1).unwrap();
+            if is_header_field(data_type) {
+                return Ok(true);
+            } else if data_type == "Notes" {
+                continue
+            }
+
+            if let Some(skip_data_types) = skip_data_types {
+                if skip_data_types.contains(&data_type) {
+                    continue;
+                }
+            }
+
+            if let Some(data_types) = data_types {
+                if !data_types.contains(&data_type) {
+                    return Err!("Invalid data record type: {}", format_error_record(record));
+                }
+            }
+
+            // Matches totals records. For example:
+            // * Deposits & Withdrawals,Data,Total,,,1000
+            // * Deposits & Withdrawals,Data,Total in USD,,,1000
+            // * Deposits & Withdrawals,Data,Total Deposits & Withdrawals in USD,,,1000
+            // * Interest,Data,Total,,,100
+            // * Interest,Data,Total in USD,,,100
+            // * Interest,Data,Total Interest in USD,,,100
+            if skip_totals && record.get(2).unwrap().starts_with("Total") {
+                continue;
+            }
+
+            parser.parse(self, &Record::new(&spec, record)).map_err(|e| format!(
+                "Failed to parse {} record: {}", format_error_record(record), e
+            ))?;
+        }
+
+        Ok(false)
+    }
+
     fn base_currency(&self) -> GenericResult<&str> {
         Ok(self.base_currency.as_deref().ok_or("Unable to determine account base currency")?)
     }
@@ -334,16 +343,39 @@ mod tests {
     #[rstest(name => ["no-activity", "multi-currency-activity"])]
     fn parse_real_partial(name: &str) {
         let path = format!("testdata/interactive-brokers/partial/{}.csv", name);
-        StatementReader::new(TaxRemapping::new(), ReadingStrictness::all()).unwrap()
+        StatementReader::new(TaxRemapping::new(), ReadingStrictness::all(), StatementSections::all()).unwrap()
             .read(&path, true).unwrap();
     }
 
+    #[rstest(data, error,
+        case("Foo,Bar\n", r#"Invalid record: ("Foo", "Bar") (line 1, byte offset 0)"#),
+        case(
+            "Fees,Header,Currency\nFees,Invalid,USD\n",
+            r#"Invalid data record type: ("Fees", "Invalid", "USD") (line 2, byte offset 21)"#,
+        ),
+        case(
+            "Fees,Header,Currency\nFees,Header,Currency\n", concat!(
+            r#"Failed to parse ("Fees", "Header", "Currency") (line 2, byte offset 21) header: "#,
+            "Got a duplicated Fees section"),
+        ),
+    )]
+    fn error_position(data: &str, error: &str) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("statement.csv");
+        std::fs::write(&path, data).unwrap();
+
+        let mut reader = StatementReader::new(
+            TaxRemapping::new(), ReadingStrictness::all(), StatementSections::all()).unwrap();
+        let result = reader.read(path.to_str().unwrap(), true);
+        assert_eq!(result.err().unwrap().to_string(), error);
+    }
+
     fn parse_full(name: &str, tax_remapping: Option<TaxRemapping>) -> BrokerStatement {
         let broker = Broker::InteractiveBrokers.get_info(&Config::mock(), None).unwrap();
         let path = format!("testdata/interactive-brokers/{}", name);
         let tax_remapping = tax_remapping.unwrap_or_else(TaxRemapping::new);
         BrokerStatement::read(
             broker, &path, &Default::default(), &Default::default(), &Default::default(), tax_remapping, &[], &[],
-            ReadingStrictness::all()).unwrap()
+            ReadingStrictness::all(), StatementSections::all()).unwrap()
     }
 }
\ No newline at end of file
@@ -3,13 +3,14 @@ use std::collections::{HashMap, hash_map::Entry};
 use csv::StringRecord;
 use log::trace;
 
+use crate::broker_statement::StatementSections;
 use crate::core::{GenericResult, EmptyResult};
 
 use super::StatementParser;
 use super::cash::{CashReportParser, DepositsAndWithdrawalsParser, StatementOfFundsParser};
 use super::common::{RecordSpec, RecordParser, UnknownRecordParser, format_record};
 use super::corporate_actions::CorporateActionsParser;
-use super::dividends::DividendsParser;
+use super::dividends::{DividendsParser, OpenDividendAccrualsParser};
 use super::fees::FeesParser;
 use super::grants::GrantsParser;
 use super::instruments::{OpenPositionsParser, FinancialInstrumentInformationParser};
@@ -32,18 +33,21 @@ pub struct SectionParsers {
     deposits_and_withdrawals_parser: DepositsAndWithdrawalsParser,
     fees_parser: FeesParser,
     dividends_parser: DividendsParser,
+    open_dividend_accruals_parser: OpenDividendAccrualsParser,
     withholding_tax_parser: WithholdingTaxParser,
     interest_parser: InterestParser,
     financial_instrument_information_parser: FinancialInstrumentInformationParser,
 
     unknown_record_parser: UnknownRecordParser,
     duplicated_record_parser: UnknownRecordParser,
+    skipped_record_parser: UnknownRecordParser,
 
+    sections: StatementSections,
     parsed_sections: HashMap<String, bool>,
 }
 
 impl SectionParsers {
-    pub fn new() -> SectionParsers {
+    pub fn new(sections: StatementSections) -> SectionParsers {
         SectionParsers {
             statement_info_parser: StatementInfoParser {},
             account_information_parser: AccountInformationParser{},
@@ -58,19 +62,28 @@ impl SectionParsers {
             deposits_and_withdrawals_parser: DepositsAndWithdrawalsParser {},
             fees_parser: FeesParser {},
             dividends_parser: DividendsParser {},
+            open_dividend_accruals_parser: OpenDividendAccrualsParser {},
             withholding_tax_parser: WithholdingTaxParser {},
             interest_parser: InterestParser {},
             financial_instrument_information_parser: FinancialInstrumentInformationParser {},
 
             unknown_record_parser: UnknownRecordParser {},
             duplicated_record_parser: UnknownRecordParser {},
+            skipped_record_parser: UnknownRecordParser {},
 
+            sections: sections,
             parsed_sections: HashMap::new(),
         }
     }
 
     pub fn select<'p, 'r>(&'p mut self, record: &'r StringRecord) -> GenericResult<(RecordSpec<'r>, &'p mut dyn RecordParser)> {
         let spec = parse_header(record);
+
+        if !self.sections.contains(get_required_sections(spec.name)) {
+            trace!("Skipping {} section.", spec.name);
+            return Ok((spec, &mut self.skipped_record_parser));
+        }
+
         let mut parser: &mut dyn RecordParser = match spec.name {
             "Statement" => &mut self.statement_info_parser,
             "Account Information" => &mut self.account_information_parser,
@@ -85,6 +98,7 @@ impl SectionParsers {
             "Deposits & Withdrawals" => &mut self.deposits_and_withdrawals_parser,
             "Fees" => &mut self.fees_parser,
             "Dividends" => &mut self.dividends_parser,
+            "Open Dividend Accruals" => &mut self.open_dividend_accruals_parser,
             "Withholding Tax" => &mut self.withholding_tax_parser,
             "Interest" => &mut self.interest_parser,
             "Financial Instrument Information" => &mut self.financial_instrument_information_parser,
@@ -140,10 +154,41 @@ impl SectionParsers {
     }
 }
 
+fn get_required_sections(name: &str) -> StatementSections {
+    match name {
+        "Trades" | "Corporate Actions" | "Grant Activity" => StatementSections::TRADES,
+        // Statement of Funds is used only to determine cash flow dates of dividends and withheld taxes
+        "Dividends" | "Withholding Tax" | "Statement of Funds" => StatementSections::DIVIDENDS,
+        "Deposits & Withdrawals" | "Fees" | "Interest" => StatementSections::CASH_FLOWS,
+        _ => StatementSections::empty(),
+    }
+}
+
 fn parse_header(record: &StringRecord) -> RecordSpec {
     let offset = 2;
     let name = record.get(0).unwrap();
     let fields = record.iter().skip(offset).collect::<Vec<_>>();
     trace!("Header: {}: {}.", name, format_record(fields.iter().cloned()));
     RecordSpec::new(name, fields, offset)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(name, required,
+        case("Open Positions", true),
+        case("Cash Report", true),
+        case("Open Dividend Accruals", true),
+        case("Financial Instrument Information", true),
+        case("Trades", false),
+        case("Dividends", false),
+        case("Statement of Funds", false),
+        case("Fees", false),
+    )]
+    fn section_skipping(name: &str, required: bool) {
+        assert_eq!(StatementSections::empty().contains(get_required_sections(name)), required);
+        assert!(StatementSections::all().contains(get_required_sections(name)));
+    }
+}
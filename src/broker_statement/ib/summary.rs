@@ -5,6 +5,7 @@ use log::warn;
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
 use crate::time::{self, Date, Period};
+use crate::types::DateTime;
 use crate::util::DecimalRestrictions;
 
 use super::StatementParser;
@@ -14,10 +15,16 @@ pub struct StatementInfoParser {}
 
 impl RecordParser for StatementInfoParser {
     fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
-        if record.get_value("Field Name")? == "Period" {
-            let period = record.get_value("Field Value")?;
-            let period = parse_period(period)?;
-            parser.statement.set_period(period)?;
+        match record.get_value("Field Name")? {
+            "Period" => {
+                let period = parse_period(record.get_value("Field Value")?)?;
+                parser.statement.set_period(period)?;
+            },
+            "WhenGenerated" => {
+                let time = parse_generation_time(record.get_value("Field Value")?)?;
+                parser.statement.set_generation_time(time)?;
+            },
+            _ => {},
         }
 
         Ok(())
@@ -65,7 +72,13 @@ impl RecordParser for NavParser {
         parser.statement.assets.other.get_or_insert_with(|| Cash::zero(&currency));
 
         match asset_class {
-            "Cash" | "Dividend Accruals" | "Interest Accruals"| "Total" => {},
+            "Cash" | "Total" => {},
+
+            // Dividends and interest which are already accrued, but not paid yet
+            "Dividend Accruals" | "Interest Accruals" => {
+                let amount = record.parse_amount("Current Total", DecimalRestrictions::No)?;
+                parser.statement.assets.receivables.get_or_insert_with(|| Cash::zero(&currency)).amount += amount;
+            },
 
             // Appear when Stock Yield Enhancement Program is enabled:
             // * Securities Lent - total value of lent securities
@@ -126,6 +139,13 @@ fn parse_period_date(date: &str) -> GenericResult<Date> {
     time::parse_date(date, "%B %d, %Y")
 }
 
+// Generation time is specified in the account's time zone which is the same for all statements, so we may ignore it
+fn parse_generation_time(time: &str) -> GenericResult<DateTime> {
+    let local_time = time.rsplit_once(' ').map(|(local_time, _timezone)| local_time).ok_or_else(|| format!(
+        "Invalid statement generation time: {:?}", time))?;
+    time::parse_date_time(local_time, "%Y-%m-%d, %H:%M:%S")
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -141,4 +161,11 @@ mod tests {
         assert_eq!(period.first_date(), first);
         assert_eq!(period.last_date(), last);
     }
+
+    #[test]
+    fn generation_time_parsing() {
+        assert_eq!(
+            parse_generation_time("2021-01-04, 04:46:44 EST").unwrap(),
+            date_time!(2021, 1, 4, 4, 46, 44));
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::broker_statement::trades::{ForexTrade, StockBuy, StockSell};
+use crate::broker_statement::trades::{DerivativeTrade, DerivativeType, ForexTrade, StockBuy, StockSell};
 use crate::core::EmptyResult;
 use crate::time::DateTime;
 use crate::util::DecimalRestrictions;
@@ -38,6 +38,9 @@ impl RecordParser for TradesParser {
         match asset_category {
             "Forex" => parse_forex_record(parser, record, symbol, conclusion_time),
             "Stocks" => parse_stock_record(parser, record, symbol, conclusion_time),
+            "Equity and Index Options" => parse_derivative_record(
+                parser, record, DerivativeType::Option, symbol, conclusion_time),
+            "Futures" => parse_derivative_record(parser, record, DerivativeType::Futures, symbol, conclusion_time),
             _ => Err!("Unsupported asset category: {}", asset_category),
         }
     }
@@ -107,4 +110,26 @@ fn parse_stock_record(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+fn parse_derivative_record(
+    parser: &mut StatementParser, record: &Record, type_: DerivativeType, symbol: &str, conclusion_time: DateTime,
+) -> EmptyResult {
+    let currency = record.get_value("Currency")?;
+    let quantity = record.parse_quantity("Quantity", DecimalRestrictions::NonZero)?;
+    let commission = -record.parse_cash("Comm/Fee", currency, DecimalRestrictions::NegativeOrZero)?;
+    let execution_date = parser.get_execution_date(symbol, conclusion_time);
+
+    // Price is zero for option exercises, assignments and expirations. Volume includes contract multiplier.
+    let price = record.parse_cash("T. Price", currency, DecimalRestrictions::PositiveOrZero)?;
+    let volume = record.parse_cash("Proceeds", currency, if quantity.is_sign_positive() {
+        DecimalRestrictions::NegativeOrZero
+    } else {
+        DecimalRestrictions::PositiveOrZero
+    })?;
+
+    parser.statement.derivative_trades.push(DerivativeTrade::new(
+        type_, symbol, quantity, price, volume, commission, conclusion_time.into(), execution_date));
+
+    Ok(())
+}
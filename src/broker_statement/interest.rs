@@ -22,4 +22,18 @@ impl IdleCashInterest {
         let amount = converter.convert_to_cash_rounding(self.date, self.amount, country.currency)?;
         Ok(calculator.tax_income(IncomeType::Interest, self.date.year(), amount, None).expected)
     }
+}
+
+/// Interest paid on borrowed cash (margin loan)
+pub struct MarginInterest {
+    pub date: Date,
+    pub amount: Cash, // May be negative in case of refund
+}
+
+impl MarginInterest {
+    pub fn new(date: Date, amount: Cash) -> MarginInterest {
+        MarginInterest {
+            date, amount
+        }
+    }
 }
\ No newline at end of file
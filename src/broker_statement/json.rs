@@ -0,0 +1,541 @@
+//! Normalized JSON statement format.
+//!
+//! It's a broker-independent representation of a parsed statement which is used by statement plugins, for statement
+//! export/import and for hand-crafted statements of brokers which don't provide any parseable reports. The format is
+//! described in docs/brokers.md.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, CashAssets};
+use crate::exchanges::Exchange;
+use crate::formatting;
+use crate::instruments::{self, InstrumentId};
+use crate::time::{self, Date, DateOptTime, Period};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::fees::Fee;
+use super::interest::IdleCashInterest;
+use super::partial::PartialBrokerStatement;
+use super::payments::{Payments, Withholding};
+use super::reader::BrokerStatementReader;
+use super::trades::{ForexTrade, StockBuy, StockSell, StockSellType, StockSource};
+
+const FILE_EXTENSION: &str = ".json";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonStatement {
+    version: u32,
+    period: PeriodInfo,
+    starting_assets: bool,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cash_assets: Vec<CashInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    deposits_and_withdrawals: Vec<CashFlowInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fees: Vec<FeeInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    interest: Vec<CashFlowInfo>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    forex_trades: Vec<ForexTradeInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trades: Vec<TradeInfo>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dividends: Vec<PaymentInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    taxes: Vec<PaymentInfo>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    open_positions: Vec<PositionInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    instruments: Vec<InstrumentInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PeriodInfo {
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CashInfo {
+    currency: String,
+    amount: Decimal,
+}
+
+impl From<Cash> for CashInfo {
+    fn from(cash: Cash) -> CashInfo {
+        CashInfo {currency: cash.currency.to_owned(), amount: cash.amount.normalize()}
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CashFlowInfo {
+    date: String,
+    currency: String,
+    amount: Decimal,
+}
+
+impl CashFlowInfo {
+    fn new(date: Date, amount: Cash) -> CashFlowInfo {
+        CashFlowInfo {date: format_date(date), currency: amount.currency.to_owned(), amount: amount.amount.normalize()}
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeeInfo {
+    date: String,
+    currency: String,
+    amount: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ForexTradeInfo {
+    date: String,
+    from: CashInfo,
+    to: CashInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commission: Option<CashInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TradeInfo {
+    date: String,
+    settlement_date: String,
+    symbol: String,
+    currency: String,
+    quantity: Decimal,
+    price: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    volume: Option<Decimal>,
+    #[serde(default)]
+    commission: Decimal,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PaymentInfo {
+    date: String,
+    symbol: String,
+    currency: String,
+    amount: Decimal,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PositionInfo {
+    symbol: String,
+    quantity: Decimal,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstrumentInfo {
+    symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    isin: Option<String>,
+}
+
+impl JsonStatement {
+    pub fn parse(data: &[u8]) -> GenericResult<JsonStatement> {
+        let statement: JsonStatement = serde_json::from_slice(data)?;
+        if statement.version != FORMAT_VERSION {
+            return Err!("Unsupported statement format version: {}", statement.version);
+        }
+        Ok(statement)
+    }
+
+    pub fn read(path: &Path) -> GenericResult<JsonStatement> {
+        let data = fs::read(path)?;
+        JsonStatement::parse(&data)
+    }
+
+    /// Converts the statement to the normalized format. Fails on the data which is not supported by the format yet.
+    pub fn new(statement: &PartialBrokerStatement) -> GenericResult<JsonStatement> {
+        let period = statement.get_period()?;
+
+        for (name, empty) in [
+            ("cash flows", statement.cash_flows.is_empty()),
+            ("margin interest", statement.margin_interest.is_empty()),
+            ("tax agent withholdings", statement.tax_agent_withholdings.is_empty()),
+            ("coupons", statement.coupons.is_empty()),
+            ("derivative trades", statement.derivative_trades.is_empty()),
+            ("cash grants", statement.cash_grants.is_empty()),
+            ("stock grants", statement.stock_grants.is_empty()),
+            ("corporate actions", statement.corporate_actions.is_empty()),
+        ] {
+            if !empty {
+                return Err!("The statement has {} which are not supported by JSON statement format yet", name);
+            }
+        }
+
+        let mut json = JsonStatement {
+            version: FORMAT_VERSION,
+            period: PeriodInfo {
+                from: format_date(period.first_date()),
+                to: format_date(period.last_date()),
+            },
+            starting_assets: statement.get_has_starting_assets()?,
+
+            cash_assets: Vec::new(),
+            deposits_and_withdrawals: statement.deposits_and_withdrawals.iter()
+                .map(|assets| CashFlowInfo::new(assets.date, assets.cash))
+                .collect(),
+            fees: statement.fees.iter().map(|fee| {
+                let amount = fee.amount.withholding();
+                FeeInfo {
+                    date: format_date(fee.date),
+                    currency: amount.currency.to_owned(),
+                    amount: amount.amount.normalize(),
+                    description: fee.description.clone(),
+                }
+            }).collect(),
+            interest: statement.idle_cash_interest.iter()
+                .map(|interest| CashFlowInfo::new(interest.date, interest.amount))
+                .collect(),
+
+            forex_trades: statement.forex_trades.iter().map(|trade| ForexTradeInfo {
+                date: format_date_opt_time(trade.conclusion_time),
+                from: trade.from.into(),
+                to: trade.to.into(),
+                commission: Some(trade.commission.into()),
+            }).collect(),
+            trades: Vec::new(),
+
+            dividends: export_payments(&statement.dividend_accruals.iter()
+                .map(|(id, accruals)| (&id.issuer, accruals)).collect::<Vec<_>>())?,
+            taxes: export_payments(&statement.tax_accruals.iter()
+                .map(|(id, accruals)| (&id.issuer, accruals)).collect::<Vec<_>>())?,
+
+            open_positions: Vec::new(),
+            instruments: Vec::new(),
+        };
+
+        if let Some(ref cash_assets) = statement.assets.cash {
+            json.cash_assets = cash_assets.iter().map(Into::into).collect();
+            json.cash_assets.sort_by(|a, b| a.currency.cmp(&b.currency));
+        }
+
+        for trade in &statement.stock_buys {
+            let StockSource::Trade {price, volume, commission} = trade.type_ else {
+                return Err!("{} stock buy from {} is not a trade which is not supported by JSON statement format yet",
+                            trade.symbol, formatting::format_date(trade.conclusion_time));
+            };
+            json.trades.push(TradeInfo::new(
+                &trade.symbol, trade.quantity, price, volume, commission, trade.conclusion_time,
+                trade.execution_date)?);
+        }
+
+        for trade in &statement.stock_sells {
+            let StockSellType::Trade {price, volume, commission} = trade.type_ else {
+                return Err!("{} stock sell from {} is not a trade which is not supported by JSON statement format yet",
+                            trade.symbol, formatting::format_date(trade.conclusion_time));
+            };
+            json.trades.push(TradeInfo::new(
+                &trade.symbol, -trade.quantity, price, volume, commission, trade.conclusion_time,
+                trade.execution_date)?);
+        }
+        json.trades.sort_by(|a, b| a.date.cmp(&b.date));
+
+        for (symbol, &quantity) in &statement.open_positions {
+            json.open_positions.push(PositionInfo {symbol: symbol.clone(), quantity: quantity.normalize()});
+        }
+        json.open_positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+        for instrument in statement.instrument_info.iter() {
+            let isin = match instrument.isin.len() {
+                0 => None,
+                1 => instrument.isin.iter().next().map(ToString::to_string),
+                _ => return Err!("{} has several ISINs which is not supported by JSON statement format yet",
+                                 instrument.symbol),
+            };
+
+            let name = instrument.name().map(ToOwned::to_owned);
+            if name.is_some() || isin.is_some() {
+                json.instruments.push(InstrumentInfo {symbol: instrument.symbol.clone(), name, isin});
+            }
+        }
+        json.instruments.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+        Ok(json)
+    }
+
+    pub fn to_json(&self) -> GenericResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn convert(self, exchanges: &[Exchange]) -> GenericResult<PartialBrokerStatement> {
+        let mut statement = PartialBrokerStatement::new(exchanges, true);
+        self.convert_to(&mut statement)?;
+        statement.validate()
+    }
+
+    fn convert_to(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        statement.set_period(Period::new(parse_date(&self.period.from)?, parse_date(&self.period.to)?)?)?;
+        statement.set_has_starting_assets(self.starting_assets)?;
+
+        let cash_assets = statement.assets.cash.as_mut().unwrap();
+        for assets in self.cash_assets {
+            if cash_assets.has_assets(&assets.currency) {
+                return Err!("Got duplicated {} cash assets", assets.currency);
+            }
+            cash_assets.deposit(parse_cash("cash assets", &assets, DecimalRestrictions::No)?);
+        }
+
+        for deposit in self.deposits_and_withdrawals {
+            statement.deposits_and_withdrawals.push(CashAssets::new(
+                parse_date(&deposit.date)?, &deposit.currency,
+                util::validate_named_decimal("deposit amount", deposit.amount, DecimalRestrictions::NonZero)?));
+        }
+
+        for fee in self.fees {
+            let amount = util::validate_named_cash(
+                "fee amount", &fee.currency, fee.amount, DecimalRestrictions::NonZero)?;
+            statement.fees.push(Fee::new(parse_date(&fee.date)?, Withholding::new(amount), fee.description));
+        }
+
+        for interest in self.interest {
+            let amount = util::validate_named_cash(
+                "interest amount", &interest.currency, interest.amount, DecimalRestrictions::NonZero)?;
+            statement.idle_cash_interest.push(IdleCashInterest::new(parse_date(&interest.date)?, amount));
+        }
+
+        for trade in self.forex_trades {
+            let from = parse_cash("forex trade volume", &trade.from, DecimalRestrictions::StrictlyPositive)?;
+            let to = parse_cash("forex trade volume", &trade.to, DecimalRestrictions::StrictlyPositive)?;
+            let commission = match trade.commission {
+                Some(ref commission) => parse_cash("commission", commission, DecimalRestrictions::PositiveOrZero)?,
+                None => Cash::zero(from.currency),
+            };
+            statement.forex_trades.push(ForexTrade::new(parse_date_opt_time(&trade.date)?, from, to, commission));
+        }
+
+        for trade in self.trades {
+            trade.convert(statement).map_err(|e| format!(
+                "Failed to process {} trade from {}: {}", trade.symbol, trade.date, e))?;
+        }
+
+        for dividend in self.dividends {
+            let date = parse_date(&dividend.date)?;
+            let amount = util::validate_named_cash(
+                "dividend amount", &dividend.currency, dividend.amount, DecimalRestrictions::NonZero)?;
+
+            let accruals = statement.dividend_accruals(date, InstrumentId::Symbol(dividend.symbol), true);
+            if amount.is_negative() {
+                accruals.reverse(date, -amount);
+            } else {
+                accruals.add(date, amount);
+            }
+        }
+
+        for tax in self.taxes {
+            let date = parse_date(&tax.date)?;
+            let amount = util::validate_named_cash(
+                "tax amount", &tax.currency, tax.amount, DecimalRestrictions::NonZero)?;
+
+            let accruals = statement.tax_accruals(date, InstrumentId::Symbol(tax.symbol), true);
+            if amount.is_negative() {
+                accruals.reverse(date, -amount);
+            } else {
+                accruals.add(date, amount);
+            }
+        }
+
+        for position in self.open_positions {
+            statement.add_open_position(&position.symbol, position.quantity)?;
+        }
+
+        for info in self.instruments {
+            let instrument = statement.instrument_info.get_or_add(&info.symbol);
+            if let Some(ref name) = info.name {
+                instrument.set_name(name);
+            }
+            if let Some(ref isin) = info.isin {
+                instrument.add_isin(instruments::parse_isin(isin)?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TradeInfo {
+    fn new(
+        symbol: &str, quantity: Decimal, price: Cash, volume: Cash, commission: Cash,
+        conclusion_time: DateOptTime, execution_date: Date,
+    ) -> GenericResult<TradeInfo> {
+        if volume.currency != price.currency || commission.currency != price.currency {
+            return Err!(
+                "{} trade from {} has commission or volume in a currency other than trade currency which is not \
+                 supported by JSON statement format yet", symbol, formatting::format_date(conclusion_time));
+        }
+
+        Ok(TradeInfo {
+            date: format_date_opt_time(conclusion_time),
+            settlement_date: format_date(execution_date),
+            symbol: symbol.to_owned(),
+            currency: price.currency.to_owned(),
+            quantity: quantity.normalize(),
+            price: price.amount.normalize(),
+            volume: Some(volume.amount.normalize()),
+            commission: commission.amount.normalize(),
+        })
+    }
+
+    fn convert(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let conclusion_time = parse_date_opt_time(&self.date)?;
+        let execution_date = parse_date(&self.settlement_date)?;
+
+        let quantity = util::validate_named_decimal("quantity", self.quantity, DecimalRestrictions::NonZero)?;
+        let price = util::validate_named_cash(
+            "price", &self.currency, self.price, DecimalRestrictions::StrictlyPositive)?;
+        let commission = util::validate_named_cash(
+            "commission", &self.currency, self.commission, DecimalRestrictions::PositiveOrZero)?;
+        let volume = match self.volume {
+            Some(volume) => util::validate_named_cash(
+                "volume", &self.currency, volume, DecimalRestrictions::StrictlyPositive)?,
+            None => (price * quantity.abs()).round(),
+        };
+
+        if quantity.is_sign_positive() {
+            statement.stock_buys.push(StockBuy::new_trade(
+                &self.symbol, quantity, price, volume, commission, conclusion_time, execution_date));
+        } else {
+            statement.stock_sells.push(StockSell::new_trade(
+                &self.symbol, -quantity, price, volume, commission, conclusion_time, execution_date, false));
+        }
+
+        Ok(())
+    }
+}
+
+fn export_payments(accruals: &[(&InstrumentId, &Payments)]) -> GenericResult<Vec<PaymentInfo>> {
+    let mut payments = Vec::new();
+
+    for &(issuer, accruals) in accruals {
+        let InstrumentId::Symbol(symbol) = issuer else {
+            return Err!("Got a payment with {} issuer which is not supported by JSON statement format yet", issuer);
+        };
+
+        for transaction in accruals.transactions() {
+            payments.push(PaymentInfo {
+                date: format_date(transaction.date),
+                symbol: symbol.clone(),
+                currency: transaction.cash.currency.to_owned(),
+                amount: transaction.cash.amount.normalize(),
+            });
+        }
+    }
+
+    payments.sort_by(|a, b| (&a.date, &a.symbol).cmp(&(&b.date, &b.symbol)));
+    Ok(payments)
+}
+
+/// Reads statements in the normalized JSON format and passes all other files to the broker's native reader.
+pub struct JsonStatementReader {
+    reader: Box<dyn BrokerStatementReader>,
+    exchanges: Vec<Exchange>,
+}
+
+impl JsonStatementReader {
+    pub fn new(exchanges: Vec<Exchange>, reader: Box<dyn BrokerStatementReader>) -> Box<dyn BrokerStatementReader> {
+        Box::new(JsonStatementReader {reader, exchanges})
+    }
+}
+
+impl BrokerStatementReader for JsonStatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        Ok(path.ends_with(FILE_EXTENSION) || self.reader.check(path)?)
+    }
+
+    fn read(&mut self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        if !path.ends_with(FILE_EXTENSION) {
+            return self.reader.read(path, is_last);
+        }
+        JsonStatement::read(Path::new(path))?.convert(&self.exchanges)
+    }
+
+    fn close(self: Box<Self>) -> EmptyResult {
+        self.reader.close()
+    }
+}
+
+fn parse_date(date: &str) -> GenericResult<Date> {
+    time::parse_date(date, "%Y-%m-%d")
+}
+
+fn parse_date_opt_time(date: &str) -> GenericResult<DateOptTime> {
+    Ok(match date.len() {
+        10 => parse_date(date)?.into(),
+        _ => time::parse_date_time(date, "%Y-%m-%dT%H:%M:%S")?.into(),
+    })
+}
+
+fn format_date(date: Date) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn format_date_opt_time(time: DateOptTime) -> String {
+    match time.time {
+        Some(_) => time.or_min_time().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        None => format_date(time.date),
+    }
+}
+
+fn parse_cash(name: &str, cash: &CashInfo, restrictions: DecimalRestrictions) -> GenericResult<Cash> {
+    util::validate_named_cash(name, &cash.currency, cash.amount, restrictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let statement = JsonStatement::parse(include_bytes!("testdata/json-statement.json")).unwrap();
+        let partial = statement.convert(&[Exchange::Us]).unwrap();
+
+        assert_eq!(partial.period.unwrap(), Period::new(date!(2023, 1, 1), date!(2023, 12, 31)).unwrap());
+        assert_eq!(partial.assets.cash.as_ref().unwrap().get("USD"), Some(Cash::new("USD", dec!(395.5))));
+        assert_eq!(partial.deposits_and_withdrawals.len(), 1);
+        assert_eq!(partial.fees.len(), 1);
+        assert_eq!(partial.idle_cash_interest.len(), 1);
+        assert_eq!(partial.forex_trades.len(), 1);
+        assert_eq!(partial.stock_buys.len(), 1);
+        assert_eq!(partial.stock_sells.len(), 1);
+        assert_eq!(partial.dividend_accruals.len(), 1);
+        assert_eq!(partial.tax_accruals.len(), 1);
+        assert_eq!(partial.open_positions.get("AAPL"), Some(&dec!(5)));
+        assert_eq!(partial.instrument_info.get_name("AAPL"), "Apple Inc. (AAPL)");
+    }
+
+    #[test]
+    fn export() {
+        let statement = JsonStatement::parse(include_bytes!("testdata/json-statement.json")).unwrap();
+        let partial = statement.convert(&[Exchange::Us]).unwrap();
+
+        let exported = JsonStatement::new(&partial).unwrap().to_json().unwrap();
+        let partial = JsonStatement::parse(exported.as_bytes()).unwrap().convert(&[Exchange::Us]).unwrap();
+
+        assert_eq!(JsonStatement::new(&partial).unwrap().to_json().unwrap(), exported);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use crate::brokers::BrokerInfo;
+use crate::core::GenericResult;
+use crate::currency::{Cash, CashAssets};
+use crate::portfolio::ManualTrade;
+use crate::time::{self, Period};
+use crate::types::Decimal;
+
+use super::BrokerStatement;
+use super::partial::PartialBrokerStatement;
+use super::trades::{StockBuy, StockSell};
+
+impl BrokerStatement {
+    /// Builds a statement from the trades recorded manually for portfolios without broker statements. Each buy is
+    /// considered to be funded by a deposit of the same amount and each sell to be followed by a withdrawal, so the
+    /// portfolio never holds any cash.
+    pub fn new_manual(
+        broker: BrokerInfo, trades: &[ManualTrade], instrument_names: &HashMap<String, String>,
+    ) -> GenericResult<BrokerStatement> {
+        let first_date = trades.first().map(|trade| trade.date).ok_or(
+            "The portfolio has neither broker statements nor manually recorded trades")?;
+
+        let mut partial = PartialBrokerStatement::new(&broker.exchanges(), true);
+        partial.set_period(Period::new(first_date, time::today())?)?;
+        partial.set_has_starting_assets(false)?;
+
+        let mut open_positions: HashMap<&str, Decimal> = HashMap::new();
+
+        for trade in trades {
+            let symbol = &trade.symbol;
+            let quantity = trade.quantity.abs();
+            let volume = trade.price * quantity;
+            let commission = Cash::zero(trade.price.currency);
+
+            if trade.quantity.is_sign_positive() {
+                partial.deposits_and_withdrawals.push(CashAssets::new_from_cash(trade.date, volume));
+                partial.stock_buys.push(StockBuy::new_trade(
+                    symbol, quantity, trade.price, volume, commission, trade.date.into(), trade.date));
+            } else {
+                partial.stock_sells.push(StockSell::new_trade(
+                    symbol, quantity, trade.price, volume, commission, trade.date.into(), trade.date, false));
+                partial.deposits_and_withdrawals.push(CashAssets::new_from_cash(trade.date, -volume));
+            }
+
+            *open_positions.entry(symbol).or_default() += trade.quantity;
+        }
+
+        for (symbol, quantity) in open_positions {
+            if !quantity.is_zero() {
+                partial.add_open_position(symbol, quantity)?;
+            }
+        }
+
+        let mut statement = BrokerStatement::new_empty_from(broker, &partial)?;
+        let last_date = statement.period.last_date();
+        statement.merge(partial, last_date, true, true)?;
+
+        for (symbol, name) in instrument_names {
+            statement.instrument_info.get_or_add(symbol).set_name(name);
+        }
+
+        statement.validate()?;
+        statement.process_trades(None)?;
+
+        Ok(statement)
+    }
+}
@@ -1,8 +1,11 @@
 use chrono::{Datelike, Weekday};
+use log::{debug, warn};
 
 use crate::core::EmptyResult;
 use crate::time::{Date, Month, Period};
 
+use super::PartialBrokerStatement;
+
 #[derive(Clone, Copy)]
 pub enum StatementsMergingStrategy {
     ContinuousOnly,
@@ -16,6 +19,75 @@ pub enum StatementsMergingStrategy {
 }
 
 impl StatementsMergingStrategy {
+    /// Brokers reissue corrected statements for past periods, so when several statements cover the same period, the
+    /// newest one (by its generation time) replaces the others. Statements without generation time are left as is.
+    pub fn replace_reissued(
+        mut statements: Vec<(String, PartialBrokerStatement)>,
+    ) -> Vec<(String, PartialBrokerStatement)> {
+        statements.sort_by_key(|(_, statement)| (statement.period, statement.generation_time));
+
+        let mut result: Vec<(String, PartialBrokerStatement)> = Vec::with_capacity(statements.len());
+
+        for (name, statement) in statements {
+            if let Some((prev_name, prev)) = result.last_mut() {
+                let reissued = prev.period == statement.period && matches!(
+                    (prev.generation_time, statement.generation_time), (Some(prev), Some(next)) if prev < next);
+
+                if reissued {
+                    warn!("{} broker statement is replaced with a newer {} one for the same period ({}).",
+                          prev_name, name, statement.period.unwrap().format());
+                    *prev_name = name;
+                    *prev = statement;
+                    continue;
+                }
+            }
+
+            result.push((name, statement));
+        }
+
+        result
+    }
+
+    /// Users often keep both consolidated (yearly) and monthly statements, so statements which periods are fully
+    /// covered by some other statement are skipped in favor of the consolidated one.
+    pub fn skip_covered(
+        mut statements: Vec<(String, PartialBrokerStatement)>,
+    ) -> Vec<(String, PartialBrokerStatement)> {
+        statements.sort_by_key(|(_, statement)| {
+            let period = statement.period.unwrap();
+            (period.first_date(), std::cmp::Reverse(period.last_date()))
+        });
+
+        let mut result: Vec<(String, PartialBrokerStatement)> = Vec::with_capacity(statements.len());
+        let mut covering: Option<usize> = None;
+
+        for (name, statement) in statements {
+            let period = statement.period.unwrap();
+
+            if let Some(index) = covering {
+                let (covering_name, covering_statement) = &result[index];
+                let covering_period = covering_statement.period.unwrap();
+
+                if period != covering_period && covering_period.contains(period.first_date())
+                    && covering_period.contains(period.last_date()) {
+                    debug!("Skipping {} broker statement ({}): it's covered by {} ({}).",
+                           name, period.format(), covering_name, covering_period.format());
+                    continue;
+                }
+
+                if period.last_date() > covering_period.last_date() {
+                    covering = Some(result.len());
+                }
+            } else {
+                covering = Some(result.len());
+            }
+
+            result.push((name, statement));
+        }
+
+        result
+    }
+
     pub fn validate(self, first: Period, second: Period, last_date: Date) -> EmptyResult {
         let error = |message| Err!("{}: {}, {}", message, first.format(), second.format());
 
@@ -87,8 +159,55 @@ impl StatementsMergingStrategy {
 mod tests {
     use matches::assert_matches;
     use rstest::rstest;
+
+    use crate::types::DateTime;
+
     use super::*;
 
+    #[test]
+    fn reissued_statements() {
+        let statement = |period: Period, generation_time: Option<DateTime>| {
+            let mut statement = PartialBrokerStatement::new(&[], true);
+            statement.period = Some(period);
+            statement.generation_time = generation_time;
+            statement
+        };
+
+        let january = Period::new(date!(2021, 1, 1), date!(2021, 1, 31)).unwrap();
+        let february = Period::new(date!(2021, 2, 1), date!(2021, 2, 28)).unwrap();
+
+        let statements = StatementsMergingStrategy::replace_reissued(vec![
+            (s!("february.csv"), statement(february, Some(date_time!(2021, 3, 1, 0, 0, 0)))),
+            (s!("january-corrected.csv"), statement(january, Some(date_time!(2021, 3, 1, 0, 0, 0)))),
+            (s!("january.csv"), statement(january, Some(date_time!(2021, 2, 1, 0, 0, 0)))),
+            (s!("february-unknown.csv"), statement(february, None)),
+        ]);
+
+        let names: Vec<_> = statements.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["january-corrected.csv", "february-unknown.csv", "february.csv"]);
+    }
+
+    #[test]
+    fn covered_statements() {
+        let statement = |first: Date, last: Date| {
+            let mut statement = PartialBrokerStatement::new(&[], true);
+            statement.period = Some(Period::new(first, last).unwrap());
+            statement
+        };
+
+        let statements = StatementsMergingStrategy::skip_covered(vec![
+            (s!("2021-02.csv"), statement(date!(2021, 2, 1), date!(2021, 2, 28))),
+            (s!("2022-01.csv"), statement(date!(2022, 1, 1), date!(2022, 1, 31))),
+            (s!("2021.csv"), statement(date!(2021, 1, 1), date!(2021, 12, 31))),
+            (s!("2021-12.csv"), statement(date!(2021, 12, 1), date!(2021, 12, 31))),
+            (s!("2022-01-other.csv"), statement(date!(2022, 1, 1), date!(2022, 1, 31))),
+            (s!("2021-12-2022-02.csv"), statement(date!(2021, 12, 1), date!(2022, 2, 28))),
+        ]);
+
+        let names: Vec<_> = statements.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["2021.csv", "2021-12-2022-02.csv"]);
+    }
+
     #[rstest(delay, first, second, last, ok,
         // Previous month: continuous
         case(0, date!(2021, 12, 29), date!(2021, 12, 30), date!(2022, 1, 4), true),
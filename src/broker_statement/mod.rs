@@ -1,26 +1,37 @@
+mod bonds;
 mod cash_flows;
 mod corporate_actions;
+mod derivatives;
+mod dividend_validation;
 mod dividends;
 mod fees;
 mod grants;
 mod interest;
+mod json;
+mod manual;
 mod merging;
 mod partial;
 mod payments;
+mod plugins;
 mod reader;
+mod repo;
 mod taxes;
 mod trades;
 mod validators;
 
+#[cfg(feature = "bench")] pub mod bench;
+
+mod alfa;
 mod bcs;
 mod firstrade;
 mod ib;
 mod open;
 mod sber;
+mod schwab;
 mod tbank;
 
 use std::cmp::Ordering;
-use std::collections::{HashMap, BTreeMap, BTreeSet, hash_map::Entry};
+use std::collections::{HashMap, BTreeMap, BTreeSet, VecDeque, hash_map::Entry};
 
 use itertools::Itertools;
 use log::{debug, warn};
@@ -32,7 +43,8 @@ use crate::currency::{Cash, CashAssets, MultiCurrencyCashAccount};
 use crate::currency::converter::CurrencyConverter;
 use crate::exchanges::{Exchange, Exchanges, TradingMode};
 use crate::formatting;
-use crate::instruments::{InstrumentInternalIds, InstrumentInfo};
+use crate::instruments::{InstrumentId, InstrumentInternalIds, InstrumentInfo};
+use crate::profiling::{self, Phase};
 use crate::quotes::{Quotes, QuoteQuery};
 use crate::taxes::{TaxRemapping, TaxExemption, long_term_ownership};
 use crate::time::{self, Date, DateOptTime, Period};
@@ -45,17 +57,25 @@ use self::reader::BrokerStatementReader;
 use self::taxes::{TaxId, TaxAccruals, TaxAgentWithholdings};
 use self::validators::{DateValidator, sort_and_validate_trades};
 
+pub use self::bonds::Coupon;
 pub use self::cash_flows::{CashFlow, CashFlowType};
-pub use self::corporate_actions::{CorporateAction, StockSplitController, process_corporate_actions};
-pub use self::dividends::Dividend;
+pub use self::corporate_actions::{
+    CorporateAction, CorporateActionType, StockSplitController, StockSplitRatio, process_corporate_actions};
+pub use self::dividend_validation::DividendHistory;
+pub use self::derivatives::DerivativeIncome;
+pub use self::dividends::{Dividend, PendingDividend};
 pub use self::fees::Fee;
-pub use self::grants::{CashGrant, StockGrant, process_grants};
-pub use self::interest::IdleCashInterest;
+pub use self::grants::{CashGrant, CashGrantsTaxCalculator, StockGrant, process_grants};
+pub use self::interest::{IdleCashInterest, MarginInterest};
+pub use self::json::JsonStatement;
 pub use self::merging::StatementsMergingStrategy;
 pub use self::payments::Withholding;
-pub use self::reader::ReadingStrictness;
+pub use self::reader::{ReadingStrictness, StatementSections, read_single};
+pub use self::repo::RepoDeal;
 pub use self::taxes::TaxAgentWithholding;
-pub use self::trades::{ForexTrade, StockBuy, StockSource, StockSell, StockSellType, StockSourceDetails, SellDetails, FifoDetails};
+pub use self::trades::{
+    DerivativeTrade, DerivativeType, ForexTrade, SellLot, StockBuy, StockSource, StockSell, StockSellType,
+    StockSourceDetails, SellDetails, FifoDetails};
 
 pub struct BrokerStatement {
     pub broker: BrokerInfo,
@@ -63,25 +83,33 @@ pub struct BrokerStatement {
 
     pub assets: NetAssets,
     pub historical_assets: BTreeMap<Date, NetAssets>,
+    pub pending_dividends: Vec<PendingDividend>,
 
     pub fees: Vec<Fee>,
     pub cash_flows: Vec<CashFlow>,
+    pub repo_deals: Vec<RepoDeal>,
     pub deposits_and_withdrawals: Vec<CashAssets>,
     pub idle_cash_interest: Vec<IdleCashInterest>,
+    pub margin_interest: Vec<MarginInterest>,
     pub tax_agent_withholdings: TaxAgentWithholdings,
 
     pub exchanges: Exchanges,
     pub forex_trades: Vec<ForexTrade>,
     pub stock_buys: Vec<StockBuy>,
     pub stock_sells: Vec<StockSell>,
+    pub derivative_trades: Vec<DerivativeTrade>,
     pub dividends: Vec<Dividend>,
+    pub coupons: Vec<Coupon>,
 
     pub cash_grants: Vec<CashGrant>,
     stock_grants: Vec<StockGrant>,
-    corporate_actions: Vec<CorporateAction>,
+    pub corporate_actions: Vec<CorporateAction>,
     pub stock_splits: StockSplitController,
 
     pub open_positions: HashMap<String, Decimal>,
+    pub pledged_positions: HashMap<String, Decimal>, // Pledged as collateral (supported only for some brokers)
+    pub derivative_positions: HashMap<String, Decimal>,
+    pub derivatives_income: Vec<DerivativeIncome>,
     pub instrument_info: InstrumentInfo,
 }
 
@@ -90,23 +118,15 @@ impl BrokerStatement {
         broker: BrokerInfo, statement_dir_path: &str, symbol_remapping: &HashMap<String, String>,
         instrument_internal_ids: &InstrumentInternalIds, instrument_names: &HashMap<String, String>,
         tax_remapping: TaxRemapping, tax_exemptions: &[TaxExemption], corporate_actions: &[CorporateAction],
-        strictness: ReadingStrictness,
+        strictness: ReadingStrictness, sections: StatementSections,
     ) -> GenericResult<BrokerStatement> {
+        let _timer = profiling::start(Phase::StatementParsing);
         let broker_jurisdiction = broker.type_.jurisdiction();
 
-        let mut statements = reader::read(broker.type_, statement_dir_path, tax_remapping, strictness)?;
+        let mut statements = reader::read(&broker, statement_dir_path, tax_remapping, strictness, sections)?;
         statements.sort_by_key(|statement| statement.period.unwrap());
 
-        let mut last_period = statements.first().unwrap().period.unwrap();
-        for statement in &statements[1..] {
-            let period = statement.period.unwrap();
-            if period.first_date() <= last_period.last_date() {
-                return Err!(
-                    "Overlapping broker statement periods: {} and {}",
-                    last_period.format(), period.format());
-            }
-            last_period = period;
-        }
+        let last_period = statements.last().unwrap().period.unwrap();
 
         let last_index = statements.len() - 1;
         let mut statement = BrokerStatement::new_empty_from(broker, statements.first().unwrap())?;
@@ -182,10 +202,16 @@ impl BrokerStatement {
             statement.instrument_info.get_or_add(symbol).set_name(name);
         }
 
-        statement.validate(strictness)?;
+        statement.validate()?;
 
-        process_corporate_actions(&mut statement)?;
-        statement.process_trades(None)?;
+        // Open positions are declared by the statement, so trades processing is required only for the trade history
+        if sections.contains(StatementSections::TRADES) {
+            process_corporate_actions(&mut statement)?;
+            statement.process_trades(None)?;
+
+            (statement.derivatives_income, statement.derivative_positions) =
+                derivatives::process_derivative_trades(&statement.derivative_trades)?;
+        }
 
         statement.check_otc_instruments(strictness);
         statement.validate_tax_exemptions(tax_exemptions, strictness)?;
@@ -209,18 +235,23 @@ impl BrokerStatement {
 
             assets: NetAssets::default(),
             historical_assets: BTreeMap::new(),
+            pending_dividends: Vec::new(),
 
             fees: Vec::new(),
             cash_flows: Vec::new(),
+            repo_deals: Vec::new(),
             deposits_and_withdrawals: Vec::new(),
             idle_cash_interest: Vec::new(),
+            margin_interest: Vec::new(),
             tax_agent_withholdings: TaxAgentWithholdings::new(),
 
             exchanges: Exchanges::new_empty(),
             forex_trades: Vec::new(),
             stock_buys: Vec::new(),
             stock_sells: Vec::new(),
+            derivative_trades: Vec::new(),
             dividends: Vec::new(),
+            coupons: Vec::new(),
 
             cash_grants: Vec::new(),
             stock_grants: Vec::new(),
@@ -228,10 +259,56 @@ impl BrokerStatement {
             stock_splits: StockSplitController::default(),
 
             open_positions: HashMap::new(),
+            pledged_positions: HashMap::new(),
+            derivative_positions: HashMap::new(),
+            derivatives_income: Vec::new(),
             instrument_info: InstrumentInfo::new(),
         })
     }
 
+    /// Reads only periods of the broker statements from the specified directory without merging them
+    pub fn read_periods(
+        broker: &BrokerInfo, statement_dir_path: &str, tax_remapping: TaxRemapping,
+    ) -> GenericResult<Vec<Period>> {
+        let _timer = profiling::start(Phase::StatementParsing);
+        reader::read_periods(broker, statement_dir_path, tax_remapping)
+    }
+
+    /// Suggests symbols for all broker-specific internal instrument IDs from the broker statements which aren't mapped
+    /// yet. The statements are read without merging, since it's impossible until all the IDs are mapped.
+    pub fn suggest_internal_ids(
+        broker: &BrokerInfo, statement_dir_path: &str, tax_remapping: TaxRemapping,
+        internal_ids: &InstrumentInternalIds,
+    ) -> GenericResult<Vec<(String, Vec<String>)>> {
+        let _timer = profiling::start(Phase::StatementParsing);
+        let mut statements = reader::read(
+            broker, statement_dir_path, tax_remapping, ReadingStrictness::empty(), StatementSections::all())?;
+        statements.sort_by_key(|statement| statement.period.unwrap());
+
+        let mut ids = BTreeSet::new();
+        let mut instrument_info = InstrumentInfo::new();
+
+        for statement in statements {
+            let issuers = statement.dividend_accruals.keys().map(|id| &id.issuer)
+                .chain(statement.tax_accruals.keys().map(|id| &id.issuer));
+
+            for issuer in issuers {
+                if let InstrumentId::InternalId(id) = issuer {
+                    if !internal_ids.contains(id) {
+                        ids.insert(id.clone());
+                    }
+                }
+            }
+
+            instrument_info.merge(statement.instrument_info);
+        }
+
+        Ok(ids.into_iter().map(|id| {
+            let symbols = instrument_info.suggest_internal_id_symbols(&id);
+            (id, symbols)
+        }).collect())
+    }
+
     pub fn check_date(&self) {
         let days = (time::today() - self.period.last_date()).num_days();
         let months = Decimal::from(days) / dec!(30);
@@ -282,10 +359,60 @@ impl BrokerStatement {
         QuoteQuery::Stock(symbol.to_owned(), exchanges.get_prioritized())
     }
 
+    /// Returns quantity of the position which is pledged as collateral and thus can't be sold
+    pub fn pledged_quantity(&self, symbol: &str) -> Decimal {
+        self.pledged_positions.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Returns quantity of the position which is available for selling
+    pub fn free_quantity(&self, symbol: &str) -> Decimal {
+        let quantity = self.open_positions.get(symbol).copied().unwrap_or_default();
+        std::cmp::max(dec!(0), quantity - self.pledged_quantity(symbol))
+    }
+
+    /// Returns declared dividends which are overdue for more than the configured delay and thus may never be paid
+    pub fn at_risk_dividends(&self) -> impl Iterator<Item=&PendingDividend> {
+        let today = time::today();
+        let delay_days = self.broker.at_risk_dividends_days();
+        self.pending_dividends.iter().filter(move |dividend| dividend.is_at_risk(today, delay_days))
+    }
+
+    /// Returns securities lending income which is taxed as interest income
+    pub fn securities_lending_income(&self) -> impl Iterator<Item=IdleCashInterest> + '_ {
+        self.cash_flows.iter()
+            .filter(|cash_flow| matches!(cash_flow.type_, CashFlowType::SecuritiesLending))
+            .map(|cash_flow| IdleCashInterest::new(cash_flow.date.date, cash_flow.amount))
+    }
+
+    /// Returns financial result of the completed repo deals which is taxed as interest income
+    pub fn repo_income(&self) -> impl Iterator<Item=IdleCashInterest> + '_ {
+        self.repo_deals.iter().map(RepoDeal::interest)
+    }
+
+    /// Returns accrued, but not paid yet dividends and interest excluding the dividends which are at risk
+    pub fn expected_receivables(&self, converter: &CurrencyConverter, currency: &str) -> GenericResult<Option<Cash>> {
+        let Some(receivables) = self.assets.receivables else {
+            return Ok(None);
+        };
+
+        let mut amount = converter.real_time_convert_to(receivables, currency)?;
+        for dividend in self.at_risk_dividends() {
+            amount -= converter.real_time_convert_to(dividend.amount, currency)?;
+        }
+
+        Ok(Some(Cash::new(currency, amount)))
+    }
+
     pub fn net_value(
         &self, converter: &CurrencyConverter, quotes: &Quotes, currency: &str, realtime: bool,
     ) -> GenericResult<Cash> {
         let mut net_value = self.assets.cash.clone();
+        if let Some(receivables) = self.assets.receivables {
+            net_value.deposit(receivables);
+            for dividend in self.at_risk_dividends() {
+                net_value.withdraw(dividend.amount);
+            }
+        }
 
         match self.assets.other {
             Some(other) if !realtime => {
@@ -308,6 +435,20 @@ impl BrokerStatement {
         &mut self, symbol: &str, quantity: Decimal, price: Cash,
         commission_calc: &mut CommissionCalc,
     ) -> EmptyResult {
+        self.emulate_sell_lots(symbol, quantity, price, Vec::new(), commission_calc)
+    }
+
+    /// Emulates selling of the specified lots (the rest of the quantity is matched using FIFO)
+    pub fn emulate_sell_lots(
+        &mut self, symbol: &str, quantity: Decimal, price: Cash, lots: Vec<SellLot>,
+        commission_calc: &mut CommissionCalc,
+    ) -> EmptyResult {
+        let lots_quantity: Decimal = lots.iter().map(|lot| lot.quantity).sum();
+        if lots_quantity > quantity {
+            return Err!("The specified {} lots quantity is greater than the quantity to sell: {} vs {}",
+                symbol, lots_quantity, quantity);
+        }
+
         let trading_mode = self.get_instrument_supposed_trading_mode(symbol);
 
         let conclusion_time = crate::exchanges::today_trade_conclusion_time();
@@ -327,9 +468,10 @@ impl BrokerStatement {
         let commission = commission_calc.add_trade(
             conclusion_time.date, TradeType::Sell, quantity, price)?;
 
-        let stock_sell = StockSell::new_trade(
+        let mut stock_sell = StockSell::new_trade(
             symbol, quantity, price, volume, commission,
             conclusion_time, execution_date, true);
+        stock_sell.lots = lots;
 
         if let Entry::Occupied(mut open_position) = self.open_positions.entry(symbol.to_owned()) {
             let available = open_position.get_mut();
@@ -370,15 +512,20 @@ impl BrokerStatement {
     }
 
     pub fn process_trades(&mut self, until: Option<DateOptTime>) -> EmptyResult {
-        let mut unsold_buys: HashMap<String, Vec<usize>> = HashMap::new();
+        // Trades are always sorted by conclusion time here (see sort_and_validate_trades()), so we can cut off the
+        // trades that must be left unprocessed without scanning them.
+        let (buys_count, sells_count) = match until {
+            Some(time) => (
+                self.stock_buys.partition_point(|trade| trade.conclusion_time < time),
+                self.stock_sells.partition_point(|trade| trade.conclusion_time < time),
+            ),
+            None => (self.stock_buys.len(), self.stock_sells.len()),
+        };
 
-        for (index, stock_buy) in self.stock_buys.iter().enumerate().rev() {
-            if let Some(time) = until {
-                if stock_buy.conclusion_time >= time {
-                    continue;
-                }
-            }
+        // Per-symbol queues of unsold lots in FIFO order
+        let mut unsold_buys: HashMap<String, VecDeque<usize>> = HashMap::new();
 
+        for (index, stock_buy) in self.stock_buys[..buys_count].iter().enumerate() {
             if stock_buy.is_sold() {
                 continue;
             }
@@ -388,16 +535,10 @@ impl BrokerStatement {
                 None => unsold_buys.entry(stock_buy.symbol.clone()).or_default(),
             };
 
-            symbol_buys.push(index);
+            symbol_buys.push_back(index);
         }
 
-        for stock_sell in &mut self.stock_sells {
-            if let Some(time) = until {
-                if stock_sell.conclusion_time >= time {
-                    continue;
-                }
-            }
-
+        for stock_sell in &mut self.stock_sells[..sells_count] {
             if stock_sell.is_processed() {
                 continue;
             }
@@ -410,8 +551,44 @@ impl BrokerStatement {
                 stock_sell.original_symbol
             ))?;
 
+            if !stock_sell.lots.is_empty() {
+                for lot in &stock_sell.lots {
+                    let mut lot_quantity = lot.quantity;
+
+                    for &index in symbol_buys.iter() {
+                        let stock_buy = &mut self.stock_buys[index];
+                        if lot_quantity.is_zero() {
+                            break;
+                        } else if stock_buy.conclusion_time.date != lot.date || stock_buy.is_sold() {
+                            continue;
+                        }
+
+                        let multiplier = self.stock_splits.get_multiplier(
+                            &stock_sell.symbol, stock_buy.conclusion_time, stock_sell.conclusion_time);
+
+                        let sell_quantity = std::cmp::min(lot_quantity, multiplier * stock_buy.get_unsold());
+                        let source_quantity = (sell_quantity / multiplier).normalize();
+                        assert_eq!(source_quantity * multiplier, sell_quantity);
+
+                        sources.push(stock_buy.sell(source_quantity, multiplier));
+                        lot_quantity -= sell_quantity;
+                        remaining_quantity -= sell_quantity;
+                    }
+
+                    if !lot_quantity.is_zero() {
+                        return Err!(
+                            "Error while processing {} position closing: {}",
+                            stock_sell.original_symbol, format!(
+                                "There are not enough open positions bought on {}",
+                                formatting::format_date(lot.date)));
+                    }
+                }
+
+                symbol_buys.retain(|&index| !self.stock_buys[index].is_sold());
+            }
+
             while !remaining_quantity.is_zero() {
-                let index = symbol_buys.last().copied().ok_or_else(|| format!(
+                let index = symbol_buys.front().copied().ok_or_else(|| format!(
                     "Error while processing {} position closing: There are no open positions for it",
                     stock_sell.original_symbol
                 ))?;
@@ -431,7 +608,7 @@ impl BrokerStatement {
                 remaining_quantity -= sell_quantity;
 
                 if stock_buy.is_sold() {
-                    symbol_buys.pop();
+                    symbol_buys.pop_front();
                 }
             }
 
@@ -461,9 +638,10 @@ impl BrokerStatement {
             self.period = Period::new(self.period.first_date(), period.last_date()).unwrap();
         }
 
-        if let partial::NetAssets{cash: Some(cash), other} = statement.assets {
-            let assets = NetAssets{cash, other};
+        if let partial::NetAssets{cash: Some(cash), other, receivables} = statement.assets {
+            let assets = NetAssets{cash, other, receivables};
             self.assets = assets.clone();
+            self.pending_dividends = statement.pending_dividends;
             assert!(self.historical_assets.insert(self.period.last_date(), assets).is_none());
         } else if last {
             return Err!("Unable to find any information about current cash assets");
@@ -473,18 +651,22 @@ impl BrokerStatement {
         self.cash_flows.extend(statement.cash_flows);
         self.deposits_and_withdrawals.extend(statement.deposits_and_withdrawals);
         self.idle_cash_interest.extend(statement.idle_cash_interest);
+        self.margin_interest.extend(statement.margin_interest);
         self.tax_agent_withholdings.merge(statement.tax_agent_withholdings);
 
         self.exchanges.merge(statement.exchanges);
         self.forex_trades.extend(statement.forex_trades);
         self.stock_buys.extend(statement.stock_buys);
         self.stock_sells.extend(statement.stock_sells);
+        self.derivative_trades.extend(statement.derivative_trades);
+        self.coupons.extend(statement.coupons);
 
         self.cash_grants.extend(statement.cash_grants);
         self.stock_grants.extend(statement.stock_grants);
         self.corporate_actions.extend(statement.corporate_actions);
 
         self.open_positions = statement.open_positions;
+        self.pledged_positions = statement.pledged_positions;
         self.instrument_info.merge(statement.instrument_info);
 
         Ok(())
@@ -536,6 +718,10 @@ impl BrokerStatement {
                 }
             }
 
+            if let Some(quantity) = self.pledged_positions.remove(symbol) {
+                self.pledged_positions.insert(new_symbol.to_owned(), quantity);
+            }
+
             self.instrument_info.remap(symbol, new_symbol)?;
         } else {
             self.stock_splits.rename(symbol, new_symbol)?;
@@ -553,6 +739,10 @@ impl BrokerStatement {
             rename(dividend.date.into(), &mut dividend.issuer, &mut dividend.original_issuer);
         }
 
+        for coupon in &mut self.coupons {
+            rename(coupon.date.into(), &mut coupon.symbol, &mut coupon.original_symbol);
+        }
+
         if remapping {
             for cash_flow in &mut self.cash_flows {
                 if let Some(original_symbol) = cash_flow.mut_symbol() {
@@ -561,6 +751,19 @@ impl BrokerStatement {
                     }
                 }
             }
+
+            for dividend in &mut self.pending_dividends {
+                if dividend.symbol == symbol {
+                    new_symbol.clone_into(&mut dividend.symbol);
+                }
+            }
+
+            // Corporate actions from broker statements (for example, bond amortizations) refer to the original symbols
+            for action in &mut self.corporate_actions {
+                if action.symbol == symbol {
+                    new_symbol.clone_into(&mut action.symbol);
+                }
+            }
         }
 
         if check_existence && !found {
@@ -570,7 +773,7 @@ impl BrokerStatement {
         Ok(())
     }
 
-    fn validate(&mut self, strictness: ReadingStrictness) -> EmptyResult {
+    fn validate(&mut self) -> EmptyResult {
         let validator = DateValidator::new(self.period);
 
         validator.sort_and_validate(
@@ -580,22 +783,16 @@ impl BrokerStatement {
         self.sort_and_alter_fees(self.period.last_date());
         validator.validate("a fee", &self.fees, |fee| fee.date)?;
 
-        if
-            strictness.contains(ReadingStrictness::REPO_TRADES) &&
-            self.cash_flows.iter().any(|cash_flow| matches!(cash_flow.type_, CashFlowType::Repo{..}))
-        {
-            warn!(concat!(
-                "Broker statement contains repo trades which aren't supported yet. ",
-                "All repo trades will be ignored during the calculations."
-            ));
-        }
-
         self.cash_flows.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
         validator.validate("a cash flow", &self.cash_flows, |cash_flow| cash_flow.date)?;
+        self.repo_deals = repo::match_deals(&self.cash_flows)?;
 
         validator.sort_and_validate(
             "an idle cash interest", &mut self.idle_cash_interest, |interest| interest.date)?;
 
+        validator.sort_and_validate(
+            "a margin interest", &mut self.margin_interest, |interest| interest.date)?;
+
         self.tax_agent_withholdings.sort_and_validate(&validator)?;
 
         validator.sort_and_validate(
@@ -604,9 +801,15 @@ impl BrokerStatement {
         self.sort_and_validate_stock_buys()?;
         self.sort_and_validate_stock_sells()?;
 
+        validator.sort_and_validate(
+            "a derivative trade", &mut self.derivative_trades, |trade| trade.conclusion_time)?;
+
         self.dividends.sort_by(|a, b| (a.date, &a.issuer).cmp(&(b.date, &b.original_issuer)));
         validator.validate("a dividend", &self.dividends, |dividend| dividend.date)?;
 
+        self.coupons.sort_by(|a, b| (a.date, &a.symbol).cmp(&(b.date, &b.symbol)));
+        validator.validate("a coupon", &self.coupons, |coupon| coupon.date)?;
+
         validator.sort_and_validate("a cash grant", &mut self.cash_grants, |grant| grant.date)?;
         validator.sort_and_validate("a stock grant", &mut self.stock_grants, |grant| grant.date)?;
 
@@ -757,4 +960,5 @@ impl BrokerStatement {
 pub struct NetAssets {
     pub cash: MultiCurrencyCashAccount,
     pub other: Option<Cash>, // Supported only for some brokers
+    pub receivables: Option<Cash>, // Accrued, but not paid yet dividends and interest (supported only for some brokers)
 }
\ No newline at end of file
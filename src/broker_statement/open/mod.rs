@@ -7,7 +7,7 @@ use crate::core::GenericResult;
 use crate::formats::xml;
 #[cfg(test)] use crate::taxes::TaxRemapping;
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementSections};
 use super::{BrokerStatementReader, PartialBrokerStatement};
 
 mod common;
@@ -129,7 +129,7 @@ mod tests {
         BrokerStatement::read(
             broker, &format!("testdata/open/{}", name),
             &Default::default(), &portfolio.instrument_internal_ids, &Default::default(), TaxRemapping::new(), &[],
-            &portfolio.corporate_actions, ReadingStrictness::all(),
+            &portfolio.corporate_actions, ReadingStrictness::all(), StatementSections::all(),
         ).unwrap()
     }
 }
\ No newline at end of file
@@ -123,6 +123,7 @@ impl<const REPO: bool> ConcludedTrade<REPO> {
                 if REPO {
                     statement.cash_flows.push(CashFlow::new(self.conclusion_time.into(), -volume, CashFlowType::Repo {
                         symbol: symbol.to_owned(),
+                        quantity,
                         commission,
                     }));
                 } else {
@@ -140,6 +141,7 @@ impl<const REPO: bool> ConcludedTrade<REPO> {
                 if REPO {
                     statement.cash_flows.push(CashFlow::new(self.conclusion_time.into(), volume, CashFlowType::Repo {
                         symbol: symbol.to_owned(),
+                        quantity,
                         commission,
                     }));
                 } else {
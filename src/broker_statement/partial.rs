@@ -8,34 +8,39 @@ use crate::currency::{Cash, CashAssets, MultiCurrencyCashAccount};
 use crate::exchanges::{Exchange, Exchanges};
 use crate::instruments::{InstrumentId, InstrumentInfo};
 use crate::time::{Date, Period};
-use crate::types::Decimal;
+use crate::types::{DateTime, Decimal};
 use crate::util::{DecimalRestrictions, validate_named_decimal};
 
+use super::bonds::Coupon;
 use super::cash_flows::CashFlow;
 use super::corporate_actions::CorporateAction;
-use super::dividends::{DividendId, DividendAccruals};
+use super::dividends::{DividendId, DividendAccruals, PendingDividend};
 use super::fees::Fee;
 use super::grants::{CashGrant, StockGrant};
-use super::interest::IdleCashInterest;
-use super::trades::{ForexTrade, StockBuy, StockSell};
+use super::interest::{IdleCashInterest, MarginInterest};
+use super::trades::{DerivativeTrade, ForexTrade, StockBuy, StockSell};
 use super::taxes::{TaxId, TaxAccruals, TaxAgentWithholdings};
 
 pub type PartialBrokerStatementRc = Rc<RefCell<PartialBrokerStatement>>;
 
 pub struct PartialBrokerStatement {
     pub period: Option<Period>,
+    pub generation_time: Option<DateTime>, // Supported only for some brokers
 
     pub has_starting_assets: Option<bool>,
     pub deposits_and_withdrawals: Vec<CashAssets>,
     pub cash_flows: Vec<CashFlow>,
     pub fees: Vec<Fee>,
     pub idle_cash_interest: Vec<IdleCashInterest>,
+    pub margin_interest: Vec<MarginInterest>,
     pub tax_agent_withholdings: TaxAgentWithholdings,
 
     pub exchanges: Exchanges,
     pub forex_trades: Vec<ForexTrade>,
     pub stock_buys: Vec<StockBuy>,
     pub stock_sells: Vec<StockSell>,
+    pub derivative_trades: Vec<DerivativeTrade>,
+    pub coupons: Vec<Coupon>,
 
     pub dividend_accruals: HashMap<DividendId, DividendAccruals>,
     pub tax_accruals: HashMap<TaxId, TaxAccruals>,
@@ -47,31 +52,38 @@ pub struct PartialBrokerStatement {
     // Please note that some brokers (Firstrade) provide this information only for the last
     // statement (current date).
     pub assets: NetAssets,
+    pub pending_dividends: Vec<PendingDividend>, // Supported only for some brokers
     pub open_positions: HashMap<String, Decimal>,
+    pub pledged_positions: HashMap<String, Decimal>, // Supported only for some brokers
     pub instrument_info: InstrumentInfo,
 }
 
 pub struct NetAssets {
     pub cash: Option<MultiCurrencyCashAccount>,
     pub other: Option<Cash>, // Supported only for some brokers
+    pub receivables: Option<Cash>, // Supported only for some brokers
 }
 
 impl PartialBrokerStatement {
     pub fn new(exchanges: &[Exchange], zero_cash_assets: bool) -> PartialBrokerStatement {
         PartialBrokerStatement {
             period: None,
+            generation_time: None,
 
             has_starting_assets: None,
             deposits_and_withdrawals: Vec::new(),
             cash_flows: Vec::new(),
             fees: Vec::new(),
             idle_cash_interest: Vec::new(),
+            margin_interest: Vec::new(),
             tax_agent_withholdings: TaxAgentWithholdings::new(),
 
             exchanges: Exchanges::new(exchanges),
             forex_trades: Vec::new(),
             stock_buys: Vec::new(),
             stock_sells: Vec::new(),
+            derivative_trades: Vec::new(),
+            coupons: Vec::new(),
 
             dividend_accruals: HashMap::new(),
             tax_accruals: HashMap::new(),
@@ -86,9 +98,12 @@ impl PartialBrokerStatement {
                 } else {
                     None
                 },
-                other: None
+                other: None,
+                receivables: None,
             },
+            pending_dividends: Vec::new(),
             open_positions: HashMap::new(),
+            pledged_positions: HashMap::new(),
             instrument_info: InstrumentInfo::new(),
         }
     }
@@ -105,6 +120,10 @@ impl PartialBrokerStatement {
         get_option("statement period", self.period)
     }
 
+    pub fn set_generation_time(&mut self, time: DateTime) -> EmptyResult {
+        set_option("statement generation time", &mut self.generation_time, time)
+    }
+
     pub fn set_has_starting_assets(&mut self, exists: bool) -> EmptyResult {
         set_option("has starting assets flag", &mut self.has_starting_assets, exists)
     }
@@ -125,6 +144,18 @@ impl PartialBrokerStatement {
         Ok(())
     }
 
+    pub fn add_pledged_position(&mut self, symbol: &str, quantity: Decimal) -> EmptyResult {
+        validate_named_decimal(
+            &format!("{} pledged position", symbol), quantity, DecimalRestrictions::StrictlyPositive)?;
+
+        match self.pledged_positions.entry(symbol.to_owned()) {
+            Entry::Vacant(entry) => entry.insert(quantity),
+            Entry::Occupied(_) => return Err!("Got a duplicated pledged position for {}", symbol),
+        };
+
+        Ok(())
+    }
+
     pub fn dividend_accruals(&mut self, date: Date, issuer: InstrumentId, strict: bool) -> &mut DividendAccruals {
         self.dividend_accruals.entry(DividendId::new(date, issuer))
             .or_insert_with(|| DividendAccruals::new(strict))
@@ -138,6 +169,15 @@ impl PartialBrokerStatement {
     pub fn validate(self) -> GenericResult<PartialBrokerStatement> {
         self.get_period()?;
         self.get_has_starting_assets()?;
+
+        for (symbol, &pledged) in &self.pledged_positions {
+            let quantity = self.open_positions.get(symbol).copied().unwrap_or_default();
+            if pledged > quantity {
+                return Err!("Pledged {} quantity is greater than the open position: {} vs {}",
+                    symbol, pledged, quantity);
+            }
+        }
+
         Ok(self)
     }
 }
@@ -0,0 +1,143 @@
+//! External statement parsers support.
+//!
+//! A plugin is an executable named `investments-statement-<name>` which is looked up in `PATH`. It's called as
+//! `<plugin> check --broker <id> <path>` to find out whether it supports the specified file (exit code 0 means
+//! supported, 1 – not supported) and as `<plugin> parse --broker <id> <path>` to convert the file to the normalized
+//! JSON statement format (see docs/brokers.md) which is printed to stdout.
+
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+use log::debug;
+
+use crate::brokers::BrokerInfo;
+use crate::core::{EmptyResult, GenericResult};
+use crate::exchanges::Exchange;
+
+use super::json::JsonStatement;
+use super::partial::PartialBrokerStatement;
+use super::reader::BrokerStatementReader;
+
+const PLUGIN_PREFIX: &str = "investments-statement-";
+
+struct Plugin {
+    name: String,
+    path: PathBuf,
+}
+
+impl Plugin {
+    fn find(name: &str) -> GenericResult<Plugin> {
+        let mut file_name = OsString::from(format!("{}{}", PLUGIN_PREFIX, name));
+        if cfg!(windows) {
+            file_name.push(".exe");
+        }
+
+        let paths = env::var_os("PATH").unwrap_or_default();
+        for dir in env::split_paths(&paths) {
+            let path = dir.join(&file_name);
+            if path.is_file() {
+                debug!("Found {:?} statement plugin: {:?}.", name, path);
+                return Ok(Plugin {name: name.to_owned(), path});
+            }
+        }
+
+        Err!("Unable to find {:?} statement plugin: there is no {:?} executable in PATH", name, file_name)
+    }
+
+    fn check(&self, broker: &str, path: &str) -> GenericResult<bool> {
+        let output = self.run("check", broker, path)?;
+
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err!("{:?} statement plugin failed to check {:?}: {}",
+                      self.name, path, get_error(&output)),
+        }
+    }
+
+    fn parse(&self, broker: &str, path: &str) -> GenericResult<JsonStatement> {
+        let output = self.run("parse", broker, path)?;
+        if !output.status.success() {
+            return Err!("{:?} statement plugin failed to parse the statement: {}", self.name, get_error(&output));
+        }
+
+        Ok(JsonStatement::parse(&output.stdout).map_err(|e| format!(
+            "{:?} statement plugin returned an invalid statement: {}", self.name, e))?)
+    }
+
+    fn run(&self, command: &str, broker: &str, path: &str) -> GenericResult<std::process::Output> {
+        Ok(Command::new(&self.path).args([command, "--broker", broker, path]).output().map_err(|e| format!(
+            "Failed to execute {:?}: {}", self.path, e))?)
+    }
+}
+
+fn get_error(output: &std::process::Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr.trim();
+
+    if message.is_empty() {
+        output.status.to_string()
+    } else {
+        message.to_owned()
+    }
+}
+
+/// Reads statements with the broker's native reader and falls back to the configured plugins for the files which are
+/// not supported by it.
+pub struct PluginStatementReader {
+    reader: Box<dyn BrokerStatementReader>,
+    broker: &'static str,
+    exchanges: Vec<Exchange>,
+    plugins: Vec<Plugin>,
+}
+
+impl PluginStatementReader {
+    pub fn new(
+        broker: &BrokerInfo, reader: Box<dyn BrokerStatementReader>,
+    ) -> GenericResult<Box<dyn BrokerStatementReader>> {
+        let plugins = broker.statement_plugins().iter()
+            .map(|name| Plugin::find(name))
+            .collect::<GenericResult<Vec<_>>>()?;
+
+        if plugins.is_empty() {
+            return Ok(reader);
+        }
+
+        Ok(Box::new(PluginStatementReader {
+            reader,
+            broker: broker.type_.id(),
+            exchanges: broker.exchanges(),
+            plugins,
+        }))
+    }
+
+    fn get_plugin(&self, path: &str) -> GenericResult<Option<&Plugin>> {
+        for plugin in &self.plugins {
+            if plugin.check(self.broker, path)? {
+                return Ok(Some(plugin));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl BrokerStatementReader for PluginStatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        Ok(self.reader.check(path)? || self.get_plugin(path)?.is_some())
+    }
+
+    fn read(&mut self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        if self.reader.check(path)? {
+            return self.reader.read(path, is_last);
+        }
+
+        let plugin = self.get_plugin(path)?.ok_or("None of statement plugins support the file")?;
+        plugin.parse(self.broker, path)?.convert(&self.exchanges)
+    }
+
+    fn close(self: Box<Self>) -> EmptyResult {
+        self.reader.close()
+    }
+}
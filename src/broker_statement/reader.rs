@@ -5,11 +5,16 @@ use bitflags::bitflags;
 use log::debug;
 
 use crate::core::{GenericResult, EmptyResult};
-use crate::brokers::Broker;
+use crate::brokers::{Broker, BrokerInfo};
+use crate::errors::{Error, StatementError};
+use crate::progress;
 use crate::taxes::TaxRemapping;
+use crate::time::{Month, Period};
 
-use super::{bcs, firstrade, ib, open, sber, tbank};
-use super::PartialBrokerStatement;
+use super::{alfa, bcs, firstrade, ib, open, sber, schwab, tbank};
+use super::{PartialBrokerStatement, StatementsMergingStrategy};
+use super::json::JsonStatementReader;
+use super::plugins::PluginStatementReader;
 
 bitflags! {
     #[derive(Clone, Copy)]
@@ -18,8 +23,18 @@ bitflags! {
         const CASH_FLOW_DATES   = 1 << 1;
         const OTC_INSTRUMENTS   = 1 << 2;
         const TAX_EXEMPTIONS    = 1 << 3;
-        const REPO_TRADES       = 1 << 4;
-        const GRANTS            = 1 << 5;
+        const GRANTS            = 1 << 4;
+    }
+}
+
+bitflags! {
+    /// Broker statement sections required by the command. Readers may skip parsing of the other sections to speed up
+    /// reading of large statements. Period, cash assets and open positions are always read.
+    #[derive(Clone, Copy)]
+    pub struct StatementSections: u32 {
+        const TRADES     = 1 << 0; // Trades, corporate actions and grants
+        const DIVIDENDS  = 1 << 1; // Dividends and withheld taxes
+        const CASH_FLOWS = 1 << 2; // Deposits, withdrawals, fees and interest
     }
 }
 
@@ -31,51 +46,131 @@ pub trait BrokerStatementReader {
 }
 
 pub fn read(
-    broker: Broker, statement_dir_path: &str, tax_remapping: TaxRemapping,
-    strictness: ReadingStrictness,
+    broker: &BrokerInfo, statement_dir_path: &str, tax_remapping: TaxRemapping,
+    strictness: ReadingStrictness, sections: StatementSections,
 ) -> GenericResult<Vec<PartialBrokerStatement>> {
+    let statements = read_statements(broker, statement_dir_path, tax_remapping, strictness, sections)?;
+
+    let periods = statements.iter()
+        .map(|(file_name, statement)| Ok((file_name.as_str(), statement.get_period()?)))
+        .collect::<GenericResult<Vec<_>>>()?;
+
+    check_periods(broker.type_, periods).map_err(|e| format!(
+        "Error while reading {:?}: {}", statement_dir_path, e))?;
+
+    Ok(statements.into_iter().map(|(_, statement)| statement).collect())
+}
+
+/// Reads only periods of the broker statements without checking their continuity
+pub fn read_periods(
+    broker: &BrokerInfo, statement_dir_path: &str, tax_remapping: TaxRemapping,
+) -> GenericResult<Vec<Period>> {
+    let statements = read_statements(
+        broker, statement_dir_path, tax_remapping, ReadingStrictness::empty(), StatementSections::empty())?;
+
+    let mut periods = statements.iter()
+        .map(|(_, statement)| statement.get_period())
+        .collect::<GenericResult<Vec<_>>>()?;
+    periods.sort();
+
+    Ok(periods)
+}
+
+/// Reads a single broker statement file in isolation (without statements merging and other checks which require the
+/// whole statements directory)
+pub fn read_single(broker: &BrokerInfo, path: &str) -> GenericResult<PartialBrokerStatement> {
+    let mut tax_remapping = Some(TaxRemapping::new());
+    let mut statement_reader = create_reader(
+        broker, &mut tax_remapping, ReadingStrictness::empty(), StatementSections::all())?;
+
+    let read = |statement_reader: &mut dyn BrokerStatementReader| -> GenericResult<PartialBrokerStatement> {
+        if !statement_reader.check(path)? {
+            return Err!("The file doesn't look like {} broker statement ({} is expected)",
+                        broker.brief_name, get_statement_file_type(broker.type_));
+        }
+
+        // Some brokers provide additional information in the last statement which requires some auxiliary files from
+        // the statements directory, so read the statement as an intermediate one.
+        statement_reader.read(path, false)
+    };
+
+    let statement = read(statement_reader.as_mut()).map_err(|e| Error::from(StatementError::Read {
+        path: path.to_owned(), source: e,
+    }))?;
+    statement_reader.close()?;
+
+    Ok(statement)
+}
+
+fn read_statements(
+    broker: &BrokerInfo, statement_dir_path: &str, tax_remapping: TaxRemapping,
+    strictness: ReadingStrictness, sections: StatementSections,
+) -> GenericResult<Vec<(String, PartialBrokerStatement)>> {
     let mut tax_remapping = Some(tax_remapping);
-    let mut statement_reader = match broker {
-        Broker::Bcs => bcs::StatementReader::new(),
-        Broker::Firstrade => firstrade::StatementReader::new(),
-        Broker::InteractiveBrokers => ib::StatementReader::new(tax_remapping.take().unwrap(), strictness),
-        Broker::Open => open::StatementReader::new(),
-        Broker::Sber => sber::StatementReader::new(),
-        Broker::Tbank => tbank::StatementReader::new(),
-    }?;
+    let mut statement_reader = create_reader(broker, &mut tax_remapping, strictness, sections)?;
 
     let mut file_names = preprocess_statement_directory(statement_dir_path, statement_reader.as_mut())
         .map_err(|e| format!("Error while reading {:?}: {}", statement_dir_path, e))?;
 
     if file_names.is_empty() {
-        return Err!("{:?} doesn't contain any broker statement", statement_dir_path);
+        return Err(Error::from(StatementError::NotFound(statement_dir_path.to_owned())).into());
     }
     file_names.sort_unstable();
 
     let mut statements = Vec::new();
 
-    for (id, file_name) in file_names.iter().enumerate() {
-        let is_last = id == file_names.len() - 1;
+    let count = file_names.len();
+    let mut progress = progress::start(&format!("Reading {} broker statements", broker.brief_name), count);
 
-        let path = Path::new(statement_dir_path).join(file_name);
+    for (id, file_name) in file_names.into_iter().enumerate() {
+        let is_last = id == count - 1;
+
+        let path = Path::new(statement_dir_path).join(&file_name);
         let path = path.to_str().unwrap();
 
         debug!("Reading {:?}...", path);
-        let statement = statement_reader.read(path, is_last).map_err(|e| format!(
-            "Error while reading {:?} broker statement: {}", path, e))?;
+        let statement = statement_reader.read(path, is_last).map_err(|e| Error::from(StatementError::Read {
+            path: path.to_owned(), source: e,
+        }))?;
 
-        statements.push(statement);
+        statements.push((file_name, statement));
+        progress.inc();
     }
+    drop(progress);
+
+    let statements = StatementsMergingStrategy::replace_reissued(statements);
+    let statements = StatementsMergingStrategy::skip_covered(statements);
+
+    debug!("Using the following broker statements: {}.",
+           statements.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", "));
 
     if let Some(tax_remapping) = tax_remapping {
         tax_remapping.ensure_all_mapped().map_err(|e| format!(
-            "{}. Tax remapping is not supported for {} yet", e, broker.brief_name()))?;
+            "{}. Tax remapping is not supported for {} yet", e, broker.brief_name))?;
     }
     statement_reader.close()?;
 
     Ok(statements)
 }
 
+fn create_reader(
+    broker: &BrokerInfo, tax_remapping: &mut Option<TaxRemapping>,
+    strictness: ReadingStrictness, sections: StatementSections,
+) -> GenericResult<Box<dyn BrokerStatementReader>> {
+    let reader = match broker.type_ {
+        Broker::Alfa => alfa::StatementReader::new(),
+        Broker::Bcs => bcs::StatementReader::new(),
+        Broker::Firstrade => firstrade::StatementReader::new(),
+        Broker::InteractiveBrokers => ib::StatementReader::new(tax_remapping.take().unwrap(), strictness, sections),
+        Broker::Open => open::StatementReader::new(),
+        Broker::Sber => sber::StatementReader::new(),
+        Broker::Schwab => schwab::StatementReader::new(),
+        Broker::Tbank => tbank::StatementReader::new(broker.exchange_aliases.clone()),
+    }?;
+    let reader = JsonStatementReader::new(broker.exchanges(), reader);
+    PluginStatementReader::new(broker, reader)
+}
+
 fn preprocess_statement_directory(
     statement_dir_path: &str, statement_reader: &mut dyn BrokerStatementReader
 ) -> GenericResult<Vec<String>> {
@@ -98,4 +193,91 @@ fn preprocess_statement_directory(
     }
 
     Ok(file_names)
-}
\ No newline at end of file
+}
+
+// Checks the statements for gaps and overlaps before merging to report all missing statements at once
+fn check_periods(broker: Broker, mut periods: Vec<(&str, Period)>) -> EmptyResult {
+    periods.sort_by_key(|&(_, period)| period);
+
+    let strategy = broker.statements_merging_strategy();
+    let last_date = periods.iter().map(|(_, period)| period.last_date()).max().unwrap();
+    let mut problems = Vec::new();
+
+    for pair in periods.windows(2) {
+        let ((first_name, first), (second_name, second)) = (pair[0], pair[1]);
+
+        if second.first_date() <= first.last_date() {
+            problems.push(format!(
+                "{} ({}) overlaps with {} ({})", second_name, second.format(), first_name, first.format()));
+        } else if strategy.validate(first, second, last_date).is_err() {
+            let missing = Period::new(first.next_date(), second.prev_date()).unwrap();
+            problems.push(format!(
+                "Missing {} statements for {} ({})",
+                get_statement_file_type(broker), get_months(missing).join(", "), missing.format()));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err!(
+            "The broker statements don't cover a continuous period:\n{}",
+            problems.iter().map(|problem| format!("* {}", problem)).collect::<Vec<_>>().join("\n"));
+    }
+
+    Ok(())
+}
+
+fn get_statement_file_type(broker: Broker) -> &'static str {
+    match broker {
+        Broker::Alfa => "*.xlsx",
+        Broker::Bcs => "*.xls",
+        Broker::Firstrade => "*.ofx",
+        Broker::InteractiveBrokers => "*.csv",
+        Broker::Open => "*.xml",
+        Broker::Sber => "*.html",
+        Broker::Schwab => "*.csv",
+        Broker::Tbank => "*.xlsx",
+    }
+}
+
+fn get_months(period: Period) -> Vec<String> {
+    let mut months = Vec::new();
+    let mut month = Month::from(period.first_date());
+
+    while month <= Month::from(period.last_date()) {
+        months.push(month.format());
+        month = month.next();
+    }
+
+    months
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn period(first: (i32, u32, u32), last: (i32, u32, u32)) -> Period {
+        Period::new(date!(first.0, first.1, first.2), date!(last.0, last.1, last.2)).unwrap()
+    }
+
+    #[test]
+    fn periods_checking() {
+        let periods = vec![
+            ("2021-12.csv", period((2021, 12, 1), (2021, 12, 31))),
+            ("2021-01.csv", period((2021, 1, 1), (2021, 1, 31))),
+            ("2021-04.csv", period((2021, 4, 1), (2021, 4, 30))),
+            ("2021-05-10.csv", period((2021, 5, 1), (2021, 10, 31))),
+            ("2021-10-11.csv", period((2021, 10, 1), (2021, 11, 30))),
+        ];
+        assert_eq!(check_periods(Broker::InteractiveBrokers, periods).unwrap_err().to_string(), [
+            "The broker statements don't cover a continuous period:",
+            "* Missing *.csv statements for 02.2021, 03.2021 (01.02.2021 - 31.03.2021)",
+            "* 2021-10-11.csv (01.10.2021 - 30.11.2021) overlaps with 2021-05-10.csv (01.05.2021 - 31.10.2021)",
+        ].join("\n"));
+
+        let periods = vec![
+            ("2021-01.csv", period((2021, 1, 1), (2021, 1, 29))),
+            ("2021-02.csv", period((2021, 2, 1), (2021, 2, 28))),
+        ];
+        check_periods(Broker::InteractiveBrokers, periods).unwrap();
+    }
+}
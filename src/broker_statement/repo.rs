@@ -0,0 +1,120 @@
+use std::collections::{HashMap, VecDeque};
+
+use log::debug;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::formatting;
+use crate::time::Date;
+use crate::types::Decimal;
+
+use super::cash_flows::{CashFlow, CashFlowType};
+use super::interest::IdleCashInterest;
+
+/// Completed repo deal. Its first and second legs cancel each other out position-wise, so only its financial result
+/// matters which is accounted as interest income (or expense if negative).
+pub struct RepoDeal {
+    pub symbol: String,
+    pub open_date: Date,
+    pub close_date: Date,
+    pub result: Cash, // Including commissions
+}
+
+impl RepoDeal {
+    pub fn interest(&self) -> IdleCashInterest {
+        IdleCashInterest::new(self.close_date, self.result)
+    }
+}
+
+/// Matches first and second legs of repo deals (cash flows must be sorted by date)
+pub fn match_deals(cash_flows: &[CashFlow]) -> GenericResult<Vec<RepoDeal>> {
+    let mut deals = Vec::new();
+    let mut open_legs: HashMap<&str, VecDeque<(Date, Decimal, Cash)>> = HashMap::new();
+
+    for cash_flow in cash_flows {
+        let CashFlowType::Repo {ref symbol, quantity, commission} = cash_flow.type_ else {
+            continue;
+        };
+
+        let date = cash_flow.date.date;
+        let amount = cash_flow.amount - commission;
+        let legs = open_legs.entry(symbol).or_default();
+
+        // The second leg always has the same quantity and moves cash in the opposite direction
+        let Some(index) = legs.iter().position(|&(_, open_quantity, open_amount)| {
+            open_quantity == quantity && open_amount.is_positive() != cash_flow.amount.is_positive()
+        }) else {
+            legs.push_back((date, quantity, amount));
+            continue;
+        };
+
+        let (open_date, _, open_amount) = legs.remove(index).unwrap();
+        if open_amount.currency != amount.currency {
+            return Err!(
+                "Got {} repo deal legs with different currencies: {} and {}",
+                symbol, open_amount.currency, amount.currency);
+        }
+
+        deals.push(RepoDeal {
+            symbol: symbol.clone(),
+            open_date,
+            close_date: date,
+            result: open_amount + amount,
+        });
+    }
+
+    for (symbol, legs) in open_legs {
+        for (date, _, _) in legs {
+            debug!("{} repo deal from {} is not closed yet.", symbol, formatting::format_date(date));
+        }
+    }
+
+    Ok(deals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching() {
+        let deals = match_deals(&[
+            leg(date!(2023, 1, 10), 10, dec!(10_000), dec!(1)),
+            leg(date!(2023, 1, 11), 10, dec!(-10_005), dec!(0)),
+            leg(date!(2023, 1, 12), 20, dec!(-20_000), dec!(0)),
+        ]).unwrap();
+
+        assert_eq!(deals.len(), 1);
+        assert_eq!(deals[0].open_date, date!(2023, 1, 10));
+        assert_eq!(deals[0].close_date, date!(2023, 1, 11));
+        assert_eq!(deals[0].result, Cash::new("RUB", dec!(-6)));
+    }
+
+    #[test]
+    fn overlapping_deals() {
+        let deals = match_deals(&[
+            leg(date!(2023, 1, 10), 10, dec!(10_000), dec!(0)),
+            leg(date!(2023, 1, 11), 20, dec!(20_000), dec!(0)),
+            leg(date!(2023, 1, 12), 20, dec!(-20_010), dec!(0)),
+            leg(date!(2023, 1, 13), 10, dec!(-10_003), dec!(0)),
+        ]).unwrap();
+
+        assert_eq!(deals.len(), 2);
+
+        assert_eq!(deals[0].open_date, date!(2023, 1, 11));
+        assert_eq!(deals[0].close_date, date!(2023, 1, 12));
+        assert_eq!(deals[0].result, Cash::new("RUB", dec!(-10)));
+
+        assert_eq!(deals[1].open_date, date!(2023, 1, 10));
+        assert_eq!(deals[1].close_date, date!(2023, 1, 13));
+        assert_eq!(deals[1].result, Cash::new("RUB", dec!(-3)));
+    }
+
+    fn leg(date: Date, quantity: u32, amount: Decimal, commission: Decimal) -> CashFlow {
+        CashFlow::new(date.into(), Cash::new("RUB", amount), CashFlowType::Repo {
+            symbol: s!("SBER"),
+            quantity: quantity.into(),
+            commission: Cash::new("RUB", commission),
+        })
+    }
+}
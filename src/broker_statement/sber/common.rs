@@ -2,6 +2,7 @@ use std::borrow::Cow;
 
 use scraper::{CaseSensitivity, ElementRef};
 
+use crate::brokers::Broker;
 use crate::core::GenericResult;
 use crate::formats::html::{self, Cell};
 use crate::time::{self, Date, Time};
@@ -21,8 +22,7 @@ pub fn parse_time_cell(cell: &Cell) -> GenericResult<Time> {
 }
 
 pub fn parse_decimal_cell(cell: &Cell) -> GenericResult<Decimal> {
-    let value = html::get_string_cell(cell)?.replace(' ', "");
-    util::parse_decimal(&value, DecimalRestrictions::No)
+    util::parse_formatted_decimal(html::get_string_cell(cell)?, Broker::Sber.number_format(), DecimalRestrictions::No)
 }
 
 pub fn trim_column_title(title: &str) -> Cow<str> {
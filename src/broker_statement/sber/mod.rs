@@ -12,7 +12,7 @@ use std::rc::Rc;
 
 use itertools::Itertools;
 
-#[cfg(test)] use crate::broker_statement::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections};
 use crate::broker_statement::{BrokerStatementReader, PartialBrokerStatement};
 #[cfg(test)] use crate::brokers::Broker;
 #[cfg(test)] use crate::config::Config;
@@ -90,7 +90,7 @@ mod tests {
 
         let statement = BrokerStatement::read(
             broker, &path, &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(), &[],
-            corporate_actions, ReadingStrictness::all()).unwrap();
+            corporate_actions, ReadingStrictness::all(), StatementSections::all()).unwrap();
 
         assert_eq!(statement.assets.cash.is_empty(), name == "my");
         assert!(statement.assets.other.is_none()); // TODO(konishchev): Get it from statements
@@ -5,6 +5,7 @@ use std::rc::Rc;
 use log::trace;
 use scraper::ElementRef;
 
+use crate::broker_statement::bonds;
 use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
 use crate::broker_statement::trades::{StockBuy, StockSell};
 use crate::core::EmptyResult;
@@ -90,19 +91,21 @@ struct TradeRow {
 
 impl TradeRow {
     fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
-        if !self.accumulated_coupon_income.is_zero() {
-            return Err!("Bonds aren't supported yet");
-        }
-
         let time = DateTime::new(self.date, self.time);
         let quantity = util::validate_named_decimal("quantity", self.quantity, DecimalRestrictions::StrictlyPositive)?;
 
-        let price = util::validate_named_decimal("price", self.price, DecimalRestrictions::StrictlyPositive)
+        let mut price = util::validate_named_decimal("price", self.price, DecimalRestrictions::StrictlyPositive)
             .map(|price| Cash::new(&self.currency, price))?;
 
-        let volume = util::validate_named_decimal("trade volume", self.volume, DecimalRestrictions::StrictlyPositive)
-            .map(|volume| Cash::new(&self.currency, volume))?;
-        debug_assert_eq!(volume, (price * quantity).round());
+        let mut volume = util::validate_named_decimal(
+            "trade volume", self.volume, DecimalRestrictions::StrictlyPositive,
+        ).map(|volume| Cash::new(&self.currency, volume))?;
+
+        if self.accumulated_coupon_income.is_zero() {
+            debug_assert_eq!(volume, (price * quantity).round());
+        } else {
+            (price, volume) = bonds::get_bond_trade_volume(quantity, volume, self.accumulated_coupon_income)?;
+        }
 
         let broker_commission = util::validate_named_decimal(
             "broker commission", self.broker_commission, DecimalRestrictions::PositiveOrZero)?;
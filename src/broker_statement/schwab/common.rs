@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::fs::File;
+
+use csv::{ReaderBuilder, StringRecord};
+
+use crate::core::GenericResult;
+use crate::time::{self, Date};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions, NumberFormat};
+
+pub const CURRENCY: &str = "USD";
+
+/// Schwab CSV export: a title line followed by a regular CSV table
+pub struct Table {
+    pub title: String,
+    columns: HashMap<String, usize>,
+    pub rows: Vec<StringRecord>,
+}
+
+impl Table {
+    pub fn read(path: &str) -> GenericResult<Table> {
+        let mut records = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(File::open(path)?)
+            .into_records();
+
+        let title = match records.next() {
+            Some(record) => parse_title(&record?),
+            None => return Err!("The statement is empty"),
+        };
+
+        let header = records.next().ok_or("The statement has no table header")??;
+        let columns = header.iter().enumerate()
+            .filter(|(_, name)| !name.is_empty())
+            .map(|(index, name)| (name.trim().to_owned(), index))
+            .collect();
+
+        let mut rows = Vec::new();
+
+        for record in records {
+            let record = record?;
+            if record.iter().all(|value| value.trim().is_empty()) {
+                continue;
+            }
+            rows.push(record);
+        }
+
+        Ok(Table {title, columns, rows})
+    }
+
+    pub fn get<'a>(&self, row: &'a StringRecord, name: &str) -> GenericResult<&'a str> {
+        let index = *self.columns.get(name).ok_or_else(|| format!(
+            "The statement has no {:?} column", name))?;
+
+        Ok(row.get(index).map(str::trim).unwrap_or_default())
+    }
+}
+
+pub fn read_title(path: &str) -> GenericResult<String> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(File::open(path)?);
+
+    let mut record = StringRecord::new();
+    if !reader.read_record(&mut record)? {
+        return Ok(String::new());
+    }
+
+    Ok(parse_title(&record))
+}
+
+fn parse_title(record: &StringRecord) -> String {
+    let title = record.get(0).unwrap_or_default();
+    util::fold_spaces(title.trim_start_matches('\u{feff}').trim()).to_string()
+}
+
+/// Parses transaction date which is specified as "MM/DD/YYYY as of MM/DD/YYYY" for backdated transactions (the
+/// second one is the actual transaction date in this case).
+pub fn parse_date(value: &str) -> GenericResult<Date> {
+    let date = match value.split_once(" as of ") {
+        Some((_, date)) => date,
+        None => value,
+    };
+    time::parse_date(date.trim(), "%m/%d/%Y")
+}
+
+/// Parses decimal values which may be formatted as "$1,234.56" or "-$1,234.56"
+pub fn parse_decimal(name: &str, value: &str, restrictions: DecimalRestrictions) -> GenericResult<Decimal> {
+    let (sign, amount) = match value.strip_prefix('-') {
+        Some(amount) => ("-", amount),
+        None => ("", value),
+    };
+    let amount = amount.strip_prefix('$').unwrap_or(amount);
+
+    let value = format!("{}{}", sign, amount);
+
+    util::parse_formatted_decimal(&value, NumberFormat::English, restrictions).map_err(|e| format!(
+        "Invalid {}: {}", name, e).into())
+}
+
+/// Parses optional decimal value which is specified as an empty string when it's missing
+pub fn parse_optional_decimal(name: &str, value: &str, restrictions: DecimalRestrictions) -> GenericResult<Decimal> {
+    if value.is_empty() {
+        return util::validate_named_decimal(name, Decimal::ZERO, restrictions);
+    }
+    parse_decimal(name, value, restrictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("12/29/2023", date!(2023, 12, 29)),
+        case("01/02/2024 as of 12/29/2023", date!(2023, 12, 29)),
+    )]
+    fn date_parsing(value: &str, expected: Date) {
+        assert_eq!(parse_date(value).unwrap(), expected);
+    }
+
+    #[rstest(value, expected,
+        case("10", dec!(10)),
+        case("$236.50", dec!(236.50)),
+        case("-$2,365.00", dec!(-2365)),
+        case("$1,234,567.89", dec!(1234567.89)),
+    )]
+    fn decimal_parsing(value: &str, expected: Decimal) {
+        assert_eq!(parse_decimal("amount", value, DecimalRestrictions::No).unwrap(), expected);
+    }
+}
@@ -0,0 +1,85 @@
+/*
+Charles Schwab provides account history as two separate CSV exports:
+
+* Transactions statement: all transactions for the specified period.
+* Positions statement: cash balance and open positions as of the statement generation date.
+
+Positions statement isn't a broker statement on its own, so it's used only to get assets of the last transactions
+statement which must end at the same date.
+*/
+
+mod common;
+mod positions;
+mod transactions;
+
+use crate::core::GenericResult;
+use crate::exchanges::Exchange;
+use crate::formatting;
+
+use super::{BrokerStatementReader, PartialBrokerStatement};
+
+use common::Table;
+use positions::Positions;
+
+pub struct StatementReader {
+    positions: Option<Positions>,
+}
+
+impl StatementReader {
+    pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
+        Ok(Box::new(StatementReader{
+            positions: None,
+        }))
+    }
+}
+
+impl BrokerStatementReader for StatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        if !path.ends_with(".csv") {
+            return Ok(false);
+        }
+
+        let title = common::read_title(path)?;
+
+        if title.starts_with(transactions::TITLE_PREFIX) {
+            Ok(true)
+        } else if title.starts_with(positions::TITLE_PREFIX) {
+            let positions = Positions::read(path).map_err(|e| format!(
+                "Error while reading {:?} positions statement: {}", path, e))?;
+
+            if self.positions.replace(positions).is_some() {
+                return Err!("Got several positions statements. Only the latest one is expected to be kept");
+            }
+
+            Ok(false)
+        } else {
+            Err!("Got an unexpected Charles Schwab statement {:?}: {:?}", path, title)
+        }
+    }
+
+    fn read(&mut self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        let table = Table::read(path)?;
+        let period = transactions::parse_period(&table.title)?;
+
+        let mut statement = PartialBrokerStatement::new(&[Exchange::Us], false);
+        statement.set_period(period)?;
+        statement.set_has_starting_assets(false)?;
+
+        transactions::parse(&table, &mut statement)?;
+
+        if is_last {
+            let positions = self.positions.as_ref().ok_or(
+                "Positions statement is missing. It's required to get current cash balance and open positions")?;
+
+            if positions.date != period.last_date() {
+                return Err!(
+                    "Positions statement date ({}) doesn't match the last transactions statement end date ({})",
+                    formatting::format_date(positions.date), formatting::format_date(period.last_date()));
+            }
+
+            positions.parse(&mut statement)?;
+        }
+
+        statement.validate()
+    }
+}
@@ -0,0 +1,82 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::time::{self, Date};
+use crate::types::Decimal;
+use crate::util::DecimalRestrictions;
+
+use super::PartialBrokerStatement;
+use super::common::{self, CURRENCY, Table};
+
+pub const TITLE_PREFIX: &str = "Positions for account ";
+
+const CASH_SYMBOL: &str = "Cash & Cash Investments";
+const TOTAL_SYMBOL: &str = "Account Total";
+
+/// Current cash balance and open positions which are provided by Schwab as a separate statement
+pub struct Positions {
+    pub date: Date,
+    cash: Decimal,
+    securities: Vec<(String, Decimal)>,
+}
+
+impl Positions {
+    pub fn read(path: &str) -> GenericResult<Positions> {
+        lazy_static! {
+            static ref TITLE_REGEX: Regex = Regex::new(
+                r"^Positions for account .+ as of .+ (?P<date>\d{4}/\d{2}/\d{2})$").unwrap();
+        }
+
+        let table = Table::read(path)?;
+
+        let captures = TITLE_REGEX.captures(&table.title).ok_or_else(|| format!(
+            "Unexpected positions statement title: {:?}", table.title))?;
+        let date = time::parse_date(captures.name("date").unwrap().as_str(), "%Y/%m/%d")?;
+
+        let mut cash = None;
+        let mut securities = Vec::new();
+
+        for row in &table.rows {
+            let symbol = table.get(row, "Symbol")?;
+
+            match symbol {
+                CASH_SYMBOL => {
+                    let amount = common::parse_decimal(
+                        "cash amount", table.get(row, "Market Value")?, DecimalRestrictions::No)?;
+
+                    if cash.replace(amount).is_some() {
+                        return Err!("Got a duplicated cash balance");
+                    }
+                },
+                TOTAL_SYMBOL => {},
+                _ => {
+                    let quantity = common::parse_decimal(
+                        &format!("{} quantity", symbol), table.get(row, "Quantity")?,
+                        DecimalRestrictions::StrictlyPositive)?;
+
+                    securities.push((symbol.to_owned(), quantity));
+                },
+            }
+        }
+
+        Ok(Positions {
+            date,
+            cash: cash.ok_or("Unable to find cash balance in the positions statement")?,
+            securities,
+        })
+    }
+
+    pub fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let mut cash = MultiCurrencyCashAccount::new();
+        cash.deposit(Cash::new(CURRENCY, self.cash));
+        statement.assets.cash.replace(cash);
+
+        for (symbol, quantity) in &self.securities {
+            statement.add_open_position(symbol, *quantity)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use csv::StringRecord;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::broker_statement::{StockBuy, StockSell, IdleCashInterest, Fee, Withholding};
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, CashAssets};
+use crate::exchanges::Exchange;
+use crate::instruments::InstrumentId;
+use crate::time::{self, Date, Period};
+use crate::util::DecimalRestrictions;
+
+use super::PartialBrokerStatement;
+use super::common::{self, CURRENCY, Table};
+
+pub const TITLE_PREFIX: &str = "Transactions for account ";
+
+const TOTAL_ROW: &str = "Transactions Total";
+
+pub fn parse_period(title: &str) -> GenericResult<Period> {
+    lazy_static! {
+        static ref TITLE_REGEX: Regex = Regex::new(concat!(
+            r"^Transactions for account .+ from (?P<start>\d{2}/\d{2}/\d{4}) ",
+            r"to (?P<end>\d{2}/\d{2}/\d{4})(?: |$)",
+        )).unwrap();
+    }
+
+    let captures = TITLE_REGEX.captures(title).ok_or_else(|| format!(
+        "Unexpected transactions statement title: {:?}", title))?;
+
+    let parse_date = |name| time::parse_date(captures.name(name).unwrap().as_str(), "%m/%d/%Y");
+    Period::new(parse_date("start")?, parse_date("end")?)
+}
+
+pub fn parse(table: &Table, statement: &mut PartialBrokerStatement) -> EmptyResult {
+    for row in &table.rows {
+        if table.get(row, "Date")? == TOTAL_ROW {
+            continue;
+        }
+
+        Transaction::new(table, row)?.parse(statement).map_err(|e| format!(
+            "Failed to parse {:?} transaction from {}: {}",
+            table.get(row, "Action").unwrap_or_default(), table.get(row, "Date").unwrap_or_default(), e))?;
+    }
+
+    Ok(())
+}
+
+struct Transaction<'a> {
+    table: &'a Table,
+    row: &'a StringRecord,
+    date: Date,
+    action: &'a str,
+    symbol: &'a str,
+    description: &'a str,
+}
+
+impl<'a> Transaction<'a> {
+    fn new(table: &'a Table, row: &'a StringRecord) -> GenericResult<Transaction<'a>> {
+        Ok(Transaction {
+            table, row,
+            date: common::parse_date(table.get(row, "Date")?)?,
+            action: table.get(row, "Action")?,
+            symbol: table.get(row, "Symbol")?,
+            description: table.get(row, "Description")?,
+        })
+    }
+
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        match self.action {
+            "Buy" => self.parse_trade(statement, true)?,
+            "Sell" => self.parse_trade(statement, false)?,
+
+            "Qualified Dividend" | "Non-Qualified Div" | "Cash Dividend" | "Special Dividend" => {
+                let amount = self.amount("dividend amount", DecimalRestrictions::StrictlyPositive)?;
+                statement.dividend_accruals(self.date, self.issuer()?, true).add(self.date, amount);
+            },
+
+            // Withheld tax is negative and its refund is positive
+            "NRA Tax Adj" | "NRA Withholding" => {
+                let amount = self.amount("tax amount", DecimalRestrictions::NonZero)?;
+                let accruals = statement.tax_accruals(self.date, self.issuer()?, false);
+
+                if amount.is_negative() {
+                    accruals.add(self.date, -amount);
+                } else {
+                    accruals.reverse(self.date, amount);
+                }
+            },
+
+            "Credit Interest" | "Bank Interest" => {
+                let amount = self.amount("interest amount", DecimalRestrictions::NonZero)?;
+                statement.idle_cash_interest.push(IdleCashInterest::new(self.date, amount));
+            },
+
+            "MoneyLink Transfer" | "MoneyLink Deposit" | "Wire Funds" | "Wire Funds Received" | "Journal" => {
+                let amount = self.amount("transfer amount", DecimalRestrictions::NonZero)?;
+                statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(self.date, amount));
+            },
+
+            "Service Fee" | "Wire Fee" => {
+                let amount = self.amount("fee amount", DecimalRestrictions::NonZero)?;
+                statement.fees.push(Fee::new(self.date, Withholding::new(-amount), Some(self.description.to_owned())));
+            },
+
+            _ => return Err!("Unsupported transaction type: {:?}", self.description),
+        }
+
+        Ok(())
+    }
+
+    fn parse_trade(&self, statement: &mut PartialBrokerStatement, buy: bool) -> EmptyResult {
+        let symbol = self.symbol()?;
+
+        let quantity = common::parse_decimal(
+            "quantity", self.get("Quantity")?, DecimalRestrictions::StrictlyPositive)?.normalize();
+
+        let price = common::parse_decimal(
+            "price", self.get("Price")?, DecimalRestrictions::StrictlyPositive
+        ).map(|price| Cash::new(CURRENCY, price.normalize()))?;
+
+        let commission = common::parse_optional_decimal(
+            "commission", self.get("Fees & Comm")?, DecimalRestrictions::PositiveOrZero
+        ).map(|commission| Cash::new(CURRENCY, commission))?;
+
+        // Trade amount includes commission
+        let volume = if buy {
+            -self.amount("trade amount", DecimalRestrictions::StrictlyNegative)? - commission
+        } else {
+            self.amount("trade amount", DecimalRestrictions::StrictlyPositive)? + commission
+        };
+        debug_assert_eq!(volume, (price * quantity).round());
+
+        // Statements don't provide trade settlement date, so it's assumed to be the regular one
+        let execution_date = Exchange::Us.trading_mode().execution_date(self.date);
+
+        if buy {
+            statement.stock_buys.push(StockBuy::new_trade(
+                symbol, quantity, price, volume, commission, self.date.into(), execution_date));
+        } else {
+            statement.stock_sells.push(StockSell::new_trade(
+                symbol, quantity, price, volume, commission, self.date.into(), execution_date, false));
+        }
+
+        if !self.description.is_empty() {
+            statement.instrument_info.get_or_add(symbol).set_name(self.description);
+        }
+
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> GenericResult<&'a str> {
+        self.table.get(self.row, name)
+    }
+
+    fn symbol(&self) -> GenericResult<&'a str> {
+        if self.symbol.is_empty() {
+            return Err!("The transaction has no symbol");
+        }
+        Ok(self.symbol)
+    }
+
+    fn issuer(&self) -> GenericResult<InstrumentId> {
+        Ok(InstrumentId::Symbol(self.symbol()?.to_owned()))
+    }
+
+    fn amount(&self, name: &str, restrictions: DecimalRestrictions) -> GenericResult<Cash> {
+        let amount = common::parse_decimal(name, self.get("Amount")?, restrictions)?;
+        Ok(Cash::new(CURRENCY, amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn period_parsing() {
+        assert_eq!(
+            parse_period("Transactions for account Individual ...123 from 01/01/2023 to 12/31/2023").unwrap(),
+            Period::new(date!(2023, 1, 1), date!(2023, 12, 31)).unwrap(),
+        );
+    }
+}
@@ -3,7 +3,7 @@ use log::trace;
 use crate::broker_statement::partial::PartialBrokerStatementRc;
 use crate::core::EmptyResult;
 use crate::formats::xls::{self, XlsTableRow, XlsStatementParser, SectionParser, SheetReader, Cell, SkipCell, TableReader};
-use crate::instruments::parse_isin;
+use crate::instruments::SecurityId;
 
 use super::common::{SecuritiesRegistryRc, read_next_table_row, trim_column_title};
 
@@ -29,7 +29,7 @@ impl SectionParser for AssetsParser {
             let info = securities.entry(asset.name.clone()).or_default();
 
             // We can have multiple rows per one instrument: a technical one with ISIN and a real one with stock symbol
-            if let Ok(isin) = parse_isin(&asset.code) {
+            if let SecurityId::Isin(isin) = SecurityId::parse(&asset.code) {
                 trace!("* ISIN: {}: {}", isin, asset.name);
                 info.isin.insert(isin);
             } else {
@@ -44,6 +44,10 @@ impl SectionParser for AssetsParser {
             if asset.planned != 0 {
                 statement.add_open_position(&asset.code, asset.planned.into())?;
             }
+
+            if let Some(pledged) = asset.pledged.filter(|&pledged| pledged != 0) {
+                statement.add_pledged_position(&asset.code, pledged.into())?;
+            }
         }
 
         Ok(())
@@ -79,6 +83,10 @@ struct AssetsRow {
     _10: Option<SkipCell>,
     #[column(name="Рыночная стоимость", optional=true)]
     _11: Option<SkipCell>,
+
+    // Securities pledged as collateral for repo or margin trading
+    #[column(name="В т.ч. в залоге", optional=true, strict=false)]
+    pledged: Option<u32>,
 }
 
 impl TableReader for AssetsRow {
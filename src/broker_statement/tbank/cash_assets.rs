@@ -5,7 +5,9 @@ use chrono::Datelike;
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::broker_statement::{CashFlow, CashFlowType, Coupon, CorporateAction, CorporateActionType};
 use crate::broker_statement::fees::Fee;
+use crate::broker_statement::grants::CashGrant;
 use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
 use crate::broker_statement::payments::Withholding;
 use crate::core::{EmptyResult, GenericResult};
@@ -219,6 +221,19 @@ impl CashFlowRow {
                 // All commissions are calculated during trades processing
             },
 
+            // Promotional bonuses (for example, for participation in broker's marketing campaigns)
+            "Бонус" | "Зачисление бонуса" | "Вознаграждение по акции" => {
+                let amount = check_amount(deposit)?;
+                let description = self.comment.as_deref().unwrap_or(operation);
+                statement.cash_grants.push(CashGrant::new(date, amount, description));
+            },
+
+            // Securities lending income (the broker borrows client's securities overnight)
+            "Доход от оверната ЦБ" => {
+                let amount = check_amount(deposit)?;
+                statement.cash_flows.push(CashFlow::new(date.into(), amount, CashFlowType::SecuritiesLending));
+            },
+
             "Комиссия по тарифу" => {
                 let amount = check_amount(withdrawal)?;
                 let description = operation.clone();
@@ -240,6 +255,41 @@ impl CashFlowRow {
                 statement.tax_accruals(self.execution_date, issuer_id, true).add(date, amount);
             },
 
+            "Выплата купонов" => {
+                let (isin, _quantity) = parse_bond_description(self.comment.as_deref().unwrap_or_default())?;
+                statement.coupons.push(Coupon::new(date, isin, check_amount(deposit)?));
+            },
+            "Частичное погашение облигации" => {
+                let (isin, _quantity) = parse_bond_description(self.comment.as_deref().unwrap_or_default())?;
+                let amount = check_amount(deposit)?;
+
+                statement.corporate_actions.push(CorporateAction {
+                    time: date.into(),
+                    report_date: Some(self.execution_date),
+                    symbol: isin.to_owned(),
+                    action: CorporateActionType::Amortization {
+                        amount: amount.amount,
+                        currency: amount.currency.to_owned(),
+                    },
+                });
+            },
+            "Погашение облигации" => {
+                let (isin, quantity) = parse_bond_description(self.comment.as_deref().unwrap_or_default())?;
+                let amount = check_amount(deposit)?;
+
+                statement.corporate_actions.push(CorporateAction {
+                    time: date.into(),
+                    report_date: Some(self.execution_date),
+                    symbol: isin.to_owned(),
+                    action: CorporateActionType::Liquidation {
+                        quantity,
+                        price: amount.amount / quantity,
+                        volume: amount.amount,
+                        currency: amount.currency.to_owned(),
+                    },
+                });
+            },
+
             "Налог" => {
                 let year = date.year();
 
@@ -272,6 +322,23 @@ fn parse_dividend_description(description: &str) -> GenericResult<&str> {
     Ok(captures.name("issuer_name").unwrap().as_str())
 }
 
+fn parse_bond_description(description: &str) -> GenericResult<(&str, Decimal)> {
+    lazy_static! {
+        static ref REGEX: Regex = Regex::new(&format!(
+            r"^(?P<isin>{isin})/ [^/]+/ (?P<quantity>\d+) шт\.$", isin=ISIN_REGEX),
+        ).unwrap();
+    }
+
+    let captures = REGEX.captures(description).ok_or_else(|| format!(
+        "Unexpected bond operation description: {:?}", description))?;
+
+    let isin = captures.name("isin").unwrap().as_str();
+    let quantity = captures.name("quantity").unwrap().as_str().parse::<Decimal>().map_err(|_| format!(
+        "Unexpected bond operation description: {:?}", description))?;
+
+    Ok((isin, quantity))
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -284,4 +351,12 @@ mod tests {
     fn dividend_parsing(description: &str, issuer: &str) {
         assert_eq!(parse_dividend_description(description).unwrap(), issuer);
     }
+
+    #[test]
+    fn bond_operation_parsing() {
+        assert_eq!(
+            parse_bond_description("RU000A0JX0J2/ ОФЗ 26222/ 15 шт.").unwrap(),
+            ("RU000A0JX0J2", dec!(15)));
+        assert!(parse_bond_description("ОФЗ 26222/ 15 шт.").is_err());
+    }
 }
\ No newline at end of file
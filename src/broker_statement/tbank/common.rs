@@ -4,8 +4,10 @@ use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use isin::ISIN;
+use log::warn;
 
-use crate::core::{EmptyResult, GenericResult};
+use crate::brokers::Broker;
+use crate::core::GenericResult;
 use crate::exchanges::Exchange;
 use crate::formats::xls::{self, SheetReader, Cell, CellType};
 use crate::instruments::InstrumentInfo;
@@ -74,10 +76,8 @@ pub fn parse_fractional_quantity_cell(cell: &Cell) -> GenericResult<Decimal> {
 
 pub fn parse_decimal_cell(cell: &Cell) -> GenericResult<Decimal> {
     match cell {
-        Cell::String(value) => {
-            let value = value.replace(',', ".");
-            util::parse_decimal(&value, DecimalRestrictions::No)
-        },
+        Cell::String(value) => util::parse_formatted_decimal(
+            value, Broker::Tbank.number_format(), DecimalRestrictions::No),
         _ => Decimal::parse(cell, true),
     }
 }
@@ -108,12 +108,61 @@ pub fn read_next_table_row(sheet: &mut SheetReader) -> Option<&[Cell]> {
     None
 }
 
-pub fn save_instrument_exchange_info(instruments: &mut InstrumentInfo, symbol: &str, exchange: &str) -> EmptyResult {
-    let exchange = match exchange {
-        "ММВБ" | "МосБиржа" => Exchange::Moex,
-        "СПБ" | "СПБиржа" => Exchange::Spb,
-        "ВНБ" => Exchange::Otc, // https://github.com/KonishchevDmitry/investments/issues/82
-        _ => return Err!("Unknown exchange: {:?}", exchange),
-    };
-    Ok(instruments.get_or_add(symbol).exchanges.add_prioritized(exchange))
-}
\ No newline at end of file
+pub type ExchangesRegistryRc = Rc<RefCell<ExchangesRegistry>>;
+
+pub struct ExchangesRegistry {
+    aliases: HashMap<String, Exchange>,
+    unknown: HashSet<String>,
+}
+
+impl ExchangesRegistry {
+    pub fn new(aliases: HashMap<String, Exchange>) -> ExchangesRegistryRc {
+        Rc::new(RefCell::new(ExchangesRegistry {aliases, unknown: HashSet::new()}))
+    }
+
+    fn get(&mut self, exchange: &str) -> Exchange {
+        if let Some(&alias) = self.aliases.get(exchange) {
+            return alias;
+        }
+
+        match exchange {
+            "ММВБ" | "МосБиржа" => Exchange::Moex,
+            "СПБ" | "СПБиржа" => Exchange::Spb,
+            "ВНБ" => Exchange::Otc, // https://github.com/KonishchevDmitry/investments/issues/82
+            _ => {
+                if self.unknown.insert(exchange.to_owned()) {
+                    warn!(concat!(
+                        "Got an unknown exchange: {:?}. Considering it as OTC. ",
+                        "Use brokers.tbank.exchange_aliases configuration option to specify its actual meaning."
+                    ), exchange);
+                }
+                Exchange::Otc
+            },
+        }
+    }
+}
+
+pub fn save_instrument_exchange_info(
+    instruments: &mut InstrumentInfo, exchanges: &mut ExchangesRegistry, symbol: &str, exchange: &str,
+) {
+    let exchange = exchanges.get(exchange);
+    instruments.get_or_add(symbol).exchanges.add_prioritized(exchange)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(code, expected,
+        case("ММВБ", Exchange::Moex),
+        case("СПБиржа", Exchange::Spb),
+        case("ВНБ", Exchange::Otc),
+        case("ПФТС", Exchange::Moex),
+        case("Неизвестная", Exchange::Otc),
+    )]
+    fn exchange_parsing(code: &str, expected: Exchange) {
+        let exchanges = ExchangesRegistry::new(hashmap!{s!("ПФТС") => Exchange::Moex});
+        assert!(exchanges.borrow_mut().get(code) == expected);
+    }
+}
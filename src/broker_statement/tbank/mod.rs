@@ -39,15 +39,15 @@ use crate::core::{GenericResult, EmptyResult, GenericError};
 use crate::exchanges::Exchange;
 use crate::formats::xls::{XlsStatementParser, Section, SheetParser, SectionParserRc, Cell};
 use crate::formatting;
-use crate::instruments::{InstrumentId, parse_isin};
+use crate::instruments::{InstrumentId, SecurityId};
 #[cfg(test)] use crate::taxes::TaxRemapping;
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementSections};
 use super::{BrokerStatementReader, PartialBrokerStatement};
 
 use assets::AssetsParser;
 use cash_assets::CashAssetsParser;
-use common::SecuritiesRegistryRc;
+use common::{ExchangesRegistry, ExchangesRegistryRc, SecuritiesRegistryRc};
 use foreign_income::ForeignIncomeStatementReader;
 use period::PeriodParser;
 use securities::SecuritiesInfoParser;
@@ -55,14 +55,16 @@ use trades::{TradesParser, TradesRegistryRc};
 
 pub struct StatementReader {
     trades: TradesRegistryRc,
+    exchanges: ExchangesRegistryRc,
     foreign_income: HashMap<DividendId, (DividendAccruals, TaxAccruals)>,
     show_missing_foreign_income_info_warning: bool,
 }
 
 impl StatementReader {
-    pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
+    pub fn new(exchange_aliases: HashMap<String, Exchange>) -> GenericResult<Box<dyn BrokerStatementReader>> {
         Ok(Box::new(StatementReader{
             trades: TradesRegistryRc::default(),
+            exchanges: ExchangesRegistry::new(exchange_aliases),
             foreign_income: HashMap::new(),
             show_missing_foreign_income_info_warning: true,
         }))
@@ -85,7 +87,7 @@ impl StatementReader {
         let mut taxes = HashMap::new();
 
         for trade in &mut statement.stock_buys {
-            if let Ok(isin) = parse_isin(&trade.symbol) {
+            if let SecurityId::Isin(isin) = SecurityId::parse(&trade.symbol) {
                 let instrument = statement.instrument_info.get_by_id(&InstrumentId::Isin(isin)).map_err(|e| format!(
                     "Failed to remap {} trade from ISIN to stock symbol: {}", trade.symbol, e))?;
                 trade.original_symbol.clone_from(&instrument.symbol);
@@ -94,7 +96,7 @@ impl StatementReader {
         }
 
         for trade in &mut statement.stock_sells {
-            if let Ok(isin) = parse_isin(&trade.symbol) {
+            if let SecurityId::Isin(isin) = SecurityId::parse(&trade.symbol) {
                 let instrument = statement.instrument_info.get_by_id(&InstrumentId::Isin(isin)).map_err(|e| format!(
                     "Failed to remap {} trade from ISIN to stock symbol: {}", trade.symbol, e))?;
                 trade.original_symbol.clone_from(&instrument.symbol);
@@ -105,20 +107,38 @@ impl StatementReader {
         for cash_flow in &mut statement.cash_flows {
             match &mut cash_flow.type_ {
                 CashFlowType::Repo {symbol, ..} => {
-                    if let Ok(isin) = parse_isin(symbol) {
+                    if let SecurityId::Isin(isin) = SecurityId::parse(symbol) {
                         let instrument = statement.instrument_info.get_by_id(&InstrumentId::Isin(isin)).map_err(|e| format!(
                             "Failed to remap {} trade from ISIN to stock symbol: {}", symbol, e))?;
                         symbol.clone_from(&instrument.symbol);
                     }
                 },
+                CashFlowType::SecuritiesLending => {},
                 CashFlowType::Dividend {..} | CashFlowType::Tax {..} => {
                     unreachable!();
                 },
             }
         }
 
+        for coupon in &mut statement.coupons {
+            if let SecurityId::Isin(isin) = SecurityId::parse(&coupon.symbol) {
+                let instrument = statement.instrument_info.get_by_id(&InstrumentId::Isin(isin)).map_err(|e| format!(
+                    "Failed to remap {} coupon from ISIN to stock symbol: {}", coupon.symbol, e))?;
+                coupon.original_symbol.clone_from(&instrument.symbol);
+                coupon.symbol.clone_from(&instrument.symbol);
+            }
+        }
+
+        for action in &mut statement.corporate_actions {
+            if let SecurityId::Isin(isin) = SecurityId::parse(&action.symbol) {
+                let instrument = statement.instrument_info.get_by_id(&InstrumentId::Isin(isin)).map_err(|e| format!(
+                    "Failed to remap {} corporate action from ISIN to stock symbol: {}", action.symbol, e))?;
+                action.symbol.clone_from(&instrument.symbol);
+            }
+        }
+
         for symbol in statement.open_positions.keys().cloned().collect::<Vec<String>>() {
-            if let Ok(isin) = parse_isin(&symbol) {
+            if let SecurityId::Isin(isin) = SecurityId::parse(&symbol) {
                 let map_err = |e: GenericError| -> GenericError {
                     format!("Failed to remap {} open position from ISIN to stock symbol: {}", symbol, e).into()
                 };
@@ -126,6 +146,10 @@ impl StatementReader {
                 let new_symbol = statement.instrument_info.get_by_id(&InstrumentId::Isin(isin)).map_err(map_err)?.symbol.clone();
                 let quantity = statement.open_positions.remove(&symbol).unwrap();
                 statement.add_open_position(&new_symbol, quantity).map_err(map_err)?;
+
+                if let Some(quantity) = statement.pledged_positions.remove(&symbol) {
+                    statement.add_pledged_position(&new_symbol, quantity).map_err(map_err)?;
+                }
             }
         }
 
@@ -190,16 +214,16 @@ impl BrokerStatementReader for StatementReader {
             PeriodParser::new(statement.clone())));
 
         let executed_trades_parser: SectionParserRc = Rc::new(RefCell::new(
-            TradesParser::new(true, statement.clone(), self.trades.clone())));
+            TradesParser::new(true, statement.clone(), self.trades.clone(), self.exchanges.clone())));
 
         let pending_trades_parser: SectionParserRc = Rc::new(RefCell::new(
-            TradesParser::new(false, statement.clone(), self.trades.clone())));
+            TradesParser::new(false, statement.clone(), self.trades.clone(), self.exchanges.clone())));
 
         let cash_assets_parser = CashAssetsParser::new(statement.clone());
 
         let securities = SecuritiesRegistryRc::default();
         let assets_parser = AssetsParser::new(statement.clone(), securities.clone());
-        let securities_info_parser = SecuritiesInfoParser::new(statement.clone(), securities);
+        let securities_info_parser = SecuritiesInfoParser::new(statement.clone(), securities, self.exchanges.clone());
 
         XlsStatementParser::read(path, parser, vec![
             Section::new(PeriodParser::CALCULATION_DATE_PREFIX).by_prefix()
@@ -329,7 +353,7 @@ mod tests {
         BrokerStatement::read(
             broker, &format!("testdata/tbank/{}", name),
             &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(), &[],
-            &portfolio.corporate_actions, ReadingStrictness::all(),
+            &portfolio.corporate_actions, ReadingStrictness::all(), StatementSections::all(),
         ).unwrap()
     }
 }
\ No newline at end of file
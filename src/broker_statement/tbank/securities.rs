@@ -7,16 +7,20 @@ use crate::formats::xls::{self, XlsTableRow, XlsStatementParser, SectionParser,
 use crate::instruments::parse_isin;
 
 use super::common::{
-    SecuritiesRegistry, SecuritiesRegistryRc, read_next_table_row, save_instrument_exchange_info, trim_column_title};
+    ExchangesRegistryRc, SecuritiesRegistry, SecuritiesRegistryRc, read_next_table_row, save_instrument_exchange_info,
+    trim_column_title};
 
 pub struct SecuritiesInfoParser {
     statement: PartialBrokerStatementRc,
     securities: SecuritiesRegistryRc,
+    exchanges: ExchangesRegistryRc,
 }
 
 impl SecuritiesInfoParser {
-    pub fn new(statement: PartialBrokerStatementRc, securities: SecuritiesRegistryRc) -> Box<dyn SectionParser> {
-        Box::new(SecuritiesInfoParser {statement, securities})
+    pub fn new(
+        statement: PartialBrokerStatementRc, securities: SecuritiesRegistryRc, exchanges: ExchangesRegistryRc,
+    ) -> Box<dyn SectionParser> {
+        Box::new(SecuritiesInfoParser {statement, securities, exchanges})
     }
 }
 
@@ -38,7 +42,8 @@ impl SectionParser for SecuritiesInfoParser {
                     continue;
                 }
 
-                save_instrument_exchange_info(&mut statement.instrument_info, symbol, exchange)?;
+                save_instrument_exchange_info(
+                    &mut statement.instrument_info, &mut self.exchanges.borrow_mut(), symbol, exchange);
             }
 
             let instrument = statement.instrument_info.get_or_add(symbol);
@@ -6,6 +6,7 @@ use std::rc::Rc;
 
 use log::debug;
 
+use crate::broker_statement::bonds;
 use crate::broker_statement::cash_flows::{CashFlow, CashFlowType};
 use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
 use crate::broker_statement::trades::{ForexTrade, StockBuy, StockSell};
@@ -20,8 +21,8 @@ use crate::util;
 use crate::util::DecimalRestrictions;
 
 use super::common::{
-    read_next_table_row, parse_date_cell, parse_planned_actual_date_cell, parse_decimal_cell,
-    parse_fractional_quantity_cell, parse_time_cell, save_instrument_exchange_info, trim_column_title};
+    ExchangesRegistry, ExchangesRegistryRc, read_next_table_row, parse_date_cell, parse_planned_actual_date_cell,
+    parse_decimal_cell, parse_fractional_quantity_cell, parse_time_cell, save_instrument_exchange_info, trim_column_title};
 
 pub type TradesRegistryRc = Rc<RefCell<HashMap<TradeId, bool>>>;
 
@@ -29,13 +30,15 @@ pub struct TradesParser {
     executed: bool,
     statement: PartialBrokerStatementRc,
     processed_trades: TradesRegistryRc,
+    exchanges: ExchangesRegistryRc,
 }
 
 impl TradesParser {
     pub fn new(
         executed: bool, statement: PartialBrokerStatementRc, processed_trades: TradesRegistryRc,
+        exchanges: ExchangesRegistryRc,
     ) -> Box<dyn SectionParser> {
-        Box::new(TradesParser {executed, processed_trades, statement})
+        Box::new(TradesParser {executed, processed_trades, statement, exchanges})
     }
 
     fn check_trade_id(&self, trade_id: &TradeId) -> GenericResult<bool> {
@@ -92,7 +95,7 @@ impl SectionParser for TradesParser {
                 continue;
             }
 
-            trade.parse(&mut statement)?;
+            trade.parse(&mut statement, &mut self.exchanges.borrow_mut())?;
         }
 
         Ok(())
@@ -184,11 +187,7 @@ impl TableReader for TradeRow {
 }
 
 impl TradeRow {
-    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
-        if !self.accumulated_coupon_income.is_zero() {
-            return Err!("Bonds aren't supported yet");
-        }
-
+    fn parse(self, statement: &mut PartialBrokerStatement, exchanges: &mut ExchangesRegistry) -> EmptyResult {
         let forex = parse_forex_code(&self.symbol).ok();
         let operation = self.operation.as_str();
 
@@ -197,12 +196,17 @@ impl TradeRow {
             return Err!("Invalid {} trade quantity: {}", self.symbol, self.quantity);
         }
 
-        let price = util::validate_named_cash(
+        let mut price = util::validate_named_cash(
             "price", &self.price_currency, self.price, DecimalRestrictions::StrictlyPositive)?;
 
-        let volume = util::validate_named_cash(
+        let mut volume = util::validate_named_cash(
             "trade volume", &self.settlement_currency, self.volume, DecimalRestrictions::StrictlyPositive)?;
-        debug_assert_eq!(volume, (price * self.quantity).round());
+
+        if self.accumulated_coupon_income.is_zero() {
+            debug_assert_eq!(volume, (price * self.quantity).round());
+        } else {
+            (price, volume) = bonds::get_bond_trade_volume(self.quantity, volume, self.accumulated_coupon_income)?;
+        }
 
         let mut commission = match self.commission_currency {
             Some(currency) => {
@@ -271,6 +275,7 @@ impl TradeRow {
 
                 statement.cash_flows.push(CashFlow::new(conclusion_time.into(), amount, CashFlowType::Repo {
                     symbol: self.symbol.clone(),
+                    quantity: self.quantity,
                     commission
                 }));
 
@@ -282,7 +287,7 @@ impl TradeRow {
         // Old statements contain a valid exchange, but later the column has been broken and now always contains the same value "Б"
         if forex.is_none() && !repo_trade && self.exchange != "Б" {
             save_instrument_exchange_info(
-                &mut statement.instrument_info, &self.symbol, &self.exchange)?;
+                &mut statement.instrument_info, exchanges, &self.symbol, &self.exchange);
         }
 
         Ok(())
@@ -22,6 +22,41 @@ impl ForexTrade {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DerivativeType {
+    Option,
+    Futures,
+}
+
+// Derivative contract trade. Unlike stocks, derivative positions may be opened by sells (written options or short
+// futures), so quantity is positive for buys and negative for sells.
+pub struct DerivativeTrade {
+    pub type_: DerivativeType,
+    pub symbol: String,
+
+    pub quantity: Decimal,
+    pub price: Cash,
+    // Positive for sells and negative for buys. It's option premium or futures notional value - the later isn't paid
+    // in cash, but difference between opening and closing notional values is exactly the variation margin accumulated
+    // by the contract.
+    pub volume: Cash,
+    pub commission: Cash,
+
+    pub conclusion_time: DateOptTime,
+    pub execution_date: Date,
+}
+
+impl DerivativeTrade {
+    pub fn new(
+        type_: DerivativeType, symbol: &str, quantity: Decimal, price: Cash, volume: Cash, commission: Cash,
+        conclusion_time: DateOptTime, execution_date: Date,
+    ) -> DerivativeTrade {
+        DerivativeTrade {
+            type_, symbol: symbol.to_owned(), quantity, price, volume, commission, conclusion_time, execution_date,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum StockSource {
     // Ordinary trade
@@ -129,6 +164,19 @@ impl StockBuy {
             execution_date: self.execution_date,
         }
     }
+
+    // Returns the specified part of the purchase cost of the unsold shares (for example, on bond amortization)
+    pub fn reduce_cost(&mut self, date: Date, amount: Cash) {
+        assert!(!self.is_sold());
+
+        // Cost of partial sells is calculated as a fraction of the whole lot cost, so scale the amount accordingly
+        let cost = -amount * (self.quantity / self.get_unsold());
+
+        self.cost.0.push(PurchaseCost {
+            transactions: vec![PurchaseTransaction::new(date, PurchaseCostType::Trade, cost)],
+            fraction: Fraction(dec!(1), dec!(1)),
+        });
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -150,6 +198,14 @@ pub enum StockSellType {
     CorporateAction,
 }
 
+/// Specific tax lot identified by its purchase date
+#[derive(Clone, Debug, PartialEq)]
+pub struct SellLot {
+    pub symbol: String,
+    pub date: Date,
+    pub quantity: Decimal,
+}
+
 #[derive(Clone)]
 pub struct StockSell {
     pub symbol: String,
@@ -163,6 +219,7 @@ pub struct StockSell {
     pub out_of_order_execution: bool,
 
     pub emulation: bool,
+    pub lots: Vec<SellLot>, // Specific lots to close before falling back to FIFO (supported only for emulated sells)
     sources: Vec<StockSellSource>,
 }
 
@@ -175,7 +232,7 @@ impl StockSell {
             symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
             quantity, type_: StockSellType::Trade {price, volume, commission},
             conclusion_time, execution_date, out_of_order_execution: false,
-            emulation, sources: Vec::new(),
+            emulation, lots: Vec::new(), sources: Vec::new(),
         }
     }
 
@@ -186,7 +243,7 @@ impl StockSell {
             symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
             quantity, type_: StockSellType::CorporateAction,
             conclusion_time, execution_date, out_of_order_execution: true,
-            emulation: false, sources: Vec::new(),
+            emulation: false, lots: Vec::new(), sources: Vec::new(),
         }
     }
 
@@ -194,6 +251,11 @@ impl StockSell {
         !self.sources.is_empty()
     }
 
+    #[cfg(test)]
+    pub fn sources(&self) -> &[StockSellSource] {
+        &self.sources
+    }
+
     pub fn process(&mut self, sources: Vec<StockSellSource>) {
         assert!(!self.is_processed());
         assert_eq!(
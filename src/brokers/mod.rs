@@ -1,10 +1,12 @@
 mod plans;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
 
 use matches::matches;
 use serde::Deserialize;
 use serde::de::{Deserializer, Error as _};
+use strum::EnumIter;
 
 use crate::broker_statement::StatementsMergingStrategy;
 use crate::commissions::CommissionSpec;
@@ -13,98 +15,165 @@ use crate::core::GenericResult;
 use crate::currency::{Cash, CashAssets};
 use crate::exchanges::Exchange;
 use crate::localities::{Country, Jurisdiction};
+use crate::types::Decimal;
+use crate::util::NumberFormat;
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+type PlanFn = fn() -> CommissionSpec;
+
+const BROKER_IDS: &[&str] = &[
+    "alfa", "bcs", "firstrade", "interactive-brokers", "open-broker", "sber", "schwab", "tbank",
+];
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, EnumIter)]
 pub enum Broker {
+    Alfa,
     Bcs,
     Firstrade,
     InteractiveBrokers,
     Open,
     Sber,
+    Schwab,
     Tbank,
 }
 
 impl Broker {
     pub fn get_info(self, config: &Config, plan: Option<&String>) -> GenericResult<BrokerInfo> {
+        let exchange_aliases = match self {
+            Broker::Tbank => config.brokers.as_ref()
+                .and_then(|brokers| brokers.tbank.as_ref())
+                .map(|tbank| tbank.exchange_aliases.clone())
+                .unwrap_or_default(),
+            _ => HashMap::new(),
+        };
+
         let config = config.brokers.as_ref()
             .and_then(|brokers| self.get_config(brokers).cloned())
             .unwrap_or_default();
 
-        let statements_merging_strategy = match self {
-            Broker::InteractiveBrokers => StatementsMergingStrategy::SparseOnHolidays(1),
-            Broker::Open => StatementsMergingStrategy::SparseSingleDaysLastMonth(0),
-            Broker::Sber => StatementsMergingStrategy::Sparse,
-            _ => StatementsMergingStrategy::ContinuousOnly,
-        };
-
         Ok(BrokerInfo {
             type_: self,
             name: self.name(),
             brief_name: self.brief_name(),
 
             config: config,
+            exchange_aliases,
             commission_spec: self.get_commission_spec(plan)?,
             allow_future_fees: matches!(self, Broker::Tbank),
             fractional_shares_trading: matches!(self, Broker::InteractiveBrokers),
-            statements_merging_strategy: statements_merging_strategy,
+            statements_merging_strategy: self.statements_merging_strategy(),
         })
     }
 
     pub fn id(self) -> &'static str {
         match self {
+            Broker::Alfa => "alfa",
             Broker::Bcs => "bcs",
             Broker::Firstrade => "firstrade",
             Broker::InteractiveBrokers => "interactive-brokers",
             Broker::Open => "open",
             Broker::Sber => "sber",
+            Broker::Schwab => "schwab",
             Broker::Tbank => "tbank",
         }
     }
 
     pub fn name(self) -> &'static str {
         match self {
+            Broker::Alfa => "АО «Альфа-Банк»",
             Broker::Bcs => "ООО «Компания БКС»",
             Broker::Firstrade => "Firstrade Securities Inc.",
             Broker::InteractiveBrokers => "Interactive Brokers LLC",
             Broker::Open => "АО «Открытие Брокер»",
             Broker::Sber => "ПАО «Сбербанк»",
+            Broker::Schwab => "Charles Schwab & Co., Inc.",
             Broker::Tbank => "АО «ТБанк»",
         }
     }
 
     pub fn brief_name(self) -> &'static str {
         match self {
+            Broker::Alfa => "Альфа-Инвестиции",
             Broker::Bcs => "БКС",
             Broker::Firstrade => "Firstrade",
             Broker::InteractiveBrokers => "Interactive Brokers",
             Broker::Open => "Открытие",
             Broker::Sber => "Сбер",
+            Broker::Schwab => "Charles Schwab",
             Broker::Tbank => "Т‑Банк",
         }
     }
 
     pub fn jurisdiction(self) -> Jurisdiction {
         match self {
-            Broker::Bcs | Broker::Open | Broker::Sber | Broker::Tbank => Jurisdiction::Russia,
-            Broker::Firstrade | Broker::InteractiveBrokers => Jurisdiction::Usa,
+            Broker::Alfa | Broker::Bcs | Broker::Open | Broker::Sber | Broker::Tbank => Jurisdiction::Russia,
+            Broker::Firstrade | Broker::InteractiveBrokers | Broker::Schwab => Jurisdiction::Usa,
+        }
+    }
+
+    pub fn statements_merging_strategy(self) -> StatementsMergingStrategy {
+        match self {
+            Broker::InteractiveBrokers => StatementsMergingStrategy::SparseOnHolidays(1),
+            Broker::Open => StatementsMergingStrategy::SparseSingleDaysLastMonth(0),
+            Broker::Sber => StatementsMergingStrategy::Sparse,
+            _ => StatementsMergingStrategy::ContinuousOnly,
+        }
+    }
+
+    /// Number formatting conventions used in the broker's statements
+    pub fn number_format(self) -> NumberFormat {
+        match self {
+            Broker::Alfa | Broker::Bcs | Broker::Open | Broker::Sber | Broker::Tbank => NumberFormat::Russian,
+            Broker::Firstrade | Broker::InteractiveBrokers | Broker::Schwab => NumberFormat::English,
         }
     }
 
     fn get_config(self, config: &BrokersConfig) -> Option<&BrokerConfig> {
         match self {
+            Broker::Alfa => config.alfa.as_ref(),
             Broker::Bcs => config.bcs.as_ref(),
             Broker::Firstrade => config.firstrade.as_ref(),
             Broker::InteractiveBrokers => config.interactive_brokers.as_ref(),
             Broker::Open => config.open_broker.as_ref(),
             Broker::Sber => config.sber.as_ref(),
+            Broker::Schwab => config.schwab.as_ref(),
             Broker::Tbank => config.tbank.as_ref().and_then(|tbank| tbank.broker.as_ref()),
         }
     }
 
+    /// Returns all available commission plans (an empty plan name for brokers with a single implicit plan)
+    pub fn get_commission_plans(self) -> Vec<(&'static str, CommissionSpec)> {
+        let (default, plans) = self.get_plans();
+
+        if plans.is_empty() {
+            return vec![("", default())];
+        }
+
+        plans.into_iter().map(|(name, plan)| (name, plan())).collect()
+    }
+
     fn get_commission_spec(self, plan: Option<&String>) -> GenericResult<CommissionSpec> {
-        type PlanFn = fn() -> CommissionSpec;
+        let (default, plans) = self.get_plans();
+
+        let plan = match plan {
+            Some(plan) => {
+                *plans.get(plan.as_str()).ok_or_else(|| format!(
+                    "Invalid plan for {}: {}. Available plans: {}",
+                    self.name(), plan, plans.keys().copied().collect::<Vec<_>>().join(", "),
+                ))?
+            },
+            None => default,
+        };
+
+        Ok(plan())
+    }
+
+    fn get_plans(self) -> (PlanFn, BTreeMap<&'static str, PlanFn>) {
+        match self {
+            Broker::Alfa => (plans::alfa::investor, btreemap!{
+                "Инвестор" => plans::alfa::investor as PlanFn,
+                "Трейдер" => plans::alfa::trader as PlanFn,
+            }),
 
-        let (default, plans): (PlanFn, BTreeMap<&str, PlanFn>) = match self {
             Broker::Bcs => (plans::bcs::investor, btreemap!{
                 "Инвестор" => plans::bcs::investor as PlanFn,
                 "Трейдер" => plans::bcs::trader as PlanFn,
@@ -129,47 +198,45 @@ impl Broker {
                 "Самостоятельный" => plans::sber::manual as PlanFn,
             }),
 
+            Broker::Schwab => (plans::schwab::free, btreemap!{}),
+
             Broker::Tbank => (plans::tbank::investor, btreemap!{
                 "Инвестор" => plans::tbank::investor as PlanFn,
                 "Трейдер" => plans::tbank::trader as PlanFn,
                 "Премиум" => plans::tbank::premium as PlanFn,
             }),
-        };
-
-        let plan = match plan {
-            Some(plan) => {
-                *plans.get(plan.as_str()).ok_or_else(|| format!(
-                    "Invalid plan for {}: {}. Available plans: {}",
-                    self.name(), plan, plans.keys().copied().collect::<Vec<_>>().join(", "),
-                ))?
-            },
-            None => default,
-        };
-
-        Ok(plan())
+        }
     }
 }
 
-impl<'de> Deserialize<'de> for Broker {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
-        let value = String::deserialize(deserializer)?;
+impl FromStr for Broker {
+    type Err = String;
 
-        Ok(match value.as_str() {
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "alfa" => Broker::Alfa,
             "bcs" => Broker::Bcs,
             "firstrade" => Broker::Firstrade,
             "interactive-brokers" => Broker::InteractiveBrokers,
             "open-broker" => Broker::Open,
             "sber" => Broker::Sber,
+            "schwab" => Broker::Schwab,
             "tbank" => Broker::Tbank,
             "tinkoff" => Broker::Tbank,
 
-            _ => return Err(D::Error::unknown_variant(&value, &[
-                "bcs", "firstrade", "interactive-brokers", "open-broker", "sber", "tbank",
-            ])),
+            _ => return Err(format!(
+                "Unknown broker: {:?} (expected one of: {})", value, BROKER_IDS.join(", "))),
         })
     }
 }
 
+impl<'de> Deserialize<'de> for Broker {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(|_| D::Error::unknown_variant(&value, BROKER_IDS))
+    }
+}
+
 #[derive(Clone)]
 pub struct BrokerInfo {
     pub type_: Broker,
@@ -177,6 +244,7 @@ pub struct BrokerInfo {
     pub brief_name: &'static str,
 
     config: BrokerConfig,
+    pub exchange_aliases: HashMap<String, Exchange>,
     pub commission_spec: CommissionSpec,
     pub allow_future_fees: bool,
     pub fractional_shares_trading: bool,
@@ -198,12 +266,69 @@ impl BrokerInfo {
         Ok(Cash::new(currency, commission))
     }
 
+    /// Returns expected commission for currency conversion to the specified amount (if it's configured)
+    pub fn get_forex_commission(&self, amount: Cash) -> Option<Cash> {
+        let spec = self.config.forex_commission.as_ref()?;
+        let commission = std::cmp::max(amount.amount * spec.percent / dec!(100), spec.minimum);
+        Some(Cash::new(amount.currency, commission).round())
+    }
+
+    /// Returns minimum quantity increment which is used to round displayed quantities. Brokers with fractional shares
+    /// trading have their own precision, while for others fractional quantities may appear only after corporate
+    /// actions.
+    pub fn quantity_increment(&self) -> Decimal {
+        self.config.quantity_increment.unwrap_or(if self.fractional_shares_trading {
+            dec!(0.0001)
+        } else {
+            dec!(0.000001)
+        })
+    }
+
+    /// Returns number of days after the expected payment date after which unpaid dividends are considered to be at risk
+    /// of not being paid at all (for example, dividends on securities blocked due to sanctions).
+    pub fn at_risk_dividends_days(&self) -> u32 {
+        self.config.at_risk_dividends_days.unwrap_or(30)
+    }
+
+    /// Returns names of external statement parsers which are used for the files unsupported by the native parser
+    pub fn statement_plugins(&self) -> &[String] {
+        &self.config.statement_plugins
+    }
+
     pub fn exchanges(&self) -> Vec<Exchange> {
         match self.type_ {
-            Broker::Bcs | Broker::Open | Broker::Sber => vec![Exchange::Moex, Exchange::Spb],
+            Broker::Alfa | Broker::Bcs | Broker::Open | Broker::Sber => vec![Exchange::Moex, Exchange::Spb],
             Broker::Tbank => vec![Exchange::Moex, Exchange::Spb, Exchange::Otc],
-            Broker::Firstrade => vec![Exchange::Us],
+            Broker::Firstrade | Broker::Schwab => vec![Exchange::Us],
             Broker::InteractiveBrokers => vec![Exchange::Us, Exchange::Other],
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+
+    use crate::types::Decimal;
+
+    use super::*;
+
+    #[rstest(amount, expected,
+        case(dec!(100), dec!(1)),
+        case(dec!(10_000), dec!(15)),
+    )]
+    fn forex_commission(amount: Decimal, expected: Decimal) {
+        let mut config = Config::mock();
+        config.brokers = Some(serde_yaml::from_str(indoc!("
+            interactive_brokers:
+              forex_commission: {percent: 0.15, minimum: 1}
+        ")).unwrap());
+
+        let broker = Broker::InteractiveBrokers.get_info(&config, None).unwrap();
+        assert_eq!(broker.get_forex_commission(Cash::new("RUB", amount)), Some(Cash::new("RUB", expected)));
+
+        let broker = Broker::Firstrade.get_info(&config, None).unwrap();
+        assert_eq!(broker.get_forex_commission(Cash::new("RUB", amount)), None);
+    }
+}
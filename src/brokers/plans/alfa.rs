@@ -0,0 +1,27 @@
+use crate::commissions::{
+    CommissionSpec, CommissionSpecBuilder, TradeCommissionSpecBuilder, TransactionCommissionSpecBuilder,
+    CumulativeCommissionSpecBuilder};
+
+pub fn investor() -> CommissionSpec {
+    CommissionSpecBuilder::new("RUB")
+        .trade(TradeCommissionSpecBuilder::new()
+            .commission(TransactionCommissionSpecBuilder::new()
+                .percent(dec!(0.3))
+                .build().unwrap())
+            .build())
+        .build()
+}
+
+// Please note: The plan has 199 ₽ monthly fee which is charged only for months with trades. It's not supported yet.
+pub fn trader() -> CommissionSpec {
+    CommissionSpecBuilder::new("RUB")
+        .trade(TradeCommissionSpecBuilder::new()
+            .commission(TransactionCommissionSpecBuilder::new()
+                .percent(dec!(0.014))
+                .build().unwrap())
+            .build())
+        .cumulative(CumulativeCommissionSpecBuilder::new()
+            .percent_fee(dec!(0.01)) // Estimated exchange fee
+            .build())
+        .build()
+}
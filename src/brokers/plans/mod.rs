@@ -1,6 +1,8 @@
+pub mod alfa;
 pub mod bcs;
 pub mod firstrade;
 pub mod ib;
 pub mod open;
 pub mod sber;
+pub mod schwab;
 pub mod tbank;
\ No newline at end of file
@@ -9,7 +9,7 @@ use crate::time::{Date, Period};
 use crate::types::Decimal;
 
 use super::mapper::{CashFlow, map_broker_statement_to_cash_flow};
-use super::comparator::CashAssetsComparator;
+use super::comparator::{CashAssetsComparator, ComparisonTolerance};
 
 pub struct CashFlowSummary {
     pub starting: Decimal,
@@ -18,7 +18,7 @@ pub struct CashFlowSummary {
     pub ending: Decimal,
 }
 
-pub fn calculate(statement: &BrokerStatement, period: Period) -> (
+pub fn calculate(statement: &BrokerStatement, period: Period, tolerance: ComparisonTolerance) -> (
     BTreeMap<&'static str, CashFlowSummary>, Vec<CashFlow>
 ) {
     let historical_cash_assets = statement.historical_assets.iter().map(|(&date, assets)| {
@@ -29,7 +29,7 @@ pub fn calculate(statement: &BrokerStatement, period: Period) -> (
     let ending_assets_date = period.last_date();
 
     let comparator = CashAssetsComparator::new(
-        &historical_cash_assets, vec![starting_assets_date, ending_assets_date]);
+        &historical_cash_assets, vec![starting_assets_date, ending_assets_date], tolerance);
 
     Calculator {
         statement, comparator,
@@ -76,8 +76,11 @@ impl Calculator<'_> {
             self.process_date(cash_flow.time.date);
 
             self.process_cash_flow(cash_flow.time.date, cash_flow.amount);
+            self.comparator.add_cash_flow(cash_flow.time.date, cash_flow.amount, &cash_flow.description);
+
             if let Some(amount) = cash_flow.sibling_amount {
                 self.process_cash_flow(cash_flow.time.date, amount);
+                self.comparator.add_cash_flow(cash_flow.time.date, amount, &cash_flow.description);
             }
         }
 
@@ -90,7 +93,7 @@ impl Calculator<'_> {
         }
 
         self.process_date(self.statement.period.next_date());
-        assert!(self.comparator.consumed());
+        self.comparator.finish();
 
         let mut summaries = BTreeMap::new();
         let starting_assets = self.starting_assets.unwrap();
@@ -1,81 +1,218 @@
-use std::collections::{BTreeMap, BTreeSet, btree_map};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use log::{Level, log};
 
 use crate::currency::{Cash, MultiCurrencyCashAccount};
 use crate::formatting::format_date;
-use crate::types::Date;
+use crate::types::{Date, Decimal};
+
+// Cash flows within this number of days around the mismatch date are considered as its possible causes
+const EXPLANATION_WINDOW: i64 = 7;
+
+/// Allowed difference between the calculated cash assets and the ones reported by the broker
+#[derive(Clone, Copy, Default)]
+pub struct ComparisonTolerance {
+    // Broker may report cash assets with settlement date drift (T+N), so calculated assets are also compared to the
+    // ones from the specified number of days around the date.
+    pub days: u32,
+    pub amount: Decimal,
+}
 
 pub struct CashAssetsComparator<'a> {
-    iter: btree_map::Iter<'a, Date, MultiCurrencyCashAccount>,
-    next: Option<(&'a Date, &'a MultiCurrencyCashAccount)>,
+    historical: &'a BTreeMap<Date, MultiCurrencyCashAccount>,
     important_dates: Vec<Date>,
-    currencies: BTreeSet<&'static str>,
+    tolerance: ComparisonTolerance,
+
+    calculated: BTreeMap<Date, MultiCurrencyCashAccount>, // Calculated assets at the end of the day
+    cash_flows: Vec<(Date, Cash, String)>,
+    next_date: Option<Date>,
 }
 
 impl<'a> CashAssetsComparator<'a> {
     pub fn new(
         historical: &'a BTreeMap<Date, MultiCurrencyCashAccount>, important_dates: Vec<Date>,
+        tolerance: ComparisonTolerance,
     ) -> CashAssetsComparator<'a> {
-        let mut comparator = CashAssetsComparator {
-            iter: historical.iter(),
-            next: None,
-            important_dates,
-            currencies: BTreeSet::new(),
-        };
-        comparator.next();
-        comparator
+        CashAssetsComparator {
+            historical, important_dates, tolerance,
+            calculated: BTreeMap::new(),
+            cash_flows: Vec::new(),
+            next_date: None,
+        }
     }
 
+    /// Registers the calculated assets which are actual for all days before the specified date
     pub fn compare(&mut self, date: Date, calculated: &MultiCurrencyCashAccount) {
-        while let Some((&historical_date, actual)) = self.next {
-            if historical_date >= date {
-                break
+        if self.next_date.is_some_and(|next_date| next_date >= date) {
+            return;
+        }
+
+        self.calculated.insert(date.pred_opt().unwrap(), calculated.clone());
+        self.next_date.replace(date);
+    }
+
+    pub fn add_cash_flow(&mut self, date: Date, amount: Cash, description: &str) {
+        self.cash_flows.push((date, amount, description.to_owned()));
+    }
+
+    /// Compares the calculated assets with the historical ones (must be called when all cash flows are processed)
+    pub fn finish(&self) {
+        let Some(last_date) = self.next_date.and_then(|date| date.pred_opt()) else {
+            return;
+        };
+        let last_compared_date = self.historical.range(..=last_date).next_back().map(|(&date, _)| date);
+
+        let mut currencies = BTreeSet::new();
+        let mut prev_errors = HashMap::new();
+
+        for (&date, actual) in self.historical.range(..=last_date) {
+            if let Some(calculated) = self.snapshot(date) {
+                currencies.extend(calculated.iter().map(|assets| assets.currency));
             }
+            currencies.extend(actual.iter().map(|assets| assets.currency));
+
+            let important = self.important_dates.contains(&date) || Some(date) == last_compared_date;
+
+            for &currency in &currencies {
+                let calculated_amount = self.calculated_at(date, currency);
+                let actual_amount = actual.get(currency).unwrap_or_else(|| Cash::zero(currency));
+
+                if calculated_amount == actual_amount {
+                    prev_errors.remove(currency);
+                    continue;
+                }
 
-            self.next();
-            self.compare_to(historical_date, actual, calculated);
+                let error = calculated_amount - actual_amount;
+
+                let (level, explanation) = match self.match_within_tolerance(date, actual_amount) {
+                    Some(explanation) => {
+                        prev_errors.remove(currency);
+                        (Level::Debug, explanation)
+                    },
+                    None => {
+                        let explanation = self.explain(
+                            date, error, prev_errors.get(currency), actual.get(currency).is_none());
+                        prev_errors.insert(currency, (date, error));
+                        (if important { Level::Warn } else { Level::Debug }, explanation)
+                    },
+                };
+
+                log!(level, "Calculation error for {}: {} vs {} ({}): {}",
+                     format_date(date), calculated_amount, actual_amount, error, explanation);
+            }
         }
     }
 
-    fn compare_to(&mut self,
-        date: Date, actual: &MultiCurrencyCashAccount, calculated: &MultiCurrencyCashAccount,
-    ) {
-        self.currencies.extend(actual.iter().map(|assets| assets.currency));
-        self.currencies.extend(calculated.iter().map(|assets| assets.currency));
+    fn snapshot(&self, date: Date) -> Option<&MultiCurrencyCashAccount> {
+        self.calculated.range(date..).next()
+            .or_else(|| self.calculated.last_key_value())
+            .map(|(_, assets)| assets)
+    }
+
+    fn calculated_at(&self, date: Date, currency: &str) -> Cash {
+        self.snapshot(date).and_then(|assets| assets.get(currency)).unwrap_or_else(|| Cash::zero(currency))
+    }
+
+    fn is_equal(&self, calculated: Cash, actual: Cash) -> bool {
+        // The calculations aren't 100% accurate. For example, Forex trades information contains
+        // rounded numbers which may lead to calculation error with around 0.00001 precision.
+        calculated.round() == actual.round() || (calculated - actual).amount.abs() <= self.tolerance.amount
+    }
 
-        for &currency in &self.currencies {
-            let calculated_amount = calculated.get(currency).unwrap_or_else(||
-                Cash::zero(currency));
+    fn match_within_tolerance(&self, date: Date, actual: Cash) -> Option<String> {
+        let calculated = |date| self.calculated_at(date, actual.currency);
 
-            let actual_amount = actual.get(currency).unwrap_or_else(||
-                Cash::zero(currency));
+        if self.is_equal(calculated(date), actual) {
+            return Some(s!("within the tolerance"));
+        }
 
-            if calculated_amount == actual_amount {
-                continue;
+        for offset in 1..=i64::from(self.tolerance.days) {
+            for other_date in [date - chrono::Duration::days(offset), date + chrono::Duration::days(offset)] {
+                if self.is_equal(calculated(other_date), actual) {
+                    return Some(format!(
+                        "matches the calculated assets for {} (settlement date drift)", format_date(other_date)));
+                }
             }
+        }
+
+        None
+    }
 
-            // The calculations aren't 100% accurate. For example, Forex trades information contains
-            // rounded numbers which may lead to calculation error with around 0.00001 precision.
-            let equal = calculated_amount.round() == actual_amount.round();
+    fn explain(&self, date: Date, error: Cash, prev_error: Option<&(Date, Cash)>, not_reported: bool) -> String {
+        let window = std::cmp::max(EXPLANATION_WINDOW, i64::from(self.tolerance.days));
+        let suspect = self.cash_flows.iter()
+            .filter(|(cash_flow_date, amount, _)| {
+                (*cash_flow_date - date).num_days().abs() <= window && amount.currency == error.currency &&
+                    (self.is_equal(*amount, error) || self.is_equal(-*amount, error))
+            })
+            .min_by_key(|(cash_flow_date, _, _)| (*cash_flow_date - date).num_days().abs());
 
-            let level = if !equal && (self.consumed() || self.important_dates.contains(&date)) {
-                Level::Warn
+        if let Some((cash_flow_date, _, description)) = suspect {
+            let accounted = if *cash_flow_date > date {
+                "earlier"
             } else {
-                Level::Debug
+                "later"
             };
 
-            log!(level, "Calculation error for {}: {} vs {} ({})",
-                 format_date(date), calculated_amount, actual_amount,
-                 calculated_amount - actual_amount);
+            return format!(
+                "{} from {} is probably accounted by the broker {} (settlement date drift)",
+                description, format_date(*cash_flow_date), accounted);
+        }
+
+        if let Some((prev_date, prev_error)) = prev_error {
+            if self.is_equal(*prev_error, error) {
+                return format!("the same error as for {}, so it's carried over from an earlier operation",
+                               format_date(*prev_date));
+            }
         }
-    }
 
-    pub fn consumed(&self) -> bool {
-        self.next.is_none()
+        if not_reported {
+            return format!(
+                "there are no {} assets in the broker statement: probably a missing currency conversion",
+                error.currency);
+        }
+
+        s!("probably an unsupported or incorrectly parsed operation or a missing broker statement")
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn next(&mut self) {
-        self.next = self.iter.next();
+    #[test]
+    fn settlement_date_drift() {
+        let account = |amount| {
+            let mut account = MultiCurrencyCashAccount::new();
+            account.deposit(Cash::new("USD", amount));
+            account
+        };
+
+        // The broker accounts the deposit one day later than we do
+        let historical = btreemap!{
+            date!(2020, 1, 1) => account(dec!(0)),
+            date!(2020, 1, 2) => account(dec!(0)),
+            date!(2020, 1, 3) => account(dec!(100)),
+        };
+
+        let mut comparator = CashAssetsComparator::new(&historical, Vec::new(), ComparisonTolerance::default());
+        comparator.compare(date!(2020, 1, 2), &account(dec!(0)));
+        comparator.add_cash_flow(date!(2020, 1, 2), Cash::new("USD", dec!(100)), "Deposit");
+        comparator.compare(date!(2020, 1, 4), &account(dec!(100)));
+
+        assert_eq!(comparator.match_within_tolerance(date!(2020, 1, 2), Cash::new("USD", dec!(0))), None);
+        assert_eq!(
+            comparator.explain(date!(2020, 1, 2), Cash::new("USD", dec!(100)), None, false),
+            "Deposit from 02.01.2020 is probably accounted by the broker later (settlement date drift)");
+
+        comparator.tolerance.days = 1;
+        assert_eq!(
+            comparator.match_within_tolerance(date!(2020, 1, 2), Cash::new("USD", dec!(0))).unwrap(),
+            "matches the calculated assets for 01.01.2020 (settlement date drift)");
+
+        comparator.tolerance = ComparisonTolerance {days: 0, amount: dec!(100)};
+        assert_eq!(
+            comparator.match_within_tolerance(date!(2020, 1, 2), Cash::new("USD", dec!(0))).unwrap(),
+            "within the tolerance");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use num_traits::ToPrimitive;
+use rust_xlsxwriter::{Format, Workbook};
+
+use crate::core::EmptyResult;
+use crate::currency;
+use crate::formatting;
+use crate::time::Period;
+use crate::types::Decimal;
+
+pub const CASH_SECTION: &str = "Денежные средства";
+pub const OTHER_ASSETS_SECTION: &str = "Иные финансовые активы";
+
+/// Annual figures of the report on cash flows and other financial assets for one currency
+pub struct ReportRow {
+    pub section: &'static str,
+    pub currency: &'static str,
+    pub starting: Option<Decimal>,
+    pub deposits: Decimal,
+    pub withdrawals: Decimal,
+    pub ending: Option<Decimal>,
+}
+
+/// Exports the report to CSV or XLSX file depending on the file extension
+pub fn export(path: &Path, period: Period, rows: &[ReportRow]) -> EmptyResult {
+    let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase);
+
+    match extension.as_deref() {
+        Some("csv") => export_csv(path, period, rows),
+        Some("xlsx") => export_xlsx(path, period, rows),
+        _ => Err!("Unsupported report file format: {:?}. Only *.csv and *.xlsx files are supported", path),
+    }.map_err(|e| format!("Failed to export the report to {:?}: {}", path, e).into())
+}
+
+fn get_titles(period: Period) -> [String; 6] {
+    [
+        s!("Раздел"), s!("Валюта"),
+        format!("Остаток на {}", formatting::format_date(period.first_date())),
+        s!("Зачислено"), s!("Списано"),
+        format!("Остаток на {}", formatting::format_date(period.last_date())),
+    ]
+}
+
+fn export_csv(path: &Path, period: Period, rows: &[ReportRow]) -> EmptyResult {
+    let format = |amount: Option<Decimal>| {
+        amount.map(|amount| format!("{:.2}", currency::round(amount))).unwrap_or_default()
+    };
+
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(get_titles(period))?;
+
+    for row in rows {
+        writer.write_record([
+            row.section.to_owned(), row.currency.to_owned(),
+            format(row.starting), format(Some(row.deposits)), format(Some(row.withdrawals)), format(row.ending),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn export_xlsx(path: &Path, period: Period, rows: &[ReportRow]) -> EmptyResult {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+
+    let title_format = Format::new().set_bold();
+    let amount_format = Format::new().set_num_format("#,##0.00");
+
+    for (column, title) in get_titles(period).iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, title, &title_format)?;
+    }
+
+    for (index, row) in rows.iter().enumerate() {
+        let row_id = index as u32 + 1;
+
+        sheet.write_string(row_id, 0, row.section)?;
+        sheet.write_string(row_id, 1, row.currency)?;
+
+        let amounts = [row.starting, Some(row.deposits), Some(row.withdrawals), row.ending];
+        for (column, amount) in amounts.into_iter().enumerate() {
+            if let Some(amount) = amount {
+                let amount = currency::round(amount).to_f64().unwrap();
+                sheet.write_number_with_format(row_id, column as u16 + 2, amount, &amount_format)?;
+            }
+        }
+    }
+
+    sheet.autofit();
+    workbook.save(path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn csv_export() {
+        let period = Period::new(date!(2023, 1, 1), date!(2023, 12, 31)).unwrap();
+        let rows = [
+            ReportRow {
+                section: CASH_SECTION,
+                currency: "USD",
+                starting: Some(dec!(100.5)),
+                deposits: dec!(1000),
+                withdrawals: dec!(900.555),
+                ending: Some(dec!(199.945)),
+            },
+            ReportRow {
+                section: OTHER_ASSETS_SECTION,
+                currency: "USD",
+                starting: None,
+                deposits: dec!(900),
+                withdrawals: dec!(0),
+                ending: Some(dec!(950)),
+            },
+        ];
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("report.csv");
+        export(&path, period, &rows).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), indoc!("
+            Раздел,Валюта,Остаток на 01.01.2023,Зачислено,Списано,Остаток на 31.12.2023
+            Денежные средства,USD,100.50,1000.00,900.56,199.95
+            Иные финансовые активы,USD,,900.00,0.00,950.00
+        "));
+
+        assert!(export(&temp_dir.path().join("report.txt"), period, &rows).is_err());
+    }
+}
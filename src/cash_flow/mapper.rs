@@ -2,8 +2,9 @@ use std::cmp::Ordering;
 use std::fmt::Write;
 
 use crate::broker_statement::{
-    BrokerStatement, ForexTrade, StockBuy, StockSource, StockSell, StockSellType, Dividend, Fee,
-    IdleCashInterest, CashGrant, TaxAgentWithholding, Withholding, CashFlow as CashFlowDetails, CashFlowType};
+    BrokerStatement, ForexTrade, StockBuy, StockSource, StockSell, StockSellType, DerivativeTrade, DerivativeType,
+    DerivativeIncome, Dividend, Coupon, Fee, IdleCashInterest, MarginInterest, CashGrant, CorporateActionType,
+    TaxAgentWithholding, Withholding, CashFlow as CashFlowDetails, CashFlowType};
 use crate::currency::{Cash, CashAssets};
 use crate::formatting;
 use crate::time::DateOptTime;
@@ -34,10 +35,26 @@ impl CashFlowMapper {
             self.interest(interest);
         }
 
+        for interest in &statement.margin_interest {
+            self.margin_interest(interest);
+        }
+
         for dividend in &statement.dividends {
             self.dividend(statement, dividend);
         }
 
+        for coupon in &statement.coupons {
+            self.coupon(&statement.instrument_info.get_name(&coupon.original_symbol), coupon);
+        }
+
+        for action in &statement.corporate_actions {
+            if let CorporateActionType::Amortization {amount, ref currency} = action.action {
+                let description = format!(
+                    "Частичное погашение номинала {}", statement.instrument_info.get_name(&action.symbol));
+                self.add(action.time, Operation::SellTrade, Cash::new(currency, amount), description);
+            }
+        }
+
         for grant in &statement.cash_grants {
             self.grant(grant);
         }
@@ -58,6 +75,14 @@ impl CashFlowMapper {
             self.stock_buy(&statement.instrument_info.get_name(&trade.original_symbol), trade);
         }
 
+        for trade in &statement.derivative_trades {
+            self.derivative_trade(&statement.instrument_info.get_name(&trade.symbol), trade);
+        }
+
+        for income in &statement.derivatives_income {
+            self.derivative_income(&statement.instrument_info.get_name(&income.symbol), income);
+        }
+
         for fee in &statement.fees {
             self.fee(fee);
         }
@@ -91,6 +116,16 @@ impl CashFlowMapper {
             "Проценты на остаток по счету");
     }
 
+    fn margin_interest(&mut self, interest: &MarginInterest) {
+        self.add_static(
+            interest.date.into(), Operation::Fee, -interest.amount,
+            "Проценты за использование маржинального кредита");
+    }
+
+    fn coupon(&mut self, name: &str, coupon: &Coupon) {
+        self.add(coupon.date.into(), Operation::Interest, coupon.amount, format!("Купон по {}", name));
+    }
+
     fn forex_trade(&mut self, trade: &ForexTrade) {
         let description = format!("Конвертация {} -> {}", trade.from, trade.to);
         let cash_flow = self.add(trade.conclusion_time, Operation::ForexTrade, -trade.from, description);
@@ -134,6 +169,32 @@ impl CashFlowMapper {
         }
     }
 
+    fn derivative_trade(&mut self, name: &str, trade: &DerivativeTrade) {
+        let (operation, action, commission_action) = if trade.quantity.is_sign_positive() {
+            (Operation::BuyTrade, "Покупка", "покупку")
+        } else {
+            (Operation::SellTrade, "Продажа", "продажу")
+        };
+        let quantity = trade.quantity.abs();
+
+        // Futures trades don't move cash: their result is settled via variation margin
+        if trade.type_ == DerivativeType::Option && !trade.volume.is_zero() {
+            self.add(trade.conclusion_time, operation, trade.volume, format!("{} {} {}", action, quantity, name));
+        }
+
+        if !trade.commission.is_zero() {
+            let description = format!("Комиссия за {} {} {}", commission_action, quantity, name);
+            self.add(trade.conclusion_time, Operation::Commission, -trade.commission, description);
+        }
+    }
+
+    fn derivative_income(&mut self, name: &str, income: &DerivativeIncome) {
+        if income.type_ == DerivativeType::Futures {
+            let description = format!("Вариационная маржа по {} {}", income.quantity, name);
+            self.add(income.execution_date.into(), Operation::SellTrade, income.trading_result(), description);
+        }
+    }
+
     fn dividend(&mut self, statement: &BrokerStatement, dividend: &Dividend) {
         if dividend.skip_from_cash_flow {
             return
@@ -186,7 +247,7 @@ impl CashFlowMapper {
                 });
             },
 
-            CashFlowType::Repo {ref symbol, commission} => {
+            CashFlowType::Repo {ref symbol, commission, ..} => {
                 let description = statement.instrument_info.get_name(symbol);
 
                 self.add(date, Operation::RepoDeal, amount, format!(
@@ -197,6 +258,10 @@ impl CashFlowMapper {
                         "Комиссия за заключение сделки РЕПО с {}", description));
                 }
             },
+
+            CashFlowType::SecuritiesLending => {
+                self.add_static(date, Operation::Interest, amount, "Доход от предоставления ценных бумаг в заем");
+            },
         }
     }
 
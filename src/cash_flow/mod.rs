@@ -1,55 +1,111 @@
 mod calculator;
 mod comparator;
+mod export;
 mod mapper;
 
 use std::collections::BTreeMap;
+use std::path::Path;
 
 use itertools::Itertools;
 use log::warn;
 
-use crate::broker_statement::{BrokerStatement, ReadingStrictness, NetAssets};
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections, NetAssets};
 use crate::config::Config;
 use crate::core::{GenericResult, EmptyResult};
-use crate::currency::{self, Cash, converter::CurrencyConverter};
+use crate::currency::{self, Cash, MultiCurrencyCashAccount, converter::CurrencyConverter};
 use crate::db;
 use crate::formatting::{self, table::{Table, Column, Cell}};
+use crate::instruments;
 use crate::localities::Jurisdiction;
+use crate::portfolio;
+use crate::quotes::Quotes;
 use crate::telemetry::TelemetryRecordBuilder;
 use crate::time::{Date, Period};
 
 use self::calculator::CashFlowSummary;
+use self::export::ReportRow;
 use self::mapper::{CashFlow, Operation};
 
-pub fn generate_cash_flow_report(config: &Config, portfolio_name: &str, year: Option<i32>) -> GenericResult<TelemetryRecordBuilder> {
+pub use self::comparator::ComparisonTolerance;
+
+pub fn generate_cash_flow_report(
+    config: &Config, portfolio_name: &str, year: Option<i32>, export_path: Option<&Path>,
+    tolerance: ComparisonTolerance,
+) -> GenericResult<TelemetryRecordBuilder> {
     let portfolio = config.get_portfolio(portfolio_name)?;
     let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
 
     let database = db::connect(&config.db_path)?;
     let converter = CurrencyConverter::new(database, None, year.is_some());
 
-    let statement = BrokerStatement::read(
+    let mut statement = BrokerStatement::read(
         broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
         &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
-        &portfolio.corporate_actions, ReadingStrictness::CASH_FLOW_DATES)?;
+        &portfolio.corporate_actions, ReadingStrictness::CASH_FLOW_DATES, StatementSections::all())?;
+    instruments::reference::enrich(config, &mut statement.instrument_info);
 
     let period = match year {
         Some(year) => statement.check_period_against_tax_year(year)?,
         None => statement.period,
     };
 
-    let (summaries, cash_flows) = calculator::calculate(&statement, period);
-    generate_cash_summary_report(period, &summaries);
+    let (summaries, cash_flows) = calculator::calculate(&statement, period, tolerance);
+    let mut report = generate_cash_summary_report(period, &summaries);
 
     if statement.broker.type_.jurisdiction() == Jurisdiction::Usa {
-        generate_other_summary_report(&statement, period, &cash_flows, &converter, "USD")?;
+        report.push(generate_other_summary_report(config, &statement, period, &cash_flows, &converter, "USD")?);
     }
 
     generate_details_report(&summaries, cash_flows);
 
+    if let Some(path) = export_path {
+        export::export(path, period, &report)?;
+    }
+
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
 }
 
-fn generate_cash_summary_report(period: Period, summaries: &BTreeMap<&'static str, CashFlowSummary>) {
+/// Calculates cash assets at the end of the specified date by replaying all cash flows from the broker statement
+pub fn calculate_cash_assets(statement: &BrokerStatement, date: Date) -> MultiCurrencyCashAccount {
+    let mut assets = MultiCurrencyCashAccount::new();
+
+    for cash_flow in mapper::map_broker_statement_to_cash_flow(statement) {
+        if cash_flow.time.date > date {
+            break;
+        }
+
+        assets.deposit(cash_flow.amount);
+        if let Some(amount) = cash_flow.sibling_amount {
+            assets.deposit(amount);
+        }
+    }
+
+    assets
+}
+
+/// Calculates cash assets at the end of every day with cash flows by replaying all cash flows from the broker statement
+pub fn calculate_daily_cash_assets(statement: &BrokerStatement) -> Vec<(Date, MultiCurrencyCashAccount)> {
+    let mut daily_assets = Vec::new();
+    let mut assets = MultiCurrencyCashAccount::new();
+    let mut cash_flows = mapper::map_broker_statement_to_cash_flow(statement).into_iter().peekable();
+
+    while let Some(cash_flow) = cash_flows.next() {
+        assets.deposit(cash_flow.amount);
+        if let Some(amount) = cash_flow.sibling_amount {
+            assets.deposit(amount);
+        }
+
+        let date = cash_flow.time.date;
+        if cash_flows.peek().map(|next| next.time.date != date).unwrap_or(true) {
+            daily_assets.push((date, assets.clone()));
+        }
+    }
+
+    daily_assets
+}
+
+fn generate_cash_summary_report(period: Period, summaries: &BTreeMap<&'static str, CashFlowSummary>) -> Vec<ReportRow> {
+    let mut report = Vec::new();
     let mut columns = vec![Column::new("")];
     let mut starting_assets_row = vec![period.first_date().into()];
     let mut deposits_row = vec!["Зачисления".into()];
@@ -70,6 +126,14 @@ fn generate_cash_summary_report(period: Period, summaries: &BTreeMap<&'static st
         add_cell(&mut deposits_row, deposits);
         add_cell(&mut withdrawals_row, -withdrawals);
         add_cell(&mut ending_assets_row, ending);
+
+        report.push(ReportRow {
+            section: export::CASH_SECTION,
+            currency,
+            starting: Some(starting),
+            deposits, withdrawals,
+            ending: Some(ending),
+        });
     }
 
     let mut table = Table::new(columns);
@@ -78,35 +142,59 @@ fn generate_cash_summary_report(period: Period, summaries: &BTreeMap<&'static st
     table.add_row(withdrawals_row);
     table.add_row(ending_assets_row);
     table.print("Движение денежных средств");
+
+    report
 }
 
 fn generate_other_summary_report(
-    statement: &BrokerStatement, period: Period, cash_flows: &[CashFlow],
-    converter: &CurrencyConverter, jurisdiction_currency: &str,
-) -> EmptyResult {
+    config: &Config, statement: &BrokerStatement, period: Period, cash_flows: &[CashFlow],
+    converter: &CurrencyConverter, jurisdiction_currency: &'static str,
+) -> GenericResult<ReportRow> {
     let mut missing = false;
     let mut currency = None;
 
-    let end_assets = if let Some(NetAssets{other: Some(assets), ..}) = statement.historical_assets.get(&period.last_date()) {
+    let mut end_assets = if let Some(NetAssets{other: Some(assets), ..}) = statement.historical_assets.get(&period.last_date()) {
         currency.get_or_insert(assets.currency);
-        Cell::from(*assets)
+        Some(*assets)
     } else {
-        missing = true;
-        Cell::new_empty()
+        None
     };
 
-    let start_assets = if let Some(NetAssets{other: Some(assets), ..}) = statement.historical_assets.get(&period.prev_date()) {
+    let mut start_assets = if let Some(NetAssets{other: Some(assets), ..}) = statement.historical_assets.get(&period.prev_date()) {
         currency.get_or_insert(assets.currency);
-        Cell::from(*assets)
+        Some(*assets)
     } else if period.first_date() == statement.period.first_date() {
-        Cell::from(Cash::zero(currency.unwrap_or(jurisdiction_currency)))
+        Some(Cash::zero(currency.unwrap_or(jurisdiction_currency)))
     } else {
-        missing = true;
-        Cell::new_empty()
+        None
     };
 
     let currency = currency.unwrap_or(jurisdiction_currency);
 
+    // Fall back to the value of the positions reconstructed from the broker statement
+    for (date, assets) in [(period.prev_date(), &mut start_assets), (period.last_date(), &mut end_assets)] {
+        if assets.is_some() {
+            continue;
+        }
+
+        if statement.period.contains(date) {
+            match reconstruct_other_assets(config, statement, date, currency, converter) {
+                Ok(value) => {
+                    warn!(concat!(
+                        "There is no net asset value information for {} in the broker statement. ",
+                        "Using the value of the reconstructed positions: {}."),
+                        formatting::format_date(date), value);
+                    assets.replace(value);
+                    continue;
+                },
+                Err(e) => warn!(
+                    "Unable to reconstruct the positions as of {}: {}.", formatting::format_date(date), e),
+            }
+        }
+
+        missing = true;
+    }
+
     let mut deposits = dec!(0);
     let mut withdrawals = dec!(0);
     let mut process = |date: Date, amount: Cash| -> EmptyResult {
@@ -131,10 +219,10 @@ fn generate_other_summary_report(
     }
 
     let mut table = Table::new(vec![Column::new(""), Column::new("")]);
-    table.add_row(vec![period.first_date().into(), start_assets]);
+    table.add_row(vec![period.first_date().into(), Cell::from(start_assets)]);
     table.add_row(vec!["Зачисления".into(), Cash::new(currency, deposits).into()]);
     table.add_row(vec!["Списания".into(), Cash::new(currency, withdrawals).into()]);
-    table.add_row(vec![period.last_date().into(), end_assets]);
+    table.add_row(vec![period.last_date().into(), Cell::from(end_assets)]);
     table.hide_titles();
     table.print("Стоимость иных финансовых активов");
 
@@ -156,7 +244,24 @@ fn generate_other_summary_report(
             clarification);
     }
 
-    Ok(())
+    Ok(ReportRow {
+        section: export::OTHER_ASSETS_SECTION,
+        currency,
+        starting: start_assets.map(|assets| assets.amount),
+        deposits,
+        withdrawals: -withdrawals,
+        ending: end_assets.map(|assets| assets.amount),
+    })
+}
+
+fn reconstruct_other_assets(
+    config: &Config, statement: &BrokerStatement, date: Date, currency: &str, converter: &CurrencyConverter,
+) -> GenericResult<Cash> {
+    let quotes = Quotes::new(config, db::connect(&config.db_path)?)?;
+    let portfolio = portfolio::reconstruct(statement, date, currency, converter, &quotes)?;
+
+    let value = portfolio.positions.iter().map(|position| position.value).sum();
+    Ok(Cash::new(currency, value).round())
 }
 
 fn generate_details_report(
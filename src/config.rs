@@ -8,23 +8,32 @@ use serde::Deserialize;
 use serde::de::{Deserializer, IgnoredAny, Error};
 use validator::Validate;
 
-use crate::analysis::config::PerformanceMergingConfig;
+use crate::analysis::config::{PerformanceMergingConfig, StressScenarioConfig};
 use crate::broker_statement::CorporateAction;
 use crate::brokers::Broker;
 use crate::core::{GenericResult, EmptyResult};
-use crate::formatting;
+use crate::currency;
+use crate::errors::{self, ConfigError};
+use crate::exchanges::Exchange;
+use crate::formatting::{self, Locale};
 use crate::instruments::InstrumentInternalIds;
+use crate::instruments::reference::InstrumentReferenceConfig;
 use crate::localities::{self, Country, Jurisdiction};
 use crate::metrics::{self, config::MetricsConfig};
+use crate::network::{Proxy, RetryPolicy};
+use crate::portfolio::IdleCashConfig;
 use crate::quotes::QuotesConfig;
 use crate::quotes::alphavantage::AlphaVantageConfig;
 use crate::quotes::fcsapi::FcsApiConfig;
 use crate::quotes::finnhub::FinnhubConfig;
 use crate::quotes::tbank::TbankApiConfig;
 use crate::quotes::twelvedata::TwelveDataConfig;
-use crate::taxes::{self, TaxConfig, TaxExemption, TaxPaymentDay, TaxPaymentDaySpec, TaxRemapping};
+use crate::secrets;
+use crate::tax_statement::{BankInterestConfig, ControlledForeignCompanyConfig};
+use crate::taxes::{
+    self, AdvanceTaxPaymentsSpec, TaxConfig, TaxExemption, TaxPaymentDay, TaxPaymentDaySpec, TaxRemapping};
 use crate::telemetry::TelemetryConfig;
-use crate::time::{self, deserialize_date};
+use crate::time::{self, deserialize_date, deserialize_optional_date};
 use crate::types::{Date, Decimal};
 use crate::util::{self, DecimalRestrictions};
 
@@ -43,6 +52,7 @@ pub struct Config {
     #[serde(default)]
     pub portfolios: Vec<PortfolioConfig>,
     pub brokers: Option<BrokersConfig>,
+    #[validate(nested)]
     #[serde(default)]
     pub taxes: TaxConfig,
 
@@ -54,6 +64,17 @@ pub struct Config {
     pub metrics: MetricsConfig,
     #[serde(default)]
     pub telemetry: TelemetryConfig,
+    pub instrument_reference: Option<InstrumentReferenceConfig>,
+    #[serde(default)]
+    pub stress_scenarios: HashMap<String, StressScenarioConfig>,
+
+    // Proxy for all network clients which may be overridden in quotes provider / API client settings
+    pub proxy: Option<Proxy>,
+    // Retry policy for all network clients which may be overridden in quotes provider / API client settings
+    pub retry: Option<RetryPolicy>,
+
+    #[serde(default)]
+    pub locale: Locale,
 
     // Deprecated
     pub alphavantage: Option<AlphaVantageConfig>,
@@ -66,7 +87,7 @@ pub struct Config {
 }
 
 impl Config {
-    #[cfg(test)]
+    #[cfg(any(test, feature = "bench"))]
     pub fn mock() -> Config {
         Config {
             db_path: s!("/mock"),
@@ -87,12 +108,23 @@ impl Config {
             finnhub: None,
             twelvedata: None,
             telemetry: Default::default(),
+            instrument_reference: None,
+            stress_scenarios: HashMap::new(),
+            proxy: None,
+            retry: None,
+            locale: Locale::default(),
 
             _anchors: Default::default(),
         }
     }
 
     pub fn load(path: &str) -> GenericResult<Config> {
+        Ok(Config::load_inner(path).map_err(|e| errors::Error::from(ConfigError::Read {
+            path: path.to_owned(), source: e,
+        }))?)
+    }
+
+    fn load_inner(path: &str) -> GenericResult<Config> {
         let mut config: Config = Config::read(path)?;
 
         config.validate()?;
@@ -134,7 +166,7 @@ impl Config {
             }
         }
 
-        Err!("{:?} portfolio is not defined in the configuration file", name)
+        Err(errors::Error::from(ConfigError::UnknownPortfolio(name.to_owned())).into())
     }
 
     fn read(path: &str) -> GenericResult<Config> {
@@ -146,8 +178,26 @@ impl Config {
 
             use yaml_merge_keys::serde_yaml::{self as yaml, Value};
 
+            fn interpolate_env(value: &mut Value) -> EmptyResult {
+                match value {
+                    Value::String(string) if string.contains("${") => {
+                        *string = secrets::interpolate_env(string)?;
+                    },
+                    Value::Sequence(values) => for value in values {
+                        interpolate_env(value)?;
+                    },
+                    Value::Mapping(mapping) => for (_, value) in mapping.iter_mut() {
+                        interpolate_env(value)?;
+                    },
+                    _ => {},
+                }
+                Ok(())
+            }
+
             let value: Value = yaml::from_slice(&data)?;
-            let merged = yaml_merge_keys::merge_keys_serde(value.clone())?;
+            let mut merged = yaml_merge_keys::merge_keys_serde(value.clone())?;
+            interpolate_env(&mut merged)?;
+
             if merged == value {
                 return Ok(serde_yaml::from_slice(&data)?);
             }
@@ -234,12 +284,18 @@ pub struct PortfolioConfig {
     pub plan: Option<String>,
 
     pub statements: Option<String>,
+    /// Account opening date (the first broker statement date is used by default)
+    #[serde(default, deserialize_with = "deserialize_optional_date")]
+    pub open_date: Option<Date>,
     #[serde(default)]
     pub symbol_remapping: HashMap<String, String>,
     #[serde(default, deserialize_with = "InstrumentInternalIds::deserialize")]
     pub instrument_internal_ids: InstrumentInternalIds,
     #[serde(default)]
     pub instrument_names: HashMap<String, String>,
+    /// Currency which currency-hedged instruments are hedged to (symbol -> currency)
+    #[serde(default)]
+    currency_hedging: HashMap<String, String>,
     #[serde(default)]
     tax_remapping: Vec<TaxRemappingConfig>,
     #[serde(default)]
@@ -250,6 +306,7 @@ pub struct PortfolioConfig {
     pub min_cash_assets: Option<Decimal>,
     pub restrict_buying: Option<bool>,
     pub restrict_selling: Option<bool>,
+    pub idle_cash: Option<IdleCashConfig>,
 
     #[serde(default)]
     pub merge_performance: PerformanceMergingConfig,
@@ -259,12 +316,26 @@ pub struct PortfolioConfig {
 
     #[serde(default, rename = "tax_payment_day", deserialize_with = "TaxPaymentDaySpec::deserialize")]
     tax_payment_day_spec: TaxPaymentDaySpec,
+    advance_tax_payments: Option<AdvanceTaxPaymentsSpec>,
 
     #[serde(default)]
     pub tax_exemptions: Vec<TaxExemption>,
 
     #[serde(default, deserialize_with = "deserialize_cash_flows")]
     pub tax_deductions: Vec<(Date, Decimal)>,
+    #[serde(default)]
+    pub iis_deduction: bool,
+    #[serde(default)]
+    pub iis_paid_taxes: BTreeMap<i32, Decimal>,
+    #[serde(default)]
+    pub carry_forward_losses: bool,
+    #[serde(default)]
+    pub exclude_wash_sale_losses: bool,
+
+    #[serde(default)]
+    pub controlled_foreign_companies: Vec<ControlledForeignCompanyConfig>,
+    #[serde(default)]
+    pub bank_interest: Vec<BankInterestConfig>,
 }
 
 impl PortfolioConfig {
@@ -286,8 +357,26 @@ impl PortfolioConfig {
         symbols
     }
 
+    /// Returns target weights of all stocks from the asset allocation
+    pub fn get_target_weights(&self) -> BTreeMap<String, Decimal> {
+        let mut weights = BTreeMap::new();
+        AssetAllocationConfig::get_target_weights(&self.assets, dec!(1), &mut weights);
+        weights
+    }
+
+    /// Returns currency which the instrument's value actually depends on: it's the trading currency for regular
+    /// instruments and the hedging currency for currency-hedged ones.
+    pub fn get_currency_exposure<'a>(&'a self, symbol: &str, trading_currency: &'a str) -> &'a str {
+        self.currency_hedging.get(symbol).map(String::as_str).unwrap_or(trading_currency)
+    }
+
     pub fn tax_payment_day(&self) -> TaxPaymentDay {
-        TaxPaymentDay::new(self.broker.jurisdiction(), self.tax_payment_day_spec)
+        let tax_payment_day = TaxPaymentDay::new(self.broker.jurisdiction(), self.tax_payment_day_spec);
+
+        match self.advance_tax_payments {
+            Some(ref spec) => tax_payment_day.with_advance_payments(spec),
+            None => tax_payment_day,
+        }
     }
 
     pub fn get_tax_remapping(&self) -> GenericResult<TaxRemapping> {
@@ -318,6 +407,12 @@ impl PortfolioConfig {
             }
         }
 
+        for (symbol, currency) in &self.currency_hedging {
+            if currency::validate_currency(currency).is_err() {
+                return Err!("Invalid currency hedging configuration: Invalid {} currency: {:?}", symbol, currency);
+            }
+        }
+
         if
             matches!(self.tax_payment_day_spec, TaxPaymentDaySpec::OnClose(_)) &&
             self.broker.jurisdiction() != Jurisdiction::Russia
@@ -325,8 +420,25 @@ impl PortfolioConfig {
             return Err!("On close tax payment date is only available for brokers with Russia jurisdiction")
         }
 
+        if
+            matches!(self.tax_payment_day_spec, TaxPaymentDaySpec::OnClose(_)) &&
+            self.advance_tax_payments.is_some()
+        {
+            return Err!("Advance tax payments can't be used with on close tax payment date")
+        }
+
         taxes::validate_tax_exemptions(self.broker, &self.tax_exemptions)?;
 
+        if self.iis_deduction {
+            if self.broker.jurisdiction() != Jurisdiction::Russia {
+                return Err!("IIS deduction is only available for brokers with Russia jurisdiction");
+            } else if !self.tax_deductions.is_empty() {
+                return Err!("Tax deductions can't be specified when IIS deduction is calculated automatically");
+            }
+        } else if !self.iis_paid_taxes.is_empty() {
+            return Err!("IIS paid taxes can only be specified when IIS deduction is calculated automatically");
+        }
+
         Ok(())
     }
 }
@@ -346,6 +458,8 @@ struct TaxRemappingConfig {
 pub struct AssetAllocationConfig {
     pub name: String,
     pub symbol: Option<String>,
+    /// Overrides the exchange to get the asset quotes from (crypto for cryptocurrencies for example)
+    pub exchange: Option<Exchange>,
 
     #[serde(deserialize_with = "deserialize_weight")]
     pub weight: Decimal,
@@ -356,6 +470,18 @@ pub struct AssetAllocationConfig {
 }
 
 impl AssetAllocationConfig {
+    fn get_target_weights(assets: &[AssetAllocationConfig], weight: Decimal, weights: &mut BTreeMap<String, Decimal>) {
+        for asset in assets {
+            let asset_weight = weight * asset.weight;
+
+            if let Some(ref symbol) = asset.symbol {
+                *weights.entry(symbol.clone()).or_default() += asset_weight;
+            } else if let Some(ref assets) = asset.assets {
+                AssetAllocationConfig::get_target_weights(assets, asset_weight, weights);
+            }
+        }
+    }
+
     fn get_stock_symbols(&self, symbols: &mut HashSet<String>) {
         if let Some(ref symbol) = self.symbol {
             symbols.insert(symbol.to_owned());
@@ -372,11 +498,13 @@ impl AssetAllocationConfig {
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct BrokersConfig {
+    pub alfa: Option<BrokerConfig>,
     pub bcs: Option<BrokerConfig>,
     pub firstrade: Option<BrokerConfig>,
     pub interactive_brokers: Option<BrokerConfig>,
     pub open_broker: Option<BrokerConfig>,
     pub sber: Option<BrokerConfig>,
+    pub schwab: Option<BrokerConfig>,
     #[serde(alias = "tinkoff")]
     pub tbank: Option<TbankConfig>,
 }
@@ -388,12 +516,21 @@ pub struct TbankConfig {
     pub broker: Option<BrokerConfig>,
     #[serde(flatten)]
     pub api: Option<TbankApiConfig>,
+    #[serde(default)]
+    pub exchange_aliases: HashMap<String, Exchange>,
 }
 
 #[derive(Deserialize, Default, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct BrokerConfig {
+    #[serde(default)]
     pub deposit_commissions: HashMap<String, TransactionCommissionSpec>,
+    pub forex_commission: Option<ForexCommissionSpec>,
+    #[serde(default)]
+    pub statement_plugins: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_quantity_increment")]
+    pub quantity_increment: Option<Decimal>,
+    pub at_risk_dividends_days: Option<u32>,
 }
 
 #[derive(Deserialize, Default)]
@@ -413,11 +550,19 @@ pub struct TransactionCommissionSpec {
     pub fixed_amount: Decimal,
 }
 
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ForexCommissionSpec {
+    pub percent: Decimal,
+    #[serde(default)]
+    pub minimum: Decimal,
+}
+
 fn default_expire_time() -> Duration {
     Duration::minutes(1)
 }
 
-fn deserialize_cash_flows<'de, D>(deserializer: D) -> Result<Vec<(Date, Decimal)>, D::Error>
+pub fn deserialize_cash_flows<'de, D>(deserializer: D) -> Result<Vec<(Date, Decimal)>, D::Error>
     where D: Deserializer<'de>
 {
     let deserialized: HashMap<String, Decimal> = Deserialize::deserialize(deserializer)?;
@@ -453,4 +598,47 @@ fn deserialize_weight<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
         }).ok_or_else(|| D::Error::custom(format!("Invalid weight: {}", weight)))?;
 
     Ok(weight / dec!(100))
-}
\ No newline at end of file
+}
+
+fn deserialize_quantity_increment<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where D: Deserializer<'de>
+{
+    let increment: Decimal = Deserialize::deserialize(deserializer)?;
+    Ok(Some(util::validate_decimal(increment, DecimalRestrictions::StrictlyPositive).map_err(|_|
+        D::Error::custom(format!("Invalid quantity increment: {}", increment)))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_weights() {
+        let asset = |name: &str, symbol: Option<&str>, weight, assets| AssetAllocationConfig {
+            name: name.to_owned(),
+            symbol: symbol.map(ToOwned::to_owned),
+            exchange: None,
+            weight,
+            restrict_buying: None,
+            restrict_selling: None,
+            assets,
+        };
+
+        let assets = vec![
+            asset("Stocks", None, dec!(0.6), Some(vec![
+                asset("VTI", Some("VTI"), dec!(0.5), None),
+                asset("VXUS", Some("VXUS"), dec!(0.5), None),
+            ])),
+            asset("Bonds", Some("BND"), dec!(0.4), None),
+        ];
+
+        let mut weights = BTreeMap::new();
+        AssetAllocationConfig::get_target_weights(&assets, dec!(1), &mut weights);
+
+        assert_eq!(weights, btreemap!{
+            s!("BND") => dec!(0.4),
+            s!("VTI") => dec!(0.3),
+            s!("VXUS") => dec!(0.3),
+        });
+    }
+}
@@ -0,0 +1,225 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::core::GenericResult;
+
+/// Appends the item (`- value` for sequences or `key: value` for mappings) to the specified portfolio option
+/// preserving the rest of the configuration file as is
+pub fn append_portfolio_option_item(config: &str, portfolio: &str, option: &str, item: &str) -> GenericResult<String> {
+    lazy_static! {
+        static ref NAME_REGEX: Regex = Regex::new(
+            r#"^(?P<indent>\s*(?:-\s+)?)name:\s*(?P<name>[^\s"'#][^#]*?|"[^"]*"|'[^']*')\s*(?:#.*)?$"#).unwrap();
+    }
+
+    let option_regex = Regex::new(&format!(
+        r"^{}:\s*(?P<value>[^#\s][^#]*?)?\s*(?:#.*)?$", regex::escape(option))).unwrap();
+
+    let mut lines: Vec<String> = config.lines().map(ToOwned::to_owned).collect();
+    let is_content = |line: &str| !line.trim().is_empty() && !line.trim_start().starts_with('#');
+    let indent = |line: &str| line.len() - line.trim_start().len();
+
+    let portfolios = lines.iter().position(|line| line.trim_end() == "portfolios:").ok_or(
+        "Unable to find portfolios section")?;
+
+    let section_end = lines.iter().enumerate().skip(portfolios + 1)
+        .find(|(_, line)| is_content(line) && indent(line) == 0 && !line.starts_with('-'))
+        .map(|(index, _)| index).unwrap_or(lines.len());
+
+    let (name_index, key_indent) = lines[portfolios + 1..section_end].iter().enumerate().find_map(|(index, line)| {
+        let captures = NAME_REGEX.captures(line)?;
+        let name = captures.name("name").unwrap().as_str().trim_matches(['"', '\'']);
+        (name == portfolio).then(|| (portfolios + 1 + index, captures.name("indent").unwrap().as_str().len()))
+    }).ok_or_else(|| format!("Unable to find {:?} portfolio", portfolio))?;
+
+    let item_start = (portfolios + 1..=name_index).rev()
+        .find(|&index| lines[index].trim_start().starts_with('-') && indent(&lines[index]) < key_indent)
+        .ok_or_else(|| format!("Unable to find {:?} portfolio", portfolio))?;
+
+    let item_end = (name_index + 1..section_end)
+        .find(|&index| is_content(&lines[index]) && indent(&lines[index]) < key_indent)
+        .unwrap_or(section_end);
+
+    let key = (item_start..item_end).find_map(|index| {
+        let line = &lines[index];
+        let value = line.get(key_indent..)?;
+        let prefix = &line[..key_indent];
+
+        if !prefix.trim_start_matches([' ', '-']).is_empty() {
+            return None;
+        }
+
+        option_regex.captures(value).map(|captures| {
+            (index, captures.name("value").map(|value| value.as_str().to_owned()))
+        })
+    });
+
+    let last_content_line = |lines: &[String], start: usize, end: usize| {
+        (start..end).rev().find(|&index| is_content(&lines[index])).unwrap_or(start)
+    };
+
+    let (position, item_indent) = match key {
+        Some((index, value)) => {
+            match value.as_deref() {
+                None => {},
+                Some(value @ ("[]" | "{}")) => lines[index] = lines[index].replacen(value, "", 1).trim_end().to_owned(),
+                Some(_) => return Err!("{} option must be specified in block style to be edited", option),
+            }
+
+            let value_end = (index + 1..item_end)
+                .find(|&index| {
+                    let line = &lines[index];
+                    is_content(line) && indent(line) <= key_indent && !(
+                        indent(line) == key_indent && line.trim_start().starts_with('-'))
+                })
+                .unwrap_or(item_end);
+
+            let item_indent = (index + 1..value_end)
+                .find(|&index| is_content(&lines[index]))
+                .map(|index| indent(&lines[index]))
+                .unwrap_or(key_indent + 2);
+
+            (last_content_line(&lines, index, value_end) + 1, item_indent)
+        },
+        None => {
+            let position = last_content_line(&lines, item_start, item_end) + 1;
+            lines.insert(position, format!("{}{}:", " ".repeat(key_indent), option));
+            (position + 1, key_indent + 2)
+        },
+    };
+
+    lines.insert(position, format!("{}{}", " ".repeat(item_indent), item));
+
+    let mut config = lines.join("\n");
+    config.push('\n');
+    Ok(config)
+}
+
+// JSON string is a valid YAML double-quoted scalar
+pub fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(config, expected,
+        case(indoc!(r#"
+            portfolios:
+              - name: ib
+                broker: interactive-brokers
+                corporate_actions:
+                  - {date: 2020.10.27, symbol: NEE, type: stock-split, ratio: 4:1}
+
+                min_trade_volume: 200
+
+              - name: tbank
+                broker: tbank
+        "#), indoc!(r#"
+            portfolios:
+              - name: ib
+                broker: interactive-brokers
+                corporate_actions:
+                  - {date: 2020.10.27, symbol: NEE, type: stock-split, ratio: 4:1}
+                  - ENTRY
+
+                min_trade_volume: 200
+
+              - name: tbank
+                broker: tbank
+        "#)),
+        case(indoc!(r#"
+            deposits:
+              - name: ib
+
+            portfolios:
+              -
+                # Comment
+                name: "ib"
+                broker: interactive-brokers
+                corporate_actions: []
+                min_trade_volume: 200
+
+            # Comment
+            quotes: {}
+        "#), indoc!(r#"
+            deposits:
+              - name: ib
+
+            portfolios:
+              -
+                # Comment
+                name: "ib"
+                broker: interactive-brokers
+                corporate_actions:
+                  - ENTRY
+                min_trade_volume: 200
+
+            # Comment
+            quotes: {}
+        "#)),
+        case(indoc!(r#"
+            portfolios:
+            - name: tbank
+              broker: tbank
+            - name: ib # Comment
+              broker: interactive-brokers
+
+              # Comment
+            quotes: {}
+        "#), indoc!(r#"
+            portfolios:
+            - name: tbank
+              broker: tbank
+            - name: ib # Comment
+              broker: interactive-brokers
+              corporate_actions:
+                - ENTRY
+
+              # Comment
+            quotes: {}
+        "#)),
+    )]
+    fn sequence_item_appending(config: &str, expected: &str) {
+        assert_eq!(append_portfolio_option_item(config, "ib", "corporate_actions", "- ENTRY").unwrap(), expected);
+    }
+
+    #[test]
+    fn mapping_item_appending() {
+        let config = indoc!(r#"
+            portfolios:
+              - name: ib
+                broker: open-broker
+                instrument_internal_ids: {}
+              - name: other
+                broker: open-broker
+                instrument_internal_ids:
+                  ROS AGRO PLC-GDR: AGRO
+        "#);
+
+        let config = append_portfolio_option_item(config, "ib", "instrument_internal_ids", "KEY1: VALUE1").unwrap();
+        let config = append_portfolio_option_item(&config, "ib", "instrument_internal_ids", "KEY2: VALUE2").unwrap();
+        let config = append_portfolio_option_item(&config, "other", "instrument_internal_ids", "KEY: VALUE").unwrap();
+
+        assert_eq!(config, indoc!(r#"
+            portfolios:
+              - name: ib
+                broker: open-broker
+                instrument_internal_ids:
+                  KEY1: VALUE1
+                  KEY2: VALUE2
+              - name: other
+                broker: open-broker
+                instrument_internal_ids:
+                  ROS AGRO PLC-GDR: AGRO
+                  KEY: VALUE
+        "#));
+
+        assert_eq!(
+            append_portfolio_option_item(&config, "unknown", "corporate_actions", "- ENTRY").unwrap_err().to_string(),
+            r#"Unable to find "unknown" portfolio"#);
+    }
+}
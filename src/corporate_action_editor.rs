@@ -0,0 +1,447 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::broker_statement::{
+    BrokerStatement, CorporateAction, CorporateActionType, ReadingStrictness, StatementSections, StockSplitRatio};
+use crate::config::Config;
+use crate::config_editor;
+use crate::core::GenericResult;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::{self, DateOptTime, Period};
+use crate::types::Decimal;
+
+// Corporate action types as they are specified in the configuration file
+const ACTION_TYPES: [(&str, &str); 5] = [
+    ("Rename", "rename"),
+    ("Stock split", "stock-split"),
+    ("Stock dividend", "stock-dividend"),
+    ("Dividend reinvestment", "dividend-reinvestment"),
+    ("Delisting", "delisting"),
+];
+
+/// Interactively asks for a corporate action, validates it against the broker statement, previews its effect on open
+/// positions and appends it to the portfolio configuration.
+pub fn add(config: &Config, config_path: &Path, portfolio_name: &str) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let read_statement = |corporate_actions: &[CorporateAction], sections| BrokerStatement::read(
+        broker.clone(), portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
+        corporate_actions, ReadingStrictness::empty(), sections);
+
+    // Trades processing may fail until the corporate action is added, so read only open positions here
+    let statement = read_statement(&portfolio.corporate_actions, StatementSections::empty())?;
+    let symbols = statement.open_positions.keys().cloned().collect();
+
+    let preview = |action: &CorporateAction| -> GenericResult<Vec<PositionChange>> {
+        let mut corporate_actions = portfolio.corporate_actions.clone();
+        corporate_actions.push(action.clone());
+
+        let statement = read_statement(&corporate_actions, StatementSections::all())?;
+        Ok(calculate_position_changes(&statement, action))
+    };
+
+    let mut editor = Editor::new(io::stdin().lock(), io::stdout(), statement.period, symbols, preview);
+    let Some(action) = editor.run()? else {
+        return Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker));
+    };
+
+    let path = config_path.display();
+    let config = fs::read_to_string(config_path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+    let item = format!("- {}", format_corporate_action(&action));
+    let config = config_editor::append_portfolio_option_item(&config, portfolio_name, "corporate_actions", &item)
+        .map_err(|e| format!("Unable to add the corporate action to {}: {}", path, e))?;
+    fs::write(config_path, config).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    println!("The corporate action has been added to {}.", path);
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+#[derive(Debug, PartialEq)]
+struct PositionChange {
+    symbol: String,
+    before: Decimal,
+    after: Decimal,
+}
+
+fn calculate_position_changes(statement: &BrokerStatement, action: &CorporateAction) -> Vec<PositionChange> {
+    let date = action.time.date;
+    let before = DateOptTime::new_max_time(date.pred_opt().unwrap());
+    let after = action.time;
+
+    let position = |symbol: &str, time| get_position(statement, symbol, time);
+    let change = |symbol: &str| PositionChange {
+        symbol: symbol.to_owned(),
+        before: position(symbol, before),
+        after: position(symbol, after),
+    };
+
+    match action.action {
+        // Rename corporate action renames all previous operations, so the old symbol has no history at all
+        CorporateActionType::Rename {ref new_symbol} => vec![
+            PositionChange {symbol: action.symbol.clone(), before: position(new_symbol, before), after: dec!(0)},
+            PositionChange {symbol: new_symbol.clone(), before: dec!(0), after: position(new_symbol, after)},
+        ],
+
+        CorporateActionType::StockDividend {stock: Some(ref stock), ..} if *stock != action.symbol => vec![
+            change(&action.symbol), change(stock),
+        ],
+
+        _ => vec![change(&action.symbol)],
+    }
+}
+
+// Calculates position by the trades history
+fn get_position(statement: &BrokerStatement, symbol: &str, time: DateOptTime) -> Decimal {
+    let mut quantity = dec!(0);
+
+    for trade in &statement.stock_buys {
+        if trade.symbol == symbol && trade.conclusion_time <= time {
+            quantity += trade.quantity * statement.stock_splits.get_multiplier(symbol, trade.conclusion_time, time);
+        }
+    }
+
+    for trade in &statement.stock_sells {
+        if trade.symbol == symbol && trade.conclusion_time <= time {
+            quantity -= trade.quantity * statement.stock_splits.get_multiplier(symbol, trade.conclusion_time, time);
+        }
+    }
+
+    quantity.normalize()
+}
+
+struct Editor<R: BufRead, W: Write, P: FnMut(&CorporateAction) -> GenericResult<Vec<PositionChange>>> {
+    input: R,
+    output: W,
+    period: Period,
+    symbols: HashSet<String>,
+    preview: P,
+}
+
+impl<R, W, P> Editor<R, W, P>
+    where R: BufRead, W: Write, P: FnMut(&CorporateAction) -> GenericResult<Vec<PositionChange>>
+{
+    fn new(input: R, output: W, period: Period, symbols: HashSet<String>, preview: P) -> Editor<R, W, P> {
+        Editor {input, output, period, symbols, preview}
+    }
+
+    fn run(&mut self) -> GenericResult<Option<CorporateAction>> {
+        loop {
+            let action = self.ask_action()?;
+
+            writeln!(self.output)?;
+            writeln!(self.output, "Checking the corporate action against the broker statement...")?;
+
+            let changes = match (self.preview)(&action) {
+                Ok(changes) => changes,
+                Err(e) => {
+                    writeln!(self.output, "The corporate action can't be applied: {}.", e)?;
+                    writeln!(self.output)?;
+                    continue;
+                },
+            };
+
+            writeln!(self.output, "Open positions change:")?;
+            for change in changes {
+                writeln!(self.output, "* {}: {} -> {}", change.symbol, change.before, change.after)?;
+            }
+
+            loop {
+                match self.ask_with_default("Add the corporate action to the configuration file?", "yes")?.as_str() {
+                    "y" | "yes" => return Ok(Some(action)),
+                    "n" | "no" => return Ok(None),
+                    _ => writeln!(self.output, "Please answer yes or no.")?,
+                }
+            }
+        }
+    }
+
+    fn ask_action(&mut self) -> GenericResult<CorporateAction> {
+        writeln!(self.output, "Supported corporate action types:")?;
+        for (index, (name, _)) in ACTION_TYPES.iter().enumerate() {
+            writeln!(self.output, "{}. {}", index + 1, name)?;
+        }
+
+        let type_ = loop {
+            let choice = self.ask("Choose corporate action type")?;
+            match choice.parse::<usize>().ok().and_then(|index| ACTION_TYPES.get(index.wrapping_sub(1))) {
+                Some(&(_, type_)) => break type_,
+                None => writeln!(self.output, "Invalid corporate action type number.")?,
+            }
+        };
+
+        let symbol = loop {
+            let symbol = self.ask("Symbol")?;
+            if symbol.is_empty() {
+                continue;
+            }
+
+            if !self.symbols.contains(&symbol) {
+                writeln!(self.output, "The portfolio has no {} position according to the broker statement.", symbol)?;
+                continue;
+            }
+
+            break symbol;
+        };
+
+        let date = loop {
+            let date = self.ask("Date (YYYY.MM.DD)")?;
+
+            let date = match time::parse_user_date(&date) {
+                Ok(date) => date,
+                Err(e) => {
+                    writeln!(self.output, "{}.", e)?;
+                    continue;
+                },
+            };
+
+            if !self.period.contains(date) {
+                writeln!(self.output, "The date is out of the broker statement period ({}).", self.period.format())?;
+                continue;
+            }
+
+            break date;
+        };
+
+        let action = match type_ {
+            "rename" => {
+                let new_symbol = loop {
+                    let new_symbol = self.ask("New symbol")?;
+                    if new_symbol == symbol {
+                        writeln!(self.output, "The new symbol must differ from the old one.")?;
+                        continue;
+                    } else if !new_symbol.is_empty() {
+                        break new_symbol;
+                    }
+                };
+                CorporateActionType::Rename {new_symbol}
+            },
+
+            "stock-split" => {
+                let ratio = loop {
+                    match self.ask("Split ratio (new shares:old shares)")?.parse::<StockSplitRatio>() {
+                        Ok(ratio) => break ratio,
+                        Err(e) => writeln!(self.output, "{}.", e)?,
+                    }
+                };
+                CorporateActionType::StockSplit {ratio, from_change: None, to_change: None}
+            },
+
+            "stock-dividend" => {
+                let stock = self.ask_with_default("Paid stock symbol", &symbol)?;
+                let quantity = self.ask_quantity("Paid shares quantity")?;
+                CorporateActionType::StockDividend {
+                    stock: (stock != symbol).then_some(stock),
+                    quantity,
+                }
+            },
+
+            "dividend-reinvestment" => {
+                let quantity = self.ask_quantity("Bought shares quantity")?;
+                let price = self.ask_price("Share price")?;
+                let currency = loop {
+                    let currency = self.ask("Price currency")?;
+                    if !currency.is_empty() {
+                        break currency.to_uppercase();
+                    }
+                };
+                CorporateActionType::DividendReinvestment {quantity, price, currency}
+            },
+
+            "delisting" => CorporateActionType::Delisting {
+                quantity: self.ask_quantity("Delisted shares quantity")?,
+            },
+
+            _ => unreachable!(),
+        };
+
+        Ok(CorporateAction {
+            time: date.into(),
+            report_date: None,
+            symbol,
+            action,
+        })
+    }
+
+    fn ask_quantity(&mut self, prompt: &str) -> GenericResult<Decimal> {
+        loop {
+            let value = self.ask(prompt)?;
+            match value.parse::<Decimal>() {
+                Ok(quantity) if quantity.is_sign_positive() && !quantity.is_zero() => return Ok(quantity),
+                _ => writeln!(self.output, "Invalid quantity: {:?}.", value)?,
+            }
+        }
+    }
+
+    fn ask_price(&mut self, prompt: &str) -> GenericResult<Decimal> {
+        loop {
+            let value = self.ask(prompt)?;
+            match value.parse::<Decimal>() {
+                Ok(price) if price.is_sign_positive() && !price.is_zero() => return Ok(price),
+                _ => writeln!(self.output, "Invalid price: {:?}.", value)?,
+            }
+        }
+    }
+
+    fn ask_with_default(&mut self, prompt: &str, default: &str) -> GenericResult<String> {
+        let value = self.ask(&format!("{} [{}]", prompt, default))?;
+        Ok(if value.is_empty() {
+            default.to_owned()
+        } else {
+            value
+        })
+    }
+
+    fn ask(&mut self, prompt: &str) -> GenericResult<String> {
+        write!(self.output, "{}: ", prompt)?;
+        self.output.flush()?;
+
+        let mut value = String::new();
+        if self.input.read_line(&mut value)? == 0 {
+            return Err!("Unexpected end of input");
+        }
+
+        Ok(value.trim().to_owned())
+    }
+}
+
+// Formats the corporate action as YAML flow mapping
+fn format_corporate_action(action: &CorporateAction) -> String {
+    let mut fields = vec![
+        format!("date: {}", action.time.date.format("%Y.%m.%d")),
+        format!("symbol: {}", config_editor::quote(&action.symbol)),
+    ];
+
+    let type_ = match action.action {
+        CorporateActionType::Rename {ref new_symbol} => {
+            fields.push(format!("new_symbol: {}", config_editor::quote(new_symbol)));
+            "rename"
+        },
+        CorporateActionType::StockSplit {ratio, ..} => {
+            fields.push(format!("ratio: {}", config_editor::quote(&ratio.to_string())));
+            "stock-split"
+        },
+        CorporateActionType::StockDividend {ref stock, quantity} => {
+            if let Some(stock) = stock {
+                fields.push(format!("stock: {}", config_editor::quote(stock)));
+            }
+            fields.push(format!("quantity: {}", quantity));
+            "stock-dividend"
+        },
+        CorporateActionType::DividendReinvestment {quantity, price, ref currency} => {
+            fields.push(format!("quantity: {}", quantity));
+            fields.push(format!("price: {}", price));
+            fields.push(format!("currency: {}", currency));
+            "dividend-reinvestment"
+        },
+        CorporateActionType::Delisting {quantity} => {
+            fields.push(format!("quantity: {}", quantity));
+            "delisting"
+        },
+        _ => unreachable!(),
+    };
+    fields.insert(2, format!("type: {}", type_));
+
+    format!("{{{}}}", fields.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn editor() {
+        let input = indoc!("
+            6
+            2
+            AAPL
+            TSLA
+            2019.01.01
+            2020.08.31
+            4
+            5:1
+            2
+            TSLA
+            2020.08.31
+            3:1
+            yes
+        ");
+
+        let mut previews = Vec::new();
+        let preview = |action: &CorporateAction| {
+            previews.push(action.clone());
+            if previews.len() == 1 {
+                return Err!("Unsupported stock split");
+            }
+            Ok(vec![PositionChange {symbol: s!("TSLA"), before: dec!(10), after: dec!(30)}])
+        };
+
+        let period = Period::new(date!(2020, 1, 1), date!(2020, 12, 31)).unwrap();
+        let symbols = hashset! {s!("TSLA")};
+
+        let mut output = Vec::new();
+        let action = Editor::new(input.as_bytes(), &mut output, period, symbols, preview).run().unwrap().unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Invalid corporate action type number."));
+        assert!(output.contains("The portfolio has no AAPL position according to the broker statement."));
+        assert!(output.contains("The date is out of the broker statement period"));
+        assert!(output.contains(r#"Invalid stock split ratio: "4"."#));
+        assert!(output.contains("The corporate action can't be applied: Unsupported stock split."));
+        assert!(output.contains("* TSLA: 10 -> 30"));
+
+        assert_eq!(action, CorporateAction {
+            time: date!(2020, 8, 31).into(),
+            report_date: None,
+            symbol: s!("TSLA"),
+            action: CorporateActionType::StockSplit {
+                ratio: StockSplitRatio::new(1, 3),
+                from_change: None,
+                to_change: None,
+            },
+        });
+        assert_eq!(
+            format_corporate_action(&action),
+            r#"{date: 2020.08.31, symbol: "TSLA", type: stock-split, ratio: "3:1"}"#);
+    }
+
+    #[test]
+    fn dividend_reinvestment() {
+        let input = indoc!("
+            4
+            VOO
+            2020.06.30
+            0.0234
+            0
+            301.5
+            usd
+            yes
+        ");
+
+        let preview = |_: &CorporateAction| Ok(vec![
+            PositionChange {symbol: s!("VOO"), before: dec!(10), after: dec!(10.0234)},
+        ]);
+
+        let period = Period::new(date!(2020, 1, 1), date!(2020, 12, 31)).unwrap();
+        let symbols = hashset! {s!("VOO")};
+
+        let mut output = Vec::new();
+        let action = Editor::new(input.as_bytes(), &mut output, period, symbols, preview).run().unwrap().unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(r#"Invalid price: "0"."#));
+        assert!(output.contains("* VOO: 10 -> 10.0234"));
+
+        let formatted = format_corporate_action(&action);
+        assert_eq!(formatted, concat!(
+            r#"{date: 2020.06.30, symbol: "VOO", type: dividend-reinvestment, "#,
+            r#"quantity: 0.0234, price: 301.5, currency: USD}"#));
+        assert_eq!(serde_yaml::from_str::<CorporateAction>(&formatted).unwrap(), action);
+    }
+}
@@ -4,9 +4,9 @@ use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
 use std::str::FromStr;
 
 use num_traits::{ToPrimitive, Zero};
-use separator::Separatable;
 
 use crate::core::{GenericResult, EmptyResult};
+use crate::formatting;
 use crate::time::Date;
 use crate::types::Decimal;
 
@@ -87,8 +87,8 @@ impl Cash {
     }
 
     pub fn format_rounded(&self) -> String {
-        let amount = super::round_to(self.amount, 0).to_i64().unwrap().separated_string();
-        super::format_currency(self.currency, &amount)
+        let amount = super::round_to(self.amount, 0).to_i64().unwrap();
+        formatting::format_currency(self.currency, &amount.to_string())
     }
 
     fn ensure_same_currency(self, other: Cash) -> EmptyResult {
@@ -185,7 +185,7 @@ impl fmt::Display for Cash {
             amount = Decimal::new(amount.to_i64().unwrap() * 10, 2)
         }
 
-        write!(f, "{}", super::format_currency(self.currency, &separated_float!(amount.to_string())))
+        write!(f, "{}", formatting::format_currency(self.currency, &amount.to_string()))
     }
 }
 
@@ -5,14 +5,16 @@ use chrono::Duration;
 #[cfg(test)] use matches::assert_matches;
 
 use crate::core::{EmptyResult, GenericResult};
-use crate::currency::{self, Cash};
+use crate::currency::{self, Cash, ManualCurrencyRates};
 use crate::currency::rate_cache::{CurrencyRateCache, CurrencyRateCacheResult};
 use crate::db;
 use crate::forex::get_currency_pair;
 use crate::formatting;
 use crate::localities;
+use crate::profiling::{self, Phase};
 use crate::quotes::{cbr, CurrencyRate, Quotes, QuoteQuery};
 #[cfg(test)] use crate::time;
+use crate::time::Period;
 use crate::types::{Date, Decimal};
 #[cfg(test)] use crate::util;
 
@@ -33,12 +35,25 @@ use crate::types::{Date, Decimal};
 // and portfolio analysis / sell simulations where all calculations are processed in T+N mode and
 // forex quotes at conclusion date will be the closest approximation to the future CBR currency rate
 // for trade execution date.
+//
+// Manual currency rates may be specified for tax calculations when CBR doesn't publish daily rates for some currency.
+// They take precedence over CBR currency rates.
+//
+// Exotic currencies may have no direct quotes (for example, forex quote providers may lack HKD/RUB pair), so if direct
+// conversion fails, the converter builds a cross rate through an intermediate currency.
 pub struct CurrencyConverter {
     backend: Box<dyn CurrencyConverterBackend>,
+    manual_rates: ManualCurrencyRates,
 }
 
 pub type CurrencyConverterRc = Rc<CurrencyConverter>;
 
+// Intermediate currency paths for cross rates: the shortest ones are tried first
+const CROSS_RATE_PATHS: [&[&str]; 4] = [
+    &["USD"], &[cbr::BASE_CURRENCY],
+    &["USD", cbr::BASE_CURRENCY], &[cbr::BASE_CURRENCY, "USD"],
+];
+
 impl CurrencyConverter {
     pub fn new(database: db::Connection, quotes: Option<Rc<Quotes>>, strict_mode: bool) -> CurrencyConverterRc {
         let rate_cache = CurrencyRateCache::new(database);
@@ -46,22 +61,43 @@ impl CurrencyConverter {
         Rc::new(CurrencyConverter::new_with_backend(backend))
     }
 
+    pub fn new_with_manual_rates(database: db::Connection, manual_rates: ManualCurrencyRates) -> CurrencyConverterRc {
+        let rate_cache = CurrencyRateCache::new(database);
+        let backend = CurrencyRateCacheBackend::new(rate_cache, None, true);
+        Rc::new(CurrencyConverter {backend, manual_rates})
+    }
+
     #[cfg(test)]
     pub fn mock() -> CurrencyConverterRc {
         Rc::new(CurrencyConverter::new_with_backend(CurrencyRateCacheBackendMock::new()))
     }
 
     pub fn new_with_backend(source: Box<dyn CurrencyConverterBackend>) -> CurrencyConverter {
-        CurrencyConverter { backend: source }
+        CurrencyConverter {
+            backend: source,
+            manual_rates: ManualCurrencyRates::default(),
+        }
     }
 
     pub fn batch(&self, date: Date, from: &str, to: &str) -> EmptyResult {
-        if from != to {
+        let _timer = profiling::start(Phase::CurrencyConversion);
+        if from != to && self.manual_rates.currency_rate(from, to, date).is_none() {
             self.backend.batch(from, to, date)?;
         }
         Ok(())
     }
 
+    /// Fetches official currency rates for the whole period with a single request per currency. It's much faster than
+    /// fetching them on demand when there are a lot of operations to convert (for example, in tax statements of active
+    /// traders).
+    pub fn preload(&self, currencies: &[&str], period: Period) -> EmptyResult {
+        let _timer = profiling::start(Phase::CurrencyConversion);
+        for currency in currencies {
+            self.backend.preload(currency, period.first_date(), period.last_date())?;
+        }
+        Ok(())
+    }
+
     pub fn currency_rate(&self, date: Date, from: &str, to: &str) -> GenericResult<Decimal> {
         self.convert(from, to, date, dec!(1))
     }
@@ -98,11 +134,43 @@ impl CurrencyConverter {
         Ok(Cash::new(to, self.convert_to(date, cash, to)?))
     }
 
-    pub fn convert(&self, from: &str, to: &str, date: Date, mut amount: Decimal) -> GenericResult<Decimal> {
+    pub fn convert(&self, from: &str, to: &str, date: Date, amount: Decimal) -> GenericResult<Decimal> {
+        let _timer = profiling::start(Phase::CurrencyConversion);
         if from == to {
             return Ok(amount);
         }
 
+        let error = match self.convert_directly(from, to, date, amount) {
+            Ok(amount) => return Ok(amount),
+            Err(error) => error,
+        };
+
+        for path in CROSS_RATE_PATHS {
+            if path.contains(&from) || path.contains(&to) {
+                continue;
+            }
+
+            let mut result = Ok(amount);
+            let mut current = from;
+
+            for &next in path.iter().chain([to].iter()) {
+                result = result.and_then(|amount| self.convert_directly(current, next, date, amount));
+                current = next;
+            }
+
+            if let Ok(amount) = result {
+                return Ok(amount);
+            }
+        }
+
+        Err(error)
+    }
+
+    fn convert_directly(&self, from: &str, to: &str, date: Date, mut amount: Decimal) -> GenericResult<Decimal> {
+        if let Some(rate) = self.manual_rates.currency_rate(from, to, date) {
+            return Ok(amount * rate);
+        }
+
         let (multiplier, divider) = self.backend.currency_rate(from, to, date)?;
         if let Some(multiplier) = multiplier {
             amount *= multiplier;
@@ -123,6 +191,10 @@ pub trait CurrencyConverterBackend {
     fn today(&self) -> Date;
     fn batch(&self, from: &str, to: &str, date: Date) -> EmptyResult;
     fn currency_rate(&self, from: &str, to: &str, date: Date) -> GenericResult<(Option<Decimal>, Option<Decimal>)>;
+
+    fn preload(&self, _currency: &str, _start_date: Date, _end_date: Date) -> EmptyResult {
+        Ok(())
+    }
 }
 
 struct CurrencyRateCacheBackend {
@@ -137,7 +209,8 @@ impl CurrencyRateCacheBackend {
     pub fn new(rate_cache: CurrencyRateCache, quotes: Option<Rc<Quotes>>, strict_mode: bool) -> Box<dyn CurrencyConverterBackend> {
         Box::new(CurrencyRateCacheBackend {
             #[cfg(not(test))]
-            cbr: cbr::Cbr::new("https://www.cbr.ru"),
+            // The client uses the global network settings
+            cbr: cbr::Cbr::new("https://www.cbr.ru", None, None),
             quotes,
             rate_cache,
             strict_mode,
@@ -235,6 +308,22 @@ impl CurrencyConverterBackend for CurrencyRateCacheBackend {
         Ok(())
     }
 
+    fn preload(&self, currency: &str, start_date: Date, end_date: Date) -> EmptyResult {
+        if currency == cbr::BASE_CURRENCY {
+            return Ok(());
+        }
+
+        // Currency rate for the first day may be taken from one of the previous days
+        let start_date = localities::get_russian_central_bank_min_last_working_day(start_date);
+
+        if let Some((start_date, end_date)) = self.rate_cache.get_missing_range(currency, start_date, end_date)? {
+            let currency_rates = self.get_rates(currency, start_date, end_date)?;
+            self.rate_cache.save(currency, start_date, end_date, currency_rates)?;
+        }
+
+        Ok(())
+    }
+
     fn currency_rate(&self, from: &str, to: &str, date: Date) -> GenericResult<(Option<Decimal>, Option<Decimal>)> {
         if let Some(quotes) = self.check_date(date)? {
             let price = quotes.get(QuoteQuery::Forex(get_currency_pair(from, to)))?;
@@ -307,8 +396,36 @@ impl CurrencyConverterBackend for CurrencyRateCacheBackendMock {
     }
 }
 
+#[cfg(test)]
+struct CrossRatesBackendMock {
+    rates: Vec<(&'static str, &'static str, Decimal)>,
+}
+
+#[cfg(test)]
+impl CurrencyConverterBackend for CrossRatesBackendMock {
+    fn today(&self) -> Date {
+        time::today()
+    }
+
+    fn batch(&self, _from: &str, _to: &str, _date: Date) -> EmptyResult {
+        Ok(())
+    }
+
+    fn currency_rate(&self, from: &str, to: &str, _date: Date) -> GenericResult<(Option<Decimal>, Option<Decimal>)> {
+        for &(base, quote, rate) in &self.rates {
+            if (from, to) == (base, quote) {
+                return Ok((Some(rate), None));
+            } else if (from, to) == (quote, base) {
+                return Ok((None, Some(rate)));
+            }
+        }
+        Err!("Unable to find {}/{} currency rate", from, to)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
     use super::*;
 
     #[test]
@@ -371,4 +488,37 @@ mod tests {
             );
         }
     }
-}
\ No newline at end of file
+
+    #[rstest(from, to, amount, expected,
+        case("USD", "RUB", dec!(2), dec!(180)),
+        case("HKD", "RUB", dec!(10), dec!(115.2)),
+        case("RUB", "HKD", dec!(115.2), dec!(10)),
+        case("GBP", "HKD", dec!(2), dec!(19.53125)),
+        case("CNY", "GBP", dec!(50), dec!(5.6)),
+    )]
+    fn cross_rates(from: &str, to: &str, amount: Decimal, expected: Decimal) {
+        let converter = CurrencyConverter::new_with_backend(Box::new(CrossRatesBackendMock {
+            rates: vec![
+                ("USD", "RUB", dec!(90)),
+                ("HKD", "USD", dec!(0.128)),
+                ("GBP", "USD", dec!(1.25)),
+                ("CNY", "RUB", dec!(12.6)),
+            ],
+        }));
+
+        let result = converter.convert(from, to, date!(2024, 1, 1), amount).unwrap();
+        assert_eq!(util::round(result, 8), expected);
+    }
+
+    #[test]
+    fn missing_cross_rate() {
+        let converter = CurrencyConverter::new_with_backend(Box::new(CrossRatesBackendMock {
+            rates: vec![("USD", "RUB", dec!(90))],
+        }));
+
+        assert_matches!(
+            converter.convert("HKD", "RUB", date!(2024, 1, 1), dec!(1)),
+            Err(ref e) if e.to_string() == "Unable to find HKD/RUB currency rate"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use serde::Deserialize;
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::quotes::cbr;
+use crate::time::{Period, deserialize_date};
+use crate::types::{Date, Decimal};
+use crate::util::{self, DecimalRestrictions};
+
+/// Official currency rate which is specified manually for the currencies CBR doesn't publish daily rates for
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ManualCurrencyRateConfig {
+    pub currency: String,
+    #[serde(deserialize_with = "deserialize_date")]
+    pub from: Date,
+    #[serde(deserialize_with = "deserialize_date")]
+    pub to: Date,
+    /// Price of one currency unit in RUB
+    pub rate: Decimal,
+    /// Where the rate has been taken from (to be shown in reports)
+    pub source: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ManualCurrencyRate {
+    pub currency: String,
+    pub period: Period,
+    pub rate: Decimal,
+    pub source: Option<String>,
+}
+
+impl ManualCurrencyRate {
+    pub fn source(&self) -> String {
+        match self.source {
+            Some(ref source) => format!("{} ({})", source, self.period.format()),
+            None => format!("Задан вручную ({})", self.period.format()),
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ManualCurrencyRates {
+    rates: Vec<ManualCurrencyRate>,
+}
+
+impl ManualCurrencyRates {
+    pub fn new(configs: &[ManualCurrencyRateConfig]) -> GenericResult<ManualCurrencyRates> {
+        let mut rates = ManualCurrencyRates::default();
+
+        for config in configs {
+            rates.add(config).map_err(|e| format!(
+                "Invalid {} manual currency rate: {}", config.currency, e))?;
+        }
+
+        Ok(rates)
+    }
+
+    fn add(&mut self, config: &ManualCurrencyRateConfig) -> EmptyResult {
+        if config.currency == cbr::BASE_CURRENCY {
+            return Err!("Currency rates are specified relative to {}", cbr::BASE_CURRENCY);
+        }
+
+        let rate = ManualCurrencyRate {
+            currency: config.currency.clone(),
+            period: Period::new(config.from, config.to)?,
+            rate: util::validate_named_decimal("currency rate", config.rate, DecimalRestrictions::StrictlyPositive)?,
+            source: config.source.clone(),
+        };
+
+        for other in &self.rates {
+            if other.currency == rate.currency && (
+                rate.period.contains(other.period.first_date()) || other.period.contains(rate.period.first_date())
+            ) {
+                return Err!("{} overlaps with {}", rate.period.format(), other.period.format());
+            }
+        }
+
+        self.rates.push(rate);
+        Ok(())
+    }
+
+    pub fn get(&self, currency: &str, date: Date) -> Option<&ManualCurrencyRate> {
+        self.rates.iter().find(|rate| rate.currency == currency && rate.period.contains(date))
+    }
+
+    /// Returns from -> to currency rate if it's specified manually
+    pub fn currency_rate(&self, from: &str, to: &str, date: Date) -> Option<Decimal> {
+        if to == cbr::BASE_CURRENCY {
+            self.get(from, date).map(|rate| rate.rate)
+        } else if from == cbr::BASE_CURRENCY {
+            self.get(to, date).map(|rate| dec!(1) / rate.rate)
+        } else {
+            None
+        }
+    }
+
+    pub fn format_source(&self, currency: &str, date: Date) -> String {
+        match self.get(currency, date) {
+            Some(rate) => rate.source(),
+            None => s!("ЦБ РФ"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(currency: &str, from: Date, to: Date, rate: Decimal) -> ManualCurrencyRateConfig {
+        ManualCurrencyRateConfig {
+            currency: currency.to_owned(), from, to, rate,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn currency_rates() {
+        let rates = ManualCurrencyRates::new(&[
+            rate("AED", date!(2024, 1, 1), date!(2024, 6, 30), dec!(24.5)),
+            rate("AED", date!(2024, 7, 1), date!(2024, 12, 31), dec!(25)),
+        ]).unwrap();
+
+        assert_eq!(rates.currency_rate("AED", "RUB", date!(2024, 6, 30)), Some(dec!(24.5)));
+        assert_eq!(rates.currency_rate("RUB", "AED", date!(2024, 7, 1)), Some(dec!(0.04)));
+        assert_eq!(rates.currency_rate("AED", "USD", date!(2024, 7, 1)), None);
+        assert_eq!(rates.currency_rate("AED", "RUB", date!(2025, 1, 1)), None);
+        assert_eq!(rates.currency_rate("USD", "RUB", date!(2024, 7, 1)), None);
+    }
+
+    #[test]
+    fn overlapping_rates() {
+        let result = ManualCurrencyRates::new(&[
+            rate("AED", date!(2024, 1, 1), date!(2024, 6, 30), dec!(24.5)),
+            rate("AED", date!(2024, 6, 1), date!(2024, 12, 31), dec!(25)),
+        ]);
+        assert!(result.is_err());
+    }
+}
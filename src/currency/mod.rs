@@ -8,6 +8,7 @@ use crate::types::Decimal;
 use crate::util;
 
 mod cash;
+mod manual_rates;
 mod multi;
 mod name_cache;
 mod rate_cache;
@@ -15,6 +16,7 @@ mod rate_cache;
 pub mod converter;
 
 pub use self::cash::{Cash, CashAssets};
+pub use self::manual_rates::{ManualCurrencyRateConfig, ManualCurrencyRates};
 pub use self::multi::MultiCurrencyCashAccount;
 
 pub fn round(amount: Decimal) -> Decimal {
@@ -46,42 +48,6 @@ pub fn validate_currency_list<C, I>(currencies: C) -> Result<(), ValidationError
     Ok(())
 }
 
-fn format_currency(currency: &str, mut amount: &str) -> String {
-    let prefix = match currency {
-        "AUD" => Some("AU$"),
-        "CNY" => Some("¥"),
-        "EUR" => Some("€"),
-        "GBP" => Some("£"),
-        "USD" => Some("$"),
-        _ => None,
-    };
-
-    let mut buffer = String::with_capacity(amount.len() + prefix.map(str::len).unwrap_or(1));
-
-    if let Some(prefix) = prefix {
-        if amount.starts_with('-') || amount.starts_with('+') {
-            buffer.push_str(&amount[..1]);
-            amount = &amount[1..];
-        }
-        buffer.push_str(prefix);
-    }
-
-    buffer.push_str(amount);
-
-    if prefix.is_none() {
-        match currency {
-            "HKD" => buffer.push_str(" HK$"),
-            "RUB" => buffer.push('₽'),
-            _ => {
-                buffer.push(' ');
-                buffer.push_str(currency);
-            },
-        };
-    }
-
-    buffer
-}
-
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -200,6 +200,44 @@ impl CurrencyRateCache {
     }
 }
 
+impl CurrencyRateCache {
+    /// Returns date range which has to be fetched to have currency rates for the whole specified period or `None` if
+    /// they are already cached. The range is extended up to the next known currency rate, so it conforms to the same
+    /// rules as the ranges returned by `get()`.
+    pub fn get_missing_range(
+        &self, currency: &str, start_date: Date, end_date: Date,
+    ) -> GenericResult<Option<(Date, Date)>> {
+        let end_date = std::cmp::min(end_date, self.today);
+        if start_date > end_date {
+            return Ok(None);
+        }
+
+        let mut db = self.db.borrow();
+
+        let cached_days: i64 = currency_rates::table
+            .filter(currency_rates::currency.eq(currency))
+            .filter(currency_rates::date.ge(start_date))
+            .filter(currency_rates::date.le(end_date))
+            .count()
+            .get_result(db.deref_mut())?;
+
+        if cached_days > (end_date - start_date).num_days() {
+            return Ok(None);
+        }
+
+        let next_date = currency_rates::table
+            .select(currency_rates::date)
+            .filter(currency_rates::currency.eq(currency))
+            .filter(currency_rates::date.gt(end_date))
+            .filter(currency_rates::price.is_not_null())
+            .order(currency_rates::date.asc())
+            .limit(1)
+            .get_result::<Date>(db.deref_mut()).optional()?;
+
+        Ok(Some((start_date, next_date.unwrap_or(self.tomorrow))))
+    }
+}
+
 #[derive(Debug)]
 pub enum CurrencyRateCacheResult {
     Exists(Option<Decimal>),
@@ -297,4 +335,28 @@ mod tests {
                 if from == last_date.succ_opt().unwrap() && to == cache.tomorrow
         );
     }
+
+    #[test]
+    fn missing_range() {
+        let currency = "USD";
+        let (_database, mut cache) = CurrencyRateCache::new_temporary();
+
+        cache.today = date!(2018, 2, 8);
+        cache.tomorrow = date!(2018, 2, 9);
+
+        let period = (date!(2017, 1, 1), date!(2017, 12, 31));
+        assert_eq!(cache.get_missing_range(currency, period.0, period.1).unwrap(), Some((period.0, cache.tomorrow)));
+        assert_eq!(cache.get_missing_range(currency, cache.tomorrow, cache.tomorrow).unwrap(), None);
+
+        cache.save(currency, date!(2017, 6, 1), date!(2018, 1, 10), vec![
+            CurrencyRate {date: date!(2017, 6, 1), price: dec!(56.5)},
+            CurrencyRate {date: date!(2018, 1, 10), price: dec!(57.1)},
+        ]).unwrap();
+
+        assert_eq!(cache.get_missing_range(currency, period.0, period.1).unwrap(),
+                   Some((period.0, date!(2018, 1, 10))));
+        assert_eq!(cache.get_missing_range(currency, date!(2017, 6, 1), period.1).unwrap(), None);
+        assert_eq!(cache.get_missing_range(currency, date!(2018, 1, 1), date!(2018, 2, 1)).unwrap(),
+                   Some((date!(2018, 1, 1), cache.tomorrow)));
+    }
 }
\ No newline at end of file
@@ -3,7 +3,7 @@ pub mod schema;
 
 use std::sync::{Arc, Mutex, MutexGuard};
 
-use diesel::{Connection as ConnectionTrait, SqliteConnection};
+use diesel::{Connection as ConnectionTrait, RunQueryDsl, SqliteConnection};
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness};
 #[cfg(test)] use tempfile::NamedTempFile;
 
@@ -20,6 +20,22 @@ impl Connection {
     }
 }
 
+/// Runs SQLite integrity check returning a list of found problems
+pub fn check_integrity(connection: &Connection) -> GenericResult<Vec<String>> {
+    #[derive(QueryableByName)]
+    struct Row {
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        integrity_check: String,
+    }
+
+    let rows: Vec<Row> = diesel::sql_query("PRAGMA integrity_check").load(&mut *connection.borrow())?;
+
+    Ok(rows.into_iter()
+        .map(|row| row.integrity_check)
+        .filter(|message| message != "ok")
+        .collect())
+}
+
 pub fn connect(url: &str) -> GenericResult<Connection> {
     let mut connection = SqliteConnection::establish(url).map_err(|e| format!(
         "Unable to open {:?} database: {}", url, e))?;
@@ -1,4 +1,6 @@
-use crate::db::schema::{AssetType, assets, currency_rates, quotes, settings, telemetry};
+use crate::db::schema::{
+    AssetType, assets, currency_rates, instrument_reference, manual_trades, quotes, rate_limits, settings, telemetry,
+    trading_results};
 use crate::types::{Date, DateTime};
 
 #[derive(Insertable, Queryable)]
@@ -18,6 +20,25 @@ pub struct NewCurrencyRate<'a> {
     pub price: Option<String>,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = instrument_reference)]
+pub struct NewInstrumentReference<'a> {
+    pub id: &'a str,
+    pub time: DateTime,
+    pub info: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = manual_trades)]
+pub struct NewManualTrade<'a> {
+    pub portfolio: &'a str,
+    pub date: Date,
+    pub symbol: &'a str,
+    pub quantity: String,
+    pub price: String,
+    pub currency: &'a str,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = quotes)]
 pub struct NewQuote<'a> {
@@ -27,6 +48,14 @@ pub struct NewQuote<'a> {
     pub price: String,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = rate_limits)]
+pub struct NewRateLimit<'a> {
+    pub name: &'a str,
+    pub period: i64,
+    pub time: DateTime,
+}
+
 pub const SETTING_USER_ID: &str = "user_id";
 
 #[derive(Insertable)]
@@ -40,4 +69,13 @@ pub struct NewSetting<'a> {
 #[diesel(table_name = telemetry)]
 pub struct NewTelemetryRecord {
     pub payload: String,
-}
\ No newline at end of file
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = trading_results)]
+pub struct NewTradingResult<'a> {
+    pub portfolio: &'a str,
+    pub market: &'a str,
+    pub year: i32,
+    pub taxable_profit: String,
+}
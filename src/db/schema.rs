@@ -26,6 +26,26 @@ table! {
     }
 }
 
+table! {
+    instrument_reference (id) {
+        id -> Text,
+        time -> Timestamp,
+        info -> Nullable<Text>,
+    }
+}
+
+table! {
+    manual_trades (id) {
+        id -> BigInt,
+        portfolio -> Text,
+        date -> Date,
+        symbol -> Text,
+        quantity -> Text,
+        price -> Text,
+        currency -> Text,
+    }
+}
+
 table! {
     quotes (symbol) {
         symbol -> Text,
@@ -35,6 +55,14 @@ table! {
     }
 }
 
+table! {
+    rate_limits (name, period) {
+        name -> Text,
+        period -> BigInt,
+        time -> Timestamp,
+    }
+}
+
 table! {
     settings (name) {
         name -> Text,
@@ -47,4 +75,13 @@ table! {
         id -> BigInt,
         payload -> Text,
     }
-}
\ No newline at end of file
+}
+
+table! {
+    trading_results (portfolio, market, year) {
+        portfolio -> Text,
+        market -> Text,
+        year -> Integer,
+        taxable_profit -> Text,
+    }
+}
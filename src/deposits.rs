@@ -1,29 +1,44 @@
-use chrono::Duration;
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration};
 
 use static_table_derive::StaticTable;
 
 use crate::analysis::deposit_emulator::{DepositEmulator, Transaction};
 use crate::config::DepositConfig;
+use crate::core::EmptyResult;
 use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::db;
 use crate::formatting::{self, table::Style};
 use crate::localities::Country;
+use crate::taxes::DepositInterestTax;
+use crate::time::Month;
 use crate::types::{Date, Decimal};
 
-pub fn list(country: &Country, deposits: Vec<DepositConfig>, today: Date, cron_mode: bool, notify_days: Option<u32>) {
+pub fn list(
+    country: &Country, db_path: &str, deposits: Vec<DepositConfig>, today: Date, cron_mode: bool, monthly: bool,
+    notify_days: Option<u32>,
+) -> EmptyResult {
     let mut deposits: Vec<DepositConfig> = deposits.into_iter().filter(|deposit| {
         deposit.open_date <= today
     }).collect();
 
     if deposits.is_empty() {
-        return
+        return Ok(());
     }
     deposits.sort_by_key(|deposit| deposit.close_date);
 
     if cron_mode {
         print_cron_mode(country, deposits, today, notify_days)
+    } else if monthly {
+        let converter = CurrencyConverter::new(db::connect(db_path)?, None, false);
+        print_monthly(country, &converter, &deposits)?;
     } else {
         print(country, deposits, today);
     }
+
+    Ok(())
 }
 
 #[derive(StaticTable)]
@@ -76,6 +91,114 @@ fn print(country: &Country, deposits: Vec<DepositConfig>, today: Date) {
     table.print("Open deposits");
 }
 
+#[derive(StaticTable)]
+#[table(name="MonthlyTable")]
+struct MonthlyRow {
+    #[column(name="Month")]
+    month: String,
+    #[column(name="Contributions")]
+    contributions: MultiCurrencyCashAccount,
+    #[column(name="Interest")]
+    interest: MultiCurrencyCashAccount,
+    #[column(name="Tax")]
+    tax: Cash,
+    #[column(name="Assets")]
+    assets: MultiCurrencyCashAccount,
+}
+
+#[derive(Default)]
+struct MonthTotals {
+    contributions: MultiCurrencyCashAccount,
+    interest: MultiCurrencyCashAccount,
+    local_interest: Decimal,
+    assets: MultiCurrencyCashAccount,
+}
+
+// Emulates all deposits till their close dates. Assets are shown for the deposits which are still open at the end of
+// the month. Tax is calculated for interest of all deposits converted to local currency (at the current rate for the
+// future months).
+fn print_monthly(country: &Country, converter: &CurrencyConverter, deposits: &[DepositConfig]) -> EmptyResult {
+    let mut months: BTreeMap<Month, MonthTotals> = BTreeMap::new();
+
+    for deposit in deposits {
+        let currency = deposit.currency.as_ref().map_or(country.currency, String::as_str);
+
+        let mut contributions = vec![(deposit.open_date, deposit.amount)];
+        contributions.extend(&deposit.contributions);
+
+        let transactions: Vec<_> = contributions.iter()
+            .map(|&(date, amount)| Transaction::new(date, amount))
+            .collect();
+
+        let statements = DepositEmulator::new(deposit.open_date, deposit.close_date, deposit.interest)
+            .with_monthly_capitalization(deposit.capitalization)
+            .emulate_monthly(&transactions);
+
+        for statement in statements {
+            let totals = months.entry(statement.month).or_default();
+            totals.contributions.deposit(Cash::new(currency, statement.contributions));
+            totals.interest.deposit(Cash::new(currency, statement.interest).round());
+
+            let conversion_date = std::cmp::min(statement.month.period().last_date(), converter.real_time_date());
+            totals.local_interest += converter.convert_to(
+                conversion_date, Cash::new(currency, statement.interest), country.currency)?;
+
+            if deposit.close_date > statement.month.period().last_date() {
+                totals.assets.deposit(Cash::new(currency, statement.assets).round());
+            }
+        }
+    }
+
+    let years = months.keys().next().zip(months.keys().next_back()).map(|(first, last)| {
+        first.period().first_date().year()..=last.period().first_date().year()
+    }).unwrap();
+    let interest_tax = DepositInterestTax::new(country, country.currency, years);
+
+    let mut table = MonthlyTable::new();
+    let mut total_contributions = MultiCurrencyCashAccount::new();
+    let mut total_interest = MultiCurrencyCashAccount::new();
+    let mut total_tax = Cash::zero(country.currency);
+
+    let mut year = None;
+    let mut year_interest = dec!(0);
+    let mut year_tax = dec!(0);
+
+    for (month, totals) in months {
+        let month_year = month.period().first_date().year();
+        if year != Some(month_year) {
+            year = Some(month_year);
+            year_interest = dec!(0);
+            year_tax = dec!(0);
+        }
+
+        // The tax is calculated for the whole year, so monthly tax is an increment of the yearly one
+        year_interest += totals.local_interest;
+        let tax = interest_tax.as_ref().map_or(dec!(0), |interest_tax| interest_tax.tax(month_year, year_interest));
+        let month_tax = Cash::new(country.currency, tax - year_tax);
+        year_tax = tax;
+
+        total_contributions.add(&totals.contributions);
+        total_interest.add(&totals.interest);
+        total_tax += month_tax;
+
+        table.add_row(MonthlyRow {
+            month: month.format(),
+            contributions: totals.contributions,
+            interest: totals.interest,
+            tax: month_tax,
+            assets: totals.assets,
+        });
+    }
+
+    let mut totals = table.add_empty_row();
+    totals.set_contributions(total_contributions);
+    totals.set_interest(total_interest);
+    totals.set_tax(total_tax);
+
+    table.print("Deposits by months");
+    Ok(())
+}
+
 fn print_cron_mode(country: &Country, deposits: Vec<DepositConfig>, today: Date, notify_days: Option<u32>) {
     let mut expiring_deposits = Vec::new();
     let mut closed_deposits = Vec::new();
@@ -0,0 +1,305 @@
+use std::path::Path;
+
+use ansi_term::Color;
+use log::info;
+
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections, StatementsMergingStrategy};
+use crate::config::{Config, PortfolioConfig};
+use crate::core::GenericResult;
+use crate::db;
+use crate::quotes::{self, Moex, Quotes};
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::{self, Date, Period};
+
+// Broker statements older than this are considered as outdated
+const STATEMENT_MAX_AGE_DAYS: i64 = 31;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug)]
+struct Problem {
+    severity: Severity,
+    subject: String,
+    message: String,
+    hint: Option<String>,
+}
+
+#[derive(Default)]
+struct Problems(Vec<Problem>);
+
+impl Problems {
+    fn add(&mut self, severity: Severity, subject: &str, message: String, hint: Option<String>) {
+        self.0.push(Problem {severity, subject: subject.to_owned(), message, hint});
+    }
+
+    fn error<M: ToString>(&mut self, subject: &str, message: M, hint: Option<String>) {
+        self.add(Severity::Error, subject, message.to_string(), hint);
+    }
+
+    fn warning<M: ToString>(&mut self, subject: &str, message: M, hint: Option<String>) {
+        self.add(Severity::Warning, subject, message.to_string(), hint);
+    }
+
+    fn has_errors(&self) -> bool {
+        self.0.iter().any(|problem| problem.severity == Severity::Error)
+    }
+
+    fn print(mut self) {
+        if self.0.is_empty() {
+            println!("{}", Color::Green.paint("No problems found."));
+            return;
+        }
+
+        // Stable sort keeps the checking order within the same severity
+        self.0.sort_by_key(|problem| problem.severity);
+
+        println!("Found {} problem(s):", self.0.len());
+
+        for (index, problem) in self.0.iter().enumerate() {
+            let severity = match problem.severity {
+                Severity::Error => Color::Red.paint("error"),
+                Severity::Warning => Color::Yellow.paint("warning"),
+            };
+
+            println!("{:>3}. [{}] {}: {}.", index + 1, severity, problem.subject, problem.message);
+            if let Some(hint) = problem.hint.as_ref() {
+                println!("     Hint: {}.", hint);
+            }
+        }
+    }
+}
+
+/// Checks the environment and data for common problems and prints them ordered by their severity
+pub fn run(config: &Config, check_dividends: bool) -> GenericResult<TelemetryRecordBuilder> {
+    let mut problems = Problems::default();
+
+    let database = check_database(config, &mut problems);
+
+    if config.portfolios.is_empty() {
+        problems.warning("Configuration", "No portfolios are configured", Some(s!(
+            "See docs/config-example.yaml for portfolio configuration examples")));
+    }
+
+    let quotes = database.as_ref().and_then(|database| {
+        info!("Checking quotes providers...");
+
+        for (provider, result) in quotes::check_credentials(config, database.clone()) {
+            if let Err(e) = result {
+                problems.error(provider, format!("API token check has failed: {}", e), Some(format!(
+                    "Check {} API token in the configuration file", provider)));
+            }
+        }
+
+        Quotes::new(config, database.clone()).map_err(|e| {
+            problems.error("Configuration", e, Some(s!(
+                "See docs/quotes.md for quotes providers configuration")));
+        }).ok()
+    });
+
+    for portfolio in &config.portfolios {
+        info!("Checking {} portfolio...", portfolio.name);
+        check_portfolio(config, portfolio, quotes.as_ref(), check_dividends, &mut problems);
+    }
+
+    let has_errors = problems.has_errors();
+    problems.print();
+
+    if has_errors {
+        return Err!("Found problems which must be fixed");
+    }
+
+    Ok(TelemetryRecordBuilder::new())
+}
+
+fn check_database(config: &Config, problems: &mut Problems) -> Option<db::Connection> {
+    info!("Checking the database...");
+
+    let database = match db::connect(&config.db_path) {
+        Ok(database) => database,
+        Err(e) => {
+            problems.error("Database", e, None);
+            return None;
+        },
+    };
+
+    match db::check_integrity(&database) {
+        Ok(errors) => if !errors.is_empty() {
+            problems.error("Database", format!("The database is corrupted: {}", errors.join(", ")), Some(format!(
+                "The database contains only cached data, so it can be safely removed: {}", config.db_path)));
+        },
+        Err(e) => problems.error("Database", format!("Integrity check has failed: {}", e), None),
+    }
+
+    Some(database)
+}
+
+fn check_portfolio(
+    config: &Config, portfolio: &PortfolioConfig, quotes: Option<&Quotes>, check_dividends: bool,
+    problems: &mut Problems,
+) {
+    let subject = format!("{:?} portfolio", portfolio.name);
+    let subject = subject.as_str();
+
+    let statements_path = match portfolio.statements.as_ref() {
+        Some(path) => path,
+        None => {
+            problems.warning(subject, "Broker statements path is not specified", Some(s!(
+                "Specify `statements` option to be able to analyse the portfolio and generate tax statements")));
+            return;
+        },
+    };
+
+    if !Path::new(statements_path).is_dir() {
+        problems.error(subject, format!("{:?} broker statements directory doesn't exist", statements_path), None);
+        return;
+    }
+
+    match check_statements(config, portfolio, statements_path, subject, problems) {
+        Ok(true) => {},
+        Ok(false) => return,
+        Err(e) => {
+            problems.error(subject, e, None);
+            return;
+        },
+    }
+
+    let statement = match read_statement(config, portfolio, statements_path) {
+        Ok(statement) => statement,
+        Err(e) => {
+            problems.error(subject, e, None);
+            return;
+        },
+    };
+
+    if !portfolio.assets.is_empty() {
+        let asset_allocation_symbols = portfolio.get_stock_symbols();
+
+        let mut missing_symbols: Vec<&str> = statement.open_positions.keys()
+            .filter(|symbol| !asset_allocation_symbols.contains(*symbol))
+            .map(String::as_str).collect();
+        missing_symbols.sort_unstable();
+
+        if !missing_symbols.is_empty() {
+            problems.warning(subject, format!(
+                "The portfolio contains stocks which are missing in asset allocation configuration: {}",
+                missing_symbols.join(", ")), Some(s!("Add them to `assets` to be able to rebalance the portfolio")));
+        }
+    }
+
+    if let Some(quotes) = quotes {
+        if let Err(e) = statement.batch_quotes(quotes).and_then(|_| quotes.execute()) {
+            problems.error(subject, e, Some(s!(concat!(
+                "Check quotes providers configuration or specify the quotes manually in `quotes.static` ",
+                "configuration section"))));
+        }
+    }
+
+    if check_dividends {
+        let history = Moex::new("https://iss.moex.com", "TQBR", config.proxy.as_ref(), config.retry.as_ref());
+
+        match statement.validate_dividends(&history) {
+            Ok(discrepancies) => for discrepancy in discrepancies {
+                problems.warning(subject, discrepancy, Some(s!(concat!(
+                    "Check that all broker statements are in place and compare the dividend with the broker's ",
+                    "report"))));
+            },
+            Err(e) => problems.error(subject, format!("Dividends validation has failed: {}", e), None),
+        }
+    }
+}
+
+// Returns false if broker statements can't be merged due to the found problems
+fn check_statements(
+    config: &Config, portfolio: &PortfolioConfig, statements_path: &str, subject: &str, problems: &mut Problems,
+) -> GenericResult<bool> {
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+    let periods = BrokerStatement::read_periods(&broker, statements_path, portfolio.get_tax_remapping()?)?;
+    let mut mergeable = true;
+
+    for (severity, message, hint) in check_periods(broker.statements_merging_strategy, &periods, time::today()) {
+        mergeable &= severity != Severity::Error;
+        problems.add(severity, subject, message, hint.map(|hint| format!("{} to {:?}", hint, statements_path)));
+    }
+
+    Ok(mergeable)
+}
+
+fn read_statement(config: &Config, portfolio: &PortfolioConfig, statements_path: &str) -> GenericResult<BrokerStatement> {
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    BrokerStatement::read(
+        broker, statements_path, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
+        &portfolio.corporate_actions, ReadingStrictness::empty(), StatementSections::all())
+}
+
+fn check_periods(
+    strategy: StatementsMergingStrategy, periods: &[Period], today: Date,
+) -> Vec<(Severity, String, Option<&'static str>)> {
+    let mut problems = Vec::new();
+
+    let last_date = match periods.last() {
+        Some(period) => period.last_date(),
+        None => return problems,
+    };
+
+    for pair in periods.windows(2) {
+        let (first, second) = (pair[0], pair[1]);
+
+        if second.first_date() <= first.last_date() {
+            problems.push((Severity::Error, format!(
+                "Overlapping broker statements: {}, {}", first.format(), second.format(),
+            ), Some("Remove duplicate broker statements from the statements directory and save the full ones")));
+        } else if strategy.validate(first, second, last_date).is_err() {
+            let missing = Period::new(first.next_date(), second.prev_date()).unwrap();
+            problems.push((Severity::Error, format!(
+                "Missing broker statements for {}", missing.format(),
+            ), Some("Download broker statements for the missing period and save them")));
+        }
+    }
+
+    let age = (today - last_date).num_days();
+    if age > STATEMENT_MAX_AGE_DAYS {
+        problems.push((Severity::Warning, format!(
+            "The latest broker statement is {} days old", age,
+        ), Some("Download the latest broker statement and save it")));
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn period(first: Date, last: Date) -> Period {
+        Period::new(first, last).unwrap()
+    }
+
+    #[test]
+    fn periods_checking() {
+        let periods = [
+            period(date!(2020, 1, 1), date!(2020, 12, 31)),
+            period(date!(2021, 1, 1), date!(2021, 6, 30)),
+            period(date!(2021, 9, 1), date!(2021, 12, 31)),
+            period(date!(2021, 12, 1), date!(2022, 1, 31)),
+        ];
+
+        let problems = check_periods(StatementsMergingStrategy::ContinuousOnly, &periods, date!(2022, 2, 1));
+        let messages: Vec<_> = problems.iter().map(|(severity, message, _)| (*severity, message.as_str())).collect();
+        assert_eq!(messages, vec![
+            (Severity::Error, "Missing broker statements for 01.07.2021 - 31.08.2021"),
+            (Severity::Error, "Overlapping broker statements: 01.09.2021 - 31.12.2021, 01.12.2021 - 31.01.2022"),
+        ]);
+
+        let problems = check_periods(StatementsMergingStrategy::Sparse, &periods[..3], date!(2022, 3, 1));
+        let messages: Vec<_> = problems.iter().map(|(severity, message, _)| (*severity, message.as_str())).collect();
+        assert_eq!(messages, vec![
+            (Severity::Warning, "The latest broker statement is 60 days old"),
+        ]);
+    }
+}
@@ -0,0 +1,133 @@
+//! Structured errors which can be handled programmatically by library users.
+//!
+//! All fallible functions return [GenericError], so the errors are boxed into it and may be extracted using [chain()]
+//! which also looks through error sources.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use thiserror::Error as ThisError;
+
+use crate::core::GenericError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Statement(#[from] StatementError),
+
+    #[error(transparent)]
+    Quotes(#[from] QuotesError),
+
+    #[error(transparent)]
+    Tax(#[from] TaxError),
+}
+
+#[derive(Debug, ThisError)]
+pub enum ConfigError {
+    #[error("Error while reading {path:?} configuration file: {source}")]
+    Read {path: String, source: GenericError},
+
+    #[error("{0:?} portfolio is not defined in the configuration file")]
+    UnknownPortfolio(String),
+}
+
+#[derive(Debug, ThisError)]
+pub enum StatementError {
+    #[error("{0:?} doesn't contain any broker statement")]
+    NotFound(String),
+
+    #[error("Error while reading {path:?} broker statement: {source}")]
+    Read {path: String, source: GenericError},
+}
+
+#[derive(Debug, ThisError)]
+pub enum QuotesError {
+    #[error("Failed to get quotes from {provider}: {source}")]
+    Provider {provider: String, source: GenericError},
+
+    #[error("Failed to get historical quotes from {provider}: {source}")]
+    HistoricalProvider {provider: String, source: GenericError},
+
+    #[error("Unable to find quotes for following symbols: {}", .0.join(", "))]
+    NotFound(Vec<String>),
+}
+
+#[derive(Debug, ThisError)]
+pub enum TaxError {
+    #[error("Error while reading {path:?} tax statement: {source}")]
+    Read {path: PathBuf, source: GenericError},
+
+    #[error("Tax statement year ({actual}) doesn't match the requested year {expected}")]
+    YearMismatch {actual: i32, expected: i32},
+
+    #[error("Failed to process {income}: {source}")]
+    Income {income: IncomeKind, source: GenericError},
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IncomeKind {
+    Trading,
+    Dividends,
+    Interest,
+    Coupons,
+    Derivatives,
+    BankInterest,
+    ControlledForeignCompanies,
+    Other,
+}
+
+impl fmt::Display for IncomeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IncomeKind::Trading => "income from stock trading",
+            IncomeKind::Dividends => "dividend income",
+            IncomeKind::Interest => "income from idle cash interest",
+            IncomeKind::Coupons => "bond coupon income",
+            IncomeKind::Derivatives => "income from derivatives trading",
+            IncomeKind::BankInterest => "foreign bank interest income",
+            IncomeKind::ControlledForeignCompanies => "controlled foreign companies profit",
+            IncomeKind::Other => "other income",
+        })
+    }
+}
+
+/// Returns all structured errors from the error's source chain starting from the outermost one
+pub fn chain(error: &GenericError) -> impl Iterator<Item = &Error> {
+    let mut next: Option<&(dyn std::error::Error + 'static)> = Some(error.as_ref());
+
+    std::iter::from_fn(move || {
+        while let Some(error) = next {
+            next = error.source();
+            if let Some(error) = error.downcast_ref::<Error>() {
+                return Some(error);
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_chain() {
+        let error: GenericError = Error::from(TaxError::Income {
+            income: IncomeKind::Trading,
+            source: Error::from(QuotesError::NotFound(vec![s!("AAPL"), s!("VTI")])).into(),
+        }).into();
+
+        assert_eq!(error.to_string(), concat!(
+            "Failed to process income from stock trading: ",
+            "Unable to find quotes for following symbols: AAPL, VTI"));
+
+        let errors: Vec<_> = chain(&error).collect();
+        assert!(matches!(errors[..], [
+            Error::Tax(TaxError::Income {income: IncomeKind::Trading, ..}),
+            Error::Quotes(QuotesError::NotFound(_)),
+        ]));
+    }
+}
@@ -1,16 +1,20 @@
 use std::ops::Add;
 
-use chrono::Duration;
+use chrono::{Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
 
-use crate::time::{self, Date, DateOptTime};
+use crate::time::{self, Date, DateOptTime, DateTime};
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum Exchange {
     Moex,
     Spb,
     Us,
     Otc,
     Other,
+    Crypto,
 }
 
 impl Exchange {
@@ -22,6 +26,17 @@ impl Exchange {
         TradingMode(1)
     }
 
+    // Holidays aren't taken into account
+    pub fn trading_hours(self) -> Option<TradingHours> {
+        Some(match self {
+            // Morning, main and evening sessions on weekdays + weekend sessions
+            Exchange::Moex => TradingHours::new(chrono_tz::Europe::Moscow, (6, 50), (23, 50))
+                .with_weekend_sessions(date!(2025, 3, 1), (10, 0), (19, 0)),
+            Exchange::Us => TradingHours::new(chrono_tz::America::New_York, (9, 30), (16, 0)),
+            Exchange::Spb | Exchange::Otc | Exchange::Other | Exchange::Crypto => return None,
+        })
+    }
+
     pub fn min_last_working_day(self, today: Date) -> Date {
         // Experimentally deduced timeout. Originally was smaller, but for example in 2022 when FinEx
         // ETF have been suspended, MOEX returned their price, but with day delay, so for example during
@@ -66,6 +81,96 @@ impl Exchanges {
     }
 }
 
+type TradingSession = (NaiveTime, NaiveTime);
+
+pub struct TradingHours {
+    timezone: Tz,
+    weekdays: TradingSession,
+    weekends: Option<(Date, TradingSession)>,
+}
+
+impl TradingHours {
+    fn new(timezone: Tz, open: (u32, u32), close: (u32, u32)) -> TradingHours {
+        TradingHours {
+            timezone,
+            weekdays: TradingHours::session(open, close),
+            weekends: None,
+        }
+    }
+
+    fn with_weekend_sessions(mut self, since: Date, open: (u32, u32), close: (u32, u32)) -> TradingHours {
+        self.weekends = Some((since, TradingHours::session(open, close)));
+        self
+    }
+
+    fn session(open: (u32, u32), close: (u32, u32)) -> TradingSession {
+        (
+            NaiveTime::from_hms_opt(open.0, open.1, 0).unwrap(),
+            NaiveTime::from_hms_opt(close.0, close.1, 0).unwrap(),
+        )
+    }
+
+    fn get_session(&self, date: Date) -> Option<TradingSession> {
+        match date.weekday() {
+            Weekday::Sat | Weekday::Sun => self.weekends
+                .and_then(|(since, session)| (date >= since).then_some(session)),
+            _ => Some(self.weekdays),
+        }
+    }
+
+    /// Returns time of the last trading session close (in UTC) or `None` if the exchange is open now
+    pub fn last_close(&self, now: DateTime) -> Option<DateTime> {
+        let now = self.timezone.from_utc_datetime(&now);
+
+        let mut date = now.date_naive();
+        let mut session = self.get_session(date);
+
+        match session {
+            Some((open, close)) if now.time() >= open => {
+                if now.time() < close {
+                    return None;
+                }
+            },
+            _ => {
+                date = date.pred_opt().unwrap();
+                session = self.get_session(date);
+            },
+        }
+
+        let close = loop {
+            if let Some((_, close)) = session {
+                break close;
+            }
+            date = date.pred_opt().unwrap();
+            session = self.get_session(date);
+        };
+
+        let close = self.timezone.from_local_datetime(&date.and_time(close)).earliest()?;
+        Some(close.with_timezone(&Utc).naive_utc())
+    }
+
+    /// Returns the number of trading sessions which have been opened after the specified time (both times are in UTC)
+    pub fn sessions_since(&self, time: DateTime, now: DateTime) -> usize {
+        let mut date = self.timezone.from_utc_datetime(&time).date_naive();
+        let today = self.timezone.from_utc_datetime(&now).date_naive();
+        let mut sessions = 0;
+
+        while date <= today {
+            if let Some((open, _)) = self.get_session(date) {
+                let open = self.timezone.from_local_datetime(&date.and_time(open)).earliest()
+                    .map(|open| open.with_timezone(&Utc).naive_utc());
+
+                if open.is_some_and(|open| time < open && open <= now) {
+                    sessions += 1;
+                }
+            }
+            date = date.succ_opt().unwrap();
+        }
+
+        sessions
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct TradingMode(u8);
 
@@ -77,4 +182,44 @@ impl TradingMode {
 
 pub fn today_trade_conclusion_time() -> DateOptTime {
     time::now().into()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(now, expected,
+        // Friday, before the session
+        case(date!(2024, 11, 1).and_hms_opt(13, 0, 0).unwrap(), Some(date!(2024, 10, 31).and_hms_opt(20, 0, 0).unwrap())),
+        // Friday, the session is open
+        case(date!(2024, 11, 1).and_hms_opt(14, 0, 0).unwrap(), None),
+        // Friday, after the session
+        case(date!(2024, 11, 1).and_hms_opt(21, 0, 0).unwrap(), Some(date!(2024, 11, 1).and_hms_opt(20, 0, 0).unwrap())),
+        // Monday morning (after DST switch)
+        case(date!(2024, 11, 4).and_hms_opt(10, 0, 0).unwrap(), Some(date!(2024, 11, 1).and_hms_opt(20, 0, 0).unwrap())),
+        // Monday, the session is open
+        case(date!(2024, 11, 4).and_hms_opt(20, 59, 0).unwrap(), None),
+    )]
+    fn trading_hours(now: DateTime, expected: Option<DateTime>) {
+        let trading_hours = Exchange::Us.trading_hours().unwrap();
+        assert_eq!(trading_hours.last_close(now), expected);
+    }
+
+    #[rstest(now, expected,
+        // Saturday, before weekend sessions introduction
+        case(date!(2024, 11, 2).and_hms_opt(9, 0, 0).unwrap(), Some(date!(2024, 11, 1).and_hms_opt(20, 50, 0).unwrap())),
+        // Saturday, the weekend session is open
+        case(date!(2025, 3, 8).and_hms_opt(9, 0, 0).unwrap(), None),
+        // Saturday, after the weekend session
+        case(date!(2025, 3, 8).and_hms_opt(17, 0, 0).unwrap(), Some(date!(2025, 3, 8).and_hms_opt(16, 0, 0).unwrap())),
+        // Monday, before the morning session
+        case(date!(2025, 3, 10).and_hms_opt(2, 0, 0).unwrap(), Some(date!(2025, 3, 9).and_hms_opt(16, 0, 0).unwrap())),
+        // Monday, the evening session is open
+        case(date!(2025, 3, 10).and_hms_opt(20, 0, 0).unwrap(), None),
+    )]
+    fn weekend_sessions(now: DateTime, expected: Option<DateTime>) {
+        let trading_hours = Exchange::Moex.trading_hours().unwrap();
+        assert_eq!(trading_hours.last_close(now), expected);
+    }
+}
@@ -0,0 +1,89 @@
+use static_table_derive::StaticTable;
+
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::db;
+use crate::forex;
+use crate::quotes::{ForexQuoteSource, QuoteQuery, Quotes};
+use crate::telemetry::TelemetryRecordBuilder;
+
+/// Requests today's quotes of the specified (or configured for metrics) currency pairs bypassing the quotes cache and
+/// prints which provider has returned each quote and how it has been derived.
+pub fn audit(config: &Config, pairs: &[String]) -> GenericResult<TelemetryRecordBuilder> {
+    let pairs: Vec<String> = if pairs.is_empty() {
+        config.metrics.currency_rates.iter().cloned().collect()
+    } else {
+        pairs.to_vec()
+    };
+
+    if pairs.is_empty() {
+        return Err!(concat!(
+            "No currency pairs are specified: pass them as command arguments or configure them in ",
+            "metrics.currency_rates"));
+    }
+
+    for pair in &pairs {
+        forex::parse_currency_pair(pair)?;
+    }
+
+    let database = db::connect(&config.db_path)?;
+    let quotes = Quotes::new_uncached(config, database)?;
+
+    quotes.batch_all(pairs.iter().map(|pair| QuoteQuery::Forex(pair.to_owned())))?;
+
+    let mut table = Table::new();
+
+    for pair in pairs {
+        let rate = quotes.get(QuoteQuery::Forex(pair.clone()))?;
+        let source = quotes.get_forex_source(&pair);
+
+        table.add_row(Row {
+            provider: source.map(|source| source.provider).unwrap_or("Cache"),
+            path: source.map(format_path).unwrap_or_else(|| s!("unknown")),
+            pair, rate,
+        });
+    }
+
+    table.print("Currency rates");
+
+    Ok(TelemetryRecordBuilder::new())
+}
+
+fn format_path(source: ForexQuoteSource) -> String {
+    match (source.reversed, source.cross_currency) {
+        (false, None) => s!("direct"),
+        (true, None) => s!("reversed"),
+        (false, Some(currency)) => format!("cross via {}", currency),
+        (true, Some(currency)) => format!("reversed cross via {}", currency),
+    }
+}
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Pair")]
+    pair: String,
+    #[column(name="Rate")]
+    rate: Cash,
+    #[column(name="Provider")]
+    provider: &'static str,
+    #[column(name="Path")]
+    path: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(reversed, cross_currency, expected,
+        case(false, None, "direct"),
+        case(true, None, "reversed"),
+        case(false, Some("RUB"), "cross via RUB"),
+        case(true, Some("RUB"), "reversed cross via RUB"),
+    )]
+    fn path_formatting(reversed: bool, cross_currency: Option<&'static str>, expected: &str) {
+        let source = ForexQuoteSource {provider: "CBR", reversed, cross_currency};
+        assert_eq!(format_path(source), expected);
+    }
+}
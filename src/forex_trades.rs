@@ -0,0 +1,226 @@
+use chrono::Datelike;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, ForexTrade, ReadingStrictness, StatementSections};
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::db;
+use crate::formatting::table::Cell;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::Date;
+use crate::types::Decimal;
+use crate::util;
+
+/// Lists all currency conversions comparing the achieved rate to the CBR one, which shows how much is lost on broker
+/// forex spreads and commissions.
+pub fn generate_forex_report(
+    config: &Config, portfolio_name: &str, year: Option<i32>,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let country = config.get_tax_country();
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let statement = BrokerStatement::read(
+        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
+        &portfolio.corporate_actions, ReadingStrictness::empty(), StatementSections::all())?;
+
+    if let Some(year) = year {
+        statement.check_period_against_tax_year(year)?;
+    }
+
+    let database = db::connect(&config.db_path)?;
+    let converter = CurrencyConverter::new(database, None, true);
+
+    let conversions = analyse(&statement.forex_trades, country.currency, year, &converter)?;
+    if conversions.is_empty() {
+        println!("There are no currency conversions for the specified period.");
+        return Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker));
+    }
+
+    print(&conversions, country.currency, year);
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+struct Conversion {
+    date: Date,
+    from: Cash,
+    to: Cash,
+    commission: Cash,
+
+    pair: String,
+    rate: Decimal,
+    market_rate: Decimal,
+
+    volume: Decimal,
+    cost: Decimal,
+}
+
+fn analyse(
+    trades: &[ForexTrade], currency: &str, year: Option<i32>, converter: &CurrencyConverter,
+) -> GenericResult<Vec<Conversion>> {
+    let mut conversions = Vec::new();
+
+    for trade in trades {
+        let date = trade.conclusion_time.date;
+        if year.map(|year| date.year() != year).unwrap_or(false) {
+            continue;
+        }
+
+        // Express the rate as price of the foreign currency in the local one if possible
+        let (base, quote) = if trade.from.currency == currency {
+            (trade.to, trade.from)
+        } else {
+            (trade.from, trade.to)
+        };
+
+        if base.amount.is_zero() {
+            return Err!("Got an invalid forex trade: {} -> {}", trade.from, trade.to);
+        }
+
+        let volume = converter.convert_to(date, trade.from, currency)?;
+        let cost = volume
+            - converter.convert_to(date, trade.to, currency)?
+            + converter.convert_to(date, trade.commission, currency)?;
+
+        conversions.push(Conversion {
+            date,
+            from: trade.from,
+            to: trade.to,
+            commission: trade.commission,
+
+            pair: format!("{}/{}", base.currency, quote.currency),
+            rate: quote.amount / base.amount,
+            market_rate: converter.precise_currency_rate(date, base.currency, quote.currency)?,
+
+            volume, cost,
+        });
+    }
+
+    Ok(conversions)
+}
+
+#[derive(StaticTable)]
+#[table(name="ForexTradesTable")]
+struct Row {
+    #[column(name="Date")]
+    date: Date,
+    #[column(name="Sold")]
+    from: Cash,
+    #[column(name="Bought")]
+    to: Cash,
+    #[column(name="Commission")]
+    commission: Option<Cash>,
+    #[column(name="Pair")]
+    pair: String,
+    #[column(name="Rate")]
+    rate: Decimal,
+    #[column(name="CBR rate")]
+    market_rate: Decimal,
+    #[column(name="Cost")]
+    cost: Cash,
+    #[column(name="Cost ratio")]
+    cost_ratio: Option<Cell>,
+    #[column(name="Total cost")]
+    total_cost: Cash,
+}
+
+fn print(conversions: &[Conversion], currency: &str, year: Option<i32>) {
+    let round_rate = |rate| util::round(rate, 4);
+    let mut table = ForexTradesTable::new();
+
+    let mut total_volume = dec!(0);
+    let mut total_cost = dec!(0);
+
+    for conversion in conversions {
+        total_volume += conversion.volume;
+        total_cost += conversion.cost;
+
+        table.add_row(Row {
+            date: conversion.date,
+            from: conversion.from,
+            to: conversion.to,
+            commission: (!conversion.commission.is_zero()).then_some(conversion.commission),
+            pair: conversion.pair.clone(),
+            rate: round_rate(conversion.rate),
+            market_rate: round_rate(conversion.market_rate),
+            cost: Cash::new(currency, conversion.cost).round(),
+            cost_ratio: ratio(conversion.cost, conversion.volume),
+            total_cost: Cash::new(currency, total_cost).round(),
+        });
+    }
+
+    let mut totals = table.add_empty_row();
+    totals.set_cost(Cash::new(currency, total_cost).round());
+    if let Some(ratio) = ratio(total_cost, total_volume) {
+        totals.set_cost_ratio(ratio);
+    }
+
+    table.print(&match year {
+        Some(year) => format!("Currency conversions for {}", year),
+        None => s!("Currency conversions"),
+    });
+}
+
+fn ratio(cost: Decimal, volume: Decimal) -> Option<Cell> {
+    (!volume.is_zero()).then(|| Cell::new_ratio(cost / volume))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::EmptyResult;
+    use crate::currency::converter::CurrencyConverterBackend;
+    use crate::time::DateOptTime;
+
+    use super::*;
+
+    struct Backend;
+
+    impl CurrencyConverterBackend for Backend {
+        fn today(&self) -> Date {
+            date!(2024, 1, 1)
+        }
+
+        fn batch(&self, _from: &str, _to: &str, _date: Date) -> EmptyResult {
+            Ok(())
+        }
+
+        fn currency_rate(
+            &self, from: &str, to: &str, _date: Date,
+        ) -> GenericResult<(Option<Decimal>, Option<Decimal>)> {
+            Ok(match (from, to) {
+                ("USD", "RUB") => (Some(dec!(90)), None),
+                ("RUB", "USD") => (None, Some(dec!(90))),
+                _ => unreachable!(),
+            })
+        }
+    }
+
+    #[test]
+    fn analysis() {
+        let converter = CurrencyConverter::new_with_backend(Box::new(Backend));
+
+        let (rub, usd) = (|amount| Cash::new("RUB", amount), |amount| Cash::new("USD", amount));
+        let trade = |date, from, to, commission| ForexTrade::new(
+            DateOptTime::new_max_time(date), from, to, commission);
+
+        let trades = [
+            trade(date!(2022, 12, 30), rub(dec!(10_000)), usd(dec!(100)), rub(dec!(0))),
+            trade(date!(2023, 3, 1), rub(dec!(9_200)), usd(dec!(100)), rub(dec!(10))),
+            trade(date!(2023, 6, 1), usd(dec!(50)), rub(dec!(4_400)), usd(dec!(0.1))),
+        ];
+
+        let conversions = analyse(&trades, "RUB", Some(2023), &converter).unwrap();
+        let conversions: Vec<_> = conversions.iter().map(|conversion| (
+            conversion.date, conversion.pair.as_str(), conversion.rate, conversion.market_rate, conversion.cost,
+        )).collect();
+
+        assert_eq!(conversions, vec![
+            (date!(2023, 3, 1), "USD/RUB", dec!(92), dec!(90), dec!(210)),
+            (date!(2023, 6, 1), "USD/RUB", dec!(88), dec!(90), dec!(109)),
+        ]);
+    }
+}
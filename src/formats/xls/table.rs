@@ -2,10 +2,11 @@ use std::borrow::Cow;
 
 use itertools::Itertools;
 use lazy_static::lazy_static;
-use log::trace;
+use log::{trace, warn};
 use regex::{self, Regex, RegexBuilder};
 
 use crate::core::GenericResult;
+use crate::util;
 
 use super::{SheetReader, Cell, is_empty_row};
 
@@ -41,7 +42,9 @@ pub fn read_table<T: TableRow + TableReader>(sheet: &mut SheetReader) -> Generic
 
     trace!("Reading {} table starting from #{} row...", std::any::type_name::<T>(), sheet.next_human_row_id());
 
-    let mut columns_mapping = match map_columns(sheet.next_row_checked()?, &columns, T::trim_column_title) {
+    let hierarchical = columns.iter().any(|column| column.parent.is_some());
+
+    let mut columns_mapping = match read_header(sheet, &columns, hierarchical, T::trim_column_title) {
         Ok(mapping) => mapping,
         Err(err) => {
             if T::next_row(sheet).is_none() && !sheet.parse_empty_tables() {
@@ -51,14 +54,33 @@ pub fn read_table<T: TableRow + TableReader>(sheet: &mut SheetReader) -> Generic
             return Err(err);
         },
     };
+    check_layout::<T>(&columns_mapping, &columns);
 
     while let Some(row) = T::next_row(sheet) {
-        if repeatable_table_column_titles {
-            if let Ok(new_mapping) = map_columns(row, &columns, T::trim_column_title) {
-                columns_mapping = new_mapping;
-                continue;
+        let row: Cow<[Cell]> = if repeatable_table_column_titles && hierarchical {
+            let parent_row = row.to_vec();
+
+            if let Some(row) = sheet.next_row() {
+                if let Ok(new_mapping) = map_hierarchical_columns(Some(&parent_row), row, &columns, T::trim_column_title) {
+                    columns_mapping = new_mapping;
+                    check_layout::<T>(&columns_mapping, &columns);
+                    continue;
+                }
+                sheet.step_back();
             }
-        }
+
+            parent_row.into()
+        } else {
+            if repeatable_table_column_titles {
+                if let Ok(new_mapping) = map_columns(row, &columns, T::trim_column_title) {
+                    columns_mapping = new_mapping;
+                    check_layout::<T>(&columns_mapping, &columns);
+                    continue;
+                }
+            }
+            row.into()
+        };
+        let row = row.as_ref();
 
         if <T as TableRow>::skip_row(row) {
             continue;
@@ -75,8 +97,37 @@ pub fn read_table<T: TableRow + TableReader>(sheet: &mut SheetReader) -> Generic
     Ok(table)
 }
 
+// Columns which aren't known to the parser may contain information which affects the meaning of the known ones, so
+// don't ignore them silently
+fn check_layout<T: TableReader>(mapping: &ColumnsMapping, columns: &[TableColumn]) {
+    let name = std::any::type_name::<T>();
+
+    if mapping.extra_columns.is_empty() {
+        trace!("{} table layout: {}.", name, mapping.describe(columns));
+    } else {
+        warn!("{} table has unexpected columns which are ignored: {}. Table layout: {}.",
+            name, mapping.extra_columns.iter().map(|(_, title)| format!("{:?}", title)).join(", "),
+            mapping.describe(columns));
+    }
+}
+
+// Hierarchical headers occupy two rows: the first one contains parent titles (merged cells which span their
+// subcolumns, so the value is stored only in the leftmost cell) and titles of ordinary columns (merged vertically),
+// the second one contains child titles.
+fn read_header(
+    sheet: &mut SheetReader, columns: &[TableColumn], hierarchical: bool, trim_title: fn(&str) -> Cow<str>,
+) -> GenericResult<ColumnsMapping> {
+    if !hierarchical {
+        return map_columns(sheet.next_row_checked()?, columns, trim_title);
+    }
+
+    let parent_row = sheet.next_row_checked()?.to_vec();
+    map_hierarchical_columns(Some(&parent_row), sheet.next_row_checked()?, columns, trim_title)
+}
+
 pub struct TableColumn {
     name: &'static str,
+    parent: Option<&'static str>,
     regex: bool,
     aliases: &'static [&'static str],
     case_insensitive: bool,
@@ -86,34 +137,63 @@ pub struct TableColumn {
 
 impl TableColumn {
     pub fn new(
-        name: &'static str, regex: bool, aliases: &'static [&'static str],
+        name: &'static str, parent: Option<&'static str>, regex: bool, aliases: &'static [&'static str],
         case_insensitive: bool, space_insensitive: bool, optional: bool
     ) -> TableColumn {
-        TableColumn {name, regex, aliases, case_insensitive, space_insensitive, optional}
+        TableColumn {name, parent, regex, aliases, case_insensitive, space_insensitive, optional}
     }
 
-    fn find(&self, row: &[Cell], trim_title: fn(&str) -> Cow<str>) -> GenericResult<Option<usize>> {
+    fn title(&self) -> Cow<'static, str> {
+        match self.parent {
+            Some(parent) => format!("{} / {}", parent, self.name).into(),
+            None => self.name.into(),
+        }
+    }
+
+    fn find(
+        &self, row: &[Cell], parents: Option<&[Option<String>]>, trim_title: fn(&str) -> Cow<str>,
+    ) -> GenericResult<Option<usize>> {
+        if self.parent.is_some() && parents.is_none() {
+            return Err!("{:?} column requires a hierarchical table header", self.title());
+        }
+
         for (cell_id, cell) in row.iter().enumerate() {
             match cell {
                 Cell::String(value) => {
-                    return if self.matches(&trim_title(value))? {
+                    let parent = parents.and_then(|parents| parents[cell_id].as_deref());
+
+                    return if self.matches(&trim_title(value))? && self.matches_parent(parent, trim_title) {
                         Ok(Some(cell_id))
                     } else if self.optional {
                         Ok(None)
                     } else {
-                        Err!("Unable to find {:?} column - got {:?} instead", self.name, value)
+                        Err!("Unable to find {:?} column - got {:?} instead", self.title(), match parent {
+                            Some(parent) => format!("{} / {}", parent, value),
+                            None => value.clone(),
+                        })
                     };
                 },
                 Cell::Empty => {}
                 _ => return Err!(
-                    "Unable to find {:?} column - got an unexpected {:?} cell", self.name, cell),
+                    "Unable to find {:?} column - got an unexpected {:?} cell", self.title(), cell),
             };
         }
 
         if self.optional {
             Ok(None)
         } else {
-            Err!("The table has no {:?} column", self.name)
+            Err!("The table has no {:?} column", self.title())
+        }
+    }
+
+    fn matches_parent(&self, parent: Option<&str>, trim_title: fn(&str) -> Cow<str>) -> bool {
+        match (self.parent, parent) {
+            (Some(expected), Some(actual)) => {
+                let actual = trim_title(actual);
+                self.transform_for_matching(util::fold_spaces(&actual)) == self.transform_for_matching(expected)
+            },
+            (None, None) => true,
+            _ => false,
         }
     }
 
@@ -180,6 +260,7 @@ impl TableColumn {
 
 pub struct ColumnsMapping {
     mapping: Vec<Option<usize>>,
+    extra_columns: Vec<(usize, String)>,
 }
 
 impl ColumnsMapping {
@@ -193,39 +274,36 @@ impl ColumnsMapping {
 
     pub fn map<'a>(&self, row: &'a[Cell]) -> GenericResult<Vec<Option<&'a Cell>>> {
         let mut mapped_row = Vec::with_capacity(self.mapping.len());
-        let mut current_cell_id = 0;
 
         for column_id in 0..self.mapping.len() {
-            let mapped_cell_id = match self.map_id(row, column_id)? {
-                Some(cell_id) => cell_id,
-                None => {
-                    mapped_row.push(None);
-                    continue;
-                },
-            };
-
-            if current_cell_id < mapped_cell_id {
-                let spare_cells = &row[current_cell_id..mapped_cell_id];
-                if !is_empty_row(spare_cells) {
-                    return Err!(
-                        "The row contains non-empty cells between column cells: {:?}", spare_cells);
-                }
-            } else {
-                assert_eq!(current_cell_id, mapped_cell_id);
-            }
-
-            mapped_row.push(Some(&row[mapped_cell_id]));
-            current_cell_id = mapped_cell_id + 1;
+            mapped_row.push(self.map_id(row, column_id)?.map(|cell_id| &row[cell_id]));
         }
 
-        let spare_cells = &row[current_cell_id..];
-        if !is_empty_row(spare_cells) {
-            return Err!("The row contains non-empty cells after column cells: {:?}", spare_cells);
+        let spare_cells = row.iter().enumerate().filter(|&(cell_id, cell)| {
+            !matches!(cell, Cell::Empty) &&
+                !self.mapping.contains(&Some(cell_id)) &&
+                !self.extra_columns.iter().any(|&(extra_cell_id, _)| extra_cell_id == cell_id)
+        }).map(|(_, cell)| cell).collect_vec();
+
+        if !spare_cells.is_empty() {
+            return Err!("The row contains non-empty cells outside of table columns: {:?}", spare_cells);
         }
 
         Ok(mapped_row)
     }
 
+    /// Returns a human-readable description of the detected table layout
+    pub fn describe(&self, columns: &[TableColumn]) -> String {
+        let mut cells = self.mapping.iter().zip(columns).filter_map(|(cell_id, column)| {
+            cell_id.map(|cell_id| (cell_id, column.title().into_owned()))
+        }).chain(self.extra_columns.iter().map(|(cell_id, title)| {
+            (*cell_id, format!("{} (ignored)", title))
+        })).collect_vec();
+
+        cells.sort_by_key(|&(cell_id, _)| cell_id);
+        cells.iter().map(|(cell_id, title)| format!("#{}: {:?}", cell_id + 1, title)).join(", ")
+    }
+
     fn map_id(&self, row: &[Cell], column_id: usize) -> GenericResult<Option<usize>> {
         let cell_id = match self.mapping[column_id] {
             Some(cell_id) => cell_id,
@@ -240,14 +318,39 @@ impl ColumnsMapping {
     }
 }
 
-pub fn map_columns(mut row: &[Cell], columns: &[TableColumn], trim_title: fn(&str) -> Cow<str>) -> GenericResult<ColumnsMapping> {
+pub fn map_columns(row: &[Cell], columns: &[TableColumn], trim_title: fn(&str) -> Cow<str>) -> GenericResult<ColumnsMapping> {
+    map_hierarchical_columns(None, row, columns, trim_title)
+}
+
+pub fn map_hierarchical_columns(
+    parent_row: Option<&[Cell]>, row: &[Cell], columns: &[TableColumn], trim_title: fn(&str) -> Cow<str>,
+) -> GenericResult<ColumnsMapping> {
+    let (row, parents) = match parent_row {
+        Some(parent_row) => {
+            let (row, parents) = flatten_hierarchical_header(parent_row, row)?;
+            (Cow::Owned(row), Some(parents))
+        },
+        None => (Cow::Borrowed(row), None),
+    };
+
+    // Statement formats drift over time: columns get inserted and reordered. So we try the strict positional mapping
+    // first (it resolves ambiguous column names in the most natural way) and fall back to name-driven mapping.
+    map_columns_by_position(&row, parents.as_deref(), columns, trim_title).or_else(|err| {
+        map_columns_by_name(&row, parents.as_deref(), columns, trim_title).map_err(|_| err)
+    })
+}
+
+fn map_columns_by_position(
+    mut row: &[Cell], mut parents: Option<&[Option<String>]>, columns: &[TableColumn], trim_title: fn(&str) -> Cow<str>,
+) -> GenericResult<ColumnsMapping> {
     let mut mapping = Vec::with_capacity(columns.len());
     let mut offset = 0;
 
     for column in columns {
-        let cell_id = match column.find(row, trim_title)? {
+        let cell_id = match column.find(row, parents, trim_title)? {
             Some(index) => {
                 row = &row[index + 1..];
+                parents = parents.map(|parents| &parents[index + 1..]);
                 let cell_id = offset + index;
                 offset += index + 1;
                 Some(cell_id)
@@ -268,5 +371,174 @@ pub fn map_columns(mut row: &[Cell], columns: &[TableColumn], trim_title: fn(&st
             }).format(", "))
     }
 
-    Ok(ColumnsMapping { mapping })
-}
\ No newline at end of file
+    Ok(ColumnsMapping {
+        mapping,
+        extra_columns: Vec::new(),
+    })
+}
+
+fn map_columns_by_name(
+    row: &[Cell], parents: Option<&[Option<String>]>, columns: &[TableColumn], trim_title: fn(&str) -> Cow<str>,
+) -> GenericResult<ColumnsMapping> {
+    let mut mapping = Vec::with_capacity(columns.len());
+    let mut used = vec![false; row.len()];
+
+    for column in columns {
+        let mut cell_id = None;
+
+        for (id, cell) in row.iter().enumerate() {
+            if used[id] {
+                continue;
+            }
+
+            if let Cell::String(value) = cell {
+                let parent = parents.and_then(|parents| parents[id].as_deref());
+                if column.matches(&trim_title(value))? && column.matches_parent(parent, trim_title) {
+                    cell_id.replace(id);
+                    break;
+                }
+            }
+        }
+
+        match cell_id {
+            Some(id) => used[id] = true,
+            None if column.optional => {},
+            None => return Err!("The table has no {:?} column", column.title()),
+        }
+
+        mapping.push(cell_id);
+    }
+
+    let mut extra_columns = Vec::new();
+
+    for (cell_id, cell) in row.iter().enumerate() {
+        if used[cell_id] {
+            continue;
+        }
+
+        match cell {
+            Cell::Empty => {},
+            Cell::String(title) => extra_columns.push((cell_id, match parents.and_then(|parents| parents[cell_id].as_ref()) {
+                Some(parent) => format!("{} / {}", parent, title),
+                None => title.clone(),
+            })),
+            _ => return Err!("Got an unexpected {:?} cell in table header", cell),
+        }
+    }
+
+    Ok(ColumnsMapping {mapping, extra_columns})
+}
+
+// Merges two header rows into one: subcolumns get their child titles and the title of the parent which spans them,
+// ordinary columns get their titles from the parent row.
+fn flatten_hierarchical_header(parent_row: &[Cell], row: &[Cell]) -> GenericResult<(Vec<Cell>, Vec<Option<String>>)> {
+    let size = std::cmp::max(parent_row.len(), row.len());
+
+    let mut titles = Vec::with_capacity(size);
+    let mut parents = Vec::with_capacity(size);
+    let mut current_parent: Option<&String> = None;
+
+    for cell_id in 0..size {
+        let parent_cell = parent_row.get(cell_id).unwrap_or(&Cell::Empty);
+        let cell = row.get(cell_id).unwrap_or(&Cell::Empty);
+
+        let parent_title = match parent_cell {
+            Cell::String(value) => Some(value),
+            Cell::Empty => None,
+            _ => return Err!("Got an unexpected {:?} cell in table header", parent_cell),
+        };
+
+        match cell {
+            Cell::Empty => {
+                current_parent = None;
+                titles.push(parent_title.map(|title| Cell::String(title.clone())).unwrap_or(Cell::Empty));
+                parents.push(None);
+            },
+            Cell::String(_) => {
+                if parent_title.is_some() {
+                    current_parent = parent_title;
+                }
+
+                let parent = current_parent.ok_or_else(|| format!(
+                    "Got {:?} subcolumn without parent column", cell))?;
+
+                titles.push(cell.clone());
+                parents.push(Some(parent.clone()));
+            },
+            _ => return Err!("Got an unexpected {:?} cell in table header", cell),
+        }
+    }
+
+    Ok((titles, parents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hierarchical_header() {
+        let columns = [
+            TableColumn::new("Дата", None, false, &[], false, false, false),
+            TableColumn::new("RUB", Some("Сумма"), false, &[], false, false, false),
+            TableColumn::new("USD", Some("Сумма"), false, &[], false, false, false),
+            TableColumn::new("Комментарий", None, false, &[], false, false, false),
+        ];
+
+        let string = |value: &str| Cell::String(value.to_owned());
+        let parent_row = [string("Дата"), Cell::Empty, string("Сумма"), Cell::Empty, string("Комментарий")];
+        let row = [Cell::Empty, Cell::Empty, string("RUB"), string("USD"), Cell::Empty];
+
+        let mapping = map_hierarchical_columns(Some(&parent_row), &row, &columns, trim_title).unwrap();
+        assert_eq!(mapping.mapping, [Some(0), Some(2), Some(3), Some(4)]);
+
+        let row = [Cell::Empty, Cell::Empty, string("USD"), string("RUB"), Cell::Empty];
+        let mapping = map_hierarchical_columns(Some(&parent_row), &row, &columns, trim_title).unwrap();
+        assert_eq!(mapping.mapping, [Some(0), Some(3), Some(2), Some(4)]);
+
+        let parent_row = [string("Дата"), Cell::Empty, string("Итого"), Cell::Empty, string("Комментарий")];
+        let row = [Cell::Empty, Cell::Empty, string("RUB"), string("USD"), Cell::Empty];
+        assert!(map_hierarchical_columns(Some(&parent_row), &row, &columns, trim_title).is_err());
+
+        assert!(map_columns(&parent_row, &columns, trim_title).is_err());
+    }
+
+    #[test]
+    fn columns_reordering() {
+        let columns = [
+            TableColumn::new("Дата", None, false, &[], false, false, false),
+            TableColumn::new("Сумма", None, false, &[], false, false, false),
+            TableColumn::new("Валюта", None, false, &[], false, false, true),
+            TableColumn::new("Комментарий", None, false, &[], false, false, false),
+        ];
+
+        let string = |value: &str| Cell::String(value.to_owned());
+
+        let row = [string("Дата"), Cell::Empty, string("Сумма"), string("Комментарий")];
+        let mapping = map_columns(&row, &columns, trim_title).unwrap();
+        assert_eq!(mapping.mapping, [Some(0), Some(2), None, Some(3)]);
+        assert!(mapping.extra_columns.is_empty());
+
+        let row = [string("Дата"), string("Комментарий"), string("Сумма"), string("Валюта")];
+        let mapping = map_columns(&row, &columns, trim_title).unwrap();
+        assert_eq!(mapping.mapping, [Some(0), Some(2), Some(3), Some(1)]);
+        assert!(mapping.extra_columns.is_empty());
+
+        let row = [string("Дата"), string("Код"), string("Сумма"), string("Комментарий")];
+        let mapping = map_columns(&row, &columns, trim_title).unwrap();
+        assert_eq!(mapping.mapping, [Some(0), Some(2), None, Some(3)]);
+        assert_eq!(mapping.describe(&columns), r#"#1: "Дата", #2: "Код (ignored)", #3: "Сумма", #4: "Комментарий""#);
+        assert_eq!(mapping.extra_columns, [(1, s!("Код"))]);
+
+        let row = [string("01.01.2024"), string("X"), Cell::Float(1.0), Cell::Empty];
+        assert_eq!(mapping.map(&row).unwrap().len(), columns.len());
+        assert!(mapping.map(&[string("01.01.2024"), Cell::Empty, Cell::Float(1.0), Cell::Empty, string("X")]).is_err());
+
+        let row = [string("Дата"), string("Комментарий")];
+        assert!(map_columns(&row, &columns, trim_title).is_err());
+    }
+
+    fn trim_title(title: &str) -> Cow<str> {
+        title.into()
+    }
+}
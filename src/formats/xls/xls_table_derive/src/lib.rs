@@ -48,12 +48,16 @@ fn table_row_derive_impl(format: Format, input: TokenStream) -> GenericResult<To
 
     let columns_code = columns.iter().map(|column| {
         let name = &column.name;
+        let parent = match column.parent {
+            Some(ref parent) => quote!(Some(#parent)),
+            None => quote!(None),
+        };
         let regex = column.regex;
         let case_insensitive = table.case_insensitive_match;
         let space_insensitive = table.space_insensitive_match;
         let optional = column.optional;
         let aliases = column.aliases.iter().map(|alias| quote!(#alias));
-        quote!(#mod_ident::TableColumn::new(#name, #regex, &[#(#aliases,)*], #case_insensitive, #space_insensitive, #optional))
+        quote!(#mod_ident::TableColumn::new(#name, #parent, #regex, &[#(#aliases,)*], #case_insensitive, #space_insensitive, #optional))
     });
 
     let columns_parse_code = columns.iter().enumerate().map(|(id, column)| {
@@ -72,7 +76,15 @@ fn table_row_derive_impl(format: Format, input: TokenStream) -> GenericResult<To
             #parse_code.map_err(|e| format!("Column {:?}: {}", #name, e))?
         };
 
-        let parser_code = if column.optional {
+        let mut parser_code = if let Some(ref default) = column.default {
+            let default = default.parse::<proc_macro2::TokenStream>().unwrap();
+            quote! {
+                match row[#id] {
+                    Some(cell) if !matches!(cell, #mod_ident::Cell::Empty) => #parse_code,
+                    _ => #default,
+                }
+            }
+        } else if column.optional {
             quote! {
                 match row[#id] {
                     Some(cell) => #parse_code,
@@ -88,6 +100,17 @@ fn table_row_derive_impl(format: Format, input: TokenStream) -> GenericResult<To
             }
         };
 
+        if let Some(ref validate_func) = column.validate_with {
+            let validate_func = validate_func.parse::<proc_macro2::TokenStream>().unwrap();
+            parser_code = quote! {
+                {
+                    let value = #parser_code;
+                    #validate_func(&value).map_err(|e| format!("Column {:?}: {}", #name, e))?;
+                    value
+                }
+            };
+        }
+
         quote! {
             #field: #parser_code
         }
@@ -191,10 +214,13 @@ impl syn::parse::Parser for TableParamsParser {
 struct Column {
     field: String,
     name: String,
+    parent: Option<String>,
     regex: bool,
     aliases: Vec<String>,
     strict: Option<bool>,
     parse_with: Option<String>,
+    default: Option<String>,
+    validate_with: Option<String>,
     optional: bool,
 }
 
@@ -241,10 +267,13 @@ impl Column {
             columns.push(Column {
                 field: field_ident.to_string(),
                 name: column_params.name,
+                parent: column_params.parent,
                 regex: column_params.regex,
                 aliases: aliases,
                 strict: column_params.strict,
                 parse_with: column_params.parse_with,
+                default: column_params.default,
+                validate_with: column_params.validate_with,
                 optional: column_params.optional,
             })
         }
@@ -257,6 +286,8 @@ impl Column {
 struct ColumnParams {
     name: String,
     #[darling(default)]
+    parent: Option<String>,
+    #[darling(default)]
     regex: bool,
     #[darling(default)]
     alias: Option<String>,
@@ -266,6 +297,12 @@ struct ColumnParams {
     strict: Option<bool>,
     #[darling(default)]
     parse_with: Option<String>,
+    // An expression which is used for missing optional columns and empty cells
+    #[darling(default)]
+    default: Option<String>,
+    // A function with `fn(&T) -> EmptyResult` signature which validates the parsed value
+    #[darling(default)]
+    validate_with: Option<String>,
     #[darling(default)]
     optional: bool,
 }
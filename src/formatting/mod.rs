@@ -1,7 +1,52 @@
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
 use crate::time::{DateTime, DateOptTime};
+use crate::types::Decimal;
 
 pub mod table;
 
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Number and currency formatting conventions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    /// 1,234.56 and $1,234.56
+    #[default]
+    En,
+    /// 1 234,56 and 1 234,56 $
+    Ru,
+}
+
+impl Locale {
+    fn group_separator(self) -> char {
+        match self {
+            Locale::En => ',',
+            Locale::Ru => ' ',
+        }
+    }
+
+    fn decimal_separator(self) -> char {
+        match self {
+            Locale::En => '.',
+            Locale::Ru => ',',
+        }
+    }
+}
+
+/// Sets the locale which is used to format all numbers and amounts. Must be called once on startup.
+pub fn configure(locale: Locale) {
+    if LOCALE.set(locale).is_err() {
+        panic!("An attempt to configure the formatting twice");
+    }
+}
+
+fn get_locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
 pub fn format_date<T>(date: T) -> String where T: Into<DateOptTime> {
     let date = date.into();
 
@@ -22,4 +67,132 @@ pub fn untitle(string: &str) -> String {
     }
 
     result
-}
\ No newline at end of file
+}
+
+/// Formats the number according to the configured locale: 1234.5 -> 1,234.5
+pub fn format_number<T: ToString>(number: T) -> String {
+    format_number_with(get_locale(), &number.to_string())
+}
+
+/// Rounds the quantity to the specified minimum increment for displaying: fractional share quantities may have
+/// excessive precision after corporate actions or proportional calculations.
+pub fn round_quantity(quantity: Decimal, increment: Decimal) -> Decimal {
+    ((quantity / increment).round() * increment).normalize()
+}
+
+/// Formats the quantity rounded to the specified minimum increment: 1234.56789, 0.01 -> 1,234.57
+pub fn format_quantity(quantity: Decimal, increment: Decimal) -> String {
+    format_number(round_quantity(quantity, increment))
+}
+
+/// Formats the amount according to the configured locale: USD, 1234.5 -> $1,234.5
+pub fn format_currency(currency: &str, amount: &str) -> String {
+    format_currency_with(get_locale(), currency, amount)
+}
+
+fn format_number_with(locale: Locale, number: &str) -> String {
+    let (sign, number) = match number.strip_prefix(['-', '+']) {
+        Some(stripped) => (&number[..1], stripped),
+        None => ("", number),
+    };
+
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+
+    if !integer.bytes().all(|c| c.is_ascii_digit()) {
+        return format!("{}{}", sign, number);
+    }
+
+    let mut result = String::with_capacity(sign.len() + number.len() + integer.len() / 3);
+    result.push_str(sign);
+
+    for (index, digit) in integer.chars().enumerate() {
+        if index != 0 && (integer.len() - index) % 3 == 0 {
+            result.push(locale.group_separator());
+        }
+        result.push(digit);
+    }
+
+    if let Some(fraction) = fraction {
+        result.push(locale.decimal_separator());
+        result.push_str(fraction);
+    }
+
+    result
+}
+
+fn format_currency_with(locale: Locale, currency: &str, amount: &str) -> String {
+    let amount = format_number_with(locale, amount);
+
+    let prefix = match currency {
+        "AUD" => Some("AU$"),
+        "CNY" => Some("¥"),
+        "EUR" => Some("€"),
+        "GBP" => Some("£"),
+        "USD" => Some("$"),
+        _ => None,
+    };
+
+    let suffix = match currency {
+        "HKD" => "HK$",
+        "RUB" => "₽",
+        _ => currency,
+    };
+
+    match (locale, prefix) {
+        (Locale::En, Some(prefix)) => {
+            let (sign, amount) = match amount.strip_prefix(['-', '+']) {
+                Some(stripped) => (&amount[..1], stripped),
+                None => ("", amount.as_str()),
+            };
+            format!("{}{}{}", sign, prefix, amount)
+        },
+        (Locale::En, None) if currency == "RUB" => format!("{}{}", amount, suffix),
+        (_, prefix) => format!("{} {}", amount, prefix.unwrap_or(suffix)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(number, en, ru,
+        case("0", "0", "0"),
+        case("123", "123", "123"),
+        case("1234", "1,234", "1 234"),
+        case("-1234567.891", "-1,234,567.891", "-1 234 567,891"),
+        case("+123456.7", "+123,456.7", "+123 456,7"),
+    )]
+    fn number_formatting(number: &str, en: &str, ru: &str) {
+        assert_eq!(format_number_with(Locale::En, number), en);
+        assert_eq!(format_number_with(Locale::Ru, number), ru);
+    }
+
+    #[rstest(quantity, increment, expected,
+        case(dec!(10), dec!(1), dec!(10)),
+        case(dec!(0.33333333333), dec!(0.0001), dec!(0.3333)),
+        case(dec!(2.5000), dec!(0.0001), dec!(2.5)),
+        case(dec!(-1.23456789), dec!(0.000001), dec!(-1.234568)),
+        case(dec!(7.3), dec!(0.5), dec!(7.5)),
+    )]
+    fn quantity_rounding(quantity: Decimal, increment: Decimal, expected: Decimal) {
+        let rounded = round_quantity(quantity, increment);
+        assert_eq!(rounded, expected);
+        assert_eq!(rounded.to_string(), expected.normalize().to_string());
+    }
+
+    #[rstest(currency, amount, en, ru,
+        case("USD", "-1234.5", "-$1,234.5", "-1 234,5 $"),
+        case("EUR", "+1234", "+€1,234", "+1 234 €"),
+        case("RUB", "1234.56", "1,234.56₽", "1 234,56 ₽"),
+        case("HKD", "1234", "1,234 HK$", "1 234 HK$"),
+        case("CHF", "-1234", "-1,234 CHF", "-1 234 CHF"),
+    )]
+    fn currency_formatting(currency: &str, amount: &str, en: &str, ru: &str) {
+        assert_eq!(format_currency_with(Locale::En, currency, amount), en);
+        assert_eq!(format_currency_with(Locale::Ru, currency, amount), ru);
+    }
+}
@@ -1,9 +1,13 @@
+use chrono::Datelike;
 use num_traits::ToPrimitive;
 use prettytable::{Table as RawTable, Row as RawRow, Cell as RawCell, Attr};
 use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
-use separator::Separatable;
+use rust_xlsxwriter::{ExcelDateTime, Format, Worksheet};
+use serde_json::json;
 
-use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::core::EmptyResult;
+use crate::currency::{self, Cash, MultiCurrencyCashAccount};
+use crate::profiling::{self, Phase};
 use crate::types::{Date, Decimal};
 use crate::util;
 
@@ -54,6 +58,7 @@ impl Table {
     }
 
     pub fn print(&self, title: &str) {
+        let _timer = profiling::start(Phase::Rendering);
         let mut table = RawTable::new();
         let mut columns = Vec::new();
         let mut titles = Vec::new();
@@ -82,6 +87,94 @@ impl Table {
 
         print_table(title, &table);
     }
+
+    /// Writes the table to the worksheet preserving numeric and date values, so they remain usable in spreadsheets
+    pub fn export_xlsx(&self, sheet: &mut Worksheet, title: &str) -> EmptyResult {
+        let bold_format = Format::new().set_bold();
+        let date_format = Format::new().set_num_format("dd.mm.yyyy");
+        let ratio_format = Format::new().set_num_format("0.0%");
+        let wrapped_format = Format::new().set_text_wrap();
+
+        sheet.write_string_with_format(0, 0, title, &bold_format)?;
+
+        let columns: Vec<usize> = self.columns.iter().enumerate()
+            .filter_map(|(index, column)| (!column.hidden).then_some(index))
+            .collect();
+
+        let mut row_id = 2;
+
+        if self.show_titles {
+            for (column_id, &index) in columns.iter().enumerate() {
+                sheet.write_string_with_format(row_id, column_id as u16, self.columns[index].name, &bold_format)?;
+            }
+            row_id += 1;
+        }
+
+        for row in &self.rows {
+            for (column_id, &index) in columns.iter().enumerate() {
+                let column_id = column_id as u16;
+                let cell = &row[index];
+
+                match cell.value {
+                    Some(Value::Number(value)) => {
+                        sheet.write_number(row_id, column_id, value.to_f64().unwrap())?;
+                    },
+                    Some(Value::Cash(amount)) => {
+                        let format = Format::new().set_num_format(format!("#,##0.00 \"{}\"", amount.currency));
+                        let amount = currency::round(amount.amount).to_f64().unwrap();
+                        sheet.write_number_with_format(row_id, column_id, amount, &format)?;
+                    },
+                    Some(Value::Ratio(ratio)) => {
+                        sheet.write_number_with_format(row_id, column_id, ratio.to_f64().unwrap(), &ratio_format)?;
+                    },
+                    Some(Value::Date(date)) => {
+                        let date = ExcelDateTime::from_ymd(date.year() as u16, date.month() as u8, date.day() as u8)?;
+                        sheet.write_datetime_with_format(row_id, column_id, date, &date_format)?;
+                    },
+                    None if cell.text.contains('\n') => {
+                        sheet.write_string_with_format(row_id, column_id, &cell.text, &wrapped_format)?;
+                    },
+                    None if !cell.text.is_empty() => {
+                        sheet.write_string(row_id, column_id, &cell.text)?;
+                    },
+                    None => {},
+                }
+            }
+            row_id += 1;
+        }
+
+        sheet.autofit();
+        Ok(())
+    }
+
+    /// Exports the table as JSON preserving full precision of numeric values, so it can be processed by other programs
+    pub fn export_json(&self) -> serde_json::Value {
+        let columns: Vec<usize> = self.columns.iter().enumerate()
+            .filter_map(|(index, column)| (!column.hidden).then_some(index))
+            .collect();
+
+        let rows: Vec<serde_json::Value> = self.rows.iter().map(|row| {
+            columns.iter().map(|&index| {
+                let cell = &row[index];
+
+                match cell.value {
+                    Some(Value::Number(value) | Value::Ratio(value)) => json!(value.normalize()),
+                    Some(Value::Cash(amount)) => json!({
+                        "amount": amount.amount.normalize(),
+                        "currency": amount.currency,
+                    }),
+                    Some(Value::Date(date)) => json!(date.format("%Y-%m-%d").to_string()),
+                    None if cell.text.is_empty() => serde_json::Value::Null,
+                    None => json!(cell.text),
+                }
+            }).collect()
+        }).collect();
+
+        json!({
+            "columns": columns.iter().map(|&index| self.columns[index].name.replace('\n', " ")).collect::<Vec<_>>(),
+            "rows": rows,
+        })
+    }
 }
 
 fn print_table(title: &str, table: &RawTable) {
@@ -126,13 +219,27 @@ pub type Row = Vec<Cell>;
 
 pub struct Cell {
     text: String,
+    value: Option<Value>,
     default_alignment: Alignment,
     style: Option<Style>,
 }
 
+// Original typed value of the cell which is used on export to spreadsheets
+#[derive(Clone, Copy)]
+enum Value {
+    Number(Decimal),
+    Cash(Cash),
+    Ratio(Decimal),
+    Date(Date),
+}
+
 impl Cell {
     fn new(text: String, default_alignment: Alignment) -> Cell {
-        Cell {text, default_alignment, style: None}
+        Cell {text, value: None, default_alignment, style: None}
+    }
+
+    fn new_with_value(text: String, value: Value, default_alignment: Alignment) -> Cell {
+        Cell {text, value: Some(value), default_alignment, style: None}
     }
 
     pub fn new_empty() -> Cell {
@@ -140,11 +247,18 @@ impl Cell {
     }
 
     pub fn new_ratio(ratio: Decimal) -> Cell {
-        Cell::new(format!("{}%", util::round(ratio * dec!(100), 1)), Alignment::RIGHT)
+        let text = format!("{}%", super::format_number(util::round(ratio * dec!(100), 1)));
+        Cell::new_with_value(text, Value::Ratio(ratio), Alignment::RIGHT)
+    }
+
+    /// Displays the quantity rounded to the minimum increment, but keeps its full precision for export
+    pub fn new_quantity(quantity: Decimal, increment: Decimal) -> Cell {
+        Cell::new_with_value(super::format_quantity(quantity, increment), Value::Number(quantity), Alignment::RIGHT)
     }
 
     pub fn new_round_decimal(value: Decimal) -> Cell {
-        Cell::new(value.to_i64().unwrap().separated_string(), Alignment::RIGHT)
+        let value = value.to_i64().unwrap();
+        Cell::new_with_value(super::format_number(value), Value::Number(value.into()), Alignment::RIGHT)
     }
 
     pub fn style(&mut self, style: Style) -> &mut Cell {
@@ -171,7 +285,7 @@ macro_rules! impl_from_number_to_cell {
     ($T:ty) => {
         impl From<$T> for Cell {
             fn from(value: $T) -> Cell {
-                Cell::new(value.to_string(), Alignment::RIGHT)
+                Cell::new_with_value(value.to_string(), Value::Number(value.into()), Alignment::RIGHT)
             }
         }
     };
@@ -179,7 +293,12 @@ macro_rules! impl_from_number_to_cell {
 impl_from_number_to_cell!(i32);
 impl_from_number_to_cell!(u32);
 impl_from_number_to_cell!(usize);
-impl_from_number_to_cell!(Decimal);
+
+impl From<Decimal> for Cell {
+    fn from(value: Decimal) -> Cell {
+        Cell::new_with_value(super::format_number(value), Value::Number(value), Alignment::RIGHT)
+    }
+}
 
 impl From<bool> for Cell {
     fn from(value: bool) -> Cell {
@@ -214,13 +333,13 @@ impl<T: Into<Cell>> From<Option<T>> for Cell {
 
 impl From<Date> for Cell {
     fn from(date: Date) -> Cell {
-        Cell::new(super::format_date(date), Alignment::CENTER)
+        Cell::new_with_value(super::format_date(date), Value::Date(date), Alignment::CENTER)
     }
 }
 
 impl From<Cash> for Cell {
     fn from(amount: Cash) -> Cell {
-        Cell::new(amount.to_string(), Alignment::RIGHT)
+        Cell::new_with_value(amount.to_string(), Value::Cash(amount), Alignment::RIGHT)
     }
 }
 
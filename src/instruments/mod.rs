@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet, hash_map::Entry};
+pub mod reference;
+mod security_id;
+
+use std::collections::{BTreeSet, HashMap, HashSet, hash_map::Entry};
 use std::default::Default;
 use std::fmt::{self, Display};
 
@@ -16,6 +19,9 @@ use crate::exchanges::Exchanges;
 use crate::localities::Jurisdiction;
 use crate::time::Date;
 
+use self::reference::SecurityInfo;
+pub use self::security_id::SecurityId;
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum InstrumentId {
     Symbol(String),
@@ -40,6 +46,10 @@ impl Display for InstrumentId {
 pub struct InstrumentInternalIds(HashMap<String, String>);
 
 impl InstrumentInternalIds {
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
+
     fn get_symbol(&self, id: &str) -> GenericResult<&str> {
         Ok(self.0.get(id).ok_or_else(|| format!(concat!(
             "Unable to determine stock symbol by its broker-specific internal ID ({}). ",
@@ -99,6 +109,10 @@ impl InstrumentInfo {
         self.instruments.get(symbol)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item=&Instrument> {
+        self.instruments.values()
+    }
+
     pub fn get_or_empty(&self, symbol: &str) -> MaybeOwned<Instrument> {
         match self.instruments.get(symbol) {
             Some(instrument) => MaybeOwned::Borrowed(instrument),
@@ -197,14 +211,14 @@ impl InstrumentInfo {
             // statements contain symbol <-> ISIN mapping, but new ones have only ISIN (since it's considered as
             // an OTC stock).
 
-            let Ok(isin) = parse_isin(symbol) else {
+            let SecurityId::Isin(isin) = SecurityId::parse(symbol) else {
                 continue;
             };
 
             let mut real_symbol = None;
 
             for instrument in self.instruments.values() {
-                if instrument.isin.contains(&isin) && parse_isin(&instrument.symbol).is_err() {
+                if instrument.isin.contains(&isin) && SecurityId::parse(&instrument.symbol).is_symbol() {
                     if let Some(other_symbol) = real_symbol.replace(instrument.symbol.clone()) {
                         debug!(concat!(
                             "Do not provide {isin} -> {other_symbol} automatic symbol remapping: ",
@@ -224,6 +238,60 @@ impl InstrumentInfo {
         rules
     }
 
+    pub fn suggest_internal_id_symbols(&self, id: &str) -> Vec<String> {
+        // Broker-specific internal IDs are typically issuer names with security type suffix ("ROS AGRO PLC-GDR",
+        // "ГАЗПРОМ-ао-2"), so match them against instrument names and choose the best matches.
+
+        let id_words = get_words(id.split('-').next().unwrap());
+        let mut best_score = 0;
+        let mut candidates = Vec::new();
+
+        for instrument in self.instruments.values() {
+            let Some(ref name) = instrument.name else {
+                continue;
+            };
+
+            let name_words = get_words(name);
+            let score = id_words.iter().filter(|id_word| {
+                name_words.iter().any(|name_word| name_word.starts_with(id_word.as_str()))
+            }).count();
+
+            if score == 0 || score < best_score {
+                continue;
+            } else if score > best_score {
+                best_score = score;
+                candidates.clear();
+            }
+
+            candidates.push(instrument);
+        }
+
+        let mut symbols = BTreeSet::new();
+
+        for instrument in candidates {
+            if SecurityId::parse(&instrument.symbol).is_symbol() {
+                symbols.insert(instrument.symbol.clone());
+                continue;
+            }
+
+            // The instrument has lost its symbol in newer statements, so find the real symbol by ISIN in the older ones
+            let mut resolved = false;
+
+            for other in self.instruments.values() {
+                if SecurityId::parse(&other.symbol).is_symbol() && !other.isin.is_disjoint(&instrument.isin) {
+                    symbols.insert(other.symbol.clone());
+                    resolved = true;
+                }
+            }
+
+            if !resolved {
+                symbols.insert(instrument.symbol.clone());
+            }
+        }
+
+        symbols.into_iter().collect()
+    }
+
     pub fn remap(&mut self, old_symbol: &str, new_symbol: &str) -> EmptyResult {
         let Some(mut old_info) = self.instruments.remove(old_symbol) else {
             return Ok(());
@@ -233,8 +301,8 @@ impl InstrumentInfo {
             Entry::Occupied(mut entry) => {
                 let new_info = entry.get_mut();
 
-                match parse_isin(old_symbol) {
-                    Ok(isin) if new_info.isin.contains(&isin) => {
+                match SecurityId::parse(old_symbol) {
+                    SecurityId::Isin(isin) if new_info.isin.contains(&isin) => {
                         // Assuming the case when some stock became delisted, lost its symbol and we want to restore the
                         // original symbol back to merge the instruments which are actually the same.
                         new_info.merge(old_info, false)
@@ -276,6 +344,10 @@ pub struct Instrument {
     pub isin: HashSet<ISIN>,
     cusip: HashSet<CUSIP>,
     pub exchanges: Exchanges,
+
+    // Information from securities reference
+    country: Option<String>,
+    type_: Option<String>,
 }
 
 impl Instrument {
@@ -286,9 +358,16 @@ impl Instrument {
             isin:      HashSet::new(),
             cusip:     HashSet::new(),
             exchanges: Exchanges::new_empty(),
+
+            country:   None,
+            type_:     None,
         }
     }
 
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
     pub fn set_name(&mut self, name: &str) {
         self.name.replace(name.to_owned());
     }
@@ -301,6 +380,28 @@ impl Instrument {
         self.cusip.insert(cusip);
     }
 
+    /// Returns instrument type obtained from securities reference (its values are provider-specific)
+    pub fn type_(&self) -> Option<&str> {
+        self.type_.as_deref()
+    }
+
+    // Broker statements are the primary source of information, so reference information only fills the gaps
+    fn enrich(&mut self, info: SecurityInfo) {
+        debug!("{}: Got the following information from securities reference: {:?}.", self.symbol, info);
+
+        if self.name.is_none() {
+            self.name = info.name.or(info.issuer);
+        }
+
+        if self.country.is_none() {
+            self.country = info.country;
+        }
+
+        if self.type_.is_none() {
+            self.type_ = info.type_;
+        }
+    }
+
     pub fn get_taxation_type(&self, date: Date, broker_jurisdiction: Jurisdiction) -> GenericResult<IssuerTaxationType> {
         let russian_country_code = Jurisdiction::Russia.traits().code;
         let russian_brokers_are_full_tax_agents = date.year() >= 2024;
@@ -344,6 +445,13 @@ impl Instrument {
             }
         }
 
+        // Issuer's country from securities reference is used only when the broker statement has no information
+        if result_taxation_type.is_none() {
+            if let Some(ref country) = self.country {
+                result_taxation_type.replace(get_taxation_type(country));
+            }
+        }
+
         Ok(if let Some(taxation_type) = result_taxation_type {
             taxation_type
         } else if broker_jurisdiction == Jurisdiction::Russia {
@@ -367,6 +475,14 @@ impl Instrument {
         self.isin.extend(other.isin);
         self.cusip.extend(other.cusip);
         self.exchanges.merge(other.exchanges);
+
+        for (value, other) in [(&mut self.country, other.country), (&mut self.type_, other.type_)] {
+            if let Some(other) = other {
+                if value.is_none() || newer {
+                    value.replace(other);
+                }
+            }
+        }
     }
 }
 
@@ -392,6 +508,66 @@ pub enum IssuerTaxationType {
 
 pub const ISIN_REGEX: &str = r"[A-Z]{2}[A-Z0-9]{9}[0-9]";
 
+fn get_words(text: &str) -> Vec<String> {
+    text.split(|char: char| !char.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
 pub fn parse_isin(value: &str) -> GenericResult<ISIN> {
     Ok(value.parse().map_err(|_| format!("Invalid ISIN: {}", value))?)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_id_symbols_suggestion() {
+        let mut info = InstrumentInfo::new();
+        let mut add = |symbol: &str, name: &str, isin: &str| {
+            let instrument = info.add(symbol).unwrap();
+            instrument.set_name(name);
+            instrument.add_isin(parse_isin(isin).unwrap());
+        };
+
+        add("AGRO", "ROS AGRO PLC", "US7496552057");
+        add("US7496552057", "ROS AGRO PLC", "US7496552057");
+        add("GAZP", "ПАО \"Газпром\"", "RU0007661625");
+        add("GMKN", "ПАО \"ГМК \"Норильский никель\"", "RU0007288411");
+        add("ABBV", "AbbVie Inc.", "US00287Y1091");
+        add("MO", "Altria Group, Inc.", "US02209S1033");
+
+        assert_eq!(info.suggest_internal_id_symbols("ROS AGRO PLC-GDR"), vec![s!("AGRO")]);
+        assert_eq!(info.suggest_internal_id_symbols("ГАЗПРОМ-ао-2"), vec![s!("GAZP")]);
+        assert_eq!(info.suggest_internal_id_symbols("ABBVIE INC-ао"), vec![s!("ABBV")]);
+        assert_eq!(info.suggest_internal_id_symbols("INC-ао"), vec![s!("ABBV"), s!("MO")]);
+        assert!(info.suggest_internal_id_symbols("ЛУКОЙЛ-ао").is_empty());
+    }
+
+    #[test]
+    fn reference_country() {
+        let country = |instrument: &Instrument| -> Option<String> {
+            match instrument.get_taxation_type(date!(2023, 1, 1), Jurisdiction::Russia).ok()? {
+                IssuerTaxationType::Manual {country_code} => country_code,
+                IssuerTaxationType::TaxAgent {..} => None,
+            }
+        };
+
+        let mut instrument = Instrument::new("VTI");
+        assert_eq!(country(&instrument), None);
+
+        instrument.enrich(SecurityInfo {
+            country: Some(s!("US")),
+            type_: Some(s!("ETP")),
+            ..Default::default()
+        });
+        assert_eq!(instrument.type_(), Some("ETP"));
+        assert_eq!(country(&instrument), Some(s!("US")));
+
+        // Broker statement information has priority
+        instrument.add_isin(parse_isin("IE00B3XXRP09").unwrap());
+        assert_eq!(country(&instrument), Some(s!("IE")));
+    }
+}
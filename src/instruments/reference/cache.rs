@@ -0,0 +1,93 @@
+use std::ops::DerefMut;
+
+use chrono::Duration;
+use diesel::{self, prelude::*};
+#[cfg(test)] use tempfile::NamedTempFile;
+
+use crate::core::{GenericResult, EmptyResult};
+use crate::db::{self, schema::instrument_reference, models};
+use crate::time;
+
+use super::SecurityInfo;
+
+pub struct Cache {
+    db: db::Connection,
+    expire_time: Duration,
+}
+
+impl Cache {
+    pub fn new(connection: db::Connection) -> Cache {
+        Cache {
+            db: connection,
+            // Reference information changes very rarely
+            expire_time: Duration::days(30),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn new_temporary() -> (NamedTempFile, Cache) {
+        let (database, connection) = db::new_temporary();
+        (database, Cache::new(connection))
+    }
+
+    // Returns `Some(None)` for cached misses
+    pub fn get(&self, id: &str) -> GenericResult<Option<Option<SecurityInfo>>> {
+        let expire_time = time::now() - self.expire_time;
+
+        let result = instrument_reference::table
+            .select(instrument_reference::info)
+            .filter(instrument_reference::id.eq(id))
+            .filter(instrument_reference::time.gt(&expire_time))
+            .get_result::<Option<String>>(self.db.borrow().deref_mut()).optional()?;
+
+        Ok(match result {
+            Some(Some(info)) => Some(Some(serde_json::from_str(&info).map_err(|e| format!(
+                "Got an invalid {} instrument reference information from the database: {}", id, e))?)),
+            Some(None) => Some(None),
+            None => None,
+        })
+    }
+
+    pub fn save(&self, id: &str, info: Option<&SecurityInfo>) -> EmptyResult {
+        diesel::replace_into(instrument_reference::table)
+            .values(models::NewInstrumentReference {
+                id: id,
+                time: time::now(),
+                info: info.map(serde_json::to_string).transpose()?,
+            })
+            .execute(self.db.borrow().deref_mut())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache() {
+        let (_database, mut cache) = Cache::new_temporary();
+
+        let id = "isin:US9229087690";
+        let info = SecurityInfo {
+            name: Some(s!("Vanguard Total Stock Market ETF")),
+            type_: Some(s!("ETP")),
+            country: Some(s!("US")),
+            ..Default::default()
+        };
+        let missing_id = "symbol:UNKNOWN";
+
+        assert_eq!(cache.get(id).unwrap(), None);
+        assert_eq!(cache.get(missing_id).unwrap(), None);
+
+        cache.save(id, Some(&info)).unwrap();
+        cache.save(missing_id, None).unwrap();
+        assert_eq!(cache.get(id).unwrap(), Some(Some(info)));
+        assert_eq!(cache.get(missing_id).unwrap(), Some(None));
+
+        cache.expire_time = Duration::seconds(0);
+        assert_eq!(cache.get(id).unwrap(), None);
+        assert_eq!(cache.get(missing_id).unwrap(), None);
+    }
+}
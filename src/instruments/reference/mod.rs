@@ -0,0 +1,191 @@
+mod cache;
+mod moex;
+mod openfigi;
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::db;
+use crate::exchanges::{Exchange, Exchanges};
+use crate::network::{Proxy, RetryPolicy};
+
+use super::{InstrumentInfo, SecurityId};
+
+use self::cache::Cache;
+use self::moex::Moex;
+use self::openfigi::{OpenFigi, OpenFigiConfig};
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct InstrumentReferenceConfig {
+    #[serde(default)]
+    moex: bool,
+    openfigi: Option<OpenFigiConfig>,
+}
+
+/// Instrument information obtained from a securities reference
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct SecurityInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    // Issuer's country code (ISO 3166-1 alpha-2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+impl SecurityInfo {
+    fn merge(&mut self, other: SecurityInfo) {
+        let SecurityInfo {name, issuer, type_, country} = other;
+
+        for (value, other) in [
+            (&mut self.name, name),
+            (&mut self.issuer, issuer),
+            (&mut self.type_, type_),
+            (&mut self.country, country),
+        ] {
+            if value.is_none() {
+                *value = other;
+            }
+        }
+    }
+}
+
+pub fn enrich(config: &Config, instrument_info: &mut InstrumentInfo) {
+    let Some(reference_config) = config.instrument_reference.as_ref() else {
+        return;
+    };
+
+    let database = match db::connect(&config.db_path) {
+        Ok(database) => database,
+        Err(e) => {
+            warn!("Unable to use securities reference: {}.", e);
+            return;
+        },
+    };
+
+    if let Some(reference) = InstrumentReference::new(
+        reference_config, config.proxy.as_ref(), config.retry.as_ref(), database,
+    ) {
+        reference.enrich(instrument_info);
+    }
+}
+
+pub trait SecurityReferenceProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Returns the exchange which symbols the provider is able to look up
+    fn symbol_exchange(&self) -> Option<Exchange>;
+    fn get_info(&self, id: &SecurityId) -> GenericResult<Option<SecurityInfo>>;
+}
+
+pub struct InstrumentReference {
+    cache: Cache,
+    providers: Vec<Box<dyn SecurityReferenceProvider>>,
+}
+
+impl InstrumentReference {
+    pub fn new(
+        config: &InstrumentReferenceConfig, proxy: Option<&Proxy>, retry_policy: Option<&RetryPolicy>,
+        database: db::Connection,
+    ) -> Option<InstrumentReference> {
+        let mut providers: Vec<Box<dyn SecurityReferenceProvider>> = Vec::new();
+
+        if config.moex {
+            providers.push(Box::new(Moex::new("https://iss.moex.com", proxy, retry_policy)));
+        }
+
+        if let Some(ref config) = config.openfigi {
+            providers.push(Box::new(OpenFigi::new(config, Some(database.clone()))));
+        }
+
+        if providers.is_empty() {
+            return None;
+        }
+
+        Some(InstrumentReference {
+            cache: Cache::new(database),
+            providers,
+        })
+    }
+
+    // The integration is optional, so any errors are reported as warnings
+    pub fn enrich(&self, instrument_info: &mut InstrumentInfo) {
+        for instrument in instrument_info.instruments.values_mut() {
+            let isin = if instrument.isin.len() == 1 {
+                instrument.isin.iter().next().cloned()
+            } else {
+                None
+            };
+
+            let id = match isin {
+                Some(isin) => SecurityId::Isin(isin),
+                None if instrument.isin.is_empty() => SecurityId::parse(&instrument.symbol),
+                None => continue,
+            };
+
+            let info = match self.get_info(&id, &instrument.exchanges) {
+                Ok(Some(info)) => info,
+                Ok(None) => {
+                    debug!("There is no reference information for {}.", instrument.symbol);
+                    continue;
+                },
+                Err(e) => {
+                    warn!("Failed to get reference information for {}: {}.", instrument.symbol, e);
+                    continue;
+                },
+            };
+
+            instrument.enrich(info);
+        }
+    }
+
+    fn get_info(&self, id: &SecurityId, exchanges: &Exchanges) -> GenericResult<Option<SecurityInfo>> {
+        let mut providers: Vec<&dyn SecurityReferenceProvider> = self.providers.iter()
+            .map(|provider| provider.as_ref()).collect();
+
+        let cache_id = match id {
+            SecurityId::Symbol(symbol) => {
+                // Symbols are not unique across exchanges, so look them up only on the instrument's own exchanges
+                let exchanges = exchanges.get_prioritized();
+                providers.retain(|provider| provider.symbol_exchange().is_some_and(|exchange| {
+                    exchanges.contains(&exchange)
+                }));
+
+                if providers.is_empty() {
+                    return Ok(None);
+                }
+
+                let names: Vec<&str> = providers.iter().map(|provider| provider.name()).collect();
+                format!("symbol:{}:{}", names.join(","), symbol)
+            },
+            SecurityId::Isin(isin) => format!("isin:{}", isin),
+            SecurityId::Figi(figi) => format!("figi:{}", figi),
+        };
+
+        if let Some(info) = self.cache.get(&cache_id)? {
+            return Ok(info);
+        }
+
+        let mut result: Option<SecurityInfo> = None;
+
+        for provider in providers {
+            let info = provider.get_info(id).map_err(|e| format!(
+                "{}: {}", provider.name(), e))?;
+
+            if let Some(info) = info {
+                match result {
+                    Some(ref mut result) => result.merge(info),
+                    None => result = Some(info),
+                }
+            }
+        }
+
+        self.cache.save(&cache_id, result.as_ref())?;
+        Ok(result)
+    }
+}
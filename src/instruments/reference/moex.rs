@@ -0,0 +1,173 @@
+#[cfg(test)] use indoc::indoc;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::core::GenericResult;
+use crate::exchanges::Exchange;
+use crate::formats::xml;
+use crate::instruments::{SecurityId, parse_isin};
+use crate::network::{Client, Proxy, RetryPolicy};
+use crate::quotes::common::send_request;
+
+use super::{SecurityInfo, SecurityReferenceProvider};
+
+pub struct Moex {
+    url: String,
+    client: Client,
+}
+
+impl Moex {
+    pub fn new(url: &str, proxy: Option<&Proxy>, retry_policy: Option<&RetryPolicy>) -> Moex {
+        Moex {
+            url: url.to_owned(),
+            client: Client::new("Moscow Exchange", proxy, retry_policy),
+        }
+    }
+}
+
+impl SecurityReferenceProvider for Moex {
+    fn name(&self) -> &'static str {
+        "Moscow Exchange"
+    }
+
+    fn symbol_exchange(&self) -> Option<Exchange> {
+        Some(Exchange::Moex)
+    }
+
+    fn get_info(&self, id: &SecurityId) -> GenericResult<Option<SecurityInfo>> {
+        let query = match id {
+            SecurityId::Symbol(symbol) => symbol.to_string(),
+            SecurityId::Isin(isin) => isin.to_string(),
+            SecurityId::Figi(_) => return Ok(None),
+        };
+
+        let url = Url::parse_with_params(&format!("{}/iss/securities.xml", self.url), &[
+            ("q", query.as_str()),
+            ("iss.meta", "off"),
+        ])?;
+
+        let get = |url: &Url| -> GenericResult<Option<SecurityInfo>> {
+            let response = send_request(&self.client, url, None)?;
+            parse_securities(&response.bytes()?, id)
+        };
+
+        Ok(get(&url).map_err(|e| format!("Failed to get security information from {}: {}", url, e))?)
+    }
+}
+
+fn parse_securities(data: &[u8], id: &SecurityId) -> GenericResult<Option<SecurityInfo>> {
+    #[derive(Deserialize)]
+    struct Document {
+        data: Vec<Data>,
+    }
+
+    #[derive(Deserialize)]
+    struct Data {
+        id: String,
+
+        #[serde(rename = "rows")]
+        table: Table,
+    }
+
+    #[derive(Deserialize)]
+    struct Table {
+        #[serde(rename = "row", default)]
+        rows: Vec<Row>,
+    }
+
+    #[derive(Deserialize)]
+    struct Row {
+        secid: String,
+        name: Option<String>,
+        isin: Option<String>,
+        emitent_title: Option<String>,
+        #[serde(rename = "type")]
+        type_: Option<String>,
+    }
+
+    let document: Document = xml::deserialize(data)?;
+    let securities = document.data.into_iter()
+        .find(|data| data.id == "securities")
+        .ok_or("Unable to find securities info in server response")?;
+
+    // The search is fuzzy, so filter out the other securities
+    let security = securities.table.rows.into_iter().find(|row| match id {
+        SecurityId::Symbol(symbol) => row.secid == *symbol,
+        SecurityId::Isin(isin) => row.isin.as_deref() == Some(isin.to_string().as_str()),
+        SecurityId::Figi(_) => false,
+    });
+
+    let non_empty = |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+
+    let Some(security) = security else {
+        return Ok(None);
+    };
+
+    // ISIN prefix is issuer's country code
+    let country = match non_empty(security.isin) {
+        Some(isin) => Some(parse_isin(&isin)?.prefix().to_owned()),
+        None => None,
+    };
+
+    Ok(Some(SecurityInfo {
+        name: non_empty(security.name),
+        issuer: non_empty(security.emitent_title),
+        type_: non_empty(security.type_),
+        country,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Server;
+
+    use crate::instruments::parse_isin;
+
+    use super::*;
+
+    #[test]
+    fn security_info() {
+        let mut server = Server::new();
+        let client = Moex::new(&server.url(), None, None);
+
+        let _mock = server.mock("GET", "/iss/securities.xml?q=SBER&iss.meta=off")
+            .with_status(200)
+            .with_header("Content-Type", "application/xml; charset=utf-8")
+            .with_body(indoc!(r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <document>
+                    <data id="securities">
+                        <rows>
+                            <row id="2785" secid="SBERP" shortname="Сбербанк-п" name="Сбербанк России ПАО ап" isin="RU0009029557" emitent_title="Публичное акционерное общество &quot;Сбербанк России&quot;" type="preferred_share" />
+                            <row id="2784" secid="SBER" shortname="Сбербанк" name="Сбербанк России ПАО ао" isin="RU0009029540" emitent_title="Публичное акционерное общество &quot;Сбербанк России&quot;" type="common_share" />
+                        </rows>
+                    </data>
+                </document>
+            "#).trim())
+            .create();
+
+        assert_eq!(client.get_info(&SecurityId::Symbol(s!("SBER"))).unwrap(), Some(SecurityInfo {
+            name: Some(s!("Сбербанк России ПАО ао")),
+            issuer: Some(s!(r#"Публичное акционерное общество "Сбербанк России""#)),
+            type_: Some(s!("common_share")),
+            country: Some(s!("RU")),
+        }));
+
+        let _mock = server.mock("GET", "/iss/securities.xml?q=RU000A0JX0J2&iss.meta=off")
+            .with_status(200)
+            .with_header("Content-Type", "application/xml; charset=utf-8")
+            .with_body(indoc!(r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <document>
+                    <data id="securities">
+                        <rows>
+                        </rows>
+                    </data>
+                </document>
+            "#).trim())
+            .create();
+
+        let isin = parse_isin("RU000A0JX0J2").unwrap();
+        assert_eq!(client.get_info(&SecurityId::Isin(isin)).unwrap(), None);
+    }
+}
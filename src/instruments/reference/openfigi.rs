@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+#[cfg(test)] use indoc::indoc;
+use serde::{Deserialize, Serialize};
+
+use crate::core::GenericResult;
+use crate::exchanges::Exchange;
+use crate::db;
+use crate::instruments::SecurityId;
+use crate::network::{Client, Proxy, RetryPolicy};
+use crate::quotes::common::parse_response;
+use crate::rate_limiter::RateLimiter;
+use crate::secrets;
+
+use super::{SecurityInfo, SecurityReferenceProvider};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenFigiConfig {
+    #[serde(skip, default="OpenFigiConfig::default_url")]
+    url: String,
+    #[serde(default, deserialize_with = "secrets::deserialize_optional")]
+    api_key: Option<String>,
+    proxy: Option<Proxy>,
+    retry: Option<RetryPolicy>,
+}
+
+impl OpenFigiConfig {
+    fn default_url() -> String {
+        s!("https://api.openfigi.com")
+    }
+}
+
+pub struct OpenFigi {
+    url: String,
+    api_key: Option<String>,
+
+    client: Client,
+    rate_limiter: RateLimiter,
+}
+
+impl OpenFigi {
+    pub fn new(config: &OpenFigiConfig, database: Option<db::Connection>) -> OpenFigi {
+        // See https://www.openfigi.com/api#rate-limit
+        let mut rate_limiter = match config.api_key {
+            Some(_) => RateLimiter::new().with_limit(25, Duration::from_secs(6)),
+            None => RateLimiter::new().with_limit(25, Duration::from_secs(60)),
+        };
+
+        if let Some(database) = database {
+            rate_limiter = rate_limiter.persistent(database, "OpenFIGI");
+        }
+
+        OpenFigi {
+            url: config.url.clone(),
+            api_key: config.api_key.clone(),
+
+            client: Client::new("OpenFIGI", config.proxy.as_ref(), config.retry.as_ref()),
+            rate_limiter,
+        }
+    }
+}
+
+impl SecurityReferenceProvider for OpenFigi {
+    fn name(&self) -> &'static str {
+        "OpenFIGI"
+    }
+
+    fn symbol_exchange(&self) -> Option<Exchange> {
+        None
+    }
+
+    fn get_info(&self, id: &SecurityId) -> GenericResult<Option<SecurityInfo>> {
+        #[derive(Serialize)]
+        struct Job {
+            #[serde(rename = "idType")]
+            id_type: &'static str,
+            #[serde(rename = "idValue")]
+            id_value: String,
+        }
+
+        #[derive(Deserialize)]
+        struct JobResult {
+            #[serde(default)]
+            data: Vec<Instrument>,
+        }
+
+        #[derive(Deserialize)]
+        struct Instrument {
+            name: Option<String>,
+            #[serde(rename = "securityType")]
+            security_type: Option<String>,
+        }
+
+        let (id_type, isin) = match id {
+            SecurityId::Isin(isin) => ("ID_ISIN", Some(isin)),
+            SecurityId::Figi(_) => ("ID_BB_GLOBAL", None),
+            SecurityId::Symbol(_) => return Ok(None),
+        };
+
+        let url = format!("{}/v3/mapping", self.url);
+        let jobs = [Job {
+            id_type,
+            id_value: id.to_string(),
+        }];
+
+        let get = || -> GenericResult<Option<SecurityInfo>> {
+            self.rate_limiter.wait(&format!("request to {}", url))?;
+
+            let mut request = self.client.post(&url).json(&jobs);
+            if let Some(ref api_key) = self.api_key {
+                request = request.header("X-OPENFIGI-APIKEY", api_key);
+            }
+
+            let response = self.client.send(request)?;
+
+            let mut results: Vec<JobResult> = parse_response(&response.text()?)?;
+            if results.len() != jobs.len() {
+                return Err!("Got an unexpected number of results: {}", results.len());
+            }
+
+            // The same instrument is listed on several exchanges, but name and type are the same for all of them
+            let instrument = match results.pop().unwrap().data.into_iter().next() {
+                Some(instrument) => instrument,
+                None => return Ok(None),
+            };
+
+            Ok(Some(SecurityInfo {
+                name: instrument.name,
+                issuer: None,
+                type_: instrument.security_type,
+                country: isin.map(|isin| isin.prefix().to_owned()),
+            }))
+        };
+
+        Ok(get().map_err(|e| format!("Failed to get security information from {}: {}", url, e))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Matcher, Server};
+
+    use crate::instruments::parse_isin;
+
+    use super::*;
+
+    #[test]
+    fn security_info() {
+        let mut server = Server::new();
+        let client = OpenFigi::new(&OpenFigiConfig {
+            url: server.url(),
+            api_key: Some(s!("mock")),
+            proxy: None,
+            retry: None,
+        }, None);
+
+        let _mock = server.mock("POST", "/v3/mapping")
+            .match_header("X-OPENFIGI-APIKEY", "mock")
+            .match_body(Matcher::JsonString(s!(r#"[{"idType": "ID_ISIN", "idValue": "US9229087690"}]"#)))
+            .with_status(200)
+            .with_body(indoc!(r#"
+                [{
+                    "data": [{
+                        "figi": "BBG000HS77T5",
+                        "name": "VANGUARD TOTAL STOCK MKT ETF",
+                        "ticker": "VTI",
+                        "exchCode": "US",
+                        "compositeFIGI": "BBG000HS77T5",
+                        "securityType": "ETP",
+                        "marketSector": "Equity",
+                        "shareClassFIGI": "BBG001T7R9Z9",
+                        "securityType2": "Mutual Fund",
+                        "securityDescription": "VTI"
+                    }]
+                }]
+            "#))
+            .create();
+
+        let isin = parse_isin("US9229087690").unwrap();
+        assert_eq!(client.get_info(&SecurityId::Isin(isin)).unwrap(), Some(SecurityInfo {
+            name: Some(s!("VANGUARD TOTAL STOCK MKT ETF")),
+            issuer: None,
+            type_: Some(s!("ETP")),
+            country: Some(s!("US")),
+        }));
+
+        let _mock = server.mock("POST", "/v3/mapping")
+            .match_body(Matcher::JsonString(s!(r#"[{"idType": "ID_ISIN", "idValue": "US0000000002"}]"#)))
+            .with_status(200)
+            .with_body(r#"[{"warning": "No identifier found."}]"#)
+            .create();
+
+        let isin = parse_isin("US0000000002").unwrap();
+        assert_eq!(client.get_info(&SecurityId::Isin(isin)).unwrap(), None);
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use isin::ISIN;
+
+use crate::core::GenericError;
+
+/// Security identifier as it may be specified in broker statements or quotes provider requests: stock symbol (ticker),
+/// ISIN or FIGI.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SecurityId {
+    Symbol(String),
+    Isin(ISIN),
+    Figi(Figi),
+}
+
+impl SecurityId {
+    /// Detects identifier type by its format. ISIN and FIGI are case-insensitive and are normalized to upper case. Any
+    /// value which is neither valid ISIN nor valid FIGI is considered as symbol.
+    pub fn parse(value: &str) -> SecurityId {
+        let value = value.trim();
+        let normalized = value.to_uppercase();
+
+        // FIGI format is stricter, but some FIGIs are valid ISINs as well (BBG000BPH459 for example), so check it first
+        if let Ok(figi) = normalized.parse() {
+            SecurityId::Figi(figi)
+        } else if let Ok(isin) = normalized.parse() {
+            SecurityId::Isin(isin)
+        } else {
+            SecurityId::Symbol(value.to_owned())
+        }
+    }
+
+    pub fn is_symbol(&self) -> bool {
+        matches!(self, SecurityId::Symbol(_))
+    }
+}
+
+impl Display for SecurityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id: &dyn Display = match self {
+            SecurityId::Symbol(symbol) => symbol,
+            SecurityId::Isin(isin) => isin,
+            SecurityId::Figi(figi) => figi,
+        };
+        id.fmt(f)
+    }
+}
+
+/// Financial Instrument Global Identifier (https://www.openfigi.com/about/figi)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Figi(String);
+
+impl FromStr for Figi {
+    type Err = GenericError;
+
+    fn from_str(value: &str) -> Result<Figi, GenericError> {
+        let chars = value.as_bytes();
+
+        let valid =
+            chars.len() == 12 &&
+            chars[..2].iter().all(|&char| is_consonant(char)) &&
+            !matches!(&value[..2], "BS" | "BM" | "GG" | "GB" | "GH" | "KY" | "VG") &&
+            chars[2] == b'G' &&
+            chars[3..11].iter().all(|&char| char.is_ascii_digit() || is_consonant(char)) &&
+            chars[11].is_ascii_digit() && get_check_digit(&chars[..11]) == chars[11] - b'0';
+
+        if !valid {
+            return Err!("Invalid FIGI: {}", value);
+        }
+
+        Ok(Figi(value.to_owned()))
+    }
+}
+
+impl Display for Figi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+fn is_consonant(char: u8) -> bool {
+    char.is_ascii_uppercase() && !matches!(char, b'A' | b'E' | b'I' | b'O' | b'U')
+}
+
+// Modified Luhn algorithm where letters are converted to numbers (A = 10, ..., Z = 35) and each even character value is
+// doubled.
+fn get_check_digit(chars: &[u8]) -> u8 {
+    let sum: u32 = chars.iter().enumerate().map(|(index, &char)| {
+        let mut value = if char.is_ascii_digit() {
+            u32::from(char - b'0')
+        } else {
+            u32::from(char - b'A') + 10
+        };
+
+        if index % 2 == 1 {
+            value *= 2;
+        }
+
+        value / 10 + value % 10
+    }).sum();
+
+    ((10 - sum % 10) % 10) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("AAPL", SecurityId::Symbol(s!("AAPL"))),
+        case(" SBER ", SecurityId::Symbol(s!("SBER"))),
+        case("US0378331005", SecurityId::Isin("US0378331005".parse().unwrap())),
+        case("us0378331005", SecurityId::Isin("US0378331005".parse().unwrap())),
+        case("BBG000B9XRY4", SecurityId::Figi(Figi(s!("BBG000B9XRY4")))),
+        case("bbg004730n88", SecurityId::Figi(Figi(s!("BBG004730N88")))),
+        case("BBG000BPH459", SecurityId::Figi(Figi(s!("BBG000BPH459")))), // Also a valid ISIN
+        case("BBG000B9XRY5", SecurityId::Symbol(s!("BBG000B9XRY5"))), // Invalid check digit
+        case("BBG000B9XAY4", SecurityId::Symbol(s!("BBG000B9XAY4"))), // Vowel
+    )]
+    fn parsing(value: &str, expected: SecurityId) {
+        let id = SecurityId::parse(value);
+        assert_eq!(id, expected);
+        assert_eq!(id.to_string(), value.trim().to_uppercase());
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::broker_statement::BrokerStatement;
+use crate::config::Config;
+use crate::config_editor;
+use crate::core::GenericResult;
+use crate::telemetry::TelemetryRecordBuilder;
+
+/// Suggests symbols for broker-specific instrument internal IDs which aren't mapped yet (matching them with instrument
+/// names and ISINs from all broker statements) and appends the accepted mappings to the portfolio configuration.
+pub fn map(config: &Config, config_path: &Path, portfolio_name: &str) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let suggestions = BrokerStatement::suggest_internal_ids(
+        &broker, portfolio.statements_path()?, portfolio.get_tax_remapping()?,
+        &portfolio.instrument_internal_ids)?;
+
+    if suggestions.is_empty() {
+        println!("All instrument internal IDs are already mapped.");
+        return Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker));
+    }
+
+    let mappings = Editor::new(io::stdin().lock(), io::stdout()).run(&suggestions)?;
+    if mappings.is_empty() {
+        println!("No mappings have been accepted.");
+        return Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker));
+    }
+
+    let path = config_path.display();
+    let mut config = fs::read_to_string(config_path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+
+    for (id, symbol) in &mappings {
+        let item = format!("{}: {}", config_editor::quote(id), config_editor::quote(symbol));
+        config = config_editor::append_portfolio_option_item(&config, portfolio_name, "instrument_internal_ids", &item)
+            .map_err(|e| format!("Unable to add the mappings to {}: {}", path, e))?;
+    }
+
+    fs::write(config_path, config).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    println!("{} mappings have been added to {}.", mappings.len(), path);
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+struct Editor<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Editor<R, W> {
+    fn new(input: R, output: W) -> Editor<R, W> {
+        Editor {input, output}
+    }
+
+    fn run(&mut self, suggestions: &[(String, Vec<String>)]) -> GenericResult<Vec<(String, String)>> {
+        writeln!(self.output, "Found {} unmapped instrument internal IDs. Press Enter to skip an ID.", suggestions.len())?;
+
+        let mut mappings = Vec::new();
+
+        for (id, symbols) in suggestions {
+            writeln!(self.output)?;
+            writeln!(self.output, "{}:", id)?;
+
+            if symbols.is_empty() {
+                writeln!(self.output, "No suggestions.")?;
+            }
+            for (index, symbol) in symbols.iter().enumerate() {
+                writeln!(self.output, "{}. {}", index + 1, symbol)?;
+            }
+
+            let prompt = if symbols.is_empty() {
+                "Symbol"
+            } else {
+                "Choose the symbol number or enter another symbol"
+            };
+
+            let symbol = loop {
+                let value = self.ask(prompt)?;
+                if value.is_empty() {
+                    break None;
+                }
+
+                let Ok(index) = value.parse::<usize>() else {
+                    break Some(value);
+                };
+
+                match symbols.get(index.wrapping_sub(1)) {
+                    Some(symbol) => break Some(symbol.clone()),
+                    None => writeln!(self.output, "Invalid symbol number.")?,
+                }
+            };
+
+            if let Some(symbol) = symbol {
+                mappings.push((id.clone(), symbol));
+            }
+        }
+
+        Ok(mappings)
+    }
+
+    fn ask(&mut self, prompt: &str) -> GenericResult<String> {
+        write!(self.output, "{}: ", prompt)?;
+        self.output.flush()?;
+
+        let mut value = String::new();
+        if self.input.read_line(&mut value)? == 0 {
+            return Err!("Unexpected end of input");
+        }
+
+        Ok(value.trim().to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn editor() {
+        let suggestions = [
+            (s!("ABBVIE INC-ао"), vec![s!("ABBV")]),
+            (s!("INC-ао"), vec![s!("ABBV"), s!("MO")]),
+            (s!("ЛУКОЙЛ-ао"), vec![]),
+            (s!("ГАЗПРОМ-ао-2"), vec![s!("GAZP")]),
+        ];
+
+        let input = indoc!("
+            1
+            3
+            2
+            LKOH
+
+        ");
+
+        let mut output = Vec::new();
+        let mappings = Editor::new(input.as_bytes(), &mut output).run(&suggestions).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Invalid symbol number."));
+        assert!(output.contains("ЛУКОЙЛ-ао:\nNo suggestions."));
+
+        assert_eq!(mappings, vec![
+            (s!("ABBVIE INC-ао"), s!("ABBV")),
+            (s!("INC-ао"), s!("MO")),
+            (s!("ЛУКОЙЛ-ао"), s!("LKOH")),
+        ]);
+    }
+}
@@ -1,34 +1,52 @@
 #[macro_use] extern crate diesel;
 #[macro_use] extern crate diesel_migrations;
 #[macro_use] extern crate maplit;
-#[macro_use] extern crate separator;
 
 #[macro_use] pub mod core;
 #[macro_use] pub mod types;
 
 pub mod analysis;
+pub mod broker_costs;
 pub mod cash_flow;
 pub mod config;
+pub mod corporate_action_editor;
 pub mod db;
 pub mod deposits;
+pub mod doctor;
+pub mod errors;
+pub mod forex_rates;
+pub mod forex_trades;
+pub mod formatting;
+pub mod internal_ids_editor;
 pub mod metrics;
+pub mod network;
 pub mod portfolio;
+pub mod profiling;
+pub mod progress;
+pub mod secrets;
+pub mod setup;
+pub mod statement_check;
+pub mod statement_json;
+pub mod statement_sampler;
 pub mod tax_statement;
 pub mod telemetry;
 pub mod time;
+pub mod timeline;
 pub mod util;
 
 mod broker_statement;
 mod brokers;
 mod commissions;
+mod config_editor;
 mod currency;
 mod exchanges;
 mod forex;
 mod formats;
-mod formatting;
 mod instruments;
 mod localities;
 mod quotes;
 mod rate_limiter;
 mod taxes;
-mod trades;
\ No newline at end of file
+mod trades;
+
+#[cfg(feature = "bench")] #[doc(hidden)] pub use self::broker_statement::bench;
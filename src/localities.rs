@@ -3,9 +3,10 @@ use std::rc::Rc;
 
 use chrono::{Datelike, Duration};
 
+use crate::core::GenericResult;
 use crate::currency::Cash;
 use crate::exchanges::Exchange;
-use crate::taxes::{FixedTaxRate, ProgressiveTaxRate, TaxConfig, TaxRate};
+use crate::taxes::{FixedTaxRate, ProgressiveTaxRate, SeparateTaxBasesRate, TaxConfig, TaxRate};
 use crate::types::{Date, Decimal};
 
 #[derive(Clone)]
@@ -82,29 +83,67 @@ pub fn russia(config: &TaxConfig) -> Country {
     });
 
     // Starting from 2025 we've got progressive tax rate with two tax bases:
-    // 1. Income from employment
+    // 1. Income from employment and other income - five-bracket scale
     // 2. Dividends, interest, trading, property
-    let rates_2025 = Rc::new(btreemap!{
+    let investment_rates_2025 = Rc::new(btreemap!{
         dec!(0) => dec!(0.13),
         dec!(2_400_000) => dec!(0.15),
     });
+    let main_rates_2025 = Rc::new(btreemap!{
+        dec!(0) => dec!(0.13),
+        dec!(2_400_000) => dec!(0.15),
+        dec!(5_000_000) => dec!(0.18),
+        dec!(20_000_000) => dec!(0.20),
+        dec!(50_000_000) => dec!(0.22),
+    });
+    let rates_2025 = |main_income| Box::new(SeparateTaxBasesRate::new(
+        ProgressiveTaxRate::new(dec!(0), investment_rates_2025.clone(), tax_precision),
+        ProgressiveTaxRate::new(main_income, main_rates_2025.clone(), tax_precision),
+    )) as Box<dyn TaxRate>;
 
     let tax_agent_calculators = btreemap! {
         i32::MIN => Box::new(FixedTaxRate::new(dec!(0.13), tax_precision)) as Box<dyn TaxRate>,
         2021 => Box::new(ProgressiveTaxRate::new(dec!(0), rates_2021.clone(), tax_precision)) as Box<dyn TaxRate>,
-        2025 => Box::new(ProgressiveTaxRate::new(dec!(0), rates_2025.clone(), tax_precision)) as Box<dyn TaxRate>,
+        2025 => rates_2025(dec!(0)),
     };
 
     let mut tax_calculators = tax_agent_calculators.clone();
 
-    for (&year, &income) in config.income.range(2021..2025) {
-        let calc = Box::new(ProgressiveTaxRate::new(income, rates_2021.clone(), tax_precision));
+    for (&year, &income) in config.external_income.range(2021..) {
+        let calc = if year < 2025 {
+            Box::new(ProgressiveTaxRate::new(income, rates_2021.clone(), tax_precision))
+        } else {
+            rates_2025(income)
+        };
         tax_calculators.insert(year, calc);
+
+        // External income is specified per year, so it mustn't affect the next years
+        let next_year = year + 1;
+        if !config.external_income.contains_key(&next_year) {
+            let (_, calc) = tax_agent_calculators.range(..=next_year).last().unwrap();
+            tax_calculators.insert(next_year, calc.clone());
+        }
     }
 
     Country::new(Jurisdiction::Russia, tax_calculators, tax_agent_calculators)
 }
 
+/// Returns the amount of deposit interest which isn't taxed in the specified year or `None` if deposit interest isn't
+/// taxed at all. Starting from 2023 only interest above 1 000 000 RUB multiplied by the maximum CBR key rate on the
+/// first day of each month of the year is taxed.
+pub fn russian_deposit_interest_tax_free_amount(year: i32) -> GenericResult<Option<Decimal>> {
+    let max_key_rate = match year {
+        ..=2022 => return Ok(None),
+        2023 => dec!(0.15),
+        2024 => dec!(0.21),
+        2025 => dec!(0.21),
+        // The key rate as of 01.01.2026. Must be updated if it's raised during the year.
+        2026 => dec!(0.16),
+        _ => return Err!("The maximum CBR key rate for {} is unknown", year),
+    };
+    Ok(Some(dec!(1_000_000) * max_key_rate))
+}
+
 pub fn get_russian_central_bank_min_last_working_day(today: Date) -> Date {
     // New Year holidays
     if today.month() == 1 && today.day() < 12 {
@@ -19,6 +19,9 @@ pub struct MetricsConfig {
 
     #[serde(default)]
     pub merge_performance: PerformanceMergingConfig,
+
+    #[serde(default)]
+    pub attribute_fees: bool,
 }
 
 impl MetricsConfig {
@@ -1,17 +1,21 @@
 pub mod config;
+mod state;
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::{BufWriter, Write};
 use std::fs::{self, File};
 use std::path::Path;
 
 use lazy_static::lazy_static;
+use log::debug;
 use num_traits::ToPrimitive;
 use prometheus::{self, TextEncoder, Encoder, Gauge, GaugeVec, register_gauge, register_gauge_vec};
+use prometheus::core::Collector;
 use strum::IntoEnumIterator;
 
 use crate::analysis::{self, PerformanceAnalysisMethod};
-use crate::analysis::portfolio_statistics::{Asset, AssetGroup, PortfolioCurrencyStatistics, LtoStatistics};
+use crate::analysis::portfolio_statistics::{
+    Asset, AssetGroup, PortfolioCurrencyStatistics, PortfolioStatistics, LtoStatistics};
 use crate::config::Config;
 use crate::core::{EmptyResult, GenericError, GenericResult};
 use crate::forex;
@@ -21,6 +25,8 @@ use crate::time;
 use crate::types::Decimal;
 use crate::util;
 
+use self::state::{MetricsState, PortfolioMetrics, Sample};
+
 lazy_static! {
     static ref UPDATE_TIME: Gauge = register_simple_metric(
         "time", "Metrics generation time");
@@ -37,6 +43,9 @@ lazy_static! {
     static ref ASSET_GROUPS: GaugeVec = register_metric(
         "asset_groups", "Net asset value of custom groups", &["name", "currency"]);
 
+    static ref ASSET_GROUP_BREACHES: GaugeVec = register_metric(
+        "asset_group_breaches", "Whether custom group share is out of the configured bounds", &["name"]);
+
     static ref PERFORMANCE: GaugeVec = register_performance_metric(
         "performance", "Instrument performance");
 
@@ -61,6 +70,15 @@ lazy_static! {
     static ref PROJECTED_COMMISSIONS: GaugeVec = register_portfolio_metric(
         "projected_commissions", "Projected commissions to pay");
 
+    static ref PORTFOLIO_AGE: GaugeVec = register_metric(
+        "portfolio_age", "Portfolio age in years", &["portfolio"]);
+
+    static ref CONTRIBUTIONS: GaugeVec = register_metric(
+        "contributions", "Cumulative portfolio contributions", &["portfolio", "currency"]);
+
+    static ref AVERAGE_ANNUAL_CONTRIBUTION: GaugeVec = register_metric(
+        "average_annual_contribution", "Average annual portfolio contribution", &["portfolio", "currency"]);
+
     static ref LTO: GaugeVec = register_metric(
         "lto", "Long-term ownership tax exemption applying results", &["year", "type"]);
 
@@ -71,19 +89,25 @@ lazy_static! {
         "forex_pairs", "Forex quotes", &["base", "quote"]);
 }
 
-pub fn collect(config: &Config, path: &Path) -> GenericResult<TelemetryRecordBuilder> {
+pub fn collect(config: &Config, path: &Path, incremental: bool) -> GenericResult<TelemetryRecordBuilder> {
+    if incremental {
+        return collect_incremental(config, path);
+    }
+
     let (statistics, quotes, telemetry) = analysis::analyse(
         config, None, false, &config.metrics.asset_groups,
-        Some(&config.metrics.merge_performance), false)?;
+        Some(&config.metrics.merge_performance), config.metrics.attribute_fees, false)?;
 
     UPDATE_TIME.set(cast::f64(time::timestamp()));
 
     for statistics in &statistics.currencies {
-        collect_portfolio_metrics(statistics);
+        collect_portfolio_metrics(statistics, PORTFOLIO_LABEL_ALL);
     }
 
     collect_forex_quotes(quotes, &config.metrics.currency_rates)?;
     collect_asset_groups(&statistics.asset_groups);
+    collect_asset_group_breaches(&statistics.asset_groups);
+    collect_funding_metrics(&statistics);
     collect_lto_metrics(statistics.lto.as_ref().unwrap());
 
     save(path)?;
@@ -91,7 +115,75 @@ pub fn collect(config: &Config, path: &Path) -> GenericResult<TelemetryRecordBui
     Ok(telemetry)
 }
 
-fn collect_portfolio_metrics(statistics: &PortfolioCurrencyStatistics) {
+// Analyses only the portfolios which broker statements have been changed since the previous run and takes metrics of
+// the other portfolios from the state file. Metrics of the whole portfolio are collected per portfolio in this mode,
+// and long-term ownership and asset group breach metrics, which can't be calculated per portfolio, are omitted.
+fn collect_incremental(config: &Config, path: &Path) -> GenericResult<TelemetryRecordBuilder> {
+    if config.portfolios.is_empty() {
+        return Err!("There is no any portfolio defined in the configuration file")
+    }
+
+    let state_path = MetricsState::get_path(path);
+    let mut previous_state = MetricsState::load(&state_path)?;
+
+    let mut state = MetricsState::default();
+    let mut telemetry = TelemetryRecordBuilder::new();
+    let mut quotes = None;
+
+    for portfolio in &config.portfolios {
+        telemetry.add_broker(portfolio.broker);
+
+        let statements_hash = state::hash_statements(portfolio.statements_path()?)?;
+        let previous = previous_state.portfolios.remove(&portfolio.name).filter(|previous| {
+            previous.statements_hash == statements_hash
+        });
+
+        if let Some(previous) = previous {
+            debug!("{} broker statements haven't changed. Using the previously collected metrics.", portfolio.name);
+            state.portfolios.insert(portfolio.name.clone(), previous);
+            continue;
+        }
+
+        let (statistics, portfolio_quotes, _) = analysis::analyse(
+            config, Some(&portfolio.name), false, &config.metrics.asset_groups,
+            Some(&config.metrics.merge_performance), config.metrics.attribute_fees, false)?;
+
+        reset_portfolio_metrics();
+        for statistics in &statistics.currencies {
+            collect_portfolio_metrics(statistics, &portfolio.name);
+        }
+        collect_asset_groups(&statistics.asset_groups);
+        collect_funding_metrics(&statistics);
+
+        state.portfolios.insert(portfolio.name.clone(), PortfolioMetrics {
+            statements_hash,
+            metrics: get_portfolio_metrics(),
+        });
+        quotes.get_or_insert(portfolio_quotes);
+    }
+
+    reset_portfolio_metrics();
+    for portfolio in state.portfolios.values() {
+        for sample in &portfolio.metrics {
+            restore_metric(sample)?;
+        }
+    }
+
+    let quotes = match quotes {
+        Some(quotes) => quotes,
+        None => analysis::load_tools(config)?.1,
+    };
+
+    UPDATE_TIME.set(cast::f64(time::timestamp()));
+    collect_forex_quotes(quotes, &config.metrics.currency_rates)?;
+
+    save(path)?;
+    state.save(&state_path)?;
+
+    Ok(telemetry)
+}
+
+fn collect_portfolio_metrics(statistics: &PortfolioCurrencyStatistics, portfolio: &str) {
     let currency = &statistics.currency;
     let income_structure = &statistics.real_performance.as_ref().unwrap().income_structure;
 
@@ -102,9 +194,9 @@ fn collect_portfolio_metrics(statistics: &PortfolioCurrencyStatistics) {
     for (instrument, portfolios) in &statistics.assets {
         let mut total = Asset::default();
 
-        for (portfolio, asset) in portfolios {
-            set_instrument_metric(&ASSETS, portfolio, currency, instrument, asset.value);
-            set_instrument_metric(&NET_ASSETS, portfolio, currency, instrument, asset.net_value);
+        for (name, asset) in portfolios {
+            set_instrument_metric(&ASSETS, name, currency, instrument, asset.value);
+            set_instrument_metric(&NET_ASSETS, name, currency, instrument, asset.net_value);
             total.add(asset);
         }
 
@@ -118,29 +210,32 @@ fn collect_portfolio_metrics(statistics: &PortfolioCurrencyStatistics) {
 
         for (instrument, analysis) in &performance.instruments {
             if let Some(interest) = analysis.interest {
-                set_performance_metric(&PERFORMANCE, currency, instrument, method_name, interest);
+                set_performance_metric(&PERFORMANCE, portfolio, currency, instrument, method_name, interest);
             }
         }
 
         if let Some(interest) = performance.portfolio.interest {
-            set_performance_metric(&PERFORMANCE, currency, "Portfolio", method_name, interest);
+            set_performance_metric(&PERFORMANCE, portfolio, currency, "Portfolio", method_name, interest);
         }
     }
 
-    set_portfolio_metric(&PROFIT, currency, income_structure.profit());
-    set_portfolio_metric(&NET_PROFIT, currency, income_structure.net_profit);
+    set_portfolio_metric(&PROFIT, portfolio, currency, income_structure.profit());
+    set_portfolio_metric(&NET_PROFIT, portfolio, currency, income_structure.net_profit);
 
-    set_structure_metric(&INCOME_STRUCTURE, currency, "Trading", income_structure.net_trading_income());
-    set_structure_metric(&INCOME_STRUCTURE, currency, "Dividends", income_structure.net_dividend_income());
-    set_structure_metric(&INCOME_STRUCTURE, currency, "Interest", income_structure.net_interest_income());
-    set_structure_metric(&INCOME_STRUCTURE, currency, "Tax deductions", income_structure.tax_deductions());
+    set_structure_metric(&INCOME_STRUCTURE, portfolio, currency, "Trading", income_structure.net_trading_income());
+    set_structure_metric(&INCOME_STRUCTURE, portfolio, currency, "Dividends", income_structure.net_dividend_income());
+    set_structure_metric(&INCOME_STRUCTURE, portfolio, currency, "Interest", income_structure.net_interest_income());
+    set_structure_metric(&INCOME_STRUCTURE, portfolio, currency, "Other", income_structure.net_other_income());
+    set_structure_metric(&INCOME_STRUCTURE, portfolio, currency, "Tax deductions", income_structure.tax_deductions());
 
-    set_structure_metric(&EXPENCES_STRUCTURE, currency, "Taxes", income_structure.taxes());
-    set_structure_metric(&EXPENCES_STRUCTURE, currency, "Commissions", income_structure.commissions);
+    set_structure_metric(&EXPENCES_STRUCTURE, portfolio, currency, "Taxes", income_structure.taxes());
+    set_structure_metric(&EXPENCES_STRUCTURE, portfolio, currency, "Commissions", income_structure.commissions);
+    set_structure_metric(&EXPENCES_STRUCTURE, portfolio, currency, "Margin interest", income_structure.margin_interest);
+    set_structure_metric(&EXPENCES_STRUCTURE, portfolio, currency, "Holding costs", income_structure.holding_costs);
 
-    set_portfolio_metric(&PROJECTED_TAXES, currency, statistics.projected_taxes);
-    set_portfolio_metric(&PROJECTED_TAX_DEDUCTIONS, currency, statistics.projected_tax_deductions);
-    set_portfolio_metric(&PROJECTED_COMMISSIONS, currency, statistics.projected_commissions);
+    set_portfolio_metric(&PROJECTED_TAXES, portfolio, currency, statistics.projected_taxes);
+    set_portfolio_metric(&PROJECTED_TAX_DEDUCTIONS, portfolio, currency, statistics.projected_tax_deductions);
+    set_portfolio_metric(&PROJECTED_COMMISSIONS, portfolio, currency, statistics.projected_commissions);
 }
 
 fn collect_asset_groups(groups: &BTreeMap<String, AssetGroup>) {
@@ -151,6 +246,26 @@ fn collect_asset_groups(groups: &BTreeMap<String, AssetGroup>) {
     }
 }
 
+fn collect_asset_group_breaches(groups: &BTreeMap<String, AssetGroup>) {
+    for (name, group) in groups {
+        if group.has_constraints() {
+            let breach = if group.breach().is_some() { dec!(1) } else { dec!(0) };
+            set_metric(&ASSET_GROUP_BREACHES, &[name], breach);
+        }
+    }
+}
+
+fn collect_funding_metrics(statistics: &PortfolioStatistics) {
+    for funding in &statistics.funding {
+        let portfolio = funding.portfolio.as_str();
+        let currency = funding.contributions.currency;
+
+        set_metric(&PORTFOLIO_AGE, &[portfolio], funding.age);
+        set_metric(&CONTRIBUTIONS, &[portfolio, currency], funding.contributions.amount);
+        set_metric(&AVERAGE_ANNUAL_CONTRIBUTION, &[portfolio, currency], funding.average_annual_contribution.amount);
+    }
+}
+
 fn collect_lto_metrics(lto: &LtoStatistics) {
     for (year, result) in &lto.applied {
         let year = year.to_string();
@@ -179,6 +294,56 @@ fn collect_forex_quotes(quotes: QuotesRc, pairs: &BTreeSet<String>) -> EmptyResu
     Ok(())
 }
 
+fn portfolio_metrics() -> [&'static GaugeVec; 15] {
+    [
+        &BROKERS, &ASSETS, &NET_ASSETS, &ASSET_GROUPS, &PERFORMANCE, &INCOME_STRUCTURE, &EXPENCES_STRUCTURE,
+        &PROFIT, &NET_PROFIT, &PROJECTED_TAXES, &PROJECTED_TAX_DEDUCTIONS, &PROJECTED_COMMISSIONS,
+        &PORTFOLIO_AGE, &CONTRIBUTIONS, &AVERAGE_ANNUAL_CONTRIBUTION,
+    ]
+}
+
+fn reset_portfolio_metrics() {
+    for collector in portfolio_metrics() {
+        collector.reset();
+    }
+}
+
+fn get_portfolio_metrics() -> Vec<Sample> {
+    let mut samples = Vec::new();
+
+    for collector in portfolio_metrics() {
+        for family in collector.collect() {
+            for metric in family.get_metric() {
+                samples.push(Sample {
+                    name: family.get_name().to_owned(),
+                    labels: metric.get_label().iter().map(|label| {
+                        (label.get_name().to_owned(), label.get_value().to_owned())
+                    }).collect(),
+                    value: metric.get_gauge().get_value(),
+                });
+            }
+        }
+    }
+
+    samples
+}
+
+// Brokers, asset groups and "all" instrument totals may be reported by several portfolios, so they are summed up
+fn restore_metric(sample: &Sample) -> EmptyResult {
+    let collector = portfolio_metrics().into_iter()
+        .find(|collector| collector.desc()[0].fq_name == sample.name)
+        .ok_or_else(|| format!("Got an unknown metric in the metrics state: {}", sample.name))?;
+
+    let labels: HashMap<&str, &str> = sample.labels.iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+
+    collector.get_metric_with(&labels).map_err(|e| format!(
+        "Got an invalid {} metric in the metrics state: {}", sample.name, e))?.add(sample.value);
+
+    Ok(())
+}
+
 fn save(path: &Path) -> EmptyResult {
     let encoder = TextEncoder::new();
     let metrics = prometheus::gather();
@@ -230,20 +395,22 @@ fn register_simple_metric(name: &str, help: &str) -> Gauge {
     register_gauge!(&format!("{}_{}", NAMESPACE, name), help).unwrap()
 }
 
-fn set_portfolio_metric(collector: &GaugeVec, currency: &str, value: Decimal) {
-    set_metric(collector, &[PORTFOLIO_LABEL_ALL, currency], value)
+fn set_portfolio_metric(collector: &GaugeVec, portfolio: &str, currency: &str, value: Decimal) {
+    set_metric(collector, &[portfolio, currency], value)
 }
 
 fn set_instrument_metric(collector: &GaugeVec, portfolio: &str, currency: &str, instrument: &str, value: Decimal) {
     set_metric(collector, &[portfolio, currency, instrument], value)
 }
 
-fn set_performance_metric(collector: &GaugeVec, currency: &str, instrument: &str, method: &str, value: Decimal) {
-    set_metric(collector, &[PORTFOLIO_LABEL_ALL, currency, instrument, method], value)
+fn set_performance_metric(
+    collector: &GaugeVec, portfolio: &str, currency: &str, instrument: &str, method: &str, value: Decimal,
+) {
+    set_metric(collector, &[portfolio, currency, instrument, method], value)
 }
 
-fn set_structure_metric(collector: &GaugeVec, currency: &str, type_: &str, value: Decimal) {
-    set_metric(collector, &[PORTFOLIO_LABEL_ALL, currency, type_], value)
+fn set_structure_metric(collector: &GaugeVec, portfolio: &str, currency: &str, type_: &str, value: Decimal) {
+    set_metric(collector, &[portfolio, currency, type_], value)
 }
 
 fn set_metric(collector: &GaugeVec, labels: &[&str], value: Decimal) {
@@ -0,0 +1,130 @@
+// State of incremental metrics collection: metrics of each portfolio are stored along with a hash of its broker
+// statements, so the portfolio is analysed again only when its statements change.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::util;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsState {
+    pub portfolios: BTreeMap<String, PortfolioMetrics>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortfolioMetrics {
+    pub statements_hash: String,
+    pub metrics: Vec<Sample>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+impl MetricsState {
+    pub fn get_path(metrics_path: &Path) -> PathBuf {
+        let mut path = metrics_path.as_os_str().to_os_string();
+        path.push(".state");
+        PathBuf::from(path)
+    }
+
+    pub fn load(path: &Path) -> GenericResult<MetricsState> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(MetricsState::default()),
+            Err(e) => return Err!("Failed to read {:?}: {}", path, e),
+        };
+
+        // The state is just a cache, so it's safe to drop it
+        Ok(serde_json::from_slice(&data).unwrap_or_else(|e| {
+            warn!("Ignoring invalid metrics state file {:?}: {}.", path, e);
+            MetricsState::default()
+        }))
+    }
+
+    pub fn save(&self, path: &Path) -> EmptyResult {
+        let temp_path = util::temp_path(path);
+        fs::write(&temp_path, serde_json::to_vec(self)?)?;
+        Ok(fs::rename(&temp_path, path)?)
+    }
+}
+
+/// Calculates a hash of all files in the broker statements directory (FNV-1a, which is stable between releases unlike
+/// the standard library hashers)
+pub fn hash_statements(path: &str) -> GenericResult<String> {
+    let mut paths = Vec::new();
+
+    for entry in fs::read_dir(path).map_err(|e| format!("Unable to read {:?}: {}", path, e))? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+
+    let mut hasher = Fnv1a::new();
+
+    for path in paths {
+        let data = fs::read(&path).map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+        let name = path.file_name().unwrap().as_encoded_bytes();
+
+        for chunk in [name, &data] {
+            hasher.write(&(chunk.len() as u64).to_le_bytes());
+            hasher.write(chunk);
+        }
+    }
+
+    Ok(format!("{:016x}", hasher.0))
+}
+
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_hash() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+        let hash = || hash_statements(path).unwrap();
+
+        let empty = hash();
+
+        fs::write(temp_dir.path().join("a.csv"), "data").unwrap();
+        let first = hash();
+        assert_ne!(first, empty);
+        assert_eq!(hash(), first);
+
+        fs::write(temp_dir.path().join("a.csv"), "changed").unwrap();
+        let changed = hash();
+        assert_ne!(changed, first);
+
+        fs::rename(temp_dir.path().join("a.csv"), temp_dir.path().join("b.csv")).unwrap();
+        assert_ne!(hash(), changed);
+    }
+}
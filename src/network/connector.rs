@@ -0,0 +1,135 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hyper_util::rt::TokioIo;
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{self, TcpStream};
+use tokio_socks::tcp::Socks5Stream;
+use tonic::transport::Uri;
+use tower_service::Service;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Protects from endless reading of a malformed response
+const MAX_RESPONSE_SIZE: usize = 8192;
+
+/// Connector for gRPC clients which establishes connections through HTTP (CONNECT method) or SOCKS5 proxy
+#[derive(Clone)]
+pub struct ProxyConnector {
+    proxy: Url,
+}
+
+impl ProxyConnector {
+    pub fn new(proxy: &Url) -> Result<ProxyConnector, String> {
+        match proxy.scheme() {
+            "http" | "socks5" | "socks5h" => {},
+            scheme => return Err(format!("{} proxies are not supported for gRPC connections", scheme)),
+        }
+
+        Ok(ProxyConnector {
+            proxy: proxy.clone(),
+        })
+    }
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let proxy = self.proxy.clone();
+        Box::pin(async move {
+            let stream = connect(&proxy, &uri).await.map_err(|e| format!(
+                "Failed to connect to {} via {} proxy: {}", uri, proxy.scheme(), e))?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+async fn connect(proxy: &Url, uri: &Uri) -> Result<TcpStream, Error> {
+    let host = uri.host().ok_or("The URI has no host")?.to_owned();
+    let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+        Some("http") => 80,
+        _ => 443,
+    });
+
+    let proxy_host = proxy.host_str().ok_or("Invalid proxy URL")?;
+    let proxy_port = proxy.port_or_known_default().unwrap_or(1080);
+    let credentials = (!proxy.username().is_empty()).then(|| {
+        (proxy.username(), proxy.password().unwrap_or_default())
+    });
+
+    match proxy.scheme() {
+        "http" => {
+            let mut stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+            http_connect(&mut stream, &host, port, credentials).await?;
+            Ok(stream)
+        },
+
+        scheme => {
+            let proxy_address = (proxy_host, proxy_port);
+
+            // socks5h resolves hostnames on proxy side
+            let stream = if scheme == "socks5" {
+                let address = net::lookup_host((host.as_str(), port)).await?.next().ok_or_else(|| format!(
+                    "Unable to resolve {}", host))?;
+
+                match credentials {
+                    Some((username, password)) => Socks5Stream::connect_with_password(
+                        proxy_address, address, username, password).await?,
+                    None => Socks5Stream::connect(proxy_address, address).await?,
+                }
+            } else {
+                let address = (host.as_str(), port);
+
+                match credentials {
+                    Some((username, password)) => Socks5Stream::connect_with_password(
+                        proxy_address, address, username, password).await?,
+                    None => Socks5Stream::connect(proxy_address, address).await?,
+                }
+            };
+
+            Ok(stream.into_inner())
+        },
+    }
+}
+
+async fn http_connect(
+    stream: &mut TcpStream, host: &str, port: u16, credentials: Option<(&str, &str)>,
+) -> Result<(), Error> {
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some((username, password)) = credentials {
+        let token = BASE64.encode(format!("{}:{}", username, password));
+        request += &format!("Proxy-Authorization: Basic {}\r\n", token);
+    }
+    request += "\r\n";
+
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_RESPONSE_SIZE {
+            return Err("Got too big response from the proxy".into());
+        }
+
+        let byte = stream.read_u8().await?;
+        response.push(byte);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or_default();
+
+    match status.split_whitespace().nth(1) {
+        Some("200") => Ok(()),
+        _ => Err(format!("The proxy returned an error: {}", status).into()),
+    }
+}
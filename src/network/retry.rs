@@ -0,0 +1,337 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use regex::Regex;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::de::{Deserializer, Error as _};
+
+use crate::core::{EmptyResult, GenericError, GenericResult};
+
+// How long the circuit stays open before the next probe request is allowed
+const CIRCUIT_BREAKER_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts per request (including the first one)
+    #[serde(default = "RetryPolicy::default_max_attempts", deserialize_with = "deserialize_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Delay before the first retry which is doubled on each subsequent retry
+    #[serde(default = "RetryPolicy::default_backoff", deserialize_with = "deserialize_backoff")]
+    pub backoff: Duration,
+
+    /// Number of consecutive failures after which all requests fail fast for some time (0 disables circuit breaker)
+    #[serde(default = "RetryPolicy::default_circuit_breaker_threshold")]
+    pub circuit_breaker_threshold: u32,
+}
+
+impl RetryPolicy {
+    pub const fn new(max_attempts: u32, backoff: Duration, circuit_breaker_threshold: u32) -> RetryPolicy {
+        RetryPolicy {max_attempts, backoff, circuit_breaker_threshold}
+    }
+
+    fn default_max_attempts() -> u32 {
+        3
+    }
+
+    fn default_backoff() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn default_circuit_breaker_threshold() -> u32 {
+        5
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::new(
+            RetryPolicy::default_max_attempts(), RetryPolicy::default_backoff(),
+            RetryPolicy::default_circuit_breaker_threshold())
+    }
+}
+
+fn deserialize_max_attempts<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: u32 = Deserialize::deserialize(deserializer)?;
+    if value == 0 {
+        return Err(D::Error::custom("Maximum number of attempts must be positive"));
+    }
+    Ok(value)
+}
+
+fn deserialize_backoff<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: String = Deserialize::deserialize(deserializer)?;
+    parse_backoff(&value).map_err(D::Error::custom)
+}
+
+fn parse_backoff(value: &str) -> GenericResult<Duration> {
+    let re = Regex::new(r"^(?P<number>\d+)(?P<unit>ms|s)$").unwrap();
+
+    let captures = re.captures(value).ok_or_else(|| format!("Invalid backoff: {:?}", value))?;
+    let number: u64 = captures.name("number").unwrap().as_str().parse().map_err(|_| format!(
+        "Invalid backoff: {:?}", value))?;
+
+    Ok(match captures.name("unit").unwrap().as_str() {
+        "ms" => Duration::from_millis(number),
+        "s" => Duration::from_secs(number),
+        _ => unreachable!(),
+    })
+}
+
+/// An error returned when server responds with unsuccessful HTTP status code
+#[derive(Debug)]
+pub struct StatusError(pub StatusCode);
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Server returned an error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StatusError {
+}
+
+/// An error with some context which preserves the original error for transient errors detection
+#[derive(Debug)]
+struct ContextError {
+    context: String,
+    error: GenericError,
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.error)
+    }
+}
+
+impl std::error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+pub fn with_context<E: Into<GenericError>>(error: E, context: String) -> GenericError {
+    Box::new(ContextError {context, error: error.into()})
+}
+
+/// Checks whether the error is caused by temporary server or network unavailability, so the request may be retried
+pub fn is_transient(error: &GenericError) -> bool {
+    if let Some(ContextError {error, ..}) = error.downcast_ref() {
+        return is_transient(error);
+    }
+
+    if let Some(StatusError(status)) = error.downcast_ref() {
+        return status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS;
+    }
+
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return error.is_timeout() || error.is_connect();
+    }
+
+    if let Some(status) = error.downcast_ref::<tonic::Status>() {
+        use tonic::Code;
+        return matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted);
+    }
+
+    false
+}
+
+#[derive(Default)]
+struct CircuitState {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Retries operations failed due to transient errors with exponential backoff. Also acts as a circuit breaker: after
+/// too many consecutive failures all operations fail fast for some time to not waste time on an unavailable service.
+pub struct Retrier {
+    name: String,
+    policy: RetryPolicy,
+    state: Mutex<CircuitState>,
+}
+
+impl Retrier {
+    pub fn new(name: &str, policy: RetryPolicy) -> Retrier {
+        Retrier {
+            name: name.to_owned(),
+            policy,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn call<T, F>(&self, mut operation: F) -> GenericResult<T>
+        where F: FnMut() -> GenericResult<T>
+    {
+        let mut errors = Vec::new();
+
+        for attempt in 1..=self.policy.max_attempts {
+            if attempt > 1 {
+                let backoff = self.policy.backoff * 2_u32.saturating_pow(attempt - 2);
+                debug!("{}: Retrying in {:?} (attempt #{})...", self.name, backoff, attempt);
+                thread::sleep(backoff);
+            }
+
+            self.check_circuit().map_err(|e| aggregate_errors(&errors, e))?;
+
+            let error = match operation() {
+                Ok(result) => {
+                    self.on_success();
+                    return Ok(result);
+                },
+                Err(error) => error,
+            };
+
+            if !is_transient(&error) {
+                return Err(aggregate_errors(&errors, error));
+            }
+
+            debug!("{}: Got a transient error: {}.", self.name, error);
+            self.on_failure();
+            errors.push(error);
+        }
+
+        let error = errors.pop().unwrap();
+        Err(aggregate_errors(&errors, error))
+    }
+
+    fn check_circuit(&self) -> EmptyResult {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(open_until) = state.open_until {
+            if Instant::now() < open_until {
+                return Err!(
+                    "{} is temporarily unavailable: got {} consecutive failures",
+                    self.name, state.failures);
+            }
+
+            // Let one probe request through: the circuit will be opened again on its failure
+            state.open_until = None;
+        }
+
+        Ok(())
+    }
+
+    fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failures = 0;
+        state.open_until = None;
+    }
+
+    fn on_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failures += 1;
+
+        let threshold = self.policy.circuit_breaker_threshold;
+        if threshold != 0 && state.failures >= threshold && state.open_until.is_none() {
+            warn!("{} seems to be unavailable: got {} consecutive failures.", self.name, state.failures);
+            state.open_until = Some(Instant::now() + CIRCUIT_BREAKER_TIMEOUT);
+        }
+    }
+}
+
+fn aggregate_errors(previous: &[GenericError], last: GenericError) -> GenericError {
+    if previous.is_empty() {
+        return last;
+    }
+
+    let mut message = format!("{} (attempt #{}", last, previous.len() + 1);
+    if previous.iter().all(|error| error.to_string() == last.to_string()) {
+        message += ", all previous attempts have failed with the same error)";
+    } else {
+        message += "; previous errors: ";
+        message += &previous.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+        message += ")";
+    }
+
+    message.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn server_error(status: u16) -> GenericError {
+        Box::new(StatusError(StatusCode::from_u16(status).unwrap()))
+    }
+
+    #[rstest(value, expected,
+        case("0ms", Some(Duration::ZERO)),
+        case("500ms", Some(Duration::from_millis(500))),
+        case("2s", Some(Duration::from_secs(2))),
+        case("2", None),
+        case("1m", None),
+    )]
+    fn backoff_parsing(value: &str, expected: Option<Duration>) {
+        assert_eq!(parse_backoff(value).ok(), expected);
+    }
+
+    #[test]
+    fn retrying() {
+        let retrier = Retrier::new("Test", RetryPolicy::new(3, Duration::ZERO, 0));
+        let attempts = Cell::new(0);
+
+        let result = retrier.call(|| {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(server_error(503))
+            } else {
+                Ok(attempts.get())
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        attempts.set(0);
+        let error = retrier.call(|| -> GenericResult<()> {
+            attempts.set(attempts.get() + 1);
+            Err(server_error(500))
+        }).unwrap_err();
+        assert_eq!(attempts.get(), 3);
+        assert_eq!(error.to_string(), concat!(
+            "Server returned an error: 500 Internal Server Error ",
+            "(attempt #3, all previous attempts have failed with the same error)"));
+
+        attempts.set(0);
+        let error = retrier.call(|| -> GenericResult<()> {
+            attempts.set(attempts.get() + 1);
+            Err(server_error(404))
+        }).unwrap_err();
+        assert_eq!(attempts.get(), 1);
+        assert_eq!(error.to_string(), "Server returned an error: 404 Not Found");
+    }
+
+    #[test]
+    fn circuit_breaker() {
+        let retrier = Retrier::new("Test", RetryPolicy::new(2, Duration::ZERO, 3));
+        let attempts = Cell::new(0);
+
+        let call = || retrier.call(|| -> GenericResult<()> {
+            attempts.set(attempts.get() + 1);
+            Err(server_error(502))
+        });
+
+        call().unwrap_err();
+        assert_eq!(attempts.get(), 2);
+
+        let error = call().unwrap_err();
+        assert_eq!(attempts.get(), 3);
+        assert_eq!(error.to_string(), concat!(
+            "Test is temporarily unavailable: got 3 consecutive failures ",
+            "(attempt #2; previous errors: Server returned an error: 502 Bad Gateway)"));
+
+        call().unwrap_err();
+        assert_eq!(attempts.get(), 3);
+    }
+}
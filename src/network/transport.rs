@@ -0,0 +1,184 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::blocking::{Request, Response};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EmptyResult, GenericResult};
+
+use super::create_client;
+
+/// Executes HTTP requests. Network clients use the real network by default, but it may be replaced with recorded
+/// responses to test them offline.
+pub trait Transport: Send + Sync {
+    fn execute(&self, request: Request) -> GenericResult<Response>;
+}
+
+impl Transport for reqwest::blocking::Client {
+    fn execute(&self, request: Request) -> GenericResult<Response> {
+        Ok(reqwest::blocking::Client::execute(self, request)?)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Interaction {
+    method: String,
+    url: String,
+    status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    body: String,
+}
+
+impl Interaction {
+    // Bodies are stored decoded to be human-readable, but replayed in their original encoding
+    fn encoding(&self) -> &'static Encoding {
+        self.content_type.as_ref()
+            .and_then(|content_type| content_type.split(';').find_map(|param| {
+                param.trim().strip_prefix("charset=")
+            }))
+            .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+            .unwrap_or(UTF_8)
+    }
+}
+
+/// VCR-style transport: replays HTTP interactions previously recorded to a YAML file, so network clients can be
+/// tested against real server responses without network access. Only text responses are supported.
+pub struct Cassette {
+    path: PathBuf,
+    recorder: Option<reqwest::blocking::Client>,
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl Cassette {
+    /// Loads interactions to replay. Each recorded response is replayed only once in the order of recording.
+    pub fn load(path: &Path) -> GenericResult<Cassette> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+        let interactions: Vec<Interaction> = serde_yaml::from_str(&data).map_err(|e| format!(
+            "Error while reading {:?}: {}", path, e))?;
+
+        Ok(Cassette {
+            path: path.to_owned(),
+            recorder: None,
+            interactions: Mutex::new(interactions.into_iter().map(|interaction| (interaction, false)).collect()),
+        })
+    }
+
+    /// Sends requests to the real server saving all interactions to the specified file
+    pub fn record(path: &Path) -> GenericResult<Cassette> {
+        Ok(Cassette {
+            path: path.to_owned(),
+            recorder: Some(create_client(None)?),
+            interactions: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn replay(&self, method: &str, url: &str) -> GenericResult<Interaction> {
+        let mut interactions = self.interactions.lock().unwrap();
+
+        let (interaction, replayed) = interactions.iter_mut()
+            .find(|(interaction, replayed)| !replayed && interaction.method == method && interaction.url == url)
+            .ok_or_else(|| format!("{:?} has no recorded response for {} {}", self.path, method, url))?;
+
+        *replayed = true;
+        Ok(interaction.clone())
+    }
+
+    fn save(&self, interaction: Interaction) -> EmptyResult {
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push((interaction, true));
+
+        let data = serde_yaml::to_string(&interactions.iter().map(|(interaction, _)| interaction).collect::<Vec<_>>())?;
+        fs::write(&self.path, data).map_err(|e| format!("Failed to write {:?}: {}", self.path, e))?;
+
+        Ok(())
+    }
+}
+
+impl Transport for Cassette {
+    fn execute(&self, request: Request) -> GenericResult<Response> {
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+
+        let interaction = match self.recorder {
+            Some(ref client) => {
+                let response = client.execute(request)?;
+                let mut interaction = Interaction {
+                    method, url,
+                    status: response.status().as_u16(),
+                    content_type: response.headers().get(CONTENT_TYPE)
+                        .and_then(|value| value.to_str().ok()).map(ToOwned::to_owned),
+                    body: String::new(),
+                };
+
+                let data = response.bytes()?;
+                let (body, _, errors) = interaction.encoding().decode(&data);
+                if errors {
+                    return Err!("Got a non-text response from {}", interaction.url);
+                }
+                interaction.body = body.into_owned();
+
+                self.save(interaction.clone())?;
+                interaction
+            },
+            None => self.replay(&method, &url)?,
+        };
+
+        let (body, _, errors) = interaction.encoding().encode(&interaction.body);
+        if errors {
+            return Err!("Unable to encode recorded response for {} {}", interaction.method, interaction.url);
+        }
+
+        let mut response = http::Response::builder().status(interaction.status);
+        if let Some(ref content_type) = interaction.content_type {
+            response = response.header(CONTENT_TYPE, content_type);
+        }
+        let response = response.body(body.into_owned())?;
+
+        Ok(response.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use reqwest::StatusCode;
+
+    use super::*;
+
+    #[test]
+    fn replay() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("cassette.yaml");
+
+        fs::write(&path, indoc!(r#"
+            - method: GET
+              url: https://example.com/quotes?symbol=AAPL
+              status: 200
+              body: '{"price": 100}'
+            - method: GET
+              url: https://example.com/quotes?symbol=AAPL
+              status: 503
+              body: ''
+        "#)).unwrap();
+
+        let cassette = Cassette::load(&path).unwrap();
+        let client = reqwest::blocking::Client::new();
+        let request = || client.get("https://example.com/quotes?symbol=AAPL").build().unwrap();
+
+        let response = cassette.execute(request()).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().unwrap(), r#"{"price": 100}"#);
+
+        let response = cassette.execute(request()).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let error = cassette.execute(request()).unwrap_err().to_string();
+        assert!(error.ends_with(
+            "has no recorded response for GET https://example.com/quotes?symbol=AAPL"), "{}", error);
+    }
+}
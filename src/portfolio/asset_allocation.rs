@@ -4,7 +4,7 @@ use crate::broker_statement::BrokerStatement;
 use crate::brokers::BrokerInfo;
 use crate::config::{PortfolioConfig, AssetAllocationConfig};
 use crate::core::{EmptyResult, GenericResult};
-use crate::currency::Cash;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
 use crate::currency::converter::CurrencyConverter;
 use crate::quotes::{Quotes, QuoteQuery};
 use crate::trades;
@@ -12,6 +12,7 @@ use crate::types::{Decimal, TradeType};
 use crate::util;
 
 use super::Assets;
+use super::currency_conversion::CurrencyConversion;
 
 pub struct Portfolio {
     pub name: String,
@@ -22,12 +23,15 @@ pub struct Portfolio {
     pub min_cash_assets: Decimal,
 
     pub assets: Vec<AssetAllocation>,
+    pub cash_assets: MultiCurrencyCashAccount,
     pub current_cash_assets: Decimal,
     pub current_net_value: Decimal,
+    pub receivables: Decimal,
 
     pub target_cash_assets: Decimal,
     pub target_net_value: Decimal,
     pub commissions: Decimal,
+    pub conversions: Vec<CurrencyConversion>,
 }
 
 impl Portfolio {
@@ -51,16 +55,18 @@ impl Portfolio {
             return Err!("The portfolio has no asset allocation configuration");
         }
 
-        for symbol in config.get_stock_symbols() {
-            quotes.batch(match statement {
-                Some(statement) => statement.get_quote_query(&symbol),
-                None => QuoteQuery::Stock(symbol, broker.exchanges()),
-            })?;
-        }
+        batch_quotes(&config.assets, &broker, statement, quotes)?;
 
         let cash_assets = assets.cash.total_assets_real_time(currency, converter)?;
         let mut net_value = cash_assets;
 
+        // Accrued dividends and interest aren't available for trading yet, so they are reported, but don't participate
+        // in rebalancing. Dividends which are at risk of not being paid at all are excluded.
+        let receivables = match statement {
+            Some(statement) => statement.expected_receivables(converter, currency)?.map_or(dec!(0), |cash| cash.amount),
+            None => dec!(0),
+        };
+
         let mut stocks = assets.stocks;
         let mut symbols = HashSet::new();
         let mut assets_allocation = Vec::new();
@@ -86,12 +92,15 @@ impl Portfolio {
             min_cash_assets: min_cash_assets,
 
             assets: assets_allocation,
+            cash_assets: assets.cash,
             current_cash_assets: cash_assets,
             current_net_value: net_value,
+            receivables: receivables,
 
             target_cash_assets: cash_assets,
             target_net_value: net_value,
             commissions: dec!(0),
+            conversions: Vec::new(),
         };
         check_weights(&portfolio.name, &portfolio.assets)?;
 
@@ -128,6 +137,7 @@ pub struct StockHolding {
     pub currency_price: Cash,
     pub current_shares: Decimal,
     pub target_shares: Decimal,
+    pub pledged_shares: Decimal, // Shares pledged as collateral which can't be sold
     pub fractional_shares_trading: bool,
 }
 
@@ -200,10 +210,7 @@ impl AssetAllocation {
                         symbol);
                 }
 
-                let currency_price = quotes.get(match statement {
-                    Some(statement) => statement.get_quote_query(symbol),
-                    None => QuoteQuery::Stock(symbol.to_owned(), broker.exchanges()),
-                })?;
+                let currency_price = quotes.get(get_quote_query(config, symbol, broker, statement))?;
 
                 // Convert price with a reasonable precision. In other case we might get Decimal
                 // precision overflow which will lead to `price * quantity / price != quantity`.
@@ -213,12 +220,17 @@ impl AssetAllocation {
                 let shares = stocks.remove(symbol).unwrap_or_else(|| dec!(0));
                 let current_value = shares * price;
 
+                let pledged_shares = statement.map_or(dec!(0), |statement| {
+                    std::cmp::min(statement.pledged_quantity(symbol), shares)
+                });
+
                 let holding = StockHolding {
                     symbol: symbol.clone(),
                     price: price,
                     currency_price: currency_price,
                     current_shares: shares,
                     target_shares: shares,
+                    pledged_shares: pledged_shares,
                     fractional_shares_trading: broker.fractional_shares_trading,
                 };
 
@@ -334,6 +346,35 @@ impl AssetAllocation {
     }
 }
 
+fn batch_quotes(
+    assets: &[AssetAllocationConfig], broker: &BrokerInfo, statement: Option<&BrokerStatement>, quotes: &Quotes,
+) -> EmptyResult {
+    for asset in assets {
+        if let Some(ref symbol) = asset.symbol {
+            quotes.batch(get_quote_query(asset, symbol, broker, statement))?;
+        }
+
+        if let Some(ref assets) = asset.assets {
+            batch_quotes(assets, broker, statement, quotes)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn get_quote_query(
+    config: &AssetAllocationConfig, symbol: &str, broker: &BrokerInfo, statement: Option<&BrokerStatement>,
+) -> QuoteQuery {
+    if let Some(exchange) = config.exchange {
+        return QuoteQuery::Stock(symbol.to_owned(), vec![exchange]);
+    }
+
+    match statement {
+        Some(statement) => statement.get_quote_query(symbol),
+        None => QuoteQuery::Stock(symbol.to_owned(), broker.exchanges()),
+    }
+}
+
 fn check_weights(name: &str, assets: &[AssetAllocation]) -> EmptyResult {
     let mut weight = dec!(0);
 
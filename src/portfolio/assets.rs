@@ -3,6 +3,7 @@ use std::ops::DerefMut;
 use std::str::FromStr;
 
 use diesel::{self, prelude::*};
+use static_table_derive::StaticTable;
 
 use crate::config::PortfolioConfig;
 use crate::core::{EmptyResult, GenericError, GenericResult};
@@ -77,6 +78,22 @@ impl Assets {
         Ok(())
     }
 
+    /// Returns open positions which have been added, removed or changed in comparison with the previous assets state
+    pub fn diff(&self, previous: &Assets) -> Vec<PositionChange> {
+        let mut symbols: Vec<&String> = self.stocks.keys().chain(previous.stocks.keys()).collect();
+        symbols.sort_unstable();
+        symbols.dedup();
+
+        symbols.into_iter().filter_map(|symbol| {
+            let change = PositionChange {
+                symbol: symbol.clone(),
+                previous: previous.stocks.get(symbol).cloned(),
+                current: self.stocks.get(symbol).cloned(),
+            };
+            (change.previous != change.current).then_some(change)
+        }).collect()
+    }
+
     pub fn save(&self, database: db::Connection, portfolio: &str) -> EmptyResult {
         database.borrow().transaction::<_, GenericError, _>(|db| {
             diesel::delete(assets::table.filter(assets::portfolio.eq(portfolio)))
@@ -111,10 +128,75 @@ impl Assets {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct PositionChange {
+    pub symbol: String,
+    pub previous: Option<Decimal>,
+    pub current: Option<Decimal>,
+}
+
+#[derive(StaticTable)]
+#[table(name="PositionChangesTable")]
+struct Row {
+    #[column(name="Symbol")]
+    symbol: String,
+    #[column(name="Previous")]
+    previous: Option<Decimal>,
+    #[column(name="Current")]
+    current: Option<Decimal>,
+    #[column(name="Change")]
+    change: String,
+}
+
+pub fn print_position_changes(changes: &[PositionChange]) {
+    let mut table = PositionChangesTable::new();
+
+    for change in changes {
+        let previous = change.previous.unwrap_or_default();
+        let current = change.current.unwrap_or_default();
+
+        table.add_row(Row {
+            symbol: change.symbol.clone(),
+            previous: change.previous.map(|quantity| quantity.normalize()),
+            current: change.current.map(|quantity| quantity.normalize()),
+            change: match (change.previous, change.current) {
+                (None, _) => s!("Added"),
+                (_, None) => s!("Removed"),
+                _ => format!("{:+}", (current - previous).normalize()),
+            },
+        });
+    }
+
+    table.print("Open positions changes");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn diff() {
+        let previous = Assets::new(MultiCurrencyCashAccount::new(), hashmap! {
+            s!("AAA") => dec!(10),
+            s!("BBB") => dec!(20),
+            s!("CCC") => dec!(30),
+        });
+
+        let current = Assets::new(MultiCurrencyCashAccount::new(), hashmap! {
+            s!("BBB") => dec!(20),
+            s!("CCC") => dec!(25),
+            s!("DDD") => dec!(5),
+        });
+
+        assert_eq!(current.diff(&previous), vec![
+            PositionChange {symbol: s!("AAA"), previous: Some(dec!(10)), current: None},
+            PositionChange {symbol: s!("CCC"), previous: Some(dec!(30)), current: Some(dec!(25))},
+            PositionChange {symbol: s!("DDD"), previous: None, current: Some(dec!(5))},
+        ]);
+
+        assert_eq!(current.diff(&current), vec![]);
+    }
+
     #[test]
     fn save_load() {
         let (_database, connection) = db::new_temporary();
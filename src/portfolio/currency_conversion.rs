@@ -0,0 +1,121 @@
+use std::cmp::Ordering;
+
+use crate::commissions::CommissionCalc;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverterRc;
+use crate::types::{Date, Decimal, TradeType};
+
+use super::asset_allocation::{AssetAllocation, Holding, Portfolio};
+
+pub struct CurrencyConversion {
+    pub sell: Cash,
+    pub buy: Cash,
+    pub rate: Decimal,
+    pub commission: Cash,
+}
+
+/// Plans currency conversions which are required to execute the rebalancing trades: target allocation may require
+/// buying assets in a currency which there is not enough free cash in.
+pub fn plan(portfolio: &Portfolio, converter: CurrencyConverterRc) -> GenericResult<Vec<CurrencyConversion>> {
+    let date = crate::exchanges::today_trade_conclusion_time().date;
+    let mut commission_calc = CommissionCalc::new(
+        converter.clone(), portfolio.broker.commission_spec.clone(),
+        Cash::new(&portfolio.currency, portfolio.current_net_value))?;
+
+    let mut balance = portfolio.cash_assets.clone();
+    calculate_balance(&portfolio.assets, date, &mut balance, &mut commission_calc)?;
+
+    let mut deficits = Vec::new();
+    let mut surpluses = Vec::new();
+
+    for cash in balance.iter() {
+        if cash.is_negative() {
+            deficits.push(-cash);
+        } else if cash.is_positive() {
+            let value = converter.real_time_convert_to(cash, &portfolio.currency)?;
+            surpluses.push((cash, value));
+        }
+    }
+
+    // Prefer to convert cash in portfolio currency and then the largest balances
+    surpluses.sort_by(|(a, a_value), (b, b_value)| {
+        match (a.currency == portfolio.currency, b.currency == portfolio.currency) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => b_value.cmp(a_value),
+        }
+    });
+
+    let mut conversions = Vec::new();
+
+    for mut need in deficits {
+        for (available, _) in &mut surpluses {
+            if !need.is_positive() {
+                break;
+            } else if !available.is_positive() {
+                continue;
+            }
+
+            let rate = converter.real_time_currency_rate(need.currency, available.currency)?;
+
+            let mut buy = need.round();
+            let mut sell = Cash::new(available.currency, buy.amount * rate).round();
+            let mut commission = commission_calc.add_trade(date, TradeType::Buy, dec!(1), sell)?;
+
+            if sell.amount + commission.amount > available.amount {
+                sell = Cash::new(available.currency, available.amount - commission.amount).round();
+                if !sell.is_positive() {
+                    continue;
+                }
+
+                commission = commission_calc.add_trade(date, TradeType::Buy, dec!(1), sell)?;
+                buy = Cash::new(need.currency, sell.amount / rate).round();
+            }
+
+            available.amount -= sell.amount + commission.amount;
+            need.amount -= buy.amount;
+
+            conversions.push(CurrencyConversion {sell, buy, rate, commission});
+        }
+    }
+
+    Ok(conversions)
+}
+
+fn calculate_balance(
+    assets: &[AssetAllocation], date: Date, balance: &mut MultiCurrencyCashAccount,
+    commission_calc: &mut CommissionCalc,
+) -> EmptyResult {
+    for asset in assets {
+        let holding = match &asset.holding {
+            Holding::Stock(holding) => holding,
+            Holding::Group(assets) => {
+                calculate_balance(assets, date, balance, commission_calc)?;
+                continue;
+            },
+        };
+
+        if holding.target_shares == holding.current_shares {
+            continue;
+        }
+
+        let (trade_type, shares) = if holding.target_shares > holding.current_shares {
+            (TradeType::Buy, holding.target_shares - holding.current_shares)
+        } else {
+            (TradeType::Sell, holding.current_shares - holding.target_shares)
+        };
+
+        let volume = holding.currency_price * shares;
+        let commission = commission_calc.add_trade(date, trade_type, shares, holding.currency_price)
+            .map_err(|e| format!("{}: {}", asset.full_name(), e))?;
+
+        match trade_type {
+            TradeType::Buy => balance.withdraw(volume),
+            TradeType::Sell => balance.deposit(volume),
+        }
+        balance.withdraw(commission);
+    }
+
+    Ok(())
+}
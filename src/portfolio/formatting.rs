@@ -3,21 +3,26 @@ use std::fmt::Write;
 use ansi_term::{Style, Color, ANSIString};
 
 use crate::currency::Cash;
+use crate::formatting;
+use crate::profiling::{self, Phase};
 use crate::types::Decimal;
 use crate::util;
 
 use super::asset_allocation::{Portfolio, AssetAllocation, Holding};
 
 pub fn print_portfolio(portfolio: Portfolio, flat: bool) {
+    let _timer = profiling::start(Phase::Rendering);
     let mut assets = portfolio.assets;
     if flat {
         assets = flatify(assets, dec!(1));
     }
 
-    print_assets(assets, portfolio.target_net_value - portfolio.min_cash_assets, &portfolio.currency, 0);
+    let quantity_increment = portfolio.broker.quantity_increment();
+    print_assets(
+        assets, portfolio.target_net_value - portfolio.min_cash_assets, &portfolio.currency, quantity_increment, 0);
 
     println!("\n{} {}", colorify_title("Total value:"),
-             format_cash(&portfolio.currency, portfolio.target_net_value));
+             format_cash(&portfolio.currency, portfolio.target_net_value + portfolio.receivables));
 
     print!("{} {}", colorify_title("Cash assets:"),
            format_cash(&portfolio.currency, portfolio.current_cash_assets));
@@ -26,10 +31,33 @@ pub fn print_portfolio(portfolio: Portfolio, flat: bool) {
     }
     println!();
 
+    if !portfolio.receivables.is_zero() {
+        println!("{} {}", colorify_title("Receivables:"), format_cash(&portfolio.currency, portfolio.receivables));
+    }
+
     if !portfolio.commissions.is_zero() {
         println!("{} {}", colorify_title("Commissions:"),
                  colorify_commission(&format_cash(&portfolio.currency, portfolio.commissions)));
     }
+
+    if !portfolio.conversions.is_empty() {
+        println!("\n{}", colorify_title("Currency conversions:"));
+
+        for conversion in &portfolio.conversions {
+            let mut buffer = format!(
+                "• {sell} → {buy} (estimated rate: {rate})",
+                sell=colorify_sell(&conversion.sell.format_rounded()),
+                buy=colorify_buy(&conversion.buy.format_rounded()),
+                rate=formatting::format_number(util::round(conversion.rate, 4)));
+
+            if !conversion.commission.is_zero() {
+                write!(&mut buffer, ", commission: {}",
+                       colorify_commission(&conversion.commission.format_rounded())).unwrap();
+            }
+
+            println!("{}", buffer);
+        }
+    }
 }
 
 fn flatify(assets: Vec<AssetAllocation>, expected_weight: Decimal) -> Vec<AssetAllocation> {
@@ -51,15 +79,20 @@ fn flatify(assets: Vec<AssetAllocation>, expected_weight: Decimal) -> Vec<AssetA
     flat_assets
 }
 
-fn print_assets(mut assets: Vec<AssetAllocation>, expected_total_value: Decimal, currency: &str, depth: usize) {
+fn print_assets(
+    mut assets: Vec<AssetAllocation>, expected_total_value: Decimal, currency: &str, quantity_increment: Decimal,
+    depth: usize,
+) {
     assets.sort_by_key(|asset: &AssetAllocation| -asset.target_value);
 
     for asset in assets {
-        print_asset(asset, expected_total_value, currency, depth);
+        print_asset(asset, expected_total_value, currency, quantity_increment, depth);
     }
 }
 
-fn print_asset(asset: AssetAllocation, expected_total_value: Decimal, currency: &str, depth: usize) {
+fn print_asset(
+    asset: AssetAllocation, expected_total_value: Decimal, currency: &str, quantity_increment: Decimal, depth: usize,
+) {
     let expected_value = expected_total_value * asset.expected_weight;
 
     let mut buffer = String::new();
@@ -74,10 +107,17 @@ fn print_asset(asset: AssetAllocation, expected_total_value: Decimal, currency:
         write!(&mut buffer, " {}", colorify_restriction("[sell blocked]")).unwrap();
     }
 
+    if let Holding::Stock(ref holding) = asset.holding {
+        if !holding.pledged_shares.is_zero() {
+            let pledged = format!("[{} pledged]", format_shares(holding.pledged_shares, quantity_increment, false));
+            write!(&mut buffer, " {}", colorify_restriction(&pledged)).unwrap();
+        }
+    }
+
     write!(&mut buffer, " -").unwrap();
 
     if let Holding::Stock(ref holding) = asset.holding {
-        write!(&mut buffer, " {}", format_shares(holding.current_shares, false)).unwrap();
+        write!(&mut buffer, " {}", format_shares(holding.current_shares, quantity_increment, false)).unwrap();
     }
 
     write!(&mut buffer, " {current_weight} ({current_value})",
@@ -97,7 +137,7 @@ fn print_asset(asset: AssetAllocation, expected_total_value: Decimal, currency:
 
             let changes = format!(
                 "{shares_change} ({value_change})",
-                shares_change=format_shares(shares_change, true),
+                shares_change=format_shares(shares_change, quantity_increment, true),
                 value_change=format_cash(currency, value_change.abs()));
 
             write!(&mut buffer, " {}", colorify_func(&changes)).unwrap();
@@ -114,7 +154,7 @@ fn print_asset(asset: AssetAllocation, expected_total_value: Decimal, currency:
 
     if let Holding::Group(holdings) = asset.holding {
         println!("{}:", buffer);
-        print_assets(holdings, expected_value, currency, depth + 1);
+        print_assets(holdings, expected_value, currency, quantity_increment, depth + 1);
     } else {
         println!("{}", buffer);
     }
@@ -124,14 +164,14 @@ fn format_cash(currency: &str, amount: Decimal) -> String {
     Cash::new(currency, amount).format_rounded()
 }
 
-fn format_shares(shares: Decimal, with_sign: bool) -> String {
-    let shares = shares.normalize();
+fn format_shares(shares: Decimal, increment: Decimal, with_sign: bool) -> String {
+    let shares = formatting::round_quantity(shares, increment);
     let symbol = 's';
 
     if with_sign {
-        format!("{:+}{}", shares, symbol)
+        format!("{}{}", formatting::format_number(format!("{:+}", shares)), symbol)
     } else {
-        format!("{}{}", shares, symbol)
+        format!("{}{}", formatting::format_number(shares), symbol)
     }
 }
 
@@ -147,7 +187,7 @@ fn format_weight(weight: Decimal) -> String {
     if weight == Decimal::MAX {
         s!("∞")
     } else {
-        format!("{}%", util::round(weight * dec!(100), 2))
+        format!("{}%", formatting::format_number(util::round(weight * dec!(100), 2)))
     }
 }
 
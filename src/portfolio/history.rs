@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use log::warn;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, StockSellType, StockSource};
+use crate::cash_flow;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting::{self, table::Cell};
+use crate::quotes::{HistoricalQuoteKind, Quotes};
+use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
+use crate::util;
+
+/// Portfolio state as of the end of some past date reconstructed from broker statements
+pub struct HistoricalPortfolio {
+    pub date: Date,
+    pub currency: String,
+    pub positions: Vec<HistoricalPosition>,
+    pub cash: Vec<HistoricalCash>,
+}
+
+pub struct HistoricalPosition {
+    pub symbol: String,
+    pub name: String,
+    pub quantity: Decimal,
+    pub price: Cash,
+    pub value: Decimal,
+    /// Describes how the price has been determined if it's not an actual quote
+    pub note: Option<String>,
+}
+
+pub struct HistoricalCash {
+    pub amount: Cash,
+    pub value: Decimal,
+}
+
+impl HistoricalPortfolio {
+    pub fn total_value(&self) -> Decimal {
+        self.positions.iter().map(|position| position.value)
+            .chain(self.cash.iter().map(|cash| cash.value))
+            .sum()
+    }
+}
+
+/// Reconstructs positions and cash assets as of the end of the specified date. Positions are valued using historical
+/// quotes when they are available and the last trade price from the broker statement otherwise.
+pub fn reconstruct(
+    statement: &BrokerStatement, date: Date, currency: &str, converter: &CurrencyConverter, quotes: &Quotes,
+) -> GenericResult<HistoricalPortfolio> {
+    if !statement.period.contains(date) {
+        return Err!(
+            "Unable to reconstruct the portfolio as of {}: the broker statement period is {}",
+            formatting::format_date(date), statement.period.format());
+    }
+
+    let quantities = get_positions(statement, date);
+    quotes.prefetch_historical(quantities.keys().map(|symbol| (statement.get_quote_query(symbol), date)))?;
+
+    let mut positions = Vec::new();
+
+    for (symbol, quantity) in quantities {
+        let (price, note) = match quotes.get_historical(statement.get_quote_query(&symbol), date)? {
+            Some(quote) => match quote.kind {
+                HistoricalQuoteKind::Actual => (quote.price, None),
+                HistoricalQuoteKind::Stale => {
+                    warn!("There are no quotes for {} since {}. Using the last known price: {}.",
+                          symbol, formatting::format_date(quote.date), quote.price);
+                    (quote.price, Some(format!("Stale since {}", formatting::format_date(quote.date))))
+                },
+                HistoricalQuoteKind::Interpolated => {
+                    warn!("There is a gap in {} quotes as of {}. Using the interpolated price: {}.",
+                          symbol, formatting::format_date(date), quote.price);
+                    (quote.price, Some(s!("Interpolated")))
+                },
+            },
+            None => {
+                let price = get_last_trade_price(statement, &symbol, date).ok_or_else(|| format!(
+                    "Unable to determine {} price as of {}", symbol, formatting::format_date(date)))?;
+
+                warn!(concat!(
+                    "There is no historical quote for {} as of {} (historical quotes are available only for MOEX ",
+                    "instruments). Using the last trade price from the broker statement: {}."),
+                    symbol, formatting::format_date(date), price);
+
+                (price, Some(s!("Last trade price")))
+            },
+        };
+
+        positions.push(HistoricalPosition {
+            name: statement.instrument_info.get_name(&symbol),
+            value: converter.convert_to(date, price * quantity, currency)?,
+            symbol, quantity, price, note,
+        });
+    }
+
+    let mut cash = Vec::new();
+
+    for amount in cash_flow::calculate_cash_assets(statement, date).iter() {
+        let amount = amount.round();
+        if !amount.is_zero() {
+            cash.push(HistoricalCash {
+                amount,
+                value: converter.convert_to(date, amount, currency)?,
+            });
+        }
+    }
+
+    Ok(HistoricalPortfolio {
+        date,
+        currency: currency.to_owned(),
+        positions, cash,
+    })
+}
+
+fn get_positions(statement: &BrokerStatement, date: Date) -> BTreeMap<String, Decimal> {
+    let mut positions: BTreeMap<String, Decimal> = BTreeMap::new();
+    let time = DateOptTime::new_max_time(date);
+
+    let mut add = |symbol: &str, quantity: Decimal, conclusion_time: DateOptTime| {
+        let multiplier = statement.stock_splits.get_multiplier(symbol, conclusion_time, time);
+        *positions.entry(symbol.to_owned()).or_default() += multiplier * quantity;
+    };
+
+    for trade in &statement.stock_buys {
+        if trade.conclusion_time.date <= date {
+            add(&trade.symbol, trade.quantity, trade.conclusion_time);
+        }
+    }
+
+    for trade in &statement.stock_sells {
+        if !trade.emulation && trade.conclusion_time.date <= date {
+            add(&trade.symbol, -trade.quantity, trade.conclusion_time);
+        }
+    }
+
+    positions.retain(|_, quantity| !quantity.is_zero());
+    positions
+}
+
+fn get_last_trade_price(statement: &BrokerStatement, symbol: &str, date: Date) -> Option<Cash> {
+    let buys = statement.stock_buys.iter().filter_map(|trade| match trade.type_ {
+        StockSource::Trade {price, ..} if trade.symbol == symbol => Some((trade.conclusion_time, price)),
+        _ => None,
+    });
+
+    let sells = statement.stock_sells.iter().filter_map(|trade| match trade.type_ {
+        StockSellType::Trade {price, ..} if !trade.emulation && trade.symbol == symbol => {
+            Some((trade.conclusion_time, price))
+        },
+        _ => None,
+    });
+
+    let (conclusion_time, price) = buys.chain(sells)
+        .filter(|(conclusion_time, price)| conclusion_time.date <= date && !price.is_zero())
+        .max_by_key(|&(conclusion_time, _)| conclusion_time)?;
+
+    let multiplier = statement.stock_splits.get_multiplier(
+        symbol, conclusion_time, DateOptTime::new_max_time(date));
+
+    Some(Cash::new(price.currency, price.amount / multiplier))
+}
+
+#[derive(StaticTable)]
+#[table(name="HistoricalPortfolioTable")]
+struct Row {
+    #[column(name="Instrument")]
+    symbol: String,
+    #[column(name="Name")]
+    name: String,
+    #[column(name="Quantity")]
+    quantity: Option<Cell>,
+    #[column(name="Price")]
+    price: Option<Cash>,
+    #[column(name="Value")]
+    value: Cell,
+    #[column(name="Note")]
+    note: Option<String>,
+}
+
+impl HistoricalPortfolio {
+    pub fn print(&self, title: &str, quantity_increment: Decimal) {
+        let round = |value| Cell::new_round_decimal(util::round(value, 0));
+        let mut table = HistoricalPortfolioTable::new();
+
+        for position in &self.positions {
+            table.add_row(Row {
+                symbol: position.symbol.clone(),
+                name: position.name.clone(),
+                quantity: Some(Cell::new_quantity(position.quantity, quantity_increment)),
+                price: Some(position.price),
+                value: round(position.value),
+                note: position.note.clone(),
+            });
+        }
+
+        for cash in &self.cash {
+            table.add_row(Row {
+                symbol: cash.amount.currency.to_owned(),
+                name: s!("Cash"),
+                quantity: Some(cash.amount.amount.into()),
+                price: None,
+                value: round(cash.value),
+                note: None,
+            });
+        }
+
+        let approximate = self.positions.iter().any(|position| position.note.is_some());
+        if !approximate {
+            table.hide_note();
+        }
+
+        let mut totals = table.add_empty_row();
+        totals.set_value(round(self.total_value()));
+        if approximate {
+            totals.set_note(Some(s!("Approximate")));
+        }
+
+        table.print(&format!(
+            "{} as of {} ({})", title, formatting::format_date(self.date), self.currency));
+    }
+}
@@ -0,0 +1,201 @@
+use std::str::FromStr;
+
+use log::warn;
+use serde::Deserialize;
+use serde::de::{Deserializer, Error};
+
+use crate::broker_statement::BrokerStatement;
+use crate::commissions::CommissionCalc;
+use crate::config::PortfolioConfig;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverterRc;
+use crate::formatting;
+use crate::localities::Country;
+use crate::quotes::Quotes;
+use crate::taxes::TaxCalculator;
+use crate::time::{self, Date};
+use crate::types::Decimal;
+use crate::util;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct IdleCashConfig {
+    threshold: IdleCashThreshold,
+    days: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum IdleCashThreshold {
+    Amount(Decimal),
+    Ratio(Decimal),
+}
+
+impl<'de> Deserialize<'de> for IdleCashThreshold {
+    fn deserialize<D>(deserializer: D) -> Result<IdleCashThreshold, D::Error>
+        where D: Deserializer<'de>
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Amount(Decimal),
+            String(String),
+        }
+
+        let value = Value::deserialize(deserializer)?;
+        let string = match value {
+            Value::Amount(amount) => return Ok(IdleCashThreshold::Amount(amount)),
+            Value::String(string) => string,
+        };
+
+        parse_threshold(&string).ok_or_else(|| D::Error::custom(format!(
+            "Invalid idle cash threshold: {:?}", string)))
+    }
+}
+
+fn parse_threshold(value: &str) -> Option<IdleCashThreshold> {
+    Some(match value.strip_suffix('%') {
+        Some(percent) => {
+            let percent = Decimal::from_str(percent.trim()).ok()?;
+            if !percent.is_sign_positive() || percent > dec!(100) {
+                return None;
+            }
+            IdleCashThreshold::Ratio(percent / dec!(100))
+        },
+        None => IdleCashThreshold::Amount(Decimal::from_str(value).ok()?),
+    })
+}
+
+/// Warns about cash which stays uninvested longer than configured in the portfolio's idle cash settings
+pub fn check(
+    country: &Country, portfolio: &PortfolioConfig, statement: &BrokerStatement,
+    converter: &CurrencyConverterRc, quotes: &Quotes,
+) -> EmptyResult {
+    let Some(config) = portfolio.idle_cash else {
+        return Ok(());
+    };
+
+    let today = time::today();
+    let currency = portfolio.currency();
+
+    let threshold = match config.threshold {
+        IdleCashThreshold::Amount(amount) => amount,
+        IdleCashThreshold::Ratio(ratio) => {
+            statement.net_value(converter, quotes, currency, true)?.amount * ratio
+        },
+    };
+
+    let cash_assets = statement.assets.cash.total_assets_real_time(currency, converter)?;
+    if cash_assets <= threshold {
+        return Ok(());
+    }
+
+    // Broker statements give us cash assets at the end of each statement period, so find the first period since which
+    // cash assets stay above the threshold.
+    let mut since = None;
+
+    for (&date, assets) in statement.historical_assets.iter().rev() {
+        if assets.cash.total_assets(date, currency, converter)? <= threshold {
+            break;
+        }
+        since = Some(date);
+    }
+
+    let Some(since) = since else {
+        return Ok(());
+    };
+
+    let days = (today - since).num_days();
+    if days < i64::from(config.days) {
+        return Ok(());
+    }
+
+    let reserve = calculate_reserve(country, portfolio, statement, converter, today)?;
+    let available = cash_assets - reserve;
+    if available <= threshold {
+        return Ok(());
+    }
+
+    let format = |amount| Cash::new(currency, util::round(amount, 0));
+    warn!(concat!(
+        "{:?} portfolio has {} of idle cash since {} ({} days). ",
+        "{} is available to invest after reserving {} for upcoming fees and taxes."),
+        portfolio.name, format(cash_assets), formatting::format_date(since), days,
+        format(available), format(reserve));
+
+    Ok(())
+}
+
+fn calculate_reserve(
+    country: &Country, portfolio: &PortfolioConfig, statement: &BrokerStatement,
+    converter: &CurrencyConverterRc, today: Date,
+) -> GenericResult<Decimal> {
+    let currency = portfolio.currency();
+    let mut reserve = portfolio.min_cash_assets.unwrap_or_default();
+
+    // Commissions which are already accrued but not charged yet
+    let commission_calc = CommissionCalc::new(
+        converter.clone(), statement.broker.commission_spec.clone(), Cash::zero(currency))?;
+
+    for commissions in commission_calc.calculate()?.values() {
+        reserve += commissions.total_assets_real_time(currency, converter)?;
+    }
+
+    // Taxes on already received income which are going to be paid in the future
+    let tax_payment_day = portfolio.tax_payment_day();
+    let mut tax_calculator = TaxCalculator::new(country.clone());
+
+    let mut add_tax = |date: Date, tax: Cash| -> EmptyResult {
+        let (_, tax_payment_date) = tax_payment_day.get(date, false);
+        if tax_payment_date > today && !tax.is_zero() {
+            reserve += converter.real_time_convert_to(tax, currency)?;
+        }
+        Ok(())
+    };
+
+    for dividend in &statement.dividends {
+        add_tax(dividend.date, dividend.tax(country, converter, &mut tax_calculator)?.to_pay)?;
+    }
+
+    for coupon in &statement.coupons {
+        add_tax(coupon.date, coupon.tax(country, converter, &mut tax_calculator)?)?;
+    }
+
+    for interest in &statement.idle_cash_interest {
+        add_tax(interest.date, interest.tax(country, converter, &mut tax_calculator)?)?;
+    }
+
+    for interest in statement.securities_lending_income().chain(statement.repo_income()) {
+        add_tax(interest.date, interest.tax(country, converter, &mut tax_calculator)?)?;
+    }
+
+    Ok(reserve)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("1000", Some(IdleCashThreshold::Amount(dec!(1000)))),
+        case("5%", Some(IdleCashThreshold::Ratio(dec!(0.05)))),
+        case("12.5%", Some(IdleCashThreshold::Ratio(dec!(0.125)))),
+        case("101%", None),
+        case("-1%", None),
+        case("x", None),
+    )]
+    fn threshold_parsing(value: &str, expected: Option<IdleCashThreshold>) {
+        assert_eq!(parse_threshold(value), expected);
+    }
+
+    #[rstest(config, expected,
+        case("threshold: 1000\ndays: 30", IdleCashThreshold::Amount(dec!(1000))),
+        case("threshold: 5%\ndays: 30", IdleCashThreshold::Ratio(dec!(0.05))),
+    )]
+    fn config_parsing(config: &str, expected: IdleCashThreshold) {
+        let config: IdleCashConfig = serde_yaml::from_str(config).unwrap();
+        assert_eq!(config.threshold, expected);
+        assert_eq!(config.days, 30);
+    }
+}
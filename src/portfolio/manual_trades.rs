@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+use diesel::{self, prelude::*};
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::db::{self, schema::manual_trades, models};
+use crate::time::Date;
+use crate::types::Decimal;
+
+/// A trade recorded manually via `buy`/`sell` commands for portfolios without broker statements
+pub struct ManualTrade {
+    pub date: Date,
+    pub symbol: String,
+    pub quantity: Decimal, // Positive for buys and negative for sells
+    pub price: Cash,
+}
+
+impl ManualTrade {
+    pub fn load(database: db::Connection, portfolio: &str) -> GenericResult<Vec<ManualTrade>> {
+        let rows: Vec<(Date, String, String, String, String)> = manual_trades::table
+            .select((
+                manual_trades::date, manual_trades::symbol, manual_trades::quantity, manual_trades::price,
+                manual_trades::currency,
+            ))
+            .filter(manual_trades::portfolio.eq(portfolio))
+            .order_by((manual_trades::date.asc(), manual_trades::id.asc()))
+            .load(&mut *database.borrow())?;
+
+        rows.into_iter().map(|(date, symbol, quantity, price, currency)| {
+            let quantity = Decimal::from_str(&quantity).map_err(|_| format!(
+                "Got an invalid {} trade quantity from the database: {:?}", symbol, quantity))?;
+            let price = Cash::new_from_string(&currency, &price).map_err(|e| format!(
+                "Got an invalid {} trade price from the database: {}", symbol, e))?;
+            Ok(ManualTrade {date, symbol, quantity, price})
+        }).collect()
+    }
+
+    pub fn save(&self, database: db::Connection, portfolio: &str) -> EmptyResult {
+        diesel::insert_into(manual_trades::table)
+            .values(models::NewManualTrade {
+                portfolio,
+                date: self.date,
+                symbol: &self.symbol,
+                quantity: self.quantity.to_string(),
+                price: self.price.amount.to_string(),
+                currency: self.price.currency,
+            })
+            .execute(&mut *database.borrow())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage() {
+        let (_database, connection) = db::new_temporary();
+
+        for (portfolio, date, symbol, quantity) in [
+            ("main", date!(2024, 3, 1), "VTI", dec!(-3)),
+            ("other", date!(2024, 1, 1), "BND", dec!(1)),
+            ("main", date!(2024, 2, 1), "VTI", dec!(10)),
+        ] {
+            ManualTrade {
+                date, symbol: symbol.to_owned(), quantity, price: Cash::new("USD", dec!(200.5)),
+            }.save(connection.clone(), portfolio).unwrap();
+        }
+
+        let trades = ManualTrade::load(connection, "main").unwrap();
+        assert_eq!(
+            trades.iter().map(|trade| (trade.date, trade.quantity, trade.price)).collect::<Vec<_>>(),
+            vec![
+                (date!(2024, 2, 1), dec!(10), Cash::new("USD", dec!(200.5))),
+                (date!(2024, 3, 1), dec!(-3), Cash::new("USD", dec!(200.5))),
+            ],
+        );
+    }
+}
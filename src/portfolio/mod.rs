@@ -1,7 +1,7 @@
 use std::collections::hash_map::Entry;
 use std::rc::Rc;
 
-use crate::broker_statement::{BrokerStatement, ReadingStrictness};
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections};
 use crate::config::{Config, PortfolioConfig};
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
@@ -9,18 +9,28 @@ use crate::currency::converter::CurrencyConverter;
 use crate::db;
 use crate::quotes::Quotes;
 use crate::telemetry::TelemetryRecordBuilder;
+use crate::formatting::format_date;
+use crate::time::{self, Date};
 use crate::types::Decimal;
 
 use self::asset_allocation::Portfolio;
-use self::assets::Assets;
+use self::assets::{Assets, print_position_changes};
 use self::formatting::print_portfolio;
 
 mod asset_allocation;
 mod assets;
+mod currency_conversion;
 mod formatting;
+mod history;
+mod idle_cash;
+mod manual_trades;
 mod rebalancing;
 
-pub fn sync(config: &Config, portfolio_name: &str) -> GenericResult<TelemetryRecordBuilder> {
+pub use self::history::{HistoricalPortfolio, HistoricalPosition, HistoricalCash, reconstruct};
+pub use self::idle_cash::{IdleCashConfig, check as check_idle_cash};
+pub use self::manual_trades::ManualTrade;
+
+pub fn sync(config: &Config, portfolio_name: &str, dry_run: bool) -> GenericResult<TelemetryRecordBuilder> {
     let portfolio = config.get_portfolio(portfolio_name)?;
     let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
     let database = db::connect(&config.db_path)?;
@@ -28,20 +38,33 @@ pub fn sync(config: &Config, portfolio_name: &str) -> GenericResult<TelemetryRec
     let statement = BrokerStatement::read(
         broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
         &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
-        &portfolio.corporate_actions, ReadingStrictness::empty())?;
+        &portfolio.corporate_actions, ReadingStrictness::empty(), StatementSections::empty())?;
     statement.check_date();
 
     let assets = Assets::new(statement.assets.cash, statement.open_positions);
     assets.validate(portfolio)?;
-    assets.save(database, &portfolio.name)?;
+
+    let changes = assets.diff(&Assets::load(database.clone(), &portfolio.name)?);
+    if changes.is_empty() {
+        println!("Open positions haven't changed.");
+    } else {
+        print_position_changes(&changes);
+    }
+
+    if dry_run {
+        println!("Dry run mode: the changes haven't been saved.");
+    } else {
+        assets.save(database, &portfolio.name)?;
+    }
 
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
 }
 
 pub fn buy(
     config: &Config, portfolio_name: &str, positions: &[(String, Decimal)], cash_assets: Decimal,
+    price: Option<Decimal>, date: Option<Date>,
 ) -> GenericResult<TelemetryRecordBuilder> {
-    modify_assets(config, portfolio_name, |portfolio, assets| {
+    modify_assets(config, portfolio_name, |portfolio, database, assets| {
         let asset_allocation_symbols = portfolio.get_stock_symbols();
 
         for (symbol, quantity) in positions {
@@ -56,15 +79,20 @@ pub fn buy(
                 .or_insert(*quantity);
         }
 
+        let trades: Vec<_> = positions.iter().map(|(symbol, quantity)| (symbol.as_str(), *quantity)).collect();
+        record_manual_trade(portfolio, database, &trades, price, date)?;
+
         set_cash_assets_impl(portfolio, assets, cash_assets)
     })
 }
 
 pub fn sell(
     config: &Config, portfolio_name: &str, positions: &[(String, Option<Decimal>)],
-    cash_assets: Decimal,
+    cash_assets: Decimal, price: Option<Decimal>, date: Option<Date>,
 ) -> GenericResult<TelemetryRecordBuilder> {
-    modify_assets(config, portfolio_name, |portfolio, assets| {
+    modify_assets(config, portfolio_name, |portfolio, database, assets| {
+        let mut trades = Vec::new();
+
         for (symbol, quantity) in positions {
             let mut entry = match assets.stocks.entry(symbol.to_owned()) {
                 Entry::Occupied(entry) => entry,
@@ -88,31 +116,66 @@ pub fn sell(
             } else {
                 *current = (*current - quantity).normalize();
             }
+
+            trades.push((symbol.as_str(), -quantity));
         }
 
+        record_manual_trade(portfolio, database, &trades, price, date)?;
         set_cash_assets_impl(portfolio, assets, cash_assets)
     })
 }
 
 pub fn set_cash_assets(config: &Config, portfolio_name: &str, cash_assets: Decimal) -> GenericResult<TelemetryRecordBuilder> {
-    modify_assets(config, portfolio_name, |portfolio, assets| {
+    modify_assets(config, portfolio_name, |portfolio, _database, assets| {
         set_cash_assets_impl(portfolio, assets, cash_assets)
     })
 }
 
 fn modify_assets<F>(config: &Config, portfolio_name: &str, modify: F) -> GenericResult<TelemetryRecordBuilder>
-    where F: Fn(&PortfolioConfig, &mut Assets) -> EmptyResult
+    where F: Fn(&PortfolioConfig, &db::Connection, &mut Assets) -> EmptyResult
 {
     let portfolio = config.get_portfolio(portfolio_name)?;
     let database = db::connect(&config.db_path)?;
 
     let mut assets = Assets::load(database.clone(), &portfolio.name)?;
-    modify(portfolio, &mut assets)?;
+    modify(portfolio, &database, &mut assets)?;
     assets.save(database, &portfolio.name)?;
 
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
 }
 
+// Trades with specified price are recorded to be used by performance analysis of the portfolios which don't have broker
+// statements
+fn record_manual_trade(
+    portfolio: &PortfolioConfig, database: &db::Connection, trades: &[(&str, Decimal)],
+    price: Option<Decimal>, date: Option<Date>,
+) -> EmptyResult {
+    let Some(price) = price else {
+        return Ok(());
+    };
+
+    if portfolio.statements.is_some() {
+        return Err!("Manual trades can be recorded only for portfolios without broker statements");
+    }
+
+    let &[(symbol, quantity)] = trades else {
+        return Err!("Trade price can be specified only for a single position");
+    };
+
+    let today = time::today();
+    let date = date.unwrap_or(today);
+    if date > today {
+        return Err!("Invalid trade date: {}", format_date(date));
+    }
+
+    ManualTrade {
+        date,
+        symbol: symbol.to_owned(),
+        quantity,
+        price: Cash::new(portfolio.currency(), price),
+    }.save(database.clone(), &portfolio.name)
+}
+
 fn set_cash_assets_impl(portfolio: &PortfolioConfig, assets: &mut Assets, cash_assets: Decimal) -> EmptyResult {
     assets.cash.clear();
     assets.cash.deposit(Cash::new(portfolio.currency(), cash_assets));
@@ -123,6 +186,25 @@ pub fn show(config: &Config, portfolio_name: &str, flat: bool) -> GenericResult<
     process(config, portfolio_name, false, flat)
 }
 
+pub fn show_as_of(config: &Config, portfolio_name: &str, date: Date) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+    let database = db::connect(&config.db_path)?;
+
+    let quotes = Rc::new(Quotes::new(config, database.clone())?);
+    let converter = CurrencyConverter::new(database, Some(quotes.clone()), false);
+
+    let statement = BrokerStatement::read(
+        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
+        &portfolio.corporate_actions, ReadingStrictness::CASH_FLOW_DATES, StatementSections::all())?;
+
+    let historical = reconstruct(&statement, date, portfolio.currency(), &converter, &quotes)?;
+    historical.print(&format!("{:?} portfolio", portfolio.name), statement.broker.quantity_increment());
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
 pub fn rebalance(config: &Config, portfolio_name: &str, flat: bool) -> GenericResult<TelemetryRecordBuilder> {
     process(config, portfolio_name, true, flat)
 }
@@ -143,17 +225,21 @@ fn process(config: &Config, portfolio_name: &str, rebalance: bool, flat: bool) -
             broker.clone(), path, &portfolio_config.symbol_remapping,
             &portfolio_config.instrument_internal_ids, &portfolio_config.instrument_names,
             portfolio_config.get_tax_remapping()?, &portfolio_config.tax_exemptions,
-            &portfolio_config.corporate_actions, ReadingStrictness::empty())
+            &portfolio_config.corporate_actions, ReadingStrictness::empty(), StatementSections::all())
     }).transpose()?;
 
     let mut portfolio = Portfolio::load(
         portfolio_config, broker, assets, statement.as_ref(), &converter, &quotes)?;
 
     if rebalance {
-        rebalancing::rebalance_portfolio(&mut portfolio, converter)?;
+        rebalancing::rebalance_portfolio(&mut portfolio, converter.clone())?;
     }
 
     print_portfolio(portfolio, flat);
 
+    if let Some(statement) = statement.as_ref() {
+        check_idle_cash(&config.get_tax_country(), portfolio_config, statement, &converter, &quotes)?;
+    }
+
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio_config.broker))
 }
\ No newline at end of file
@@ -12,6 +12,7 @@ use crate::types::{Decimal, TradeType};
 use crate::util;
 
 use super::asset_allocation::{Portfolio, AssetAllocation, Holding, StockHolding};
+use super::currency_conversion;
 
 pub fn rebalance_portfolio(portfolio: &mut Portfolio, converter: CurrencyConverterRc) -> EmptyResult {
     let portfolio_info = PortfolioInfo::new(portfolio);
@@ -46,13 +47,15 @@ pub fn rebalance_portfolio(portfolio: &mut Portfolio, converter: CurrencyConvert
     // allocation configuration.
     distribute_cash_assets(portfolio, &portfolio_info, converter.clone())?;
 
-    let (trade_commissions, additional_commissions) = calculate_total_commissions(portfolio, converter)?;
+    let (trade_commissions, additional_commissions) = calculate_total_commissions(portfolio, converter.clone())?;
     assert_eq!(
         portfolio.commissions - interim_total_commissions,
         trade_commissions - interim_trade_commissions,
     );
     portfolio.change_commission(additional_commissions - interim_additional_commissions);
 
+    portfolio.conversions = currency_conversion::plan(portfolio, converter)?;
+
     Ok(())
 }
 
@@ -64,11 +67,12 @@ fn calculate_restrictions(assets: &mut Vec<AssetAllocation>) -> (Decimal, Option
     for asset in assets {
         let (min_value, max_value) = match &mut asset.holding {
             Holding::Group(holdings) => calculate_restrictions(holdings),
-            Holding::Stock(_) => {
+            Holding::Stock(ref holding) => {
+                // Pledged shares are still valued, but can't be sold
                 let min_value = if asset.restrict_selling.unwrap_or(false) {
                     asset.current_value
                 } else {
-                    dec!(0)
+                    holding.pledged_shares * holding.price
                 };
 
                 let max_value = if asset.restrict_buying.unwrap_or(false) {
@@ -117,7 +121,7 @@ fn propagate_zero_weight(asset: &mut AssetAllocation) {
             }
         }
     } else if let Some(max_value) = asset.max_value {
-        assert_eq!(max_value, asset.min_value);
+        assert!(max_value >= asset.min_value);
     }
 
     asset.max_value = Some(asset.min_value);
@@ -0,0 +1,230 @@
+/// Implements a simple profiler which measures how much time command execution spends in its main phases.
+///
+/// Time is accounted exclusively: when a phase is entered from another one (quotes are requested during currency
+/// conversion for example), the outer phase is paused until the inner one is finished.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use static_table_derive::StaticTable;
+
+use crate::formatting::table::Cell;
+use crate::types::Decimal;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Phase {
+    StatementParsing,
+    Quotes,
+    CurrencyConversion,
+    Taxes,
+    Rendering,
+}
+
+impl Phase {
+    pub fn id(self) -> &'static str {
+        match self {
+            Phase::StatementParsing => "statement-parsing",
+            Phase::Quotes => "quotes",
+            Phase::CurrencyConversion => "currency-conversion",
+            Phase::Taxes => "taxes",
+            Phase::Rendering => "rendering",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::StatementParsing => "Broker statements parsing",
+            Phase::Quotes => "Quotes",
+            Phase::CurrencyConversion => "Currency conversion",
+            Phase::Taxes => "Taxes calculation",
+            Phase::Rendering => "Rendering",
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// All phases are expected to be executed from the main thread, so the profiler is thread local
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::new());
+}
+
+struct Profiler {
+    start_time: Instant,
+    stack: Vec<(Phase, Instant)>,
+    timings: BTreeMap<Phase, Duration>,
+}
+
+impl Profiler {
+    fn new() -> Profiler {
+        Profiler {
+            start_time: Instant::now(),
+            stack: Vec::new(),
+            timings: BTreeMap::new(),
+        }
+    }
+
+    fn enter(&mut self, phase: Phase) {
+        let now = Instant::now();
+        self.pause(now);
+        self.stack.push((phase, now));
+    }
+
+    fn leave(&mut self, phase: Phase) {
+        let now = Instant::now();
+
+        let current = self.pause(now);
+        assert_eq!(current, Some(phase));
+        self.stack.pop();
+
+        if let Some((_, start_time)) = self.stack.last_mut() {
+            *start_time = now;
+        }
+    }
+
+    fn pause(&mut self, now: Instant) -> Option<Phase> {
+        let &mut (phase, ref mut start_time) = self.stack.last_mut()?;
+        *self.timings.entry(phase).or_default() += now - *start_time;
+        *start_time = now;
+        Some(phase)
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+    PROFILER.with(|profiler| *profiler.borrow_mut() = Profiler::new());
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Accounts the time to the specified phase until the returned guard is dropped
+#[must_use]
+pub struct Timer {
+    phase: Option<Phase>,
+}
+
+pub fn start(phase: Phase) -> Timer {
+    if !is_enabled() {
+        return Timer {phase: None};
+    }
+
+    PROFILER.with(|profiler| profiler.borrow_mut().enter(phase));
+    Timer {phase: Some(phase)}
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(phase) = self.phase {
+            PROFILER.with(|profiler| profiler.borrow_mut().leave(phase));
+        }
+    }
+}
+
+pub struct Timings {
+    pub total: Duration,
+    pub phases: BTreeMap<Phase, Duration>,
+}
+
+impl Timings {
+    pub fn other(&self) -> Duration {
+        self.total.saturating_sub(self.phases.values().sum())
+    }
+}
+
+/// Returns the time spent in each phase so far or `None` if profiling is disabled
+pub fn timings() -> Option<Timings> {
+    if !is_enabled() {
+        return None;
+    }
+
+    Some(PROFILER.with(|profiler| {
+        let profiler = profiler.borrow();
+        Timings {
+            total: profiler.start_time.elapsed(),
+            phases: profiler.timings.clone(),
+        }
+    }))
+}
+
+#[derive(StaticTable)]
+#[table(name="TimingsTable")]
+struct Row {
+    #[column(name="Phase")]
+    phase: &'static str,
+    #[column(name="Time")]
+    time: String,
+    #[column(name="Share")]
+    share: Cell,
+}
+
+pub fn print() {
+    let Some(timings) = timings() else {
+        return;
+    };
+
+    let mut table = TimingsTable::new();
+    let total = timings.total.as_secs_f64();
+
+    let mut add_row = |phase, duration: Duration| {
+        let share = if total > 0.0 {
+            Decimal::try_from(duration.as_secs_f64() / total).unwrap_or_default()
+        } else {
+            Decimal::ZERO
+        };
+
+        table.add_row(Row {
+            phase,
+            time: format_duration(duration),
+            share: Cell::new_ratio(share),
+        });
+    };
+
+    for (&phase, &duration) in &timings.phases {
+        add_row(phase.name(), duration);
+    }
+    add_row("Other", timings.other());
+
+    let mut totals = table.add_empty_row();
+    totals.set_time(format_duration(timings.total));
+
+    table.print("Execution time profile");
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn nested_phases() {
+        let sleep = || thread::sleep(Duration::from_millis(10));
+        enable();
+
+        {
+            let _timer = start(Phase::CurrencyConversion);
+            sleep();
+            {
+                let _timer = start(Phase::Quotes);
+                sleep();
+            }
+            sleep();
+        }
+
+        let timings = timings().unwrap();
+        let conversion = timings.phases[&Phase::CurrencyConversion];
+        let quotes = timings.phases[&Phase::Quotes];
+
+        assert!(conversion >= Duration::from_millis(20));
+        assert!(quotes >= Duration::from_millis(10));
+        assert!(conversion + quotes <= timings.total);
+        assert!(!timings.phases.contains_key(&Phase::Taxes));
+    }
+}
@@ -0,0 +1,138 @@
+/// Implements progress reporting for long-running operations: a progress bar in interactive mode and periodic log
+/// messages otherwise.
+///
+/// Operations which finish fast enough don't report any progress at all.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use log::{Log, Metadata, Record, info};
+
+const REPORT_DELAY: Duration = Duration::from_secs(1);
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+const BAR_WIDTH: usize = 30;
+
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+static DRAWN: AtomicBool = AtomicBool::new(false);
+
+/// Enables progress bars instead of log messages (stderr must be a terminal and the logger must be wrapped with
+/// `wrap_logger()`)
+pub fn enable_interactive() {
+    INTERACTIVE.store(true, Ordering::Relaxed);
+}
+
+fn is_interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// Wraps the logger to clear the progress bar before each log message, so they don't get mixed on the terminal. The
+/// bar is redrawn on the next progress update.
+pub fn wrap_logger(logger: Box<dyn Log>) -> Box<dyn Log> {
+    Box::new(ProgressAwareLogger {logger})
+}
+
+struct ProgressAwareLogger {
+    logger: Box<dyn Log>,
+}
+
+impl Log for ProgressAwareLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.logger.enabled(record.metadata()) {
+            clear();
+        }
+        self.logger.log(record);
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+fn clear() {
+    if DRAWN.swap(false, Ordering::Relaxed) {
+        let _ = write!(io::stderr(), "\r\x1b[K");
+    }
+}
+
+/// Reports progress of the operation until the returned guard is dropped
+#[must_use]
+pub struct Progress {
+    title: String,
+    current: usize,
+    total: usize,
+    start_time: Instant,
+    last_report_time: Option<Instant>,
+}
+
+pub fn start(title: &str, total: usize) -> Progress {
+    Progress {
+        title: title.to_owned(),
+        current: 0,
+        total,
+        start_time: Instant::now(),
+        last_report_time: None,
+    }
+}
+
+impl Progress {
+    pub fn inc(&mut self) {
+        self.set(self.current + 1);
+    }
+
+    pub fn set(&mut self, current: usize) {
+        self.current = std::cmp::min(current, self.total);
+
+        let now = Instant::now();
+        if now - self.start_time < REPORT_DELAY {
+            return;
+        }
+
+        let interval = if is_interactive() {
+            REDRAW_INTERVAL
+        } else {
+            LOG_INTERVAL
+        };
+
+        if self.last_report_time.is_some_and(|time| now - time < interval) {
+            return;
+        }
+        self.last_report_time.replace(now);
+
+        if is_interactive() {
+            let _ = write!(io::stderr(), "\r{}\x1b[K", format_bar(&self.title, self.current, self.total));
+            DRAWN.store(true, Ordering::Relaxed);
+        } else {
+            info!("{}: {} of {}...", self.title, self.current, self.total);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        clear();
+    }
+}
+
+fn format_bar(title: &str, current: usize, total: usize) -> String {
+    let filled = (BAR_WIDTH * current).checked_div(total).unwrap_or(BAR_WIDTH);
+
+    format!("{} [{}{}] {}/{}", title, "#".repeat(filled), "-".repeat(BAR_WIDTH - filled), current, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bar() {
+        assert_eq!(format_bar("Test", 0, 3), format!("Test [{}] 0/3", "-".repeat(30)));
+        assert_eq!(format_bar("Test", 1, 3), format!("Test [{}{}] 1/3", "#".repeat(10), "-".repeat(20)));
+        assert_eq!(format_bar("Test", 3, 3), format!("Test [{}] 3/3", "#".repeat(30)));
+    }
+}
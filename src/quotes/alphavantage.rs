@@ -3,12 +3,14 @@ use std::collections::HashMap;
 #[cfg(test)] use indoc::indoc;
 use log::error;
 use reqwest::Url;
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::Response;
 use serde::Deserialize;
 
 use crate::core::GenericResult;
 use crate::currency::Cash;
 use crate::exchanges::Exchange;
+use crate::network::{Client, Proxy, RetryPolicy};
+use crate::secrets;
 use crate::time;
 use crate::util::{self, DecimalRestrictions};
 
@@ -20,7 +22,10 @@ use super::common::{send_request, is_outdated_time};
 pub struct AlphaVantageConfig {
     #[serde(skip, default = "AlphaVantageConfig::default_url")]
     url: String,
+    #[serde(deserialize_with = "secrets::deserialize")]
     api_key: String,
+    proxy: Option<Proxy>,
+    retry: Option<RetryPolicy>,
 }
 
 impl AlphaVantageConfig {
@@ -44,7 +49,7 @@ impl AlphaVantage {
         AlphaVantage {
             url: config.url.clone(),
             api_key: config.api_key.clone(),
-            client: Client::new(),
+            client: Client::new("Alpha Vantage", config.proxy.as_ref(), config.retry.as_ref()),
         }
     }
 }
@@ -207,7 +212,9 @@ mod tests {
 
         let client = AlphaVantage::new(&AlphaVantageConfig {
             url: server.url(),
-            api_key: s!("mock")
+            api_key: s!("mock"),
+            proxy: None,
+            retry: None,
         });
 
         (server, client)
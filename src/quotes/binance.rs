@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+#[cfg(test)] use indoc::indoc;
+use serde::Deserialize;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::exchanges::Exchange;
+use crate::network::{Client, Proxy, RetryPolicy};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::{SupportedExchange, QuotesMap, QuotesProvider};
+use super::common::{send_request, parse_response};
+
+// USDT is a stablecoin pegged to USD, so its pairs are considered as USD quotes
+const QUOTE_ASSET: &str = "USDT";
+const CURRENCY: &str = "USD";
+
+/// Provides cryptocurrency quotes using Binance public API (doesn't require any authorization)
+pub struct Binance {
+    url: String,
+    client: Client,
+}
+
+impl Binance {
+    pub fn new(url: &str, proxy: Option<&Proxy>, retry_policy: Option<&RetryPolicy>) -> Binance {
+        Binance {
+            url: url.to_owned(),
+            client: Client::new("Binance", proxy, retry_policy),
+        }
+    }
+
+    fn get_prices(&self) -> GenericResult<HashMap<String, Decimal>> {
+        #[derive(Deserialize)]
+        struct Ticker {
+            symbol: String,
+            price: String,
+        }
+
+        // Unknown symbols lead to request failure, so request all tickers at once instead of the specific ones
+        let url = format!("{}/api/v3/ticker/price", self.url);
+
+        let tickers: Vec<Ticker> = send_request(&self.client, &url, None)
+            .and_then(|response| parse_response(&response.text()?))
+            .map_err(|e| format!("Failed to get quotes from {}: {}", url, e))?;
+
+        let mut prices = HashMap::new();
+
+        for ticker in tickers {
+            let Some(symbol) = ticker.symbol.strip_suffix(QUOTE_ASSET) else {
+                continue;
+            };
+
+            let price = util::parse_decimal(&ticker.price, DecimalRestrictions::PositiveOrZero).map_err(|_| format!(
+                "Got an invalid {} price from Binance: {:?}", symbol, ticker.price))?;
+
+            prices.insert(symbol.to_owned(), price);
+        }
+
+        Ok(prices)
+    }
+}
+
+impl QuotesProvider for Binance {
+    fn name(&self) -> &'static str {
+        "Binance"
+    }
+
+    fn supports_stocks(&self) -> SupportedExchange {
+        SupportedExchange::Some(Exchange::Crypto)
+    }
+
+    fn get_quotes(&self, symbols: &[&str]) -> GenericResult<QuotesMap> {
+        let prices = self.get_prices()?;
+        let mut quotes = QuotesMap::new();
+
+        for &symbol in symbols {
+            let price = if symbol == QUOTE_ASSET {
+                dec!(1)
+            } else {
+                // Delisted pairs have zero price
+                match prices.get(symbol) {
+                    Some(&price) if !price.is_zero() => price,
+                    _ => continue,
+                }
+            };
+
+            quotes.insert(symbol.to_owned(), Cash::new(CURRENCY, price));
+        }
+
+        Ok(quotes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Server;
+    use super::*;
+
+    #[test]
+    fn quotes() {
+        let mut server = Server::new();
+        let client = Binance::new(&server.url(), None, None);
+
+        let _mock = server.mock("GET", "/api/v3/ticker/price")
+            .with_status(200)
+            .with_header("Content-Type", "application/json;charset=UTF-8")
+            .with_body(indoc!(r#"
+                [
+                    {"symbol": "ETHBTC", "price": "0.03601000"},
+                    {"symbol": "BTCUSDT", "price": "67210.01000000"},
+                    {"symbol": "ETHUSDT", "price": "2420.55000000"},
+                    {"symbol": "LUNAUSDT", "price": "0.00000000"},
+                    {"symbol": "BTCEUR", "price": "62119.21000000"}
+                ]
+            "#))
+            .create();
+
+        assert_eq!(client.get_quotes(&["BTC", "ETH", "LUNA", "USDT", "UNKNOWN"]).unwrap(), hashmap! {
+            s!("BTC")  => Cash::new("USD", dec!(67210.01)),
+            s!("ETH")  => Cash::new("USD", dec!(2420.55)),
+            s!("USDT") => Cash::new("USD", dec!(1)),
+        });
+    }
+}
@@ -2,27 +2,36 @@ use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::sync::Mutex;
 
-use chrono::Duration;
+use chrono::{Duration, Local, TimeZone};
 use diesel::{self, prelude::*};
 #[cfg(test)] use tempfile::NamedTempFile;
 
 use crate::core::{GenericResult, EmptyResult};
 use crate::currency::Cash;
 use crate::db::{self, schema::quotes, models};
-use crate::time;
+use crate::exchanges::Exchange;
+use crate::time::{self, DateTime};
 use crate::util::{self, DecimalRestrictions};
 
+// Quotes providers may return delayed quotes, so give them some time to return the closing price
+const CLOSE_PRICE_DELAY_MINUTES: i64 = 30;
+
 pub struct Cache {
     db: db::Connection,
     expire_time: Duration,
+    exchange_expire_times: HashMap<Exchange, Duration>,
     cache: Option<Mutex<HashMap<String, Cash>>>,
 }
 
 impl Cache {
-    pub fn new(connection: db::Connection, expire_time: Duration, in_memory_cache: bool) -> Cache {
+    pub fn new(
+        connection: db::Connection, expire_time: Duration, exchange_expire_times: HashMap<Exchange, Duration>,
+        in_memory_cache: bool,
+    ) -> Cache {
         Cache {
             db: connection,
             expire_time: expire_time,
+            exchange_expire_times: exchange_expire_times,
             cache: if in_memory_cache {
                 Some(Mutex::new(HashMap::new()))
             } else {
@@ -34,17 +43,18 @@ impl Cache {
     #[cfg(test)]
     pub fn new_temporary() -> (NamedTempFile, Cache) {
         let (database, connection) = db::new_temporary();
-        (database, Cache::new(connection, Duration::minutes(1), false))
+        (database, Cache::new(connection, Duration::minutes(1), HashMap::new(), false))
     }
 
-    pub fn get(&self, symbol: &str) -> GenericResult<Option<Cash>> {
+    // Exchanges determine the expire time: an empty list means that the quote is traded around the clock
+    pub fn get(&self, symbol: &str, exchanges: &[Exchange]) -> GenericResult<Option<Cash>> {
         if let Some(ref cache) = self.cache {
             if let Some(price) = cache.lock().unwrap().get(symbol).copied() {
                 return Ok(Some(price));
             }
         }
 
-        let expire_time = time::now() - self.expire_time;
+        let expire_time = self.get_expire_time(exchanges, time::utc_now());
         let result = quotes::table
             .select((quotes::currency, quotes::price))
             .filter(quotes::symbol.eq(symbol))
@@ -67,6 +77,30 @@ impl Cache {
         Ok(Some(price))
     }
 
+    // Returns the time before which all quotes are considered as expired
+    fn get_expire_time(&self, exchanges: &[Exchange], now: DateTime) -> DateTime {
+        let expire_time = exchanges.iter()
+            .map(|exchange| self.exchange_expire_times.get(exchange).copied().unwrap_or(self.expire_time))
+            .min().unwrap_or(self.expire_time);
+
+        let now = Local.from_utc_datetime(&now);
+        let mut expire_time = now.naive_local() - expire_time;
+
+        // Quotes don't change while all exchanges are closed, so quotes received after the last trading session close
+        // are valid until the next session.
+        let last_close = exchanges.iter()
+            .map(|exchange| exchange.trading_hours().and_then(|hours| hours.last_close(now.naive_utc())))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|closes| closes.into_iter().max());
+
+        if let Some(last_close) = last_close {
+            let last_close = last_close + Duration::minutes(CLOSE_PRICE_DELAY_MINUTES);
+            expire_time = expire_time.min(Local.from_utc_datetime(&last_close).naive_local());
+        }
+
+        expire_time
+    }
+
     pub fn save(&self, symbol: &str, price: Cash) -> EmptyResult {
         if let Some(ref cache) = self.cache {
             cache.lock().unwrap().insert(symbol.to_owned(), price);
@@ -108,19 +142,49 @@ mod tests {
             })
             .execute(cache.db.borrow().deref_mut()).unwrap();
 
-        assert_eq!(cache.get(symbol).unwrap(), None);
-        assert_eq!(cache.get(other_symbol).unwrap(), None);
+        assert_eq!(cache.get(symbol, &[]).unwrap(), None);
+        assert_eq!(cache.get(other_symbol, &[]).unwrap(), None);
 
         cache.save(symbol, price).unwrap();
-        assert_eq!(cache.get(symbol).unwrap(), Some(price));
-        assert_eq!(cache.get(other_symbol).unwrap(), None);
+        assert_eq!(cache.get(symbol, &[]).unwrap(), Some(price));
+        assert_eq!(cache.get(other_symbol, &[]).unwrap(), None);
 
         cache.save(other_symbol, other_price).unwrap();
-        assert_eq!(cache.get(symbol).unwrap(), Some(price));
-        assert_eq!(cache.get(other_symbol).unwrap(), Some(other_price));
+        assert_eq!(cache.get(symbol, &[]).unwrap(), Some(price));
+        assert_eq!(cache.get(other_symbol, &[]).unwrap(), Some(other_price));
 
         cache.expire_time = Duration::seconds(0);
-        assert_eq!(cache.get(symbol).unwrap(), None);
-        assert_eq!(cache.get(other_symbol).unwrap(), None);
+        assert_eq!(cache.get(symbol, &[]).unwrap(), None);
+        assert_eq!(cache.get(other_symbol, &[]).unwrap(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn expire_time() {
+        let (_database, mut cache) = Cache::new_temporary();
+        cache.exchange_expire_times.insert(Exchange::Moex, Duration::minutes(5));
+
+        let local = |time: DateTime| Local.from_utc_datetime(&time).naive_local();
+        let check = |cache: &Cache, exchanges: &[Exchange], now: DateTime, expected: DateTime| {
+            assert_eq!(cache.get_expire_time(exchanges, now), local(expected));
+        };
+
+        // Both exchanges are open
+        let now = date!(2024, 11, 1).and_hms_opt(14, 0, 0).unwrap();
+        check(&cache, &[], now, now - Duration::minutes(1));
+        check(&cache, &[Exchange::Us], now, now - Duration::minutes(1));
+        check(&cache, &[Exchange::Moex], now, now - Duration::minutes(5));
+        check(&cache, &[Exchange::Moex, Exchange::Us], now, now - Duration::minutes(1));
+
+        // Both exchanges are closed
+        let now = date!(2024, 11, 2).and_hms_opt(12, 0, 0).unwrap();
+        check(&cache, &[], now, now - Duration::minutes(1));
+        check(&cache, &[Exchange::Us], now, date!(2024, 11, 1).and_hms_opt(20, 30, 0).unwrap());
+        check(&cache, &[Exchange::Moex], now, date!(2024, 11, 1).and_hms_opt(21, 20, 0).unwrap());
+        check(&cache, &[Exchange::Moex, Exchange::Us], now, date!(2024, 11, 1).and_hms_opt(21, 20, 0).unwrap());
+        check(&cache, &[Exchange::Moex, Exchange::Other], now, now - Duration::minutes(1));
+
+        // Just closed
+        let now = date!(2024, 11, 1).and_hms_opt(20, 10, 0).unwrap();
+        check(&cache, &[Exchange::Us], now, now - Duration::minutes(1));
+    }
+}
@@ -1,11 +1,11 @@
 use std::collections::HashMap;
 use std::str::FromStr;
+#[cfg(test)] use std::sync::Arc;
 use std::sync::OnceLock;
 
 #[cfg(test)] use indoc::indoc;
 use log::warn;
 use reqwest::Url;
-use reqwest::blocking::Client;
 use serde::Deserialize;
 use serde::de::{DeserializeOwned, Deserializer, Error};
 use validator::{Validate, ValidationError};
@@ -16,6 +16,8 @@ use crate::formats::xml;
 use crate::formatting;
 use crate::forex;
 use crate::localities;
+use crate::network::{Client, Proxy, RetryPolicy};
+#[cfg(test)] use crate::network::Transport;
 use crate::quotes::{CurrencyRate, QuotesMap, QuotesProvider};
 use crate::time;
 use crate::types::{Date, Decimal};
@@ -33,15 +35,21 @@ pub struct Cbr {
 }
 
 impl Cbr {
-    pub fn new(url: &str) -> Cbr {
+    pub fn new(url: &str, proxy: Option<&Proxy>, retry_policy: Option<&RetryPolicy>) -> Cbr {
         Cbr {
             url: url.to_owned(),
-            client: Client::new(),
+            client: Client::new("CBR", proxy, retry_policy),
             codes: OnceLock::new(),
             rates: OnceLock::new(),
         }
     }
 
+    #[cfg(test)]
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Cbr {
+        self.client = self.client.with_transport(transport);
+        self
+    }
+
     fn get_currency_rates(&self) -> GenericResult<HashMap<String, Decimal>> {
         #[derive(Deserialize, Validate)]
         struct Rates {
@@ -183,6 +191,10 @@ impl QuotesProvider for Cbr {
         true
     }
 
+    fn forex_cross_currency(&self, base: &str, quote: &str) -> Option<&'static str> {
+        (base != BASE_CURRENCY && quote != BASE_CURRENCY).then_some(BASE_CURRENCY)
+    }
+
     fn get_quotes(&self, symbols: &[&str]) -> GenericResult<QuotesMap> {
         let rates = self.rates.get_or_init(|| {
             self.get_currency_rates()
@@ -246,6 +258,8 @@ fn get_quote(base: &str, quote: &str, rates: &HashMap<String, Decimal>) -> Optio
 mod tests {
     use mockito::{Server, ServerGuard, Mock};
 
+    use crate::quotes::common::cassette;
+
     use super::*;
 
     #[test]
@@ -305,6 +319,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn recorded_rates() {
+        let client = Cbr::new("https://www.cbr.ru", None, None).with_transport(cassette("cbr"));
+        assert_eq!(client.get_quotes(&["USD/RUB", "CNY/RUB"]).unwrap(), hashmap!{
+            s!("USD/RUB") => Cash::new("RUB", dec!(87.8064)),
+            s!("CNY/RUB") => Cash::new("RUB", dec!(11.8748)),
+        });
+    }
+
     #[test]
     fn empty_historical_rates() {
         let (mut server, client) = create_server();
@@ -386,7 +409,7 @@ mod tests {
 
     fn create_server() -> (ServerGuard, Cbr) {
         let server = Server::new();
-        let client = Cbr::new(&server.url());
+        let client = Cbr::new(&server.url(), None, None);
         (server, client)
     }
 
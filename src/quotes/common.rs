@@ -1,14 +1,18 @@
 use std::collections::HashMap;
+#[cfg(test)] use std::path::Path;
+#[cfg(test)] use std::sync::Arc;
 use std::sync::Mutex;
 
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc, Local};
-use log::trace;
 use rayon::prelude::*;
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::Response;
 use serde::de::DeserializeOwned;
 
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
+use crate::exchanges::Exchange;
+use crate::network::Client;
+#[cfg(test)] use crate::network::{Cassette, Transport};
 use crate::quotes::QuotesMap;
 use crate::time::{SystemTime, TimeProvider};
 
@@ -33,12 +37,23 @@ pub fn parallelize_quotes<F>(symbols: &[&str], get_quote: F) -> GenericResult<Qu
     Ok(quotes.into_inner().unwrap())
 }
 
-pub fn is_outdated_quote<T: TimeZone>(date_time: DateTime<T>, now_provider: &dyn TimeProvider) -> Option<DateTime<Local>> {
-    let lifetime = now_provider.now().naive_utc() - date_time.naive_utc();
-    if lifetime.num_days() >= 5 {
-        return Some(date_time.with_timezone(&Local))
-    }
-    None
+// Allow missing quotes for a few sessions due to holidays which aren't known to us and low liquidity instruments
+const MAX_MISSED_SESSIONS: usize = 3;
+
+/// Checks the quote time against the exchange's trading sessions schedule (if it's known), so quotes from weekend
+/// sessions are considered as actual while weekday quotes become outdated after a few missed sessions.
+pub fn is_outdated_quote<T: TimeZone>(
+    date_time: DateTime<T>, exchange: Option<Exchange>, now_provider: &dyn TimeProvider,
+) -> Option<DateTime<Local>> {
+    let time = date_time.naive_utc();
+    let now = now_provider.now().naive_utc();
+
+    let outdated = match exchange.and_then(Exchange::trading_hours) {
+        Some(trading_hours) => trading_hours.sessions_since(time, now) > MAX_MISSED_SESSIONS,
+        None => (now - time).num_days() >= 5,
+    };
+
+    outdated.then(|| date_time.with_timezone(&Local))
 }
 
 pub fn is_outdated_unix_time(time: i64, test_outdated_time: i64) -> GenericResult<Option<DateTime<Local>>> {
@@ -59,29 +74,54 @@ pub fn is_outdated_time<T: TimeZone>(date_time: DateTime<T>, test_outdated_time:
             None
         }
     } else {
-        is_outdated_quote(date_time, &SystemTime())
+        is_outdated_quote(date_time, None, &SystemTime())
     }
 }
 
 pub fn send_request<U: AsRef<str>>(client: &Client, url: U, authorization: Option<&str>) -> GenericResult<Response> {
-    let url = url.as_ref();
-
-    let mut request = client.get(url);
+    let mut request = client.get(url.as_ref());
     if let Some(authorization) = authorization {
         request = request.bearer_auth(authorization);
     }
-
-    trace!("Sending request to {}...", url);
-    let response = request.send()?;
-    trace!("Got response from {}.", url);
-
-    if !response.status().is_success() {
-        return Err!("Server returned an error: {}", response.status());
-    }
-
-    Ok(response)
+    client.send(request)
 }
 
 pub fn parse_response<T: DeserializeOwned>(response: &str) -> GenericResult<T> {
     Ok(serde_json::from_str(response).map_err(|e| format!("Got an unexpected response: {}", e))?)
-}
\ No newline at end of file
+}
+
+/// Returns transport which replays responses recorded to testdata/$name-cassette.yaml
+#[cfg(test)]
+pub fn cassette(name: &str) -> Arc<dyn Transport> {
+    let path = Path::new(file!()).parent().unwrap().join("testdata").join(format!("{}-cassette.yaml", name));
+    Arc::new(Cassette::load(&path).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::time::FakeTime;
+
+    use super::*;
+
+    #[rstest(time, exchange, outdated,
+        // Weekend session quote
+        case(date_time!(2025, 3, 9, 15, 0, 0), Some(Exchange::Moex), false),
+        // Friday evening session quote
+        case(date_time!(2025, 3, 7, 17, 0, 0), Some(Exchange::Moex), false),
+        // Thursday quote with missed weekday and weekend sessions
+        case(date_time!(2025, 3, 6, 20, 0, 0), Some(Exchange::Moex), true),
+        case(date_time!(2025, 3, 6, 20, 0, 0), None, false),
+        // No weekend sessions
+        case(date_time!(2025, 3, 4, 20, 0, 0), Some(Exchange::Us), false),
+        case(date_time!(2025, 3, 3, 20, 0, 0), Some(Exchange::Us), true),
+        case(date_time!(2025, 3, 3, 20, 0, 0), None, true),
+    )]
+    fn outdated_quotes(time: NaiveDateTime, exchange: Option<Exchange>, outdated: bool) {
+        // Monday, MOEX is open while US exchanges are closed
+        let now = FakeTime::new(Utc.from_utc_datetime(&date_time!(2025, 3, 10, 9, 0, 0)));
+        let time = Utc.from_utc_datetime(&time);
+        assert_eq!(is_outdated_quote(time, exchange, &now).is_some(), outdated);
+    }
+}
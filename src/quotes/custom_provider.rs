@@ -1,12 +1,13 @@
 #[cfg(test)] use indoc::indoc;
 use reqwest::Url;
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::Response;
 use serde::Deserialize;
 use validator::Validate;
 
 use crate::core::GenericResult;
 #[cfg(test)] use crate::currency::Cash;
 use crate::forex;
+use crate::network::{Client, Proxy, RetryPolicy};
 use crate::types::Decimal;
 use crate::util::{self, DecimalRestrictions};
 
@@ -18,6 +19,8 @@ use super::common::{send_request, parse_response};
 pub struct CustomProviderConfig {
     #[validate(url)]
     url: String,
+    proxy: Option<Proxy>,
+    retry: Option<RetryPolicy>,
 }
 
 pub struct CustomProvider {
@@ -29,7 +32,7 @@ impl CustomProvider {
     pub fn new(config: &CustomProviderConfig) -> CustomProvider {
         CustomProvider {
             url: config.url.clone(),
-            client: Client::new(),
+            client: Client::new("custom quotes provider", config.proxy.as_ref(), config.retry.as_ref()),
         }
     }
 }
@@ -150,6 +153,8 @@ mod tests {
 
         let client = CustomProvider::new(&CustomProviderConfig {
             url: server.url(),
+            proxy: None,
+            retry: None,
         });
 
         (server, client)
@@ -3,13 +3,16 @@ use std::time::Duration;
 #[cfg(test)] use indoc::indoc;
 use log::debug;
 use reqwest::Url;
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::Response;
 use serde::Deserialize;
 
 use crate::core::GenericResult;
 #[cfg(test)] use crate::currency::Cash;
+use crate::db;
 use crate::forex;
+use crate::network::{Client, Proxy, RetryPolicy};
 use crate::rate_limiter::RateLimiter;
+use crate::secrets;
 use crate::types::Decimal;
 use crate::util::{self, DecimalRestrictions};
 
@@ -21,10 +24,24 @@ use super::common::{send_request, parse_response, is_outdated_unix_time};
 pub struct FcsApiConfig {
     #[serde(skip, default = "FcsApiConfig::default_url")]
     url: String,
+    #[serde(deserialize_with = "secrets::deserialize")]
     access_key: String,
+    daily_limit: Option<u32>,
+    proxy: Option<Proxy>,
+    retry: Option<RetryPolicy>,
 }
 
 impl FcsApiConfig {
+    pub fn new(access_key: &str) -> FcsApiConfig {
+        FcsApiConfig {
+            url: FcsApiConfig::default_url(),
+            access_key: access_key.to_owned(),
+            daily_limit: None,
+            proxy: None,
+            retry: None,
+        }
+    }
+
     fn default_url() -> String {
         s!("https://fcsapi.com")
     }
@@ -39,13 +56,24 @@ pub struct FcsApi {
 }
 
 impl FcsApi {
-    pub fn new(config: &FcsApiConfig) -> FcsApi {
+    pub fn new(config: &FcsApiConfig, database: Option<db::Connection>) -> FcsApi {
+        let mut rate_limiter = RateLimiter::new();
+
+        if let Some(daily_limit) = config.daily_limit {
+            rate_limiter = rate_limiter.with_limit(daily_limit, Duration::from_secs(60 * 60 * 24));
+        }
+        rate_limiter = rate_limiter.with_quota(Duration::from_secs(30), 2);
+
+        if let Some(database) = database {
+            rate_limiter = rate_limiter.persistent(database, "FCS API");
+        }
+
         FcsApi {
             url: config.url.clone(),
             access_key: config.access_key.clone(),
 
-            client: Client::new(),
-            rate_limiter: RateLimiter::new().with_quota(Duration::from_secs(30), 2),
+            client: Client::new("FCS API", config.proxy.as_ref(), config.retry.as_ref()),
+            rate_limiter,
         }
     }
 }
@@ -70,7 +98,7 @@ impl QuotesProvider for FcsApi {
             ("access_key", &self.access_key),
         ])?;
 
-        self.rate_limiter.wait(&format!("request to {}", url));
+        self.rate_limiter.wait(&format!("request to {}", url))?;
         Ok(send_request(&self.client, &url, None).and_then(get_quotes).map_err(|e| format!(
             "Failed to get quotes from {}: {}", url, e))?)
     }
@@ -214,8 +242,11 @@ mod tests {
 
         let client = FcsApi::new(&FcsApiConfig {
             url: server.url(),
-            access_key: s!("mock")
-        });
+            access_key: s!("mock"),
+            daily_limit: None,
+            proxy: None,
+            retry: None,
+        }, None);
 
         (server, client)
     }
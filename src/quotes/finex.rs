@@ -4,12 +4,13 @@ use std::io::Cursor;
 #[cfg(test)] use std::path::Path;
 
 use calamine::{Reader, Xlsx};
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::Response;
 
 use crate::core::GenericResult;
 #[cfg(test)] use crate::currency::Cash;
 use crate::exchanges::Exchange;
 use crate::formats::xls::{self, XlsTableRow, SheetReader, SheetParser, TableReader, SkipCell};
+use crate::network::{Client, Proxy, RetryPolicy};
 use crate::types::Decimal;
 use crate::util::{self, DecimalRestrictions};
 
@@ -23,10 +24,10 @@ pub struct Finex {
 }
 
 impl Finex {
-    pub fn new(url: &str) -> Finex {
+    pub fn new(url: &str, proxy: Option<&Proxy>, retry_policy: Option<&RetryPolicy>) -> Finex {
         Finex {
             url: url.to_owned(),
-            client: Client::new(),
+            client: Client::new("FinEx", proxy, retry_policy),
         }
     }
 }
@@ -115,7 +116,7 @@ mod tests {
     #[test]
     fn quotes() {
         let mut server = Server::new();
-        let client = Finex::new(&server.url());
+        let client = Finex::new(&server.url(), None, None);
         let _mock = mock_response(&mut server);
 
         let mut quotes = QuotesMap::new();
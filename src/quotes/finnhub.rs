@@ -1,16 +1,20 @@
+#[cfg(test)] use std::sync::Arc;
 use std::time::Duration;
 
 #[cfg(test)] use indoc::indoc;
 use log::debug;
 use reqwest::Url;
-use reqwest::blocking::Client;
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
 
 use crate::core::GenericResult;
 use crate::currency::Cash;
+use crate::db;
 use crate::exchanges::Exchange;
+use crate::network::{Client, Proxy, RetryPolicy};
+#[cfg(test)] use crate::network::Transport;
 use crate::rate_limiter::RateLimiter;
+use crate::secrets;
 use crate::util::{self, DecimalRestrictions};
 use crate::types::Decimal;
 
@@ -22,10 +26,22 @@ use super::common::{parallelize_quotes, send_request, is_outdated_unix_time};
 pub struct FinnhubConfig {
     #[serde(skip, default="FinnhubConfig::default_url")]
     url: String,
+    #[serde(deserialize_with = "secrets::deserialize")]
     token: String,
+    proxy: Option<Proxy>,
+    retry: Option<RetryPolicy>,
 }
 
 impl FinnhubConfig {
+    pub fn new(token: &str) -> FinnhubConfig {
+        FinnhubConfig {
+            url: FinnhubConfig::default_url(),
+            token: token.to_owned(),
+            proxy: None,
+            retry: None,
+        }
+    }
+
     fn default_url() -> String {
         s!("https://finnhub.io")
     }
@@ -40,18 +56,30 @@ pub struct Finnhub {
 }
 
 impl Finnhub {
-    pub fn new(config: &FinnhubConfig) -> Finnhub {
+    pub fn new(config: &FinnhubConfig, database: Option<db::Connection>) -> Finnhub {
+        let mut rate_limiter = RateLimiter::new()
+            .with_limit(60 / 2, Duration::from_secs(60))
+            .with_limit(30 / 2, Duration::from_secs(1));
+
+        if let Some(database) = database {
+            rate_limiter = rate_limiter.persistent(database, "Finnhub");
+        }
+
         Finnhub {
             url: config.url.clone(),
             token: config.token.clone(),
 
-            client: Client::new(),
-            rate_limiter: RateLimiter::new()
-                .with_limit(60 / 2, Duration::from_secs(60))
-                .with_limit(30 / 2, Duration::from_secs(1)),
+            client: Client::new("Finnhub", config.proxy.as_ref(), config.retry.as_ref()),
+            rate_limiter,
         }
     }
 
+    #[cfg(test)]
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Finnhub {
+        self.client = self.client.with_transport(transport);
+        self
+    }
+
     fn get_quote(&self, symbol: &str) -> GenericResult<Option<Cash>> {
         #[derive(Deserialize)]
         struct Quote {
@@ -105,7 +133,7 @@ impl Finnhub {
         ])?;
 
         let get = |url| -> GenericResult<Option<T>> {
-            self.rate_limiter.wait(&format!("request to {}", url));
+            self.rate_limiter.wait(&format!("request to {}", url))?;
 
             let reply = send_request(&self.client, url, None)?.text()?;
             if reply.trim() == "Symbol not supported" {
@@ -141,6 +169,7 @@ impl QuotesProvider for Finnhub {
 mod tests {
     use mockito::{Server, Mock};
     use rstest::rstest;
+    use crate::quotes::common::cassette;
     use super::*;
 
     #[rstest]
@@ -149,8 +178,10 @@ mod tests {
 
         let client = Finnhub::new(&FinnhubConfig {
             url: server.url(),
-            token: s!("mock")
-        });
+            token: s!("mock"),
+            proxy: None,
+            retry: None,
+        }, None);
 
         let _bnd_profile_mock = mock(&mut server, "/api/v1/stock/profile2?symbol=BND&token=mock", indoc!(r#"
             {
@@ -258,6 +289,14 @@ mod tests {
         });
     }
 
+    #[test]
+    fn recorded_quotes() {
+        let client = Finnhub::new(&FinnhubConfig::new("mock"), None).with_transport(cassette("finnhub"));
+        assert_eq!(client.get_quotes(&["BND"]).unwrap(), hashmap! {
+            s!("BND") => Cash::new("USD", dec!(85.80000305175781)),
+        });
+    }
+
     fn mock(server: &mut Server, path: &str, data: &str) -> Mock {
         // All responses are always 200 OK, some of them are returned with application/json content
         // type, some - with text/plain even for JSON payload.
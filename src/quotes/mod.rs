@@ -1,7 +1,8 @@
 pub mod alphavantage;
+mod binance;
 mod cache;
 pub mod cbr;
-mod common;
+pub mod common;
 mod custom_provider;
 pub mod fcsapi;
 mod finex;
@@ -10,6 +11,7 @@ mod moex;
 mod static_provider;
 pub mod tbank;
 pub mod twelvedata;
+mod valuation;
 
 use std::cell::RefCell;
 use std::collections::{hash_map::Entry, HashMap};
@@ -17,30 +19,39 @@ use std::rc::Rc;
 use std::sync::Arc;
 #[cfg(test)] use std::sync::Mutex;
 
-use itertools::Itertools;
-use log::debug;
+use chrono::{Datelike, Duration, Weekday};
+use log::{debug, warn};
 use rayon::prelude::*;
 use serde::Deserialize;
+use serde::de::{Deserializer, Error};
 use validator::Validate;
 
 use crate::config::Config;
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
 use crate::db;
+use crate::errors::{self, QuotesError};
 use crate::exchanges::{Exchange, Exchanges};
 use crate::forex;
-use crate::time::Date;
+use crate::formatting;
+use crate::profiling::{self, Phase};
+use crate::progress;
+use crate::time::{self, Date};
 use crate::types::Decimal;
 
+use self::binance::Binance;
 use self::cache::Cache;
 use self::cbr::Cbr;
 use self::custom_provider::{CustomProvider, CustomProviderConfig};
 use self::fcsapi::{FcsApi, FcsApiConfig};
 use self::finex::Finex;
 use self::finnhub::{Finnhub, FinnhubConfig};
-use self::moex::Moex;
+use self::moex::{MoexConfig, PriceAdjustment};
 use self::static_provider::{StaticProvider, StaticProviderConfig};
-use self::tbank::{Tbank, TbankExchange};
+use self::tbank::{Tbank, TbankApiConfig, TbankExchange};
+use self::valuation::{ValuationPolicy, ValuationPolicies};
+
+pub use self::moex::Moex;
 
 #[derive(Clone)]
 pub enum QuoteQuery {
@@ -62,6 +73,29 @@ impl QuoteQuery {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistoricalQuote {
+    /// Trading day of the quote which may precede the requested date
+    pub date: Date,
+    pub price: Cash,
+    pub kind: HistoricalQuoteKind,
+}
+
+impl HistoricalQuote {
+    pub fn new(date: Date, price: Cash) -> HistoricalQuote {
+        HistoricalQuote {date, price, kind: HistoricalQuoteKind::Actual}
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoricalQuoteKind {
+    Actual,
+    /// There is a gap in the quotes before the requested date, so the quote is the last known one
+    Stale,
+    /// There is a gap in the quotes around the requested date, so the quote is interpolated between the nearest ones
+    Interpolated,
+}
+
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CurrencyRate {
@@ -74,22 +108,91 @@ pub struct CurrencyRate {
 pub struct QuotesConfig {
     pub fcsapi: Option<FcsApiConfig>,
     pub finnhub: Option<FinnhubConfig>,
+    #[serde(default)]
+    moex: MoexConfig,
     #[validate(nested)]
     custom_provider: Option<CustomProviderConfig>,
     #[serde(rename="static")]
     static_provider: Option<StaticProviderConfig>,
+
+    /// Valuation policies for instruments which don't have actual quotes (for example, suspended from trading)
+    #[serde(default)]
+    valuation: ValuationPolicies,
+
+    /// Per-exchange quote cache expire time which overrides the global one
+    #[serde(default, deserialize_with = "deserialize_cache_expire_time")]
+    pub cache_expire_time: HashMap<Exchange, Duration>,
+}
+
+fn deserialize_cache_expire_time<'de, D>(deserializer: D) -> Result<HashMap<Exchange, Duration>, D::Error>
+    where D: Deserializer<'de>
+{
+    let values: HashMap<String, String> = Deserialize::deserialize(deserializer)?;
+    let mut expire_times = HashMap::new();
+
+    for (exchange, expire_time) in values {
+        let exchange = match exchange.as_str() {
+            "moex" => Exchange::Moex,
+            "spb" => Exchange::Spb,
+            "us" => Exchange::Us,
+            "otc" => Exchange::Otc,
+            "other" => Exchange::Other,
+            "crypto" => Exchange::Crypto,
+            _ => return Err(D::Error::unknown_variant(
+                &exchange, &["moex", "spb", "us", "otc", "other", "crypto"])),
+        };
+
+        let expire_time = time::parse_duration(&expire_time).map_err(D::Error::custom)?;
+        expire_times.insert(exchange, expire_time);
+    }
+
+    Ok(expire_times)
 }
 
 pub struct Quotes {
     cache: Cache,
     providers: Vec<Arc<dyn QuotesProvider>>,
     batched_requests: RefCell<HashMap<String, QuoteRequest>>,
+    historical_quotes: RefCell<HashMap<(String, Date), Option<HistoricalQuote>>>,
+    valuation_policies: ValuationPolicies,
+    valuated_quotes: RefCell<HashMap<String, Cash>>,
+    forex_sources: RefCell<HashMap<String, ForexQuoteSource>>,
+}
+
+/// Describes how a forex quote has been obtained from the provider
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForexQuoteSource {
+    pub provider: &'static str,
+    // The provider has returned the reverse pair quote
+    pub reversed: bool,
+    // The provider has calculated the quote as a cross rate via the specified currency
+    pub cross_currency: Option<&'static str>,
 }
 
+// Historical quotes are requested one by one, so limit the number of concurrent requests to a single provider to not
+// exceed its rate limits.
+const MAX_PARALLEL_HISTORICAL_REQUESTS: usize = 4;
+
+// Historical quotes may be missing for a few trading days due to holidays which aren't known to us, but larger gaps
+// are considered as provider's data issues.
+const MAX_HISTORICAL_QUOTES_GAP: usize = 3;
+
+// How far to look after the requested date for a quote to interpolate the price in case of a gap
+const HISTORICAL_QUOTES_LOOKAHEAD_DAYS: i64 = 14;
+
 pub type QuotesRc = Rc<Quotes>;
 
 impl Quotes {
     pub fn new(config: &Config, database: db::Connection) -> GenericResult<Quotes> {
+        Quotes::new_impl(config, database, config.cache_expire_time)
+    }
+
+    /// Creates quotes client which ignores the previously cached quotes
+    pub fn new_uncached(config: &Config, database: db::Connection) -> GenericResult<Quotes> {
+        Quotes::new_impl(config, database, Duration::zero())
+    }
+
+    fn new_impl(config: &Config, database: db::Connection, cache_expire_time: Duration) -> GenericResult<Quotes> {
         let mut providers = Vec::<Arc<dyn QuotesProvider>>::new();
         let mut has_custom_provider = false;
 
@@ -119,11 +222,12 @@ impl Quotes {
         // * FCS API is too restrictive
         //
         // So use CBR API here and fallback to FCS API only for unknown currencies.
-        providers.push(Arc::new(Cbr::new("https://www.cbr.ru")));
+        let (proxy, retry_policy) = (config.proxy.as_ref(), config.retry.as_ref());
+        providers.push(Arc::new(Cbr::new("https://www.cbr.ru", proxy, retry_policy)));
 
         // Use FCS API for forex
         if let Some(config) = config.quotes.fcsapi.as_ref() {
-            providers.push(Arc::new(FcsApi::new(config)))
+            providers.push(Arc::new(FcsApi::new(config, Some(database.clone()))))
         } else if !has_custom_provider {
             return Err!("FCS API access key is not set in the configuration file");
         }
@@ -135,22 +239,33 @@ impl Quotes {
 
         // Use Finnhub for US stocks
         if let Some(config) = config.quotes.finnhub.as_ref() {
-            providers.push(Arc::new(Finnhub::new(config)))
+            providers.push(Arc::new(Finnhub::new(config, Some(database.clone()))))
         } else if !has_custom_provider {
             return Err!("Finnhub token is not set in the configuration file");
         }
 
         // Prefer FinEx provider over MOEX until their funds are suspended
-        providers.push(Arc::new(Finex::new("https://api.finex-etf.ru")));
-        providers.push(Arc::new(Moex::new("https://iss.moex.com", "TQTF")));
-        providers.push(Arc::new(Moex::new("https://iss.moex.com", "TQBR")));
+        providers.push(Arc::new(Finex::new("https://api.finex-etf.ru", proxy, retry_policy)));
+        let moex_adjustment = if config.quotes.moex.dividend_adjusted_history {
+            PriceAdjustment::Dividends
+        } else {
+            PriceAdjustment::None
+        };
+        for board in ["TQTF", "TQBR"] {
+            providers.push(Arc::new(
+                Moex::new("https://iss.moex.com", board, proxy, retry_policy).with_adjustment(moex_adjustment)));
+        }
+
+        // Cryptocurrencies are requested only for assets which are explicitly marked as traded on crypto exchange
+        providers.push(Arc::new(Binance::new("https://api.binance.com", proxy, retry_policy)));
 
         // As a best effort for unsupported exchanges provide a fallback to T-Bank SPB/OTC stocks
         if let Some(config) = tbank {
             providers.push(Arc::new(Tbank::new(config, TbankExchange::Unknown)?));
         }
 
-        Ok(Quotes::new_with(Cache::new(database, config.cache_expire_time, true), providers))
+        let cache = Cache::new(database, cache_expire_time, config.quotes.cache_expire_time.clone(), true);
+        Ok(Quotes::new_with(cache, providers).with_valuation_policies(config.quotes.valuation.clone()))
     }
 
     fn new_with(cache: Cache, providers: Vec<Arc<dyn QuotesProvider>>) -> Quotes {
@@ -158,10 +273,20 @@ impl Quotes {
             cache: cache,
             providers: providers,
             batched_requests: RefCell::new(HashMap::new()),
+            historical_quotes: RefCell::new(HashMap::new()),
+            valuation_policies: ValuationPolicies::new(),
+            valuated_quotes: RefCell::new(HashMap::new()),
+            forex_sources: RefCell::new(HashMap::new()),
         }
     }
 
+    fn with_valuation_policies(mut self, policies: ValuationPolicies) -> Quotes {
+        self.valuation_policies = policies;
+        self
+    }
+
     pub fn batch(&self, query: QuoteQuery) -> GenericResult<Option<Cash>> {
+        let _timer = profiling::start(Phase::Quotes);
         match query {
             QuoteQuery::Forex(symbol) => self.batch_forex(symbol),
             QuoteQuery::Stock(symbol, exchanges) => self.batch_stock(symbol, exchanges),
@@ -178,23 +303,176 @@ impl Quotes {
     }
 
     pub fn execute(&self) -> EmptyResult {
+        let _timer = profiling::start(Phase::Quotes);
         self.execute_query_plan(self.build_query_plan())
     }
 
     pub fn get(&self, query: QuoteQuery) -> GenericResult<Cash> {
+        let _timer = profiling::start(Phase::Quotes);
         if let Some(price) = self.batch(query.clone())? {
             return Ok(price);
         }
 
         self.execute()?;
 
-        Ok(self.cache.get(query.symbol())?.unwrap())
+        if let Some(&price) = self.valuated_quotes.borrow().get(query.symbol()) {
+            return Ok(price);
+        }
+
+        Ok(self.cache.get(query.symbol(), &[])?.unwrap())
+    }
+
+    /// Returns the source of the forex quote if it has been received from a provider (not taken from the cache)
+    pub fn get_forex_source(&self, pair: &str) -> Option<ForexQuoteSource> {
+        self.forex_sources.borrow().get(pair).copied()
+    }
+
+    /// Returns the stock's close price for the specified date (or the nearest previous trading day) if any of the
+    /// providers supports historical quotes. Historical quotes aren't cached in the database.
+    pub fn get_historical(&self, query: QuoteQuery, date: Date) -> GenericResult<Option<HistoricalQuote>> {
+        let symbol = query.symbol().to_owned();
+        self.prefetch_historical([(query, date)])?;
+        Ok(self.historical_quotes.borrow().get(&(symbol, date)).cloned().flatten())
+    }
+
+    /// Fetches historical quotes for all the specified queries, so the following `get_historical()` calls for them
+    /// don't send any requests. The requests are grouped per provider and sent in parallel.
+    ///
+    /// Quotes are validated against the trading calendar: if a provider has a gap in quotes before the requested
+    /// date, the fallback providers are tried and if none of them has a fresh quote, the price is interpolated
+    /// between the nearest known quotes.
+    pub fn prefetch_historical<T>(&self, queries: T) -> EmptyResult
+        where T: IntoIterator<Item=(QuoteQuery, Date)>
+    {
+        let _timer = profiling::start(Phase::Quotes);
+        let mut plan: HashMap<(String, Date), Vec<usize>> = HashMap::new();
+
+        for (query, date) in queries {
+            let (symbol, exchanges) = match query {
+                QuoteQuery::Stock(symbol, exchanges) => (symbol, exchanges),
+                QuoteQuery::Forex(_) => return Err!("Historical quotes are supported only for stocks"),
+            };
+
+            let key = (symbol, date);
+            if !self.historical_quotes.borrow().contains_key(&key) && !plan.contains_key(&key) {
+                plan.insert(key, self.get_stock_providers(exchanges));
+            }
+        }
+
+        let total = plan.len();
+        let mut progress = progress::start("Fetching historical quotes", total);
+
+        // The latest quotes which have a gap before the requested date
+        let mut stale_quotes: HashMap<(String, Date), (usize, HistoricalQuote)> = HashMap::new();
+        let mut pass = 0;
+
+        while !plan.is_empty() {
+            let mut pass_plan: HashMap<usize, Vec<(String, Date)>> = HashMap::new();
+
+            plan.retain(|key, providers| {
+                match providers.get(pass) {
+                    Some(&provider_id) => {
+                        pass_plan.entry(provider_id).or_default().push(key.clone());
+                        true
+                    },
+                    None => {
+                        if !stale_quotes.contains_key(key) {
+                            self.historical_quotes.borrow_mut().insert(key.clone(), None);
+                        }
+                        false
+                    },
+                }
+            });
+
+            for (provider_id, quotes) in self.get_historical_quotes(pass_plan)? {
+                for (key, quote) in quotes {
+                    let quote = match quote {
+                        Some(quote) => quote,
+                        None => continue,
+                    };
+
+                    if get_missing_trading_days(quote.date, key.1) <= MAX_HISTORICAL_QUOTES_GAP {
+                        plan.remove(&key);
+                        stale_quotes.remove(&key);
+                        self.historical_quotes.borrow_mut().insert(key, Some(quote));
+                        continue;
+                    }
+
+                    match stale_quotes.entry(key) {
+                        Entry::Occupied(mut entry) => if entry.get().1.date < quote.date {
+                            entry.insert((provider_id, quote));
+                        },
+                        Entry::Vacant(entry) => {
+                            entry.insert((provider_id, quote));
+                        },
+                    }
+                }
+            }
+
+            pass += 1;
+            progress.set(total - plan.len());
+        }
+        drop(progress);
+
+        self.fill_historical_quotes_gaps(stale_quotes)
+    }
+
+    fn fill_historical_quotes_gaps(
+        &self, stale_quotes: HashMap<(String, Date), (usize, HistoricalQuote)>,
+    ) -> EmptyResult {
+        let today = time::today();
+        let mut plan: HashMap<usize, Vec<(String, Date)>> = HashMap::new();
+
+        for ((symbol, date), (provider_id, _)) in &stale_quotes {
+            let lookahead_date = std::cmp::min(*date + Duration::days(HISTORICAL_QUOTES_LOOKAHEAD_DAYS), today);
+            plan.entry(*provider_id).or_default().push((symbol.clone(), lookahead_date));
+        }
+
+        let mut next_quotes = HashMap::new();
+        for (_, quotes) in self.get_historical_quotes(plan)? {
+            for ((symbol, _), quote) in quotes {
+                if let Some(quote) = quote {
+                    next_quotes.insert(symbol, quote);
+                }
+            }
+        }
+
+        for ((symbol, date), (_, mut quote)) in stale_quotes {
+            match next_quotes.get(&symbol) {
+                Some(next) if next.date > date && next.price.currency == quote.price.currency => {
+                    let (prev_days, days) = ((date - quote.date).num_days(), (next.date - quote.date).num_days());
+                    let change = (next.price.amount - quote.price.amount)
+                        * Decimal::from(prev_days) / Decimal::from(days);
+
+                    quote.date = date;
+                    quote.price.amount = (quote.price.amount + change).normalize();
+                    quote.kind = HistoricalQuoteKind::Interpolated;
+                },
+                _ => quote.kind = HistoricalQuoteKind::Stale,
+            }
+
+            self.historical_quotes.borrow_mut().insert((symbol, date), Some(quote));
+        }
+
+        Ok(())
+    }
+
+    fn get_historical_quotes(
+        &self, plan: HashMap<usize, Vec<(String, Date)>>,
+    ) -> GenericResult<Vec<(usize, Vec<((String, Date), Option<HistoricalQuote>)>)>> {
+        let plan: Vec<_> = plan.into_iter().map(|(provider_id, requests)| {
+            (provider_id, self.providers[provider_id].clone(), requests)
+        }).collect();
+
+        plan.into_par_iter().map(|(provider_id, provider, requests)| {
+            Ok((provider_id, get_historical_quotes(provider.as_ref(), requests)?))
+        }).collect()
     }
 
     fn batch_forex(&self, mut symbol: String) -> GenericResult<Option<Cash>> {
         let (base, quote) = forex::parse_currency_pair(&symbol)?;
 
-        if let Some(price) = self.cache.get(&symbol)? {
+        if let Some(price) = self.cache.get(&symbol, &[])? {
             return Ok(Some(price));
         }
 
@@ -227,7 +505,11 @@ impl Quotes {
         }
         assert!(!exchanges.is_empty());
 
-        if let Some(price) = self.cache.get(&symbol)? {
+        if let Some(&price) = self.valuated_quotes.borrow().get(&symbol) {
+            return Ok(Some(price));
+        }
+
+        if let Some(price) = self.cache.get(&symbol, &exchanges)? {
             return Ok(Some(price));
         }
 
@@ -253,34 +535,20 @@ impl Quotes {
         let mut plan = HashMap::new();
 
         for (symbol, request) in self.batched_requests.borrow_mut().drain() {
-            let mut providers = Vec::new();
-
-            match request {
+            let providers = match request {
                 QuoteRequest::Forex => {
+                    let mut providers = Vec::new();
+
                     for (index, provider) in self.providers.iter().enumerate() {
                         if provider.supports_forex() {
                             providers.push(index);
                         }
                     }
+
+                    providers
                 },
-                QuoteRequest::Stock(exchanges) => {
-                    for exchange in self.pre_process_stock_exchanges(exchanges) {
-                        for (index, provider) in self.providers.iter().enumerate() {
-                            match provider.supports_stocks() {
-                                SupportedExchange::Some(provider_exchange) => {
-                                    if provider_exchange == exchange {
-                                        providers.push(index);
-                                    }
-                                },
-                                SupportedExchange::Any => {
-                                    providers.push(index);
-                                },
-                                SupportedExchange::None => {},
-                            }
-                        }
-                    }
-                },
-            }
+                QuoteRequest::Stock(exchanges) => self.get_stock_providers(exchanges),
+            };
 
             plan.insert(symbol, providers);
         }
@@ -288,6 +556,28 @@ impl Quotes {
         plan
     }
 
+    fn get_stock_providers(&self, exchanges: Vec<Exchange>) -> Vec<usize> {
+        let mut providers = Vec::new();
+
+        for exchange in self.pre_process_stock_exchanges(exchanges) {
+            for (index, provider) in self.providers.iter().enumerate() {
+                match provider.supports_stocks() {
+                    SupportedExchange::Some(provider_exchange) => {
+                        if provider_exchange == exchange {
+                            providers.push(index);
+                        }
+                    },
+                    SupportedExchange::Any => {
+                        providers.push(index);
+                    },
+                    SupportedExchange::None => {},
+                }
+            }
+        }
+
+        providers
+    }
+
     fn has_stock_provider(&self, exchange: Exchange) -> bool {
         self.providers.iter().any(|provider| provider.supports_stocks() == SupportedExchange::Some(exchange))
     }
@@ -355,8 +645,8 @@ impl Quotes {
                        provider.name(), symbols.join(", "));
 
                 let symbols: Vec<_> = symbols.iter().map(String::as_str).collect();
-                let quotes = provider.get_quotes(&symbols).map_err(|e| format!(
-                    "Failed to get quotes from {}: {}", provider.name(), e))?;
+                let quotes = provider.get_quotes(&symbols).map_err(|e| errors::Error::from(
+                    QuotesError::Provider {provider: provider.name().to_owned(), source: e}))?;
 
                 Ok((provider, quotes))
             }).collect::<Vec<_>>() {
@@ -375,6 +665,16 @@ impl Quotes {
                             let reverse_price = Cash::new(base, dec!(1) / price.amount);
                             self.cache.save(&reverse_pair, reverse_price)?;
                             plan.remove(&reverse_pair);
+
+                            let source = ForexQuoteSource {
+                                provider: provider.name(),
+                                reversed: false,
+                                cross_currency: provider.forex_cross_currency(base, quote),
+                            };
+
+                            let mut sources = self.forex_sources.borrow_mut();
+                            sources.insert(reverse_pair, ForexQuoteSource {reversed: true, ..source});
+                            sources.insert(symbol.clone(), source);
                         },
 
                         // Stocks
@@ -401,16 +701,161 @@ impl Quotes {
             pass += 1;
         }
 
+        self.apply_valuation_policies(&mut plan)?;
+
         if !plan.is_empty() {
-            return Err!(
-                "Unable to find quotes for following symbols: {}",
-                plan.into_keys().join(", "));
+            return Err(errors::Error::from(QuotesError::NotFound(plan.into_keys().collect())).into());
+        }
+
+        Ok(())
+    }
+
+    // Valuates the instruments which have no actual quotes according to the configured policies. The resulting prices
+    // aren't saved to the cache, so the quotes will be requested again on the next run.
+    fn apply_valuation_policies(&self, plan: &mut HashMap<String, Vec<usize>>) -> EmptyResult {
+        let today = time::today();
+
+        let mut policies = Vec::new();
+        let mut historical_plan: HashMap<usize, Vec<(String, Date)>> = HashMap::new();
+
+        for (symbol, providers) in plan.iter() {
+            let policy = match self.valuation_policies.get(symbol) {
+                Some(&policy) => policy,
+                None => continue,
+            };
+
+            // Zero price also requires the last known quote to determine the instrument currency
+            if !matches!(policy, ValuationPolicy::Manual(_)) {
+                for &provider_id in providers {
+                    historical_plan.entry(provider_id).or_default().push((symbol.clone(), today));
+                }
+            }
+
+            policies.push((symbol.clone(), policy));
+        }
+
+        let mut last_quotes: HashMap<String, HistoricalQuote> = HashMap::new();
+
+        for (_, quotes) in self.get_historical_quotes(historical_plan)? {
+            for ((symbol, _), quote) in quotes {
+                let quote = match quote {
+                    Some(quote) => quote,
+                    None => continue,
+                };
+
+                match last_quotes.entry(symbol) {
+                    Entry::Occupied(mut entry) => if entry.get().date < quote.date {
+                        entry.insert(quote);
+                    },
+                    Entry::Vacant(entry) => {
+                        entry.insert(quote);
+                    },
+                }
+            }
+        }
+
+        for (symbol, policy) in policies {
+            let price = match policy {
+                ValuationPolicy::Manual(price) => price,
+                ValuationPolicy::Last | ValuationPolicy::Zero => {
+                    let quote = last_quotes.get(&symbol).ok_or_else(|| format!(
+                        "Unable to apply {} valuation policy to {}: there are no quotes for it", policy, symbol))?;
+
+                    if policy == ValuationPolicy::Zero {
+                        Cash::zero(quote.price.currency)
+                    } else {
+                        warn!("{} has no quotes since {}.", symbol, formatting::format_date(quote.date));
+                        quote.price
+                    }
+                },
+            };
+
+            warn!("{} is valued at {} according to its valuation policy ({}).", symbol, price, policy);
+
+            self.valuated_quotes.borrow_mut().insert(symbol.clone(), price);
+            plan.remove(&symbol);
         }
 
         Ok(())
     }
 }
 
+// Requests historical quotes from the provider sending up to MAX_PARALLEL_HISTORICAL_REQUESTS requests in parallel
+fn get_historical_quotes(
+    provider: &dyn QuotesProvider, requests: Vec<(String, Date)>,
+) -> GenericResult<Vec<((String, Date), Option<HistoricalQuote>)>> {
+    let mut quotes = Vec::with_capacity(requests.len());
+
+    for chunk in requests.chunks(MAX_PARALLEL_HISTORICAL_REQUESTS) {
+        for result in chunk.par_iter().map(|(symbol, date)| -> GenericResult<_> {
+            debug!("Getting {} historical quote for {} from {}...", symbol, date, provider.name());
+
+            let quote = provider.get_historical_quote(symbol, *date).map_err(|e| errors::Error::from(
+                QuotesError::HistoricalProvider {provider: provider.name().to_owned(), source: e}))?;
+
+            Ok(((symbol.clone(), *date), quote))
+        }).collect::<Vec<_>>() {
+            quotes.push(result?);
+        }
+    }
+
+    Ok(quotes)
+}
+
+// Returns the number of trading days (holidays aren't taken into account) after the quote date up to the specified date
+fn get_missing_trading_days(quote_date: Date, date: Date) -> usize {
+    quote_date.iter_days().skip(1).take_while(|&day| day <= date)
+        .filter(|day| !matches!(day.weekday(), Weekday::Sat | Weekday::Sun))
+        .count()
+}
+
+/// Checks FCS API access key by requesting a quote which must always be available
+pub fn check_fcsapi_access_key(access_key: &str) -> EmptyResult {
+    check_provider(&FcsApi::new(&FcsApiConfig::new(access_key), None), "USD/RUB")
+}
+
+/// Checks Finnhub token by requesting a quote which must always be available
+pub fn check_finnhub_token(token: &str) -> EmptyResult {
+    check_provider(&Finnhub::new(&FinnhubConfig::new(token), None), "AAPL")
+}
+
+/// Checks T-Bank API token by requesting a quote which must always be available
+pub fn check_tbank_api_token(token: &str) -> EmptyResult {
+    check_provider(&Tbank::new(&TbankApiConfig::new(token), TbankExchange::Currency)?, "USD/RUB")
+}
+
+/// Checks credentials of all configured quotes providers which require them
+pub fn check_credentials(config: &Config, database: db::Connection) -> Vec<(&'static str, EmptyResult)> {
+    let mut results = Vec::new();
+
+    let tbank = config.brokers.as_ref()
+        .and_then(|brokers| brokers.tbank.as_ref())
+        .and_then(|tbank| tbank.api.as_ref());
+
+    if let Some(config) = tbank {
+        results.push(("T-Bank", Tbank::new(config, TbankExchange::Currency).and_then(|provider| {
+            check_provider(&provider, "USD/RUB")
+        })));
+    }
+
+    if let Some(config) = config.quotes.fcsapi.as_ref() {
+        results.push(("FCS API", check_provider(&FcsApi::new(config, Some(database.clone())), "USD/RUB")));
+    }
+
+    if let Some(config) = config.quotes.finnhub.as_ref() {
+        results.push(("Finnhub", check_provider(&Finnhub::new(config, Some(database)), "AAPL")));
+    }
+
+    results
+}
+
+fn check_provider(provider: &dyn QuotesProvider, symbol: &str) -> EmptyResult {
+    if !provider.get_quotes(&[symbol])?.contains_key(symbol) {
+        return Err!("{} hasn't returned {} quote", provider.name(), symbol);
+    }
+    Ok(())
+}
+
 type QuotesMap = HashMap<String, Cash>;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -426,6 +871,11 @@ trait QuotesProvider: Send + Sync {
     fn supports_forex(&self) -> bool {false}
     fn high_precision(&self) -> bool {false}
     fn get_quotes(&self, symbols: &[&str]) -> GenericResult<QuotesMap>;
+    fn get_historical_quote(&self, _symbol: &str, _date: Date) -> GenericResult<Option<HistoricalQuote>> {Ok(None)}
+
+    // Providers which calculate cross rates for the pairs they don't have direct quotes for return the intermediate
+    // currency here
+    fn forex_cross_currency(&self, _base: &str, _quote: &str) -> Option<&'static str> {None}
 }
 
 #[cfg(test)]
@@ -567,4 +1017,207 @@ mod tests {
         assert_eq!(quotes.get(query("IWDA")).unwrap(), Cash::new("USD", dec!(79.76)));
         assert_eq!(quotes.get(query("BNDX")).unwrap(), Cash::new("USD", dec!(90.12)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn historical_prefetch() {
+        struct HistoricalProvider {
+            name: &'static str,
+            quotes: HashMap<&'static str, Vec<(Date, Decimal)>>,
+            requests: Mutex<Vec<(String, Date)>>,
+        }
+
+        impl QuotesProvider for HistoricalProvider {
+            fn name(&self) -> &'static str {
+                self.name
+            }
+
+            fn supports_stocks(&self) -> SupportedExchange {
+                SupportedExchange::Some(Exchange::Moex)
+            }
+
+            fn get_quotes(&self, _symbols: &[&str]) -> GenericResult<QuotesMap> {
+                unreachable!()
+            }
+
+            fn get_historical_quote(&self, symbol: &str, date: Date) -> GenericResult<Option<HistoricalQuote>> {
+                self.requests.lock().unwrap().push((symbol.to_owned(), date));
+
+                Ok(self.quotes.get(symbol).and_then(|quotes| {
+                    quotes.iter().rev().find(|&&(quote_date, _)| quote_date <= date)
+                }).map(|&(quote_date, price)| HistoricalQuote::new(quote_date, Cash::new("RUB", price))))
+            }
+        }
+
+        let first = Arc::new(HistoricalProvider {
+            name: "first-provider",
+            quotes: hashmap! {
+                "SBER" => vec![(date!(2023, 12, 28), dec!(271.06))],
+                "GAZP" => vec![(date!(2023, 12, 15), dec!(159.8))],
+                "LKOH" => vec![(date!(2023, 12, 15), dec!(7000)), (date!(2024, 1, 4), dec!(7100))],
+                "MGNT" => vec![(date!(2023, 12, 15), dec!(6000))],
+            },
+            requests: Mutex::default(),
+        });
+
+        let second = Arc::new(HistoricalProvider {
+            name: "second-provider",
+            quotes: hashmap! {
+                "SBER" => vec![(date!(2023, 12, 29), dec!(1))],
+                "FXRL" => vec![(date!(2023, 12, 29), dec!(3541))],
+                "MGNT" => vec![(date!(2023, 12, 29), dec!(6100))],
+            },
+            requests: Mutex::default(),
+        });
+
+        let (_database, cache) = Cache::new_temporary();
+        let quotes = Quotes::new_with(cache, vec![first.clone(), second.clone()]);
+
+        let date = date!(2023, 12, 29);
+        let query = |symbol: &str| QuoteQuery::Stock(symbol.to_owned(), vec![Exchange::Moex]);
+
+        let symbols = ["SBER", "GAZP", "LKOH", "MGNT", "FXRL", "UNKNOWN", "SBER"];
+        quotes.prefetch_historical(symbols.into_iter().map(|symbol| (query(symbol), date))).unwrap();
+
+        let requests = |provider: &HistoricalProvider| {
+            let mut requests = provider.requests.lock().unwrap().clone();
+            requests.sort();
+            requests
+        };
+
+        let lookahead_date = date!(2024, 1, 12);
+        assert_eq!(requests(&first), [
+            (s!("FXRL"), date),
+            (s!("GAZP"), date), (s!("GAZP"), lookahead_date),
+            (s!("LKOH"), date), (s!("LKOH"), lookahead_date),
+            (s!("MGNT"), date),
+            (s!("SBER"), date),
+            (s!("UNKNOWN"), date),
+        ]);
+        assert_eq!(requests(&second), [
+            (s!("FXRL"), date),
+            (s!("GAZP"), date),
+            (s!("LKOH"), date),
+            (s!("MGNT"), date),
+            (s!("UNKNOWN"), date),
+        ]);
+
+        let quote = |quote_date, price, kind| Some(HistoricalQuote {
+            date: quote_date,
+            price: Cash::new("RUB", price),
+            kind,
+        });
+
+        assert_eq!(quotes.get_historical(query("SBER"), date).unwrap(), quote(
+            date!(2023, 12, 28), dec!(271.06), HistoricalQuoteKind::Actual));
+        assert_eq!(quotes.get_historical(query("GAZP"), date).unwrap(), quote(
+            date!(2023, 12, 15), dec!(159.8), HistoricalQuoteKind::Stale));
+        assert_eq!(quotes.get_historical(query("LKOH"), date).unwrap(), quote(
+            date, dec!(7070), HistoricalQuoteKind::Interpolated));
+        assert_eq!(quotes.get_historical(query("MGNT"), date).unwrap(), quote(
+            date, dec!(6100), HistoricalQuoteKind::Actual));
+        assert_eq!(quotes.get_historical(query("FXRL"), date).unwrap(), quote(
+            date, dec!(3541), HistoricalQuoteKind::Actual));
+        assert_eq!(quotes.get_historical(query("UNKNOWN"), date).unwrap(), None);
+
+        assert_eq!(first.requests.lock().unwrap().len(), 8);
+        assert_eq!(second.requests.lock().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn valuation_policies() {
+        struct SuspendedProvider {
+        }
+
+        impl QuotesProvider for SuspendedProvider {
+            fn name(&self) -> &'static str {
+                "suspended-provider"
+            }
+
+            fn supports_stocks(&self) -> SupportedExchange {
+                SupportedExchange::Some(Exchange::Moex)
+            }
+
+            fn get_quotes(&self, symbols: &[&str]) -> GenericResult<QuotesMap> {
+                Ok(symbols.iter().filter(|&&symbol| symbol == "SBER").map(|&symbol| {
+                    (symbol.to_owned(), Cash::new("RUB", dec!(271.06)))
+                }).collect())
+            }
+
+            fn get_historical_quote(&self, symbol: &str, _date: Date) -> GenericResult<Option<HistoricalQuote>> {
+                Ok(match symbol {
+                    "FXUS" | "FXRB" => Some(HistoricalQuote::new(date!(2022, 2, 25), Cash::new("RUB", dec!(5766)))),
+                    _ => None,
+                })
+            }
+        }
+
+        let (_database, cache) = Cache::new_temporary();
+        let quotes = Quotes::new_with(cache, vec![Arc::new(SuspendedProvider {})]).with_valuation_policies(hashmap! {
+            s!("SBER") => ValuationPolicy::Zero,
+            s!("FXUS") => ValuationPolicy::Last,
+            s!("FXRB") => ValuationPolicy::Zero,
+            s!("FXCN") => ValuationPolicy::Manual(Cash::new("RUB", dec!(2660))),
+            s!("FXIT") => ValuationPolicy::Last,
+        });
+
+        let query = |symbol: &str| QuoteQuery::Stock(symbol.to_owned(), vec![Exchange::Moex]);
+
+        for symbol in ["SBER", "FXUS", "FXRB", "FXCN"] {
+            assert!(quotes.batch(query(symbol)).unwrap().is_none());
+        }
+        quotes.execute().unwrap();
+
+        assert_eq!(quotes.get(query("SBER")).unwrap(), Cash::new("RUB", dec!(271.06)));
+        assert_eq!(quotes.get(query("FXUS")).unwrap(), Cash::new("RUB", dec!(5766)));
+        assert_eq!(quotes.get(query("FXRB")).unwrap(), Cash::zero("RUB"));
+        assert_eq!(quotes.get(query("FXCN")).unwrap(), Cash::new("RUB", dec!(2660)));
+        assert!(quotes.get(query("FXIT")).is_err());
+    }
+
+    #[test]
+    fn forex_sources() {
+        struct ForexProvider {
+        }
+
+        impl QuotesProvider for ForexProvider {
+            fn name(&self) -> &'static str {
+                "forex-provider"
+            }
+
+            fn supports_forex(&self) -> bool {
+                true
+            }
+
+            fn forex_cross_currency(&self, base: &str, quote: &str) -> Option<&'static str> {
+                (base != "RUB" && quote != "RUB").then_some("RUB")
+            }
+
+            fn get_quotes(&self, symbols: &[&str]) -> GenericResult<QuotesMap> {
+                let mut symbols = symbols.to_vec();
+                symbols.sort_unstable();
+                assert_eq!(&symbols, &["USD/EUR", "USD/RUB"]);
+
+                // Return reverse pair quote for one of the requested pairs
+                Ok(hashmap! {
+                    s!("RUB/USD") => Cash::new("USD", dec!(0.01)),
+                    s!("USD/EUR") => Cash::new("EUR", dec!(0.8)),
+                })
+            }
+        }
+
+        let (_database, cache) = Cache::new_temporary();
+        let quotes = Quotes::new_with(cache, vec![Arc::new(ForexProvider {})]);
+        let query = |pair: &str| QuoteQuery::Forex(pair.to_owned());
+
+        quotes.batch_all([query("USD/RUB"), query("EUR/USD")]).unwrap();
+        assert_eq!(quotes.get(query("USD/RUB")).unwrap(), Cash::new("RUB", dec!(100)));
+        assert_eq!(quotes.get(query("EUR/USD")).unwrap(), Cash::new("USD", dec!(1.25)));
+
+        let source = |provider, reversed, cross_currency| Some(ForexQuoteSource {provider, reversed, cross_currency});
+        assert_eq!(quotes.get_forex_source("RUB/USD"), source("forex-provider", false, None));
+        assert_eq!(quotes.get_forex_source("USD/RUB"), source("forex-provider", true, None));
+        assert_eq!(quotes.get_forex_source("USD/EUR"), source("forex-provider", false, Some("RUB")));
+        assert_eq!(quotes.get_forex_source("EUR/USD"), source("forex-provider", true, Some("RUB")));
+        assert_eq!(quotes.get_forex_source("EUR/RUB"), None);
+    }
+}
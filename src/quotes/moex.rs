@@ -1,33 +1,73 @@
 use std::collections::HashMap;
 use std::str::FromStr;
+#[cfg(test)] use std::sync::Arc;
 
-use log::{error, trace};
+use chrono::{Duration, NaiveDateTime};
+#[cfg(not(test))] use chrono::TimeZone;
+use log::error;
 use reqwest::Url;
-use reqwest::blocking::Client;
 use serde::Deserialize;
 use serde::de::{Deserializer, Error};
 
-use crate::core::GenericResult;
+use crate::broker_statement::DividendHistory;
+use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
 use crate::exchanges::Exchange;
 use crate::formats::xml;
-use crate::time;
+use crate::network::{Client, Proxy, RetryPolicy};
+#[cfg(test)] use crate::network::Transport;
+use crate::time::{self, Period};
+#[cfg(not(test))] use crate::time::SystemTime;
 use crate::types::{Decimal, Date};
 
-use super::{SupportedExchange, QuotesMap, QuotesProvider};
+use super::{HistoricalQuote, SupportedExchange, QuotesMap, QuotesProvider};
+use super::common::send_request;
+#[cfg(not(test))] use super::common::is_outdated_quote;
+
+/// How historical prices are adjusted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PriceAdjustment {
+    None,
+    /// Prices are adjusted for the paid dividends as if they were reinvested (total return), so price change between
+    /// two dates reflects the whole investment result.
+    Dividends,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MoexConfig {
+    /// Return dividend-adjusted historical prices
+    #[serde(default)]
+    pub dividend_adjusted_history: bool,
+}
 
 pub struct Moex {
     url: String,
     board: String,
+    adjustment: PriceAdjustment,
+    client: Client,
 }
 
 impl Moex {
-    pub fn new(url: &str, board: &str) -> Moex {
+    pub fn new(url: &str, board: &str, proxy: Option<&Proxy>, retry_policy: Option<&RetryPolicy>) -> Moex {
         Moex {
             url: url.to_owned(),
             board: board.to_owned(),
+            adjustment: PriceAdjustment::None,
+            client: Client::new("Moscow Exchange", proxy, retry_policy),
         }
     }
+
+    pub fn with_adjustment(mut self, adjustment: PriceAdjustment) -> Moex {
+        self.adjustment = adjustment;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Moex {
+        self.client = self.client.with_transport(transport);
+        self
+    }
 }
 
 impl QuotesProvider for Moex {
@@ -46,13 +86,7 @@ impl QuotesProvider for Moex {
         )?;
 
         let get = |url| -> GenericResult<HashMap<String, Cash>> {
-            trace!("Sending request to {}...", url);
-            let response = Client::new().get(url).send()?;
-            trace!("Got response from {}.", url);
-
-            if !response.status().is_success() {
-                return Err!("The server returned an error: {}", response.status());
-            }
+            let response = send_request(&self.client, url, None)?;
 
             Ok(parse_quotes(&response.bytes()?).map_err(|e| format!(
                 "Quotes info parsing error: {}", e))?)
@@ -61,8 +95,96 @@ impl QuotesProvider for Moex {
         Ok(get(url.as_str()).map_err(|e| format!(
             "Failed to get quotes from {}: {}", url, e))?)
     }
+
+    fn get_historical_quote(&self, symbol: &str, date: Date) -> GenericResult<Option<HistoricalQuote>> {
+        // Request some period to get the quote from the nearest previous trading day if the date is a holiday. Adjusted
+        // prices depend on all dividends paid after the date, so request the whole period up to today in this case.
+        let first_date = date - Duration::days(HISTORY_LOOKUP_DAYS);
+        let last_date = match self.adjustment {
+            PriceAdjustment::None => date,
+            PriceAdjustment::Dividends => std::cmp::max(date, time::today()),
+        };
+
+        let candles = self.get_candles(symbol, Period::new(first_date, last_date)?, self.adjustment)?;
+
+        Ok(candles.into_iter().rev()
+            .find(|&(trade_date, _)| trade_date <= date)
+            .map(|(trade_date, price)| HistoricalQuote::new(trade_date, price)))
+    }
 }
 
+impl Moex {
+    /// Returns daily close prices for the specified period. Days without trades are skipped.
+    pub fn get_candles(
+        &self, symbol: &str, period: Period, adjustment: PriceAdjustment,
+    ) -> GenericResult<Vec<(Date, Cash)>> {
+        let mut candles = Vec::new();
+        let mut start = 0;
+
+        loop {
+            let mut params = vec![
+                ("from", period.first_date().format("%Y-%m-%d").to_string()),
+                ("till", period.last_date().format("%Y-%m-%d").to_string()),
+            ];
+            if start != 0 {
+                params.push(("start", start.to_string()));
+            }
+
+            let url = Url::parse_with_params(
+                &format!("{}/iss/history/engines/stock/markets/shares/boards/{}/securities/{}.xml",
+                         self.url, self.board, symbol),
+                &params,
+            )?;
+
+            let get = |url| -> GenericResult<HistoryPage> {
+                let response = send_request(&self.client, url, None)?;
+
+                Ok(parse_history(&response.bytes()?, symbol).map_err(|e| format!(
+                    "Quotes info parsing error: {}", e))?)
+            };
+
+            let page = get(url.as_str()).map_err(|e| format!(
+                "Failed to get historical quotes from {}: {}", url, e))?;
+
+            candles.extend(page.candles.into_iter().filter(|&(date, _)| period.contains(date)));
+
+            match page.next {
+                Some(next) if next > start => start = next,
+                _ => break,
+            }
+        }
+
+        if adjustment == PriceAdjustment::Dividends {
+            let dividends = self.get_dividends(symbol)?;
+            adjust_for_dividends(&mut candles, &dividends)?;
+        }
+
+        Ok(candles)
+    }
+
+    fn get_dividends(&self, symbol: &str) -> GenericResult<Vec<(Date, Cash)>> {
+        let url = format!("{}/iss/securities/{}/dividends.xml", self.url, symbol);
+
+        let get = |url| -> GenericResult<Vec<(Date, Cash)>> {
+            let response = send_request(&self.client, url, None)?;
+
+            Ok(parse_dividends(&response.bytes()?, symbol).map_err(|e| format!(
+                "Dividends info parsing error: {}", e))?)
+        };
+
+        Ok(get(&url).map_err(|e| format!(
+            "Failed to get dividends from {}: {}", url, e))?)
+    }
+}
+
+impl DividendHistory for Moex {
+    fn get_dividends(&self, symbol: &str) -> GenericResult<Vec<(Date, Cash)>> {
+        Moex::get_dividends(self, symbol)
+    }
+}
+
+const HISTORY_LOOKUP_DAYS: i64 = 14;
+
 fn parse_quotes(data: &[u8]) -> GenericResult<HashMap<String, Cash>> {
     #[derive(Deserialize)]
     struct Document {
@@ -171,8 +293,8 @@ fn parse_quotes(data: &[u8]) -> GenericResult<HashMap<String, Cash>> {
     for row in market_data {
         let symbol = get_value(row.symbol)?;
 
-        let date = time::parse_date_time(&get_value(row.time)?, "%Y-%m-%d %H:%M:%S")?.date();
-        if is_outdated(date) {
+        let time = time::parse_date_time(&get_value(row.time)?, "%Y-%m-%d %H:%M:%S")?;
+        if is_outdated(time) {
             outdated.push(symbol);
             continue;
         }
@@ -194,7 +316,8 @@ fn parse_quotes(data: &[u8]) -> GenericResult<HashMap<String, Cash>> {
                     return Err!("There is no last price for {}", symbol);
                 }
 
-                if is_outdated(prev_date) {
+                // The previous price is the close price of the previous trading day
+                if is_outdated(prev_date.and_hms_opt(23, 59, 59).unwrap()) {
                     outdated.push(symbol);
                     continue;
                 }
@@ -215,17 +338,241 @@ fn parse_quotes(data: &[u8]) -> GenericResult<HashMap<String, Cash>> {
     Ok(quotes)
 }
 
+struct HistoryPage {
+    candles: Vec<(Date, Cash)>,
+    next: Option<usize>,
+}
+
+fn parse_history(data: &[u8], symbol: &str) -> GenericResult<HistoryPage> {
+    #[derive(Deserialize)]
+    struct Document {
+        data: Vec<Data>,
+    }
+
+    #[derive(Deserialize)]
+    struct Data {
+        id: String,
+
+        #[serde(rename = "rows")]
+        table: Table,
+    }
+
+    #[derive(Deserialize)]
+    struct Table {
+        #[serde(rename = "row", default)]
+        rows: Vec<Row>,
+    }
+
+    #[derive(Deserialize)]
+    struct Row {
+        // History fields
+
+        #[serde(rename = "SECID")]
+        symbol: Option<String>,
+
+        #[serde(rename = "TRADEDATE")]
+        date: Option<String>,
+
+        #[serde(rename = "CURRENCYID")]
+        currency: Option<String>,
+
+        #[serde(default, rename = "LEGALCLOSEPRICE", deserialize_with = "deserialize_optional_decimal")]
+        price: Option<Decimal>,
+
+        // Cursor fields
+
+        #[serde(rename = "INDEX")]
+        index: Option<usize>,
+
+        #[serde(rename = "TOTAL")]
+        total: Option<usize>,
+
+        #[serde(rename = "PAGESIZE")]
+        page_size: Option<usize>,
+    }
+
+    let result: Document = xml::deserialize(data)?;
+    let mut candles: Vec<(Date, Cash)> = Vec::new();
+    let mut next = None;
+
+    for data in result.data {
+        match data.id.as_str() {
+            "history" => {},
+            "history.cursor" => {
+                for row in data.table.rows {
+                    let (index, total, page_size) = (
+                        get_value(row.index)?, get_value(row.total)?, get_value(row.page_size)?);
+
+                    if index + page_size < total {
+                        next = Some(index + page_size);
+                    }
+                }
+                continue;
+            },
+            _ => continue,
+        }
+
+        for row in data.table.rows {
+            let row_symbol = get_value(row.symbol)?;
+            if row_symbol != symbol {
+                return Err!("Got quotes for an unexpected symbol: {}", row_symbol);
+            }
+
+            let trade_date = time::parse_date(&get_value(row.date)?, "%Y-%m-%d")?;
+
+            // There is no close price for the days without trades
+            let price = match row.price {
+                Some(price) if !price.is_zero() => price,
+                _ => continue,
+            };
+
+            if price.is_sign_negative() {
+                return Err!("Invalid price: {}", price);
+            }
+
+            let currency = get_value(row.currency)?;
+            let currency = match currency.as_str() {
+                "SUR" => "RUB",
+                _ => return Err!("{} is nominated in an unsupported currency: {}", symbol, currency),
+            };
+
+            if let Some(&(last_date, _)) = candles.last() {
+                if trade_date <= last_date {
+                    return Err!("Got unordered quotes for {}", symbol);
+                }
+            }
+
+            candles.push((trade_date, Cash::new(currency, price)));
+        }
+    }
+
+    Ok(HistoryPage {candles, next})
+}
+
+fn parse_dividends(data: &[u8], symbol: &str) -> GenericResult<Vec<(Date, Cash)>> {
+    #[derive(Deserialize)]
+    struct Document {
+        data: Vec<Data>,
+    }
+
+    #[derive(Deserialize)]
+    struct Data {
+        id: String,
+
+        #[serde(rename = "rows")]
+        table: Table,
+    }
+
+    #[derive(Deserialize)]
+    struct Table {
+        #[serde(rename = "row", default)]
+        rows: Vec<Row>,
+    }
+
+    #[derive(Deserialize)]
+    struct Row {
+        #[serde(rename = "secid")]
+        symbol: Option<String>,
+
+        #[serde(rename = "registryclosedate")]
+        record_date: Option<String>,
+
+        #[serde(rename = "value")]
+        amount: Option<Decimal>,
+
+        #[serde(rename = "currencyid")]
+        currency: Option<String>,
+    }
+
+    let result: Document = xml::deserialize(data)?;
+    let mut dividends = Vec::new();
+
+    for data in result.data {
+        if data.id != "dividends" {
+            continue;
+        }
+
+        for row in data.table.rows {
+            let row_symbol = get_value(row.symbol)?;
+            if row_symbol != symbol {
+                return Err!("Got dividends for an unexpected symbol: {}", row_symbol);
+            }
+
+            let record_date = time::parse_date(&get_value(row.record_date)?, "%Y-%m-%d")?;
+
+            let amount = get_value(row.amount)?;
+            if amount.is_sign_negative() {
+                return Err!("Invalid dividend amount: {}", amount);
+            }
+
+            let currency = get_value(row.currency)?;
+            let currency = match currency.as_str() {
+                "RUB" | "SUR" => "RUB",
+                _ => return Err!("{} dividend is paid in an unsupported currency: {}", symbol, currency),
+            };
+
+            dividends.push((record_date, Cash::new(currency, amount)));
+        }
+    }
+
+    dividends.sort_by_key(|&(record_date, _)| record_date);
+    Ok(dividends)
+}
+
+// Adjusts the prices backward: all prices before ex-dividend date are multiplied by (1 - dividend / cum-dividend close
+// price), so the latest prices stay unchanged. Dividends with cum-dividend date outside of the candles period don't
+// affect the prices.
+fn adjust_for_dividends(candles: &mut [(Date, Cash)], dividends: &[(Date, Cash)]) -> EmptyResult {
+    for &(record_date, dividend) in dividends {
+        let mut ex_dividend_index = candles.partition_point(|&(date, _)| date < record_date);
+
+        // T+2 settlement was replaced with T+1 at 31.07.2023, so ex-dividend date moved from the trading day before
+        // record date to record date itself.
+        if record_date < date!(2023, 7, 31) {
+            if ex_dividend_index == 0 {
+                continue;
+            }
+            ex_dividend_index -= 1;
+        }
+
+        if ex_dividend_index == 0 || ex_dividend_index >= candles.len() {
+            continue;
+        }
+
+        let (_, close_price) = candles[ex_dividend_index - 1];
+        if close_price.currency != dividend.currency {
+            return Err!(
+                "Unable to adjust {} prices for {} dividend: currency mismatch",
+                close_price.currency, dividend.currency);
+        }
+
+        let factor = dec!(1) - dividend.amount / close_price.amount;
+        if !factor.is_sign_positive() || factor.is_zero() {
+            return Err!("Got an invalid {} dividend for {} close price", dividend, close_price);
+        }
+
+        for (_, price) in &mut candles[..ex_dividend_index] {
+            price.amount *= factor;
+        }
+    }
+
+    Ok(())
+}
+
 fn get_value<T>(value: Option<T>) -> GenericResult<T> {
     Ok(value.ok_or("Got an unexpected response from server")?)
 }
 
 #[cfg(not(test))]
-fn is_outdated(date: Date) -> bool {
-    date < Exchange::Moex.min_last_working_day(time::today())
+fn is_outdated(time: NaiveDateTime) -> bool {
+    // MOEX returns the time in Moscow timezone
+    chrono_tz::Europe::Moscow.from_local_datetime(&time).earliest().is_none_or(|time| {
+        is_outdated_quote(time, Some(Exchange::Moex), &SystemTime()).is_some()
+    })
 }
 
 #[cfg(test)]
-fn is_outdated(_date: Date) -> bool {
+fn is_outdated(_time: NaiveDateTime) -> bool {
     false
 }
 
@@ -251,6 +598,8 @@ mod tests {
 
     use mockito::{Server, ServerGuard, Mock};
 
+    use crate::quotes::common::cassette;
+
     use super::*;
 
     #[test]
@@ -304,9 +653,109 @@ mod tests {
         );
     }
 
+    #[test]
+    fn historical_quote() {
+        let board = "TQBR";
+        let (mut server, client) = create_server(board);
+
+        let _mock = server.mock("GET", format!(
+            "/iss/history/engines/stock/markets/shares/boards/{}/securities/SBER.xml?from=2023-12-17&till=2023-12-31",
+            board).as_str())
+            .with_status(200)
+            .with_header("Content-Type", "application/xml; charset=utf-8")
+            .with_body(read_body("moex-history.xml"))
+            .create();
+
+        let date = date!(2023, 12, 31);
+        assert_eq!(client.get_historical_quote("SBER", date).unwrap(), Some(HistoricalQuote::new(
+            date!(2023, 12, 29), Cash::new("RUB", dec!(271.06)))));
+    }
+
+    #[test]
+    fn recorded_historical_quote() {
+        let client = Moex::new("https://iss.moex.com", "TQBR", None, None).with_transport(cassette("moex"));
+        assert_eq!(
+            client.get_historical_quote("SBER", date!(2023, 12, 31)).unwrap(),
+            Some(HistoricalQuote::new(date!(2023, 12, 29), Cash::new("RUB", dec!(271.06)))));
+    }
+
+    #[test]
+    fn paginated_history() {
+        let board = "TQBR";
+        let (mut server, client) = create_server(board);
+        let path = format!("/iss/history/engines/stock/markets/shares/boards/{}/securities/SBER.xml", board);
+
+        let _first_page_mock = server.mock("GET", format!("{}?from=2023-12-26&till=2023-12-29", path).as_str())
+            .with_status(200)
+            .with_header("Content-Type", "application/xml; charset=utf-8")
+            .with_body(read_body("moex-history.xml")
+                .replace(r#"TOTAL="4" PAGESIZE="100""#, r#"TOTAL="5" PAGESIZE="4""#))
+            .create();
+
+        let _second_page_mock = server.mock("GET", format!("{}?from=2023-12-26&till=2023-12-29&start=4", path).as_str())
+            .with_status(200)
+            .with_header("Content-Type", "application/xml; charset=utf-8")
+            .with_body(read_body("moex-history.xml")
+                .replace("2023-12-2", "2024-01-0").replace(r#"INDEX="0""#, r#"INDEX="4""#))
+            .create();
+
+        let period = Period::new(date!(2023, 12, 26), date!(2023, 12, 29)).unwrap();
+        let candles = client.get_candles("SBER", period, PriceAdjustment::None).unwrap();
+
+        assert_eq!(candles, vec![
+            (date!(2023, 12, 26), Cash::new("RUB", dec!(269.75))),
+            (date!(2023, 12, 27), Cash::new("RUB", dec!(270.84))),
+            (date!(2023, 12, 28), Cash::new("RUB", dec!(271.61))),
+            (date!(2023, 12, 29), Cash::new("RUB", dec!(271.06))),
+        ]);
+    }
+
+    #[test]
+    fn dividends_parsing() {
+        assert_eq!(parse_dividends(read_body("moex-dividends.xml").as_bytes(), "SBER").unwrap(), vec![
+            (date!(2022, 5, 12), Cash::new("RUB", dec!(18.7))),
+            (date!(2023, 5, 11), Cash::new("RUB", dec!(25))),
+            (date!(2024, 7, 11), Cash::new("RUB", dec!(33.3))),
+        ]);
+    }
+
+    #[test]
+    fn dividends_adjustment() {
+        let price = |amount| Cash::new("RUB", amount);
+
+        let mut candles = vec![
+            (date!(2023, 5, 8), price(dec!(250))),
+            (date!(2023, 5, 10), price(dec!(240))),
+            (date!(2023, 5, 11), price(dec!(220))),
+            (date!(2023, 7, 28), price(dec!(100))),
+            (date!(2023, 7, 31), price(dec!(50))),
+            (date!(2023, 8, 1), price(dec!(60))),
+        ];
+
+        adjust_for_dividends(&mut candles, &[
+            // Cum-dividend date is out of the period
+            (date!(2023, 5, 8), price(dec!(10))),
+            // T+2: ex-dividend date is the trading day before the record date
+            (date!(2023, 5, 11), price(dec!(25))),
+            // T+1: ex-dividend date is the record date
+            (date!(2023, 7, 31), price(dec!(20))),
+            // Ex-dividend date is out of the period
+            (date!(2023, 8, 2), price(dec!(30))),
+        ]).unwrap();
+
+        assert_eq!(candles, vec![
+            (date!(2023, 5, 8), price(dec!(180))),
+            (date!(2023, 5, 10), price(dec!(192))),
+            (date!(2023, 5, 11), price(dec!(176))),
+            (date!(2023, 7, 28), price(dec!(80))),
+            (date!(2023, 7, 31), price(dec!(50))),
+            (date!(2023, 8, 1), price(dec!(60))),
+        ]);
+    }
+
     fn create_server(board: &str) -> (ServerGuard, Moex) {
         let server = Server::new();
-        let client = Moex::new(&server.url(), board);
+        let client = Moex::new(&server.url(), board, None, None);
         (server, client)
     }
 
@@ -319,14 +768,17 @@ mod tests {
             "/iss/engines/stock/markets/shares/boards/{}/securities.xml?securities={}",
             board, securities);
 
-        let mut body = String::new();
-        let body_path = Path::new(file!()).parent().unwrap().join("testdata").join(body_path);
-        File::open(body_path).unwrap().read_to_string(&mut body).unwrap();
-
         server.mock("GET", path.as_str())
             .with_status(200)
             .with_header("Content-Type", "application/xml; charset=utf-8")
-            .with_body(body)
+            .with_body(read_body(body_path))
             .create()
     }
+
+    fn read_body(path: &str) -> String {
+        let mut body = String::new();
+        let path = Path::new(file!()).parent().unwrap().join("testdata").join(path);
+        File::open(path).unwrap().read_to_string(&mut body).unwrap();
+        body
+    }
 }
\ No newline at end of file
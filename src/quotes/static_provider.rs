@@ -30,7 +30,7 @@ impl<'de> Deserialize<'de> for StaticProviderConfig {
     }
 }
 
-fn parse_price(value: &str) -> Option<Cash> {
+pub fn parse_price(value: &str) -> Option<Cash> {
     let value = util::fold_spaces(value);
     let mut tokens = value.split(' ');
 
@@ -1,4 +1,6 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::pin::Pin;
 use std::time::Duration;
 
 use chrono::{LocalResult, TimeZone, Utc};
@@ -12,18 +14,22 @@ use tonic::service::{Interceptor, interceptor::InterceptedService};
 use tonic::transport::{Channel, ClientTlsConfig};
 
 #[allow(clippy::all)]
-mod api {
+pub mod api {
     include!("tinkoff.public.invest.api.contract.v1.rs");
 }
 
 use api::{
     instruments_service_client::InstrumentsServiceClient, InstrumentsRequest, InstrumentStatus, RealExchange,
-    market_data_service_client::MarketDataServiceClient, GetLastPricesRequest,
+    CurrenciesResponse, SharesResponse, EtfsResponse,
+    market_data_service_client::MarketDataServiceClient, GetLastPricesRequest, GetLastPricesResponse,
 };
 
 use crate::core::{GenericResult, EmptyResult};
 use crate::exchanges::Exchange;
 use crate::forex;
+use crate::instruments::SecurityId;
+use crate::network::{self, Proxy, ProxyConnector, Retrier, RetryPolicy};
+use crate::secrets;
 use crate::util::{self, DecimalRestrictions};
 use crate::time::SystemTime;
 use crate::types::Decimal;
@@ -37,17 +43,40 @@ const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct TbankApiConfig {
-    #[serde(rename = "api_token")]
+    #[serde(rename = "api_token", deserialize_with = "secrets::deserialize")]
     token: String,
+    proxy: Option<Proxy>,
+    retry: Option<RetryPolicy>,
+}
+
+impl TbankApiConfig {
+    pub fn new(token: &str) -> TbankApiConfig {
+        TbankApiConfig {
+            token: token.to_owned(),
+            proxy: None,
+            retry: None,
+        }
+    }
+}
+
+pub type ApiFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Status>> + Send + 'a>>;
+
+/// T-Bank Invest API methods used by the quotes provider. gRPC client is used by default, but it may be replaced with
+/// recorded responses to test the provider offline.
+pub trait TbankApi: Send + Sync {
+    fn currencies(&self, request: InstrumentsRequest) -> ApiFuture<'_, CurrenciesResponse>;
+    fn shares(&self, request: InstrumentsRequest) -> ApiFuture<'_, SharesResponse>;
+    fn etfs(&self, request: InstrumentsRequest) -> ApiFuture<'_, EtfsResponse>;
+    fn get_last_prices(&self, request: GetLastPricesRequest) -> ApiFuture<'_, GetLastPricesResponse>;
 }
 
 // T-Bank Invest API (https://tinkoff.github.io/investAPI/)
 pub struct Tbank {
-    token: String,
     exchange: TbankExchange,
 
-    channel: Channel,
+    api: Box<dyn TbankApi>,
     runtime: Runtime,
+    retrier: Retrier,
 
     stocks: Mutex<HashMap<String, Vec<Stock>>>,
     currencies: Mutex<HashMap<(String, String), Currency>>,
@@ -58,32 +87,51 @@ impl Tbank {
         let runtime = tokio::runtime::Builder::new_current_thread()
             .enable_all().build().unwrap();
 
+        let connector = match network::get_proxy(config.proxy.as_ref()) {
+            Some(Proxy::Url(url)) => Some(ProxyConnector::new(url).map_err(|e| format!("T-Bank client: {e}"))?),
+            Some(Proxy::Direct) | None => None,
+        };
+
         let channel = runtime.block_on(async {
             Channel::from_static("https://sandbox-invest-public-api.tinkoff.ru")
                 .connect_timeout(CONNECT_TIMEOUT)
                 .timeout(REQUEST_TIMEOUT)
                 .tls_config(ClientTlsConfig::new().with_native_roots())
-                .map(|endpoint| endpoint.connect_lazy())
+                .map(|endpoint| match connector {
+                    Some(connector) => endpoint.connect_with_connector_lazy(connector),
+                    None => endpoint.connect_lazy(),
+                })
         }).map_err(|e| format!("T-Bank client: {e}"))?;
 
-        Ok(Tbank {
+        let api = GrpcApi {
             token: config.token.clone(),
-            exchange: exchange,
-
             channel: channel,
-            runtime: runtime,
+        };
 
-            stocks: Mutex::new(HashMap::new()),
-            currencies: Mutex::new(HashMap::new()),
-        })
+        Ok(Tbank::new_with_runtime(Box::new(api), exchange, runtime, config.retry.as_ref()))
     }
 
-    fn instruments_client(&self) -> InstrumentsServiceClient<InterceptedService<Channel, ClientInterceptor>> {
-        InstrumentsServiceClient::with_interceptor(self.channel.clone(), ClientInterceptor::new(&self.token))
+    #[cfg(test)]
+    pub fn with_api(api: Box<dyn TbankApi>, exchange: TbankExchange) -> Tbank {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all().build().unwrap();
+
+        Tbank::new_with_runtime(api, exchange, runtime, None)
     }
 
-    fn market_data_client(&self) -> MarketDataServiceClient<InterceptedService<Channel, ClientInterceptor>> {
-        MarketDataServiceClient::with_interceptor(self.channel.clone(), ClientInterceptor::new(&self.token))
+    fn new_with_runtime(
+        api: Box<dyn TbankApi>, exchange: TbankExchange, runtime: Runtime, retry_policy: Option<&RetryPolicy>,
+    ) -> Tbank {
+        Tbank {
+            exchange: exchange,
+
+            api: api,
+            runtime: runtime,
+            retrier: Retrier::new("T-Bank", network::get_retry_policy(retry_policy)),
+
+            stocks: Mutex::new(HashMap::new()),
+            currencies: Mutex::new(HashMap::new()),
+        }
     }
 
     async fn get_quotes_async(&self, symbols: &[&str]) -> GenericResult<QuotesMap> {
@@ -130,10 +178,10 @@ impl Tbank {
             }).sorted().join(", ")
         );
 
-        let last_prices = self.market_data_client().get_last_prices(GetLastPricesRequest {
+        let last_prices = self.api.get_last_prices(GetLastPricesRequest {
             instrument_id: instruments.keys().cloned().collect(),
             ..Default::default()
-        }).await?.into_inner().last_prices;
+        }).await?.last_prices;
 
         for last_price in last_prices {
             let instrument = instruments.remove(&last_price.instrument_uid).ok_or_else(|| format!(
@@ -157,7 +205,7 @@ impl Tbank {
                 _ => return Err!("Got an invalid {} quote time: {:?}", symbol, timestamp)
             };
 
-            if let Some(time) = is_outdated_quote(time, &SystemTime()) {
+            if let Some(time) = is_outdated_quote(time, self.exchange.exchange(), &SystemTime()) {
                 debug!("{}: Got outdated quotes: {}.", symbol, time);
                 continue;
             }
@@ -178,11 +226,11 @@ impl Tbank {
         let mut currencies = self.currencies.lock().await;
 
         if currencies.is_empty() {
-            let instruments = self.instruments_client().currencies(InstrumentsRequest {
+            let instruments = self.api.currencies(InstrumentsRequest {
                 ..Default::default()
-            }).await.map_err(|e| format!(
-                "Failed to get available currencies list: {}", e,
-            ))?.into_inner().instruments;
+            }).await.map_err(|e| network::with_context(
+                e, s!("Failed to get available currencies list"),
+            ))?.instruments;
 
             if instruments.is_empty() {
                 return Err!("Got an empty list of available currencies");
@@ -230,9 +278,18 @@ impl Tbank {
             *stocks = instruments;
         }
 
-        let found_stocks = match stocks.get(symbol) {
-            Some(instruments) => instruments,
-            None => return Ok(None),
+        // Instruments may be requested not only by their symbols, but also by ISIN or FIGI (for example for OTC stocks
+        // which don't have a symbol)
+        let found_stocks: Vec<&Stock> = match SecurityId::parse(symbol) {
+            SecurityId::Symbol(symbol) => stocks.get(&symbol).map(|stocks| stocks.iter().collect()).unwrap_or_default(),
+            SecurityId::Isin(isin) => {
+                let isin = isin.to_string();
+                stocks.values().flatten().filter(|stock| stock.isin == isin).collect()
+            },
+            SecurityId::Figi(figi) => {
+                let figi = figi.to_string();
+                stocks.values().flatten().filter(|stock| stock.figi == figi).collect()
+            },
         };
 
         if found_stocks.len() > 1 {
@@ -242,7 +299,10 @@ impl Tbank {
             return Err!("Got more than one stock for {:?} symbol: {}", symbol, names);
         }
 
-        Ok(found_stocks.first().cloned())
+        Ok(found_stocks.first().map(|&stock| Stock {
+            symbol: symbol.to_owned(),
+            ..stock.clone()
+        }))
     }
 
     async fn get_all_shares(&self, stocks: &mut HashMap<String, Vec<Stock>>) -> EmptyResult {
@@ -255,12 +315,12 @@ impl Tbank {
         let mut trace = InstrumentTrace::new(name, false);
 
         #[allow(clippy::needless_update)]
-        let instruments = self.instruments_client().shares(InstrumentsRequest {
+        let instruments = self.api.shares(InstrumentsRequest {
             instrument_status: status.into(),
             ..Default::default()
-        }).await.map_err(|e| format!(
-            "Failed to get available {} list: {}", name, e,
-        ))?.into_inner().instruments;
+        }).await.map_err(|e| network::with_context(
+            e, format!("Failed to get available {} list", name),
+        ))?.instruments;
 
         for stock in instruments {
             let real_exchange = stock.real_exchange();
@@ -273,6 +333,7 @@ impl Tbank {
             stocks.entry(stock.ticker.clone()).or_default().push(Stock {
                 uid: stock.uid,
                 isin: stock.isin,
+                figi: stock.figi,
                 symbol: stock.ticker.clone(),
                 name: stock.name,
                 currency: stock.currency.to_uppercase(),
@@ -295,12 +356,12 @@ impl Tbank {
         let mut trace = InstrumentTrace::new(name, may_be_empty);
 
         #[allow(clippy::needless_update)]
-        let instruments = self.instruments_client().etfs(InstrumentsRequest {
+        let instruments = self.api.etfs(InstrumentsRequest {
             instrument_status: status.into(),
             ..Default::default()
-        }).await.map_err(|e| format!(
-            "Failed to get available {} list: {}", name, e,
-        ))?.into_inner().instruments;
+        }).await.map_err(|e| network::with_context(
+            e, format!("Failed to get available {} list", name),
+        ))?.instruments;
 
         for stock in instruments {
             let real_exchange = stock.real_exchange();
@@ -313,6 +374,7 @@ impl Tbank {
             stocks.entry(stock.ticker.clone()).or_default().push(Stock {
                 uid: stock.uid,
                 isin: stock.isin,
+                figi: stock.figi,
                 symbol: stock.ticker.clone(),
                 name: stock.name,
                 currency: stock.currency.to_uppercase(),
@@ -368,7 +430,7 @@ impl QuotesProvider for Tbank {
     }
 
     fn get_quotes(&self, symbols: &[&str]) -> GenericResult<QuotesMap> {
-        self.runtime.block_on(self.get_quotes_async(symbols))
+        self.retrier.call(|| self.runtime.block_on(self.get_quotes_async(symbols)))
     }
 }
 
@@ -379,6 +441,16 @@ pub enum TbankExchange {
     Unknown, // Try to collect here instruments from exchanges that we don't support yet to use it as best effort fallback
 }
 
+impl TbankExchange {
+    fn exchange(self) -> Option<Exchange> {
+        match self {
+            TbankExchange::Currency => Some(Exchange::Moex),
+            TbankExchange::Spb => Some(Exchange::Spb),
+            TbankExchange::Unknown => None,
+        }
+    }
+}
+
 enum Instrument {
     Stock(Stock),
     Currency(Currency),
@@ -388,6 +460,7 @@ enum Instrument {
 struct Stock {
     uid: String,
     isin: String,
+    figi: String,
     symbol: String,
     name: String,
     currency: String,
@@ -402,6 +475,43 @@ struct Currency {
     denomination: Decimal,
 }
 
+struct GrpcApi {
+    token: String,
+    channel: Channel,
+}
+
+impl GrpcApi {
+    fn instruments_client(&self) -> InstrumentsServiceClient<InterceptedService<Channel, ClientInterceptor>> {
+        InstrumentsServiceClient::with_interceptor(self.channel.clone(), ClientInterceptor::new(&self.token))
+    }
+
+    fn market_data_client(&self) -> MarketDataServiceClient<InterceptedService<Channel, ClientInterceptor>> {
+        MarketDataServiceClient::with_interceptor(self.channel.clone(), ClientInterceptor::new(&self.token))
+    }
+}
+
+impl TbankApi for GrpcApi {
+    fn currencies(&self, request: InstrumentsRequest) -> ApiFuture<'_, CurrenciesResponse> {
+        let mut client = self.instruments_client();
+        Box::pin(async move { Ok(client.currencies(request).await?.into_inner()) })
+    }
+
+    fn shares(&self, request: InstrumentsRequest) -> ApiFuture<'_, SharesResponse> {
+        let mut client = self.instruments_client();
+        Box::pin(async move { Ok(client.shares(request).await?.into_inner()) })
+    }
+
+    fn etfs(&self, request: InstrumentsRequest) -> ApiFuture<'_, EtfsResponse> {
+        let mut client = self.instruments_client();
+        Box::pin(async move { Ok(client.etfs(request).await?.into_inner()) })
+    }
+
+    fn get_last_prices(&self, request: GetLastPricesRequest) -> ApiFuture<'_, GetLastPricesResponse> {
+        let mut client = self.market_data_client();
+        Box::pin(async move { Ok(client.get_last_prices(request).await?.into_inner()) })
+    }
+}
+
 struct ClientInterceptor {
     token: String,
 }
@@ -516,4 +626,119 @@ impl InstrumentTrace {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use api::{Currency as ApiCurrency, LastPrice, MoneyValue, Quotation, Share};
+
+    use crate::currency::Cash;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordedApi {
+        currencies: Vec<ApiCurrency>,
+        shares: Vec<Share>,
+        last_prices: Vec<LastPrice>,
+    }
+
+    impl TbankApi for RecordedApi {
+        fn currencies(&self, _request: InstrumentsRequest) -> ApiFuture<'_, CurrenciesResponse> {
+            let instruments = self.currencies.clone();
+            Box::pin(async move { Ok(CurrenciesResponse {instruments}) })
+        }
+
+        fn shares(&self, _request: InstrumentsRequest) -> ApiFuture<'_, SharesResponse> {
+            let instruments = self.shares.clone();
+            Box::pin(async move { Ok(SharesResponse {instruments}) })
+        }
+
+        fn etfs(&self, _request: InstrumentsRequest) -> ApiFuture<'_, EtfsResponse> {
+            Box::pin(async move { Ok(EtfsResponse::default()) })
+        }
+
+        fn get_last_prices(&self, request: GetLastPricesRequest) -> ApiFuture<'_, GetLastPricesResponse> {
+            let last_prices = self.last_prices.iter()
+                .filter(|last_price| request.instrument_id.contains(&last_price.instrument_uid))
+                .cloned().collect();
+            Box::pin(async move { Ok(GetLastPricesResponse {last_prices}) })
+        }
+    }
+
+    fn last_price(uid: &str, units: i64, nano: i32, time: chrono::DateTime<Utc>) -> LastPrice {
+        LastPrice {
+            instrument_uid: uid.to_owned(),
+            price: Some(Quotation {units, nano}),
+            time: Some(prost_types::Timestamp {seconds: time.timestamp(), nanos: 0}),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn currency_quotes() {
+        let currency = |uid: &str, ticker: &str, base: &str, units| ApiCurrency {
+            uid: uid.to_owned(),
+            ticker: ticker.to_owned(),
+            currency: s!("rub"),
+            nominal: Some(MoneyValue {currency: base.to_owned(), units, nano: 0}),
+            ..Default::default()
+        };
+
+        let now = Utc::now();
+        let api = RecordedApi {
+            currencies: vec![
+                currency("usd-uid", "USD000UTSTOM", "usd", 1),
+                currency("hkd-uid", "HKDRUB_TOM", "hkd", 100),
+                currency("eur-uid", "EUR_RUB__TOM", "eur", 1),
+            ],
+            last_prices: vec![
+                last_price("usd-uid", 90, 500_000_000, now),
+                last_price("hkd-uid", 1150, 0, now),
+                last_price("eur-uid", 100, 0, now - chrono::Duration::days(10)),
+            ],
+            ..Default::default()
+        };
+
+        let provider = Tbank::with_api(Box::new(api), TbankExchange::Currency);
+        assert_eq!(provider.get_quotes(&["USD/RUB", "HKD/RUB", "EUR/RUB", "CNY/RUB"]).unwrap(), hashmap! {
+            s!("USD/RUB") => Cash::new("RUB", dec!(90.5)),
+            s!("HKD/RUB") => Cash::new("RUB", dec!(11.5)),
+        });
+    }
+
+    #[test]
+    fn stock_quotes() {
+        let share = |uid: &str, ticker: &str, isin: &str, figi: &str| Share {
+            uid: uid.to_owned(),
+            ticker: ticker.to_owned(),
+            isin: isin.to_owned(),
+            figi: figi.to_owned(),
+            currency: s!("usd"),
+            real_exchange: RealExchange::Rts.into(),
+            ..Default::default()
+        };
+
+        let now = Utc::now();
+        let api = RecordedApi {
+            shares: vec![
+                share("aapl-uid", "AAPL", "US0378331005", "BBG000B9XRY4"),
+                share("ibm-uid", "IBM", "US4592001014", "BBG000BLNNH6"),
+                share("msft-uid", "MSFT", "US5949181045", "BBG000BPH459"),
+            ],
+            last_prices: vec![
+                last_price("aapl-uid", 200, 0, now),
+                last_price("ibm-uid", 250, 500_000_000, now),
+                last_price("msft-uid", 400, 0, now),
+            ],
+            ..Default::default()
+        };
+
+        let provider = Tbank::with_api(Box::new(api), TbankExchange::Spb);
+        assert_eq!(provider.get_quotes(&["AAPL", "US4592001014", "bbg000bph459", "UNKNOWN"]).unwrap(), hashmap! {
+            s!("AAPL") => Cash::new("USD", dec!(200)),
+            s!("US4592001014") => Cash::new("USD", dec!(250.5)),
+            s!("bbg000bph459") => Cash::new("USD", dec!(400)),
+        });
+    }
+}
@@ -2,12 +2,14 @@ use chrono::Utc;
 #[cfg(test)] use indoc::indoc;
 use log::debug;
 use reqwest::Url;
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::Response;
 use serde::Deserialize;
 
 use crate::core::GenericResult;
 use crate::currency::Cash;
 use crate::forex;
+use crate::network::{Client, Proxy, RetryPolicy};
+use crate::secrets;
 use crate::time;
 use crate::util::{self, DecimalRestrictions};
 use crate::types::Decimal;
@@ -20,7 +22,10 @@ use super::common::{send_request, parallelize_quotes, parse_response, is_outdate
 pub struct TwelveDataConfig {
     #[serde(skip, default = "TwelveDataConfig::default_url")]
     url: String,
+    #[serde(deserialize_with = "secrets::deserialize")]
     token: String,
+    proxy: Option<Proxy>,
+    retry: Option<RetryPolicy>,
 }
 
 impl TwelveDataConfig {
@@ -43,7 +48,7 @@ impl TwelveData {
         TwelveData {
             url: config.url.clone(),
             token: config.token.clone(),
-            client: Client::new(),
+            client: Client::new("Twelve Data", config.proxy.as_ref(), config.retry.as_ref()),
         }
     }
 
@@ -162,7 +167,9 @@ mod tests {
 
         let client = TwelveData::new(&TwelveDataConfig {
             url: server.url(),
-            token: s!("mock")
+            token: s!("mock"),
+            proxy: None,
+            retry: None,
         });
 
         let _forex_quote_mock = mock(&mut server, "/time_series?symbol=USD%2FRUB&interval=1min&outputsize=1&timezone=UTC&apikey=mock", indoc!(r#"
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Deserialize;
+use serde::de::{Deserializer, Error};
+
+use crate::currency::Cash;
+
+use super::static_provider;
+
+/// Valuation policy for instruments which don't have actual quotes (for example, suspended from trading)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValuationPolicy {
+    /// Use the last known price
+    Last,
+    /// Consider the instrument as worthless
+    Zero,
+    /// Use the manually specified price
+    Manual(Cash),
+}
+
+impl fmt::Display for ValuationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValuationPolicy::Last => write!(f, "last known price"),
+            ValuationPolicy::Zero => write!(f, "zero price"),
+            ValuationPolicy::Manual(price) => write!(f, "manual price of {}", price),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ValuationPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let value: String = Deserialize::deserialize(deserializer)?;
+
+        Ok(match value.as_str() {
+            "last" => ValuationPolicy::Last,
+            "zero" => ValuationPolicy::Zero,
+            _ => ValuationPolicy::Manual(static_provider::parse_price(&value).ok_or_else(|| D::Error::custom(format!(
+                "Invalid valuation policy: {:?}. Expected last, zero or a price with currency", value)))?),
+        })
+    }
+}
+
+pub type ValuationPolicies = HashMap<String, ValuationPolicy>;
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("last", Some(ValuationPolicy::Last)),
+        case("zero", Some(ValuationPolicy::Zero)),
+        case("95.02 RUB", Some(ValuationPolicy::Manual(Cash::new("RUB", dec!(95.02))))),
+        case("95.02", None),
+        case("unknown", None),
+    )]
+    fn parse(value: &str, expected: Option<ValuationPolicy>) {
+        let result: Result<ValuationPolicy, _> = serde_yaml::from_str(value);
+        assert_eq!(result.ok(), expected);
+    }
+}
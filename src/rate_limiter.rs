@@ -1,21 +1,34 @@
 use std::num::NonZeroU32;
+use std::ops::DerefMut;
 use std::time::Duration;
 
+use diesel::{self, prelude::*};
 use governor::Quota;
 use governor::clock::{Clock, DefaultClock};
 use governor::state::{RateLimiter as Limiter, NotKeyed, InMemoryState};
 use log::debug;
 
+use crate::core::{EmptyResult, GenericResult};
+use crate::db::{self, schema::rate_limits, models};
+use crate::types::DateTime;
+
+// Rate limiting with longer wait time is considered as quota exhaustion
+const MAX_WAIT_TIME: Duration = Duration::from_secs(60);
+
 pub struct RateLimiter {
     clock: DefaultClock,
+    quotas: Vec<(Duration, u32)>,
     limiters: Vec<Limiter<NotKeyed, InMemoryState, DefaultClock>>,
+    persistence: Option<(db::Connection, String)>,
 }
 
 impl RateLimiter {
     pub fn new() -> RateLimiter {
         RateLimiter {
             clock: DefaultClock::default(),
+            quotas: Vec::new(),
             limiters: Vec::new(),
+            persistence: None,
         }
     }
 
@@ -26,14 +39,31 @@ impl RateLimiter {
     pub fn with_quota(mut self, replenish_1_per: Duration, max_burst: u32) -> RateLimiter {
         let quota = Quota::with_period(replenish_1_per).unwrap()
             .allow_burst(NonZeroU32::new(max_burst).unwrap());
+        self.quotas.push((replenish_1_per, max_burst));
         self.limiters.push(Limiter::direct_with_clock(quota, self.clock.clone()));
         self
     }
 
+    /// Stores rate limiter state in the database, so quotas are respected across multiple program runs
+    pub fn persistent(mut self, connection: db::Connection, name: &str) -> RateLimiter {
+        self.persistence = Some((connection, name.to_owned()));
+        self
+    }
+
+    pub fn wait(&self, name: &str) -> EmptyResult {
+        match self.persistence {
+            Some((ref connection, ref id)) => self.wait_persistent(connection, id, name),
+            None => {
+                self.wait_in_memory(name);
+                Ok(())
+            },
+        }
+    }
+
     // Please notice: naive implementation.
     // We iterate over limiters which makes us drift to the future which reduces accuracy. To make
     // this impact less noticeable limiters should be added in order of decreasing duration.
-    pub fn wait(&self, name: &str) {
+    fn wait_in_memory(&self, name: &str) {
         let mut limited = false;
 
         for limiter in &self.limiters {
@@ -46,4 +76,111 @@ impl RateLimiter {
             }
         }
     }
-}
\ No newline at end of file
+
+    fn wait_persistent(&self, connection: &db::Connection, id: &str, name: &str) -> EmptyResult {
+        let mut limited = false;
+
+        loop {
+            let wait_time = match self.acquire(connection, id)? {
+                Some(wait_time) => wait_time,
+                None => return Ok(()),
+            };
+
+            if wait_time > MAX_WAIT_TIME {
+                return Err!(
+                    "{} quota is exhausted. It will be available again in {}",
+                    id, format_wait_time(wait_time));
+            }
+
+            if !limited {
+                debug!("Rate limiting {}...", name);
+                limited = true;
+            }
+            std::thread::sleep(wait_time);
+        }
+    }
+
+    // Implements GCRA (the same algorithm which is used by governor) storing theoretical arrival time of the next
+    // request for each quota in the database. Returns the required wait time if the request is rate limited.
+    fn acquire(&self, connection: &db::Connection, id: &str) -> GenericResult<Option<Duration>> {
+        let mut connection = connection.borrow();
+
+        connection.deref_mut().immediate_transaction(|connection| -> GenericResult<Option<Duration>> {
+            let now = chrono::Utc::now().naive_utc();
+            let mut times = Vec::with_capacity(self.quotas.len());
+            let mut wait_time = Duration::ZERO;
+
+            for &(period, max_burst) in &self.quotas {
+                let key = period.as_millis() as i64;
+
+                let time: Option<DateTime> = rate_limits::table
+                    .select(rate_limits::time)
+                    .filter(rate_limits::name.eq(id))
+                    .filter(rate_limits::period.eq(key))
+                    .get_result(connection).optional()?;
+
+                let time = time.map_or(now, |time| time.max(now));
+                let allowed_at = time - chrono::Duration::from_std(period * (max_burst - 1))?;
+
+                if allowed_at > now {
+                    wait_time = wait_time.max((allowed_at - now).to_std()?);
+                }
+
+                times.push((key, time + chrono::Duration::from_std(period)?));
+            }
+
+            if !wait_time.is_zero() {
+                return Ok(Some(wait_time));
+            }
+
+            for (period, time) in times {
+                diesel::replace_into(rate_limits::table)
+                    .values(models::NewRateLimit {name: id, period, time})
+                    .execute(connection)?;
+            }
+
+            Ok(None)
+        })
+    }
+}
+
+fn format_wait_time(duration: Duration) -> String {
+    let minutes = duration.as_secs().div_ceil(60);
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+
+    match (hours, minutes) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persistence() {
+        let (_database, connection) = db::new_temporary();
+
+        let new_limiter = || {
+            RateLimiter::new()
+                .with_limit(2, Duration::from_secs(60 * 60 * 24))
+                .persistent(connection.clone(), "test")
+        };
+
+        let limiter = new_limiter();
+        limiter.wait("request").unwrap();
+        limiter.wait("request").unwrap();
+
+        let error = new_limiter().wait("request").unwrap_err().to_string();
+        assert!(error.starts_with("test quota is exhausted. It will be available again in 12h"), "{}", error);
+    }
+
+    #[test]
+    fn wait_time_formatting() {
+        assert_eq!(format_wait_time(Duration::from_secs(59)), "1m");
+        assert_eq!(format_wait_time(Duration::from_secs(60 * 60)), "1h");
+        assert_eq!(format_wait_time(Duration::from_secs(60 * 60 + 61)), "1h 2m");
+    }
+}
@@ -0,0 +1,90 @@
+use std::env;
+
+use keyring::Entry;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde::de::{Deserializer, Error};
+
+use crate::core::{EmptyResult, GenericResult};
+
+const KEYRING_SERVICE: &str = "investments";
+const KEYRING_PREFIX: &str = "keyring:";
+
+/// Replaces all `${NAME}` occurrences with the value of the environment variable
+pub fn interpolate_env(value: &str) -> GenericResult<String> {
+    let re = Regex::new(r"\$\{([^}]*)\}").unwrap();
+    let mut error = None;
+
+    let result = re.replace_all(value, |captures: &Captures| {
+        let name = captures.get(1).unwrap().as_str();
+
+        match env::var(name) {
+            Ok(value) => value,
+            Err(_) => {
+                error.get_or_insert_with(|| format!("{:?} environment variable is not set", name));
+                String::new()
+            },
+        }
+    });
+
+    match error {
+        Some(error) => Err(error.into()),
+        None => Ok(result.into_owned()),
+    }
+}
+
+pub fn get(name: &str) -> GenericResult<String> {
+    let entry = Entry::new(KEYRING_SERVICE, name).map_err(|e| format!(
+        "Unable to access {:?} secret in the OS keyring: {}", name, e))?;
+
+    Ok(entry.get_password().map_err(|e| format!(
+        "Unable to get {:?} secret from the OS keyring: {}", name, e))?)
+}
+
+pub fn set(name: &str, value: &str) -> EmptyResult {
+    let entry = Entry::new(KEYRING_SERVICE, name).map_err(|e| format!(
+        "Unable to access {:?} secret in the OS keyring: {}", name, e))?;
+
+    Ok(entry.set_password(value).map_err(|e| format!(
+        "Unable to save {:?} secret to the OS keyring: {}", name, e))?)
+}
+
+fn resolve(value: String) -> GenericResult<String> {
+    match value.strip_prefix(KEYRING_PREFIX) {
+        Some(name) => get(name),
+        None => Ok(value),
+    }
+}
+
+/// Deserializes a sensitive value which may refer to the OS keyring using `keyring:$name` syntax
+pub fn deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: String = Deserialize::deserialize(deserializer)?;
+    resolve(value).map_err(D::Error::custom)
+}
+
+pub fn deserialize_optional<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<String> = Deserialize::deserialize(deserializer)?;
+    value.map(resolve).transpose().map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_interpolation() {
+        env::set_var("INVESTMENTS_TEST_SECRET", "secret");
+
+        assert_eq!(interpolate_env("token").unwrap(), "token");
+        assert_eq!(interpolate_env("${INVESTMENTS_TEST_SECRET}").unwrap(), "secret");
+        assert_eq!(interpolate_env("a-${INVESTMENTS_TEST_SECRET}-b").unwrap(), "a-secret-b");
+
+        assert_eq!(
+            interpolate_env("${INVESTMENTS_TEST_MISSING_SECRET}").unwrap_err().to_string(),
+            r#""INVESTMENTS_TEST_MISSING_SECRET" environment variable is not set"#);
+    }
+}
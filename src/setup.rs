@@ -0,0 +1,332 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::brokers::Broker;
+use crate::core::{EmptyResult, GenericResult};
+use crate::quotes;
+
+// Broker IDs as they are specified in the configuration file
+const BROKERS: [(Broker, &str); 8] = [
+    (Broker::Alfa, "alfa"),
+    (Broker::Bcs, "bcs"),
+    (Broker::Firstrade, "firstrade"),
+    (Broker::InteractiveBrokers, "interactive-brokers"),
+    (Broker::Open, "open-broker"),
+    (Broker::Sber, "sber"),
+    (Broker::Schwab, "schwab"),
+    (Broker::Tbank, "tbank"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    FcsApi,
+    Finnhub,
+    Tbank,
+}
+
+impl Token {
+    fn check(self, value: &str) -> EmptyResult {
+        match self {
+            Token::FcsApi => quotes::check_fcsapi_access_key(value),
+            Token::Finnhub => quotes::check_finnhub_token(value),
+            Token::Tbank => quotes::check_tbank_api_token(value),
+        }
+    }
+}
+
+type TokenChecker = fn(Token, &str) -> EmptyResult;
+
+/// Interactively creates a starter configuration file
+pub fn init(config_dir: &Path) -> EmptyResult {
+    let config_path = config_dir.join("config.yaml");
+    if config_path.exists() {
+        return Err!("{:?} already exists", config_path);
+    }
+
+    let config = Wizard::new(io::stdin().lock(), io::stdout(), Token::check).run()?;
+
+    fs::create_dir_all(config_dir).map_err(|e| format!(
+        "Unable to create {:?}: {}", config_dir, e))?;
+
+    fs::write(&config_path, config).map_err(|e| format!(
+        "Failed to write {:?}: {}", config_path, e))?;
+
+    println!();
+    println!("The configuration has been saved to {:?}.", config_path);
+    println!("See https://github.com/KonishchevDmitry/investments/blob/master/docs/config-example.yaml for all available options.");
+
+    Ok(())
+}
+
+struct Portfolio {
+    name: String,
+    broker: &'static str,
+    statements: String,
+    currency: String,
+}
+
+struct Wizard<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    checker: TokenChecker,
+}
+
+impl<R: BufRead, W: Write> Wizard<R, W> {
+    fn new(input: R, output: W, checker: TokenChecker) -> Wizard<R, W> {
+        Wizard {input, output, checker}
+    }
+
+    fn run(&mut self) -> GenericResult<String> {
+        writeln!(self.output, "This wizard will create a starter configuration file. Press Enter to skip optional settings.")?;
+
+        let mut portfolios = Vec::new();
+        while let Some(portfolio) = self.ask_portfolio(&portfolios)? {
+            portfolios.push(portfolio);
+        }
+
+        writeln!(self.output)?;
+        writeln!(self.output, "Quotes providers require API tokens which can be obtained for free:")?;
+
+        let fcsapi = self.ask_token(Token::FcsApi, "FCS API access key for forex quotes (https://fcsapi.com/)")?;
+        let finnhub = self.ask_token(Token::Finnhub, "Finnhub token for stock quotes (https://finnhub.io/register)")?;
+        let tbank = self.ask_token(Token::Tbank, concat!(
+            "T-Bank API sandbox token for MOEX and forex quotes ",
+            "(https://tinkoff.github.io/investAPI/token/)"))?;
+
+        Ok(generate(&portfolios, fcsapi.as_deref(), finnhub.as_deref(), tbank.as_deref()))
+    }
+
+    fn ask_portfolio(&mut self, portfolios: &[Portfolio]) -> GenericResult<Option<Portfolio>> {
+        writeln!(self.output)?;
+        writeln!(self.output, "Supported brokers:")?;
+        for (index, (broker, _)) in BROKERS.iter().enumerate() {
+            writeln!(self.output, "{}. {}", index + 1, broker.brief_name())?;
+        }
+
+        let (broker, broker_id) = loop {
+            let choice = self.ask(if portfolios.is_empty() {
+                "Choose your broker"
+            } else {
+                "Choose one more broker"
+            })?;
+
+            if choice.is_empty() {
+                return Ok(None);
+            }
+
+            match choice.parse::<usize>().ok().and_then(|index| BROKERS.get(index.wrapping_sub(1))) {
+                Some(&broker) => break broker,
+                None => writeln!(self.output, "Invalid broker number.")?,
+            }
+        };
+
+        let name = loop {
+            let name = self.ask_with_default("Portfolio name", broker_id)?;
+            if portfolios.iter().any(|portfolio| portfolio.name == name) {
+                writeln!(self.output, "{:?} portfolio already exists.", name)?;
+                continue;
+            }
+            break name;
+        };
+
+        let statements = loop {
+            let path = self.ask("Path to a directory with broker statements")?;
+            if path.is_empty() {
+                continue;
+            }
+
+            if !Path::new(shellexpand::tilde(&path).as_ref()).is_dir() {
+                writeln!(self.output, "{:?} is not a directory.", path)?;
+                continue;
+            }
+
+            break path;
+        };
+
+        let currency = loop {
+            let default = broker.jurisdiction().traits().currency;
+            let currency = self.ask_with_default("Base currency of your account (RUB or USD)", default)?;
+
+            match currency.to_uppercase().as_str() {
+                currency @ ("RUB" | "USD") => break currency.to_owned(),
+                _ => writeln!(self.output, "Unsupported currency: {}.", currency)?,
+            }
+        };
+
+        Ok(Some(Portfolio {name, broker: broker_id, statements, currency}))
+    }
+
+    fn ask_token(&mut self, token: Token, description: &str) -> GenericResult<Option<String>> {
+        loop {
+            let value = self.ask(description)?;
+            if value.is_empty() {
+                return Ok(None);
+            }
+
+            writeln!(self.output, "Checking the token...")?;
+            match (self.checker)(token, &value) {
+                Ok(()) => return Ok(Some(value)),
+                Err(e) => writeln!(self.output, "The token is not valid: {}.", e)?,
+            }
+        }
+    }
+
+    fn ask_with_default(&mut self, prompt: &str, default: &str) -> GenericResult<String> {
+        let value = self.ask(&format!("{} [{}]", prompt, default))?;
+        Ok(if value.is_empty() {
+            default.to_owned()
+        } else {
+            value
+        })
+    }
+
+    fn ask(&mut self, prompt: &str) -> GenericResult<String> {
+        write!(self.output, "{}: ", prompt)?;
+        self.output.flush()?;
+
+        let mut value = String::new();
+        if self.input.read_line(&mut value)? == 0 {
+            return Err!("Unexpected end of input");
+        }
+
+        Ok(value.trim().to_owned())
+    }
+}
+
+fn generate(portfolios: &[Portfolio], fcsapi: Option<&str>, finnhub: Option<&str>, tbank: Option<&str>) -> String {
+    let mut config = String::new();
+
+    // Writing to a String never fails
+    let mut add = |line: &str| {
+        writeln!(config, "{}", line).unwrap();
+    };
+
+    add("# Generated by `investments init`. See");
+    add("# https://github.com/KonishchevDmitry/investments/blob/master/docs/config-example.yaml for all available options.");
+    add("");
+
+    if portfolios.is_empty() {
+        add("portfolios: []");
+    } else {
+        add("portfolios:");
+    }
+
+    for portfolio in portfolios {
+        add("  -");
+        add("    # Name which will identify your portfolio in commands");
+        add(&format!("    name: {}", quote(&portfolio.name)));
+        add(&format!("    broker: {}", portfolio.broker));
+        add("");
+        add("    # Path to a directory with broker statements");
+        add(&format!("    statements: {}", quote(&portfolio.statements)));
+        add("");
+        add("    # Base currency of your account");
+        add(&format!("    currency: {}", portfolio.currency));
+        add("");
+        add("    # Describe your target assets allocation here to be able to rebalance the portfolio:");
+        add("    #assets:");
+        add("    #  - {name: Vanguard Total Stock Market ETF, symbol: VTI, weight: 100%}");
+        add("");
+    }
+
+    if let Some(token) = tbank {
+        add("brokers:");
+        add("  tbank:");
+        add("    # Secrets may be moved to OS keyring via `investments config set-secret NAME` and referred as keyring:NAME");
+        add(&format!("    api_token: {}", quote(token)));
+        add("");
+    }
+
+    if fcsapi.is_some() || finnhub.is_some() {
+        add("# Stock quotes providers (see https://github.com/KonishchevDmitry/investments/blob/master/docs/quotes.md for details)");
+        add("quotes:");
+
+        if let Some(access_key) = fcsapi {
+            add("  fcsapi:");
+            add(&format!("    access_key: {}", quote(access_key)));
+        }
+
+        if let Some(token) = finnhub {
+            add("  finnhub:");
+            add(&format!("    token: {}", quote(token)));
+        }
+    }
+
+    config
+}
+
+// JSON string is a valid YAML double-quoted scalar
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::config::Config;
+
+    use super::*;
+
+    #[test]
+    fn wizard() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let statements = temp_dir.path().join("statements dir");
+        fs::create_dir(&statements).unwrap();
+
+        let input = format!(indoc!("
+            9
+            4
+            ib
+            /nonexistent
+            {statements}
+
+            3
+            ib
+            firstrade
+            {statements}
+            EUR
+            usd
+
+            invalid
+            fcsapi-key
+
+
+        "), statements=statements.to_str().unwrap());
+
+        let checker: TokenChecker = |token, value| {
+            assert_eq!(token, Token::FcsApi);
+            if value == "invalid" {
+                return Err!("Invalid token");
+            }
+            Ok(())
+        };
+
+        let mut output = Vec::new();
+        let config = Wizard::new(input.as_bytes(), &mut output, checker).run().unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Invalid broker number."));
+        assert!(output.contains(r#""/nonexistent" is not a directory."#));
+        assert!(output.contains(r#""ib" portfolio already exists."#));
+        assert!(output.contains("Unsupported currency: EUR."));
+        assert!(output.contains("The token is not valid: Invalid token."));
+
+        let config_path = temp_dir.path().join("config.yaml");
+        fs::write(&config_path, config).unwrap();
+
+        let config = Config::load(config_path.to_str().unwrap()).unwrap();
+        let portfolios: Vec<_> = config.portfolios.iter().map(|portfolio| (
+            portfolio.name.as_str(), portfolio.broker, portfolio.statements.as_deref().unwrap(), portfolio.currency(),
+        )).collect();
+
+        assert_eq!(portfolios, vec![
+            ("ib", Broker::InteractiveBrokers, statements.to_str().unwrap(), "USD"),
+            ("firstrade", Broker::Firstrade, statements.to_str().unwrap(), "USD"),
+        ]);
+        assert!(config.quotes.fcsapi.is_some());
+        assert!(config.quotes.finnhub.is_none());
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use itertools::Itertools;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement;
+use crate::brokers::Broker;
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::formatting;
+use crate::telemetry::TelemetryRecordBuilder;
+
+/// Parses a single broker statement file in isolation and prints a summary of its contents, which is useful for
+/// checking newly downloaded statements before putting them into the statements directory.
+pub fn check(config: &Config, broker: &str, path: &Path) -> GenericResult<TelemetryRecordBuilder> {
+    let broker: Broker = broker.parse()?;
+    let broker_info = broker.get_info(config, None)?;
+
+    let path = path.to_str().ok_or_else(|| format!("Invalid path: {:?}", path))?;
+    let statement = broker_statement::read_single(&broker_info, path)?;
+
+    println!("{} statement: {}", broker_info.brief_name, path);
+    println!("Period: {}", statement.period.map(|period| period.format()).unwrap_or_else(|| s!("unknown")));
+    if let Some(time) = statement.generation_time {
+        println!("Generation time: {}", formatting::format_date(time));
+    }
+    if let Some(has_starting_assets) = statement.has_starting_assets {
+        println!("Has starting assets: {}", if has_starting_assets { "yes" } else { "no" });
+    }
+    if let Some(ref cash) = statement.assets.cash {
+        println!("Cash assets: {}", cash.iter().map(|assets| assets.to_string()).join(", "));
+    }
+    if let Some(other) = statement.assets.other {
+        println!("Other assets: {}", other);
+    }
+    println!("Open positions: {}", statement.open_positions.len());
+    println!();
+
+    let mut table = SectionsTable::new();
+    let mut add = |name, count: usize, amounts: Option<Vec<Cash>>| {
+        table.add_row(Row {
+            section: name,
+            count,
+            total: amounts.map(|amounts| {
+                let mut total = MultiCurrencyCashAccount::new();
+                for amount in amounts {
+                    total.deposit(amount);
+                }
+                total
+            }),
+        });
+    };
+
+    add("Deposits and withdrawals", statement.deposits_and_withdrawals.len(), Some(
+        statement.deposits_and_withdrawals.iter().map(|assets| assets.cash).collect()));
+    add("Fees", statement.fees.len(), Some(
+        statement.fees.iter().map(|fee| fee.amount.withholding()).collect()));
+    add("Idle cash interest", statement.idle_cash_interest.len(), Some(
+        statement.idle_cash_interest.iter().map(|interest| interest.amount).collect()));
+    add("Margin interest", statement.margin_interest.len(), Some(
+        statement.margin_interest.iter().map(|interest| interest.amount).collect()));
+    add("Cash flows", statement.cash_flows.len(), Some(
+        statement.cash_flows.iter().map(|cash_flow| cash_flow.amount).collect()));
+    add("Forex trades", statement.forex_trades.len(), None);
+    add("Stock buys", statement.stock_buys.len(), None);
+    add("Stock sells", statement.stock_sells.len(), None);
+    add("Derivative trades", statement.derivative_trades.len(), None);
+    add("Coupons", statement.coupons.len(), Some(
+        statement.coupons.iter().map(|coupon| coupon.amount).collect()));
+    add("Dividend accruals", statement.dividend_accruals.len(), None);
+    add("Tax accruals", statement.tax_accruals.len(), None);
+    add("Cash grants", statement.cash_grants.len(), Some(
+        statement.cash_grants.iter().map(|grant| grant.amount).collect()));
+    add("Stock grants", statement.stock_grants.len(), None);
+    add("Corporate actions", statement.corporate_actions.len(), None);
+
+    table.print("Statement sections");
+
+    Ok(TelemetryRecordBuilder::new_with_broker(broker))
+}
+
+#[derive(StaticTable)]
+#[table(name="SectionsTable")]
+struct Row {
+    #[column(name="Section")]
+    section: &'static str,
+    #[column(name="Count")]
+    count: usize,
+    #[column(name="Total")]
+    total: Option<MultiCurrencyCashAccount>,
+}
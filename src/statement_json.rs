@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::Path;
+
+use log::info;
+
+use crate::broker_statement::{self, JsonStatement};
+use crate::brokers::Broker;
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::telemetry::TelemetryRecordBuilder;
+
+/// Converts a single broker statement file to the normalized JSON statement format
+pub fn export(
+    config: &Config, broker: &str, path: &Path, output: Option<&Path>,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let broker: Broker = broker.parse()?;
+    let broker_info = broker.get_info(config, None)?;
+
+    let path = path.to_str().ok_or_else(|| format!("Invalid path: {:?}", path))?;
+    let statement = broker_statement::read_single(&broker_info, path)?;
+
+    let data = JsonStatement::new(&statement).map_err(|e| format!(
+        "Unable to export {:?}: {}", path, e))?.to_json()?;
+
+    match output {
+        Some(output) => fs::write(output, data + "\n").map_err(|e| format!(
+            "Failed to write {:?}: {}", output, e))?,
+        None => println!("{}", data),
+    }
+
+    Ok(TelemetryRecordBuilder::new_with_broker(broker))
+}
+
+/// Validates the statement in the normalized JSON format and copies it to the portfolio's statements directory
+pub fn import(config: &Config, portfolio_name: &str, path: &Path) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let file_name = path.file_name().filter(|_| path.extension().is_some_and(|extension| extension == "json"))
+        .ok_or_else(|| format!("Invalid statement path: {:?} (*.json file is expected)", path))?;
+
+    let statement = JsonStatement::read(path).and_then(|statement| statement.convert(&broker.exchanges()))
+        .map_err(|e| format!("Error while reading {:?}: {}", path, e))?;
+    let period = statement.get_period()?;
+
+    let destination = Path::new(portfolio.statements_path()?).join(file_name);
+    if destination.exists() {
+        return Err!("{:?} already exists", destination);
+    }
+
+    fs::copy(path, &destination).map_err(|e| format!(
+        "Failed to copy {:?} to {:?}: {}", path, destination, e))?;
+
+    info!("{} statement for {} has been imported to {:?}.", broker.brief_name, period.format(), destination);
+
+    Ok(TelemetryRecordBuilder::new_with_broker(broker.type_))
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv::StringRecord;
+use log::warn;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::broker_statement;
+use crate::brokers::Broker;
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::types::Decimal;
+
+/// Columns which values are kept as is when scrambling numbers: they are either not amounts or are used to match
+/// records between sections.
+const PRESERVED_COLUMNS: &[&str] = &["Price", "Rate", "Multiplier", "Strike", "Conid", "ID", "Date", "Time", "Exp"];
+
+/// Columns which values define an edge case by themselves (multi-currency activity, different asset types, etc.)
+const CATEGORY_COLUMNS: &[&str] = &["Asset Category", "Currency", "Code", "Type", "Field Name"];
+
+/// Generates a minimal synthetic statement from an (already anonymized) broker statement to be used as a test case
+/// for broker statement parsers: keeps only a few records of each kind per statement section and scrambles the
+/// amounts.
+pub fn sample(
+    config: &Config, broker: &str, path: &Path, output: &Path, rows: usize, seed: Option<u64>,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let broker: Broker = broker.parse()?;
+    if broker != Broker::InteractiveBrokers {
+        return Err!("Statement sampling is supported only for Interactive Brokers statements for now");
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+
+    let records = reader.records().collect::<Result<Vec<_>, _>>().map_err(|e| format!(
+        "Error while reading {:?}: {}", path, e))?;
+
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let sample = Sampler::new(rows, rng.gen_range(2..10)).sample(&records);
+
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_path(output)
+        .map_err(|e| format!("Unable to create {:?}: {}", output, e))?;
+
+    for record in &sample {
+        writer.write_record(record)?;
+    }
+    writer.flush().map_err(|e| format!("Failed to write {:?}: {}", output, e))?;
+
+    println!("{} of {} records have been written to {:?}.", sample.len(), records.len(), output);
+
+    // Dropped records may break some statement invariants (open positions reconciliation, for example), so check the
+    // result to let the user know that it has to be fixed manually.
+    let broker_info = broker.get_info(config, None)?;
+    let output = output.to_str().ok_or_else(|| format!("Invalid path: {:?}", output))?;
+
+    if let Err(e) = broker_statement::read_single(&broker_info, output) {
+        warn!("The generated statement can't be parsed and has to be fixed manually: {}.", e);
+    }
+
+    Ok(TelemetryRecordBuilder::new_with_broker(broker))
+}
+
+struct Sampler {
+    rows: usize,
+    factor: Decimal,
+}
+
+impl Sampler {
+    fn new(rows: usize, factor: u32) -> Sampler {
+        Sampler {rows, factor: factor.into()}
+    }
+
+    fn sample(&self, records: &[StringRecord]) -> Vec<StringRecord> {
+        let mut sample = Vec::new();
+        let mut cases: HashMap<Vec<String>, usize> = HashMap::new();
+        let mut header: Option<&StringRecord> = None;
+
+        for record in records {
+            let (section, data_type) = match (record.get(0), record.get(1)) {
+                (Some(section), Some(data_type)) => (section, data_type),
+                _ => {
+                    sample.push(record.clone());
+                    continue;
+                },
+            };
+
+            if data_type == "Header" || data_type == "MetaInfo" {
+                header.replace(record);
+                sample.push(record.clone());
+                continue;
+            }
+
+            let header = header.filter(|header| header.get(0) == Some(section));
+
+            let case = self.get_case(header, record);
+            let count = cases.entry(case).or_default();
+            if *count >= self.rows {
+                continue;
+            }
+            *count += 1;
+
+            sample.push(self.scramble(header, record));
+        }
+
+        sample
+    }
+
+    // Classifies the record by its section, data type and shape of its values, so records with empty, zero, negative
+    // or differently categorized values are considered as separate edge cases.
+    fn get_case(&self, header: Option<&StringRecord>, record: &StringRecord) -> Vec<String> {
+        let mut case = Vec::with_capacity(record.len() + 1);
+        case.push(header.map(|header| header.iter().collect::<Vec<_>>().join(",")).unwrap_or_default());
+
+        for (index, value) in record.iter().enumerate() {
+            let column = header.and_then(|header| header.get(index)).unwrap_or_default();
+
+            case.push(if index < 2 || CATEGORY_COLUMNS.contains(&column) || value.starts_with("Total") {
+                value.to_owned()
+            } else if value.is_empty() {
+                s!("empty")
+            } else {
+                match Decimal::from_str(value) {
+                    Ok(value) if value.is_zero() => s!("zero"),
+                    Ok(value) if value.is_sign_negative() => s!("negative"),
+                    Ok(_) => s!("positive"),
+                    Err(_) => s!("text"),
+                }
+            });
+        }
+
+        case
+    }
+
+    // Scales all amounts by the same integer factor: it keeps the relations between them (quantity * price = volume,
+    // sum of records = total, etc.) and doesn't change their precision.
+    fn scramble(&self, header: Option<&StringRecord>, record: &StringRecord) -> StringRecord {
+        record.iter().enumerate().map(|(index, value)| {
+            let column = header.and_then(|header| header.get(index)).unwrap_or_default();
+
+            if index < 2 || PRESERVED_COLUMNS.iter().any(|preserved| column.contains(preserved)) {
+                return value.to_owned();
+            }
+
+            match Decimal::from_str(value) {
+                Ok(amount) => (amount * self.factor).to_string(),
+                Err(_) => value.to_owned(),
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling() {
+        let records = [
+            "Statement,Header,Field Name,Field Value",
+            "Statement,Data,Period,\"January 1, 2020 - December 31, 2020\"",
+            "Statement,Data,WhenGenerated,\"2021-01-01, 10:00:00 EST\"",
+            "Trades,Header,DataDiscriminator,Asset Category,Currency,Symbol,Date/Time,Quantity,T. Price,Proceeds",
+            "Trades,Data,Order,Stocks,USD,VTI,\"2020-01-02, 10:00:00\",10,150.5,-1505",
+            "Trades,Data,Order,Stocks,USD,BND,\"2020-01-03, 10:00:00\",5,80,-400",
+            "Trades,Data,Order,Stocks,USD,VTI,\"2020-02-02, 10:00:00\",-2,160,320",
+            "Trades,Data,Order,Stocks,EUR,VGK,\"2020-02-03, 10:00:00\",3,50.25,-150.75",
+            "Trades,Total,,Stocks,USD,,,,,-1585",
+        ].into_iter().map(|record| {
+            csv::ReaderBuilder::new().has_headers(false).from_reader(record.as_bytes())
+                .records().next().unwrap().unwrap()
+        }).collect::<Vec<_>>();
+
+        let sample = Sampler::new(1, 2).sample(&records).iter().map(|record| {
+            record.iter().collect::<Vec<_>>().join(",")
+        }).collect::<Vec<_>>();
+
+        assert_eq!(sample, [
+            "Statement,Header,Field Name,Field Value",
+            "Statement,Data,Period,January 1, 2020 - December 31, 2020",
+            "Statement,Data,WhenGenerated,2021-01-01, 10:00:00 EST",
+            "Trades,Header,DataDiscriminator,Asset Category,Currency,Symbol,Date/Time,Quantity,T. Price,Proceeds",
+            "Trades,Data,Order,Stocks,USD,VTI,2020-01-02, 10:00:00,20,150.5,-3010",
+            "Trades,Data,Order,Stocks,USD,VTI,2020-02-02, 10:00:00,-4,160,640",
+            "Trades,Data,Order,Stocks,EUR,VGK,2020-02-03, 10:00:00,6,50.25,-301.50",
+            "Trades,Total,,Stocks,USD,,,,,-3170",
+        ]);
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::Datelike;
+use serde::Deserialize;
+
+use static_table_derive::StaticTable;
+
+use crate::config::deserialize_cash_flows;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting;
+use crate::localities::Country;
+use crate::taxes::{IncomeType, TaxCalculator};
+use crate::types::{Date, Decimal};
+
+use super::report::TaxReport;
+use super::statement::{TaxStatement, CountryCode};
+
+/// Interest income received on foreign bank accounts which isn't reflected in broker statements
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BankInterestConfig {
+    pub bank: String,
+    /// ISO 3166-1 alpha-2 code of bank's country
+    pub country: String,
+    pub currency: String,
+    #[serde(deserialize_with = "deserialize_cash_flows")]
+    pub payments: Vec<(Date, Decimal)>,
+}
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Банк")]
+    bank: String,
+    #[column(name="Сумма")]
+    foreign_amount: Cash,
+    #[column(name="Курс руб.")]
+    currency_rate: Option<Decimal>,
+    #[column(name="Сумма (руб)")]
+    amount: Cash,
+    #[column(name="К уплате")]
+    tax_to_pay: Cash,
+    #[column(name="Реальный доход")]
+    income: Cash,
+}
+
+pub fn process_income(
+    country: &Country, accounts: &[BankInterestConfig], year: Option<i32>,
+    tax_calculator: &mut TaxCalculator, mut tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
+    report: Option<&mut TaxReport>,
+) -> GenericResult<bool> {
+    let mut table = Table::new();
+    let mut has_income = false;
+
+    let mut total_amount = Cash::zero(country.currency);
+    let mut total_tax_to_pay = Cash::zero(country.currency);
+    let mut total_income = Cash::zero(country.currency);
+
+    for account in accounts {
+        let country_code = CountryCode::new(&account.country).map_err(|e| format!(
+            "Invalid {:?} bank country: {}", account.bank, e))?;
+
+        for &(date, amount) in &account.payments {
+            if let Some(year) = year {
+                if date.year() != year {
+                    continue;
+                }
+            }
+
+            has_income = true;
+
+            let foreign_amount = Cash::new(&account.currency, amount).round();
+            let precise_currency_rate = converter.precise_currency_rate(
+                date, foreign_amount.currency, country.currency)?;
+
+            let amount = converter.convert_to_cash_rounding(date, foreign_amount, country.currency)?;
+            let tax_to_pay = tax_calculator.tax_income(IncomeType::Interest, date.year(), amount, None).expected;
+            let income = amount - tax_to_pay;
+
+            total_amount += amount;
+            total_tax_to_pay += tax_to_pay;
+            total_income += income;
+
+            table.add_row(Row {
+                date,
+                bank: account.bank.clone(),
+                foreign_amount,
+                currency_rate: if foreign_amount.currency != country.currency {
+                    Some(precise_currency_rate)
+                } else {
+                    None
+                },
+                amount, tax_to_pay, income,
+            });
+
+            if let Some(ref mut statement) = tax_statement {
+                let description = format!("{}: Проценты по банковскому вкладу", account.bank);
+
+                statement.add_interest_income(
+                    &description, date, country_code,
+                    foreign_amount.currency, precise_currency_rate,
+                    foreign_amount.amount, amount.amount,
+                ).map_err(|e| format!(
+                    "Unable to add {} interest income from {} to the tax statement: {}",
+                    account.bank, formatting::format_date(date), e,
+                ))?;
+            }
+        }
+    }
+
+    if !table.is_empty() {
+        let mut totals = table.add_empty_row();
+        totals.set_amount(total_amount);
+        totals.set_tax_to_pay(total_tax_to_pay);
+        totals.set_income(total_income);
+
+        let title = "Расчет дохода от процентов по счетам в иностранных банках";
+        table.print(title);
+
+        if let Some(report) = report {
+            report.add("Проценты по вкладам", title, table.table, Some(total_tax_to_pay));
+        }
+    }
+
+    Ok(has_income)
+}
@@ -0,0 +1,175 @@
+use chrono::Datelike;
+use serde::Deserialize;
+use serde::de::{Deserializer, Error};
+
+use static_table_derive::StaticTable;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::Country;
+use crate::taxes::{IncomeType, TaxCalculator};
+use crate::time::deserialize_date;
+use crate::types::{Date, Decimal};
+use crate::util::{self, DecimalRestrictions};
+
+use super::report::TaxReport;
+use super::statement::{TaxStatement, CountryCode};
+
+/// Profit of controlled foreign company (КИК) which must be declared by its controlling person
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ControlledForeignCompanyConfig {
+    pub name: String,
+    /// Company number from the notification on controlled foreign companies
+    pub number: String,
+    /// ISO 3166-1 alpha-2 code of company's country of incorporation
+    pub country: String,
+    /// Date of profit recognition
+    #[serde(deserialize_with = "deserialize_date")]
+    pub date: Date,
+    pub currency: String,
+    #[serde(deserialize_with = "deserialize_profit")]
+    pub profit: Decimal,
+    /// Tax paid by the company on the profit which is credited against the personal tax
+    #[serde(default, deserialize_with = "deserialize_paid_tax")]
+    pub paid_tax: Decimal,
+    #[serde(default)]
+    pub profit_calculation_method: ProfitCalculationMethod,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfitCalculationMethod {
+    #[default]
+    FinancialStatements,
+    TaxRules,
+}
+
+impl ProfitCalculationMethod {
+    pub fn code(self) -> usize {
+        match self {
+            ProfitCalculationMethod::FinancialStatements => 1,
+            ProfitCalculationMethod::TaxRules => 2,
+        }
+    }
+}
+
+fn deserialize_profit<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+    where D: Deserializer<'de>
+{
+    let profit: Decimal = Deserialize::deserialize(deserializer)?;
+    util::validate_decimal(profit, DecimalRestrictions::StrictlyPositive).map_err(|_| D::Error::custom(format!(
+        "Invalid profit: {}", profit)))
+}
+
+fn deserialize_paid_tax<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+    where D: Deserializer<'de>
+{
+    let paid_tax: Decimal = Deserialize::deserialize(deserializer)?;
+    util::validate_decimal(paid_tax, DecimalRestrictions::PositiveOrZero).map_err(|_| D::Error::custom(format!(
+        "Invalid paid tax: {}", paid_tax)))
+}
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Компания")]
+    company: String,
+    #[column(name="Прибыль")]
+    foreign_profit: Cash,
+    #[column(name="Курс руб.")]
+    currency_rate: Option<Decimal>,
+    #[column(name="Прибыль (руб)")]
+    profit: Cash,
+    #[column(name="Налог")]
+    tax: Cash,
+    #[column(name="Уплачено (руб)")]
+    paid_tax: Cash,
+    #[column(name="К доплате")]
+    tax_to_pay: Cash,
+}
+
+pub fn process_income(
+    country: &Country, companies: &[ControlledForeignCompanyConfig], year: Option<i32>,
+    tax_calculator: &mut TaxCalculator, mut tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
+    report: Option<&mut TaxReport>,
+) -> GenericResult<bool> {
+    let mut table = Table::new();
+    let mut has_income = false;
+
+    let mut total_profit = Cash::zero(country.currency);
+    let mut total_paid_tax = Cash::zero(country.currency);
+    let mut total_tax_to_pay = Cash::zero(country.currency);
+
+    for company in companies {
+        if let Some(year) = year {
+            if company.date.year() != year {
+                continue;
+            }
+        }
+
+        has_income = true;
+
+        let country_code = CountryCode::new(&company.country).map_err(|e| format!(
+            "Invalid {:?} controlled foreign company country: {}", company.name, e))?;
+
+        let foreign_profit = Cash::new(&company.currency, company.profit).round();
+        let foreign_paid_tax = Cash::new(&company.currency, company.paid_tax).round();
+
+        let precise_currency_rate = converter.precise_currency_rate(
+            company.date, foreign_profit.currency, country.currency)?;
+
+        let profit = converter.convert_to_cash_rounding(company.date, foreign_profit, country.currency)?;
+        let paid_tax = converter.convert_to_cash_rounding(company.date, foreign_paid_tax, country.currency)?;
+
+        let tax = tax_calculator.tax_income(
+            IncomeType::ControlledForeignCompany, company.date.year(), profit, Some(paid_tax));
+
+        total_profit += profit;
+        total_paid_tax += tax.paid;
+        total_tax_to_pay += tax.to_pay;
+
+        table.add_row(Row {
+            date: company.date,
+            company: company.name.clone(),
+            foreign_profit,
+            currency_rate: if foreign_profit.currency == country.currency {
+                None
+            } else {
+                Some(precise_currency_rate)
+            },
+            profit,
+            tax: tax.expected,
+            paid_tax: tax.paid,
+            tax_to_pay: tax.to_pay,
+        });
+
+        if let Some(ref mut tax_statement) = tax_statement {
+            tax_statement.add_controlled_foreign_company_income(
+                &format!("Прибыль КИК {}", company.name), company.date, country_code,
+                &company.number, company.profit_calculation_method.code(),
+                foreign_profit.currency, precise_currency_rate,
+                foreign_profit.amount, foreign_paid_tax.amount, profit.amount, tax.paid.amount,
+            ).map_err(|e| format!("Unable to add {:?} controlled foreign company income to the tax statement: {}",
+                company.name, e))?;
+        }
+    }
+
+    if !table.is_empty() {
+        let mut totals = table.add_empty_row();
+        totals.set_profit(total_profit);
+        totals.set_paid_tax(total_paid_tax);
+        totals.set_tax_to_pay(total_tax_to_pay);
+
+        let title = "Расчет налога на прибыль контролируемых иностранных компаний";
+        table.print(title);
+
+        if let Some(report) = report {
+            report.add("КИК", title, table.table, Some(total_tax_to_pay));
+        }
+    }
+
+    Ok(has_income)
+}
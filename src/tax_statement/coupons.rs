@@ -0,0 +1,92 @@
+use chrono::Datelike;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::BrokerStatement;
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::{Country, Jurisdiction};
+use crate::taxes::TaxCalculator;
+use crate::types::Date;
+
+use super::report::TaxReport;
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Облигация")]
+    bond: String,
+    #[column(name="Сумма")]
+    foreign_amount: Cash,
+    #[column(name="Сумма (руб)")]
+    amount: Cash,
+    #[column(name="К уплате")]
+    tax_to_pay: Cash,
+    #[column(name="Реальный доход")]
+    income: Cash,
+}
+
+// Coupons paid through Russian brokers are declared by broker's tax agent, so they are only calculated here to be
+// checked against the tax withheld by the broker.
+pub fn process_income(
+    country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
+    tax_calculator: &mut TaxCalculator, converter: &CurrencyConverter, report: Option<&mut TaxReport>,
+) -> GenericResult<(Cash, bool)> {
+    let mut table = Table::new();
+    let mut has_income = false;
+
+    let mut total_foreign_amount = MultiCurrencyCashAccount::new();
+    let mut total_amount = Cash::zero(country.currency);
+    let mut total_tax_to_pay = Cash::zero(country.currency);
+    let mut total_income = Cash::zero(country.currency);
+
+    for coupon in &broker_statement.coupons {
+        if let Some(year) = year {
+            if coupon.date.year() != year {
+                continue;
+            }
+        }
+
+        if broker_statement.broker.type_.jurisdiction() != Jurisdiction::Russia {
+            return Err!("Coupon income from non-Russian brokers is not supported yet");
+        }
+
+        has_income = true;
+
+        let amount = converter.convert_to_cash_rounding(coupon.date, coupon.amount, country.currency)?;
+        let tax_to_pay = coupon.tax(country, converter, tax_calculator)?;
+        let income = amount - tax_to_pay;
+
+        total_foreign_amount.deposit(coupon.amount);
+        total_amount += amount;
+        total_tax_to_pay += tax_to_pay;
+        total_income += income;
+
+        table.add_row(Row {
+            date: coupon.date,
+            bond: broker_statement.instrument_info.get_name(&coupon.original_symbol),
+            foreign_amount: coupon.amount,
+            amount, tax_to_pay, income,
+        });
+    }
+
+    if !table.is_empty() {
+        let mut totals = table.add_empty_row();
+        totals.set_foreign_amount(total_foreign_amount);
+        totals.set_amount(total_amount);
+        totals.set_tax_to_pay(total_tax_to_pay);
+        totals.set_income(total_income);
+
+        let title = format!(
+            "Расчет купонного дохода по облигациям, полученного через {}", broker_statement.broker.name);
+        table.print(&title);
+
+        if let Some(report) = report {
+            report.add("Купоны", &title, table.table, Some(total_tax_to_pay));
+        }
+    }
+
+    Ok((total_tax_to_pay, has_income))
+}
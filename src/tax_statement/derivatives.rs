@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, DerivativeType};
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting;
+use crate::localities::{Country, Jurisdiction};
+use crate::taxes::{IncomeType, TaxCalculator};
+use crate::types::{Date, Decimal};
+
+use super::report::TaxReport;
+use super::statement::TaxStatement;
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Тип")]
+    type_: &'static str,
+    #[column(name="Контракт")]
+    contract: String,
+    #[column(name="Количество")]
+    quantity: Decimal,
+    #[column(name="Прибыль")]
+    foreign_profit: Cash,
+    #[column(name="Прибыль (руб)")]
+    profit: Cash,
+}
+
+// Income from derivatives is calculated by closed positions and taxed as a separate tax base: losses reduce only
+// derivatives income of the same year.
+pub fn process_income(
+    country: &Country, broker_statement: &BrokerStatement, year: Option<i32>, tax_calculator: &mut TaxCalculator,
+    tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter, report: Option<&mut TaxReport>,
+) -> GenericResult<(Cash, bool, bool)> {
+    let mut table = Table::new();
+    let tax_agent = broker_statement.broker.type_.jurisdiction() == Jurisdiction::Russia;
+
+    let mut yearly_profit = BTreeMap::new();
+    let mut total_foreign_profit = MultiCurrencyCashAccount::new();
+    let mut total_profit = Cash::zero(country.currency);
+
+    for income in &broker_statement.derivatives_income {
+        let income_year = income.execution_date.year();
+        if let Some(year) = year {
+            if income_year != year {
+                continue;
+            }
+        }
+
+        if income.type_ == DerivativeType::Futures && income.held_over_year_end() {
+            return Err!(concat!(
+                "Unable to calculate income from {} futures position closed on {}: ",
+                "positions held over the year end aren't supported yet (variation margin must be taxed in the year ",
+                "it's accrued)"), income.symbol, formatting::format_date(income.execution_date));
+        }
+
+        let foreign_profit = income.profit(income.currency, converter)?;
+        let profit = income.profit(country.currency, converter)?;
+
+        total_foreign_profit.deposit(foreign_profit);
+        total_profit += profit;
+        *yearly_profit.entry(income_year).or_insert_with(|| Cash::zero(country.currency)) += profit;
+
+        table.add_row(Row {
+            date: income.execution_date,
+            type_: match income.type_ {
+                DerivativeType::Option => "Опцион",
+                DerivativeType::Futures => "Фьючерс",
+            },
+            contract: broker_statement.instrument_info.get_name(&income.symbol),
+            quantity: income.quantity,
+            foreign_profit, profit,
+        });
+    }
+
+    if table.is_empty() {
+        return Ok((Cash::zero(country.currency), false, false));
+    }
+
+    let mut total_tax = Cash::zero(country.currency);
+    for (&year, &profit) in &yearly_profit {
+        if profit.is_positive() {
+            total_tax += tax_calculator.tax_income(IncomeType::Trading, year, profit, None).expected;
+        }
+    }
+
+    let mut totals = table.add_empty_row();
+    totals.set_foreign_profit(total_foreign_profit);
+    totals.set_profit(total_profit);
+
+    let title = format!("Расчет дохода от операций с ПФИ, полученного через {}", broker_statement.broker.name);
+    table.print(&title);
+
+    if let Some(report) = report {
+        report.add("ПФИ", &title, table.table, Some(total_tax));
+    }
+
+    let has_income_to_declare = !tax_agent;
+    if has_income_to_declare && tax_statement.is_some() {
+        return Err!("Automatic declaration of income from derivatives is not supported yet");
+    }
+
+    Ok((total_tax, true, has_income_to_declare))
+}
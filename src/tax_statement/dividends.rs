@@ -17,12 +17,13 @@ use crate::localities::{Country, Jurisdiction};
 use crate::taxes::TaxCalculator;
 use crate::types::{Date, Decimal};
 
+use super::report::TaxReport;
 use super::statement::{TaxStatement, CountryCode};
 
 pub fn process_income(
     country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
     tax_calculator: &mut TaxCalculator, tax_statement: Option<&mut TaxStatement>,
-    converter: &CurrencyConverter,
+    converter: &CurrencyConverter, report: Option<&mut TaxReport>,
 ) -> GenericResult<(Cash, bool, bool)> {
     let mut processor = Processor {
         broker_statement, tax_calculator, tax_statement,
@@ -56,7 +57,7 @@ pub fn process_income(
     let has_income = processor.has_income;
     let has_income_to_declare = processor.has_income_to_declare;
 
-    processor.print();
+    processor.print(report);
 
     Ok((total_tax_to_pay, has_income, has_income_to_declare))
 }
@@ -216,7 +217,18 @@ impl Processor<'_> {
     ) -> EmptyResult {
         let broker = &self.broker_statement.broker;
 
-        let income_country = match income_country {
+        // The issuer jurisdiction may become known after broker statement reading (from securities reference)
+        let reference_country = match income_country {
+            Some(_) => None,
+            None => self.broker_statement.instrument_info.get(&dividend.issuer).and_then(|instrument| {
+                match instrument.get_taxation_type(dividend.date, broker.type_.jurisdiction()) {
+                    Ok(IssuerTaxationType::Manual {country_code}) => country_code,
+                    _ => None,
+                }
+            }),
+        };
+
+        let income_country = match income_country.or(reference_country.as_deref()) {
             Some(country) => country,
             None => match broker.type_ {
                 Broker::Firstrade => {
@@ -273,7 +285,7 @@ impl Processor<'_> {
         Ok(())
     }
 
-    fn print(self) {
+    fn print(self, report: Option<&mut TaxReport>) {
         let mut table = self.table;
         if table.is_empty() {
             return;
@@ -296,9 +308,14 @@ impl Processor<'_> {
         totals.set_tax_to_pay(self.total_tax_to_pay);
         totals.set_income(self.total_income);
 
-        table.print(&format!(
+        let title = format!(
             "Расчет дохода от дивидендов, полученных через {}",
-            self.broker_statement.broker.name));
+            self.broker_statement.broker.name);
+        table.print(&title);
+
+        if let Some(report) = report {
+            report.add("Дивиденды", &title, table.table, Some(self.total_tax_to_pay));
+        }
     }
 
     fn warn(&mut self, args: fmt::Arguments) {
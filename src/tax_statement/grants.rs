@@ -0,0 +1,92 @@
+use chrono::Datelike;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, CashGrantsTaxCalculator};
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::{Country, Jurisdiction};
+use crate::taxes::TaxCalculator;
+use crate::types::Date;
+
+use super::report::TaxReport;
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Описание")]
+    description: String,
+    #[column(name="Сумма")]
+    foreign_amount: Cash,
+    #[column(name="Сумма (руб)")]
+    amount: Cash,
+    #[column(name="К уплате")]
+    tax_to_pay: Cash,
+    #[column(name="Реальный доход")]
+    income: Cash,
+}
+
+// Cash grants from Russian brokers are declared by broker's tax agent, so they are only calculated here to be checked
+// against the tax withheld by the broker.
+pub fn process_income(
+    country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
+    tax_calculator: &mut TaxCalculator, converter: &CurrencyConverter, report: Option<&mut TaxReport>,
+) -> GenericResult<(Cash, bool)> {
+    let mut table = Table::new();
+    let mut has_income = false;
+
+    let mut total_foreign_amount = MultiCurrencyCashAccount::new();
+    let mut total_amount = Cash::zero(country.currency);
+    let mut total_tax_to_pay = Cash::zero(country.currency);
+    let mut total_income = Cash::zero(country.currency);
+
+    if broker_statement.broker.type_.jurisdiction() != Jurisdiction::Russia {
+        return Ok((total_tax_to_pay, has_income));
+    }
+
+    let mut grants_tax_calculator = CashGrantsTaxCalculator::new(country, converter);
+
+    for grant in &broker_statement.cash_grants {
+        if let Some(year) = year {
+            if grant.date.year() != year {
+                continue;
+            }
+        }
+
+        has_income = true;
+
+        let (amount, tax_to_pay) = grants_tax_calculator.tax(grant, tax_calculator)?;
+        let income = amount - tax_to_pay;
+
+        total_foreign_amount.deposit(grant.amount);
+        total_amount += amount;
+        total_tax_to_pay += tax_to_pay;
+        total_income += income;
+
+        table.add_row(Row {
+            date: grant.date,
+            description: grant.description.clone(),
+            foreign_amount: grant.amount,
+            amount, tax_to_pay, income,
+        });
+    }
+
+    if !table.is_empty() {
+        let mut totals = table.add_empty_row();
+        totals.set_foreign_amount(total_foreign_amount);
+        totals.set_amount(total_amount);
+        totals.set_tax_to_pay(total_tax_to_pay);
+        totals.set_income(total_income);
+
+        let title = format!("Расчет прочих доходов (бонусов), полученных от {}", broker_statement.broker.name);
+        table.print(&title);
+
+        if let Some(report) = report {
+            report.add("Прочие доходы", &title, table.table, Some(total_tax_to_pay));
+        }
+    }
+
+    Ok((total_tax_to_pay, has_income))
+}
@@ -0,0 +1,68 @@
+use log::warn;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::BrokerStatement;
+use crate::config::PortfolioConfig;
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::localities::Country;
+use crate::taxes::IisDeduction;
+
+use super::report::TaxReport;
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Год")]
+    year: i32,
+    #[column(name="Взносы")]
+    deposits: Cash,
+    #[column(name="Вычет")]
+    deduction: Cash,
+}
+
+pub fn process_deduction(
+    country: &Country, portfolio: &PortfolioConfig, broker_statement: &BrokerStatement, year: Option<i32>,
+    has_tax_statement: bool, report: Option<&mut TaxReport>,
+) -> EmptyResult {
+    let mut table = Table::new();
+
+    let deductions = IisDeduction::calculate(
+        &broker_statement.deposits_and_withdrawals, &portfolio.iis_paid_taxes, true)?;
+
+    for deduction in deductions {
+        if let Some(year) = year {
+            if deduction.year != year {
+                continue;
+            }
+        }
+
+        if !deduction.limited {
+            warn!(concat!(
+                "IIS deduction for {} is the maximum possible one: it can't exceed personal income tax paid for the ",
+                "year which may be specified via iis_paid_taxes option."), deduction.year);
+        }
+
+        table.add_row(Row {
+            year: deduction.year,
+            deposits: Cash::new(country.currency, deduction.deposits),
+            deduction: Cash::new(country.currency, deduction.deduction),
+        });
+    }
+
+    if table.is_empty() {
+        return Ok(());
+    }
+
+    let title = "Инвестиционный налоговый вычет по ИИС типа А";
+    table.print(title);
+
+    if let Some(report) = report {
+        report.add("Вычет ИИС", title, table.table, None);
+    }
+
+    if has_tax_statement {
+        warn!("IIS deduction isn't added to the tax statement automatically - declare it manually.");
+    }
+
+    Ok(())
+}
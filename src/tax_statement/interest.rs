@@ -1,9 +1,12 @@
+use std::collections::BTreeMap;
+
 use chrono::Datelike;
+use itertools::Itertools;
 use log::warn;
 
 use static_table_derive::StaticTable;
 
-use crate::broker_statement::BrokerStatement;
+use crate::broker_statement::{BrokerStatement, IdleCashInterest, RepoDeal};
 use crate::core::GenericResult;
 use crate::currency::{Cash, MultiCurrencyCashAccount};
 use crate::currency::converter::CurrencyConverter;
@@ -13,6 +16,7 @@ use crate::taxes::TaxCalculator;
 use crate::tax_statement::statement::CountryCode;
 use crate::types::{Date, Decimal};
 
+use super::report::TaxReport;
 use super::statement::TaxStatement;
 
 #[derive(StaticTable)]
@@ -36,6 +40,7 @@ struct Row {
 pub fn process_income(
     country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
     tax_calculator: &mut TaxCalculator, mut tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
+    report: Option<&mut TaxReport>,
 ) -> GenericResult<(Cash, bool, bool)> {
     let broker_jurisdiction = broker_statement.broker.type_.jurisdiction();
 
@@ -48,7 +53,17 @@ pub fn process_income(
     let mut total_tax_to_pay = Cash::zero(country.currency);
     let mut total_income = Cash::zero(country.currency);
 
-    for interest in &broker_statement.idle_cash_interest {
+    let securities_lending_income: Vec<_> = broker_statement.securities_lending_income().collect();
+    let repo_income = net_repo_results(&broker_statement.repo_deals);
+
+    let interest_income = broker_statement.idle_cash_interest.iter()
+        .map(|interest| (interest, "Проценты на остаток по брокерскому счету"))
+        .chain(securities_lending_income.iter()
+            .map(|interest| (interest, "Доход от предоставления ценных бумаг в заем")))
+        .chain(repo_income.iter().map(|interest| (interest, "Доход по сделкам РЕПО")))
+        .sorted_by_key(|(interest, _)| interest.date);
+
+    for (interest, income_description) in interest_income {
         if let Some(year) = year {
             if interest.date.year() != year {
                 continue;
@@ -94,9 +109,7 @@ pub fn process_income(
 
                 if let Some(ref mut statement) = tax_statement {
                     let country_code = CountryCode::new(broker_jurisdiction.traits().code)?;
-                    let description = format!(
-                        "{}: Проценты на остаток по брокерскому счету",
-                        broker_statement.broker.name);
+                    let description = format!("{}: {}", broker_statement.broker.name, income_description);
 
                     statement.add_interest_income(
                         &description, interest.date, country_code,
@@ -128,10 +141,62 @@ pub fn process_income(
         totals.set_tax_to_pay(total_tax_to_pay);
         totals.set_income(total_income);
 
-        table.print(&format!(
+        let title = format!(
             "Расчет дохода от процентов на остаток по брокерскому счету, полученных через {}",
-            broker_statement.broker.name));
+            broker_statement.broker.name);
+        table.print(&title);
+
+        if let Some(report) = report {
+            report.add("Проценты", &title, table.table, Some(total_tax_to_pay));
+        }
     }
 
     Ok((total_tax_to_pay, has_income, has_income_to_declare))
-}
\ No newline at end of file
+}
+// Repo deals result is taxed for the whole year, so losses of some deals reduce income from the others
+fn net_repo_results(deals: &[RepoDeal]) -> Vec<IdleCashInterest> {
+    let mut results: BTreeMap<(i32, &str), IdleCashInterest> = BTreeMap::new();
+
+    for deal in deals {
+        let interest = deal.interest();
+
+        match results.get_mut(&(interest.date.year(), interest.amount.currency)) {
+            Some(result) => {
+                result.date = std::cmp::max(result.date, interest.date);
+                result.amount += interest.amount;
+            },
+            None => {
+                results.insert((interest.date.year(), interest.amount.currency), interest);
+            },
+        }
+    }
+
+    results.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repo_results_netting() {
+        let deal = |close_date, result| RepoDeal {
+            symbol: s!("SBER"),
+            open_date: close_date,
+            close_date,
+            result: Cash::new("RUB", result),
+        };
+
+        let results = net_repo_results(&[
+            deal(date!(2023, 3, 1), dec!(1000)),
+            deal(date!(2023, 6, 1), dec!(-300)),
+            deal(date!(2024, 2, 1), dec!(-500)),
+        ]);
+
+        let results: Vec<_> = results.iter().map(|result| (result.date, result.amount)).collect();
+        assert_eq!(results, vec![
+            (date!(2023, 6, 1), Cash::new("RUB", dec!(700))),
+            (date!(2024, 2, 1), Cash::new("RUB", dec!(-500))),
+        ]);
+    }
+}
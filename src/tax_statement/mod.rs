@@ -1,36 +1,62 @@
+mod bank_interest;
+mod cfc;
+mod coupons;
+mod derivatives;
 mod dividends;
+mod grants;
+mod iis;
 mod interest;
+mod realized_pnl;
+mod report;
 mod statement;
 mod tax_agent;
+mod tax_rates;
 mod trades;
+mod year_end;
 
+use std::collections::BTreeSet;
 use std::path::Path;
 
 use ansi_term::Color;
+use log::warn;
 
-use crate::broker_statement::{BrokerStatement, ReadingStrictness};
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections};
 use crate::config::Config;
 use crate::core::GenericResult;
+use crate::currency::ManualCurrencyRates;
 use crate::currency::converter::CurrencyConverter;
 use crate::db;
-use crate::localities::Jurisdiction;
-use crate::taxes::TaxCalculator;
+use crate::errors::{Error, IncomeKind, TaxError};
+use crate::instruments;
+use crate::localities::{Country, Jurisdiction};
+use crate::profiling::{self, Phase};
+use crate::taxes::{LossCarryforward, TaxCalculator};
 use crate::telemetry::TelemetryRecordBuilder;
 
+use self::report::TaxReport;
+
+pub use self::bank_interest::BankInterestConfig;
+pub use self::cfc::ControlledForeignCompanyConfig;
+pub use self::realized_pnl::generate_realized_pnl_ledger;
 pub use self::statement::TaxStatement;
+pub use self::tax_rates::show_tax_rates;
+pub use self::year_end::generate_year_end_report;
 
 pub fn generate_tax_statement(
-    config: &Config, portfolio_name: &str, year: Option<i32>, tax_statement_path: Option<&Path>
+    config: &Config, portfolio_name: &str, year: Option<i32>, tax_statement_path: Option<&Path>, preview: bool,
+    diff: bool, report_path: Option<&Path>, audit_path: Option<&Path>,
 ) -> GenericResult<TelemetryRecordBuilder> {
+    let _timer = profiling::start(Phase::Taxes);
     let country = config.get_tax_country();
     let portfolio = config.get_portfolio(portfolio_name)?;
     let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
 
-    let broker_statement = BrokerStatement::read(
+    let mut broker_statement = BrokerStatement::read(
         broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
         &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions, &portfolio.corporate_actions,
         ReadingStrictness::TRADE_SETTLE_DATE | ReadingStrictness::OTC_INSTRUMENTS | ReadingStrictness::TAX_EXEMPTIONS |
-        ReadingStrictness::REPO_TRADES | ReadingStrictness::GRANTS)?;
+        ReadingStrictness::GRANTS, StatementSections::all())?;
+    instruments::reference::enrich(config, &mut broker_statement.instrument_info);
 
     if let Some(year) = year {
         broker_statement.check_period_against_tax_year(year)?;
@@ -42,8 +68,7 @@ pub fn generate_tax_statement(
 
             let statement = TaxStatement::read(path)?;
             if statement.year != year {
-                return Err!("Tax statement year ({}) doesn't match the requested year {}",
-                            statement.year, year);
+                return Err(Error::from(TaxError::YearMismatch {actual: statement.year, expected: year}).into());
             }
 
             Some(statement)
@@ -51,37 +76,102 @@ pub fn generate_tax_statement(
         None => None,
     };
 
+    let manual_currency_rates = ManualCurrencyRates::new(&config.taxes.currency_rates)?;
+    if let Some(ref mut tax_statement) = tax_statement {
+        tax_statement.set_manual_currency_rates(manual_currency_rates.clone());
+    }
+
     let database = db::connect(&config.db_path)?;
-    let converter = CurrencyConverter::new(database, None, true);
+    let mut loss_carryforward = if portfolio.carry_forward_losses {
+        Some(LossCarryforward::new(database.clone(), &portfolio.name)?)
+    } else {
+        None
+    };
+
+    let converter = CurrencyConverter::new_with_manual_rates(database, manual_currency_rates);
+    preload_currency_rates(&country, &broker_statement, &converter);
+
     let mut tax_calculator = TaxCalculator::new(country.clone());
+    let mut report = (report_path.is_some() || audit_path.is_some()).then(TaxReport::new);
 
     let (trades_tax, has_trading_income, has_trading_income_to_declare) = trades::process_income(
         &country, portfolio, &broker_statement, year, &mut tax_calculator, tax_statement.as_mut(), &converter,
-    ).map_err(|e| format!("Failed to process income from stock trading: {}", e))?;
+        loss_carryforward.as_mut(), report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::Trading, source: e}))?;
 
     let (dividends_tax, has_dividend_income, has_dividend_income_to_declare) = dividends::process_income(
-        &country, &broker_statement, year, &mut tax_calculator, tax_statement.as_mut(), &converter,
-    ).map_err(|e| format!("Failed to process dividend income: {}", e))?;
+        &country, &broker_statement, year, &mut tax_calculator, tax_statement.as_mut(), &converter, report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::Dividends, source: e}))?;
 
     let (interest_tax, has_interest_income, has_interest_income_to_declare) = interest::process_income(
-        &country, &broker_statement, year, &mut tax_calculator, tax_statement.as_mut(), &converter,
-    ).map_err(|e| format!("Failed to process income from idle cash interest: {}", e))?;
+        &country, &broker_statement, year, &mut tax_calculator, tax_statement.as_mut(), &converter, report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::Interest, source: e}))?;
+
+    let (coupons_tax, has_coupon_income) = coupons::process_income(
+        &country, &broker_statement, year, &mut tax_calculator, &converter, report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::Coupons, source: e}))?;
+
+    let (derivatives_tax, has_derivatives_income, has_derivatives_income_to_declare) = derivatives::process_income(
+        &country, &broker_statement, year, &mut tax_calculator, tax_statement.as_mut(), &converter, report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::Derivatives, source: e}))?;
+
+    let (other_tax, has_other_income) = grants::process_income(
+        &country, &broker_statement, year, &mut tax_calculator, &converter, report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::Other, source: e}))?;
+
+    // Controlled foreign companies profit and foreign bank interest aren't related to the broker, so they are always
+    // declared by the taxpayer
+    let has_cfc_income = cfc::process_income(
+        &country, &portfolio.controlled_foreign_companies, year, &mut tax_calculator, tax_statement.as_mut(), &converter,
+        report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::ControlledForeignCompanies, source: e}))?;
+
+    let has_bank_interest_income = bank_interest::process_income(
+        &country, &portfolio.bank_interest, year, &mut tax_calculator, tax_statement.as_mut(), &converter,
+        report.as_mut(),
+    ).map_err(|e| Error::from(TaxError::Income {income: IncomeKind::BankInterest, source: e}))?;
+
+    if portfolio.iis_deduction {
+        iis::process_deduction(
+            &country, portfolio, &broker_statement, year, tax_statement.is_some(), report.as_mut())?;
+    }
 
-    let has_income = has_trading_income | has_dividend_income | has_interest_income;
-    let has_income_to_declare = has_trading_income_to_declare | has_dividend_income_to_declare | has_interest_income_to_declare;
+    let has_income =
+        has_trading_income | has_dividend_income | has_interest_income | has_coupon_income |
+        has_derivatives_income | has_other_income;
+    let has_income_to_declare =
+        has_trading_income_to_declare | has_dividend_income_to_declare | has_interest_income_to_declare |
+        has_derivatives_income_to_declare | has_cfc_income | has_bank_interest_income;
 
     if broker_statement.broker.type_.jurisdiction() == Jurisdiction::Russia {
-        let total_tax = trades_tax + dividends_tax + interest_tax;
+        let total_tax = trades_tax + dividends_tax + interest_tax + coupons_tax + derivatives_tax + other_tax;
         tax_agent::process_tax_agent_withholdings(&broker_statement, year, has_income, total_tax)?;
     }
 
-    if let Some(ref tax_statement) = tax_statement {
+    // Trading results are used to carry losses forward when tax statements for the next years are generated, so
+    // they are saved on regular runs only
+    if let Some(ref loss_carryforward) = loss_carryforward {
+        if !preview {
+            loss_carryforward.save()?;
+        }
+    }
+
+    if let Some(ref mut tax_statement) = tax_statement {
         assert_eq!(tax_statement.modified, has_income_to_declare);
 
-        if has_income_to_declare {
-            tax_statement.save()?;
-            println!("{}", Color::Green.paint(
-                "The income has been added to the tax statement."));
+        if diff {
+            tax_statement.print_diff()?;
+        }
+
+        if preview {
+            tax_statement.print_preview()?;
+            println!("{}", Color::Yellow.paint(
+                "Preview mode: the tax statement hasn't been modified."));
+        } else if has_income_to_declare {
+            let backup_path = tax_statement.save()?;
+            println!("{}", Color::Green.paint(format!(
+                "The income has been added to the tax statement (the original file is saved to {:?}).",
+                backup_path)));
         }
     } else if has_income_to_declare {
         println!("{}", Color::Yellow.paint(
@@ -93,5 +183,36 @@ pub fn generate_tax_statement(
             "There is no any income to declare."));
     }
 
+    if let Some(report) = report {
+        if let Some(path) = report_path {
+            report.save(path)?;
+            println!("The tax report has been saved to {:?}.", path);
+        }
+
+        if let Some(path) = audit_path {
+            report.save_audit(path, &portfolio.name, year)?;
+            println!("The tax audit trail has been saved to {:?}.", path);
+        }
+    }
+
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+// Tax calculations need official currency rates for dates of all operations, so fetch them in bulk instead of fetching
+// them on demand by small date ranges. Failures aren't fatal here: some currencies may be covered only by manual
+// currency rates and the missing rates will be fetched on demand anyway.
+fn preload_currency_rates(country: &Country, statement: &BrokerStatement, converter: &CurrencyConverter) {
+    let mut currencies = BTreeSet::new();
+
+    for assets in statement.historical_assets.values() {
+        currencies.extend(assets.cash.iter().map(|cash| cash.currency));
+    }
+    currencies.extend(statement.deposits_and_withdrawals.iter().map(|assets| assets.cash.currency));
+    currencies.extend(statement.dividends.iter().map(|dividend| dividend.amount.currency));
+    currencies.extend(statement.idle_cash_interest.iter().map(|interest| interest.amount.currency));
+    currencies.remove(country.currency);
+
+    if let Err(e) = converter.preload(&currencies.into_iter().collect::<Vec<_>>(), statement.period) {
+        warn!("Failed to preload currency rates: {}.", e);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,327 @@
+use std::path::Path;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections, StockSellType, StockSourceDetails};
+use crate::config::{Config, PortfolioConfig};
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::db;
+use crate::formatting::{self, table::Cell};
+use crate::instruments;
+use crate::localities::Country;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::Date;
+use crate::types::Decimal;
+
+/// Prints (and optionally exports to CSV) a ledger of all closed positions with one entry per sold lot, which allows to
+/// cross-check broker-provided tax reports.
+pub fn generate_realized_pnl_ledger(
+    config: &Config, portfolio_name: &str, year: Option<i32>, export_path: Option<&Path>,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let country = config.get_tax_country();
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let mut statement = BrokerStatement::read(
+        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions, &portfolio.corporate_actions,
+        ReadingStrictness::TRADE_SETTLE_DATE | ReadingStrictness::OTC_INSTRUMENTS | ReadingStrictness::TAX_EXEMPTIONS |
+        ReadingStrictness::GRANTS, StatementSections::all())?;
+    instruments::reference::enrich(config, &mut statement.instrument_info);
+
+    if let Some(year) = year {
+        statement.check_period_against_tax_year(year)?;
+    }
+
+    let database = db::connect(&config.db_path)?;
+    let converter = CurrencyConverter::new(database, None, true);
+
+    let ledger = build_ledger(&country, portfolio, &statement, year, &converter)?;
+    if ledger.is_empty() {
+        println!("There are no closed positions for the specified period.");
+        return Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker));
+    }
+
+    print(&country, &ledger, year, statement.broker.quantity_increment());
+
+    if let Some(path) = export_path {
+        export(path, &country, &ledger).map_err(|e| format!(
+            "Failed to export the ledger to {:?}: {}", path, e))?;
+    }
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+struct LedgerEntry {
+    symbol: String,
+    source: &'static str,
+    quantity: Decimal,
+
+    buy_conclusion_date: Date,
+    buy_execution_date: Date,
+    buy_quantity: Decimal,
+    multiplier: Decimal,
+    buy_price: Option<Cash>,
+    buy_currency_rate: Option<Decimal>,
+    cost: Cash,
+    local_cost: Cash,
+
+    sell_conclusion_date: Date,
+    sell_execution_date: Date,
+    sell_price: Cash,
+    sell_currency_rate: Option<Decimal>,
+    revenue: Cash,
+    local_revenue: Cash,
+    commission: Cash,
+    local_commission: Cash,
+
+    profit: Cash,
+    local_profit: Cash,
+}
+
+fn build_ledger(
+    country: &Country, portfolio: &PortfolioConfig, statement: &BrokerStatement, year: Option<i32>,
+    converter: &CurrencyConverter,
+) -> GenericResult<Vec<LedgerEntry>> {
+    let tax_payment_day = portfolio.tax_payment_day();
+    let mut ledger = Vec::new();
+
+    for trade in &statement.stock_sells {
+        let sell_price = match trade.type_ {
+            StockSellType::Trade {price, ..} => price,
+            StockSellType::CorporateAction => continue,
+        };
+
+        let (tax_year, _) = tax_payment_day.get(trade.execution_date, true);
+        if year.is_some_and(|year| year != tax_year) {
+            continue;
+        }
+
+        let instrument = statement.instrument_info.get_or_empty(&trade.symbol);
+        let details = trade.calculate(country, &instrument, &portfolio.tax_exemptions, converter)?;
+
+        let currency = sell_price.currency;
+        let sell_currency_rate = get_currency_rate(country, converter, trade.execution_date, currency)?;
+
+        // Sell revenue and commission are distributed between the sold lots proportionally to their quantity
+        let commission = details.revenue - details.purchase_cost - details.profit;
+        let ratios: Vec<Decimal> = details.fifo.iter()
+            .map(|lot| lot.quantity * lot.multiplier / trade.quantity)
+            .collect();
+
+        let revenues = distribute(details.revenue, &ratios);
+        let local_revenues = distribute(details.local_revenue, &ratios);
+        let commissions = distribute(commission, &ratios);
+        let local_commissions = distribute(details.local_commission, &ratios);
+
+        for (index, lot) in details.fifo.iter().enumerate() {
+            let (source, buy_price, buy_currency_rate) = match lot.source {
+                StockSourceDetails::Trade {price, ..} => (
+                    "Trade", Some(price),
+                    get_currency_rate(country, converter, lot.execution_date, price.currency)?,
+                ),
+                StockSourceDetails::CorporateAction => ("Corporate action", None, None),
+                StockSourceDetails::Grant => ("Grant", None, None),
+            };
+
+            let cost = lot.total_cost(currency, converter)?;
+            let local_cost = lot.total_cost(country.currency, converter)?;
+
+            ledger.push(LedgerEntry {
+                symbol: trade.original_symbol.clone(),
+                source,
+                quantity: lot.quantity * lot.multiplier,
+
+                buy_conclusion_date: lot.conclusion_time.date,
+                buy_execution_date: lot.execution_date,
+                buy_quantity: lot.quantity,
+                multiplier: lot.multiplier,
+                buy_price, buy_currency_rate,
+                cost, local_cost,
+
+                sell_conclusion_date: trade.conclusion_time.date,
+                sell_execution_date: trade.execution_date,
+                sell_price, sell_currency_rate,
+                revenue: revenues[index],
+                local_revenue: local_revenues[index],
+                commission: commissions[index],
+                local_commission: local_commissions[index],
+
+                profit: revenues[index] - commissions[index] - cost,
+                local_profit: local_revenues[index] - local_commissions[index] - local_cost,
+            });
+        }
+    }
+
+    Ok(ledger)
+}
+
+fn get_currency_rate(
+    country: &Country, converter: &CurrencyConverter, date: Date, currency: &str,
+) -> GenericResult<Option<Decimal>> {
+    Ok(if currency == country.currency {
+        None
+    } else {
+        Some(converter.precise_currency_rate(date, currency, country.currency)?)
+    })
+}
+
+// Distributes the amount according to the specified ratios so the sum of rounded parts always matches the amount
+fn distribute(amount: Cash, ratios: &[Decimal]) -> Vec<Cash> {
+    let mut rest = amount;
+
+    ratios.iter().enumerate().map(|(index, &ratio)| {
+        if index == ratios.len() - 1 {
+            rest
+        } else {
+            let part = (amount * ratio).round();
+            rest -= part;
+            part
+        }
+    }).collect()
+}
+
+#[derive(StaticTable)]
+#[table(name="LedgerTable")]
+struct LedgerRow {
+    #[column(name="Instrument")]
+    symbol: String,
+    #[column(name="Quantity")]
+    quantity: Cell,
+    #[column(name="Source")]
+    source: String,
+    #[column(name="Buy date")]
+    buy_date: Date,
+    #[column(name="Buy price")]
+    buy_price: Option<Cash>,
+    #[column(name="Buy FX rate")]
+    buy_currency_rate: Option<Decimal>,
+    #[column(name="Cost")]
+    cost: Cash,
+    #[column(name="Local cost")]
+    local_cost: Cash,
+    #[column(name="Sell date")]
+    sell_date: Date,
+    #[column(name="Sell price")]
+    sell_price: Cash,
+    #[column(name="Sell FX rate")]
+    sell_currency_rate: Option<Decimal>,
+    #[column(name="Revenue")]
+    revenue: Cash,
+    #[column(name="Local revenue")]
+    local_revenue: Cash,
+    #[column(name="Commission")]
+    commission: Cash,
+    #[column(name="Local commission")]
+    local_commission: Cash,
+    #[column(name="Profit")]
+    profit: MultiCurrencyCashAccount,
+    #[column(name="Local profit")]
+    local_profit: Cash,
+}
+
+fn print(country: &Country, ledger: &[LedgerEntry], year: Option<i32>, quantity_increment: Decimal) {
+    let mut table = LedgerTable::new();
+
+    let mut total_local_cost = Cash::zero(country.currency);
+    let mut total_local_revenue = Cash::zero(country.currency);
+    let mut total_local_commission = Cash::zero(country.currency);
+    let mut total_profit = MultiCurrencyCashAccount::new();
+    let mut total_local_profit = Cash::zero(country.currency);
+
+    for entry in ledger {
+        total_local_cost += entry.local_cost;
+        total_local_revenue += entry.local_revenue;
+        total_local_commission += entry.local_commission;
+        total_profit.deposit(entry.profit);
+        total_local_profit += entry.local_profit;
+
+        table.add_row(LedgerRow {
+            symbol: entry.symbol.clone(),
+            quantity: Cell::new_quantity(entry.quantity, quantity_increment),
+            source: entry.source.to_owned(),
+            buy_date: entry.buy_execution_date,
+            buy_price: entry.buy_price.map(|price| price / entry.multiplier),
+            buy_currency_rate: entry.buy_currency_rate,
+            cost: entry.cost.round(),
+            local_cost: entry.local_cost,
+            sell_date: entry.sell_execution_date,
+            sell_price: entry.sell_price,
+            sell_currency_rate: entry.sell_currency_rate,
+            revenue: entry.revenue,
+            local_revenue: entry.local_revenue,
+            commission: entry.commission,
+            local_commission: entry.local_commission,
+            profit: MultiCurrencyCashAccount::from(entry.profit.round()),
+            local_profit: entry.local_profit,
+        });
+    }
+
+    let mut totals = table.add_empty_row();
+    totals.set_local_cost(total_local_cost);
+    totals.set_local_revenue(total_local_revenue);
+    totals.set_local_commission(total_local_commission);
+    totals.set_profit(total_profit);
+    totals.set_local_profit(total_local_profit);
+
+    table.print(&match year {
+        Some(year) => format!("Realized profit and loss for {}", year),
+        None => s!("Realized profit and loss"),
+    });
+}
+
+fn export(path: &Path, country: &Country, ledger: &[LedgerEntry]) -> EmptyResult {
+    let local = |name: &str| format!("{} ({})", name, country.currency);
+    let mut writer = csv::Writer::from_path(path)?;
+
+    writer.write_record([
+        s!("Instrument"), s!("Quantity"), s!("Source"), s!("Currency"),
+        s!("Buy conclusion date"), s!("Buy execution date"), s!("Buy quantity"), s!("Split multiplier"),
+        s!("Buy price"), s!("Buy FX rate"), s!("Cost"), local("Cost"),
+        s!("Sell conclusion date"), s!("Sell execution date"), s!("Sell price"), s!("Sell FX rate"),
+        s!("Revenue"), local("Revenue"), s!("Commission"), local("Commission"),
+        s!("Profit"), local("Profit"),
+    ])?;
+
+    let format_date = |date| formatting::format_date(date).to_string();
+    let format_optional = |value: Option<Decimal>| value.map(|value| value.to_string()).unwrap_or_default();
+
+    for entry in ledger {
+        writer.write_record([
+            entry.symbol.clone(), entry.quantity.to_string(), entry.source.to_owned(),
+            entry.sell_price.currency.to_owned(),
+
+            format_date(entry.buy_conclusion_date), format_date(entry.buy_execution_date),
+            entry.buy_quantity.to_string(), entry.multiplier.to_string(),
+            format_optional(entry.buy_price.map(|price| price.amount)), format_optional(entry.buy_currency_rate),
+            entry.cost.round().amount.to_string(), entry.local_cost.amount.to_string(),
+
+            format_date(entry.sell_conclusion_date), format_date(entry.sell_execution_date),
+            entry.sell_price.amount.to_string(), format_optional(entry.sell_currency_rate),
+            entry.revenue.amount.to_string(), entry.local_revenue.amount.to_string(),
+            entry.commission.amount.to_string(), entry.local_commission.amount.to_string(),
+
+            entry.profit.round().amount.to_string(), entry.local_profit.amount.to_string(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution() {
+        let amount = Cash::new("USD", dec!(100));
+        let parts = distribute(amount, &[dec!(1) / dec!(3), dec!(1) / dec!(3), dec!(1) / dec!(3)]);
+
+        assert_eq!(parts, vec![
+            Cash::new("USD", dec!(33.33)), Cash::new("USD", dec!(33.33)), Cash::new("USD", dec!(33.34)),
+        ]);
+    }
+}
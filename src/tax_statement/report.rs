@@ -0,0 +1,187 @@
+use std::fs;
+use std::path::Path;
+
+use rust_xlsxwriter::Workbook;
+use serde_json::json;
+use static_table_derive::StaticTable;
+
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::formatting::table::Table;
+
+/// Tax report with income calculation details which can be attached to 3-НДФЛ tax declaration as supporting
+/// documentation or exported as a machine-readable audit trail.
+pub struct TaxReport {
+    sheets: Vec<Sheet>,
+    total_tax_to_pay: Option<Cash>,
+}
+
+struct Sheet {
+    name: &'static str,
+    title: String,
+    table: Table,
+    tax_to_pay: Option<Cash>,
+}
+
+impl TaxReport {
+    pub fn new() -> TaxReport {
+        TaxReport {
+            sheets: Vec::new(),
+            total_tax_to_pay: None,
+        }
+    }
+
+    /// Adds income calculation table as a separate sheet. Tax to pay is specified for the main income tables and is
+    /// listed in the summary sheet.
+    pub fn add(&mut self, name: &'static str, title: &str, table: Table, tax_to_pay: Option<Cash>) {
+        if let Some(tax_to_pay) = tax_to_pay {
+            self.total_tax_to_pay = Some(match self.total_tax_to_pay {
+                Some(total) => total + tax_to_pay,
+                None => tax_to_pay,
+            });
+        }
+        self.sheets.push(Sheet {name, title: title.to_owned(), table, tax_to_pay});
+    }
+
+    pub fn save(&self, path: &Path) -> EmptyResult {
+        self.save_xlsx(path).map_err(|e| format!("Failed to save the tax report to {:?}: {}", path, e).into())
+    }
+
+    /// Saves the report as an audit trail: a JSON document with all calculated figures and their inputs (trade and
+    /// payment dates, currency rates, amounts from broker statements), so any number of the tax declaration can be
+    /// traced back to its origin.
+    pub fn save_audit(&self, path: &Path, portfolio: &str, year: Option<i32>) -> EmptyResult {
+        let sections: Vec<serde_json::Value> = self.sheets.iter().map(|sheet| json!({
+            "name": sheet.name,
+            "title": sheet.title,
+            "tax_to_pay": sheet.tax_to_pay.map(cash_to_json),
+            "table": sheet.table.export_json(),
+        })).collect();
+
+        let audit = json!({
+            "portfolio": portfolio,
+            "year": year,
+            "total_tax_to_pay": self.total_tax_to_pay.map(cash_to_json),
+            "sections": sections,
+        });
+
+        let data = serde_json::to_string_pretty(&audit)?;
+        fs::write(path, data + "\n").map_err(|e| format!(
+            "Failed to save the tax audit trail to {:?}: {}", path, e).into())
+    }
+
+    fn save_xlsx(&self, path: &Path) -> EmptyResult {
+        let mut workbook = Workbook::new();
+
+        if let Some(total_tax_to_pay) = self.total_tax_to_pay {
+            let mut summary = SummaryTable::new();
+            for sheet in &self.sheets {
+                if let Some(tax_to_pay) = sheet.tax_to_pay {
+                    summary.add_row(SummaryRow {income: sheet.name, tax_to_pay});
+                }
+            }
+            summary.add_empty_row().set_tax_to_pay(total_tax_to_pay);
+
+            let sheet = workbook.add_worksheet().set_name("Итого")?;
+            summary.table.export_xlsx(sheet, "Налог к уплате")?;
+        }
+
+        for sheet_info in &self.sheets {
+            let sheet = workbook.add_worksheet().set_name(sheet_info.name)?;
+            sheet_info.table.export_xlsx(sheet, &sheet_info.title)?;
+        }
+
+        workbook.save(path)?;
+        Ok(())
+    }
+}
+
+fn cash_to_json(cash: Cash) -> serde_json::Value {
+    json!({
+        "amount": cash.amount.normalize(),
+        "currency": cash.currency,
+    })
+}
+
+#[derive(StaticTable)]
+#[table(name="SummaryTable")]
+struct SummaryRow {
+    #[column(name="Вид дохода")]
+    income: &'static str,
+    #[column(name="К уплате")]
+    tax_to_pay: Cash,
+}
+
+#[cfg(test)]
+mod tests {
+    use calamine::{Data, Reader, Xlsx};
+    use crate::formatting::table::Column;
+    use super::*;
+
+    #[test]
+    fn xlsx_export() {
+        let mut table = Table::new(vec![Column::new("Дата"), Column::new("Эмитент"), Column::new("Сумма")]);
+        table.add_row(vec![
+            date!(2023, 3, 28).into(), "Apple".into(), Cash::new("USD", dec!(12.34)).into(),
+        ]);
+
+        let mut report = TaxReport::new();
+        report.add("Дивиденды", "Расчет дохода от дивидендов", table, Some(Cash::new("RUB", dec!(1234.5))));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("report.xlsx");
+        report.save(&path).unwrap();
+
+        let mut workbook: Xlsx<_> = calamine::open_workbook(&path).unwrap();
+        assert_eq!(workbook.sheet_names(), vec![s!("Итого"), s!("Дивиденды")]);
+
+        let summary = workbook.worksheet_range("Итого").unwrap();
+        assert_eq!(summary.get_value((0, 0)), Some(&Data::String(s!("Налог к уплате"))));
+        assert_eq!(summary.get_value((2, 0)), Some(&Data::String(s!("Вид дохода"))));
+        assert_eq!(summary.get_value((3, 0)), Some(&Data::String(s!("Дивиденды"))));
+        assert_eq!(summary.get_value((3, 1)), Some(&Data::Float(1234.5)));
+        assert_eq!(summary.get_value((4, 1)), Some(&Data::Float(1234.5)));
+
+        let dividends = workbook.worksheet_range("Дивиденды").unwrap();
+        assert_eq!(dividends.get_value((0, 0)), Some(&Data::String(s!("Расчет дохода от дивидендов"))));
+        assert!(matches!(dividends.get_value((3, 0)), Some(Data::DateTime(_))));
+        assert_eq!(dividends.get_value((3, 1)), Some(&Data::String(s!("Apple"))));
+        assert_eq!(dividends.get_value((3, 2)), Some(&Data::Float(12.34)));
+    }
+
+    #[test]
+    fn audit_export() {
+        let mut table = Table::new(vec![
+            Column::new("Дата"), Column::new("Эмитент"), Column::new("Курс"), Column::new("Сумма\n(руб)")]);
+        table.add_row(vec![
+            date!(2023, 3, 28).into(), "Apple".into(), dec!(76.2286).into(), Cash::new("RUB", dec!(940.66)).into(),
+        ]);
+        table.add_empty_row();
+
+        let mut report = TaxReport::new();
+        report.add("Дивиденды", "Расчет дохода от дивидендов", table, Some(Cash::new("RUB", dec!(122))));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("audit.json");
+        report.save_audit(&path, "ib", Some(2023)).unwrap();
+
+        let audit: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(audit, json!({
+            "portfolio": "ib",
+            "year": 2023,
+            "total_tax_to_pay": {"amount": "122", "currency": "RUB"},
+            "sections": [{
+                "name": "Дивиденды",
+                "title": "Расчет дохода от дивидендов",
+                "tax_to_pay": {"amount": "122", "currency": "RUB"},
+                "table": {
+                    "columns": ["Дата", "Эмитент", "Курс", "Сумма (руб)"],
+                    "rows": [
+                        ["2023-03-28", "Apple", "76.2286", {"amount": "940.66", "currency": "RUB"}],
+                        [null, null, null, null],
+                    ],
+                },
+            }],
+        }));
+    }
+}
@@ -24,7 +24,7 @@ impl CountryCode {
         }
     }
 
-    fn to_code(self) -> Integer {
+    pub fn to_code(self) -> Integer {
         match self {
             CountryCode::Russia => 643,
             CountryCode::Usa => 840,
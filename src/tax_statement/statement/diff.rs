@@ -0,0 +1,64 @@
+use ansi_term::Color;
+
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::formatting;
+
+use super::TaxStatement;
+use super::foreign_income::CurrencyIncome;
+
+const CURRENCY: &str = "RUB";
+
+impl TaxStatement {
+    /// Prints the records which have been added to or removed from the statement since it was read
+    pub fn print_diff(&mut self) -> EmptyResult {
+        let original = self.original_incomes.clone();
+        let current = self.get_foreign_incomes()?;
+
+        let mut added: Vec<&CurrencyIncome> = current.iter().collect();
+        let mut removed = Vec::new();
+
+        for income in original.iter().flatten() {
+            match added.iter().position(|&other| other == income) {
+                Some(index) => {
+                    added.remove(index);
+                },
+                None => removed.push(income),
+            }
+        }
+
+        if added.is_empty() && removed.is_empty() {
+            println!("The tax statement hasn't been changed.");
+            return Ok(());
+        }
+
+        println!("Tax statement changes:");
+        for income in removed {
+            println!("{}", Color::Red.paint(format!("- {}", format_income(income))));
+        }
+        for income in added {
+            println!("{}", Color::Green.paint(format!("+ {}", format_income(income))));
+        }
+
+        Ok(())
+    }
+}
+
+fn format_income(income: &CurrencyIncome) -> String {
+    let income_type = income.type_.to_generic();
+
+    let mut description = format!(
+        "{} ({}) {}: {:?} {} {} = {}",
+        income_type.code, income.source_from.to_code(), formatting::format_date(income.date), income.description,
+        income.amount, income.currency.name, Cash::new(CURRENCY, income.local_amount));
+
+    if !income.local_paid_tax.is_zero() {
+        description += &format!(", уплачено {}", Cash::new(CURRENCY, income.local_paid_tax));
+    }
+
+    if !income.deduction.amount.is_zero() {
+        description += &format!(", вычет {}", Cash::new(CURRENCY, income.deduction.amount));
+    }
+
+    description
+}
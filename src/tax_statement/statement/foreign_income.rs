@@ -86,21 +86,27 @@ tax_statement_inner_record!(CurrencyInfo {
     name: String,
 });
 
+// Currency, its code, name and currency rate units
+const CURRENCIES: [(&str, &str, &str, Integer); 6] = [
+    ("AUD", "036", "Австралийский доллар", 100),
+    ("EUR", "978", "Евро", 100),
+    ("GBP", "826", "Фунт стерлингов", 100),
+    ("HKD", "344", "Гонконгский доллар", 100),
+    ("RUB", "643", "Российский рубль", 1000),
+    ("USD", "840", "Доллар США", 100),
+];
+
 impl CurrencyInfo {
-    pub fn new(currency: &str, precise_currency_rate: Decimal) -> GenericResult<CurrencyInfo> {
-        let (currency_code, currency_name, currency_rate_units) = match currency {
-            "AUD" => ("036", "Австралийский доллар", 100),
-            "EUR" => ("978", "Евро", 100),
-            "GBP" => ("826", "Фунт стерлингов", 100),
-            "HKD" => ("344", "Гонконгский доллар", 100),
-            "RUB" => ("643", "Российский рубль", 1000),
-            "USD" => ("840", "Доллар США", 100),
-            _ => return Err!("{} currency is not supported yet", currency),
-        };
+    /// The declaration program calculates currency rate automatically using CBR rates, so manually specified rates
+    /// must disable automatic conversion.
+    pub fn new(currency: &str, precise_currency_rate: Decimal, manual_rate: bool) -> GenericResult<CurrencyInfo> {
+        let &(_, currency_code, currency_name, currency_rate_units) = CURRENCIES.iter()
+            .find(|&&(name, ..)| name == currency)
+            .ok_or_else(|| format!("{} currency is not supported yet", currency))?;
         let currency_rate = currency::round(precise_currency_rate * Decimal::from(currency_rate_units));
 
         Ok(CurrencyInfo {
-            automatic_convertion: true,
+            automatic_convertion: !manual_rate,
             code: currency_code.to_owned(),
 
             income_date_rate: currency_rate,
@@ -112,6 +118,14 @@ impl CurrencyInfo {
             name: currency_name.to_owned(),
         })
     }
+
+    pub fn currency(&self) -> Option<&'static str> {
+        CURRENCIES.iter().find(|&&(_, code, ..)| code == self.code).map(|&(currency, ..)| currency)
+    }
+
+    pub fn precise_rate(&self) -> Decimal {
+        self.income_date_rate / Decimal::from(self.income_date_units)
+    }
 }
 
 tax_statement_inner_record!(DeductionInfo {
@@ -157,7 +171,7 @@ pub enum IncomeType {
 }
 
 impl IncomeType {
-    fn to_generic(&self) -> GenericIncomeType {
+    pub fn to_generic(&self) -> GenericIncomeType {
         let (category, code, name) = match self {
             IncomeType::Dividend => (0, 1010, "Дивиденды"),
             IncomeType::Stock => (0, 1530, "(01)Доходы от реализации ЦБ (обращ-ся на орг. рынке ЦБ)"),
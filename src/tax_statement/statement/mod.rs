@@ -1,40 +1,61 @@
 #[macro_use] mod record;
 
 mod countries;
+mod diff;
 mod encoding;
 mod foreign_income;
 mod parser;
+mod preview;
 mod types;
 
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::core::{EmptyResult, GenericResult};
+use crate::currency::ManualCurrencyRates;
+use crate::errors::{Error, TaxError};
+use crate::time;
 use crate::types::{Date, Decimal};
 use crate::util;
 
 use self::foreign_income::{ForeignIncome, CurrencyIncome, CurrencyInfo, DeductionInfo, IncomeType,
-                           ControlledForeignCompanyInfo};
+                           GenericIncomeType, ControlledForeignCompanyInfo};
 use self::record::Record;
-use self::parser::{TaxStatementReader, TaxStatementWriter};
+use self::parser::{TaxStatementReader, TaxStatementWriter, TaxStatementFormat};
 
 pub use self::countries::CountryCode;
 
 #[derive(Debug)]
 pub struct TaxStatement {
     path: PathBuf,
+    format: TaxStatementFormat,
     pub year: i32,
     pub modified: bool,
     records: Vec<Box<dyn Record>>,
+    added_incomes: usize,
+    original_incomes: Option<Vec<CurrencyIncome>>,
+    manual_currency_rates: ManualCurrencyRates,
 }
 
 impl TaxStatement {
     pub fn read(path: &Path) -> GenericResult<TaxStatement> {
-        Ok(TaxStatementReader::read(path).map_err(|e| format!(
-            "Error while reading {:?} tax statement: {}", path, e))?)
+        Ok(TaxStatementReader::read(path).map_err(|e| Error::from(TaxError::Read {
+            path: path.to_owned(), source: e,
+        }))?)
     }
 
-    pub fn save(&self) -> EmptyResult {
+    /// Sets manually specified currency rates, so incomes which use them will be added with disabled automatic
+    /// currency conversion.
+    pub fn set_manual_currency_rates(&mut self, rates: ManualCurrencyRates) {
+        self.manual_currency_rates = rates;
+    }
+
+    /// Saves the statement, keeping a timestamped backup of the original file. Returns the backup path.
+    pub fn save(&self) -> GenericResult<PathBuf> {
+        let backup_path = get_backup_path(&self.path);
+        fs::copy(&self.path, &backup_path).map_err(|e| format!(
+            "Failed to backup {:?} to {:?}: {}", self.path, backup_path, e))?;
+
         let temp_path = util::temp_path(&self.path);
 
         TaxStatementWriter::write(self, &temp_path).map_err(|e| {
@@ -47,7 +68,7 @@ impl TaxStatement {
             format!("Failed to rename {:?} to {:?}: {}", temp_path, self.path, e)
         })?;
 
-        Ok(())
+        Ok(backup_path)
     }
 
     pub fn add_dividend_income(
@@ -62,7 +83,7 @@ impl TaxStatement {
 
             date: date,
             tax_payment_date: date,
-            currency: CurrencyInfo::new(currency, currency_rate)?,
+            currency: self.get_currency_info(date, currency, currency_rate)?,
 
             amount: amount,
             local_amount: local_amount,
@@ -88,7 +109,7 @@ impl TaxStatement {
 
             date: date,
             tax_payment_date: date,
-            currency: CurrencyInfo::new(currency, currency_rate)?,
+            currency: self.get_currency_info(date, currency, currency_rate)?,
 
             amount: amount,
             local_amount: local_amount,
@@ -115,7 +136,7 @@ impl TaxStatement {
 
             date: date,
             tax_payment_date: date,
-            currency: CurrencyInfo::new(currency, currency_rate)?,
+            currency: self.get_currency_info(date, currency, currency_rate)?,
 
             amount: amount,
             local_amount: local_amount,
@@ -134,8 +155,53 @@ impl TaxStatement {
         })
     }
 
+    pub fn add_controlled_foreign_company_income(
+        &mut self, description: &str, date: Date, company_jurisdiction: CountryCode,
+        number: &str, profit_calculation_method: usize, currency: &str, currency_rate: Decimal,
+        amount: Decimal, paid_tax: Decimal, local_amount: Decimal, local_paid_tax: Decimal,
+    ) -> EmptyResult {
+        self.add_foreign_income(CurrencyIncome {
+            type_: IncomeType::Other(GenericIncomeType {
+                category: 0,
+                code: 1580,
+                name: s!("Прибыль контролируемой иностранной компании"),
+            }),
+            description: description.to_owned(),
+
+            source_from: company_jurisdiction,
+            received_in: company_jurisdiction,
+
+            date: date,
+            tax_payment_date: date,
+            currency: self.get_currency_info(date, currency, currency_rate)?,
+
+            amount: amount,
+            local_amount: local_amount,
+
+            paid_tax: paid_tax,
+            local_paid_tax: local_paid_tax,
+            deduction: DeductionInfo::new_none(),
+
+            controlled_foreign_company: ControlledForeignCompanyInfo {
+                number: number.to_owned(),
+                profit_calculation_method,
+                ..ControlledForeignCompanyInfo::new_none()
+            },
+        })
+    }
+
+    fn get_currency_info(&self, date: Date, currency: &str, currency_rate: Decimal) -> GenericResult<CurrencyInfo> {
+        let manual_rate = self.manual_currency_rates.get(currency, date).is_some();
+        CurrencyInfo::new(currency, currency_rate, manual_rate)
+    }
+
     fn add_foreign_income(&mut self, income: CurrencyIncome) -> EmptyResult {
+        if self.original_incomes.is_none() {
+            self.original_incomes = Some(self.get_foreign_incomes()?.clone());
+        }
+
         self.get_foreign_incomes()?.push(income);
+        self.added_incomes += 1;
         self.modified = true;
         Ok(())
     }
@@ -168,4 +234,10 @@ impl TaxStatement {
             None => None,
         })
     }
-}
\ No newline at end of file
+}
+
+fn get_backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.as_os_str().to_os_string();
+    backup_path.push(time::now().format(".%Y%m%d-%H%M%S.bak").to_string());
+    PathBuf::from(backup_path)
+}
@@ -6,15 +6,13 @@ use std::ops::Deref;
 use std::path::Path;
 use std::rc::Rc;
 
-use chrono::Datelike;
 use lazy_static::lazy_static;
-use log::{trace, debug, warn};
-use num_integer::Integer;
+use log::{trace, debug};
 use regex::Regex;
 #[cfg(test)] use tempfile::NamedTempFile;
 
 use crate::core::{EmptyResult, GenericResult};
-use crate::time;
+use crate::currency::ManualCurrencyRates;
 #[cfg(test)] use crate::types::Decimal;
 #[cfg(test)] use crate::util;
 
@@ -24,7 +22,54 @@ use super::record::{Record, UnknownRecord, is_record_name};
 use super::encoding::{TaxStatementType, TaxStatementPrimitiveType};
 use super::foreign_income::ForeignIncome;
 
-const SUPPORTED_YEAR: i32 = 2024;
+/// Tax statement file format. Декларация program is released each year and its file format changes from year to year,
+/// so every supported format must be explicitly registered in `SUPPORTED_FORMATS` and covered by tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaxStatementFormat {
+    pub year: i32,
+    version: &'static str,
+}
+
+const SUPPORTED_FORMATS: &[TaxStatementFormat] = &[
+    TaxStatementFormat {year: 2024, version: "0103"},
+];
+
+const HEADER_PREFIX: &str = "DLSG            Decl";
+const HEADER_SUFFIX: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF";
+const HEADER_SIZE: usize = HEADER_PREFIX.len() + 4 + 4 + HEADER_SUFFIX.len();
+
+impl TaxStatementFormat {
+    fn detect(header: &str) -> GenericResult<TaxStatementFormat> {
+        let format = header.strip_prefix(HEADER_PREFIX)
+            .and_then(|data| data.strip_suffix(HEADER_SUFFIX))
+            .filter(|data| data.len() == 8 && data.is_ascii())
+            .and_then(|data| {
+                let year = data[..4].parse::<i32>().ok()?;
+                Some((year, &data[4..]))
+            });
+
+        let Some((year, version)) = format else {
+            return Err!("The file has an unexpected header");
+        };
+
+        let format = SUPPORTED_FORMATS.iter().find(|format| format.year == year && format.version == version);
+
+        format.cloned().ok_or_else(|| {
+            let supported = SUPPORTED_FORMATS.iter()
+                .map(|format| format!("Декларация {} (*{})", format.year, get_extension(format.year)))
+                .collect::<Vec<_>>().join(", ");
+
+            format!(concat!(
+                "Unsupported tax statement format: it has been created by Декларация {} program (format version {}). ",
+                "The following program versions are supported: {}",
+            ), year, version, supported).into()
+        })
+    }
+
+    fn header(&self) -> String {
+        format!("{}{}{}{}", HEADER_PREFIX, self.year, self.version, HEADER_SUFFIX)
+    }
+}
 
 pub struct TaxStatementReader {
     file: BufReader<File>,
@@ -42,28 +87,18 @@ impl TaxStatementReader {
             .and_then(|captures| captures.get(1).unwrap().as_str().parse::<i32>().ok())
             .ok_or("Invalid tax statement file extension: *.dcX is expected")?;
 
-        let (mut decade, current_short_year) = time::today().year().div_mod_floor(&10);
-        if short_year > current_short_year + 1 {
-            decade -= 1;
-        }
-        let year = decade * 10 + short_year;
-
-        if year != SUPPORTED_YEAR {
-            warn!(concat!(
-                "Only *{} tax statements ({} year) are supported by the program. ",
-                "Reading or writing tax statements for other years may have issues or won't work ",
-                "at all."
-            ), get_extension(SUPPORTED_YEAR), SUPPORTED_YEAR);
-        }
-
         let mut reader = TaxStatementReader {
             file: BufReader::new(File::open(path)?),
             buffer: Vec::new(),
         };
 
-        let header = get_header(year);
-        if reader.read_raw(header.len())? != header {
-            return Err!("The file has an unexpected header");
+        let format = TaxStatementFormat::detect(&reader.read_raw(HEADER_SIZE)?)?;
+        let year = format.year;
+
+        if year % 10 != short_year {
+            return Err!(
+                "The tax statement file extension doesn't match its year ({}): *{} is expected",
+                year, get_extension(year));
         }
 
         let mut records = Vec::new();
@@ -106,9 +141,13 @@ impl TaxStatementReader {
 
         let statement = TaxStatement {
             path: path.to_owned(),
+            format: format,
             year: year,
             modified: false,
             records: records,
+            added_incomes: 0,
+            original_incomes: None,
+            manual_currency_rates: ManualCurrencyRates::default(),
         };
         debug!("Read statement:\n{:#?}", statement);
 
@@ -203,7 +242,7 @@ impl TaxStatementWriter {
             buffer: Rc::default(),
         };
 
-        writer.write_raw(&get_header(statement.year))?;
+        writer.write_raw(&statement.format.header())?;
 
         for record in &statement.records {
             record.write(&mut writer)?;
@@ -255,10 +294,6 @@ fn get_extension(year: i32) -> String {
     format!(".dc{}", year % 10)
 }
 
-fn get_header(year: i32) -> String {
-    format!(r"DLSG            Decl{}0103FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", year)
-}
-
 fn encode(data: &str) -> GenericResult<Cow<[u8]>> {
     let (encoded_data, _, errors) = encoding_rs::WINDOWS_1251.encode(data);
     if errors {
@@ -270,20 +305,20 @@ fn encode(data: &str) -> GenericResult<Cow<[u8]>> {
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
+    use rstest::rstest;
     use super::*;
 
-    #[test]
-    fn parse_empty() {
-        test_parsing(&Path::new(file!()).parent().unwrap().join(get_path("empty")));
+    #[rstest(year, case(2024))]
+    fn parse_empty(year: i32) {
+        test_parsing(&Path::new(file!()).parent().unwrap().join(get_path("empty", year)), year);
     }
 
-    #[test]
-    fn parse_filled() {
-        let path = Path::new(file!()).parent().unwrap().join(get_path("filled"));
+    #[rstest(year, case(2024))]
+    fn parse_filled(year: i32) {
+        let path = Path::new(file!()).parent().unwrap().join(get_path("filled", year));
 
         let data = get_contents(&path);
-        let mut statement = test_parsing(&path);
-        let year = statement.year;
+        let mut statement = test_parsing(&path, year);
 
         let incomes: Vec<_> = statement.get_foreign_incomes().unwrap().drain(..).collect();
         assert!(!incomes.is_empty());
@@ -355,14 +390,37 @@ mod tests {
     // FIXME(konishchev): Update
     #[test]
     fn parse_real() {
-        test_parsing(&get_path("statement"));
+        test_parsing(&get_path("statement", 2024), 2024);
+    }
+
+    #[test]
+    fn supported_formats() {
+        for format in SUPPORTED_FORMATS {
+            assert_eq!(TaxStatementFormat::detect(&format.header()).unwrap(), *format);
+        }
+    }
+
+    #[rstest(header, error,
+        case("DLSG            Decl20990103FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+             "Unsupported tax statement format: it has been created by Декларация 2099 program (format version 0103)"),
+        case("DLSG            Decl20240199FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+             "Unsupported tax statement format: it has been created by Декларация 2024 program (format version 0199)"),
+        case("DLSG            Decl20240103FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF0",
+             "The file has an unexpected header"),
+    )]
+    fn unsupported_formats(header: &str, error: &str) {
+        let message = TaxStatementFormat::detect(header).unwrap_err().to_string();
+        assert!(message.starts_with(error), "{}", message);
+        if message.starts_with("Unsupported") {
+            assert!(message.ends_with("The following program versions are supported: Декларация 2024 (*.dc4)"));
+        }
     }
 
-    fn test_parsing(path: &Path) -> TaxStatement {
+    fn test_parsing(path: &Path, year: i32) -> TaxStatement {
         let data = get_contents(path);
 
         let statement = TaxStatementReader::read(path).unwrap();
-        assert_eq!(statement.year, SUPPORTED_YEAR);
+        assert_eq!(statement.year, year);
         compare_to(&statement, &data);
 
         statement
@@ -381,8 +439,8 @@ mod tests {
         assert_eq!(&get_contents(temp_file.path()), data);
     }
 
-    fn get_path(name: &str) -> PathBuf {
-        PathBuf::from(format!("testdata/{}{}", name, get_extension(SUPPORTED_YEAR)))
+    fn get_path(name: &str, year: i32) -> PathBuf {
+        PathBuf::from(format!("testdata/{}{}", name, get_extension(year)))
     }
 
     fn get_contents(path: &Path) -> String {
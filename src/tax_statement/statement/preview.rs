@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use static_table_derive::StaticTable;
+
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::types::{Date, Decimal};
+
+use super::TaxStatement;
+use super::types::Integer;
+
+const CURRENCY: &str = "RUB";
+
+#[derive(StaticTable)]
+#[table(name="RecordsTable")]
+struct RecordRow {
+    #[column(name="Код дохода")]
+    code: Integer,
+    #[column(name="Страна")]
+    country: Integer,
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Описание")]
+    description: String,
+    #[column(name="Курс")]
+    currency_rate: Option<Decimal>,
+    #[column(name="Источник курса")]
+    currency_rate_source: Option<String>,
+    #[column(name="Доход (руб)")]
+    amount: Cash,
+    #[column(name="Уплачено (руб)")]
+    paid_tax: Cash,
+    #[column(name="Вычет (руб)")]
+    deduction: Cash,
+}
+
+#[derive(StaticTable)]
+#[table(name="TotalsTable")]
+struct TotalsRow {
+    #[column(name="Код дохода")]
+    code: Integer,
+    #[column(name="Страна")]
+    country: Integer,
+    #[column(name="Записей")]
+    records: usize,
+    #[column(name="Доход (руб)")]
+    amount: Cash,
+    #[column(name="Уплачено (руб)")]
+    paid_tax: Cash,
+    #[column(name="Вычет (руб)")]
+    deduction: Cash,
+}
+
+#[derive(Default)]
+struct Totals {
+    records: usize,
+    amount: Decimal,
+    paid_tax: Decimal,
+    deduction: Decimal,
+}
+
+impl TaxStatement {
+    /// Prints the records which have been added to the statement since it was read
+    pub fn print_preview(&mut self) -> EmptyResult {
+        let added_incomes = self.added_incomes;
+        let manual_currency_rates = self.manual_currency_rates.clone();
+        let incomes = self.get_foreign_incomes()?;
+        let mut incomes: Vec<_> = incomes[incomes.len() - added_incomes..].iter().collect();
+
+        incomes.sort_by_key(|income| (income.type_.to_generic().code, income.source_from.to_code()));
+
+        let mut records_table = RecordsTable::new();
+        let mut totals_table = TotalsTable::new();
+        let mut groups: BTreeMap<(Integer, Integer), Totals> = BTreeMap::new();
+        let mut total = Totals::default();
+
+        for income in incomes {
+            let code = income.type_.to_generic().code;
+            let country = income.source_from.to_code();
+
+            let (currency_rate, currency_rate_source) = match income.currency.currency() {
+                Some(currency) if currency != CURRENCY => (
+                    Some(income.currency.precise_rate()),
+                    Some(manual_currency_rates.format_source(currency, income.date)),
+                ),
+                _ => (None, None),
+            };
+
+            records_table.add_row(RecordRow {
+                code, country,
+                date: income.date,
+                description: income.description.clone(),
+                currency_rate, currency_rate_source,
+                amount: Cash::new(CURRENCY, income.local_amount),
+                paid_tax: Cash::new(CURRENCY, income.local_paid_tax),
+                deduction: Cash::new(CURRENCY, income.deduction.amount),
+            });
+
+            for totals in [groups.entry((code, country)).or_default(), &mut total] {
+                totals.records += 1;
+                totals.amount += income.local_amount;
+                totals.paid_tax += income.local_paid_tax;
+                totals.deduction += income.deduction.amount;
+            }
+        }
+
+        if records_table.is_empty() {
+            println!("There are no records to add to the tax statement.");
+            return Ok(());
+        }
+
+        for ((code, country), totals) in groups {
+            totals_table.add_row(TotalsRow {
+                code, country,
+                records: totals.records,
+                amount: Cash::new(CURRENCY, totals.amount),
+                paid_tax: Cash::new(CURRENCY, totals.paid_tax),
+                deduction: Cash::new(CURRENCY, totals.deduction),
+            });
+        }
+
+        let mut row = totals_table.add_empty_row();
+        row.set_records(total.records);
+        row.set_amount(Cash::new(CURRENCY, total.amount));
+        row.set_paid_tax(Cash::new(CURRENCY, total.paid_tax));
+        row.set_deduction(Cash::new(CURRENCY, total.deduction));
+
+        records_table.print("Записи к добавлению в налоговую декларацию");
+        totals_table.print("Итоги по кодам доходов и странам");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+use easy_logging::GlobalContext;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections, StockSellType};
+use crate::config::{Config, PortfolioConfig};
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::db;
+use crate::formatting::table::Cell;
+use crate::localities::Country;
+use crate::taxes::IncomeType;
+use crate::taxes::long_term_ownership::LtoDeductionCalculator;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::types::Decimal;
+
+#[derive(Default)]
+struct YearIncome {
+    trading: Decimal,
+    dividends: Decimal,
+    interest: Decimal,
+}
+
+impl YearIncome {
+    // Trading losses may be offset only against trading income, so they don't reduce dividends and interest
+    fn tax_base(&self) -> Decimal {
+        std::cmp::max(dec!(0), self.trading) + self.dividends + self.interest
+    }
+}
+
+/// Aggregates taxable investment income per year from all (or the specified) portfolios and shows which tax bracket
+/// of the progressive tax rate it falls into.
+pub fn show_tax_rates(
+    config: &Config, portfolio_name: Option<&str>, year: Option<i32>,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let country = config.get_tax_country();
+    let mut telemetry = TelemetryRecordBuilder::new();
+
+    let portfolios: Vec<&PortfolioConfig> = match portfolio_name {
+        Some(name) => vec![config.get_portfolio(name)?],
+        None => config.portfolios.iter().filter(|portfolio| portfolio.statements.is_some()).collect(),
+    };
+    if portfolios.is_empty() {
+        return Err!("There is no any portfolio with broker statements defined in the configuration file");
+    }
+
+    let database = db::connect(&config.db_path)?;
+    let converter = CurrencyConverter::new(database, None, true);
+
+    let multiple = portfolios.len() > 1;
+    let mut income = BTreeMap::new();
+
+    for portfolio in portfolios {
+        let _logging_context = multiple.then(|| GlobalContext::new(&portfolio.name));
+        telemetry.add_broker(portfolio.broker);
+        process_portfolio(&country, config, portfolio, &converter, &mut income)?;
+    }
+
+    if let Some(year) = year {
+        income.retain(|&income_year, _| income_year == year);
+    }
+
+    if income.is_empty() {
+        return Err!("There is no any taxable income");
+    }
+
+    print(&country, config, &income);
+    Ok(telemetry)
+}
+
+fn process_portfolio(
+    country: &Country, config: &Config, portfolio: &PortfolioConfig, converter: &CurrencyConverter,
+    income: &mut BTreeMap<i32, YearIncome>,
+) -> EmptyResult {
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let statement = BrokerStatement::read(
+        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
+        &portfolio.corporate_actions, ReadingStrictness::TRADE_SETTLE_DATE | ReadingStrictness::TAX_EXEMPTIONS,
+        StatementSections::all())?;
+
+    let tax_payment_day = portfolio.tax_payment_day();
+    let mut lto_calculators: BTreeMap<i32, LtoDeductionCalculator> = BTreeMap::new();
+
+    for trade in &statement.stock_sells {
+        if matches!(trade.type_, StockSellType::CorporateAction) {
+            continue;
+        }
+
+        let (year, _) = tax_payment_day.get(trade.execution_date, true);
+        let instrument = statement.instrument_info.get_or_empty(&trade.symbol);
+        let details = trade.calculate(country, &instrument, &portfolio.tax_exemptions, converter)?;
+
+        income.entry(year).or_default().trading += details.taxable_local_profit.amount;
+
+        for buy_trade in &details.fifo {
+            if let Some(ref deductible) = buy_trade.long_term_ownership_deductible {
+                lto_calculators.entry(year).or_insert_with(LtoDeductionCalculator::new)
+                    .add(deductible.profit, deductible.years, false);
+            }
+        }
+    }
+
+    for (year, calculator) in lto_calculators {
+        income.entry(year).or_default().trading -= calculator.calculate().deduction;
+    }
+
+    for dividend in &statement.dividends {
+        let amount = converter.convert_to_rounding(dividend.date, dividend.amount, country.currency)?;
+        income.entry(dividend.date.year()).or_default().dividends += amount;
+    }
+
+    let other_income: Vec<_> = statement.securities_lending_income().chain(statement.repo_income()).collect();
+    for interest in statement.idle_cash_interest.iter().chain(&other_income) {
+        let amount = converter.convert_to_rounding(interest.date, interest.amount, country.currency)?;
+        income.entry(interest.date.year()).or_default().interest += amount;
+    }
+
+    for coupon in &statement.coupons {
+        let amount = converter.convert_to_rounding(coupon.date, coupon.amount, country.currency)?;
+        income.entry(coupon.date.year()).or_default().interest += amount;
+    }
+
+    Ok(())
+}
+
+fn print(country: &Country, config: &Config, income: &BTreeMap<i32, YearIncome>) {
+    let mut table = Table::new();
+
+    for (&year, year_income) in income {
+        let total = year_income.tax_base();
+
+        // Until 2025 income from employment and investment income had a single tax base
+        let other_income = if year < 2025 {
+            config.taxes.external_income.get(&year).copied()
+        } else {
+            None
+        };
+
+        let mut tax_rate = country.tax_rate(year);
+        let tax = tax_rate.tax(IncomeType::Trading, total);
+        let bracket = tax_rate.bracket(IncomeType::Trading);
+
+        table.add_row(Row {
+            year: year,
+            trading: country.cash(year_income.trading),
+            dividends: country.cash(year_income.dividends),
+            interest: country.cash(year_income.interest),
+            other_income: other_income.map(|income| country.cash(income)),
+            tax_base: country.cash(total),
+            rate: Cell::new_ratio(bracket.rate),
+            remaining: bracket.remaining.map(|remaining| country.cash(remaining)),
+            tax: country.cash(tax),
+        });
+    }
+
+    table.print("Tax brackets");
+}
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Year")]
+    year: i32,
+    #[column(name="Trading")]
+    trading: Cash,
+    #[column(name="Dividends")]
+    dividends: Cash,
+    #[column(name="Interest")]
+    interest: Cash,
+    #[column(name="Other income")]
+    other_income: Option<Cash>,
+    #[column(name="Tax base")]
+    tax_base: Cash,
+    #[column(name="Marginal rate")]
+    rate: Cell,
+    #[column(name="Bracket room")]
+    remaining: Option<Cash>,
+    #[column(name="Tax")]
+    tax: Cash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tax_base() {
+        let income = YearIncome {trading: dec!(1000), dividends: dec!(200), interest: dec!(30)};
+        assert_eq!(income.tax_base(), dec!(1230));
+
+        let income = YearIncome {trading: dec!(-1000), dividends: dec!(200), interest: dec!(30)};
+        assert_eq!(income.tax_base(), dec!(230));
+    }
+}
@@ -16,17 +16,19 @@ use crate::currency::converter::CurrencyConverter;
 use crate::formatting::{self, table::Cell};
 use crate::localities::{Country, Jurisdiction};
 use crate::tax_statement::statement::CountryCode;
-use crate::taxes::{IncomeType, Tax, TaxCalculator, TaxPaymentDaySpec};
+use crate::taxes::{IncomeType, LossCarryforward, SecuritiesMarket, Tax, TaxCalculator, TaxPaymentDaySpec};
 use crate::taxes::long_term_ownership::LtoDeductionCalculator;
 use crate::time::{self, Date};
 use crate::trades::{self, RealProfit};
 use crate::types::Decimal;
 
+use super::report::TaxReport;
 use super::statement::TaxStatement;
 
 pub fn process_income(
     country: &Country, portfolio: &PortfolioConfig, broker_statement: &BrokerStatement, year: Option<i32>,
     tax_calculator: &mut TaxCalculator, tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
+    loss_carryforward: Option<&mut LossCarryforward>, report: Option<&mut TaxReport>,
 ) -> GenericResult<(Cash, bool, bool)> {
     let mut processor = TradesProcessor {
         portfolio,
@@ -35,10 +37,12 @@ pub fn process_income(
 
         country,
         converter,
+        loss_carryforward,
 
         trades_table: TradesTable::new(),
         fifo_table: FifoTable::new(),
         lto_table: LtoTable::new(),
+        losses_table: LossesTable::new(),
 
         same_dates: true,
         same_currency: true,
@@ -53,14 +57,21 @@ pub fn process_income(
         tax_year_stat: BTreeMap::new(),
     };
 
+    let has_tax_statement = tax_statement.is_some();
     processor.process_trades(tax_calculator, tax_statement)?;
 
     let totals = processor.process_totals(tax_calculator)?;
+    if has_tax_statement && !processor.losses_table.is_empty() {
+        warn!(concat!(
+            "Carried forward losses aren't added to the tax statement (it's not supported yet) - ",
+            "declare them manually."));
+    }
+
     let has_income = processor.has_income;
     let has_income_to_declare = processor.has_income_to_declare;
 
     if !processor.trades_table.is_empty() {
-        processor.print(&totals);
+        processor.print(&totals, report);
     }
 
     Ok((totals.tax_to_pay, has_income, has_income_to_declare))
@@ -73,10 +84,12 @@ struct TradesProcessor<'a> {
 
     country: &'a Country,
     converter: &'a CurrencyConverter,
+    loss_carryforward: Option<&'a mut LossCarryforward>,
 
     trades_table: TradesTable,
     fifo_table: FifoTable,
     lto_table: LtoTable,
+    losses_table: LossesTable,
 
     same_dates: bool,
     same_currency: bool,
@@ -193,7 +206,8 @@ impl<'a> TradesProcessor<'a> {
             let instrument = self.broker_statement.instrument_info.get_or_empty(&trade.symbol);
             let details = trade.calculate(self.country, &instrument, &self.portfolio.tax_exemptions, self.converter)?;
             let estimated_tax = details.estimate_tax(tax_calculator, tax_year);
-            self.process_trade(trade_id, trade, &details, &estimated_tax)?;
+            let market = SecuritiesMarket::of(&instrument);
+            self.process_trade(trade_id, trade, &details, &estimated_tax, market)?;
             trade_id += 1;
 
             match broker_jurisdiction {
@@ -226,7 +240,10 @@ impl<'a> TradesProcessor<'a> {
         Ok(())
     }
 
-    fn process_trade(&mut self, trade_id: usize, trade: &StockSell, details: &SellDetails, estimated_tax: &Tax) -> EmptyResult {
+    fn process_trade(
+        &mut self, trade_id: usize, trade: &StockSell, details: &SellDetails, estimated_tax: &Tax,
+        market: SecuritiesMarket,
+    ) -> EmptyResult {
         let security = self.broker_statement.instrument_info.get_name(&trade.original_symbol);
         let (price, commission) = match trade.type_ {
             StockSellType::Trade {price, commission, ..} => (price, commission),
@@ -263,6 +280,10 @@ impl<'a> TradesProcessor<'a> {
             tax_year.profit.deposit(details.profit);
             tax_year.local_profit += details.local_profit;
             tax_year.taxable_local_profit += details.taxable_local_profit;
+
+            if market == SecuritiesMarket::Unorganized {
+                tax_year.unorganized_taxable_local_profit += details.taxable_local_profit.amount;
+            }
         }
 
         self.has_income = true;
@@ -432,6 +453,22 @@ impl<'a> TradesProcessor<'a> {
         let local_currency = self.country.currency;
         let tax_payment_day = self.portfolio.tax_payment_day();
 
+        if let Some(ref mut loss_carryforward) = self.loss_carryforward {
+            // Losses are consumed by profit of the subsequent years, so years without trades must be registered too
+            let first_year = self.tax_year.or_else(|| self.tax_year_stat.keys().next().copied());
+            let last_year = self.tax_year.or_else(|| self.tax_year_stat.keys().next_back().copied());
+
+            if let (Some(first_year), Some(last_year)) = (first_year, last_year) {
+                for year in first_year..=last_year {
+                    if !self.tax_year_stat.contains_key(&year) {
+                        for market in SecuritiesMarket::ALL {
+                            loss_carryforward.register(year, market, dec!(0));
+                        }
+                    }
+                }
+            }
+        }
+
         for (&year, stat) in &mut self.tax_year_stat {
             let lto = stat.lto_calculator.take().unwrap().calculate();
             if !lto.deduction.is_zero() {
@@ -442,6 +479,26 @@ impl<'a> TradesProcessor<'a> {
                     limit: Cash::new(local_currency, lto.limit),
                 });
             }
+
+            if let Some(ref mut loss_carryforward) = self.loss_carryforward {
+                let unorganized = stat.unorganized_taxable_local_profit;
+                let organized = stat.taxable_local_profit.amount - unorganized;
+
+                for (market, taxable_profit) in [
+                    (SecuritiesMarket::Organized, organized),
+                    (SecuritiesMarket::Unorganized, unorganized),
+                ] {
+                    let deduction = loss_carryforward.apply(year, market, taxable_profit)?;
+                    if !deduction.is_zero() {
+                        stat.taxable_local_profit.amount -= deduction;
+                        self.losses_table.add_row(LossRow {
+                            year,
+                            market: market.description(),
+                            deduction: Cash::new(local_currency, deduction),
+                        });
+                    }
+                }
+            }
         }
 
         let mut total_local_profit = Cash::zero(local_currency);
@@ -501,7 +558,7 @@ impl<'a> TradesProcessor<'a> {
         })
     }
 
-    fn print(mut self, totals: &Totals) {
+    fn print(mut self, totals: &Totals, report: Option<&mut TaxReport>) {
         if self.same_dates {
             self.trades_table.hide_execution_date();
             self.trades_table.rename_conclusion_currency_rate("Курс руб.");
@@ -529,7 +586,7 @@ impl<'a> TradesProcessor<'a> {
         if !self.stock_splits {
             self.fifo_table.hide_multiplier();
         }
-        if !self.tax_exemptions && !self.long_term_ownership {
+        if !self.tax_exemptions && !self.long_term_ownership && self.losses_table.is_empty() {
             self.trades_table.hide_taxable_local_profit();
             self.trades_table.hide_tax_deduction();
         }
@@ -559,16 +616,37 @@ impl<'a> TradesProcessor<'a> {
             totals_row.set_real_local_profit_ratio(real.local_profit_ratio.map(Cell::new_ratio));
         }
 
-        self.trades_table.print(&format!(
+        let title = format!(
             "Расчет прибыли от продажи ценных бумаг, полученной через {}",
-            self.broker_statement.broker.name));
+            self.broker_statement.broker.name);
+        self.trades_table.print(&title);
 
+        let fifo_title = "Детализация расчета сделок по ФИФО";
         if !self.fifo_table.is_empty() {
-            self.fifo_table.print("Детализация расчета сделок по ФИФО");
+            self.fifo_table.print(fifo_title);
         }
 
+        let lto_title = "Льгота на долгосрочное владение ценными бумагами";
         if !self.lto_table.is_empty() {
-            self.lto_table.print("Льгота на долгосрочное владение ценными бумагами");
+            self.lto_table.print(lto_title);
+        }
+
+        let losses_title = "Перенос убытков прошлых лет";
+        if !self.losses_table.is_empty() {
+            self.losses_table.print(losses_title);
+        }
+
+        if let Some(report) = report {
+            report.add("Сделки", &title, self.trades_table.table, Some(totals.tax_to_pay));
+            if !self.fifo_table.is_empty() {
+                report.add("ФИФО", fifo_title, self.fifo_table.table, None);
+            }
+            if !self.lto_table.is_empty() {
+                report.add("ЛДВ", lto_title, self.lto_table.table, None);
+            }
+            if !self.losses_table.is_empty() {
+                report.add("Убытки", losses_title, self.losses_table.table, None);
+            }
         }
     }
 
@@ -588,6 +666,8 @@ impl<'a> TradesProcessor<'a> {
                 local_profit: zero,
                 taxable_local_profit: zero,
 
+                unorganized_taxable_local_profit: dec!(0),
+
                 deductible_fees: None,
                 lto_calculator: Some(LtoDeductionCalculator::new()),
             }
@@ -620,6 +700,8 @@ struct TaxYearStat {
     profit: MultiCurrencyCashAccount,
     local_profit: Cash,
     taxable_local_profit: Cash,
+    // Broker fees and long-term ownership deduction are accounted in the organized securities market result
+    unorganized_taxable_local_profit: Decimal,
 
     deductible_fees: Option<Decimal>,
     lto_calculator: Option<LtoDeductionCalculator>,
@@ -730,4 +812,15 @@ struct LtoRow {
     deduction: Cash,
     #[column(name="Лимит")]
     limit: Cash,
-}
\ No newline at end of file
+}
+
+#[derive(StaticTable)]
+#[table(name="LossesTable")]
+struct LossRow {
+    #[column(name="Год")]
+    year: i32,
+    #[column(name="Ценные бумаги")]
+    market: &'static str,
+    #[column(name="Учтенный убыток")]
+    deduction: Cash,
+}
@@ -0,0 +1,334 @@
+use chrono::Datelike;
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementSections, StockSellType};
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::db;
+use crate::formatting;
+use crate::instruments::{self, IssuerTaxationType};
+use crate::localities::Jurisdiction;
+use crate::taxes::{IncomeType, TaxCalculator};
+use crate::taxes::long_term_ownership::LtoDeductionCalculator;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::{self, Date};
+
+struct Summary {
+    year: i32,
+    statement_end: Date,
+    tax_agent: bool,
+
+    trading_revenue: Cash,
+    trading_profit: Cash,
+    trading_taxable_profit: Cash,
+    trading_tax: Cash,
+
+    dividends: Cash,
+    dividends_paid_tax: Cash,
+    dividends_tax_to_pay: Cash,
+
+    expected_withholding: Cash,
+    withheld_tax: Cash,
+
+    income_to_declare: Cash,
+    tax_to_pay: Cash,
+
+    unused_lto_limit: Option<Cash>,
+}
+
+/// Aggregates everything needed to close the tax year: realized profit, dividends, broker tax withholdings,
+/// income to declare, unused long-term ownership deduction limit and a checklist of actions with deadlines.
+pub fn generate_year_end_report(config: &Config, portfolio_name: &str, year: i32) -> GenericResult<TelemetryRecordBuilder> {
+    let country = config.get_tax_country();
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let mut statement = BrokerStatement::read(
+        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions, &portfolio.corporate_actions,
+        ReadingStrictness::TRADE_SETTLE_DATE | ReadingStrictness::OTC_INSTRUMENTS | ReadingStrictness::TAX_EXEMPTIONS |
+        ReadingStrictness::GRANTS, StatementSections::all())?;
+    instruments::reference::enrich(config, &mut statement.instrument_info);
+    let period = statement.check_period_against_tax_year(year)?;
+
+    let database = db::connect(&config.db_path)?;
+    let converter = CurrencyConverter::new(database, None, true);
+    let mut tax_calculator = TaxCalculator::new(country.clone());
+
+    let tax_agent = statement.broker.type_.jurisdiction() == Jurisdiction::Russia;
+    let tax_payment_day = portfolio.tax_payment_day();
+    let zero = Cash::zero(country.currency);
+
+    let mut trading_revenue = zero;
+    let mut trading_profit = zero;
+    let mut trading_taxable_profit = zero;
+    let mut lto_calculator: Option<LtoDeductionCalculator> = None;
+
+    for trade in &statement.stock_sells {
+        if matches!(trade.type_, StockSellType::CorporateAction) {
+            continue;
+        }
+
+        if tax_payment_day.get(trade.execution_date, true).0 != year {
+            continue;
+        }
+
+        let instrument = statement.instrument_info.get_or_empty(&trade.symbol);
+        let details = trade.calculate(&country, &instrument, &portfolio.tax_exemptions, &converter)?;
+
+        trading_revenue += details.local_revenue;
+        trading_profit += details.local_profit;
+        trading_taxable_profit += details.taxable_local_profit;
+
+        for buy_trade in &details.fifo {
+            if let Some(ref deductible) = buy_trade.long_term_ownership_deductible {
+                let calculator = lto_calculator.get_or_insert_with(LtoDeductionCalculator::new);
+                calculator.add(deductible.profit, deductible.years, false);
+            }
+        }
+    }
+
+    let unused_lto_limit = lto_calculator.map(|calculator| {
+        let lto = calculator.calculate();
+        trading_taxable_profit.amount -= lto.deduction;
+        Cash::new(country.currency, lto.limit - lto.deduction)
+    });
+
+    let trading_tax = tax_calculator.tax_deductible_income(
+        IncomeType::Trading, year, trading_profit, trading_taxable_profit).to_pay;
+
+    let mut dividends = zero;
+    let mut dividends_paid_tax = zero;
+    let mut dividends_tax_to_pay = zero;
+
+    let mut expected_withholding = if tax_agent {
+        trading_tax
+    } else {
+        zero
+    };
+
+    let (mut income_to_declare, mut tax_to_pay) = if tax_agent {
+        (zero, zero)
+    } else {
+        (trading_profit.max(zero), trading_tax)
+    };
+
+    for dividend in statement.dividends.iter().filter(|dividend| dividend.date.year() == year) {
+        let amount = converter.convert_to_cash_rounding(dividend.date, dividend.amount, country.currency)?;
+        let tax = dividend.tax(&country, &converter, &mut tax_calculator)?;
+
+        dividends += amount;
+        dividends_paid_tax += tax.paid;
+        dividends_tax_to_pay += tax.to_pay;
+
+        match dividend.taxation_type {
+            IssuerTaxationType::TaxAgent {..} if tax_agent => expected_withholding += tax.to_pay,
+            _ => {
+                income_to_declare += amount;
+                tax_to_pay += tax.to_pay;
+            },
+        }
+    }
+
+    let mut withheld_tax = zero;
+    for (withholding_year, withholding) in statement.tax_agent_withholdings.calculate()? {
+        if withholding_year == year {
+            withheld_tax += converter.convert_to_cash_rounding(period.last_date(), withholding, country.currency)?;
+        }
+    }
+
+    let summary = Summary {
+        year,
+        statement_end: statement.period.last_date(),
+        tax_agent,
+
+        trading_revenue, trading_profit, trading_taxable_profit, trading_tax,
+        dividends, dividends_paid_tax, dividends_tax_to_pay,
+        expected_withholding, withheld_tax,
+        income_to_declare, tax_to_pay,
+
+        unused_lto_limit,
+    };
+
+    summary.print();
+    print_checklist(checklist(&summary, time::today()));
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+#[derive(StaticTable)]
+#[table(name="SummaryTable")]
+struct SummaryRow {
+    #[column(name="Item")]
+    name: &'static str,
+    #[column(name="Amount")]
+    amount: Cash,
+}
+
+impl Summary {
+    fn print(&self) {
+        let mut table = SummaryTable::new();
+        let mut add = |name, amount: Cash| {
+            table.add_row(SummaryRow {name, amount: amount.round()});
+        };
+
+        add("Trading revenue", self.trading_revenue);
+        add("Realized profit", self.trading_profit);
+        add("Taxable profit", self.trading_taxable_profit);
+        add("Trading tax", self.trading_tax);
+
+        add("Dividends", self.dividends);
+        add("Dividends tax paid", self.dividends_paid_tax);
+        add("Dividends tax to pay", self.dividends_tax_to_pay);
+
+        if self.tax_agent {
+            add("Expected broker withholding", self.expected_withholding);
+            add("Withheld by broker", self.withheld_tax);
+        }
+
+        add("Income to declare", self.income_to_declare);
+        add("Tax to pay by declaration", self.tax_to_pay);
+
+        if let Some(limit) = self.unused_lto_limit {
+            add("Unused LTO deduction limit", limit);
+        }
+
+        table.print(&format!("Year-end summary for {}", self.year));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Action {
+    deadline: Date,
+    description: String,
+}
+
+fn checklist(summary: &Summary, today: Date) -> Vec<Action> {
+    let year_end = date!(summary.year, 12, 31);
+    let mut actions = Vec::new();
+
+    let mut add = |deadline, description: String| {
+        actions.push(Action {deadline, description});
+    };
+
+    if summary.statement_end < year_end {
+        add(date!(summary.year + 1, 1, 31), format!(
+            "Download broker statement which covers the whole {} year", summary.year));
+    }
+
+    if let Some(limit) = summary.unused_lto_limit {
+        if today <= year_end && limit.is_positive() {
+            add(year_end, format!(
+                "Consider realizing up to {} more of long-term ownership eligible profit tax-free", limit.round()));
+        }
+    }
+
+    if !summary.tax_agent {
+        add(date!(summary.year + 1, 6, 1), s!("Report foreign account cash flows to tax inspection"));
+    }
+
+    if summary.income_to_declare.is_positive() {
+        add(date!(summary.year + 1, 4, 30), format!(
+            "File 3-NDFL tax statement declaring {} of income", summary.income_to_declare.round()));
+
+        if summary.tax_to_pay.is_positive() {
+            add(date!(summary.year + 1, 7, 15), format!(
+                "Pay {} of tax for the declared income", summary.tax_to_pay.round()));
+        }
+    }
+
+    if summary.tax_agent && summary.withheld_tax < summary.expected_withholding {
+        add(date!(summary.year + 1, 12, 1), format!(
+            "Pay {} of tax not withheld by the broker according to tax inspection notice",
+            (summary.expected_withholding - summary.withheld_tax).round()));
+    }
+
+    actions.sort_by_key(|action| action.deadline);
+    actions
+}
+
+#[derive(StaticTable)]
+#[table(name="ChecklistTable")]
+struct ChecklistRow {
+    #[column(name="Deadline")]
+    deadline: String,
+    #[column(name="Action")]
+    action: String,
+}
+
+fn print_checklist(actions: Vec<Action>) {
+    if actions.is_empty() {
+        println!("Nothing to do for the tax year.");
+        return;
+    }
+
+    let mut table = ChecklistTable::new();
+    for action in actions {
+        table.add_row(ChecklistRow {
+            deadline: formatting::format_date(action.deadline),
+            action: action.description,
+        });
+    }
+    table.print("Checklist");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(tax_agent: bool) -> Summary {
+        let cash = |amount| Cash::new("RUB", amount);
+
+        Summary {
+            year: 2023,
+            statement_end: date!(2023, 11, 30),
+            tax_agent,
+
+            trading_revenue: cash(dec!(1_000_000)),
+            trading_profit: cash(dec!(100_000)),
+            trading_taxable_profit: cash(dec!(100_000)),
+            trading_tax: cash(dec!(13_000)),
+
+            dividends: cash(dec!(10_000)),
+            dividends_paid_tax: cash(dec!(1_000)),
+            dividends_tax_to_pay: cash(dec!(300)),
+
+            expected_withholding: if tax_agent { cash(dec!(13_000)) } else { cash(dec!(0)) },
+            withheld_tax: cash(dec!(10_000)),
+
+            income_to_declare: if tax_agent { cash(dec!(10_000)) } else { cash(dec!(110_000)) },
+            tax_to_pay: if tax_agent { cash(dec!(300)) } else { cash(dec!(13_300)) },
+
+            unused_lto_limit: Some(cash(dec!(2_900_000))),
+        }
+    }
+
+    #[test]
+    fn checklist_generation() {
+        let deadlines = |summary, today| -> Vec<_> {
+            checklist(&summary, today).into_iter().map(|action| action.deadline).collect()
+        };
+
+        assert_eq!(deadlines(summary(true), date!(2023, 12, 15)), vec![
+            date!(2023, 12, 31),
+            date!(2024, 1, 31),
+            date!(2024, 4, 30),
+            date!(2024, 7, 15),
+            date!(2024, 12, 1),
+        ]);
+
+        assert_eq!(deadlines(summary(false), date!(2024, 1, 15)), vec![
+            date!(2024, 1, 31),
+            date!(2024, 4, 30),
+            date!(2024, 6, 1),
+            date!(2024, 7, 15),
+        ]);
+
+        let actions = checklist(&summary(true), date!(2024, 1, 15));
+        assert_eq!(actions.last().unwrap().description, format!(
+            "Pay {} of tax not withheld by the broker according to tax inspection notice",
+            Cash::new("RUB", dec!(3000))));
+    }
+}
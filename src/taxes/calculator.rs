@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use crate::core::GenericResult;
 use crate::currency::Cash;
 use crate::localities::{Country, Jurisdiction};
+use crate::profiling::{self, Phase};
 use crate::taxes::{self, IncomeType, TaxRate};
 
 pub struct Tax {
@@ -32,11 +33,13 @@ impl TaxCalculator {
     // Attention: Modifies calculator state. Must be called only for income that won't be decreased later via deductions
     // or looses balancing.
     pub fn tax_income(&mut self, income_type: IncomeType, year: i32, income: Cash, paid_tax: Option<Cash>) -> Tax {
+        let _timer = profiling::start(Phase::Taxes);
         calculate(self.country.jurisdiction, self.year(year), income_type, income, paid_tax)
     }
 
     // Intended for dividends, tax for which was withheld by tax agent.
     pub fn tax_agent_income(&mut self, income_type: IncomeType, year: i32, income: Cash, mut paid_tax: Cash) -> GenericResult<Tax> {
+        let _timer = profiling::start(Phase::Taxes);
         if paid_tax.currency != self.country.currency {
             return Err!("Got withheld tax in an unexpected currency: {}", paid_tax.currency)
         }
@@ -80,6 +83,7 @@ impl TaxCalculator {
     // Attention: Modifies calculator state. Must be called only for income that won't be decreased later via deductions
     // or looses balancing.
     pub fn tax_deductible_income(&mut self, income_type: IncomeType, year: i32, income: Cash, taxable_income: Cash) -> Tax {
+        let _timer = profiling::start(Phase::Taxes);
         let country = self.country.jurisdiction;
 
         let calc = self.year(year);
@@ -103,6 +107,7 @@ impl TaxCalculator {
     // Attention: Always operates on clean calculator state. Intended for intermediate calculations during stock selling
     // processing which are processed before any looses balancing.
     pub fn tax_deductible_income_dry_run(&self, income_type: IncomeType, year: i32, income: Cash, taxable_income: Cash) -> Tax {
+        let _timer = profiling::start(Phase::Taxes);
         let country = self.country.jurisdiction;
 
         let mut full_calc = self.country.tax_rate(year);
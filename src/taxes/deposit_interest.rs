@@ -0,0 +1,81 @@
+// Russian tax on bank deposit interest (Article 214.2 of the Tax Code of the Russian Federation)
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use log::warn;
+
+use crate::localities::{self, Country, Jurisdiction};
+use crate::types::Decimal;
+
+use super::IncomeType;
+
+/// Starting from 2023 Russia taxes yearly interest from all bank deposits above the tax-free amount which depends on
+/// the CBR key rate.
+pub struct DepositInterestTax {
+    country: Country,
+    tax_free_amounts: BTreeMap<i32, Decimal>,
+}
+
+impl DepositInterestTax {
+    /// Returns `None` if deposit interest in the specified currency isn't taxed in the country: the tax-free amount is
+    /// specified in local currency, so foreign currency interest must be converted to it by the caller.
+    ///
+    /// Interest of the years with unknown tax-free amount isn't taxed with a warning.
+    pub fn new(country: &Country, currency: &str, years: RangeInclusive<i32>) -> Option<DepositInterestTax> {
+        if country.jurisdiction != Jurisdiction::Russia || currency != country.currency {
+            return None;
+        }
+
+        let mut tax_free_amounts = BTreeMap::new();
+
+        for year in years {
+            match localities::russian_deposit_interest_tax_free_amount(year) {
+                Ok(Some(amount)) => {
+                    tax_free_amounts.insert(year, amount);
+                },
+                Ok(None) => {},
+                Err(e) => warn!("{}. Deposit interest tax won't be calculated for {}.", e, year),
+            }
+        }
+
+        Some(DepositInterestTax {
+            country: country.clone(),
+            tax_free_amounts,
+        })
+    }
+
+    pub fn tax(&self, year: i32, interest: Decimal) -> Decimal {
+        match self.tax_free_amounts.get(&year) {
+            Some(&tax_free_amount) if interest > tax_free_amount => {
+                self.country.tax_rate(year).tax(IncomeType::Interest, interest - tax_free_amount)
+            },
+            _ => dec!(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(year, interest, expected,
+        case(2022, "500_000",      "0"),
+        case(2023, "150_000",      "0"),
+        case(2023, "250_000", "13_000"),
+        case(2026, "260_000", "13_000"),
+        case(2100, "500_000",      "0"),
+    )]
+    fn tax(year: i32, interest: &str, expected: &str) {
+        let country = localities::russia(&Default::default());
+        let tax = DepositInterestTax::new(&country, "RUB", 2022..=2100).unwrap();
+        assert_eq!(tax.tax(year, interest.parse().unwrap()), expected.parse().unwrap());
+    }
+
+    #[test]
+    fn foreign_currency() {
+        let country = localities::russia(&Default::default());
+        assert!(DepositInterestTax::new(&country, "USD", 2023..=2023).is_none());
+    }
+}
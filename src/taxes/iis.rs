@@ -0,0 +1,147 @@
+// Individual Investment Account (ИИС) type A tax deduction logic (Article 219.1 of the Tax Code of the Russian
+// Federation)
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Months};
+use itertools::Itertools;
+use log::warn;
+
+use crate::core::GenericResult;
+use crate::currency::CashAssets;
+use crate::formatting;
+use crate::time::Date;
+use crate::types::Decimal;
+
+const DEDUCTION_RATE: Decimal = dec!(0.13);
+const MAX_YEARLY_DEPOSITS: Decimal = dec!(400_000);
+
+// The account must be held for the minimum term, otherwise the received deductions must be returned
+fn get_min_ownership_years(opening_date: Date) -> u32 {
+    match opening_date.year() {
+        // IIS types A and B which can't be opened since 2024
+        ..=2023 => 3,
+        // IIS type 3: 5 years for accounts opened in 2024-2026, then the term increases by a year up to 10 years for
+        // accounts opened since 2031
+        year => std::cmp::min(5 + std::cmp::max(year - 2026, 0), 10) as u32,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct IisDeduction {
+    pub year: i32,
+    pub deposits: Decimal,
+    pub deduction: Decimal,
+    // The deduction can't exceed personal income tax paid for the year, so if it's unknown, the deduction is the
+    // maximum one
+    pub limited: bool,
+}
+
+impl IisDeduction {
+    /// Calculates yearly deductions for the specified IIS deposits and withdrawals (in RUB). Early withdrawal is an
+    /// error in strict mode (tax calculations) and a warning otherwise.
+    ///
+    /// The deductions are limited by the specified personal income tax paid for the year. If it's not specified for
+    /// the year, the deduction is the maximum possible one.
+    ///
+    /// Please note that the deduction limit is per taxpayer, not per account, so it's calculated incorrectly when the
+    /// taxpayer has several accounts (which is possible since 2024).
+    pub fn calculate(
+        deposits_and_withdrawals: &[CashAssets], paid_taxes: &BTreeMap<i32, Decimal>, strict: bool,
+    ) -> GenericResult<Vec<IisDeduction>> {
+        let mut deposits = BTreeMap::new();
+        let mut opening_date: Option<Date> = None;
+
+        for assets in deposits_and_withdrawals.iter().sorted_by_key(|assets| assets.date) {
+            if assets.cash.currency != "RUB" {
+                return Err!("Got an unexpected IIS deposit in {}: only RUB deposits are allowed", assets.cash.currency);
+            }
+
+            if assets.cash.is_positive() {
+                opening_date.get_or_insert(assets.date);
+                *deposits.entry(assets.date.year()).or_default() += assets.cash.amount;
+                continue;
+            }
+
+            if let Some(opening_date) = opening_date {
+                let min_ownership_years = get_min_ownership_years(opening_date);
+                let min_closing_date = opening_date.checked_add_months(Months::new(min_ownership_years * 12))
+                    .ok_or("Got an invalid IIS opening date")?;
+
+                if assets.date < min_closing_date {
+                    let message = format!(concat!(
+                        "Got a withdrawal from IIS on {} which is earlier than {} years after the account opening: ",
+                        "the account has to be closed and the received tax deductions returned, which isn't supported"),
+                        formatting::format_date(assets.date), min_ownership_years);
+
+                    if strict {
+                        return Err(message.into());
+                    }
+                    warn!("{}.", message);
+                }
+            }
+        }
+
+        Ok(deposits.into_iter().map(|(year, deposits): (i32, Decimal)| {
+            let mut deduction = (std::cmp::min(deposits, MAX_YEARLY_DEPOSITS) * DEDUCTION_RATE).round_dp(2);
+
+            let paid_tax = paid_taxes.get(&year).cloned();
+            if let Some(paid_tax) = paid_tax {
+                deduction = std::cmp::min(deduction, paid_tax);
+            }
+
+            IisDeduction {year, deposits, deduction, limited: paid_tax.is_some()}
+        }).collect())
+    }
+
+    /// Expected date of receiving the deduction: it may be claimed in the tax statement for the year and is paid out
+    /// by tax inspection within about four months after filing.
+    pub fn date(&self) -> Date {
+        date!(self.year + 1, 4, 30)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(deposits, expected,
+        case(&[(date!(2020, 3, 1), dec!(100_000))], &[(2020, dec!(100_000), dec!(13_000))]),
+        case(&[(date!(2022, 3, 1), dec!(100_000))], &[(2022, dec!(100_000), dec!(10_000))]),
+        case(&[(date!(2022, 3, 1), dec!(50_000))], &[(2022, dec!(50_000), dec!(6_500))]),
+        case(&[(date!(2020, 3, 1), dec!(300_000)), (date!(2020, 12, 1), dec!(300_000))],
+             &[(2020, dec!(600_000), dec!(52_000))]),
+        case(&[(date!(2020, 3, 1), dec!(1_000.55)), (date!(2021, 1, 1), dec!(500_000))],
+             &[(2020, dec!(1_000.55), dec!(130.07)), (2021, dec!(500_000), dec!(52_000))]),
+        case(&[(date!(2020, 3, 1), dec!(100_000)), (date!(2023, 3, 1), dec!(-130_000))],
+             &[(2020, dec!(100_000), dec!(13_000))]),
+    )]
+    fn calculation(deposits: &[(Date, Decimal)], expected: &[(i32, Decimal, Decimal)]) {
+        let deposits: Vec<_> = deposits.iter().map(|&(date, amount)| CashAssets::new(date, "RUB", amount)).collect();
+        let expected: Vec<_> = expected.iter().map(|&(year, deposits, deduction)| IisDeduction {
+            year, deposits, deduction, limited: year == 2022,
+        }).collect();
+
+        let paid_taxes = btreemap!{2022 => dec!(10_000)};
+        assert_eq!(IisDeduction::calculate(&deposits, &paid_taxes, true).unwrap(), expected);
+    }
+
+    #[rstest(opening_date, withdrawal_date, early,
+        case(date!(2020, 3, 1), date!(2023, 2, 28), true),
+        case(date!(2020, 3, 1), date!(2023, 3,  1), false),
+        case(date!(2024, 3, 1), date!(2027, 3,  1), true),
+        case(date!(2024, 3, 1), date!(2029, 3,  1), false),
+        case(date!(2028, 3, 1), date!(2034, 3,  1), true),
+        case(date!(2028, 3, 1), date!(2035, 3,  1), false),
+    )]
+    fn early_withdrawal(opening_date: Date, withdrawal_date: Date, early: bool) {
+        let deposits = [
+            CashAssets::new(opening_date, "RUB", dec!(100_000)),
+            CashAssets::new(withdrawal_date, "RUB", dec!(-100_000)),
+        ];
+        let paid_taxes = BTreeMap::new();
+        assert_eq!(IisDeduction::calculate(&deposits, &paid_taxes, true).is_err(), early);
+        assert!(IisDeduction::calculate(&deposits, &paid_taxes, false).is_ok());
+    }
+}
@@ -188,6 +188,23 @@ pub fn is_applicable(isin: &HashSet<ISIN>, sell_date: Date) -> Option<bool> {
     result
 }
 
+/// Returns the first date when the position bought on the specified date becomes eligible for the exemption (given
+/// that it's applicable to the instrument on that date).
+pub fn exemption_date(buy_date: Date) -> Option<Date> {
+    if buy_date < date!(2014, 1, 1) {
+        return None;
+    }
+
+    let year = buy_date.year() + 3;
+    let date = Date::from_ymd_opt(year, buy_date.month(), buy_date.day()).unwrap_or_else(|| {
+        // February 29 -> end of February
+        Date::from_ymd_opt(year, buy_date.month(), buy_date.day() - 1).unwrap()
+    });
+
+    assert_eq!(calculate_ownership_years(buy_date, date), 3);
+    Some(date)
+}
+
 pub fn is_deductible(isin: &HashSet<ISIN>, buy_date: Date, sell_date: Date) -> Option<u32> {
     if !is_applicable(isin, sell_date).unwrap_or_default() {
         return None;
@@ -205,7 +222,7 @@ pub fn is_deductible(isin: &HashSet<ISIN>, buy_date: Date, sell_date: Date) -> O
     }
 }
 
-fn calculate_ownership_years(buy_date: Date, sell_date: Date) -> u32 {
+pub fn calculate_ownership_years(buy_date: Date, sell_date: Date) -> u32 {
     assert!(buy_date <= sell_date);
     let mut years = sell_date.year() - buy_date.year();
 
@@ -253,6 +270,17 @@ mod tests {
         assert_eq!(calculate_ownership_years(buy_date, sell_date), years);
     }
 
+    #[rstest(buy_date, date,
+        case(date!(2013, 12, 31), None),
+        case(date!(2014,  1,  1), Some(date!(2017, 1, 1))),
+        case(date!(2014,  3, 19), Some(date!(2017, 3, 19))),
+        case(date!(2020,  2, 29), Some(date!(2023, 2, 28))),
+        case(date!(2021,  2, 28), Some(date!(2024, 2, 28))),
+    )]
+    fn exemption_date_calculation(buy_date: Date, date: Option<Date>) {
+        assert_eq!(exemption_date(buy_date), date);
+    }
+
     #[rstest(with_out_of_limit => [false, true])]
     fn deduction_amount_calculation(with_out_of_limit: bool) {
         let out_of_limit = if with_out_of_limit {
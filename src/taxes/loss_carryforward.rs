@@ -0,0 +1,308 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::str::FromStr;
+
+use diesel::{self, prelude::*};
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency;
+use crate::db::{self, schema::trading_results, models};
+use crate::exchanges::Exchange;
+use crate::instruments::Instrument;
+use crate::types::Decimal;
+
+// Loss from trading securities may be carried forward to the next 10 years in the order it was received. Losses from
+// securities circulating on organized securities market and not circulating on it are accounted separately and
+// decrease profit only from the same kind of securities (Article 220.1 of the Tax Code of the Russian Federation).
+const CARRY_FORWARD_YEARS: i32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum SecuritiesMarket {
+    Organized,
+    Unorganized,
+}
+
+impl SecuritiesMarket {
+    pub const ALL: [SecuritiesMarket; 2] = [SecuritiesMarket::Organized, SecuritiesMarket::Unorganized];
+
+    /// Securities traded only over the counter are considered as not circulating on organized securities market
+    pub fn of(instrument: &Instrument) -> SecuritiesMarket {
+        if instrument.exchanges.get_prioritized() == [Exchange::Otc] {
+            SecuritiesMarket::Unorganized
+        } else {
+            SecuritiesMarket::Organized
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            SecuritiesMarket::Organized => "Обращающиеся на ОРЦБ",
+            SecuritiesMarket::Unorganized => "Не обращающиеся на ОРЦБ",
+        }
+    }
+
+    fn id(self) -> &'static str {
+        match self {
+            SecuritiesMarket::Organized => "organized",
+            SecuritiesMarket::Unorganized => "unorganized",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<SecuritiesMarket> {
+        SecuritiesMarket::ALL.into_iter().find(|market| market.id() == id)
+    }
+}
+
+/// Carries trading losses forward to the next years. Losses belong to the taxpayer, so taxable trading profit of all
+/// portfolios is summed up. Taxable profit of each processed year is stored in the database (see `save()`), so losses
+/// of the previous years and other portfolios are available when tax statement is generated for a single portfolio and
+/// year.
+///
+/// Losses are consumed by profit of the subsequent years, so results of all years since the loss must be known to not
+/// deduct it twice: if some year is missing for a portfolio, the calculation fails.
+pub struct LossCarryforward {
+    database: db::Connection,
+    portfolio: String,
+    // Market -> year -> portfolio -> taxable profit
+    results: BTreeMap<SecuritiesMarket, BTreeMap<i32, BTreeMap<String, Decimal>>>,
+    processed: BTreeMap<(SecuritiesMarket, i32), Decimal>,
+}
+
+impl LossCarryforward {
+    pub fn new(database: db::Connection, portfolio: &str) -> GenericResult<LossCarryforward> {
+        let rows: Vec<(String, String, i32, String)> = trading_results::table
+            .select((
+                trading_results::portfolio, trading_results::market, trading_results::year,
+                trading_results::taxable_profit,
+            ))
+            .load(&mut *database.borrow())?;
+
+        let mut results: BTreeMap<SecuritiesMarket, BTreeMap<i32, BTreeMap<String, Decimal>>> = BTreeMap::new();
+
+        for (portfolio, market, year, taxable_profit) in rows {
+            let market = SecuritiesMarket::from_id(&market).ok_or_else(|| format!(
+                "Got an invalid {:?} portfolio {} securities market from the database: {:?}",
+                portfolio, year, market))?;
+
+            let taxable_profit = Decimal::from_str(&taxable_profit).map_err(|_| format!(
+                "Got an invalid {:?} portfolio {} taxable profit from the database: {:?}",
+                portfolio, year, taxable_profit))?;
+
+            results.entry(market).or_default().entry(year).or_default().insert(portfolio, taxable_profit);
+        }
+
+        Ok(LossCarryforward {
+            database,
+            portfolio: portfolio.to_owned(),
+            results,
+            processed: BTreeMap::new(),
+        })
+    }
+
+    /// Registers the portfolio's taxable profit (negative for loss) of the specified year without applying losses to
+    /// it (for example for years without any trades)
+    pub fn register(&mut self, year: i32, market: SecuritiesMarket, taxable_profit: Decimal) {
+        self.processed.insert((market, year), taxable_profit);
+        self.results.entry(market).or_default().entry(year).or_default()
+            .insert(self.portfolio.clone(), taxable_profit);
+    }
+
+    /// Registers the portfolio's taxable profit (negative for loss) of the specified year and returns the amount of
+    /// previous years losses which decreases it. The taxpayer's deduction is distributed between the portfolios with
+    /// profit proportionally to their profit.
+    pub fn apply(&mut self, year: i32, market: SecuritiesMarket, taxable_profit: Decimal) -> GenericResult<Decimal> {
+        self.register(year, market, taxable_profit);
+
+        if !taxable_profit.is_sign_positive() || taxable_profit.is_zero() {
+            return Ok(dec!(0));
+        }
+
+        let results = &self.results[&market];
+        let totals: BTreeMap<i32, Decimal> = results.iter()
+            .map(|(&year, portfolios)| (year, portfolios.values().sum()))
+            .collect();
+
+        let has_losses = totals.range(year - CARRY_FORWARD_YEARS..year).any(|(_, profit)| profit.is_sign_negative());
+        if has_losses {
+            self.check_completeness(year)?;
+        }
+
+        let total_profit: Decimal = results[&year].values().filter(|profit| profit.is_sign_positive()).sum();
+        let deduction = calculate(&totals, year);
+
+        Ok(std::cmp::min(taxable_profit, currency::round(deduction * taxable_profit / total_profit)))
+    }
+
+    /// Stores taxable profit of the processed years in the database
+    pub fn save(&self) -> EmptyResult {
+        for (&(market, year), taxable_profit) in &self.processed {
+            diesel::replace_into(trading_results::table)
+                .values(models::NewTradingResult {
+                    portfolio: &self.portfolio,
+                    market: market.id(),
+                    year,
+                    taxable_profit: taxable_profit.to_string(),
+                })
+                .execute(&mut *self.database.borrow())?;
+        }
+        Ok(())
+    }
+
+    // Checks that all portfolios have results for all years since their first known one: the current portfolio - up to
+    // the specified year and other portfolios - up to the previous one.
+    fn check_completeness(&self, year: i32) -> EmptyResult {
+        let mut portfolios: BTreeMap<&str, BTreeSet<i32>> = BTreeMap::new();
+
+        for results in self.results.values() {
+            for (&result_year, results) in results.range(..=year) {
+                for portfolio in results.keys() {
+                    portfolios.entry(portfolio).or_default().insert(result_year);
+                }
+            }
+        }
+
+        for (portfolio, years) in portfolios {
+            let first_year = *years.first().unwrap();
+            let last_year = if portfolio == self.portfolio {
+                year
+            } else {
+                year - 1
+            };
+
+            if let Some(missing_year) = (first_year..=last_year).find(|year| !years.contains(year)) {
+                return Err!(
+                    "Unable to carry losses forward: {:?} portfolio trading results for {} are missing. {}",
+                    portfolio, missing_year, "Generate tax statement for this year first");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn calculate(results: &BTreeMap<i32, Decimal>, year: i32) -> Decimal {
+    let mut losses = VecDeque::new();
+    let mut deduction = dec!(0);
+
+    for (&result_year, &taxable_profit) in results.range(..=year) {
+        while let Some(&(loss_year, _)) = losses.front() {
+            if loss_year + CARRY_FORWARD_YEARS >= result_year {
+                break;
+            }
+            losses.pop_front();
+        }
+
+        if taxable_profit.is_sign_negative() {
+            losses.push_back((result_year, -taxable_profit));
+            continue;
+        }
+
+        let mut profit = taxable_profit;
+        deduction = dec!(0);
+
+        while let Some((_, loss)) = losses.front_mut() {
+            if profit.is_zero() {
+                break;
+            }
+
+            let amount = std::cmp::min(*loss, profit);
+            *loss -= amount;
+            profit -= amount;
+            deduction += amount;
+
+            if loss.is_zero() {
+                losses.pop_front();
+            }
+        }
+    }
+
+    match results.get(&year) {
+        Some(taxable_profit) if taxable_profit.is_sign_positive() => deduction,
+        _ => dec!(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(results, year, expected,
+        case(btreemap!{2020 => dec!(-100), 2021 => dec!(30)}, 2021, dec!(30)),
+        case(btreemap!{2020 => dec!(-100), 2021 => dec!(30), 2022 => dec!(100)}, 2022, dec!(70)),
+        case(btreemap!{2020 => dec!(-100), 2021 => dec!(-50), 2022 => dec!(120)}, 2022, dec!(120)),
+        case(btreemap!{2020 => dec!(-100), 2021 => dec!(-50)}, 2021, dec!(0)),
+        case(btreemap!{2010 => dec!(-100), 2020 => dec!(30), 2021 => dec!(100)}, 2021, dec!(0)),
+        case(btreemap!{2010 => dec!(-100), 2020 => dec!(30)}, 2020, dec!(30)),
+        case(btreemap!{2021 => dec!(100), 2022 => dec!(-100)}, 2021, dec!(0)),
+    )]
+    fn calculation(results: BTreeMap<i32, Decimal>, year: i32, expected: Decimal) {
+        assert_eq!(calculate(&results, year), expected);
+    }
+
+    #[test]
+    fn persistence() {
+        let (_database, connection) = db::new_temporary();
+        let market = SecuritiesMarket::Organized;
+
+        // Results aren't stored until explicitly saved
+        let mut carryforward = LossCarryforward::new(connection.clone(), "first").unwrap();
+        assert_eq!(carryforward.apply(2020, market, dec!(-100)).unwrap(), dec!(0));
+
+        let mut carryforward = LossCarryforward::new(connection.clone(), "first").unwrap();
+        assert_eq!(carryforward.apply(2021, market, dec!(150)).unwrap(), dec!(0));
+
+        let mut carryforward = LossCarryforward::new(connection.clone(), "first").unwrap();
+        assert_eq!(carryforward.apply(2020, market, dec!(-100)).unwrap(), dec!(0));
+        carryforward.save().unwrap();
+
+        // Losses are shared between portfolios
+        let mut carryforward = LossCarryforward::new(connection.clone(), "second").unwrap();
+        assert_eq!(carryforward.apply(2021, market, dec!(150)).unwrap(), dec!(100));
+        carryforward.save().unwrap();
+
+        // The deduction is distributed proportionally to the portfolios' profit
+        let mut carryforward = LossCarryforward::new(connection, "first").unwrap();
+        assert_eq!(carryforward.apply(2021, market, dec!(50)).unwrap(), dec!(25));
+    }
+
+    #[test]
+    fn markets() {
+        let (_database, connection) = db::new_temporary();
+
+        let mut carryforward = LossCarryforward::new(connection, "portfolio").unwrap();
+        assert_eq!(carryforward.apply(2020, SecuritiesMarket::Unorganized, dec!(-100)).unwrap(), dec!(0));
+        assert_eq!(carryforward.apply(2020, SecuritiesMarket::Organized, dec!(0)).unwrap(), dec!(0));
+
+        // Losses of one market don't decrease profit of the other one
+        assert_eq!(carryforward.apply(2021, SecuritiesMarket::Organized, dec!(50)).unwrap(), dec!(0));
+        assert_eq!(carryforward.apply(2021, SecuritiesMarket::Unorganized, dec!(30)).unwrap(), dec!(30));
+    }
+
+    #[test]
+    fn missing_years() {
+        let (_database, connection) = db::new_temporary();
+        let market = SecuritiesMarket::Organized;
+
+        let mut carryforward = LossCarryforward::new(connection.clone(), "first").unwrap();
+        carryforward.apply(2020, market, dec!(-100)).unwrap();
+        carryforward.save().unwrap();
+
+        // Profit of 2021 is unknown, so the loss might be already consumed
+        let mut carryforward = LossCarryforward::new(connection.clone(), "first").unwrap();
+        assert!(carryforward.apply(2022, market, dec!(100)).is_err());
+
+        let mut carryforward = LossCarryforward::new(connection.clone(), "first").unwrap();
+        carryforward.register(2021, market, dec!(60));
+        assert_eq!(carryforward.apply(2022, market, dec!(100)).unwrap(), dec!(40));
+
+        // The second portfolio has no results before the loss, so it's considered as opened later
+        let mut carryforward = LossCarryforward::new(connection.clone(), "second").unwrap();
+        assert_eq!(carryforward.apply(2021, market, dec!(150)).unwrap(), dec!(100));
+        carryforward.save().unwrap();
+
+        // The first portfolio has no results for 2021
+        let mut carryforward = LossCarryforward::new(connection, "second").unwrap();
+        assert!(carryforward.apply(2022, market, dec!(150)).is_err());
+    }
+}
@@ -1,5 +1,8 @@
 mod calculator;
+mod deposit_interest;
+mod iis;
 pub mod long_term_ownership;
+mod loss_carryforward;
 mod net_calculator;
 mod payment_day;
 mod rates;
@@ -9,26 +12,45 @@ use std::collections::BTreeMap;
 
 use serde::Deserialize;
 use serde::de::{Deserializer, Error};
+use validator::{Validate, ValidationError};
 
 use crate::brokers::Broker;
 use crate::core::EmptyResult;
-use crate::currency;
+use crate::currency::{self, ManualCurrencyRateConfig};
 use crate::localities::Jurisdiction;
 use crate::types::Decimal;
 
 pub use self::calculator::{TaxCalculator, Tax};
+pub use self::deposit_interest::DepositInterestTax;
+pub use self::iis::IisDeduction;
 pub use self::long_term_ownership::{
     LtoDeductibleProfit, LtoDeductionCalculator, LtoDeduction,
     NetLtoDeduction, NetLtoDeductionCalculator};
+pub use self::loss_carryforward::{LossCarryforward, SecuritiesMarket};
 pub use self::net_calculator::{NetTax, NetTaxCalculator};
-pub use self::payment_day::{TaxPaymentDay, TaxPaymentDaySpec};
-pub use self::rates::{TaxRate, FixedTaxRate, ProgressiveTaxRate};
+pub use self::payment_day::{AdvanceTaxPaymentsSpec, TaxPaymentDay, TaxPaymentDaySpec};
+pub use self::rates::{TaxRate, FixedTaxRate, ProgressiveTaxRate, SeparateTaxBasesRate};
 pub use self::remapping::TaxRemapping;
 
-#[derive(Default, Deserialize)]
+#[derive(Default, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
 pub struct TaxConfig {
-    pub income: BTreeMap<i32, Decimal>
+    // Salary and other non-investment income by year which the progressive tax rate brackets are accumulated from
+    #[serde(default, alias = "income")]
+    #[validate(custom(function = "validate_external_income"))]
+    pub external_income: BTreeMap<i32, Decimal>,
+    #[serde(default)]
+    pub currency_rates: Vec<ManualCurrencyRateConfig>,
+}
+
+fn validate_external_income(external_income: &BTreeMap<i32, Decimal>) -> Result<(), ValidationError> {
+    for (year, &income) in external_income {
+        if income.is_sign_negative() {
+            return Err(ValidationError::new("external_income").with_message(
+                format!("Invalid {} external income: {}", year, income).into()));
+        }
+    }
+    Ok(())
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -36,11 +58,14 @@ pub enum IncomeType {
     Trading,
     Dividends,
     Interest,
+    ControlledForeignCompany,
+    Other,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum TaxExemption {
     LongTermOwnership,
+    // Trading income is tax-free (Individual Investment Account type B and type 3), while dividends are still taxed
     TaxFree,
 }
 
@@ -49,8 +74,8 @@ impl<'de> Deserialize<'de> for TaxExemption {
         let value = String::deserialize(deserializer)?;
         Ok(match value.as_str() {
             "long-term-ownership" => TaxExemption::LongTermOwnership,
-            "tax-free" => TaxExemption::TaxFree,
-            _ => return Err(D::Error::unknown_variant(&value, &["long-term-ownership", "tax-free"])),
+            "tax-free" | "iis-b" => TaxExemption::TaxFree,
+            _ => return Err(D::Error::unknown_variant(&value, &["long-term-ownership", "tax-free", "iis-b"])),
         })
     }
 }
@@ -90,6 +115,7 @@ pub fn round_tax(tax: Decimal, precision: u32) -> Decimal {
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
     use rstest::rstest;
     use super::*;
 
@@ -106,4 +132,34 @@ mod tests {
         let result = round_tax(tax, Jurisdiction::Russia.traits().tax_precision);
         assert_eq!(result, expected.parse().unwrap());
     }
+
+    #[rstest(name, expected,
+        case("long-term-ownership", TaxExemption::LongTermOwnership),
+        case("tax-free", TaxExemption::TaxFree),
+        case("iis-b", TaxExemption::TaxFree),
+    )]
+    fn tax_exemption_parsing(name: &str, expected: TaxExemption) {
+        assert_eq!(serde_yaml::from_str::<TaxExemption>(name).unwrap(), expected);
+    }
+
+    #[rstest(year, income, expected,
+        case(2021,   "100", "13"),
+        case(2022,   "100", "15"),
+        case(2023,   "100", "13"), // External income of the previous year mustn't affect the next one
+        case(2024, "1_000", "140"),
+        case(2025,   "100", "13"), // Separate tax base for investment income
+    )]
+    fn external_income(year: i32, income: &str, expected: &str) {
+        let config: TaxConfig = serde_yaml::from_str(indoc!("
+            external_income:
+              2022: 5_000_000
+              2024: 4_999_500
+              2025: 10_000_000
+        ")).unwrap();
+        config.validate().unwrap();
+
+        let country = crate::localities::russia(&config);
+        let tax = country.tax_rate(year).tax(IncomeType::Trading, income.parse().unwrap());
+        assert_eq!(tax, expected.parse().unwrap());
+    }
 }
\ No newline at end of file
@@ -12,6 +12,7 @@ pub struct NetTaxCalculator {
     country: Country,
     tax_payment_day: TaxPaymentDay,
     profit: HashMap<(i32, Date), NetProfit>,
+    lto: HashMap<i32, LtoDeductionCalculator>,
 }
 
 pub struct NetTax {
@@ -26,7 +27,6 @@ pub struct NetTax {
 struct NetProfit {
     total: Cash,
     taxable: Cash,
-    lto: LtoDeductionCalculator,
 }
 
 impl NetTaxCalculator {
@@ -35,6 +35,7 @@ impl NetTaxCalculator {
             country,
             tax_payment_day,
             profit: HashMap::new(),
+            lto: HashMap::new(),
         }
     }
 
@@ -48,39 +49,79 @@ impl NetTaxCalculator {
         let net_profit = self.profit.entry(key).or_insert_with(|| NetProfit {
             total: Cash::zero(currency),
             taxable: Cash::zero(currency),
-            lto: LtoDeductionCalculator::new(),
         });
 
         net_profit.total += total.round();
         net_profit.taxable += taxable.round();
 
+        let lto = self.lto.entry(key.0).or_insert_with(LtoDeductionCalculator::new);
         for &LtoDeductibleProfit{profit, years} in lto_deductible {
-            net_profit.lto.add(profit, years, emulated_trade);
+            lto.add(profit, years, emulated_trade);
         }
     }
 
-    pub fn calculate(self, calculator: &mut TaxCalculator) -> BTreeMap<i32, NetTax> {
-        let mut taxes = BTreeMap::new();
-
+    /// Returns taxes for each tax payment date ordered by tax year and payment date. A tax year may have several
+    /// payment dates (advance payments): in this case the annual tax is split between them according to the
+    /// year-to-date profit of each income period.
+    pub fn calculate(mut self, calculator: &mut TaxCalculator) -> Vec<(i32, NetTax)> {
+        let mut tax_years: BTreeMap<i32, BTreeMap<Date, NetProfit>> = BTreeMap::new();
         for ((tax_year, tax_payment_date), profit) in self.profit.into_iter() {
-            let lto = profit.lto.calculate();
+            tax_years.entry(tax_year).or_default().insert(tax_payment_date, profit);
+        }
 
+        let mut taxes = Vec::new();
+
+        for (tax_year, periods) in tax_years {
+            let lto = self.lto.remove(&tax_year).unwrap_or_else(LtoDeductionCalculator::new).calculate();
             let lto_deduction = self.country.cash(lto.deduction);
             let lto_loss = self.country.cash(lto.loss);
 
-            let tax = calculator.tax_deductible_income(
-                IncomeType::Trading, tax_year, profit.total, profit.taxable - lto_deduction);
-
-            let net_tax = NetTax {
-                tax_payment_date,
-                tax_deduction: tax.deduction,
-                tax_to_pay: tax.to_pay,
-                lto_deduction,
-                lto_loss,
-            };
-            assert!(taxes.insert(tax_year, net_tax).is_none());
+            let zero = Cash::zero(self.country.currency);
+            let (total, taxable) = periods.values().fold((zero, zero), |(total, taxable), profit| {
+                (total + profit.total, taxable + profit.taxable)
+            });
+
+            let annual = calculator.tax_deductible_income(
+                IncomeType::Trading, tax_year, total, taxable - lto_deduction);
+
+            let mut ytd_total = zero;
+            let mut ytd_taxable = zero;
+            let mut paid_tax = zero;
+            let mut paid_deduction = zero;
+            let last_tax_payment_date = *periods.keys().next_back().unwrap();
+
+            for (tax_payment_date, profit) in periods {
+                let (tax_to_pay, tax_deduction) = if tax_payment_date == last_tax_payment_date {
+                    (annual.to_pay - paid_tax, annual.deduction - paid_deduction)
+                } else {
+                    // Advance payments are estimated using the year-to-date profit and are never greater than the
+                    // annual tax, so all over- and underpayments are settled by the final payment.
+                    ytd_total += profit.total;
+                    ytd_taxable += profit.taxable;
+
+                    let ytd = calculator.tax_deductible_income_dry_run(
+                        IncomeType::Trading, tax_year, ytd_total, ytd_taxable);
+
+                    let advance = |ytd: Cash, annual: Cash, paid: Cash| self.country.cash(std::cmp::min(
+                        annual.amount - paid.amount, std::cmp::max(dec!(0), ytd.amount - paid.amount)));
+
+                    (advance(ytd.to_pay, annual.to_pay, paid_tax),
+                     advance(ytd.deduction, annual.deduction, paid_deduction))
+                };
+
+                paid_tax += tax_to_pay;
+                paid_deduction += tax_deduction;
+
+                let last = tax_payment_date == last_tax_payment_date;
+
+                taxes.push((tax_year, NetTax {
+                    tax_payment_date, tax_deduction, tax_to_pay,
+                    lto_deduction: if last { lto_deduction } else { zero },
+                    lto_loss: if last { lto_loss } else { zero },
+                }));
+            }
         }
 
         taxes
     }
-}
\ No newline at end of file
+}
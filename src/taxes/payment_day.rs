@@ -12,11 +12,44 @@ use crate::types::Date;
 pub struct TaxPaymentDay {
     jurisdiction: Jurisdiction,
     pub spec: TaxPaymentDaySpec,
+    advance_payments: Vec<AdvanceTaxPayment>,
+}
+
+// Tax on income received until the end of the specified month is paid on the specified day
+struct AdvanceTaxPayment {
+    last_month: u32,
+    month: u32,
+    day: u32,
 }
 
 impl TaxPaymentDay {
     pub fn new(jurisdiction: Jurisdiction, spec: TaxPaymentDaySpec) -> TaxPaymentDay {
-        TaxPaymentDay {jurisdiction, spec}
+        TaxPaymentDay {jurisdiction, spec, advance_payments: Vec::new()}
+    }
+
+    /// Configures advance tax payments: tax on income of each period of the year is paid on its own day, and tax on
+    /// income of the last period - on the tax payment day.
+    pub fn with_advance_payments(mut self, spec: &AdvanceTaxPaymentsSpec) -> TaxPaymentDay {
+        self.advance_payments = match spec {
+            AdvanceTaxPaymentsSpec::Quarterly => {
+                let schedule: &[(u32, (u32, u32))] = match self.jurisdiction {
+                    Jurisdiction::Russia => &[(3, (4, 28)), (6, (7, 28)), (9, (10, 28))],
+                    Jurisdiction::Usa => &[(3, (4, 15)), (5, (6, 15)), (8, (9, 15))],
+                };
+
+                schedule.iter().map(|&(last_month, (month, day))| AdvanceTaxPayment {
+                    last_month, month, day,
+                }).collect()
+            },
+
+            AdvanceTaxPaymentsSpec::Days(days) => {
+                days.iter().enumerate().map(|(index, &(month, day))| AdvanceTaxPayment {
+                    last_month: (index as u32 + 1) * 3, month, day,
+                }).collect()
+            },
+        };
+
+        self
     }
 
     /// Returns tax year and an approximate date when tax is going to be paid for the specified income
@@ -33,13 +66,24 @@ impl TaxPaymentDay {
                 }
             },
         };
+
+        if !self.withheld_by_broker(trading) {
+            let advance_payment = self.advance_payments.iter().find(|payment| {
+                income_date.month() <= payment.last_month
+            });
+
+            if let Some(payment) = advance_payment {
+                return (tax_year, date!(income_date.year(), payment.month, payment.day));
+            }
+        }
+
         (tax_year, self.get_for(tax_year, trading))
     }
 
     pub fn get_for(&self, tax_year: i32, trading: bool) -> Date {
         match self.spec {
             TaxPaymentDaySpec::Day {mut month, mut day} => {
-                if trading && self.jurisdiction == Jurisdiction::Russia {
+                if self.withheld_by_broker(trading) {
                     month = 1;
                     day = 1;
                 }
@@ -59,6 +103,11 @@ impl TaxPaymentDay {
             },
         }
     }
+
+    // Trading income tax is withheld by the broker (tax agent) at the end of the year
+    fn withheld_by_broker(&self, trading: bool) -> bool {
+        trading && self.jurisdiction == Jurisdiction::Russia
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -85,19 +134,105 @@ impl TaxPaymentDaySpec {
             return Ok(TaxPaymentDaySpec::OnClose(localities::get_nearest_possible_russian_account_close_date()));
         }
 
-        Regex::new(r"^(?P<day>[0-9]+)\.(?P<month>[0-9]+)$").unwrap().captures(&tax_payment_day).and_then(|captures| {
-            let day = captures.name("day").unwrap().as_str().parse::<u32>().ok();
-            let month = captures.name("month").unwrap().as_str().parse::<u32>().ok();
-            let (day, month) = match (day, month) {
-                (Some(day), Some(month)) => (day, month),
-                _ => return None,
-            };
+        parse_day(&tax_payment_day).map(|(month, day)| TaxPaymentDaySpec::Day {month, day}).ok_or_else(|| {
+            D::Error::custom(format!("Invalid tax payment day: {:?}", tax_payment_day))
+        })
+    }
+}
 
-            if Date::from_ymd_opt(time::today().year(), month, day).is_none() || (day, month) == (29, 2) {
-                return None;
-            }
+#[derive(Debug, Clone)]
+pub enum AdvanceTaxPaymentsSpec {
+    // The default quarterly schedule of the broker's jurisdiction
+    Quarterly,
+    // Payment days for the first three quarters
+    Days(Vec<(u32, u32)>),
+}
+
+impl<'de> Deserialize<'de> for AdvanceTaxPaymentsSpec {
+    fn deserialize<D>(deserializer: D) -> Result<AdvanceTaxPaymentsSpec, D::Error>
+        where D: Deserializer<'de>
+    {
+        let value: String = Deserialize::deserialize(deserializer)?;
+        if value == "quarterly" {
+            return Ok(AdvanceTaxPaymentsSpec::Quarterly);
+        }
+
+        let days: Vec<&str> = value.split(',').map(str::trim).collect();
+        if days.len() != 3 {
+            return Err(D::Error::custom(format!(
+                "Invalid advance tax payments: {:?}. Expected payment days for the first three quarters", value)));
+        }
+
+        let days = days.iter().enumerate().map(|(index, day)| {
+            let last_month = (index as u32 + 1) * 3;
+
+            // Tax must be paid after the end of the quarter and within the year
+            parse_day(day).filter(|&(month, _)| month > last_month).ok_or_else(|| D::Error::custom(format!(
+                "Invalid advance tax payment day for Q{}: {:?}", index + 1, day)))
+        }).collect::<Result<Vec<_>, _>>()?;
 
-            Some(TaxPaymentDaySpec::Day {month, day})
-        }).ok_or_else(|| D::Error::custom(format!("Invalid tax payment day: {:?}", tax_payment_day)))
+        Ok(AdvanceTaxPaymentsSpec::Days(days))
     }
-}
\ No newline at end of file
+}
+
+fn parse_day(value: &str) -> Option<(u32, u32)> {
+    let captures = Regex::new(r"^(?P<day>[0-9]+)\.(?P<month>[0-9]+)$").unwrap().captures(value)?;
+    let day = captures.name("day").unwrap().as_str().parse::<u32>().ok()?;
+    let month = captures.name("month").unwrap().as_str().parse::<u32>().ok()?;
+
+    if Date::from_ymd_opt(time::today().year(), month, day).is_none() || (day, month) == (29, 2) {
+        return None;
+    }
+
+    Some((month, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(jurisdiction, spec, income_date, trading, tax_payment_date,
+        case(Jurisdiction::Russia, "quarterly", date!(2020,  1,  1), false, date!(2020,  4, 28)),
+        case(Jurisdiction::Russia, "quarterly", date!(2020,  3, 31), false, date!(2020,  4, 28)),
+        case(Jurisdiction::Russia, "quarterly", date!(2020,  4,  1), false, date!(2020,  7, 28)),
+        case(Jurisdiction::Russia, "quarterly", date!(2020, 12, 31), false, date!(2021,  3, 15)),
+        case(Jurisdiction::Russia, "quarterly", date!(2020,  4,  1), true,  date!(2021,  1,  1)),
+
+        case(Jurisdiction::Usa, "quarterly", date!(2020,  3, 31), true, date!(2020,  4, 15)),
+        case(Jurisdiction::Usa, "quarterly", date!(2020,  5, 31), true, date!(2020,  6, 15)),
+        case(Jurisdiction::Usa, "quarterly", date!(2020,  8, 31), true, date!(2020,  9, 15)),
+        case(Jurisdiction::Usa, "quarterly", date!(2020,  9,  1), true, date!(2021,  3, 15)),
+
+        case(Jurisdiction::Usa, "15.05, 15.08, 15.11", date!(2020,  3, 31), true, date!(2020,  5, 15)),
+        case(Jurisdiction::Usa, "15.05, 15.08, 15.11", date!(2020,  9, 30), true, date!(2020, 11, 15)),
+        case(Jurisdiction::Usa, "15.05, 15.08, 15.11", date!(2020, 10,  1), true, date!(2021,  3, 15)),
+    )]
+    fn advance_payments(
+        jurisdiction: Jurisdiction, spec: &str, income_date: Date, trading: bool, tax_payment_date: Date,
+    ) {
+        let spec: AdvanceTaxPaymentsSpec = serde_yaml::from_str(spec).unwrap();
+        let tax_payment_day = TaxPaymentDay::new(jurisdiction, TaxPaymentDaySpec::default())
+            .with_advance_payments(&spec);
+        assert_eq!(tax_payment_day.get(income_date, trading), (2020, tax_payment_date));
+    }
+
+    #[test]
+    fn advance_payments_final_day() {
+        let tax_payment_day = TaxPaymentDay::new(Jurisdiction::Usa, TaxPaymentDaySpec::Day {month: 4, day: 30})
+            .with_advance_payments(&AdvanceTaxPaymentsSpec::Quarterly);
+
+        assert_eq!(tax_payment_day.get(date!(2020, 12, 31), true), (2020, date!(2021, 4, 30)));
+        assert_eq!(tax_payment_day.get_for(2020, true), date!(2021, 4, 30));
+    }
+
+    #[rstest(spec,
+        case("monthly"),
+        case("28.04, 28.07"),
+        case("28.03, 28.07, 28.10"),
+        case("28.04, 28.07, 30.02"),
+    )]
+    fn invalid_advance_payments(spec: &str) {
+        assert!(serde_yaml::from_str::<AdvanceTaxPaymentsSpec>(spec).is_err());
+    }
+}
@@ -13,6 +13,14 @@ use crate::types::Decimal;
 
 pub trait TaxRate: DynClone {
     fn tax(&mut self, income_type: IncomeType, income: Decimal) -> Decimal;
+
+    /// Returns the tax bracket which the next income of the specified type is going to fall into
+    fn bracket(&self, income_type: IncomeType) -> TaxBracket;
+}
+
+pub struct TaxBracket {
+    pub rate: Decimal,
+    pub remaining: Option<Decimal>, // Income which can be received until the next tax rate starts to apply
 }
 
 dyn_clone::clone_trait_object!(TaxRate);
@@ -36,6 +44,10 @@ impl TaxRate for FixedTaxRate {
         }
         taxes::round_tax(currency::round(income) * self.rate, self.precision)
     }
+
+    fn bracket(&self, _income_type: IncomeType) -> TaxBracket {
+        TaxBracket {rate: self.rate, remaining: None}
+    }
 }
 
 #[derive(Clone)]
@@ -75,6 +87,54 @@ impl TaxRate for ProgressiveTaxRate {
 
         tax
     }
+
+    fn bracket(&self, _income_type: IncomeType) -> TaxBracket {
+        let (_, &rate) = self.rates.range((Bound::Unbounded, Bound::Included(self.tax_base))).last().unwrap();
+        let remaining = self.rates.range((Bound::Excluded(self.tax_base), Bound::Unbounded)).next()
+            .map(|(&next_rate_tax_base, _)| next_rate_tax_base - self.tax_base);
+        TaxBracket {rate, remaining}
+    }
+}
+
+/// Progressive tax rate with separate tax bases: investment income (trading, dividends, interest, controlled foreign
+/// companies profit) is taxed using its own scale and doesn't affect the tax rate of the main tax base (employment and
+/// other income) and vice versa.
+#[derive(Clone)]
+pub struct SeparateTaxBasesRate {
+    investment: ProgressiveTaxRate,
+    main: ProgressiveTaxRate,
+}
+
+impl SeparateTaxBasesRate {
+    pub fn new(investment: ProgressiveTaxRate, main: ProgressiveTaxRate) -> SeparateTaxBasesRate {
+        SeparateTaxBasesRate {investment, main}
+    }
+
+    fn base(&self, income_type: IncomeType) -> &ProgressiveTaxRate {
+        match income_type {
+            IncomeType::Trading | IncomeType::Dividends | IncomeType::Interest |
+            IncomeType::ControlledForeignCompany => &self.investment,
+            IncomeType::Other => &self.main,
+        }
+    }
+
+    fn base_mut(&mut self, income_type: IncomeType) -> &mut ProgressiveTaxRate {
+        match income_type {
+            IncomeType::Trading | IncomeType::Dividends | IncomeType::Interest |
+            IncomeType::ControlledForeignCompany => &mut self.investment,
+            IncomeType::Other => &mut self.main,
+        }
+    }
+}
+
+impl TaxRate for SeparateTaxBasesRate {
+    fn tax(&mut self, income_type: IncomeType, income: Decimal) -> Decimal {
+        self.base_mut(income_type).tax(income_type, income)
+    }
+
+    fn bracket(&self, income_type: IncomeType) -> TaxBracket {
+        self.base(income_type).bracket(income_type)
+    }
 }
 
 #[cfg(test)]
@@ -129,4 +189,57 @@ mod tests {
             assert_eq!(tax, expected.parse().unwrap());
         }
     }
-}
\ No newline at end of file
+
+    #[rstest(income, rate, remaining,
+        case(        "0", "0.13", Some("2_400_000")),
+        case("1_000_000", "0.13", Some("1_400_000")),
+        case("2_400_000", "0.15", None),
+        case("3_000_000", "0.15", None),
+    )]
+    fn progressive_tax_rate_bracket(income: &str, rate: &str, remaining: Option<&str>) {
+        let mut calc = ProgressiveTaxRate::new(dec!(0), Rc::new(btreemap!{
+                    dec!(0) => dec!(0.13),
+            dec!(2_400_000) => dec!(0.15),
+        }), Jurisdiction::Russia.traits().tax_precision);
+        calc.tax(IncomeType::Trading, income.parse().unwrap());
+
+        let bracket = calc.bracket(IncomeType::Trading);
+        assert_eq!(bracket.rate, rate.parse().unwrap());
+        assert_eq!(bracket.remaining, remaining.map(|remaining| remaining.parse().unwrap()));
+    }
+
+    #[rstest(main_income, incomes, expected,
+        case(         "0", &[(IncomeType::Trading, "2_400_000"), (IncomeType::Other, "100")],   &["312_000", "13"]),
+        case(         "0", &[(IncomeType::Other, "2_400_000"), (IncomeType::Dividends, "100")], &["312_000", "13"]),
+        case(         "0", &[(IncomeType::Dividends, "2_400_000"), (IncomeType::Interest, "100")], &["312_000", "15"]),
+        case(         "0", &[(IncomeType::Other, "6_000_000")],                                  &["882_000"]),
+        case(         "0", &[(IncomeType::ControlledForeignCompany, "3_000_000")],               &["402_000"]),
+        case(         "0", &[(IncomeType::ControlledForeignCompany, "6_000_000")],               &["852_000"]),
+        case("10_000_000", &[(IncomeType::ControlledForeignCompany, "100")],                     &["13"]),
+        case("19_000_000", &[(IncomeType::Other, "2_000_000"), (IncomeType::Trading, "1_000")],  &["380_000", "130"]),
+        case("60_000_000", &[(IncomeType::Other, "100"), (IncomeType::Trading, "100")],          &["22", "13"]),
+    )]
+    fn separate_tax_bases_rate(main_income: &str, incomes: &[(IncomeType, &str)], expected: &[&str]) {
+        let tax_precision = Jurisdiction::Russia.traits().tax_precision;
+
+        let investment = ProgressiveTaxRate::new(dec!(0), Rc::new(btreemap!{
+                    dec!(0) => dec!(0.13),
+            dec!(2_400_000) => dec!(0.15),
+        }), tax_precision);
+
+        let main = ProgressiveTaxRate::new(main_income.parse().unwrap(), Rc::new(btreemap!{
+                     dec!(0) => dec!(0.13),
+             dec!(2_400_000) => dec!(0.15),
+             dec!(5_000_000) => dec!(0.18),
+            dec!(20_000_000) => dec!(0.20),
+            dec!(50_000_000) => dec!(0.22),
+        }), tax_precision);
+
+        let mut calc = SeparateTaxBasesRate::new(investment, main);
+
+        for ((income_type, income), expected) in incomes.iter().zip_eq(expected) {
+            let tax = calc.tax(*income_type, income.parse().unwrap());
+            assert_eq!(tax, expected.parse().unwrap());
+        }
+    }
+}
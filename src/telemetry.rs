@@ -12,7 +12,6 @@ use std::time::{Instant, SystemTime, Duration};
 use diesel::{self, prelude::*};
 use log::{trace, error};
 use platforms::target::OS;
-use reqwest::blocking::Client;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
 use uuid::Uuid;
@@ -20,6 +19,8 @@ use uuid::Uuid;
 use crate::brokers::Broker;
 use crate::core::{EmptyResult, GenericResult, GenericError};
 use crate::db::{self, schema::{settings, telemetry}, models};
+use crate::network::{Client, RetryPolicy};
+use crate::profiling;
 use crate::util;
 
 #[derive(Serialize, Clone)]
@@ -37,6 +38,10 @@ pub struct TelemetryRecord {
     command: String,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     brokers: Vec<String>,
+
+    // Execution time of command phases in milliseconds (reported only when profiling is enabled)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    timings: BTreeMap<&'static str, u128>,
 }
 
 impl TelemetryRecord {
@@ -90,10 +95,26 @@ impl TelemetryRecordBuilder {
 
             command: command.to_owned(),
             brokers,
+            timings: get_timings(),
         }
     }
 }
 
+fn get_timings() -> BTreeMap<&'static str, u128> {
+    let Some(timings) = profiling::timings() else {
+        return BTreeMap::new();
+    };
+
+    let mut result: BTreeMap<_, _> = timings.phases.iter()
+        .map(|(phase, duration)| (phase.id(), duration.as_millis()))
+        .collect();
+
+    result.insert("other", timings.other().as_millis());
+    result.insert("total", timings.total.as_millis());
+
+    result
+}
+
 #[derive(Deserialize, Default)]
 #[serde(deny_unknown_fields)]
 pub struct TelemetryConfig {
@@ -120,6 +141,14 @@ impl Telemetry {
     pub fn new(
         connection: db::Connection, user_id: Option<String>,
         url: &str, flush_thresholds: BTreeMap<usize, Duration>, max_records: usize,
+    ) -> GenericResult<Telemetry> {
+        let client = Client::new("Telemetry server", None, Some(&TELEMETRY_RETRY_POLICY));
+        Telemetry::new_with_client(connection, user_id, client, url, flush_thresholds, max_records)
+    }
+
+    pub fn new_with_client(
+        connection: db::Connection, user_id: Option<String>,
+        client: Client, url: &str, flush_thresholds: BTreeMap<usize, Duration>, max_records: usize,
     ) -> GenericResult<Telemetry> {
         let mut telemetry = Telemetry {
             db: connection,
@@ -141,7 +170,7 @@ impl Telemetry {
                 }
             }
 
-            telemetry.sender.replace(TelemetrySender::new(url, request, last_record_id, deadline));
+            telemetry.sender.replace(TelemetrySender::new(client, url, request, last_record_id, deadline));
         }
 
         Ok(telemetry)
@@ -235,6 +264,9 @@ impl Drop for Telemetry {
     }
 }
 
+// Unsent records are sent on the next run, so there is no need in aggressive retrying
+const TELEMETRY_RETRY_POLICY: RetryPolicy = RetryPolicy::new(2, Duration::from_millis(100), 0);
+
 struct TelemetrySender {
     thread: JoinHandle<()>,
     result: Arc<(Mutex<Option<Option<i64>>>, Condvar)>,
@@ -242,14 +274,16 @@ struct TelemetrySender {
 }
 
 impl TelemetrySender {
-    fn new(url: &str, request: TelemetryRequest, last_record_id: i64, deadline: Instant) -> TelemetrySender {
+    fn new(
+        client: Client, url: &str, request: TelemetryRequest, last_record_id: i64, deadline: Instant,
+    ) -> TelemetrySender {
         let result = Arc::new((Mutex::new(None), Condvar::new()));
 
         let thread = {
             let url = url.to_owned();
             let result = result.clone();
             thread::spawn(move || {
-                let ok = TelemetrySender::send(&url, request);
+                let ok = TelemetrySender::send(&client, &url, request);
 
                 let (lock, cond) = result.as_ref();
                 let mut result = lock.lock().unwrap();
@@ -292,24 +326,18 @@ impl TelemetrySender {
         result
     }
 
-    fn send(base_url: &str, request: TelemetryRequest) -> bool {
+    fn send(client: &Client, base_url: &str, request: TelemetryRequest) -> bool {
         let url = format!("{}/telemetry", base_url);
 
         trace!("Sending telemetry ({} records)...", request.records.len());
-        match Client::new().post(url).json(&request).send() {
+        match client.send(client.post(url).json(&request)) {
             Ok(response) => {
-                let status = response.status();
-                if status.is_success() {
-                    // Consume body in test mode to block on unreachable server emulation
-                    if cfg!(test) {
-                        let _ = response.bytes();
-                    }
-                    trace!("Telemetry has been successfully sent.");
-                    true
-                } else {
-                    trace!("Telemetry server returned an error: {}.", status);
-                    false
+                // Consume body in test mode to block on unreachable server emulation
+                if cfg!(test) {
+                    let _ = response.bytes();
                 }
+                trace!("Telemetry has been successfully sent.");
+                true
             },
             Err(e) => {
                 trace!("Failed to send telemetry: {}.", e);
@@ -369,7 +397,7 @@ mod tests {
 
             telemetry.close().unwrap();
         }
-        mock = mock.expect(1);
+        mock = mock.expect(2);
         mock.assert();
         compare(connection.clone(), &expected); // 8 records
 
@@ -386,7 +414,7 @@ mod tests {
 
             telemetry.close().unwrap();
         }
-        mock = mock.expect(2);
+        mock = mock.expect(4);
         mock.assert();
         compare(connection.clone(), &expected); // 9 records
 
@@ -2,7 +2,7 @@ use chrono::Datelike;
 
 use super::{Date, Period};
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct Month {
     year: i32,
     month: u32,
@@ -33,6 +33,10 @@ impl Month {
         Period::new(first_day, last_day).unwrap()
     }
 
+    pub fn format(&self) -> String {
+        format!("{:02}.{}", self.month, self.year)
+    }
+
     pub fn prev(mut self) -> Month {
         if self.month == 1 {
             self.year -= 1;
@@ -49,6 +49,12 @@ pub fn deserialize_date<'de, D>(deserializer: D) -> Result<Date, D::Error>
     parse_user_date(&date).map_err(D::Error::custom)
 }
 
+pub fn deserialize_optional_date<'de, D>(deserializer: D) -> Result<Option<Date>, D::Error>
+    where D: Deserializer<'de>
+{
+    deserialize_date(deserializer).map(Some)
+}
+
 pub fn deserialize_date_opt_time<'de, D>(deserializer: D) -> Result<DateOptTime, D::Error>
     where D: Deserializer<'de>
 {
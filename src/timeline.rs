@@ -0,0 +1,273 @@
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{
+    BrokerStatement, CashFlowType, CorporateAction, CorporateActionType, DerivativeType, ReadingStrictness,
+    StatementSections, StockSellType, StockSource, Withholding};
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::formatting;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::{Date, DateOptTime};
+
+/// Prints a chronological feed of all portfolio events with running cash balance, which is handy for auditing the
+/// broker statements and explaining the analysis results.
+pub fn show(config: &Config, portfolio_name: &str) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
+
+    let statement = BrokerStatement::read(
+        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
+        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.tax_exemptions,
+        &portfolio.corporate_actions, ReadingStrictness::empty(), StatementSections::all())?;
+
+    let mut table = Table::new();
+    let mut balance = MultiCurrencyCashAccount::new();
+
+    for event in collect_events(&statement) {
+        let current_balance = event.amount.map(|amount| {
+            balance.deposit(amount);
+            balance.get(amount.currency).unwrap()
+        });
+
+        table.add_row(Row {
+            date: event.time.date,
+            operation: event.operation,
+            description: event.description,
+            amount: event.amount,
+            balance: current_balance,
+        });
+    }
+
+    if table.is_empty() {
+        println!("The portfolio has no events.");
+    } else {
+        table.print(&format!("{} portfolio timeline", portfolio.name));
+    }
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Date")]
+    date: Date,
+    #[column(name="Operation")]
+    operation: &'static str,
+    #[column(name="Description")]
+    description: String,
+    #[column(name="Amount")]
+    amount: Option<Cash>,
+    #[column(name="Balance")]
+    balance: Option<Cash>,
+}
+
+struct Event {
+    time: DateOptTime,
+    operation: &'static str,
+    description: String,
+    amount: Option<Cash>,
+}
+
+fn collect_events(statement: &BrokerStatement) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut add = |time: DateOptTime, operation, description: String, amount: Option<Cash>| {
+        events.push(Event {time, operation, description, amount});
+    };
+    let name = |symbol: &str| statement.instrument_info.get_name(symbol);
+
+    for assets in &statement.deposits_and_withdrawals {
+        let operation = if assets.cash.is_positive() {
+            "Deposit"
+        } else {
+            "Withdrawal"
+        };
+        add(assets.date.into(), operation, String::new(), Some(assets.cash));
+    }
+
+    for interest in &statement.idle_cash_interest {
+        add(interest.date.into(), "Interest", s!("Idle cash interest"), Some(interest.amount));
+    }
+
+    for interest in &statement.margin_interest {
+        add(interest.date.into(), "Margin interest", String::new(), Some(-interest.amount));
+    }
+
+    for grant in &statement.cash_grants {
+        add(grant.date.into(), "Grant", grant.description.clone(), Some(grant.amount));
+    }
+
+    for dividend in &statement.dividends {
+        if dividend.skip_from_cash_flow {
+            continue;
+        }
+
+        let issuer = name(&dividend.original_issuer);
+        add(dividend.date.into(), "Dividend", issuer.clone(), Some(dividend.amount));
+
+        if !dividend.paid_tax.is_zero() {
+            add(dividend.date.into(), "Tax", format!("Tax withheld from {} dividend", issuer),
+                Some(-dividend.paid_tax));
+        }
+    }
+
+    for coupon in &statement.coupons {
+        add(coupon.date.into(), "Coupon", name(&coupon.original_symbol), Some(coupon.amount));
+    }
+
+    for cash_flow in &statement.cash_flows {
+        let (operation, description) = match cash_flow.type_ {
+            CashFlowType::Dividend {date, ref issuer} => (
+                "Dividend", format!("{} dividend from {}", name(issuer), formatting::format_date(date))),
+            CashFlowType::Tax {date, ref issuer} => (
+                "Tax", format!("Tax withheld from {} dividend from {}", name(issuer), formatting::format_date(date))),
+            CashFlowType::Repo {ref symbol, commission, ..} => {
+                if !commission.is_zero() {
+                    add(cash_flow.date, "Commission", format!("Repo deal with {}", name(symbol)), Some(-commission));
+                }
+                ("Repo", name(symbol))
+            },
+            CashFlowType::SecuritiesLending => ("Interest", s!("Securities lending income")),
+        };
+        add(cash_flow.date, operation, description, Some(cash_flow.amount));
+    }
+
+    for trade in &statement.forex_trades {
+        let description = format!("{} -> {}", trade.from.currency, trade.to.currency);
+        add(trade.conclusion_time, "Forex trade", description.clone(), Some(-trade.from));
+        add(trade.conclusion_time, "Forex trade", description.clone(), Some(trade.to));
+
+        if !trade.commission.is_zero() {
+            add(trade.conclusion_time, "Commission", description, Some(-trade.commission));
+        }
+    }
+
+    for trade in &statement.stock_buys {
+        let description = format!("{} {}", trade.quantity, name(&trade.original_symbol));
+
+        match trade.type_ {
+            StockSource::Trade {volume, commission, ..} => {
+                add(trade.conclusion_time, "Buy", description.clone(), Some(-volume));
+                if !commission.is_zero() {
+                    add(trade.conclusion_time, "Commission", format!("Buy of {}", description), Some(-commission));
+                }
+            },
+            StockSource::Grant => add(trade.conclusion_time, "Stock grant", description, None),
+            StockSource::CorporateAction => {},
+        };
+    }
+
+    for trade in &statement.stock_sells {
+        let description = format!("{} {}", trade.quantity, name(&trade.original_symbol));
+
+        match trade.type_ {
+            StockSellType::Trade {volume, commission, ..} => {
+                add(trade.conclusion_time, "Sell", description.clone(), Some(volume));
+                if !commission.is_zero() {
+                    add(trade.conclusion_time, "Commission", format!("Sell of {}", description), Some(-commission));
+                }
+            },
+            StockSellType::CorporateAction => {},
+        };
+    }
+
+    // Futures trades don't move cash except commissions: their result is settled on position closing
+    for trade in &statement.derivative_trades {
+        let description = format!("{} {}", trade.quantity.abs(), name(&trade.symbol));
+        let operation = if trade.quantity.is_sign_positive() {
+            "Buy"
+        } else {
+            "Sell"
+        };
+
+        if trade.type_ == DerivativeType::Option && !trade.volume.is_zero() {
+            add(trade.conclusion_time, operation, description.clone(), Some(trade.volume));
+        }
+        if !trade.commission.is_zero() {
+            add(trade.conclusion_time, "Commission", format!("{} of {}", operation, description),
+                Some(-trade.commission));
+        }
+    }
+
+    for income in &statement.derivatives_income {
+        if income.type_ == DerivativeType::Futures {
+            let description = format!("{} {}", income.quantity, name(&income.symbol));
+            add(income.execution_date.into(), "Variation margin", description, Some(income.trading_result()));
+        }
+    }
+
+    for fee in &statement.fees {
+        let (operation, description) = match fee.amount {
+            Withholding::Withholding(_) => ("Fee", "Broker fee"),
+            Withholding::Refund(_) => ("Fee refund", "Broker fee refund"),
+        };
+        let description = fee.description.clone().unwrap_or_else(|| description.to_owned());
+        add(fee.date.into(), operation, description, Some(-fee.amount.withholding()));
+    }
+
+    for tax in &statement.tax_agent_withholdings {
+        let operation = match tax.amount {
+            Withholding::Withholding(_) => "Tax",
+            Withholding::Refund(_) => "Tax refund",
+        };
+        add(tax.date.into(), operation, format!("Tax agent withholding for {}", tax.year),
+            Some(-tax.amount.withholding()));
+    }
+
+    for action in &statement.corporate_actions {
+        let (operation, amount) = match action.action {
+            CorporateActionType::Amortization {amount, ref currency} => (
+                "Amortization", Some(Cash::new(currency, amount))),
+            _ => ("Corporate action", None),
+        };
+        add(action.time, operation, describe_corporate_action(action), amount);
+    }
+
+    // The sort is stable, so events of the same time are left in order of their types above
+    events.sort_by_key(|event| event.time);
+    events
+}
+
+fn describe_corporate_action(action: &CorporateAction) -> String {
+    let symbol = &action.symbol;
+
+    match action.action {
+        CorporateActionType::Amortization {..} => format!("{symbol} bond amortization"),
+        CorporateActionType::Delisting {quantity} => format!("{symbol} delisting of {quantity} shares"),
+        CorporateActionType::DividendReinvestment {quantity, ..} => format!(
+            "{symbol} dividend reinvestment: {quantity} shares"),
+        CorporateActionType::Liquidation {quantity, ..} => format!("{symbol} liquidation of {quantity} shares"),
+        CorporateActionType::Merger {quantity, ref new_symbol, new_quantity, cash, ref currency, ..} => format!(
+            "{symbol} merger: {quantity} shares -> {new_quantity} {new_symbol} + {}", Cash::new(currency, cash)),
+        CorporateActionType::Rename {ref new_symbol} => format!("{symbol} -> {new_symbol} rename"),
+        CorporateActionType::Spinoff {symbol: ref spinoff, quantity, ..} => format!(
+            "{symbol} spinoff: {quantity} {spinoff}"),
+        CorporateActionType::StockDividend {ref stock, quantity} => format!(
+            "{symbol} stock dividend: {quantity} {}", stock.as_ref().unwrap_or(symbol)),
+        CorporateActionType::StockSplit {ratio, ..} => format!("{symbol} {ratio} stock split"),
+        CorporateActionType::SubscribableRightsIssue => format!("{symbol} subscribable rights issue"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corporate_action_description() {
+        let action = CorporateAction {
+            time: date!(2019, 11, 20).into(),
+            report_date: None,
+            symbol: s!("CELG"),
+            action: CorporateActionType::Merger {
+                quantity: dec!(10),
+                new_symbol: s!("BMY"),
+                new_quantity: dec!(10),
+                cash: dec!(500),
+                price: Some(dec!(57.65)),
+                currency: s!("USD"),
+            },
+        };
+        assert_eq!(describe_corporate_action(&action), "CELG merger: 10 shares -> 10 BMY + $500");
+    }
+}
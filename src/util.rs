@@ -27,6 +27,81 @@ pub fn parse_decimal(string: &str, restrictions: DecimalRestrictions) -> Generic
     validate_decimal(value, restrictions)
 }
 
+/// Number formatting conventions used in broker statements
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberFormat {
+    /// 1,234,567.89
+    English,
+    /// 1 234 567,89 or 1 234 567.89 (with regular, non-breaking or thin spaces)
+    Russian,
+}
+
+impl NumberFormat {
+    fn group_separators(self) -> &'static [char] {
+        match self {
+            NumberFormat::English => &[','],
+            NumberFormat::Russian => &[' ', '\u{a0}', '\u{202f}', '\u{2009}'],
+        }
+    }
+
+    fn decimal_separators(self) -> &'static [char] {
+        match self {
+            NumberFormat::English => &['.'],
+            NumberFormat::Russian => &[',', '.'],
+        }
+    }
+}
+
+/// Parses a decimal value formatted according to the specified conventions. Digit group separators are accepted only
+/// in their proper places to not misinterpret a decimal separator from another locale as a group separator.
+pub fn parse_formatted_decimal(string: &str, format: NumberFormat, restrictions: DecimalRestrictions) -> GenericResult<Decimal> {
+    let value = normalize_decimal(string, format)
+        .and_then(|value| Decimal::from_str(&value).ok())
+        .ok_or_else(|| format!("Invalid decimal value: {:?}", string))?;
+    validate_decimal(value, restrictions)
+}
+
+fn normalize_decimal(string: &str, format: NumberFormat) -> Option<String> {
+    let string = string.trim_matches(|c: char| c.is_whitespace());
+
+    let (sign, string) = match string.strip_prefix(['-', '\u{2212}']) {
+        Some(string) => ("-", string),
+        None => ("", string),
+    };
+
+    let (integer, fraction) = match string.split_once(format.decimal_separators()) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (string, None),
+    };
+
+    let is_digits = |string: &str| !string.is_empty() && string.bytes().all(|c| c.is_ascii_digit());
+
+    let mut groups = integer.split(format.group_separators());
+    let first_group = groups.next().unwrap();
+    if !is_digits(first_group) {
+        return None;
+    }
+
+    let mut normalized = format!("{}{}", sign, first_group);
+
+    for group in groups {
+        if first_group.len() > 3 || first_group.starts_with('0') || group.len() != 3 || !is_digits(group) {
+            return None;
+        }
+        normalized.push_str(group);
+    }
+
+    if let Some(fraction) = fraction {
+        if !is_digits(fraction) {
+            return None;
+        }
+        normalized.push('.');
+        normalized.push_str(fraction);
+    }
+
+    Some(normalized)
+}
+
 pub fn validate_decimal(value: Decimal, restrictions: DecimalRestrictions) -> GenericResult<Decimal> {
     if !match restrictions {
         DecimalRestrictions::No => true,
@@ -103,6 +178,7 @@ pub fn temp_path(path: &Path) -> PathBuf {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
     use rstest::rstest;
     use super::*;
 
@@ -178,4 +254,81 @@ mod tests {
     fn to_bigger_rounding(value: Decimal, expected: Decimal) {
         assert_eq!(round_with(value, 0, RoundingMethod::ToBigger), expected);
     }
-}
\ No newline at end of file
+
+    #[rstest(value, format, expected,
+        case("1020", NumberFormat::English, Some(dec!(1020))),
+        case(" -1,020,304.05 ", NumberFormat::English, Some(dec!(-1_020_304.05))),
+        case("0.5", NumberFormat::English, Some(dec!(0.5))),
+        case("1 020,5", NumberFormat::English, None),
+        case("1,02", NumberFormat::English, None),
+        case("0,102", NumberFormat::English, None),
+        case("1020,304", NumberFormat::English, None),
+        case("1.020.304", NumberFormat::English, None),
+
+        case("1 020 304,05", NumberFormat::Russian, Some(dec!(1_020_304.05))),
+        case("1\u{a0}020\u{a0}304.05", NumberFormat::Russian, Some(dec!(1_020_304.05))),
+        case("\u{2212}1\u{202f}020,5", NumberFormat::Russian, Some(dec!(-1020.5))),
+        case("1,020", NumberFormat::Russian, Some(dec!(1.020))),
+        case("1,020.5", NumberFormat::Russian, None),
+        case("1  020", NumberFormat::Russian, None),
+        case("10 20", NumberFormat::Russian, None),
+
+        case("", NumberFormat::English, None),
+        case("-", NumberFormat::Russian, None),
+        case(".5", NumberFormat::English, None),
+        case("5.", NumberFormat::Russian, None),
+    )]
+    fn formatted_decimal_parsing(value: &str, format: NumberFormat, expected: Option<Decimal>) {
+        assert_eq!(parse_formatted_decimal(value, format, DecimalRestrictions::No).ok(), expected);
+    }
+
+    fn format_decimal(value: Decimal, group_separator: char, decimal_separator: char) -> String {
+        let string = value.abs().to_string();
+        let (integer, fraction) = match string.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (string.as_str(), None),
+        };
+
+        let mut formatted = if value.is_sign_negative() { s!("-") } else { String::new() };
+
+        for (index, digit) in integer.chars().enumerate() {
+            if index != 0 && (integer.len() - index) % 3 == 0 {
+                formatted.push(group_separator);
+            }
+            formatted.push(digit);
+        }
+
+        if let Some(fraction) = fraction {
+            formatted.push(decimal_separator);
+            formatted.push_str(fraction);
+        }
+
+        formatted
+    }
+
+    proptest! {
+        #[test]
+        fn formatted_decimal_roundtrip(
+            mantissa in any::<i64>(), scale in 0_u32..10,
+            group_separator in prop::sample::select(vec![' ', '\u{a0}', '\u{202f}', '\u{2009}']),
+            decimal_separator in prop::sample::select(vec![',', '.']),
+        ) {
+            let value = Decimal::new(mantissa, scale);
+
+            let english = format_decimal(value, ',', '.');
+            prop_assert_eq!(parse_formatted_decimal(&english, NumberFormat::English, DecimalRestrictions::No).unwrap(), value);
+
+            let russian = format_decimal(value, group_separator, decimal_separator);
+            prop_assert_eq!(parse_formatted_decimal(&russian, NumberFormat::Russian, DecimalRestrictions::No).unwrap(), value);
+        }
+
+        #[test]
+        fn formatted_decimal_parsing_robustness(value in "\\PC*") {
+            for format in [NumberFormat::English, NumberFormat::Russian] {
+                if let Ok(parsed) = parse_formatted_decimal(&value, format, DecimalRestrictions::No) {
+                    prop_assert!(value.chars().any(|c| c.is_ascii_digit()), "{:?} -> {}", value, parsed);
+                }
+            }
+        }
+    }
+}
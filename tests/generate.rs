@@ -18,8 +18,10 @@ fn generate_regression_tests() {
 
     for command in [
         "sync", "show", "rebalance", "cash", "buy", "sell",
-        "analyse", "simulate-sell", "tax-statement", "cash-flow",
-        "deposits", "metrics", "completion",
+        "analyse", "withdrawal-plan", "simulate-sell", "plan-lto", "broker-costs",
+        "tax-statement", "realized-pnl", "cash-flow", "year-end", "forex",
+        "corporate-action", "corporate-action add", "map-internal-ids",
+        "deposits", "metrics", "doctor", "init", "config", "config set-secret", "completion",
     ] {
         t.add(&format!("Help {} short", command), &format!("{} -h", command));
         t.add(&format!("Help {} long", command), &format!("{} --help", command));
@@ -32,12 +34,18 @@ fn generate_regression_tests() {
     // show
     t.add("Show", "show ib");
     t.add("Show flat", "show ib --flat");
+    t.add("Show as of date", "show ib --as-of 31.12.2020");
 
     // analyse
     t.add("Analyse", "analyse --all");
     t.add("Analyse virtual", "analyse --all --method virtual");
     t.add("Analyse inflation-adjusted", "analyse --all --method inflation-adjusted");
     t.add("Analyse delisted", "analyse tbank-delisting --all").config("other");
+    t.add("Analyse missed gains", "analyse ib --missed-gains");
+    t.add("Analyse stress testing", "analyse ib --stress 2008");
+
+    // withdrawal-plan
+    t.add("Withdrawal plan", "withdrawal-plan ib --rate 4%");
 
     // simulate-sell
     t.add("Simulate sell partial", "simulate-sell ib all VTI 50 BND 50 BND");
@@ -76,6 +84,10 @@ fn generate_regression_tests() {
     t.tax_statement("Open dividends SPB", 2021).config("other");
     t.tax_statement("TBank complex full", 2020).config("other");
 
+    // realized-pnl
+    t.add("IB stock split realized P&L", "realized-pnl ib-stock-split").config("other");
+    t.add("TBank mixed currency realized P&L", "realized-pnl tbank-mixed-currency-trade").config("other");
+
     // cash-flow
     t.add("IB margin RUB cash flow", "cash-flow ib-margin-rub").config("other");
     t.add("IB stock split cash flow", "cash-flow ib-stock-split").config("other");